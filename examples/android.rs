@@ -0,0 +1,89 @@
+//! The super simple example on a phone: the circle goes where the screen is touched, and sound
+//! plays as it's put down. The game is saved as the app goes to the background, and loaded as
+//! it starts, since Android may kill it meanwhile.
+//!
+//! Built into an APK and started on the connected phone with `cargo apk run --example android`,
+//! see "Android" in `docs/BuildingForEveryPlatform.md`. On the desktop it's a library, as
+//! Android loads it.
+
+use ggez::{
+    audio::{self, SoundSource},
+    event,
+    filesystem::UserDir,
+    glam::*,
+    graphics::{self, Color},
+    input::touch::TouchPhase,
+    Context, GameResult,
+};
+
+struct MainState {
+    pos: Vec2,
+    circle: graphics::Mesh,
+    sound: audio::Source,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> GameResult<MainState> {
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            vec2(0., 0.),
+            100.0,
+            2.0,
+            Color::WHITE,
+        )?;
+        // from the APK's assets
+        let sound = audio::Source::new(ctx, "/sound.ogg")?;
+        let saved: Option<[f32; 2]> = ctx
+            .fs
+            .read_user_file(UserDir::Data, "save.json")
+            .unwrap_or_default();
+        let pos = saved.map_or(vec2(200.0, 400.0), Vec2::from);
+        Ok(MainState { pos, circle, sound })
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas =
+            graphics::Canvas::from_frame(ctx, graphics::Color::from([0.1, 0.2, 0.3, 1.0]));
+        canvas.draw(&self.circle, self.pos);
+        canvas.finish(ctx)
+    }
+
+    fn touch_event(
+        &mut self,
+        ctx: &mut Context,
+        phase: TouchPhase,
+        _id: u64,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        // touches are in physical pixels, as the screen coordinates are by default
+        self.pos = vec2(x, y);
+        if phase == TouchPhase::Started {
+            self.sound.play_detached(ctx)?;
+        }
+        Ok(())
+    }
+
+    fn suspend_event(&mut self, ctx: &mut Context) -> GameResult {
+        let pos: [f32; 2] = self.pos.into();
+        ctx.fs.write_user_file(UserDir::Data, "save.json", &pos)
+    }
+}
+
+#[cfg_attr(
+    target_os = "android",
+    ggez::ndk_glue::main(backtrace = "on", ndk_glue = "ggez::ndk_glue")
+)]
+pub fn main() {
+    let cb = ggez::ContextBuilder::new("android", "ggez");
+    let (mut ctx, event_loop) = cb.build().expect("could not start the game");
+    let state = MainState::new(&mut ctx).expect("could not load the game");
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,110 @@
+//! Records from the default microphone, drawing how loud it is.
+//!
+//! Needs the `audio-capture` feature: `cargo run --example audio_capture --features audio-capture`
+
+use ggez::audio;
+use ggez::event;
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    capture: Result<audio::CaptureStream, String>,
+    samples: Vec<f32>,
+    // smoothed, so the meter doesn't flicker
+    level: f32,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> MainState {
+        let mut s = MainState {
+            capture: Err(String::new()),
+            samples: Vec::new(),
+            level: 0.0,
+        };
+        s.start(ctx);
+        s
+    }
+
+    fn start(&mut self, ctx: &mut Context) {
+        // the game goes on without a microphone, with the error shown instead
+        self.capture = ctx
+            .audio
+            .start_capture(audio::CaptureConfig::default())
+            .map_err(|e| e.to_string());
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        self.samples.clear();
+        if let Ok(capture) = &mut self.capture {
+            if let Err(e) = capture.read(&mut self.samples) {
+                self.capture = Err(e.to_string());
+            }
+        }
+        if !self.samples.is_empty() {
+            let squares: f32 = self.samples.iter().map(|s| s * s).sum();
+            let rms = (squares / self.samples.len() as f32).sqrt();
+            self.level = rms.max(self.level * 0.9);
+        } else if self.capture.is_err() {
+            self.level = 0.0;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+
+        let text = match &self.capture {
+            Ok(capture) => format!(
+                "Recording from {}. Press escape to quit.",
+                capture
+                    .config()
+                    .device
+                    .as_deref()
+                    .unwrap_or("the microphone")
+            ),
+            Err(e) => format!("Could not record ({}). Press R to try again.", e),
+        };
+        canvas.draw(&graphics::Text::new(text), [100., 100.]);
+
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(2.0),
+            graphics::Rect::new(100.0, 150.0, 600.0, 40.0),
+            Color::WHITE,
+        )?;
+        canvas.draw(&background, graphics::DrawParam::new());
+        // almost nothing recorded is this loud, so the meter is scaled up
+        let width = (self.level * 1800.0).min(600.0);
+        if width > 0.0 {
+            let meter = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(100.0, 150.0, width, 40.0),
+                Color::GREEN,
+            )?;
+            canvas.draw(&meter, graphics::DrawParam::new());
+        }
+
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+        match input.keycode {
+            Some(KeyCode::R) if self.capture.is_err() => self.start(ctx),
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("audio_capture", "ggez");
+    let (mut ctx, event_loop) = cb.build()?;
+
+    let state = MainState::new(&mut ctx);
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,106 @@
+//! A borderless window with a title bar drawn by the game itself,
+//! which can still be moved, resized and closed like a normal window.
+
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::event::{self, MouseButton};
+use ggez::graphics::{self, Color, Rect, ResizeDirection};
+use ggez::{Context, GameResult};
+
+const TITLE_BAR_HEIGHT: f32 = 32.;
+const BUTTON_SIZE: f32 = 32.;
+const GRIP_SIZE: f32 = 16.;
+
+struct MainState;
+
+impl MainState {
+    fn close_button(ctx: &Context) -> Rect {
+        let (width, _) = ctx.gfx.drawable_size();
+        Rect::new(width - BUTTON_SIZE, 0., BUTTON_SIZE, BUTTON_SIZE)
+    }
+
+    fn resize_grip(ctx: &Context) -> Rect {
+        let (width, height) = ctx.gfx.drawable_size();
+        Rect::new(width - GRIP_SIZE, height - GRIP_SIZE, GRIP_SIZE, GRIP_SIZE)
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(40, 44, 52));
+        let (width, _) = ctx.gfx.drawable_size();
+
+        // title bar
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .scale([width, TITLE_BAR_HEIGHT])
+                .color(Color::from_rgb(24, 26, 31)),
+        );
+        canvas.draw(
+            graphics::Text::new("Drag me around").set_scale(20.),
+            graphics::DrawParam::from([8., 6.]),
+        );
+
+        // close button
+        let close = Self::close_button(ctx);
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest(close.point())
+                .scale(close.size())
+                .color(Color::from_rgb(200, 60, 60)),
+        );
+
+        // resize grip
+        let grip = Self::resize_grip(ctx);
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest(grip.point())
+                .scale(grip.size())
+                .color(Color::from_rgb(90, 95, 105)),
+        );
+
+        canvas.finish(ctx)
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        if button != MouseButton::Left {
+            return Ok(());
+        }
+        if Self::close_button(ctx).contains([x, y]) {
+            ctx.request_quit();
+        } else if Self::resize_grip(ctx).contains([x, y]) {
+            // not every platform supports this
+            if let Err(e) = ctx.gfx.begin_window_resize(ResizeDirection::SouthEast) {
+                println!("Could not resize the window: {}", e);
+            }
+        } else if y < TITLE_BAR_HEIGHT {
+            ctx.gfx.begin_window_drag()?;
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("custom_titlebar", "ggez")
+        .window_setup(WindowSetup::default().title("Custom title bar"))
+        .window_mode(
+            WindowMode::default()
+                .borderless(true)
+                .resizable(true)
+                .min_dimensions(200., 100.),
+        );
+    let (ctx, event_loop) = cb.build()?;
+    event::run(ctx, event_loop, MainState)
+}
@@ -0,0 +1,105 @@
+//! Shows the debug overlay over a field of moving dots, toggled with F3, with the number of
+//! dots watched. Pressing space adds more of them, to see what that does to the frame times.
+//! The console, opened with the backtick key, shows the log and has a `dots` command setting
+//! how many there are.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use ggez::debug::{Console, Corner, Overlay};
+use ggez::event;
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    overlay: Overlay,
+    console: Console,
+    dots: Rc<Cell<usize>>,
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let dot = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Vec2::new(0.0, 0.0),
+            2.0,
+            0.5,
+            Color::WHITE,
+        )?;
+        let t = ctx.time.time_since_start().as_secs_f32();
+        for i in 0..self.dots.get() {
+            let angle = t + i as f32 * 0.01;
+            let point = Vec2::new(
+                400.0 + angle.cos() * 200.0,
+                300.0 + (angle * 3.0).sin() * 200.0,
+            );
+            canvas.draw(&dot, point);
+        }
+        canvas.draw(
+            &graphics::Text::new("Press F3 to toggle the overlay and space for more dots."),
+            Vec2::new(10.0, 570.0),
+        );
+        canvas.finish(ctx)?;
+
+        // over everything else
+        self.overlay.draw(ctx)?;
+        self.console.draw(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        if self.console.key_down_event(ctx, input, repeat)
+            || self.overlay.key_down_event(input, repeat)
+        {
+            return Ok(());
+        }
+        if input.keycode == Some(KeyCode::Space) {
+            self.dots.set(self.dots.get() + 1000);
+            log::info!("{} dots", self.dots.get());
+        }
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) -> GameResult {
+        let _ = self.console.text_input_event(ctx, character);
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("debug_overlay", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+
+    let dots = Rc::new(Cell::new(1000));
+    let mut overlay = Overlay::new();
+    overlay.set_corner(Corner::TopRight);
+    overlay.set_visible(true);
+    let watched = dots.clone();
+    overlay.watch("dots", move || watched.get().to_string());
+
+    let mut console = Console::new();
+    console.install_logger(log::LevelFilter::Info, None)?;
+    let count = dots.clone();
+    console.register("dots", move |args| match args.first().map(|n| n.parse()) {
+        Some(Ok(n)) => {
+            count.set(n);
+            String::new()
+        }
+        Some(Err(_)) => format!("`{}` isn't a number", args[0]),
+        None => count.get().to_string(),
+    });
+
+    let state = MainState {
+        overlay,
+        console,
+        dots,
+    };
+    event::run(ctx, event_loop, state)
+}
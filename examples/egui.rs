@@ -0,0 +1,126 @@
+//! A box bouncing around the window, with an egui window of settings for it: its speed, size
+//! and color, and a text field for its label. Typing in the text field doesn't pause the game
+//! with space, as egui takes the keys while it has the focus.
+//!
+//! Run with `cargo run --example egui --features egui`.
+
+use ggez::debug::Egui;
+use ggez::egui;
+use ggez::event::{self, winit_event::WindowEvent, EventConsumed};
+use ggez::glam::*;
+use ggez::graphics::{self, Color, Rect};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    egui: Egui,
+    pos: Vec2,
+    velocity: Vec2,
+    speed: f32,
+    size: f32,
+    color: [f32; 3],
+    label: String,
+    paused: bool,
+}
+
+impl MainState {
+    fn new(ctx: &Context) -> MainState {
+        MainState {
+            egui: Egui::new(ctx),
+            pos: Vec2::new(100.0, 100.0),
+            velocity: Vec2::new(1.0, 0.7).normalize(),
+            speed: 200.0,
+            size: 60.0,
+            color: [0.9, 0.5, 0.2],
+            label: String::from("ggez"),
+            paused: false,
+        }
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let (speed, size, color, label, paused) = (
+            &mut self.speed,
+            &mut self.size,
+            &mut self.color,
+            &mut self.label,
+            &mut self.paused,
+        );
+        self.egui.run(ctx, |egui_ctx| {
+            egui::Window::new("Box").show(egui_ctx, |ui| {
+                ui.add(egui::Slider::new(speed, 0.0..=800.0).text("speed"));
+                ui.add(egui::Slider::new(size, 10.0..=200.0).text("size"));
+                ui.horizontal(|ui| {
+                    ui.label("color");
+                    ui.color_edit_button_rgb(color);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("label");
+                    ui.text_edit_singleline(label);
+                });
+                ui.checkbox(paused, "paused (space)");
+            });
+        });
+
+        if !self.paused {
+            let dt = ctx.time.delta().as_secs_f32();
+            let (width, height) = ctx.gfx.drawable_size();
+            self.pos += self.velocity * self.speed * dt;
+            if self.pos.x < 0.0 || self.pos.x + self.size > width {
+                self.velocity.x = -self.velocity.x;
+            }
+            if self.pos.y < 0.0 || self.pos.y + self.size > height {
+                self.velocity.y = -self.velocity.y;
+            }
+            self.pos = self.pos.clamp(
+                Vec2::ZERO,
+                Vec2::new(width - self.size, height - self.size).max(Vec2::ZERO),
+            );
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let [r, g, b] = self.color;
+        let rect = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Rect::new(0.0, 0.0, self.size, self.size),
+            Color::new(r, g, b, 1.0),
+        )?;
+        canvas.draw(&rect, self.pos);
+        canvas.draw(
+            &graphics::Text::new(self.label.as_str()),
+            self.pos + Vec2::new(4.0, 4.0),
+        );
+        canvas.finish(ctx)?;
+
+        // over everything else
+        self.egui.draw(ctx)
+    }
+
+    fn raw_window_event(
+        &mut self,
+        _ctx: &mut Context,
+        event: &WindowEvent<'_>,
+    ) -> GameResult<EventConsumed> {
+        Ok(self.egui.raw_window_event(event))
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        // not called for the keys typed into the label's text field
+        if input.keycode == Some(KeyCode::Space) && !repeat {
+            self.paused = !self.paused;
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("egui", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&ctx);
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,97 @@
+//! Moves a box at a fixed rate of updates, slow enough to see them, drawing it as it was at
+//! the last update above, and in between the last two below, which moves smoothly.
+
+use ggez::event;
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+const SPEED: f32 = 400.0;
+
+struct MainState {
+    // where the box was at the last fixed update, and the one before
+    x: f32,
+    previous_x: f32,
+    direction: f32,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> MainState {
+        ctx.time.set_fixed_update_rate(Some(10));
+        MainState {
+            x: 0.0,
+            previous_x: 0.0,
+            direction: 1.0,
+        }
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn fixed_update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ctx.time.fixed_delta().unwrap_or_default().as_secs_f32();
+        self.previous_x = self.x;
+        self.x += self.direction * SPEED * dt;
+        if !(0.0..=700.0).contains(&self.x) {
+            self.x = self.x.clamp(0.0, 700.0);
+            self.direction = -self.direction;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+
+        let rect = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 100.0, 100.0),
+            Color::WHITE,
+        )?;
+        canvas.draw(&rect, Vec2::new(self.x, 150.0));
+        let alpha = ctx.time.blend_factor();
+        let blended = self.previous_x + (self.x - self.previous_x) * alpha;
+        canvas.draw(&rect, Vec2::new(blended, 350.0));
+
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "{} updates a second, up and down to change. Drawing at {:.0} fps.",
+                ctx.time.fixed_update_rate().unwrap_or_default(),
+                ctx.time.fps()
+            )),
+            Vec2::new(10.0, 10.0),
+        );
+        canvas.draw(
+            &graphics::Text::new("As of the last update:"),
+            Vec2::new(10.0, 120.0),
+        );
+        canvas.draw(
+            &graphics::Text::new("Between the last two updates:"),
+            Vec2::new(10.0, 320.0),
+        );
+
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+        let rate = ctx.time.fixed_update_rate().unwrap_or(10);
+        match input.keycode {
+            Some(KeyCode::Up) => ctx.time.set_fixed_update_rate(Some(rate * 2)),
+            Some(KeyCode::Down) => ctx.time.set_fixed_update_rate(Some((rate / 2).max(1))),
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("fixed_timestep", "ggez");
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&mut ctx);
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,74 @@
+//! Runs a game a frame at a time with a `GameLoop`, from a loop of its own, as an application
+//! embedding a preview of it would, doing some work of its own in between.
+
+use std::time::{Duration, Instant};
+
+use ggez::event::{self, GameLoop, LoopControl};
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    pos_x: f32,
+    // set by the host
+    message: String,
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.pos_x = (self.pos_x + 200.0 * ctx.time.delta().as_secs_f32()) % 800.0;
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Vec2::new(0.0, 0.0),
+            50.0,
+            2.0,
+            Color::WHITE,
+        )?;
+        canvas.draw(&circle, Vec2::new(self.pos_x, 380.0));
+        canvas.draw(&graphics::Text::new(&self.message), Vec2::new(10.0, 10.0));
+        canvas.finish(ctx)
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("game_loop", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    let state = MainState {
+        pos_x: 0.0,
+        message: String::new(),
+    };
+    let mut game: GameLoop<MainState> = GameLoop::new(ctx, event_loop, state);
+
+    let start = Instant::now();
+    let mut steps = 0;
+    loop {
+        if let LoopControl::Exit(code) = game.step() {
+            println!("The game quit with code {}", code);
+            break;
+        }
+        steps += 1;
+
+        // the host's own work, which takes a while now and then
+        let elapsed = start.elapsed();
+        if steps % 100 == 0 {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        game.state_mut().message = format!(
+            "Stepped {} times by the host in {:.1}s. It stops after 10s.",
+            steps,
+            elapsed.as_secs_f32()
+        );
+        if elapsed > Duration::from_secs(10) {
+            // dropping the game closes its window
+            println!("The host stopped the game");
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Balls bouncing around the window, as entities of a [hecs](https://docs.rs/hecs) world, moved,
+//! played and drawn by systems which each borrow only the part of the context they use, out of
+//! `ctx.split()`. Clicking adds a ball where the mouse is, and every bounce goes "pew".
+
+use std::env;
+use std::path;
+
+use ggez::audio::{self, AudioContext, SoundSource};
+use ggez::event;
+use ggez::glam::*;
+use ggez::graphics::{self, Color, DrawParam, GraphicsContext};
+use ggez::input::mouse::{MouseButton, MouseContext};
+use ggez::timer::TimeContext;
+use ggez::{Context, GameResult};
+use hecs::World;
+
+struct Position(Vec2);
+
+struct Velocity(Vec2);
+
+struct Ball {
+    radius: f32,
+    color: Color,
+    // since the sound system last played its bounce
+    bounced: bool,
+}
+
+fn spawn_ball(world: &mut World, pos: Vec2) {
+    let n = world.len() as f32;
+    let angle = n * 2.4;
+    let _ = world.spawn((
+        Position(pos),
+        Velocity(Vec2::new(angle.cos(), angle.sin()) * 250.0),
+        Ball {
+            radius: 10.0 + (n * 7.0) % 20.0,
+            color: Color::new(0.5 + (n * 0.3) % 0.5, 0.4, 0.9 - (n * 0.2) % 0.5, 1.0),
+            bounced: false,
+        },
+    ));
+}
+
+fn spawn_system(world: &mut World, mouse: &MouseContext) {
+    if mouse.button_just_pressed(MouseButton::Left) {
+        spawn_ball(world, mouse.position().into());
+    }
+}
+
+fn movement_system(world: &mut World, time: &TimeContext, gfx: &GraphicsContext) {
+    let dt = time.delta().as_secs_f32();
+    let size = Vec2::from(gfx.drawable_size());
+    for (_, (pos, vel, ball)) in world.query_mut::<(&mut Position, &mut Velocity, &mut Ball)>() {
+        pos.0 += vel.0 * dt;
+        let min = Vec2::splat(ball.radius);
+        let max = (size - min).max(min);
+        if pos.0.x < min.x || pos.0.x > max.x {
+            vel.0.x = -vel.0.x;
+            ball.bounced = true;
+        }
+        if pos.0.y < min.y || pos.0.y > max.y {
+            vel.0.y = -vel.0.y;
+            ball.bounced = true;
+        }
+        pos.0 = pos.0.clamp(min, max);
+    }
+}
+
+fn sound_system(world: &mut World, audio: &AudioContext, pew: &mut audio::Source) -> GameResult {
+    for (_, ball) in world.query_mut::<&mut Ball>() {
+        if std::mem::take(&mut ball.bounced) {
+            pew.play_detached(audio)?;
+        }
+    }
+    Ok(())
+}
+
+fn render_system(world: &World, gfx: &mut GraphicsContext, circle: &graphics::Mesh) -> GameResult {
+    let mut canvas = graphics::Canvas::from_frame(gfx, Color::from([0.1, 0.2, 0.3, 1.0]));
+    for (_, (pos, ball)) in world.query::<(&Position, &Ball)>().iter() {
+        canvas.draw(
+            circle,
+            DrawParam::new()
+                .dest(pos.0)
+                .scale(Vec2::splat(ball.radius))
+                .color(ball.color),
+        );
+    }
+    canvas.finish(gfx)
+}
+
+struct MainState {
+    world: World,
+    pew: audio::Source,
+    // of radius 1, scaled for every ball
+    circle: graphics::Mesh,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> GameResult<MainState> {
+        let mut world = World::new();
+        for i in 0..5 {
+            spawn_ball(&mut world, Vec2::new(100.0 + i as f32 * 120.0, 300.0));
+        }
+        let mut pew = audio::Source::new(ctx, "/pew.ogg")?;
+        pew.set_volume(0.3);
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Vec2::ZERO,
+            1.0,
+            0.01,
+            Color::WHITE,
+        )?;
+        Ok(MainState { world, pew, circle })
+    }
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // each system gets its own part of the context, which could as well run at once
+        let parts = ctx.split();
+        spawn_system(&mut self.world, parts.mouse);
+        movement_system(&mut self.world, parts.time, parts.gfx);
+        sound_system(&mut self.world, parts.audio, &mut self.pew)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let parts = ctx.split();
+        render_system(&self.world, parts.gfx, &self.circle)
+    }
+}
+
+pub fn main() -> GameResult {
+    let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        let mut path = path::PathBuf::from(manifest_dir);
+        path.push("resources");
+        path
+    } else {
+        path::PathBuf::from("./resources")
+    };
+
+    let cb = ggez::ContextBuilder::new("hecs", "ggez").add_resource_path(resource_dir);
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&mut ctx)?;
+    event::run(ctx, event_loop, state)
+}
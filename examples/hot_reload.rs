@@ -0,0 +1,86 @@
+//! Reloads an image and a shader when their files change, with `ctx.fs.watch`. Run with
+//! `cargo run --example hot_reload --features hot-reload`, and edit `resources/player.png` or
+//! `resources/dimmer.wgsl` while it runs.
+//!
+//! Shaders which don't compile still stop the game, as they do when it starts.
+
+use crevice::std140::AsStd140;
+use ggez::event;
+use ggez::filesystem::WatchHandle;
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color};
+use ggez::{Context, GameResult};
+use std::env;
+use std::path;
+
+#[derive(AsStd140)]
+struct Dim {
+    rate: f32,
+}
+
+struct MainState {
+    image: graphics::Image,
+    shader: graphics::Shader,
+    params: graphics::ShaderParams<Dim>,
+    // the files are only watched as long as these are kept
+    _watching: Vec<WatchHandle>,
+}
+
+fn load_shader(ctx: &Context) -> GameResult<graphics::Shader> {
+    graphics::ShaderBuilder::new_wgsl()
+        .fragment_path("/dimmer.wgsl")
+        .build(&ctx.gfx)
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> GameResult<MainState> {
+        let params = graphics::ShaderParamsBuilder::new(&Dim { rate: 0.5 }).build(&mut ctx.gfx);
+        Ok(MainState {
+            image: graphics::Image::from_path(ctx, "/player.png")?,
+            shader: load_shader(ctx)?,
+            params,
+            _watching: vec![ctx.fs.watch("/player.png")?, ctx.fs.watch("/dimmer.wgsl")?],
+        })
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        for path in ctx.fs.take_changed_paths() {
+            println!("{} changed, reloading it", path);
+            match path.as_str() {
+                "/player.png" => self.image = graphics::Image::from_path(ctx, "/player.png")?,
+                "/dimmer.wgsl" => self.shader = load_shader(ctx)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let params = graphics::DrawParam::new().scale(Vec2::splat(4.0));
+        canvas.draw(&self.image, params.dest(Vec2::new(200.0, 250.0)));
+
+        canvas.set_shader(self.shader.clone());
+        canvas.set_shader_params(self.params.clone());
+        canvas.draw(&self.image, params.dest(Vec2::new(500.0, 250.0)));
+        canvas.finish(ctx)
+    }
+}
+
+pub fn main() -> GameResult {
+    let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        let mut path = path::PathBuf::from(manifest_dir);
+        path.push("resources");
+        path
+    } else {
+        path::PathBuf::from("./resources")
+    };
+
+    let cb = ggez::ContextBuilder::new("hot_reload", "ggez").add_resource_path(resource_dir);
+    let (mut ctx, event_loop) = cb.build()?;
+
+    let state = MainState::new(&mut ctx)?;
+    event::run(ctx, event_loop, state)
+}
@@ -12,6 +12,8 @@ struct MainState {
     a: i32,
     direction: i32,
     image: graphics::Image,
+    // whether a file is dragged over the window
+    hovering: bool,
     rng: oorandom::Rand32,
 }
 
@@ -57,6 +59,7 @@ impl MainState {
             a: 0,
             direction: 1,
             image,
+            hovering: false,
             rng,
         };
 
@@ -81,7 +84,12 @@ impl event::EventHandler<ggez::GameError> for MainState {
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         let c = self.a as u8;
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let background = if self.hovering {
+            Color::from([0.2, 0.4, 0.6, 1.0])
+        } else {
+            Color::from([0.1, 0.2, 0.3, 1.0])
+        };
+        let mut canvas = graphics::Canvas::from_frame(ctx, background);
 
         let color = Color::from((c, c, c, 255));
         let dest_point = Vec2::new(0.0, 0.0);
@@ -110,6 +118,35 @@ impl event::EventHandler<ggez::GameError> for MainState {
         timer::yield_now();
         Ok(())
     }
+
+    fn file_hover_event(&mut self, _ctx: &mut Context, _path: path::PathBuf) -> GameResult {
+        self.hovering = true;
+        Ok(())
+    }
+
+    fn file_hover_cancelled_event(&mut self, _ctx: &mut Context) -> GameResult {
+        self.hovering = false;
+        Ok(())
+    }
+
+    fn file_drop_event(
+        &mut self,
+        ctx: &mut Context,
+        path: path::PathBuf,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        self.hovering = false;
+        // dropped files are outside of the resource directories, so read them directly
+        let image = std::fs::read(&path)
+            .map_err(ggez::GameError::from)
+            .and_then(|bytes| graphics::Image::from_bytes(ctx, &bytes));
+        match image {
+            Ok(image) => self.image = image,
+            Err(e) => println!("Could not open {}: {}", path.display(), e),
+        }
+        Ok(())
+    }
 }
 
 pub fn main() -> GameResult {
@@ -6,7 +6,7 @@ use ggez::conf;
 use ggez::event::{self, Axis, Button, GamepadId, MouseButton};
 use ggez::glam::*;
 use ggez::graphics::{self, Color, DrawMode};
-use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::input::keyboard::{KeyCode, KeyInput, KeyMods};
 use ggez::{Context, GameResult};
 
 ///A struct which creates the input test's game state
@@ -14,6 +14,8 @@ struct MainState {
     pos_x: f32,
     pos_y: f32,
     mouse_down: bool,
+    // T toggles showing how far the triggers of the active gamepad are pulled, to test triggers and pedals
+    show_triggers: bool,
 }
 
 impl MainState {
@@ -22,6 +24,7 @@ impl MainState {
             pos_x: 100.0,
             pos_y: 100.0,
             mouse_down: false,
+            show_triggers: false,
         }
     }
 }
@@ -31,6 +34,7 @@ impl MainState {
 ///our update function...Which will define the input taken in and print said key strokes.
 impl event::EventHandler<ggez::GameError> for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // level-triggered: true for as long as the key is held
         if ctx.keyboard.is_key_pressed(KeyCode::A) {
             println!("The A key is pressed");
             if ctx
@@ -44,6 +48,36 @@ impl event::EventHandler<ggez::GameError> for MainState {
                 ctx.keyboard.pressed_keys()
             );
         }
+        // edge-triggered: true only in the frame the key went down or up
+        if ctx.keyboard.is_key_just_pressed(KeyCode::Space) {
+            println!("The space key was just pressed");
+        }
+        if ctx.keyboard.is_key_just_released(KeyCode::Space) {
+            println!("The space key was just released");
+        }
+
+        // gamepads can be polled the same way, without any of the gamepad callbacks below;
+        // the active gamepad is the one used last
+        let dt = ctx.time.delta().as_secs_f32();
+        self.pos_x += ctx.gamepad.active_axis_value(Axis::LeftStickX) * 300.0 * dt;
+        self.pos_y -= ctx.gamepad.active_axis_value(Axis::LeftStickY) * 300.0 * dt;
+        if let Some(id) = ctx.gamepad.active_gamepad() {
+            if ctx.gamepad.button_just_pressed(id, Button::South) {
+                println!(
+                    "Buttons held on {:?}: {:?}",
+                    id,
+                    ctx.gamepad.buttons_pressed(id).collect::<Vec<_>>()
+                );
+            }
+        }
+        // holding the d-pad repeats, like scrolling through a menu
+        for button in [Button::DPadUp, Button::DPadDown] {
+            if ctx.gamepad.active_button_just_pressed(button)
+                || ctx.gamepad.active_button_repeated(button)
+            {
+                println!("Menu scrolled with {:?}", button);
+            }
+        }
         Ok(())
     }
 
@@ -63,6 +97,45 @@ impl event::EventHandler<ggez::GameError> for MainState {
             Color::WHITE,
         )?;
         canvas.draw(&rectangle, Vec2::new(0.0, 0.0));
+
+        // list the connected gamepads, highlighting the active one
+        let active = ctx.gamepad.active_gamepad();
+        for (i, (id, info)) in ctx.gamepad.gamepads().enumerate() {
+            let color = if Some(id) == active {
+                Color::YELLOW
+            } else {
+                Color::WHITE
+            };
+            canvas.draw(
+                &graphics::Text::new(format!("Gamepad {}: {}", i + 1, info.name)),
+                graphics::DrawParam::from([10.0, 10.0 + 20.0 * i as f32]).color(color),
+            );
+        }
+
+        // the analog values of the triggers, after the deadzone and as reported
+        if self.show_triggers {
+            if let Some(id) = active {
+                for (i, button) in [Button::LeftTrigger2, Button::RightTrigger2]
+                    .into_iter()
+                    .enumerate()
+                {
+                    let value = ctx.gamepad.button_value(id, button);
+                    let raw = ctx.gamepad.raw_button_value(id, button);
+                    let x = 500.0 + 150.0 * i as f32;
+                    let bar = graphics::Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        graphics::Rect::new(x, 400.0 - 200.0 * value, 40.0, 200.0 * value),
+                        Color::GREEN,
+                    )?;
+                    canvas.draw(&bar, Vec2::ZERO);
+                    canvas.draw(
+                        &graphics::Text::new(format!("{:?}\n{:.2} ({:.2})", button, value, raw)),
+                        Vec2::new(x, 410.0),
+                    );
+                }
+            }
+        }
         canvas.finish(ctx)?;
         Ok(())
     }
@@ -99,28 +172,20 @@ impl event::EventHandler<ggez::GameError> for MainState {
     ///Records and displays/prints the movement of our cursor amongst our games environment.
     fn mouse_motion_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         x: f32,
         y: f32,
         xrel: f32,
         yrel: f32,
     ) -> GameResult {
         if self.mouse_down {
-            // Mouse coordinates are PHYSICAL coordinates, but here we want logical coordinates.
-
-            // If you simply use the initial coordinate system, then physical and logical
-            // coordinates are identical.
-            self.pos_x = x;
-            self.pos_y = y;
-
-            // If you change your screen coordinate system you need to calculate the
-            // logical coordinates like this:
-            /*
-            let screen_rect = graphics::screen_coordinates(_ctx);
-            let size = graphics::window(_ctx).inner_size();
-            self.pos_x = (x / (size.width  as f32)) * screen_rect.w + screen_rect.x;
-            self.pos_y = (y / (size.height as f32)) * screen_rect.h + screen_rect.y;
-            */
+            // Mouse coordinates are PHYSICAL coordinates, but here we want logical coordinates,
+            // i.e. the screen coordinates of the canvas we draw with.
+            // If you simply use the initial coordinate system these are identical, but they
+            // differ once you change the screen coordinates.
+            let pos = ctx.mouse.game_position(&ctx.gfx);
+            self.pos_x = pos.x;
+            self.pos_y = pos.y;
         }
         println!(
             "Mouse motion, x: {}, y: {}, relative x: {}, relative y: {}",
@@ -137,11 +202,19 @@ impl event::EventHandler<ggez::GameError> for MainState {
 
     ///Our key down Event Handler. Displays/prints when a key is...
     ///Pressed and held down, repeated, etc. this function coincides with our next function key up Event Handler.
-    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
         println!(
             "Key pressed: scancode {}, keycode {:?}, modifier {:?}, repeat: {}",
             input.scancode, input.keycode, input.mods, repeat
         );
+        // Alt+Enter switches to fullscreen and back
+        if input.keycode == Some(KeyCode::Return) && input.mods.contains(KeyMods::ALT) && !repeat
+        {
+            ctx.gfx.toggle_fullscreen(conf::FullscreenType::Desktop)?;
+        }
+        if input.keycode == Some(KeyCode::T) && !repeat {
+            self.show_triggers = !self.show_triggers;
+        }
         Ok(())
     }
 
@@ -223,12 +296,9 @@ pub fn main() -> GameResult {
     );
     let (ctx, event_loop) = cb.build()?;
 
-    // remove the comment to see how physical mouse coordinates can differ
-    // from logical game coordinates when the screen coordinate system changes
-    // graphics::set_screen_coordinates(&mut ctx, Rect::new(20., 50., 2000., 1000.));
-
-    // alternatively, resizing the window also leads to screen coordinates
-    // and physical window size being out of sync
+    // try calling `canvas.set_screen_coordinates(Rect::new(20., 50., 2000., 1000.))` in `draw`
+    // to see how physical mouse coordinates can differ from logical game coordinates;
+    // `ctx.mouse.game_position` keeps the rectangle under the cursor regardless
 
     let state = MainState::new();
     event::run(ctx, event_loop, state)
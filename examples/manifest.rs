@@ -0,0 +1,44 @@
+//! Generates a manifest of the files in a directory, to ship with the game and check them
+//! against with `Manifest::verify` when it starts. Run with
+//! `cargo run --example manifest --features manifest -- resources manifest.toml`, adding
+//! `--sha256` to hash them with SHA-256 rather than XXH3.
+
+use ggez::filesystem::manifest::{HashAlgorithm, Manifest};
+use ggez::GameResult;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+fn main() -> GameResult {
+    let mut algorithm = HashAlgorithm::XxHash3;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--sha256" => algorithm = HashAlgorithm::Sha256,
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let (dir, out) = match &paths[..] {
+        [dir] => (dir, None),
+        [dir, out] => (dir, Some(out)),
+        _ => {
+            eprintln!("Usage: manifest [--sha256] <directory> [<output>.toml]");
+            process::exit(2);
+        }
+    };
+
+    let manifest = Manifest::generate_dir(dir, algorithm)?;
+    let text = toml::to_string_pretty(&manifest)?;
+    match out {
+        Some(out) => {
+            std::fs::write(out, text)?;
+            println!(
+                "Wrote the manifest of {} files to {}",
+                manifest.files.len(),
+                out.display()
+            );
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Opens a second window next to the main one, drawing to both
+//! and reacting to the mouse in the second one.
+
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::event::{self, SecondaryWindowEvent};
+use ggez::graphics::{self, Color, WindowId};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    palette: Option<WindowId>,
+    picked: Color,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> MainState {
+        let palette = ctx.gfx.create_window(
+            WindowMode::default().dimensions(200., 200.),
+            WindowSetup::default().title("Palette"),
+        );
+        MainState {
+            palette: Some(palette),
+            picked: Color::WHITE,
+        }
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, self.picked);
+        canvas.draw(
+            graphics::Text::new("Click into the palette window to pick a color").set_scale(24.),
+            graphics::DrawParam::from([10., 10.]).color(Color::BLACK),
+        );
+        canvas.finish(ctx)?;
+
+        if let Some(palette) = self.palette {
+            let mut canvas = graphics::Canvas::from_window_frame(ctx, palette, Color::BLACK)?;
+            let colors = [Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW];
+            for (i, color) in colors.into_iter().enumerate() {
+                canvas.draw(
+                    &graphics::Quad,
+                    graphics::DrawParam::new()
+                        .dest([(i % 2) as f32 * 100., (i / 2) as f32 * 100.])
+                        .scale([100., 100.])
+                        .color(color),
+                );
+            }
+            canvas.finish(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn secondary_window_event(
+        &mut self,
+        _ctx: &mut Context,
+        window: WindowId,
+        event: SecondaryWindowEvent,
+    ) -> GameResult {
+        match event {
+            SecondaryWindowEvent::MouseButtonDown { x, y, .. } => {
+                self.picked = match (x < 100., y < 100.) {
+                    (true, true) => Color::RED,
+                    (false, true) => Color::GREEN,
+                    (true, false) => Color::BLUE,
+                    (false, false) => Color::YELLOW,
+                };
+            }
+            SecondaryWindowEvent::CloseRequested if Some(window) == self.palette => {
+                self.palette = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("multiple_windows", "ggez");
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&mut ctx);
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,45 @@
+//! Opens the platform's native file dialog, parented to the game window through its raw window
+//! handle, when O is pressed, and shows which file was picked.
+
+use ggez::event;
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    picked: Option<std::path::PathBuf>,
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let text = match &self.picked {
+            Some(path) => format!("Picked {}\n\nPress O to pick another file.", path.display()),
+            None => String::from("Press O to pick a file."),
+        };
+        canvas.draw(&graphics::Text::new(text), Vec2::new(10.0, 10.0));
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        if input.keycode == Some(KeyCode::O) && !repeat {
+            // the handle is only borrowed for as long as the dialog is open, which blocks
+            let handle = ctx.gfx.window_handle()?;
+            if let Some(path) = rfd::FileDialog::new().set_parent(&handle).pick_file() {
+                self.picked = Some(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("native_dialog", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    event::run(ctx, event_loop, MainState { picked: None })
+}
@@ -0,0 +1,66 @@
+//! Shows how long the engine's phases and a scope of the game's own took last frame, with
+//! `ctx.time.profile_report()`. Run with `cargo run --example profiling --features profiling`.
+//!
+//! The scopes are `tracing` spans as well, so installing a subscriber such as the one from
+//! the `tracing-tracy` crate, with `tracing::subscriber::set_global_default`, shows them in
+//! the Tracy profiler too.
+
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::{event, timer, Context, GameResult};
+
+struct MainState {
+    points: Vec<Vec2>,
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // something for the game's own scope to time
+        let _scope = timer::scope("points");
+        let t = ctx.time.time_since_start().as_secs_f32();
+        for (i, point) in self.points.iter_mut().enumerate() {
+            let angle = t + i as f32 * 0.01;
+            *point = Vec2::new(
+                400.0 + angle.cos() * 200.0,
+                300.0 + (angle * 3.0).sin() * 200.0,
+            );
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        let dot = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Vec2::new(0.0, 0.0),
+            2.0,
+            0.5,
+            Color::WHITE,
+        )?;
+        for &point in &self.points {
+            canvas.draw(&dot, point);
+        }
+
+        let mut report = String::new();
+        for entry in ctx.time.profile_report() {
+            report += &format!(
+                "{:>14}: {:>8.3} ms over {} times\n",
+                entry.name,
+                entry.total.as_secs_f64() * 1000.0,
+                entry.count
+            );
+        }
+        canvas.draw(&graphics::Text::new(report), Vec2::new(10.0, 10.0));
+        canvas.finish(ctx)
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("profiling", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    let state = MainState {
+        points: vec![Vec2::ZERO; 2000],
+    };
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,73 @@
+//! Only draws when something happens, as a tool would, with `LoopMode::WaitForEvents`, rather
+//! than drawing the same thing over and over again. Clicking runs a short animation, which
+//! asks for every frame of it to be drawn.
+
+use std::time::Duration;
+
+use ggez::event::{self, LoopMode, MouseButton};
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    frames_drawn: u64,
+    // how much of the animation is left to play, in seconds
+    animation: f32,
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if self.animation > 0.0 {
+            self.animation = (self.animation - ctx.time.delta().as_secs_f32()).max(0.0);
+            ctx.request_redraw();
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        self.frames_drawn += 1;
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "{} frames drawn. Move the mouse or click to draw more.",
+                self.frames_drawn
+            )),
+            Vec2::new(10.0, 10.0),
+        );
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Vec2::new(0.0, 0.0),
+            20.0 + 100.0 * self.animation,
+            2.0,
+            Color::WHITE,
+        )?;
+        canvas.draw(&circle, Vec2::new(400.0, 300.0));
+        canvas.finish(ctx)
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        self.animation = 1.0;
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("redraw_on_demand", "ggez");
+    let (mut ctx, event_loop) = cb.build()?;
+    ctx.set_loop_mode(LoopMode::WaitForEvents {
+        // draws at least every few seconds anyway
+        max_wait: Some(Duration::from_secs(5)),
+    });
+    let state = MainState {
+        frames_drawn: 0,
+        animation: 0.0,
+    };
+    event::run(ctx, event_loop, state)
+}
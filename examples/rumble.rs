@@ -0,0 +1,67 @@
+//! Rumbles a gamepad when its buttons are pressed: the south button (A on Xbox gamepads)
+//! rumbles the strong motor, the east button (B) the weak one.
+
+use ggez::event::{self, Button, GamepadId};
+use ggez::graphics::{self, Color};
+use ggez::input::gamepad::GamepadInfo;
+use ggez::{Context, GameResult};
+use std::time::Duration;
+
+struct MainState {
+    status: String,
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(20, 20, 30));
+        canvas.draw(
+            graphics::Text::new(&self.status).set_scale(24.),
+            graphics::DrawParam::from([10., 10.]),
+        );
+        canvas.finish(ctx)
+    }
+
+    fn gamepad_connected_event(
+        &mut self,
+        ctx: &mut Context,
+        id: GamepadId,
+        info: GamepadInfo,
+    ) -> GameResult {
+        self.status = if ctx.gamepad.supports_rumble(id) {
+            format!("{} can rumble, press A or B", info.name)
+        } else {
+            format!("{} can't rumble", info.name)
+        };
+        Ok(())
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        let result = match btn {
+            Button::South => ctx.gamepad.rumble(id, 1.0, 0.0, Duration::from_millis(300)),
+            Button::East => ctx.gamepad.rumble(id, 0.0, 1.0, Duration::from_millis(300)),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.status = e.to_string();
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("rumble", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    let state = MainState {
+        status: String::from("Connect a gamepad"),
+    };
+    event::run(ctx, event_loop, state)
+}
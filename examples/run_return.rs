@@ -0,0 +1,64 @@
+//! Runs the game until it quits with `event::run_return`, then prints how it went, which
+//! `event::run` would have exited the process before.
+
+use ggez::event;
+use ggez::glam::*;
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    frames: u64,
+    keys_pressed: u64,
+}
+
+impl event::EventHandler for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from([0.1, 0.2, 0.3, 1.0]));
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "{} keys pressed. Press escape to quit, which is reported after.",
+                self.keys_pressed
+            )),
+            Vec2::new(10.0, 10.0),
+        );
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        if !repeat {
+            self.keys_pressed += 1;
+        }
+        if input.keycode == Some(KeyCode::Escape) {
+            ctx.request_quit_with(event::ExitReason::Code(3));
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("run_return", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    let state = MainState {
+        frames: 0,
+        keys_pressed: 0,
+    };
+    let (ctx, _event_loop, state) = event::run_return(ctx, event_loop, state);
+
+    let code = ctx.exit_reason().map_or(0, event::ExitReason::code);
+    println!(
+        "Ran for {:.1}s, {} frames, {} keys pressed, quitting with code {}",
+        ctx.time.time_since_start().as_secs_f32(),
+        state.frames,
+        state.keys_pressed,
+        code
+    );
+    // closes the window before exiting
+    drop(ctx);
+    std::process::exit(code)
+}
@@ -0,0 +1,236 @@
+//! Goes from a title screen to the game, with a translucent pause menu over it, using a
+//! `SceneStack`. The best score is kept in the world the scenes share.
+
+use ggez::glam::*;
+use ggez::graphics::{self, Canvas, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::scene::{Scene, SceneStack, SceneSwitch};
+use ggez::{event, Context, GameResult};
+
+struct World {
+    best_score: u32,
+    // set by the pause menu, for the game under it
+    back_to_title: bool,
+}
+
+struct Title {
+    next: SceneSwitch<World>,
+}
+
+impl Scene<World> for Title {
+    fn update(&mut self, _ctx: &mut Context, _world: &mut World) -> GameResult<SceneSwitch<World>> {
+        Ok(std::mem::take(&mut self.next))
+    }
+
+    fn draw(&mut self, _ctx: &mut Context, world: &mut World, canvas: &mut Canvas) -> GameResult {
+        canvas.draw(
+            graphics::Text::new("Catch the boxes").set_scale(48.0),
+            Vec2::new(200.0, 200.0),
+        );
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "Best score: {}. Press enter to play, escape to quit.",
+                world.best_score
+            )),
+            Vec2::new(200.0, 280.0),
+        );
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        _world: &mut World,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        match input.keycode {
+            Some(KeyCode::Return) => self.next = SceneSwitch::replace(Game::new()),
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "title"
+    }
+}
+
+struct Game {
+    player: Vec2,
+    target: Vec2,
+    score: u32,
+    next: SceneSwitch<World>,
+}
+
+impl Game {
+    fn new() -> Game {
+        Game {
+            player: Vec2::new(400.0, 300.0),
+            target: Vec2::new(100.0, 100.0),
+            score: 0,
+            next: SceneSwitch::None,
+        }
+    }
+}
+
+impl Scene<World> for Game {
+    fn update(&mut self, ctx: &mut Context, world: &mut World) -> GameResult<SceneSwitch<World>> {
+        let dt = ctx.time.delta().as_secs_f32();
+        let keys = &ctx.keyboard;
+        let mut direction = Vec2::ZERO;
+        if keys.is_key_pressed(KeyCode::Left) {
+            direction.x -= 1.0;
+        }
+        if keys.is_key_pressed(KeyCode::Right) {
+            direction.x += 1.0;
+        }
+        if keys.is_key_pressed(KeyCode::Up) {
+            direction.y -= 1.0;
+        }
+        if keys.is_key_pressed(KeyCode::Down) {
+            direction.y += 1.0;
+        }
+        self.player += direction * 300.0 * dt;
+
+        if std::mem::take(&mut world.back_to_title) {
+            return Ok(SceneSwitch::replace(Title {
+                next: SceneSwitch::None,
+            }));
+        }
+
+        if self.player.distance(self.target) < 40.0 {
+            self.score += 1;
+            // somewhere else, not too close
+            self.target = Vec2::new(
+                ((self.target.x + 370.0) % 700.0) + 50.0,
+                ((self.target.y + 230.0) % 500.0) + 50.0,
+            );
+        }
+        Ok(std::mem::take(&mut self.next))
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _world: &mut World, canvas: &mut Canvas) -> GameResult {
+        let square = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(-20.0, -20.0, 40.0, 40.0),
+            Color::WHITE,
+        )?;
+        canvas.draw(&square, self.player);
+        canvas.draw(
+            &square,
+            graphics::DrawParam::new()
+                .dest(self.target)
+                .color(Color::YELLOW),
+        );
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "Score: {}. Arrow keys to move, escape to pause.",
+                self.score
+            )),
+            Vec2::new(10.0, 10.0),
+        );
+        Ok(())
+    }
+
+    fn on_exit(&mut self, _ctx: &mut Context, world: &mut World) -> GameResult {
+        world.best_score = world.best_score.max(self.score);
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut World,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        if input.keycode == Some(KeyCode::Escape) {
+            self.next = SceneSwitch::push(Pause {
+                next: SceneSwitch::None,
+            });
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "game"
+    }
+}
+
+struct Pause {
+    next: SceneSwitch<World>,
+}
+
+impl Scene<World> for Pause {
+    fn update(&mut self, _ctx: &mut Context, _world: &mut World) -> GameResult<SceneSwitch<World>> {
+        Ok(std::mem::take(&mut self.next))
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _world: &mut World, canvas: &mut Canvas) -> GameResult {
+        let (width, height) = ctx.gfx.drawable_size();
+        let shade = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, width, height),
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        )?;
+        canvas.draw(&shade, graphics::DrawParam::new());
+        canvas.draw(
+            graphics::Text::new("Paused").set_scale(48.0),
+            Vec2::new(200.0, 200.0),
+        );
+        canvas.draw(
+            &graphics::Text::new("Press escape to go on, or T for the title screen."),
+            Vec2::new(200.0, 280.0),
+        );
+        Ok(())
+    }
+
+    // the game is drawn under the pause menu, as it was when paused
+    fn draw_previous(&self) -> bool {
+        true
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        world: &mut World,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        match input.keycode {
+            Some(KeyCode::Escape) => self.next = SceneSwitch::Pop,
+            // the game under this goes back to the title screen once on top again
+            Some(KeyCode::T) => {
+                world.back_to_title = true;
+                self.next = SceneSwitch::Pop;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "pause"
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("scenes", "ggez");
+    let (mut ctx, event_loop) = cb.build()?;
+    let mut stack = SceneStack::new(
+        &mut ctx,
+        World {
+            best_score: 0,
+            back_to_title: false,
+        },
+        Title {
+            next: SceneSwitch::None,
+        },
+    )?;
+    stack.set_clear_color(Color::from([0.1, 0.2, 0.3, 1.0]));
+    event::run(ctx, event_loop, stack)
+}
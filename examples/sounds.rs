@@ -58,6 +58,26 @@ impl MainState {
         sound.play_detached(ctx).unwrap();
     }
 
+    /// Pauses or resumes the sound played with `play_later`, keeping its position.
+    fn toggle_pause(&mut self) {
+        if self.sound.paused() {
+            self.sound.resume();
+        } else {
+            self.sound.pause();
+        }
+    }
+
+    /// Fades the sound played with `play_later` out, stopping it, or in again after pausing it.
+    fn fade(&mut self) {
+        if self.sound.paused() {
+            self.sound.resume();
+            self.sound.fade_in(Duration::from_millis(300));
+        } else {
+            self.sound
+                .fade_out(Duration::from_millis(300), audio::AfterFade::Pause);
+        }
+    }
+
     /// Plays the sound and prints out stats until it's done.
     fn play_stats(&mut self, ctx: &mut Context) {
         let _ = self.sound.play(ctx);
@@ -80,6 +100,19 @@ impl event::EventHandler<ggez::GameError> for MainState {
             &graphics::Text::new("Press number keys 1-6 to play a sound, or escape to quit."),
             [100., 100.],
         );
+        canvas.draw(
+            &graphics::Text::new(format!(
+                "Press P to pause or resume sound 2, F to fade it out or in ({}).",
+                if self.sound.paused() {
+                    "paused"
+                } else if self.sound.playing() {
+                    "playing"
+                } else {
+                    "stopped"
+                }
+            )),
+            [100., 130.],
+        );
 
         canvas.finish(ctx)
     }
@@ -92,6 +125,8 @@ impl event::EventHandler<ggez::GameError> for MainState {
             Some(input::keyboard::KeyCode::Key4) => self.play_highpitch(ctx),
             Some(input::keyboard::KeyCode::Key5) => self.play_lowpitch(ctx),
             Some(input::keyboard::KeyCode::Key6) => self.play_stats(ctx),
+            Some(input::keyboard::KeyCode::P) => self.toggle_pause(),
+            Some(input::keyboard::KeyCode::F) => self.fade(),
             Some(input::keyboard::KeyCode::Escape) => ctx.request_quit(),
             _ => (),
         }
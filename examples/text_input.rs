@@ -0,0 +1,112 @@
+//! A text field, which polls the text typed each frame
+//! and shows what's being composed with an input method.
+//! Ctrl+C copies the text, Ctrl+V pastes into it.
+//!
+//! The default font has no glyphs for Chinese, Japanese or Korean,
+//! load one with `ctx.gfx.add_font` to see them.
+
+use ggez::event;
+use ggez::graphics::{self, Color, Rect, TextFragment};
+use ggez::input::keyboard::{KeyCode, KeyMods};
+use ggez::{Context, GameResult};
+
+const FIELD: Rect = Rect::new(50., 100., 500., 40.);
+
+struct MainState {
+    text: String,
+    // the text being composed, which isn't part of the field's text yet
+    preedit: String,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> MainState {
+        ctx.keyboard.set_ime_allowed(true);
+        ctx.keyboard.set_ime_cursor_area(FIELD);
+        ctx.keyboard.set_text_newlines(false);
+        MainState {
+            text: String::new(),
+            preedit: String::new(),
+        }
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // typed characters as well as text committed by the input method
+        self.text.push_str(ctx.keyboard.text_entered());
+
+        // Cmd on macOS, Ctrl elsewhere
+        let shortcut = if cfg!(target_os = "macos") {
+            KeyMods::LOGO
+        } else {
+            KeyMods::CTRL
+        };
+        if ctx.keyboard.is_mod_active(shortcut) {
+            if ctx.keyboard.is_key_just_pressed(KeyCode::C) {
+                ctx.keyboard.set_clipboard_text(&self.text)?;
+            } else if ctx.keyboard.is_key_just_pressed(KeyCode::V) {
+                // the clipboard may be empty or hold an image
+                match ctx.keyboard.clipboard_text() {
+                    Ok(text) => self.text.extend(text.chars().filter(|ch| !ch.is_control())),
+                    Err(e) => println!("Could not paste: {}", e),
+                }
+            }
+        }
+
+        let back = KeyCode::Back;
+        if ctx.keyboard.is_key_just_pressed(back) || ctx.keyboard.is_key_repeating(back) {
+            let _ = self.text.pop();
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(30, 30, 40));
+        canvas.draw(
+            graphics::Text::new("Type something:").set_scale(24.),
+            graphics::DrawParam::from([FIELD.x, FIELD.y - 30.]),
+        );
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest(FIELD.point())
+                .scale(FIELD.size())
+                .color(Color::from_rgb(60, 60, 75)),
+        );
+
+        let mut text = graphics::Text::new(self.text.as_str());
+        let _ = text
+            .add(TextFragment::new(self.preedit.as_str()).color(Color::YELLOW))
+            .add("_")
+            .set_scale(24.);
+        canvas.draw(
+            &text,
+            graphics::DrawParam::from([FIELD.x + 8., FIELD.y + 8.]),
+        );
+
+        canvas.finish(ctx)
+    }
+
+    fn ime_preedit_event(
+        &mut self,
+        _ctx: &mut Context,
+        text: &str,
+        _cursor: Option<(usize, usize)>,
+    ) -> GameResult {
+        self.preedit = text.to_string();
+        Ok(())
+    }
+
+    fn ime_commit_event(&mut self, _ctx: &mut Context, _text: &str) -> GameResult {
+        // the committed text arrives with `text_entered` too
+        self.preedit.clear();
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("text_input", "ggez");
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = MainState::new(&mut ctx);
+    event::run(ctx, event_loop, state)
+}
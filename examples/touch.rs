@@ -0,0 +1,64 @@
+//! Draws a circle under each finger touching the screen,
+//! growing with the force on platforms which report it.
+
+use ggez::event;
+use ggez::graphics::{self, Color};
+use ggez::input::touch::TouchPhase;
+use ggez::{Context, GameResult};
+
+struct MainState;
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(20, 20, 30));
+        let colors = [
+            Color::RED,
+            Color::GREEN,
+            Color::BLUE,
+            Color::YELLOW,
+            Color::CYAN,
+        ];
+        for touch in ctx.touch.touches() {
+            let radius = 40. + 40. * touch.force.unwrap_or(0.);
+            let circle = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::fill(),
+                touch.game_position(ctx),
+                radius,
+                1.,
+                colors[touch.id as usize % colors.len()],
+            )?;
+            canvas.draw(&circle, graphics::DrawParam::new());
+        }
+
+        let text = format!("{} touches", ctx.touch.touches().len());
+        canvas.draw(
+            graphics::Text::new(text).set_scale(32.),
+            graphics::DrawParam::from([10., 10.]),
+        );
+        canvas.finish(ctx)
+    }
+
+    fn touch_event(
+        &mut self,
+        _ctx: &mut Context,
+        phase: TouchPhase,
+        id: u64,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        // no mouse emulation here
+        println!("Touch {}: {:?} at {}, {}", id, phase, x, y);
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("touch", "ggez");
+    let (ctx, event_loop) = cb.build()?;
+    event::run(ctx, event_loop, MainState)
+}
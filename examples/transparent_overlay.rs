@@ -0,0 +1,77 @@
+//! A borderless, transparent window with a translucent panel drawn on it,
+//! like an overlay on top of whatever is behind it.
+
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::event::{self, MouseButton};
+use ggez::graphics::{self, Color, Rect};
+use ggez::{Context, GameResult};
+
+struct MainState {
+    transparent: bool,
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        // cleared to nothing, so only what's drawn shows, if the window is transparent
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::new(0., 0., 0., 0.));
+        let (width, height) = ctx.gfx.drawable_size();
+
+        let panel = graphics::Mesh::new_rounded_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Rect::new(20., 20., width - 40., height - 40.),
+            16.,
+            Color::new(0.1, 0.1, 0.15, 0.6),
+        )?;
+        canvas.draw(&panel, graphics::DrawParam::new());
+
+        let text = if self.transparent {
+            "An overlay. Drag to move, right click to close."
+        } else {
+            "Transparent windows aren't supported here, so this one is opaque."
+        };
+        canvas.draw(
+            graphics::Text::new(text).set_scale(20.),
+            graphics::DrawParam::from([40., 40.]),
+        );
+
+        canvas.finish(ctx)
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        match button {
+            MouseButton::Left => ctx.gfx.begin_window_drag()?,
+            MouseButton::Right => ctx.request_quit(),
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("transparent_overlay", "ggez")
+        .window_setup(WindowSetup::default().title("Transparent overlay"))
+        .window_mode(
+            WindowMode::default()
+                .dimensions(560., 200.)
+                .transparent(true)
+                .borderless(true)
+                .always_on_top(true),
+        );
+    let (ctx, event_loop) = cb.build()?;
+    let transparent = ctx.gfx.is_transparent();
+    if !transparent {
+        println!("Transparent windows are not supported here, the window is opaque");
+    }
+    event::run(ctx, event_loop, MainState { transparent })
+}
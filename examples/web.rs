@@ -0,0 +1,74 @@
+//! The super simple example, started the way a game in a browser is: the context is built with
+//! `ContextBuilder::build_async`, as the browser hands out the GPU asynchronously, and the
+//! window's canvas is put in the `<div id="game">` of `examples/web.html`.
+//!
+//! On the desktop it runs as it is with `cargo run --example web`. To run it in a browser, see
+//! "Web/wasm" in `docs/BuildingForEveryPlatform.md`.
+
+use ggez::{
+    event,
+    glam::*,
+    graphics::{self, Color},
+    Context, ContextBuilder, GameResult,
+};
+
+struct MainState {
+    pos_x: f32,
+    circle: graphics::Mesh,
+}
+
+impl MainState {
+    fn new(ctx: &mut Context) -> GameResult<MainState> {
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            vec2(0., 0.),
+            100.0,
+            2.0,
+            Color::WHITE,
+        )?;
+
+        Ok(MainState { pos_x: 0.0, circle })
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // by the time rather than the frame, as browsers draw at the monitor's refresh rate
+        let dt = ctx.time.delta().as_secs_f32();
+        self.pos_x = (self.pos_x + 60.0 * dt) % 800.0;
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas =
+            graphics::Canvas::from_frame(ctx, graphics::Color::from([0.1, 0.2, 0.3, 1.0]));
+        canvas.draw(&self.circle, Vec2::new(self.pos_x, 380.0));
+        canvas.finish(ctx)
+    }
+}
+
+async fn run() -> GameResult {
+    let (mut ctx, event_loop) = ContextBuilder::new("web", "ggez")
+        .web_element("game")
+        .build_async()
+        .await?;
+    let state = MainState::new(&mut ctx)?;
+    event::run(ctx, event_loop, state)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn main() -> GameResult {
+    pollster::block_on(run())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn main() {
+    // panics go to the browser's console rather than nowhere
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    wasm_bindgen_futures::spawn_local(async {
+        if let Err(e) = run().await {
+            panic!("{}", e);
+        }
+    });
+}
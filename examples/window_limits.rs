@@ -0,0 +1,75 @@
+//! A resizable window with min and max dimensions, which it can't be dragged past,
+//! and which keeps them after going fullscreen and back.
+//!
+//! Press the arrow keys to ask for sizes outside of them, which are clamped,
+//! and F to toggle fullscreen.
+
+use ggez::conf::{FullscreenType, WindowMode, WindowSetup};
+use ggez::event;
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::{Context, GameResult};
+
+const MIN: (f32, f32) = (320., 240.);
+const MAX: (f32, f32) = (1024., 768.);
+
+struct MainState {
+    fullscreen: bool,
+}
+
+impl event::EventHandler<ggez::GameError> for MainState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::from_rgb(30, 30, 40));
+        let (width, height) = ctx.gfx.drawable_size();
+        let text = format!(
+            "{}x{}, between {}x{} and {}x{}\n\nArrow keys: ask for a size outside of them\nF: toggle fullscreen",
+            width, height, MIN.0, MIN.1, MAX.0, MAX.1
+        );
+        canvas.draw(
+            graphics::Text::new(text).set_scale(20.),
+            graphics::DrawParam::from([16., 16.]),
+        );
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        if repeat {
+            return Ok(());
+        }
+        let (width, height) = ctx.gfx.drawable_size();
+        match input.keycode {
+            // clamped into the limits, with a warning logged
+            Some(KeyCode::Left) => ctx.gfx.set_drawable_size(MIN.0 / 2., height)?,
+            Some(KeyCode::Right) => ctx.gfx.set_drawable_size(MAX.0 * 2., height)?,
+            Some(KeyCode::Up) => ctx.gfx.set_drawable_size(width, MIN.1 / 2.)?,
+            Some(KeyCode::Down) => ctx.gfx.set_drawable_size(width, MAX.1 * 2.)?,
+            Some(KeyCode::F) => {
+                self.fullscreen = !self.fullscreen;
+                ctx.gfx.set_fullscreen(if self.fullscreen {
+                    FullscreenType::Desktop
+                } else {
+                    FullscreenType::Windowed
+                })?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+pub fn main() -> GameResult {
+    let cb = ggez::ContextBuilder::new("window_limits", "ggez")
+        .window_setup(WindowSetup::default().title("Window limits"))
+        .window_mode(
+            WindowMode::default()
+                .resizable(true)
+                .min_dimensions(MIN.0, MIN.1)
+                .max_dimensions(MAX.0, MAX.1),
+        );
+    let (ctx, event_loop) = cb.build()?;
+    event::run(ctx, event_loop, MainState { fullscreen: false })
+}
@@ -0,0 +1,339 @@
+//! What the mix of every sound is played through, rodio by default, see [`AudioBackend`].
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use rodio::dynamic_mixer::DynamicMixer;
+
+use super::AudioDeviceInfo;
+use crate::error::{GameError, GameResult};
+
+/// Plays the sound of an [`AudioContext`](super::AudioContext) on a device, given with
+/// [`ContextBuilder::audio_backend`](crate::ContextBuilder::audio_backend), e.g. to play
+/// through kira or a mixer of the game's own rather than rodio, or nowhere in tests.
+///
+/// Sources are decoded, resampled, and their volume, pitch, pan, filters, reverb and position
+/// applied by ggez, and mixed into one [`MixStream`], which is all a backend is given. That's
+/// what makes [`Source`](super::Source), [`SpatialSource`](super::SpatialSource) and the rest
+/// of the `audio` module behave the same whatever the backend, which only has to play the
+/// samples it's handed, in the format it asks for. It's [`RodioBackend`](super::RodioBackend)
+/// by default, and [`NullAudioBackend`] in a headless context.
+///
+/// Backends given to the builder are to be `Send`, as the builder is, which
+/// [`RodioBackend`](super::RodioBackend) isn't, as it's the default anyway.
+///
+/// # Stability
+///
+/// The trait is meant to be implemented outside of ggez, e.g. by a crate playing through kira.
+/// Methods may be added to it in minor versions, but only with a default implementation, so
+/// backends keep compiling; changing the required methods, or what [`MixStream`] is, waits for
+/// a breaking version.
+pub trait AudioBackend: fmt::Debug {
+    /// Returns the number of channels and the sample rate the mix is to be in, which is asked
+    /// once, before the first [`play()`](#tymethod.play). Usually that of the default device,
+    /// so the samples go to it as they are.
+    fn output_format(&self) -> (u16, u32);
+
+    /// Starts playing `mix` on the output device named `device`, or on the default one with
+    /// `None`, returning the name of the device it's playing on, if it's known.
+    ///
+    /// It's called once when the context is made, and again to move the sound to another
+    /// device. The mix given before ends on its own, after playing out what it mixed, once the
+    /// new one is playing, so a backend can drop it then, or keep it until it ends. It returns
+    /// an error if the device can't be played on, in which case the previous mix keeps going.
+    fn play(&mut self, device: Option<&str>, mix: MixStream) -> GameResult<Option<String>>;
+
+    /// Returns the devices which can play sound, or none if they can't be listed, which is the
+    /// default.
+    fn output_devices(&self) -> Vec<AudioDeviceInfo> {
+        Vec::new()
+    }
+
+    /// Returns the output devices if they changed since the last call, as devices are plugged
+    /// in and out or the default one changes, for the sound to move to another device. It's
+    /// called every frame, so it shouldn't list the devices itself when that's slow.
+    ///
+    /// Defaults to `None`, for devices which never change.
+    fn poll_devices(&mut self) -> Option<Vec<AudioDeviceInfo>> {
+        None
+    }
+
+    /// Returns the rodio stream the mix plays on, for
+    /// [`AudioContext::device`](super::AudioContext::device), if the backend plays through
+    /// rodio. Defaults to `None`.
+    fn stream_handle(&self) -> Option<&rodio::OutputStreamHandle> {
+        None
+    }
+}
+
+// how many samples are mixed at a time, for every channel
+pub(super) const CHUNK_FRAMES: usize = 256;
+
+/// The mix of every sound played, which an [`AudioBackend`] plays, of
+/// [`channels()`](#method.channels) interleaved samples at
+/// [`sample_rate()`](#method.sample_rate), in the format the backend asked for.
+///
+/// It's silent while nothing plays rather than ending, and ends once the backend was given
+/// another one, after the samples it already mixed.
+pub struct MixStream {
+    pub(super) mixer: Arc<Mutex<DynamicMixer<f32>>>,
+    // the mix playing, which the ones before it stop for
+    pub(super) routes: Arc<AtomicUsize>,
+    pub(super) route: usize,
+    pub(super) paused: Arc<AtomicBool>,
+    pub(super) channels: u16,
+    pub(super) sample_rate: u32,
+    pub(super) buffer: Vec<f32>,
+    pub(super) position: usize,
+}
+
+impl MixStream {
+    /// Returns the number of channels, whose samples are interleaved.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns the number of samples a second, for every channel.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl Iterator for MixStream {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.buffer.len() {
+            // the samples mixed for this device are played out before it's let go of
+            if self.routes.load(Ordering::Acquire) > self.route {
+                return None;
+            }
+            self.buffer.clear();
+            if self.paused.load(Ordering::Relaxed) {
+                // silence, without the sounds going on
+                self.buffer
+                    .resize(CHUNK_FRAMES * self.channels as usize, 0.0);
+            } else {
+                let mut mixer = self.mixer.lock().unwrap_or_else(PoisonError::into_inner);
+                // silence when nothing is playing, rather than ending
+                self.buffer.extend(
+                    (0..CHUNK_FRAMES * self.channels as usize).map(|_| mixer.next().unwrap_or(0.0)),
+                );
+            }
+            self.position = 0;
+        }
+        self.position += 1;
+        Some(self.buffer[self.position - 1])
+    }
+}
+
+impl rodio::Source for MixStream {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl fmt::Debug for MixStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MixStream")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Plays nowhere, dropping the mix it's given, which is what a headless context does, e.g. to
+/// test in CI, where there are no sound devices.
+///
+/// Everything else in the `audio` module works as it does with sound, apart from time not
+/// passing for the sources, as nothing reads their samples, so they never finish on their own.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullAudioBackend;
+
+impl AudioBackend for NullAudioBackend {
+    fn output_format(&self) -> (u16, u32) {
+        (2, 44100)
+    }
+
+    fn play(&mut self, device: Option<&str>, _mix: MixStream) -> GameResult<Option<String>> {
+        match device {
+            Some(name) => Err(GameError::AudioError(format!(
+                "There is no output device named {:?}",
+                name
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{AudioContext, RodioBackend, SoundData, SoundSource, Source, SpatialSource};
+    use crate::filesystem::Filesystem;
+    use rodio::dynamic_mixer;
+    use rodio::Source as _;
+
+    #[test]
+    fn routes() {
+        let (controller, mixer) = dynamic_mixer::mixer(2, 44100);
+        let mixer = Arc::new(Mutex::new(mixer));
+        let routes = Arc::new(AtomicUsize::new(0));
+        let paused = Arc::new(AtomicBool::new(false));
+        let route = |route| MixStream {
+            mixer: mixer.clone(),
+            routes: routes.clone(),
+            route,
+            paused: paused.clone(),
+            channels: 2,
+            sample_rate: 44100,
+            buffer: Vec::new(),
+            position: 0,
+        };
+        let mut first = route(0);
+        // silence while nothing plays
+        assert_eq!(first.next(), Some(0.0));
+        controller.add(rodio::source::SineWave::new(441.0).amplify(0.5));
+        let played: Vec<f32> = first.by_ref().take(4 * CHUNK_FRAMES).collect();
+        assert!(played.iter().any(|&sample| sample > 0.4));
+        // the first route plays out what it mixed, and the next one goes on from there
+        let _ = routes.fetch_add(1, Ordering::AcqRel);
+        let mut second = route(1);
+        assert_eq!(first.by_ref().count(), 2 * CHUNK_FRAMES - 1);
+        assert!(second
+            .by_ref()
+            .take(4 * CHUNK_FRAMES)
+            .any(|sample| sample > 0.4));
+
+        // nothing is mixed while paused, so the sound goes on from where it was after
+        paused.store(true, Ordering::Relaxed);
+        assert!(second
+            .by_ref()
+            // what was mixed before pausing is played out first
+            .skip(2 * CHUNK_FRAMES)
+            .take(4 * CHUNK_FRAMES)
+            .all(|sample| sample == 0.0));
+        paused.store(false, Ordering::Relaxed);
+        assert!(second
+            .by_ref()
+            .take(4 * CHUNK_FRAMES)
+            .any(|sample| sample > 0.4));
+    }
+
+    fn try_audio(backend: Box<dyn AudioBackend>) -> GameResult<AudioContext> {
+        let mut fs =
+            Filesystem::new("audio_backend_test", "ggez", "resources", "resources.zip").unwrap();
+        fs.sandbox_user_dirs();
+        AudioContext::with_backend(&fs, backend)
+    }
+
+    fn audio(backend: Box<dyn AudioBackend>) -> AudioContext {
+        try_audio(backend).unwrap()
+    }
+
+    // What every backend is to do the same, as what the sources do is up to ggez.
+    fn conformance(mut audio: AudioContext) {
+        let data = SoundData::from_bytes(include_bytes!("../../resources/pew.wav"));
+        let mut source = Source::from_data(&audio, data.clone()).unwrap();
+        source.set_repeat(true);
+        assert!(source.stopped());
+        assert!(!source.playing());
+
+        source.play(&audio).unwrap();
+        assert!(source.playing());
+        source.pause();
+        assert!(source.paused());
+        assert!(!source.playing());
+        source.resume();
+        assert!(source.playing());
+
+        source.set_volume(0.5);
+        assert_eq!(source.volume(), 0.5);
+        source.set_pan(-0.5);
+        assert_eq!(source.pan(), -0.5);
+        source.set_pitch(1.5);
+        assert!(source.playing());
+        audio.set_master_volume(0.25);
+        assert_eq!(audio.master_volume(), 0.25);
+
+        source.stop(&audio).unwrap();
+        assert!(source.stopped());
+        assert_eq!(source.elapsed(), Duration::ZERO);
+
+        let mut spatial = SpatialSource::from_data(&audio, data).unwrap();
+        spatial.set_repeat(true);
+        spatial.set_position([1.0, 2.0, 0.0]);
+        spatial.set_ears([-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        spatial.play(&audio).unwrap();
+        assert!(spatial.playing());
+        spatial.stop(&audio).unwrap();
+        assert!(spatial.stopped());
+
+        audio.set_output_paused(true);
+        assert!(audio.output_paused());
+        audio.set_output_paused(false);
+    }
+
+    #[test]
+    fn null_backend() {
+        conformance(audio(Box::new(NullAudioBackend)));
+        assert!(audio(Box::new(NullAudioBackend))
+            .set_output_device(Some("speakers"))
+            .is_err());
+    }
+
+    #[test]
+    fn rodio_backend() {
+        // without a sound device, e.g. in CI, the output can't be started
+        let audio = match RodioBackend::new().and_then(|backend| try_audio(Box::new(backend))) {
+            Ok(audio) => audio,
+            Err(_) => return,
+        };
+        conformance(audio);
+    }
+
+    // Keeps the mix it's given, to read the samples played.
+    #[derive(Debug, Default)]
+    struct Captured(Arc<Mutex<Option<MixStream>>>);
+
+    impl AudioBackend for Captured {
+        fn output_format(&self) -> (u16, u32) {
+            (1, 8000)
+        }
+
+        fn play(&mut self, _device: Option<&str>, mix: MixStream) -> GameResult<Option<String>> {
+            *self.0.lock().unwrap() = Some(mix);
+            Ok(Some(String::from("captured")))
+        }
+    }
+
+    #[test]
+    fn custom_backend() {
+        let mix = Arc::default();
+        let audio = audio(Box::new(Captured(Arc::clone(&mix))));
+        assert_eq!(audio.output_device(), Some("captured"));
+        let samples: Vec<f32> = (0..800).map(|i| (i as f32 * 0.1).sin() * 0.5).collect();
+        let mut source =
+            Source::from_data(&audio, SoundData::from_samples(8000, 1, &samples)).unwrap();
+        source.play(&audio).unwrap();
+
+        let mut mix = mix.lock().unwrap();
+        let mix = mix.as_mut().unwrap();
+        assert_eq!((mix.channels(), mix.sample_rate()), (1, 8000));
+        assert!(mix.take(1600).any(|sample| sample > 0.25));
+    }
+}
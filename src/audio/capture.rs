@@ -0,0 +1,217 @@
+//! Recording from a microphone, or another input device.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use rodio::cpal::traits::StreamTrait;
+use rodio::cpal::{self, Sample};
+use rodio::DeviceTrait;
+
+use super::device::find_input_device;
+use crate::error::{GameError, GameResult};
+
+/// What to record, see [`AudioContext::start_capture`](super::AudioContext::start_capture).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureConfig {
+    /// The sample rate to record at, which the device has to support.
+    pub sample_rate: u32,
+    /// How many channels to record, usually 1 for a microphone.
+    pub channels: u16,
+    /// The name of the device to record from, out of
+    /// [`AudioContext::input_devices`](super::AudioContext::input_devices), or `None` for the
+    /// default one.
+    pub device: Option<String>,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        CaptureConfig {
+            sample_rate: 44100,
+            channels: 1,
+            device: None,
+        }
+    }
+}
+
+/// The samples recorded and not read yet, written by the audio thread and read by the game
+/// without locking either.
+struct Ring {
+    samples: Box<[AtomicU32]>,
+    // the counts of samples ever written and read
+    written: AtomicUsize,
+    read: AtomicUsize,
+}
+
+impl Ring {
+    fn new(len: usize) -> Self {
+        Ring {
+            samples: (0..len.max(1)).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+        }
+    }
+
+    /// Writes what fits of `samples`, dropping the rest until the game reads some.
+    fn write(&self, samples: impl Iterator<Item = f32>) {
+        let read = self.read.load(Ordering::Acquire);
+        let mut written = self.written.load(Ordering::Relaxed);
+        for sample in samples.take(self.samples.len() - (written - read)) {
+            self.samples[written % self.samples.len()].store(sample.to_bits(), Ordering::Relaxed);
+            written += 1;
+        }
+        self.written.store(written, Ordering::Release);
+    }
+
+    fn read_into(&self, out: &mut Vec<f32>) -> usize {
+        let read = self.read.load(Ordering::Relaxed);
+        let written = self.written.load(Ordering::Acquire);
+        out.extend((read..written).map(|index| {
+            f32::from_bits(self.samples[index % self.samples.len()].load(Ordering::Relaxed))
+        }));
+        self.read.store(written, Ordering::Release);
+        written - read
+    }
+}
+
+/// Recording from an input device, which stops when dropped, see
+/// [`AudioContext::start_capture`](super::AudioContext::start_capture).
+pub struct CaptureStream {
+    _stream: cpal::Stream,
+    ring: Arc<Ring>,
+    // what went wrong on the audio thread
+    error: Arc<Mutex<Option<String>>>,
+    config: CaptureConfig,
+}
+
+impl CaptureStream {
+    /// Appends the samples recorded since the last call to `out`, with the channels interleaved,
+    /// returning how many there were.
+    ///
+    /// About a second is kept between calls, and what's recorded after that is dropped until
+    /// this is called again.
+    ///
+    /// Returns an error if recording stopped, e.g. because the device was unplugged, in which
+    /// case a new capture can be started.
+    pub fn read(&mut self, out: &mut Vec<f32>) -> GameResult<usize> {
+        let read = self.ring.read_into(out);
+        if read == 0 {
+            if let Some(error) = self
+                .error
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take()
+            {
+                return Err(GameError::AudioError(format!(
+                    "Recording stopped: {}",
+                    error
+                )));
+            }
+        }
+        Ok(read)
+    }
+
+    /// Returns what's being recorded, with the name of the device.
+    pub fn config(&self) -> &CaptureConfig {
+        &self.config
+    }
+}
+
+impl fmt::Debug for CaptureStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CaptureStream")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+fn capture_error(message: &str, error: impl fmt::Display) -> GameError {
+    GameError::AudioError(format!("{}: {}", message, error))
+}
+
+/// Starts recording as `config` says.
+pub(crate) fn start(mut config: CaptureConfig) -> GameResult<CaptureStream> {
+    let device = find_input_device(config.device.as_deref()).ok_or_else(|| {
+        GameError::AudioError(match &config.device {
+            Some(name) => format!("There is no input device named {:?}", name),
+            None => String::from("There is no input device to record from"),
+        })
+    })?;
+    config.device = device.name().ok();
+    let sample_rate = cpal::SampleRate(config.sample_rate);
+    let supported = device
+        .supported_input_configs()
+        .map_err(|error| capture_error("Could not get what the input device supports", error))?
+        .find(|supported| {
+            supported.channels() == config.channels
+                && supported.min_sample_rate() <= sample_rate
+                && sample_rate <= supported.max_sample_rate()
+        })
+        .ok_or_else(|| {
+            GameError::AudioError(format!(
+                "The input device can't record {} channels at {}Hz",
+                config.channels, config.sample_rate
+            ))
+        })?
+        .with_sample_rate(sample_rate);
+
+    let ring = Arc::new(Ring::new(
+        config.sample_rate as usize * config.channels as usize,
+    ));
+    let error = Arc::new(Mutex::new(None));
+    let stream_config = supported.config();
+    let on_error = {
+        let error = error.clone();
+        move |stream_error: cpal::StreamError| {
+            *error.lock().unwrap_or_else(PoisonError::into_inner) = Some(stream_error.to_string());
+        }
+    };
+    let stream = {
+        let ring = ring.clone();
+        match supported.sample_format() {
+            cpal::SampleFormat::F32 => device.build_input_stream(
+                &stream_config,
+                move |data: &[f32], _: &_| ring.write(data.iter().copied()),
+                on_error,
+            ),
+            cpal::SampleFormat::I16 => device.build_input_stream(
+                &stream_config,
+                move |data: &[i16], _: &_| ring.write(data.iter().map(Sample::to_f32)),
+                on_error,
+            ),
+            cpal::SampleFormat::U16 => device.build_input_stream(
+                &stream_config,
+                move |data: &[u16], _: &_| ring.write(data.iter().map(Sample::to_f32)),
+                on_error,
+            ),
+        }
+    }
+    .map_err(|error| capture_error("Could not start recording", error))?;
+    stream
+        .play()
+        .map_err(|error| capture_error("Could not start recording", error))?;
+    Ok(CaptureStream {
+        _stream: stream,
+        ring,
+        error,
+        config,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring() {
+        let ring = Ring::new(4);
+        let mut out = Vec::new();
+        ring.write([1.0, 2.0, 3.0].into_iter());
+        assert_eq!(ring.read_into(&mut out), 3);
+        // dropping what doesn't fit until read
+        ring.write([4.0, 5.0, 6.0, 7.0, 8.0].into_iter());
+        assert_eq!(ring.read_into(&mut out), 4);
+        assert_eq!(ring.read_into(&mut out), 0);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    }
+}
@@ -0,0 +1,412 @@
+//! The devices sound is played on or recorded from, through rodio or another backend, and
+//! moving the sound from one output device to another.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use rodio::cpal::traits::HostTrait;
+use rodio::cpal::{self, Device};
+use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+use rodio::DeviceTrait;
+
+use super::backend::{AudioBackend, MixStream};
+use crate::error::{GameError, GameResult};
+
+/// A device sound can be played on or recorded from, see
+/// [`AudioContext::output_devices`](super::AudioContext::output_devices).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AudioDeviceInfo {
+    /// The name of the device, as the system shows it, which identifies it too.
+    pub name: String,
+    /// Whether this is the device the system plays on or records from by default.
+    pub is_default: bool,
+}
+
+fn device_infos(
+    devices: Option<impl Iterator<Item = Device>>,
+    default: Option<Device>,
+) -> Vec<AudioDeviceInfo> {
+    let default = default.and_then(|device| device.name().ok());
+    let mut infos: Vec<AudioDeviceInfo> = Vec::new();
+    for name in devices
+        .into_iter()
+        .flatten()
+        .filter_map(|device| device.name().ok())
+    {
+        // some hosts list a device once for every format it has
+        if !infos.iter().any(|info| info.name == name) {
+            infos.push(AudioDeviceInfo {
+                is_default: default.as_ref() == Some(&name),
+                name,
+            });
+        }
+    }
+    infos
+}
+
+/// Returns the devices which can play sound, or none if they can't be listed.
+pub(crate) fn output_devices() -> Vec<AudioDeviceInfo> {
+    let host = cpal::default_host();
+    device_infos(host.output_devices().ok(), host.default_output_device())
+}
+
+/// Returns the devices which can be recorded from, or none if they can't be listed.
+#[cfg(feature = "audio-capture")]
+pub(crate) fn input_devices() -> Vec<AudioDeviceInfo> {
+    let host = cpal::default_host();
+    device_infos(host.input_devices().ok(), host.default_input_device())
+}
+
+/// Finds the output device called `name`, or the default one.
+pub(crate) fn find_output_device(name: Option<&str>) -> Option<Device> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .output_devices()
+            .ok()?
+            .find(|device| device.name().ok().as_deref() == Some(name)),
+        None => host.default_output_device(),
+    }
+}
+
+/// Finds the input device called `name`, or the default one.
+#[cfg(feature = "audio-capture")]
+pub(crate) fn find_input_device(name: Option<&str>) -> Option<Device> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .input_devices()
+            .ok()?
+            .find(|device| device.name().ok().as_deref() == Some(name)),
+        None => host.default_input_device(),
+    }
+}
+
+/// The output devices as of the last time they were listed.
+#[derive(Default)]
+struct Devices {
+    default: Option<String>,
+    names: Vec<String>,
+}
+
+/// Lists the output devices every so often on a thread of its own, as it can take a while.
+#[derive(Default)]
+struct Watcher {
+    devices: Mutex<Devices>,
+    // how many times the devices changed
+    changes: AtomicUsize,
+    stop: AtomicBool,
+}
+
+impl Watcher {
+    fn start() -> Arc<Self> {
+        let watcher = Arc::new(Watcher::default());
+        watcher.list();
+        let watching = watcher.clone();
+        let spawned = thread::Builder::new()
+            .name(String::from("ggez audio devices"))
+            .spawn(move || {
+                while !watching.stop.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(500));
+                    watching.list();
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("Could not watch for audio devices being plugged in: {}", e);
+        }
+        watcher
+    }
+
+    fn list(&self) {
+        let host = cpal::default_host();
+        let default = host
+            .default_output_device()
+            .and_then(|device| device.name().ok());
+        let names: Vec<String> = match host.output_devices() {
+            Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+            Err(_) => return,
+        };
+        let mut devices = self.devices.lock().unwrap_or_else(PoisonError::into_inner);
+        if devices.default != default || devices.names != names {
+            *devices = Devices { default, names };
+            let _ = self.changes.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+// a stream playing on a device, which stops when it's dropped, and the handle to play on it
+type Stream = (rodio::OutputStream, rodio::OutputStreamHandle);
+
+fn open(device: Option<&str>) -> GameResult<(Stream, Option<String>)> {
+    let found = find_output_device(device).ok_or_else(|| {
+        GameError::AudioError(match device {
+            Some(name) => format!("There is no output device named {:?}", name),
+            None => String::from("There is no output device to play sound on"),
+        })
+    })?;
+    let name = found.name().ok();
+    let (stream, stream_handle) = rodio::OutputStream::try_from_device(&found).map_err(|e| {
+        GameError::AudioError(format!(
+            "Could not play sound on {:?}: {}",
+            name.as_deref().unwrap_or_default(),
+            e
+        ))
+    })?;
+    Ok(((stream, stream_handle), name))
+}
+
+/// Plays sound through rodio, on the system's output devices, and moves it to the default one
+/// as that changes, which is the [`AudioBackend`] of a context unless another one is given.
+pub struct RodioBackend {
+    // `None` until the mix is played
+    stream: Option<Stream>,
+    format: (u16, u32),
+    watcher: Arc<Watcher>,
+    changes: usize,
+}
+
+impl RodioBackend {
+    /// Starts listing the output devices, returning an error if there is no default one to
+    /// play on.
+    pub fn new() -> GameResult<Self> {
+        let device = find_output_device(None).ok_or_else(|| {
+            GameError::AudioError(String::from(
+                "Could not initialize sound system using default output device (for some reason)",
+            ))
+        })?;
+        // the format rodio picks for the output, which sources are converted to for the reverb,
+        // and mixed in to move from one output device to another
+        let format = device
+            .default_output_config()
+            .map(|config| (config.channels(), config.sample_rate().0))
+            .unwrap_or((2, 44100));
+        let watcher = Watcher::start();
+        let changes = watcher.changes.load(Ordering::Acquire);
+        Ok(RodioBackend {
+            stream: None,
+            format,
+            watcher,
+            changes,
+        })
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn output_format(&self) -> (u16, u32) {
+        self.format
+    }
+
+    fn play(&mut self, device: Option<&str>, mix: MixStream) -> GameResult<Option<String>> {
+        let (stream, name) = open(device)?;
+        stream.1.play_raw(mix)?;
+        // the mix before stops once this one plays, and its stream with it
+        self.stream = Some(stream);
+        Ok(name)
+    }
+
+    fn output_devices(&self) -> Vec<AudioDeviceInfo> {
+        output_devices()
+    }
+
+    fn poll_devices(&mut self) -> Option<Vec<AudioDeviceInfo>> {
+        let changes = self.watcher.changes.load(Ordering::Acquire);
+        if changes == self.changes {
+            return None;
+        }
+        self.changes = changes;
+        let devices = self
+            .watcher
+            .devices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Some(
+            devices
+                .names
+                .iter()
+                .map(|name| AudioDeviceInfo {
+                    name: name.clone(),
+                    is_default: devices.default.as_ref() == Some(name),
+                })
+                .collect(),
+        )
+    }
+
+    fn stream_handle(&self) -> Option<&rodio::OutputStreamHandle> {
+        self.stream.as_ref().map(|(_, stream_handle)| stream_handle)
+    }
+}
+
+impl Drop for RodioBackend {
+    fn drop(&mut self) {
+        self.watcher.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for RodioBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RodioBackend")
+            .field("format", &self.format)
+            .field("playing", &self.stream.is_some())
+            .finish()
+    }
+}
+
+/// Where every sound plays into, which the backend plays on one output device, and moves to
+/// another when that's chosen or it's unplugged.
+pub(crate) struct Output {
+    controller: Arc<DynamicMixerController<f32>>,
+    mixer: Arc<Mutex<DynamicMixer<f32>>>,
+    channels: u16,
+    sample_rate: u32,
+    // the mix playing, which the ones before it stop for
+    routes: Arc<AtomicUsize>,
+    paused: Arc<AtomicBool>,
+    backend: Box<dyn AudioBackend>,
+    // whether the backend was given the mix, which isn't the case in a headless context, or
+    // where the audio failed to start
+    started: bool,
+    // the name of the device playing, and the one chosen, if not the default
+    device: Option<String>,
+    chosen: Option<String>,
+}
+
+impl Output {
+    /// Mixes in the format `backend` asks for, which plays nowhere until it's
+    /// [started](Output::start).
+    pub fn new(backend: Box<dyn AudioBackend>) -> Self {
+        let (channels, sample_rate) = backend.output_format();
+        let (controller, mixer) = dynamic_mixer::mixer(channels, sample_rate);
+        Output {
+            controller,
+            mixer: Arc::new(Mutex::new(mixer)),
+            channels,
+            sample_rate,
+            routes: Arc::default(),
+            paused: Arc::default(),
+            backend,
+            started: false,
+            device: None,
+            chosen: None,
+        }
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Has the backend play the mix on `device`, or the default one, from now on.
+    pub fn start(&mut self, device: Option<&str>) -> GameResult {
+        let route = self.routes.load(Ordering::Acquire) + 1;
+        self.device = self.backend.play(
+            device,
+            MixStream {
+                mixer: self.mixer.clone(),
+                routes: self.routes.clone(),
+                route,
+                paused: self.paused.clone(),
+                channels: self.channels,
+                sample_rate: self.sample_rate,
+                buffer: Vec::new(),
+                position: 0,
+            },
+        )?;
+        // stopping the mix on the old device
+        self.routes.store(route, Ordering::Release);
+        self.started = true;
+        Ok(())
+    }
+
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// Stops mixing the sounds, keeping them where they are, until it's unpaused.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn stream_handle(&self) -> Option<&rodio::OutputStreamHandle> {
+        self.backend.stream_handle()
+    }
+
+    pub fn output_devices(&self) -> Vec<AudioDeviceInfo> {
+        self.backend.output_devices()
+    }
+
+    /// Plays `source`, on whichever device the output is on.
+    pub fn play<S>(&self, source: S)
+    where
+        S: rodio::Source<Item = f32> + Send + 'static,
+    {
+        self.controller.add(source);
+    }
+
+    /// Returns a sink playing on the output.
+    pub fn sink(&self) -> rodio::Sink {
+        let (sink, queue) = rodio::Sink::new_idle();
+        self.play(queue);
+        sink
+    }
+
+    pub fn device(&self) -> Option<&str> {
+        self.device.as_deref()
+    }
+
+    pub fn chosen(&self) -> Option<&str> {
+        self.chosen.as_deref()
+    }
+
+    /// Moves the sound to `device`, which is kept on if it's unplugged and plugged in again,
+    /// or to the default one with `None`, following it as it changes.
+    pub fn choose(&mut self, device: Option<&str>) -> GameResult {
+        self.start(device)?;
+        self.chosen = device.map(str::to_owned);
+        Ok(())
+    }
+
+    /// Moves the sound to the default device if it's playing on that and it changed, or if the
+    /// device it's playing on is gone, returning the name of the one it moved to.
+    pub fn follow(&mut self) -> Option<String> {
+        if !self.started {
+            return None;
+        }
+        let devices = self.backend.poll_devices()?;
+        let target = match &self.chosen {
+            Some(chosen) if devices.iter().any(|device| &device.name == chosen) => {
+                Some(chosen.clone())
+            }
+            _ => devices
+                .into_iter()
+                .find(|device| device.is_default)
+                .map(|device| device.name),
+        };
+        if target.is_none() || target == self.device {
+            return None;
+        }
+        match self.start(target.as_deref()) {
+            Ok(()) => self.device.clone(),
+            Err(e) => {
+                // tried again the next time the devices change
+                warn!("Could not move the sound to another device: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Output")
+            .field("backend", &self.backend)
+            .field("device", &self.device)
+            .field("chosen", &self.chosen)
+            .finish()
+    }
+}
@@ -0,0 +1,551 @@
+//! Effects applied to the samples of a sound on the audio thread.
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI, SQRT_2};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::mixer::BusOutput;
+use super::playback::Controls;
+
+// The window of the pitch shifter, longer sounds smoother but lags more.
+const PITCH_WINDOW: Duration = Duration::from_millis(50);
+// How long panning from one side to the other takes, to not click.
+const PAN_TIME: Duration = Duration::from_millis(10);
+// About how long filters take to follow changes of their cutoffs, to not click.
+const FILTER_TIME: Duration = Duration::from_millis(20);
+// How many frames filters keep their coefficients for while their cutoffs move.
+const FILTER_UPDATE_FRAMES: u32 = 16;
+
+/// Shifts the pitch of a sound without changing its tempo, see
+/// [`SoundSource::set_pitch_semitones`](super::SoundSource::set_pitch_semitones).
+///
+/// This is a delay line read at another speed by two taps half a window apart, which jump back
+/// when they reach the end while the other one is loudest. It's cheap, but warbles and smears
+/// transients a bit, and lags by up to a window.
+pub(crate) struct PitchShift<S> {
+    input: S,
+    controls: Arc<Controls>,
+    channels: usize,
+    // the window in frames, and the frames written to it interleaved, once shifting
+    window: usize,
+    buffer: Vec<f32>,
+    write: usize,
+    // how many frames the first tap is behind the one written
+    delay: f32,
+    frame: Vec<f32>,
+    // the channel of the next sample, and whether the frame it's in is shifted
+    channel: usize,
+    shifting: bool,
+}
+
+impl<S> PitchShift<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub fn new(input: S, controls: Arc<Controls>) -> Self {
+        let channels = input.channels().max(1) as usize;
+        let window = (PITCH_WINDOW.as_secs_f32() * input.sample_rate() as f32).max(2.0) as usize;
+        PitchShift {
+            input,
+            controls,
+            channels,
+            window,
+            buffer: Vec::new(),
+            write: 0,
+            delay: 0.0,
+            frame: vec![0.0; channels],
+            channel: 0,
+            shifting: false,
+        }
+    }
+
+    // Shifts the next frame, returning whether there was one.
+    fn shift_frame(&mut self, ratio: f32) -> bool {
+        if self.buffer.is_empty() {
+            self.buffer = vec![0.0; self.window * self.channels];
+        }
+        for channel in 0..self.channels {
+            match self.input.next() {
+                Some(sample) => self.buffer[self.write * self.channels + channel] = sample,
+                None => return false,
+            }
+        }
+        let window = self.window as f32;
+        let second_delay = (self.delay + window / 2.0) % window;
+        let weight = (PI * self.delay / window).sin().powi(2);
+        for channel in 0..self.channels {
+            self.frame[channel] = self.tap(self.delay, channel) * weight
+                + self.tap(second_delay, channel) * (1.0 - weight);
+        }
+        self.delay = (self.delay + 1.0 - ratio).rem_euclid(window);
+        self.write = (self.write + 1) % self.window;
+        true
+    }
+
+    // Reads the delay line `delay` frames behind the last frame written.
+    fn tap(&self, delay: f32, channel: usize) -> f32 {
+        let position = (self.write as f32 - delay).rem_euclid(self.window as f32);
+        let before = position.floor() as usize % self.window;
+        let after = (before + 1) % self.window;
+        let t = position.fract();
+        let sample = |frame: usize| self.buffer[frame * self.channels + channel];
+        sample(before) * (1.0 - t) + sample(after) * t
+    }
+}
+
+impl<S> Iterator for PitchShift<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            let ratio = self.controls.pitch_ratio();
+            self.shifting = ratio != 1.0;
+            if self.shifting && !self.shift_frame(ratio) {
+                return None;
+            }
+        }
+        let sample = if self.shifting {
+            self.frame[self.channel]
+        } else {
+            self.input.next()?
+        };
+        self.channel = (self.channel + 1) % self.channels;
+        Some(sample)
+    }
+}
+
+impl<S> rodio::Source for PitchShift<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Pans a sound between the left and right channels, see
+/// [`SoundSource::set_pan`](super::SoundSource::set_pan).
+///
+/// Mono sounds become stereo, and only the first two channels of others are changed.
+pub(crate) struct Pan<S> {
+    input: S,
+    controls: Arc<Controls>,
+    mono: bool,
+    channels: u16,
+    // the pan of the current frame, moving up to `step` a frame towards the one of the controls
+    pan: f32,
+    step: f32,
+    gains: [f32; 2],
+    // the channel of the next sample, and the sample of a mono sound
+    channel: u16,
+    sample: f32,
+}
+
+impl<S> Pan<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub fn new(input: S, controls: Arc<Controls>) -> Self {
+        let mono = input.channels() <= 1;
+        let channels = if mono { 2 } else { input.channels() };
+        let step = 2.0 / (PAN_TIME.as_secs_f32() * input.sample_rate() as f32).max(1.0);
+        let pan = controls.pan();
+        let mut panned = Pan {
+            input,
+            controls,
+            mono,
+            channels,
+            pan,
+            step,
+            gains: [1.0; 2],
+            channel: 0,
+            sample: 0.0,
+        };
+        panned.update_gains();
+        panned
+    }
+
+    fn update_gains(&mut self) {
+        let pan = self.pan;
+        self.gains = if pan == 0.0 {
+            [1.0; 2]
+        } else if self.mono {
+            // constant power, scaled to leave the center as loud as before
+            let angle = (pan + 1.0) * FRAC_PI_4;
+            [angle.cos() * SQRT_2, angle.sin() * SQRT_2]
+        } else {
+            // balance, turning the other channel down
+            let other = (pan.abs() * FRAC_PI_2).cos();
+            if pan < 0.0 {
+                [1.0, other]
+            } else {
+                [other, 1.0]
+            }
+        };
+    }
+}
+
+impl<S> Iterator for Pan<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            let difference = self.controls.pan() - self.pan;
+            if difference != 0.0 {
+                self.pan += difference.clamp(-self.step, self.step);
+                self.update_gains();
+            }
+            if self.mono {
+                self.sample = self.input.next()?;
+            }
+        }
+        let sample = if self.mono {
+            self.sample
+        } else {
+            self.input.next()?
+        };
+        let gain = self
+            .gains
+            .get(self.channel as usize)
+            .copied()
+            .unwrap_or(1.0);
+        self.channel = (self.channel + 1) % self.channels;
+        Some(sample * gain)
+    }
+}
+
+impl<S> rodio::Source for Pan<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FilterKind {
+    LowPass,
+    HighPass,
+}
+
+/// A Butterworth biquad filter, with a state for each channel, moving smoothly to its cutoff.
+struct Biquad {
+    kind: FilterKind,
+    // whether the filter runs, and the logarithm of its cutoff for this frame
+    on: bool,
+    cutoff: f32,
+    coefficients: [f32; 5],
+    // the last two inputs and outputs of each channel
+    states: Vec<[f32; 4]>,
+}
+
+impl Biquad {
+    fn new(kind: FilterKind, channels: usize) -> Self {
+        Biquad {
+            kind,
+            on: false,
+            cutoff: 0.0,
+            coefficients: [1.0, 0.0, 0.0, 0.0, 0.0],
+            states: vec![[0.0; 4]; channels],
+        }
+    }
+
+    // The cutoff at which the filter hardly changes the sound.
+    fn open(&self, sample_rate: f32) -> f32 {
+        match self.kind {
+            FilterKind::LowPass => sample_rate * 0.45,
+            FilterKind::HighPass => 10.0,
+        }
+    }
+
+    // Moves the cutoff towards `target`, or back to open to turn off.
+    fn update(&mut self, target: Option<f32>, sample_rate: f32, smoothing: f32) {
+        let open = self.open(sample_rate).ln();
+        if !self.on {
+            if target.is_none() {
+                return;
+            }
+            self.on = true;
+            self.cutoff = open;
+            self.states.iter_mut().for_each(|state| *state = [0.0; 4]);
+        }
+        let goal = target.map_or(open, |cutoff| cutoff.clamp(10.0, sample_rate * 0.45).ln());
+        self.cutoff += (goal - self.cutoff) * smoothing;
+        if target.is_none() && (self.cutoff - open).abs() < 0.01 {
+            self.on = false;
+        } else {
+            self.recompute(sample_rate);
+        }
+    }
+
+    // The coefficients from the audio EQ cookbook, divided by a0.
+    fn recompute(&mut self, sample_rate: f32) {
+        let w0 = 2.0 * PI * self.cutoff.exp() / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / SQRT_2;
+        let a0 = 1.0 + alpha;
+        let (b0, b1) = match self.kind {
+            FilterKind::LowPass => ((1.0 - cos) / 2.0, 1.0 - cos),
+            FilterKind::HighPass => ((1.0 + cos) / 2.0, -(1.0 + cos)),
+        };
+        self.coefficients = [
+            b0 / a0,
+            b1 / a0,
+            b0 / a0,
+            -2.0 * cos / a0,
+            (1.0 - alpha) / a0,
+        ];
+    }
+
+    fn process(&mut self, channel: usize, x: f32) -> f32 {
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        let state = &mut self.states[channel];
+        let [x1, x2, y1, y2] = *state;
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        *state = [x, x1, y, y1];
+        y
+    }
+}
+
+/// Low-pass and high-pass filters of a sound and the bus it plays into, see
+/// [`SoundSource::set_lowpass`](super::SoundSource::set_lowpass).
+///
+/// Without any filters on, the samples pass straight through.
+pub(crate) struct Filter<S> {
+    input: S,
+    controls: Arc<Controls>,
+    bus_output: Arc<BusOutput>,
+    bus_seen: u32,
+    channels: usize,
+    sample_rate: f32,
+    smoothing: f32,
+    lowpass: Biquad,
+    highpass: Biquad,
+    // the channel of the next sample, and the frames until the coefficients are updated
+    channel: usize,
+    frames: u32,
+}
+
+impl<S> Filter<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub fn new(input: S, controls: Arc<Controls>) -> Self {
+        let channels = input.channels().max(1) as usize;
+        let sample_rate = input.sample_rate().max(1) as f32;
+        let update_time = FILTER_UPDATE_FRAMES as f32 / sample_rate;
+        let bus_seen = controls.bus_output().unseen();
+        Filter {
+            input,
+            controls,
+            bus_output: Arc::default(),
+            bus_seen,
+            channels,
+            sample_rate,
+            smoothing: 1.0 - (-update_time / FILTER_TIME.as_secs_f32()).exp(),
+            lowpass: Biquad::new(FilterKind::LowPass, channels),
+            highpass: Biquad::new(FilterKind::HighPass, channels),
+            channel: 0,
+            frames: 0,
+        }
+    }
+
+    fn update(&mut self) {
+        if let Some(output) = self.controls.bus_output().changed(&mut self.bus_seen) {
+            self.bus_output = output;
+        }
+        // the lowest low-pass and highest high-pass of the source and the bus
+        let lowpass = match (self.controls.lowpass(), self.bus_output.lowpass()) {
+            (Some(source), Some(bus)) => Some(source.min(bus)),
+            (source, bus) => source.or(bus),
+        };
+        let highpass = match (self.controls.highpass(), self.bus_output.highpass()) {
+            (Some(source), Some(bus)) => Some(source.max(bus)),
+            (source, bus) => source.or(bus),
+        };
+        // starting right away rather than with the next update
+        let starting =
+            !self.lowpass.on && lowpass.is_some() || !self.highpass.on && highpass.is_some();
+        if self.frames == 0 || starting {
+            self.lowpass
+                .update(lowpass, self.sample_rate, self.smoothing);
+            self.highpass
+                .update(highpass, self.sample_rate, self.smoothing);
+            self.frames = 0;
+        }
+        self.frames = (self.frames + 1) % FILTER_UPDATE_FRAMES;
+    }
+}
+
+impl<S> Iterator for Filter<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            self.update();
+        }
+        let mut sample = self.input.next()?;
+        if self.lowpass.on {
+            sample = self.lowpass.process(self.channel, sample);
+        }
+        if self.highpass.on {
+            sample = self.highpass.process(self.channel, sample);
+        }
+        self.channel = (self.channel + 1) % self.channels;
+        Some(sample)
+    }
+}
+
+impl<S> rodio::Source for Filter<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn sine(frequency: f32, sample_rate: u32, seconds: f32) -> SamplesBuffer<f32> {
+        let samples: Vec<_> = (0..(sample_rate as f32 * seconds) as usize)
+            .map(|i| (2.0 * PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect();
+        SamplesBuffer::new(1, sample_rate, samples)
+    }
+
+    fn crossings(samples: &[f32]) -> usize {
+        samples
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count()
+    }
+
+    #[test]
+    fn pitch_shift() {
+        let controls = Arc::new(Controls::default());
+        let unshifted: Vec<_> = PitchShift::new(sine(100.0, 8000, 1.0), controls.clone()).collect();
+        assert_eq!(unshifted, sine(100.0, 8000, 1.0).collect::<Vec<_>>());
+
+        // an octave up, keeping the length
+        controls.set_pitch_ratio(2.0);
+        let shifted: Vec<_> = PitchShift::new(sine(100.0, 8000, 1.0), controls).collect();
+        assert_eq!(shifted.len(), 8000);
+        let crossings = crossings(&shifted[800..]) as f32 / 0.9;
+        assert!((crossings - 400.0).abs() < 40.0, "{}", crossings);
+    }
+
+    #[test]
+    fn filters() {
+        let controls = Arc::new(Controls::default());
+        let filtered = |frequency, controls: &Arc<Controls>| {
+            let samples: Vec<_> =
+                Filter::new(sine(frequency, 8000, 1.0), controls.clone()).collect();
+            // the loudness once the cutoff settled
+            samples[4000..]
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        };
+        let passed: Vec<_> = Filter::new(sine(1000.0, 8000, 0.1), controls.clone()).collect();
+        assert_eq!(passed, sine(1000.0, 8000, 0.1).collect::<Vec<_>>());
+
+        controls.set_filters(Some(200.0), None);
+        assert!(filtered(50.0, &controls) > 0.95);
+        assert!(filtered(200.0, &controls) > 0.65 && filtered(200.0, &controls) < 0.75);
+        assert!(filtered(2000.0, &controls) < 0.02);
+        controls.set_filters(None, Some(1000.0));
+        assert!(filtered(100.0, &controls) < 0.02);
+        assert!(filtered(3000.0, &controls) > 0.95);
+
+        // the lowest low-pass of the bus and the source
+        let bus = Arc::new(BusOutput::new(1.0));
+        bus.set(1.0, Some(200.0), None);
+        controls.set_bus_output(bus);
+        controls.set_filters(Some(3000.0), None);
+        assert!(filtered(2000.0, &controls) < 0.02);
+
+        // turning off moves the cutoff back up first, without clicking
+        controls.set_filters(Some(100.0), None);
+        let mut filter = Filter::new(sine(1000.0, 8000, 1.0), controls.clone());
+        let _ = filter.by_ref().take(4000).count();
+        controls.set_filters(None, None);
+        controls.set_bus_output(Arc::default());
+        let rest: Vec<_> = filter.collect();
+        assert!(rest[..8].iter().all(|sample| sample.abs() < 0.1));
+        assert!(rest[2000..].iter().any(|sample| sample.abs() > 0.99));
+    }
+
+    #[test]
+    fn panning() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        let ones = |channels| SamplesBuffer::new(channels, 1000, vec![1.0; 1000]);
+        let controls = Arc::new(Controls::default());
+        let centered: Vec<_> = Pan::new(ones(1), controls.clone()).collect();
+        assert_eq!(centered, vec![1.0; 2000]);
+
+        controls.set_pan(-1.0);
+        let left: Vec<_> = Pan::new(ones(1), controls.clone()).collect();
+        assert!(close(left[0], SQRT_2) && close(left[1], 0.0));
+        controls.set_pan(0.5);
+        let stereo: Vec<_> = Pan::new(ones(2), controls.clone()).collect();
+        assert!(close(stereo[0], FRAC_PI_4.cos()) && stereo[1] == 1.0);
+
+        // panning while playing moves from one side to the other over 10ms, 10 frames here
+        controls.set_pan(-1.0);
+        let mut panned = Pan::new(ones(2), controls.clone());
+        let _ = panned.by_ref().take(2).count();
+        controls.set_pan(1.0);
+        let frames: Vec<_> = panned.collect();
+        assert!(close(frames[0], 1.0) && close(frames[1], (0.8 * FRAC_PI_2).cos()));
+        assert!(close(frames[38], 0.0) && close(frames[39], 1.0));
+    }
+}
@@ -0,0 +1,236 @@
+//! Loading sounds on other threads, so the game can go on meanwhile.
+
+use std::fmt;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+
+use super::SoundData;
+use crate::error::{GameError, GameResult};
+use crate::filesystem::File;
+use crate::loading::run;
+
+#[derive(Default)]
+struct Loading {
+    result: Mutex<Option<GameResult<SoundData>>>,
+    done: AtomicBool,
+    loaded: Condvar,
+    // set when the handle is dropped, to stop reading
+    cancelled: AtomicBool,
+}
+
+fn load(mut file: File, what: &str, cancelled: &AtomicBool) -> GameResult<Option<SoundData>> {
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        match file.read(&mut chunk)? {
+            0 => break,
+            read => buffer.extend_from_slice(&chunk[..read]),
+        }
+    }
+    let data = SoundData::from(buffer);
+    data.check(what)?;
+    Ok(Some(data))
+}
+
+/// A sound loading on another thread, from [`SoundData::from_path_async`], which stops loading
+/// when dropped.
+pub struct SoundDataHandle {
+    loading: Arc<Loading>,
+}
+
+impl SoundDataHandle {
+    pub(crate) fn start(file: GameResult<File>, what: String) -> Self {
+        let loading = Arc::new(Loading::default());
+        let loaded = loading.clone();
+        let cancelled = loading.clone();
+        let finish = move |result| {
+            let mut slot = loaded.result.lock().unwrap_or_else(PoisonError::into_inner);
+            *slot = Some(result);
+            // set while locked, so a wait can't miss it
+            loaded.done.store(true, Ordering::Release);
+            drop(slot);
+            loaded.loaded.notify_all();
+        };
+        match file {
+            Ok(file) => run(Box::new(move || {
+                match load(file, &what, &cancelled.cancelled) {
+                    Ok(Some(data)) => finish(Ok(data)),
+                    Ok(None) => (),
+                    Err(e) => finish(Err(e)),
+                }
+            })),
+            Err(e) => finish(Err(e)),
+        }
+        SoundDataHandle { loading }
+    }
+
+    /// Returns whether the sound finished loading, or failed to.
+    pub fn is_done(&self) -> bool {
+        self.loading.done.load(Ordering::Acquire)
+    }
+
+    /// Returns the sound once it's loaded, or the error it failed to load with, after which
+    /// this returns `None` again, as it does while the sound is loading.
+    pub fn take(&mut self) -> Option<GameResult<SoundData>> {
+        self.loading
+            .result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Waits for the sound to load, returning it, or the error it failed to load with.
+    ///
+    /// Returns an error if the sound was taken already, with [`take()`](#method.take).
+    pub fn wait(self) -> GameResult<SoundData> {
+        let mut result = self
+            .loading
+            .result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while !self.is_done() {
+            result = self
+                .loading
+                .loaded
+                .wait(result)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        result.take().unwrap_or_else(|| {
+            Err(GameError::AudioError(String::from(
+                "The sound loaded was taken already",
+            )))
+        })
+    }
+}
+
+impl Drop for SoundDataHandle {
+    fn drop(&mut self) {
+        self.loading.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for SoundDataHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SoundDataHandle")
+            .field("done", &self.is_done())
+            .finish()
+    }
+}
+
+/// Sounds loading on other threads, from [`SoundData::from_paths_async`], e.g. for a loading
+/// bar, which stop loading when dropped.
+#[derive(Debug)]
+pub struct SoundDataBatch {
+    handles: Vec<SoundDataHandle>,
+}
+
+impl SoundDataBatch {
+    pub(crate) fn new(handles: Vec<SoundDataHandle>) -> Self {
+        SoundDataBatch { handles }
+    }
+
+    /// Returns how many sounds there are to load.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns whether there are no sounds to load.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Returns how many of the sounds finished loading, or failed to.
+    pub fn done(&self) -> usize {
+        self.handles
+            .iter()
+            .filter(|handle| handle.is_done())
+            .count()
+    }
+
+    /// Returns how much of the sounds finished loading, from 0 to 1, which is 1 if there are
+    /// none.
+    pub fn progress(&self) -> f32 {
+        if self.handles.is_empty() {
+            1.0
+        } else {
+            self.done() as f32 / self.handles.len() as f32
+        }
+    }
+
+    /// Returns whether all the sounds finished loading, or failed to.
+    pub fn is_done(&self) -> bool {
+        self.handles.iter().all(SoundDataHandle::is_done)
+    }
+
+    /// Returns the sounds, or the errors they failed to load with, in the order of their paths,
+    /// once all of them are done, after which this returns `None` again, as it does while any
+    /// is loading.
+    pub fn take(&mut self) -> Option<Vec<GameResult<SoundData>>> {
+        if !self.is_done() {
+            return None;
+        }
+        let taken: Vec<_> = self
+            .handles
+            .iter_mut()
+            .filter_map(SoundDataHandle::take)
+            .collect();
+        if taken.is_empty() && !self.handles.is_empty() {
+            None
+        } else {
+            Some(taken)
+        }
+    }
+
+    /// Waits for all the sounds to load, returning them, or the errors they failed to load
+    /// with, in the order of their paths.
+    pub fn wait(self) -> Vec<GameResult<SoundData>> {
+        self.handles
+            .into_iter()
+            .map(SoundDataHandle::wait)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn start(contents: &[u8], name: &str) -> SoundDataHandle {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, contents).unwrap();
+        let file = File::VfsFile(Box::new(std::fs::File::open(&path).unwrap()));
+        std::fs::remove_file(&path).unwrap();
+        SoundDataHandle::start(Ok(file), String::from("the test"))
+    }
+
+    #[test]
+    fn loading() {
+        let wav = SoundData::from_samples(44100, 1, &[0.0; 100]);
+        if cfg!(feature = "audio-wav") {
+            let loaded = start(wav.as_ref(), "ggez_loading_test_1").wait().unwrap();
+            assert_eq!(loaded.as_ref(), wav.as_ref());
+        }
+        let mut handle = start(wav.as_ref(), "ggez_loading_test_2");
+        // what doesn't decode is an error as it loads
+        assert!(start(b"not a sound", "ggez_loading_test_3").wait().is_err());
+        // and so is what can't be opened
+        let mut batch = SoundDataBatch::new(vec![SoundDataHandle::start(
+            Err(GameError::AudioError(String::new())),
+            String::new(),
+        )]);
+        assert!(batch.is_done());
+        assert_eq!(batch.progress(), 1.0);
+        assert!(batch.take().unwrap()[0].is_err());
+        assert!(batch.take().is_none());
+        while !handle.is_done() {
+            thread::yield_now();
+        }
+        assert!(handle.take().is_some());
+        assert!(handle.take().is_none());
+    }
+}
@@ -0,0 +1,309 @@
+//! The master volume and the buses sources play into.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::error::{GameError, GameResult};
+
+/// Stores a cutoff frequency which may be off, as 0.
+pub(crate) fn cutoff_bits(cutoff: Option<f32>) -> u32 {
+    cutoff.unwrap_or(0.0).to_bits()
+}
+
+pub(crate) fn cutoff_from_bits(bits: u32) -> Option<f32> {
+    Some(f32::from_bits(bits)).filter(|&cutoff| cutoff > 0.0)
+}
+
+/// The volume and filters of a bus, read by the audio thread.
+#[derive(Debug)]
+pub(crate) struct BusOutput {
+    volume: AtomicU32,
+    lowpass: AtomicU32,
+    highpass: AtomicU32,
+}
+
+impl BusOutput {
+    pub fn new(volume: f32) -> Self {
+        BusOutput {
+            volume: AtomicU32::new(volume.to_bits()),
+            lowpass: AtomicU32::new(cutoff_bits(None)),
+            highpass: AtomicU32::new(cutoff_bits(None)),
+        }
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn lowpass(&self) -> Option<f32> {
+        cutoff_from_bits(self.lowpass.load(Ordering::Relaxed))
+    }
+
+    pub fn highpass(&self) -> Option<f32> {
+        cutoff_from_bits(self.highpass.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, volume: f32, lowpass: Option<f32>, highpass: Option<f32>) {
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+        self.lowpass.store(cutoff_bits(lowpass), Ordering::Relaxed);
+        self.highpass
+            .store(cutoff_bits(highpass), Ordering::Relaxed);
+    }
+}
+
+impl Default for BusOutput {
+    fn default() -> Self {
+        BusOutput::new(1.0)
+    }
+}
+
+#[derive(Debug)]
+struct Bus {
+    volume: f32,
+    muted: bool,
+    parent: Option<String>,
+    lowpass: Option<f32>,
+    highpass: Option<f32>,
+    // what sources playing into the bus get, with its parent and the master volume
+    output: Arc<BusOutput>,
+}
+
+impl Bus {
+    fn volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
+}
+
+/// The volumes of the buses, see [`AudioContext::create_bus`](super::AudioContext::create_bus).
+#[derive(Debug)]
+pub(crate) struct Mixer {
+    master_volume: f32,
+    // while the window isn't focused, see `UnfocusedBehavior`
+    unfocused_muted: bool,
+    // what sources without a bus get
+    master: Arc<BusOutput>,
+    buses: HashMap<String, Bus>,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Mixer {
+            master_volume: 1.0,
+            unfocused_muted: false,
+            master: Arc::default(),
+            buses: HashMap::new(),
+        }
+    }
+}
+
+fn no_bus(name: &str) -> GameError {
+    GameError::AudioError(format!("There is no audio bus named {:?}", name))
+}
+
+impl Mixer {
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.max(0.0);
+        self.update();
+    }
+
+    pub fn set_unfocused_muted(&mut self, muted: bool) {
+        self.unfocused_muted = muted;
+        self.update();
+    }
+
+    pub fn create_bus(&mut self, name: &str) {
+        let _ = self.buses.entry(name.to_owned()).or_insert_with(|| Bus {
+            volume: 1.0,
+            muted: false,
+            parent: None,
+            lowpass: None,
+            highpass: None,
+            output: Arc::default(),
+        });
+        self.update();
+    }
+
+    pub fn buses(&self) -> impl Iterator<Item = &str> {
+        self.buses.keys().map(String::as_str)
+    }
+
+    fn bus(&self, name: &str) -> GameResult<&Bus> {
+        self.buses.get(name).ok_or_else(|| no_bus(name))
+    }
+
+    fn bus_mut(&mut self, name: &str) -> GameResult<&mut Bus> {
+        self.buses.get_mut(name).ok_or_else(|| no_bus(name))
+    }
+
+    pub fn bus_volume(&self, name: &str) -> GameResult<f32> {
+        Ok(self.bus(name)?.volume)
+    }
+
+    pub fn set_bus_volume(&mut self, name: &str, volume: f32) -> GameResult {
+        self.bus_mut(name)?.volume = volume.max(0.0);
+        self.update();
+        Ok(())
+    }
+
+    pub fn bus_muted(&self, name: &str) -> GameResult<bool> {
+        Ok(self.bus(name)?.muted)
+    }
+
+    pub fn set_bus_muted(&mut self, name: &str, muted: bool) -> GameResult {
+        self.bus_mut(name)?.muted = muted;
+        self.update();
+        Ok(())
+    }
+
+    pub fn bus_parent(&self, name: &str) -> GameResult<Option<&str>> {
+        Ok(self.bus(name)?.parent.as_deref())
+    }
+
+    pub fn set_bus_parent(&mut self, name: &str, parent: Option<&str>) -> GameResult {
+        let _ = self.bus(name)?;
+        if let Some(parent) = parent {
+            if self.bus(parent)?.parent.is_some() || parent == name {
+                return Err(GameError::AudioError(format!(
+                    "Can't route the audio bus {:?} into {:?}, which goes into another bus",
+                    name, parent
+                )));
+            }
+            if self
+                .buses
+                .values()
+                .any(|bus| bus.parent.as_deref() == Some(name))
+            {
+                return Err(GameError::AudioError(format!(
+                    "Can't route the audio bus {:?} into {:?}, since other buses go into it",
+                    name, parent
+                )));
+            }
+        }
+        self.bus_mut(name)?.parent = parent.map(str::to_owned);
+        self.update();
+        Ok(())
+    }
+
+    pub fn bus_lowpass(&self, name: &str) -> GameResult<Option<f32>> {
+        Ok(self.bus(name)?.lowpass)
+    }
+
+    pub fn set_bus_lowpass(&mut self, name: &str, cutoff: Option<f32>) -> GameResult {
+        self.bus_mut(name)?.lowpass = cutoff;
+        self.update();
+        Ok(())
+    }
+
+    pub fn bus_highpass(&self, name: &str) -> GameResult<Option<f32>> {
+        Ok(self.bus(name)?.highpass)
+    }
+
+    pub fn set_bus_highpass(&mut self, name: &str, cutoff: Option<f32>) -> GameResult {
+        self.bus_mut(name)?.highpass = cutoff;
+        self.update();
+        Ok(())
+    }
+
+    /// Returns what sources playing into `bus`, or into none, get.
+    pub fn output(&self, bus: Option<&str>) -> GameResult<Arc<BusOutput>> {
+        match bus {
+            Some(name) => Ok(self.bus(name)?.output.clone()),
+            None => Ok(self.master.clone()),
+        }
+    }
+
+    // Works out the outputs of all the buses again.
+    fn update(&self) {
+        let master_volume = if self.unfocused_muted {
+            0.0
+        } else {
+            self.master_volume
+        };
+        self.master.set(master_volume, None, None);
+        for bus in self.buses.values() {
+            let parent = bus
+                .parent
+                .as_ref()
+                .and_then(|parent| self.buses.get(parent));
+            let volume = parent.map_or(1.0, Bus::volume) * bus.volume();
+            // the lowest low-pass and highest high-pass of both
+            let parent_lowpass = parent.and_then(|parent| parent.lowpass);
+            let lowpass = match (bus.lowpass, parent_lowpass) {
+                (Some(cutoff), Some(parent)) => Some(cutoff.min(parent)),
+                (cutoff, parent) => cutoff.or(parent),
+            };
+            let parent_highpass = parent.and_then(|parent| parent.highpass);
+            let highpass = match (bus.highpass, parent_highpass) {
+                (Some(cutoff), Some(parent)) => Some(cutoff.max(parent)),
+                (cutoff, parent) => cutoff.or(parent),
+            };
+            bus.output.set(master_volume * volume, lowpass, highpass);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buses() {
+        let mut mixer = Mixer::default();
+        mixer.create_bus("music");
+        mixer.create_bus("sfx");
+        mixer.create_bus("ui");
+        let music = mixer.output(Some("music")).unwrap();
+        let ui = mixer.output(Some("ui")).unwrap();
+        assert!(mixer.output(Some("voice")).is_err());
+        assert!(mixer.set_bus_volume("voice", 0.5).is_err());
+
+        mixer.set_master_volume(0.5);
+        mixer.set_bus_volume("music", 0.6).unwrap();
+        assert_eq!(mixer.bus_volume("music").unwrap(), 0.6);
+        assert_eq!(music.volume(), 0.3);
+        assert_eq!(mixer.output(None).unwrap().volume(), 0.5);
+
+        // one level of buses into buses
+        mixer.set_bus_parent("ui", Some("sfx")).unwrap();
+        mixer.set_bus_volume("sfx", 0.5).unwrap();
+        assert_eq!(ui.volume(), 0.25);
+        assert_eq!(mixer.bus_parent("ui").unwrap(), Some("sfx"));
+        assert!(mixer.set_bus_parent("music", Some("ui")).is_err());
+        assert!(mixer.set_bus_parent("sfx", Some("music")).is_err());
+        assert!(mixer.set_bus_parent("music", Some("music")).is_err());
+
+        // muting keeps the volume to unmute to
+        mixer.set_bus_muted("sfx", true).unwrap();
+        assert_eq!(ui.volume(), 0.0);
+        assert_eq!(mixer.bus_volume("sfx").unwrap(), 0.5);
+        mixer.set_bus_muted("sfx", false).unwrap();
+        assert_eq!(ui.volume(), 0.25);
+        mixer.set_unfocused_muted(true);
+        assert_eq!((ui.volume(), mixer.master_volume()), (0.0, 0.5));
+        mixer.set_unfocused_muted(false);
+        assert_eq!(ui.volume(), 0.25);
+
+        // filters of the parent apply to the buses going into it
+        mixer.set_bus_lowpass("sfx", Some(500.0)).unwrap();
+        assert_eq!(ui.lowpass(), Some(500.0));
+        mixer.set_bus_lowpass("ui", Some(1000.0)).unwrap();
+        mixer.set_bus_highpass("ui", Some(200.0)).unwrap();
+        assert_eq!((ui.lowpass(), ui.highpass()), (Some(500.0), Some(200.0)));
+        mixer.set_bus_lowpass("sfx", None).unwrap();
+        assert_eq!(ui.lowpass(), Some(1000.0));
+        assert_eq!(music.lowpass(), None);
+
+        // creating it again keeps it as it is
+        mixer.create_bus("music");
+        assert_eq!(mixer.bus_volume("music").unwrap(), 0.6);
+    }
+}
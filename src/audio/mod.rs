@@ -0,0 +1,2231 @@
+//! Provides an interface to output sound to the user's speakers.
+//!
+//! It consists of two main types: [`SoundData`](struct.SoundData.html)
+//! is just an array of raw sound data bytes, and a [`Source`](struct.Source.html) is a
+//! `SoundData` connected to a particular sound channel ready to be played.
+//!
+//! Sounds can be Ogg Vorbis, WAV, FLAC or MP3, decoded with the `audio-vorbis`, `audio-wav`,
+//! `audio-flac` and `audio-mp3` features, all of which are on by default. Recording from a
+//! microphone, with [`AudioContext::start_capture`], needs the `audio-capture` feature.
+//!
+//! Sound plays through rodio, unless another [`AudioBackend`] is given with
+//! [`ContextBuilder::audio_backend`](crate::ContextBuilder::audio_backend).
+#![cfg(feature = "audio")]
+
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::mem;
+use std::path;
+use std::time;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::context::Has;
+use crate::error::GameError;
+use crate::error::GameResult;
+use crate::error::ResultExt;
+use crate::filesystem::Filesystem;
+use crate::filesystem::InternalClone;
+
+mod backend;
+#[cfg(feature = "audio-capture")]
+mod capture;
+mod device;
+mod effects;
+mod loading;
+mod mixer;
+mod oneshot;
+mod playback;
+mod reverb;
+mod spatial;
+mod stream;
+
+pub use backend::{AudioBackend, MixStream, NullAudioBackend};
+#[cfg(feature = "audio-capture")]
+pub use capture::{CaptureConfig, CaptureStream};
+pub use device::{AudioDeviceInfo, RodioBackend};
+pub use loading::{SoundDataBatch, SoundDataHandle};
+pub use oneshot::SoundHandle;
+pub use reverb::ReverbParams;
+pub use spatial::Attenuation;
+
+use device::Output;
+use effects::{Filter, Pan, PitchShift};
+use mixer::{BusOutput, Mixer};
+use oneshot::{OneShotSettings, OneShots};
+use playback::{Controls, Events, Fade, Looped, Playback, Queue, Reopen, Samples, Shared};
+use reverb::{ReverbBus, ReverbSend};
+use spatial::{Doppler, Listener, Placement, Spatialize};
+use stream::{Generator, SourceData, StreamedFile};
+
+/// A struct that contains all information for tracking sound info.
+///
+/// You generally don't have to create this yourself, it will be part
+/// of your `Context` object.
+pub struct AudioContext {
+    fs: Filesystem,
+    output: Output,
+    mixer: Arc<Mutex<Mixer>>,
+    reverb: Arc<ReverbBus>,
+    reverb_params: Option<ReverbParams>,
+    one_shots: OneShots,
+    default_attenuation: Attenuation,
+    listener: Listener,
+    // paused with `set_output_paused`, and while the app is suspended
+    output_paused: bool,
+    suspended: bool,
+    // shared with the spatial sources
+    shared_listener: Arc<Shared<Listener>>,
+    // why there's no device, if the audio failed to start
+    failure: Option<String>,
+}
+
+impl AudioContext {
+    /// Create new `AudioContext`, playing through rodio on the default output device.
+    pub fn new(fs: &Filesystem) -> GameResult<Self> {
+        Self::with_backend(fs, Box::new(RodioBackend::new()?))
+    }
+
+    /// Creates an `AudioContext` playing through `backend`, on its default output device,
+    /// see [`ContextBuilder::audio_backend`](crate::ContextBuilder::audio_backend).
+    pub fn with_backend(fs: &Filesystem, backend: Box<dyn AudioBackend>) -> GameResult<Self> {
+        let mut output = Output::new(backend);
+        output.start(None)?;
+        Ok(Self::with_output(fs, output))
+    }
+
+    // Plays nowhere, for a context made by `ContextBuilder::headless`.
+    pub(crate) fn headless(fs: &Filesystem) -> Self {
+        Self::with_output(fs, Output::new(Box::new(NullAudioBackend)))
+    }
+
+    // Plays nowhere too, as the audio failed to start with `error`.
+    pub(crate) fn unavailable(fs: &Filesystem, error: &GameError) -> Self {
+        Self {
+            failure: Some(format!("{:#}", error)),
+            ..Self::headless(fs)
+        }
+    }
+
+    fn with_output(fs: &Filesystem, output: Output) -> Self {
+        // the sources are converted to the format of the output for the reverb
+        let reverb = ReverbBus::new(output.channels(), output.sample_rate());
+        Self {
+            fs: InternalClone::clone(fs),
+            output,
+            mixer: Arc::default(),
+            reverb: Arc::new(reverb),
+            reverb_params: None,
+            one_shots: OneShots::default(),
+            default_attenuation: Attenuation::default(),
+            listener: Listener::default(),
+            output_paused: false,
+            suspended: false,
+            shared_listener: Arc::default(),
+            failure: None,
+        }
+    }
+}
+
+impl AudioContext {
+    /// Returns the audio device.
+    ///
+    /// What's played on it directly stays on this device, rather than moving to another one
+    /// as sources do, see [`set_output_device()`](#method.set_output_device).
+    ///
+    /// # Panics
+    ///
+    /// In a context made by [`ContextBuilder::headless`](crate::ContextBuilder::headless),
+    /// or where the audio failed to start, where sound plays on no device. See
+    /// [`try_device()`](#method.try_device) for an error instead.
+    pub fn device(&self) -> &rodio::OutputStreamHandle {
+        match self.try_device() {
+            Ok(device) => device,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Returns the device sound plays on, as [`device()`](#method.device) does, or an error if
+    /// there is none, in a headless context or where the audio failed to start.
+    pub fn try_device(&self) -> GameResult<&rodio::OutputStreamHandle> {
+        self.output.stream_handle().ok_or_else(|| self.no_device())
+    }
+
+    fn no_device(&self) -> GameError {
+        GameError::AudioError(match &self.failure {
+            Some(failure) => format!(
+                "There is no audio device, as the audio failed to start: {}",
+                failure
+            ),
+            None if !self.output.started() => {
+                String::from("There is no audio device in a headless context")
+            }
+            None => String::from("There is no rodio device, as the audio backend isn't rodio"),
+        })
+    }
+
+    /// Returns the devices which can play sound, e.g. for an options menu to choose from, or
+    /// none if they can't be listed.
+    pub fn output_devices(&self) -> Vec<AudioDeviceInfo> {
+        self.output.output_devices()
+    }
+
+    /// Returns the name of the device sound is playing on, if it's known.
+    pub fn output_device(&self) -> Option<&str> {
+        self.output.device()
+    }
+
+    /// Returns the name of the device chosen with
+    /// [`set_output_device()`](#method.set_output_device), or `None` if sound follows the
+    /// default device.
+    pub fn chosen_output_device(&self) -> Option<&str> {
+        self.output.chosen()
+    }
+
+    /// Moves all the sound, of sources playing or not, to the output device named `name`, out
+    /// of [`output_devices()`](#method.output_devices), or with `None` to the default device,
+    /// following it whenever it changes, which is what sound does unless this is called.
+    ///
+    /// There is a short gap in the sound while it moves. If the device chosen is unplugged,
+    /// sound moves to the default device until it's plugged in again. The devices are checked
+    /// every half a second, and
+    /// [`EventHandler::audio_device_changed_event`](crate::event::EventHandler::audio_device_changed_event)
+    /// is called when sound moved on its own.
+    ///
+    /// Returns an error if there is no such device, or it can't be played on, in which case
+    /// sound keeps playing where it was, and in a headless context or where the audio failed to
+    /// start.
+    pub fn set_output_device(&mut self, name: Option<&str>) -> GameResult {
+        if !self.output.started() {
+            return Err(self.no_device());
+        }
+        self.output.choose(name)
+    }
+
+    /// Moves the sound to another device if the one it's on is gone, or the default one changed
+    /// and it follows that, returning the name of the device it moved to.
+    pub(crate) fn follow_output_device(&mut self) -> Option<String> {
+        self.output.follow()
+    }
+
+    fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        self.mixer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Gets the volume every source is multiplied by.
+    pub fn master_volume(&self) -> f32 {
+        self.mixer().master_volume()
+    }
+
+    /// Sets the volume every source is multiplied by, right away for the sounds playing.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.mixer().set_master_volume(volume)
+    }
+
+    pub(crate) fn set_unfocused_muted(&mut self, muted: bool) {
+        self.mixer().set_unfocused_muted(muted)
+    }
+
+    /// Pauses every sound, or resumes them with `false`, e.g. for a pause menu, keeping them
+    /// where they were rather than letting them go on silently as muting would. Sounds played
+    /// on [`device()`](#method.device) directly aren't paused.
+    ///
+    /// Sound is paused on its own while the app is suspended too, see
+    /// [`EventHandler::suspend_event`](crate::event::EventHandler::suspend_event).
+    pub fn set_output_paused(&mut self, paused: bool) {
+        self.output_paused = paused;
+        self.output.set_paused(self.output_paused || self.suspended);
+    }
+
+    /// Returns whether sound was paused with [`set_output_paused()`](#method.set_output_paused).
+    pub fn output_paused(&self) -> bool {
+        self.output_paused
+    }
+
+    pub(crate) fn set_suspended(&mut self, suspended: bool) {
+        self.suspended = suspended;
+        self.output.set_paused(self.output_paused || self.suspended);
+    }
+
+    /// Creates a bus named `name` for sources to play into, with
+    /// [`SoundSource::set_bus`], so their volume is multiplied by the bus volume, e.g. for
+    /// separate music and sound effect volumes.
+    ///
+    /// A new bus is at the full volume, unmuted, and goes straight to the output.
+    /// Creating a bus that exists does nothing. Buses are kept for as long as the context,
+    /// so they're meant to be made once, for each category of sound.
+    pub fn create_bus(&mut self, name: &str) {
+        self.mixer().create_bus(name)
+    }
+
+    /// Returns the names of the buses, in no particular order.
+    pub fn buses(&self) -> Vec<String> {
+        self.mixer().buses().map(str::to_owned).collect()
+    }
+
+    /// Gets the volume of the bus named `name`, which is kept while it's muted.
+    pub fn bus_volume(&self, name: &str) -> GameResult<f32> {
+        self.mixer().bus_volume(name)
+    }
+
+    /// Sets the volume of the bus named `name`, right away for the sounds playing into it.
+    ///
+    /// Sources playing into it sound at their own volume times that of the bus, of the bus it
+    /// goes into, if any, and the [master volume](#method.set_master_volume).
+    pub fn set_bus_volume(&mut self, name: &str, volume: f32) -> GameResult {
+        self.mixer().set_bus_volume(name, volume)
+    }
+
+    /// Gets whether the bus named `name` is muted.
+    pub fn bus_muted(&self, name: &str) -> GameResult<bool> {
+        self.mixer().bus_muted(name)
+    }
+
+    /// Mutes the bus named `name`, and the buses going into it, keeping their volumes.
+    pub fn set_bus_muted(&mut self, name: &str, muted: bool) -> GameResult {
+        self.mixer().set_bus_muted(name, muted)
+    }
+
+    /// Gets the bus the bus named `name` goes into, if any.
+    pub fn bus_parent(&self, name: &str) -> GameResult<Option<String>> {
+        Ok(self.mixer().bus_parent(name)?.map(str::to_owned))
+    }
+
+    /// Makes the bus named `name` go into the bus named `parent`, to be multiplied by its volume
+    /// as well, or straight to the output with `None`, e.g. for UI sounds under sound effects.
+    ///
+    /// Buses only go one level deep, so this returns an error if `parent` goes into another bus
+    /// or other buses go into this one, as well as if either doesn't exist.
+    pub fn set_bus_parent(&mut self, name: &str, parent: Option<&str>) -> GameResult {
+        self.mixer().set_bus_parent(name, parent)
+    }
+
+    /// Gets the cutoff of the low-pass filter of the bus named `name`, if any.
+    pub fn bus_lowpass(&self, name: &str) -> GameResult<Option<f32>> {
+        self.mixer().bus_lowpass(name)
+    }
+
+    /// Filters the sources playing into the bus named `name`, and the buses going into it, as
+    /// with [`SoundSource::set_lowpass`], e.g. to muffle the sound effects underwater.
+    ///
+    /// The lower cutoff applies where the source, or the bus it goes into, has one too.
+    pub fn set_bus_lowpass(&mut self, name: &str, cutoff_hz: Option<f32>) -> GameResult {
+        self.mixer().set_bus_lowpass(name, cutoff_hz)
+    }
+
+    /// Gets the cutoff of the high-pass filter of the bus named `name`, if any.
+    pub fn bus_highpass(&self, name: &str) -> GameResult<Option<f32>> {
+        self.mixer().bus_highpass(name)
+    }
+
+    /// Filters the sources playing into the bus named `name`, and the buses going into it, as
+    /// with [`SoundSource::set_highpass`].
+    ///
+    /// The higher cutoff applies where the source, or the bus it goes into, has one too.
+    pub fn set_bus_highpass(&mut self, name: &str, cutoff_hz: Option<f32>) -> GameResult {
+        self.mixer().set_bus_highpass(name, cutoff_hz)
+    }
+
+    /// Gets the attenuation new spatial sources start with.
+    pub fn default_attenuation(&self) -> Attenuation {
+        self.default_attenuation
+    }
+
+    /// Sets the attenuation spatial sources created from now on start with, see
+    /// [`SpatialSource::set_attenuation`].
+    pub fn set_default_attenuation(&mut self, attenuation: Attenuation) {
+        self.default_attenuation = attenuation;
+    }
+
+    /// Gets how strong the Doppler effect is, see [`set_doppler_factor()`](#method.set_doppler_factor).
+    pub fn doppler_factor(&self) -> f32 {
+        self.listener.doppler_factor
+    }
+
+    /// Sets how strong the Doppler effect of spatial sources moving relative to the listener is,
+    /// where 1 is realistic and 0, the default, turns it off.
+    ///
+    /// A source coming closer sounds faster and higher, and one going away slower and lower, by
+    /// the speed of sound plus the one of the listener divided by the one of the source, both
+    /// towards the other along the line between them, as in OpenAL, with the factor multiplying
+    /// the speeds. It's updated smoothly as velocities change, multiplies the pitch and speed the
+    /// source is set to, and makes [`SoundSource::elapsed`] go faster or slower as well.
+    /// Sources are resampled on the audio thread while it's on.
+    pub fn set_doppler_factor(&mut self, factor: f32) {
+        self.listener.doppler_factor = factor.max(0.0);
+        self.shared_listener.set(self.listener);
+    }
+
+    /// Gets the speed of sound, see [`set_speed_of_sound()`](#method.set_speed_of_sound).
+    pub fn speed_of_sound(&self) -> f32 {
+        self.listener.speed_of_sound
+    }
+
+    /// Sets the speed of sound for the Doppler effect, in the units of the positions per second,
+    /// 343.3 at first, as in meters per second.
+    pub fn set_speed_of_sound(&mut self, speed: f32) {
+        self.listener.speed_of_sound = speed.max(f32::MIN_POSITIVE);
+        self.shared_listener.set(self.listener);
+    }
+
+    /// Gets the velocity of the listener, see
+    /// [`set_listener_velocity()`](#method.set_listener_velocity).
+    pub fn listener_velocity(&self) -> mint::Vector3<f32> {
+        self.listener.velocity.into()
+    }
+
+    /// Sets the velocity of the listener, in the units of the positions per second, which is
+    /// between the ears of every spatial source, for the Doppler effect.
+    pub fn set_listener_velocity<V>(&mut self, velocity: V)
+    where
+        V: Into<mint::Vector3<f32>>,
+    {
+        self.listener.velocity = velocity.into().into();
+        self.shared_listener.set(self.listener);
+    }
+
+    /// Gets the settings of the reverb, if it's on.
+    pub fn reverb(&self) -> Option<ReverbParams> {
+        self.reverb_params
+    }
+
+    /// Turns on the reverb with `params`, or changes them right away if it's on, or turns it off
+    /// with `None`. Sources send some of their sound into it with [`SoundSource::set_reverb_send`],
+    /// e.g. with [`ReverbParams::HALL`].
+    ///
+    /// This is a Freeverb reverb, a bank of comb and allpass filters for each channel of the
+    /// output, which runs on the audio thread for as long as it's on, whether or not anything is
+    /// sent into it. What's sent is after the volume and fade of the source, so a source faded
+    /// out is out of the reverb too, though that keeps ringing for a bit. Turned off, the reverb
+    /// fades out over 50ms and its processing stops, and sources don't send into it at all.
+    pub fn set_reverb(&mut self, params: Option<ReverbParams>) -> GameResult {
+        if let Some(reverb) = self.reverb.set(params) {
+            self.output.play(reverb);
+        }
+        self.reverb_params = params;
+        Ok(())
+    }
+
+    /// Returns the devices which can be recorded from, whose names go in
+    /// [`CaptureConfig::device`], or none if they can't be listed.
+    #[cfg(feature = "audio-capture")]
+    pub fn input_devices(&self) -> Vec<AudioDeviceInfo> {
+        device::input_devices()
+    }
+
+    /// Starts recording from an input device, e.g. a microphone, as `config` says, until the
+    /// stream returned is dropped. The samples recorded are read with [`CaptureStream::read`].
+    ///
+    /// Returns an error if there is no such device, it can't record as `config` says, or it
+    /// can't be opened, e.g. as recording isn't allowed, in which case the game can go on
+    /// without it.
+    #[cfg(feature = "audio-capture")]
+    pub fn start_capture(&mut self, config: CaptureConfig) -> GameResult<CaptureStream> {
+        capture::start(config)
+    }
+
+    /// Plays `data` once, keeping the source until it finishes, so it doesn't have to be kept
+    /// around, e.g. for sound effects. The handle returned changes or stops the sound while it
+    /// plays, and can be dropped right away.
+    ///
+    /// Finished sources are reused to play the same `SoundData`, or a clone of it, again.
+    /// If as many sounds as [`set_max_one_shots_per_sound()`](#method.set_max_one_shots_per_sound)
+    /// are playing it already, or as many as [`set_max_one_shots()`](#method.set_max_one_shots)
+    /// are playing at all, the one started longest ago is stopped to make way for this one.
+    pub fn play_once(&mut self, data: &SoundData) -> GameResult<SoundHandle> {
+        self.play_once_with(data, 1.0, 1.0, 0.0)
+    }
+
+    /// Plays `data` once as with [`play_once()`](#method.play_once), at `volume`, with its speed
+    /// and pitch multiplied by `pitch` as with [`SoundSource::set_pitch`], and panned to `pan`
+    /// as with [`SoundSource::set_pan`], e.g. to vary the same sound a bit every time.
+    pub fn play_once_with(
+        &mut self,
+        data: &SoundData,
+        volume: f32,
+        pitch: f32,
+        pan: f32,
+    ) -> GameResult<SoundHandle> {
+        let settings = OneShotSettings {
+            volume,
+            pitch,
+            pan,
+            position: None,
+        };
+        self.with_one_shots(|one_shots, audio| one_shots.play(audio, data, settings))
+    }
+
+    /// Plays `data` once as with [`play_once()`](#method.play_once), as a [`SpatialSource`]
+    /// at `position`, heard by ears where they are for a new spatial source.
+    pub fn play_once_at<P>(&mut self, data: &SoundData, position: P) -> GameResult<SoundHandle>
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        let settings = OneShotSettings {
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            position: Some(position.into()),
+        };
+        self.with_one_shots(|one_shots, audio| one_shots.play(audio, data, settings))
+    }
+
+    /// Makes [`EventHandler::audio_finished_event`](crate::event::EventHandler::audio_finished_event)
+    /// report the returned handle every time a sound of `source` plays to its end, as
+    /// [`SoundSource::on_finished`] tells, until the source is dropped. Sounds played once are
+    /// reported with the handle [`play_once()`](#method.play_once) returns on their own.
+    pub fn notify_finished(&mut self, source: &impl SoundSource) -> SoundHandle {
+        self.one_shots.notify(source.on_finished())
+    }
+
+    /// Returns the sounds which finished since the last call, and which
+    /// [`audio_finished_event`](crate::event::EventHandler::audio_finished_event) reports, so it's
+    /// only of use with an event loop of your own.
+    pub fn finished_sounds(&mut self) -> Vec<SoundHandle> {
+        self.one_shots.take_finished()
+    }
+
+    /// Gets how many sounds played once may play at the same time, see
+    /// [`set_max_one_shots()`](#method.set_max_one_shots).
+    pub fn max_one_shots(&self) -> usize {
+        self.one_shots.max_sounds()
+    }
+
+    /// Sets how many sounds played with [`play_once()`](#method.play_once) may play at the same
+    /// time, at least 1 and 32 at first, which is also how many sources are kept for them.
+    pub fn set_max_one_shots(&mut self, max: usize) {
+        self.one_shots.set_max_sounds(max)
+    }
+
+    /// Gets how many times the same sound may play once at the same time, see
+    /// [`set_max_one_shots_per_sound()`](#method.set_max_one_shots_per_sound).
+    pub fn max_one_shots_per_sound(&self) -> usize {
+        self.one_shots.max_per_sound()
+    }
+
+    /// Sets how many times the same sound played with [`play_once()`](#method.play_once) may
+    /// play at the same time, at least 1 and 8 at first, so the same sound played every frame
+    /// doesn't get ever louder and drown out the others.
+    pub fn set_max_one_shots_per_sound(&mut self, max: usize) {
+        self.one_shots.set_max_per_sound(max)
+    }
+
+    // Lets the sounds played once be changed while creating and stopping sources with `self`.
+    fn with_one_shots<R>(&mut self, f: impl FnOnce(&mut OneShots, &AudioContext) -> R) -> R {
+        let mut one_shots = mem::take(&mut self.one_shots);
+        let result = f(&mut one_shots, self);
+        self.one_shots = one_shots;
+        result
+    }
+}
+
+impl fmt::Debug for AudioContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<AudioContext: {:p}>", self)
+    }
+}
+
+/// Static sound data stored in memory.
+/// It is `Arc`'ed, so cheap to clone.
+#[derive(Clone, Debug)]
+pub struct SoundData(Arc<[u8]>);
+
+impl SoundData {
+    /// Load the file at the given path and create a new `SoundData` from it.
+    pub fn new<P: AsRef<path::Path>>(fs: &impl Has<Filesystem>, path: P) -> GameResult<Self> {
+        let fs = fs.retrieve();
+        let path = path.as_ref();
+        let file = &mut fs.open(path)?;
+        SoundData::from_read(file).with_context(|| format!("reading the audio file {:?}", path))
+    }
+
+    /// Starts loading the file at the given path on another thread, returning a handle to
+    /// check on it with, e.g. to go on drawing a loading screen meanwhile.
+    ///
+    /// The file is read and checked to be a sound ggez can decode, as
+    /// [`Source::from_data`] does, on one of a few threads shared by all sounds loading, so an
+    /// error is got from the handle rather than later on. The file is opened on this thread,
+    /// which for one in a zip archive means decompressing it. Dropping the handle stops the
+    /// loading, if it's still going.
+    pub fn from_path_async<P: AsRef<path::Path>>(
+        fs: &impl Has<Filesystem>,
+        path: P,
+    ) -> SoundDataHandle {
+        let fs = fs.retrieve();
+        let path = path.as_ref();
+        SoundDataHandle::start(fs.open(path), format!("the audio file {:?}", path))
+    }
+
+    /// Starts loading the files at the given paths on other threads, as
+    /// [`from_path_async()`](#method.from_path_async) does, returning a batch of them to
+    /// check the progress of, e.g. for a loading bar.
+    pub fn from_paths_async<P: AsRef<path::Path>>(
+        fs: &impl Has<Filesystem>,
+        paths: impl IntoIterator<Item = P>,
+    ) -> SoundDataBatch {
+        SoundDataBatch::new(
+            paths
+                .into_iter()
+                .map(|path| SoundData::from_path_async(fs, path))
+                .collect(),
+        )
+    }
+
+    /// Copies the data in the given slice into a new `SoundData` object.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        SoundData(Arc::from(data))
+    }
+
+    /// Creates a `SoundData` from any `Read` object; this involves
+    /// copying it into a buffer.
+    pub fn from_read<R>(reader: &mut R) -> GameResult<Self>
+    where
+        R: Read,
+    {
+        let mut buffer = Vec::new();
+        let _ = reader.read_to_end(&mut buffer)?;
+
+        Ok(SoundData::from(buffer))
+    }
+
+    /// Creates a `SoundData` of `samples`, with `channels` interleaved, from -1 to 1,
+    /// e.g. for sound effects made up by the game.
+    ///
+    /// It's stored as a WAV file of 32-bit floats, which is what [`as_ref()`](#method.as_ref)
+    /// returns, so it needs the `audio-wav` feature to play, at the 16 bits rodio decodes to.
+    /// See [`Source::from_generator`] to make up samples as they play instead.
+    pub fn from_samples(sample_rate: u32, channels: u16, samples: &[f32]) -> Self {
+        let channels = channels.max(1);
+        let data_len = (samples.len() * 4) as u32;
+        let mut wav = Vec::with_capacity(44 + samples.len() * 4);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // IEEE floats
+        wav.extend_from_slice(&3u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * channels as u32 * 4).to_le_bytes());
+        wav.extend_from_slice(&(channels * 4).to_le_bytes());
+        wav.extend_from_slice(&32u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        SoundData::from(wav)
+    }
+
+    /// Indicates if the data can be played as a sound.
+    pub fn can_play(&self) -> bool {
+        let cursor = io::Cursor::new(self.clone());
+        rodio::Decoder::new(cursor).is_ok()
+    }
+}
+
+impl From<Arc<[u8]>> for SoundData {
+    #[inline]
+    fn from(arc: Arc<[u8]>) -> Self {
+        SoundData(arc)
+    }
+}
+
+impl SoundData {
+    // Returns an error, saying it's `what`, if the sound doesn't decode.
+    pub(crate) fn check(&self, what: &str) -> GameResult {
+        stream::SourceData::Memory(self.clone()).check(what)
+    }
+}
+
+impl From<Vec<u8>> for SoundData {
+    fn from(v: Vec<u8>) -> Self {
+        SoundData(Arc::from(v))
+    }
+}
+
+impl From<Box<[u8]>> for SoundData {
+    fn from(b: Box<[u8]>) -> Self {
+        SoundData(Arc::from(b))
+    }
+}
+
+impl AsRef<[u8]> for SoundData {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/// What a source does when a fade, see [`SoundSource::fade_to`], ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AfterFade {
+    /// Keep playing at the faded volume.
+    Continue,
+    /// Pause, as with [`SoundSource::pause`], to resume at the full volume.
+    Pause,
+    /// Stop, as with [`SoundSource::stop`], to play again at the full volume.
+    Stop,
+}
+
+/// Tells when the sounds of a source finish, see [`SoundSource::on_finished`].
+#[derive(Debug)]
+pub struct FinishedToken {
+    events: Arc<Events>,
+    // counting the times around the loop, rather than the sounds finishing
+    loops: bool,
+    seen: AtomicU32,
+}
+
+impl FinishedToken {
+    fn new(events: &Arc<Events>, loops: bool) -> Self {
+        let token = FinishedToken {
+            events: events.clone(),
+            loops,
+            seen: AtomicU32::new(0),
+        };
+        token.seen.store(token.count(), Ordering::Relaxed);
+        token
+    }
+
+    fn count(&self) -> u32 {
+        if self.loops {
+            self.events.looped()
+        } else {
+            self.events.finished()
+        }
+    }
+
+    /// Returns `true` once after a sound finished, and `false` until the next one does,
+    /// so it's meant to be polled, e.g. every update. Sounds finishing in between are
+    /// reported once.
+    pub fn finished(&self) -> bool {
+        let count = self.count();
+        self.seen.swap(count, Ordering::Relaxed) != count
+    }
+
+    // Returns whether anything but this token is left to finish.
+    fn orphaned(&self) -> bool {
+        Arc::strong_count(&self.events) == 1
+    }
+}
+
+/// A trait defining the operations possible on a sound;
+/// it is implemented by both `Source` and `SpatialSource`.
+pub trait SoundSource {
+    /// Plays the audio source; restarts the sound if currently playing
+    fn play(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()
+    }
+
+    /// Plays the `SoundSource`; waits until done if the sound is currently playing
+    fn play_later(&self) -> GameResult;
+
+    /// Play source "in the background"; cannot be stopped
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult;
+
+    /// Sets the source to repeat playback infinitely on next [`play()`](#method.play), the same
+    /// as [`set_repeat_count(u32::MAX)`](#tymethod.set_repeat_count), or to play once.
+    fn set_repeat(&mut self, repeat: bool);
+
+    /// Sets the fade-in time of the source
+    fn set_fade_in(&mut self, dur: time::Duration);
+
+    /// Sets the time from which playback begins, skipping audio up to that point.
+    ///
+    /// Calls to [`elapsed()`](#tymethod.elapsed) will measure from this point, ignoring skipped time.
+    ///
+    /// Effects such as [`set_fade_in()`](#tymethod.set_fade_in) or [`set_pitch()`](#tymethod.set_pitch)
+    /// will apply from this new start.
+    ///
+    /// If [`set_repeat()`](#tymethod.set_repeat) is set to true, then after looping, the audio will return
+    /// to the original beginning of the source, or the start of the
+    /// [loop region](#tymethod.set_loop_region), rather than the time specified here.
+    fn set_start(&mut self, dur: time::Duration);
+
+    /// Sets the speed ratio (by adjusting the playback speed), the same as
+    /// [`set_speed()`](#tymethod.set_speed).
+    fn set_pitch(&mut self, ratio: f32);
+
+    /// Sets the speed ratio on next [`play()`](#method.play) by resampling, which changes the pitch
+    /// as well, e.g. an octave lower at half the speed.
+    ///
+    /// This is cheap, and the ratio is clamped between 1/16 and 16.
+    /// [`elapsed()`](#tymethod.elapsed) goes at the speed of the sound, so twice as fast as
+    /// the clock at a speed of 2.
+    fn set_speed(&mut self, ratio: f32);
+
+    /// Shifts the pitch by `semitones`, without changing the speed, right away while playing.
+    ///
+    /// Combined with [`set_speed()`](#tymethod.set_speed) this changes the speed at the
+    /// original pitch, e.g. for slow motion at half the speed and 12 semitones up.
+    /// It's clamped to two octaves either way, and doesn't affect
+    /// [`elapsed()`](#tymethod.elapsed). Shifting sounds slightly warbly, and delays the sound
+    /// by up to 50ms, while 0 semitones doesn't process the sound at all.
+    fn set_pitch_semitones(&mut self, semitones: f32);
+
+    /// Pans the sound from -1, all the way to the left, through 0 in the center, to 1, all the
+    /// way to the right, right away while playing, moving there over 10ms to not click.
+    ///
+    /// Mono sounds are panned with a constant power law, at `cos` and `sin` of
+    /// `(pan + 1) * π/4` for the left and right, times √2 to leave the center as loud as before,
+    /// so they're 3dB louder in one ear when panned all the way. Other sounds are balanced,
+    /// turning the other channel down to `cos(|pan| * π/2)`. `SpatialSource` ignores this, as
+    /// its positions pan it.
+    fn set_pan(&mut self, pan: f32);
+
+    /// Gets where the sound is panned to, see [`set_pan()`](#tymethod.set_pan).
+    fn pan(&self) -> f32;
+
+    /// Filters out the frequencies above `cutoff_hz`, e.g. to muffle the sound behind a wall,
+    /// or stops filtering with `None`, right away while playing.
+    ///
+    /// This is a Butterworth biquad filter on the audio thread, which moves to a new cutoff over
+    /// about 20ms to not click, also when turned on or off. It costs five multiplications a sample
+    /// and recomputing its coefficients with a sine and cosine every 16 frames while it's on,
+    /// and nothing while it's off. See [`AudioContext::set_bus_lowpass`] to filter a whole bus.
+    fn set_lowpass(&mut self, cutoff_hz: Option<f32>);
+
+    /// Gets the cutoff of the low-pass filter, see [`set_lowpass()`](#tymethod.set_lowpass).
+    fn lowpass(&self) -> Option<f32>;
+
+    /// Filters out the frequencies below `cutoff_hz`, e.g. for a tinny radio, or stops filtering
+    /// with `None`, right away while playing, as [`set_lowpass()`](#tymethod.set_lowpass) does.
+    fn set_highpass(&mut self, cutoff_hz: Option<f32>);
+
+    /// Gets the cutoff of the high-pass filter, see [`set_highpass()`](#tymethod.set_highpass).
+    fn highpass(&self) -> Option<f32>;
+
+    /// Sends `amount` of the sound, from 0 to 1, into the reverb, see
+    /// [`AudioContext::set_reverb`], right away while playing, 0 at first.
+    ///
+    /// The sound itself is turned down by the dry volume of the reverb as much as it's sent,
+    /// so sending all of it plays it at the dry volume, and sending none leaves it as it is.
+    fn set_reverb_send(&mut self, amount: f32);
+
+    /// Gets how much of the sound goes into the reverb, see
+    /// [`set_reverb_send()`](#tymethod.set_reverb_send).
+    fn reverb_send(&self) -> f32;
+
+    /// Gets whether or not the source is set to repeat.
+    fn repeat(&self) -> bool;
+
+    /// Sets the source to repeat `count` more times after playing once, without a gap, e.g. 2
+    /// to play an alarm 3 times, 0 to play once, or `u32::MAX` to repeat for ever.
+    ///
+    /// With a [loop region](#tymethod.set_loop_region), the intro plays once and the loop
+    /// `count` more times after the first. The count applies from the next time around to the
+    /// repeating sound playing, so it ends at the end of the loop once it went around as many
+    /// times, rather than playing the rest of the sound. [`on_finished()`](#tymethod.on_finished)
+    /// tells when the last time around ends.
+    fn set_repeat_count(&mut self, count: u32);
+
+    /// Gets how many more times the source repeats after playing once, see
+    /// [`set_repeat_count()`](#tymethod.set_repeat_count).
+    fn repeat_count(&self) -> u32;
+
+    /// Gets how many times the repeating sound playing went around so far, 0 the first time
+    /// through, which [`elapsed()`](#tymethod.elapsed) is measured within.
+    fn iteration(&self) -> u32;
+
+    /// Makes the source repeat from `start` to `end`, or the end of the sound, on next
+    /// [`play()`](#method.play), after playing from the beginning up to `end` once, e.g. for music
+    /// with an intro. This also sets it to repeat, and [`set_repeat(false)`](#tymethod.set_repeat)
+    /// plays the sound once again, ignoring the loop.
+    ///
+    /// The loop is sample-accurate, without a gap at the seam, as it's kept in memory after the
+    /// first time around. Ogg Vorbis files with `LOOPSTART` and `LOOPEND` or `LOOPLENGTH` comments,
+    /// in samples, get their loop from those on their own.
+    ///
+    /// Returns an error if the loop ends before it starts, or after the end of the sound.
+    fn set_loop_region(&mut self, start: time::Duration, end: Option<time::Duration>)
+        -> GameResult;
+
+    /// Removes the loop, see [`set_loop_region()`](#tymethod.set_loop_region), so the source
+    /// repeats from the beginning to the end of the sound, if it repeats.
+    fn clear_loop_region(&mut self);
+
+    /// Gets where the source loops, see [`set_loop_region()`](#tymethod.set_loop_region).
+    fn loop_region(&self) -> Option<(time::Duration, Option<time::Duration>)>;
+
+    /// Pauses playback, keeping the position to [`resume()`](#tymethod.resume) from.
+    ///
+    /// Does nothing if the source is already paused or stopped. [`play()`](#method.play) still
+    /// restarts a paused source from the beginning, while [`play_later()`](#tymethod.play_later)
+    /// queues the sound after the paused one.
+    fn pause(&self);
+
+    /// Resumes playback from where it was paused.
+    ///
+    /// Does nothing if the source isn't paused.
+    fn resume(&self);
+
+    /// Stops playback, resetting the position to the beginning, unlike [`pause()`](#tymethod.pause).
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult;
+
+    /// Returns a token whose [`finished()`](FinishedToken::finished) is `true` once a sound of the
+    /// source played to its end on the audio thread, e.g. to start the music after a stinger.
+    ///
+    /// Stopping a sound, including with a fade, doesn't count, nor does a repeating sound going
+    /// around its loop, see [`on_loop()`](#tymethod.on_loop) for that. See
+    /// [`AudioContext::notify_finished`] to get an event instead.
+    fn on_finished(&self) -> FinishedToken;
+
+    /// Returns a token whose [`finished()`](FinishedToken::finished) is `true` once every time a
+    /// repeating sound of the source goes around its loop.
+    fn on_loop(&self) -> FinishedToken;
+
+    /// Returns whether or not the source is stopped
+    /// -- that is, has no more data to play.
+    ///
+    /// A paused source isn't stopped.
+    fn stopped(&self) -> bool;
+
+    /// Gets the current volume.
+    fn volume(&self) -> f32;
+
+    /// Sets the current volume.
+    fn set_volume(&mut self, value: f32);
+
+    /// Makes the source play into the bus named `name`, see [`AudioContext::create_bus`],
+    /// right away for the sounds playing, so its volume is multiplied by that of the bus.
+    ///
+    /// Returns an error if there's no such bus.
+    fn set_bus(&mut self, name: &str) -> GameResult;
+
+    /// Makes the source play into no bus, at its own volume times the master volume.
+    fn clear_bus(&mut self);
+
+    /// Gets the name of the bus the source plays into, see [`set_bus()`](#tymethod.set_bus).
+    fn bus(&self) -> Option<&str>;
+
+    /// Fades the sound playing from its current fade volume to `volume` over `over`,
+    /// then does `then`, e.g. to stop it once it's inaudible.
+    ///
+    /// The fade runs on the audio thread, so it's smooth regardless of the frame rate, and
+    /// starting another fade cancels this one, continuing from where it got to. The fade volume
+    /// multiplies the one of [`set_volume()`](#tymethod.set_volume) instead of changing it.
+    /// A stopped source doesn't fade, see [`set_fade_in()`](#tymethod.set_fade_in) to fade in
+    /// the next time it plays.
+    fn fade_to(&mut self, volume: f32, over: time::Duration, then: AfterFade);
+
+    /// Fades the sound playing in from silence to the full volume over `over`.
+    fn fade_in(&mut self, over: time::Duration);
+
+    /// Fades the sound playing out to silence over `over`, then does `then`.
+    fn fade_out(&mut self, over: time::Duration, then: AfterFade) {
+        self.fade_to(0.0, over, then);
+    }
+
+    /// Get whether or not the source is paused, see [`pause()`](#tymethod.pause).
+    fn paused(&self) -> bool;
+
+    /// Get whether or not the source is playing (ie, not paused
+    /// and not stopped).
+    fn playing(&self) -> bool;
+
+    /// Get the time the source has been playing since the last call to [`play()`](#method.play).
+    ///
+    /// Time measurement is based on audio samples consumed, so it's accurate to within the
+    /// buffer of the audio device, but may drift from the system clock over longer periods of time.
+    /// It's measured in the time of the sound, so with [`set_pitch(2.0)`](#tymethod.set_pitch)
+    /// it goes twice as fast as the clock. Pausing stops it, and stopping resets it, as does
+    /// going around the loop of a repeating source, from its start, see
+    /// [`iteration()`](#tymethod.iteration).
+    fn elapsed(&self) -> time::Duration;
+
+    /// Makes playback continue from where [`elapsed()`](#tymethod.elapsed) would be `to`,
+    /// measured from the [`set_start()`](#tymethod.set_start) point, or from the start of the loop
+    /// once a repeating source went around it.
+    ///
+    /// The sound briefly fades from the old to the new position, to not click while playing.
+    /// Seeking past the end of the sound stops it, while a repeating source lands inside the loop,
+    /// as if it had played for that long. A stopped source doesn't seek, as
+    /// [`play()`](#method.play) plays from the start.
+    ///
+    /// Playback after the new position is decoded again, up to it, on the calling thread.
+    fn seek(&mut self, to: time::Duration) -> GameResult;
+
+    /// Returns how long the sound plays once, without repeating or changing the pitch,
+    /// or `None` if it can't be told.
+    ///
+    /// This may decode the sound the first time, reading all of a streamed file.
+    fn duration(&self) -> Option<time::Duration>;
+
+    /// Set the update interval of the internal sample counter.
+    ///
+    /// This doesn't have an effect anymore, [`elapsed()`](#tymethod.elapsed) counts every sample.
+    fn set_query_interval(&mut self, t: time::Duration);
+}
+
+/// Crossfades from one source to another over `over`, e.g. to change the music, stopping `from`
+/// once it's silent.
+///
+/// `to` plays from the beginning if it's stopped, and fades to the volume set with
+/// [`set_volume()`](SoundSource::set_volume). Crossfading back before it's done fades both from
+/// where they got to, rather than jumping. The fades are linear, so the loudness dips slightly
+/// in the middle.
+pub fn crossfade(
+    audio: &impl Has<AudioContext>,
+    from: &mut impl SoundSource,
+    to: &mut impl SoundSource,
+    over: time::Duration,
+) -> GameResult {
+    from.fade_out(over, AfterFade::Stop);
+    if to.stopped() {
+        to.play(audio)?;
+        to.fade_in(over);
+    } else {
+        to.resume();
+        to.fade_to(1.0, over, AfterFade::Continue);
+    }
+    Ok(())
+}
+
+/// Internal state used by audio sources.
+#[derive(Debug)]
+pub(crate) struct SourceState {
+    data: SourceData,
+    repeat_count: u32,
+    fade_in: time::Duration,
+    skip_duration: time::Duration,
+    speed: f32,
+    semitones: f32,
+    pan: f32,
+    lowpass: Option<f32>,
+    highpass: Option<f32>,
+    reverb_send: f32,
+    loop_region: Option<(time::Duration, Option<time::Duration>)>,
+    mixer: Arc<Mutex<Mixer>>,
+    reverb: Arc<ReverbBus>,
+    bus: Option<String>,
+    // shared with the sounds playing, replaced when stopping
+    controls: Arc<Controls>,
+    events: Arc<Events>,
+    queue: Arc<Queue>,
+    duration: OnceLock<Option<time::Duration>>,
+}
+
+impl SourceState {
+    /// Create a new `SourceState` based around the given data
+    pub fn new(data: SourceData, mixer: Arc<Mutex<Mixer>>, reverb: Arc<ReverbBus>) -> Self {
+        let loop_region = ogg_loop_region(&data);
+        let state = SourceState {
+            data,
+            repeat_count: if loop_region.is_some() { u32::MAX } else { 0 },
+            loop_region,
+            fade_in: time::Duration::from_millis(0),
+            skip_duration: time::Duration::from_millis(0),
+            speed: 1.0,
+            semitones: 0.0,
+            pan: 0.0,
+            lowpass: None,
+            highpass: None,
+            reverb_send: 0.0,
+            mixer,
+            reverb,
+            bus: None,
+            controls: Arc::new(Controls::default()),
+            events: Arc::default(),
+            queue: Arc::default(),
+            duration: OnceLock::new(),
+        };
+        state.controls.set_bus_output(state.bus_output());
+        state.controls.set_repeats(state.repeat_count);
+        state
+    }
+    /// Sets the source to repeat playback infinitely on next [`play()`](#method.play)
+    pub fn set_repeat(&mut self, repeat: bool) {
+        self.set_repeat_count(if repeat { u32::MAX } else { 0 });
+    }
+
+    /// Sets how many more times the sounds repeat, see [`SoundSource::set_repeat_count`].
+    pub fn set_repeat_count(&mut self, count: u32) {
+        self.repeat_count = count;
+        self.controls.set_repeats(count);
+    }
+
+    /// Gets how many more times the sounds repeat.
+    pub fn repeat_count(&self) -> u32 {
+        self.repeat_count
+    }
+
+    /// Gets how many times the sound playing went around its loop.
+    pub fn iteration(&self) -> u32 {
+        self.controls.iteration()
+    }
+
+    /// Sets the fade-in time of the source.
+    pub fn set_fade_in(&mut self, dur: time::Duration) {
+        self.fade_in = dur;
+    }
+
+    pub fn set_start(&mut self, dur: time::Duration) {
+        self.skip_duration = dur;
+    }
+
+    /// Sets the pitch ratio (by adjusting the playback speed).
+    pub fn set_pitch(&mut self, ratio: f32) {
+        self.speed = if ratio.is_nan() {
+            1.0
+        } else {
+            ratio.clamp(1.0 / 16.0, 16.0)
+        };
+    }
+
+    /// Shifts the pitch without changing the speed, see [`SoundSource::set_pitch_semitones`].
+    pub fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.semitones = if semitones.is_nan() {
+            0.0
+        } else {
+            semitones.clamp(-24.0, 24.0)
+        };
+        self.controls.set_pitch_ratio(self.pitch_ratio());
+    }
+
+    /// Pans the sounds, see [`SoundSource::set_pan`].
+    pub fn set_pan(&mut self, pan: f32) {
+        self.pan = if pan.is_nan() {
+            0.0
+        } else {
+            pan.clamp(-1.0, 1.0)
+        };
+        self.controls.set_pan(self.pan);
+    }
+
+    /// Gets where the sounds are panned to.
+    pub fn pan(&self) -> f32 {
+        self.pan
+    }
+
+    /// Filters the sounds, see [`SoundSource::set_lowpass`].
+    pub fn set_lowpass(&mut self, cutoff_hz: Option<f32>) {
+        self.lowpass = cutoff_hz.filter(|&cutoff| cutoff > 0.0);
+        self.controls.set_filters(self.lowpass, self.highpass);
+    }
+
+    /// Gets the cutoff of the low-pass filter.
+    pub fn lowpass(&self) -> Option<f32> {
+        self.lowpass
+    }
+
+    /// Filters the sounds, see [`SoundSource::set_highpass`].
+    pub fn set_highpass(&mut self, cutoff_hz: Option<f32>) {
+        self.highpass = cutoff_hz.filter(|&cutoff| cutoff > 0.0);
+        self.controls.set_filters(self.lowpass, self.highpass);
+    }
+
+    /// Gets the cutoff of the high-pass filter.
+    pub fn highpass(&self) -> Option<f32> {
+        self.highpass
+    }
+
+    /// Sends some of the sounds into the reverb, see [`SoundSource::set_reverb_send`].
+    pub fn set_reverb_send(&mut self, amount: f32) {
+        self.reverb_send = if amount.is_nan() {
+            0.0
+        } else {
+            amount.clamp(0.0, 1.0)
+        };
+        self.controls.set_reverb_send(self.reverb_send);
+    }
+
+    /// Gets how much of the sounds goes into the reverb.
+    pub fn reverb_send(&self) -> f32 {
+        self.reverb_send
+    }
+
+    /// Queues `data` to play right after the sound playing, see [`Source::queue`], or instead of
+    /// the one queued next with `next`.
+    pub fn queue(&self, data: SoundData, next: bool) -> GameResult {
+        use rodio::Source;
+        let data = SourceData::Memory(data);
+        data.check("the given audio data")?;
+        let samples: Samples = Box::new(data.decoder()?.convert_samples());
+        if next {
+            self.queue.replace_next(samples);
+        } else {
+            self.queue.push(samples);
+        }
+        Ok(())
+    }
+
+    /// Gets how many sounds are queued.
+    pub fn queued_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Removes the sounds queued.
+    pub fn clear_queue(&self) {
+        self.queue.clear()
+    }
+
+    /// Tells when the sounds finish, or go around their loop, see [`SoundSource::on_finished`].
+    pub fn on_finished(&self, loops: bool) -> FinishedToken {
+        FinishedToken::new(&self.events, loops)
+    }
+
+    fn pitch_ratio(&self) -> f32 {
+        if self.semitones == 0.0 {
+            1.0
+        } else {
+            2.0f32.powf(self.semitones / 12.0)
+        }
+    }
+
+    /// Gets whether or not the source is set to repeat.
+    pub fn repeat(&self) -> bool {
+        self.repeat_count > 0
+    }
+
+    /// Sets where the source repeats, see [`SoundSource::set_loop_region`].
+    pub fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        if matches!(end, Some(end) if end <= start) {
+            return Err(GameError::AudioError(format!(
+                "The loop ends at {:?}, before it starts at {:?}",
+                end, start
+            )));
+        }
+        if let Some(duration) = self.duration() {
+            if start >= duration || matches!(end, Some(end) if end > duration) {
+                return Err(GameError::AudioError(format!(
+                    "The loop from {:?} to {:?} is beyond the end of the sound at {:?}",
+                    start, end, duration
+                )));
+            }
+        }
+        self.loop_region = Some((start, end));
+        if self.repeat_count == 0 {
+            self.set_repeat_count(u32::MAX);
+        }
+        Ok(())
+    }
+
+    /// Removes the loop region.
+    pub fn clear_loop_region(&mut self) {
+        self.loop_region = None;
+    }
+
+    /// Gets where the source repeats.
+    pub fn loop_region(&self) -> Option<(time::Duration, Option<time::Duration>)> {
+        self.loop_region
+    }
+
+    /// Get the time the source has been playing since the last call to [`play()`](#method.play).
+    pub fn elapsed(&self) -> time::Duration {
+        self.controls.elapsed()
+    }
+
+    /// Makes the source play into a bus, see [`SoundSource::set_bus`].
+    pub fn set_bus(&mut self, name: &str) -> GameResult {
+        let output = self.mixer().output(Some(name))?;
+        self.controls.set_bus_output(output);
+        self.bus = Some(name.to_owned());
+        Ok(())
+    }
+
+    /// Makes the source play into no bus.
+    pub fn clear_bus(&mut self) {
+        self.bus = None;
+        self.controls.set_bus_output(self.bus_output());
+    }
+
+    /// Gets the name of the bus the source plays into.
+    pub fn bus(&self) -> Option<&str> {
+        self.bus.as_deref()
+    }
+
+    fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        self.mixer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The volume of the bus, which is never removed.
+    fn bus_output(&self) -> Arc<BusOutput> {
+        self.mixer().output(self.bus.as_deref()).unwrap_or_default()
+    }
+
+    /// Decodes the samples from `position` in the sound on, which lands inside the loop
+    /// when repeating, having gone around it `iteration` times.
+    fn decode(&self, position: time::Duration, iteration: u32) -> GameResult<Samples> {
+        use rodio::Source;
+        if let SourceData::Generated(generator) = &self.data {
+            // which goes on from where it is, wherever it's meant to be
+            return Ok(Box::new(generator.samples()));
+        }
+        let decoder = self.data.decoder()?.convert_samples();
+        let samples: Samples = if self.repeat() {
+            let (start, end) = self.loop_region.unwrap_or_default();
+            if self.data.is_streamed() {
+                // rather than keeping the whole loop in memory, seek to its start again
+                let data = self.data.clone();
+                let reopen: Reopen = Arc::new(move || {
+                    let decoder = data.decoder().ok()?.convert_samples();
+                    Some(Box::new(decoder.skip_duration(start)))
+                });
+                let looped = Looped::reopened(Box::new(decoder), position, start, end, reopen);
+                Box::new(
+                    looped
+                        .with_events(self.events.clone())
+                        .with_controls(self.controls.clone(), iteration),
+                )
+            } else {
+                let looped = Looped::new(Box::new(decoder), position, start, end);
+                Box::new(
+                    looped
+                        .with_events(self.events.clone())
+                        .with_controls(self.controls.clone(), iteration),
+                )
+            }
+        } else {
+            Box::new(decoder.skip_duration(position))
+        };
+        Ok(samples)
+    }
+
+    /// Creates the sound to append to a sink.
+    fn playback(&self) -> GameResult<impl rodio::Source<Item = f32> + Send> {
+        use rodio::Source;
+        let samples = self.decode(self.skip_duration, 0)?;
+        let playback = Filter::new(
+            Playback::new(samples, self.controls.clone())
+                .with_events(self.events.clone())
+                .with_queue(self.queue.clone()),
+            self.controls.clone(),
+        );
+        Ok(PitchShift::new(playback, self.controls.clone())
+            .speed(self.speed)
+            .fade_in(self.fade_in))
+    }
+
+    /// Converts the sound to the format of the output and sends it into the reverb, last of all.
+    fn output<S>(&self, sound: S) -> impl rodio::Source<Item = f32> + Send
+    where
+        S: rodio::Source<Item = f32> + Send,
+    {
+        let sound = rodio::source::UniformSourceIterator::new(
+            sound,
+            self.reverb.channels(),
+            self.reverb.sample_rate(),
+        );
+        ReverbSend::new(sound, self.reverb.clone(), self.controls.clone())
+    }
+
+    /// Makes the sound playing continue from `to`, see [`SoundSource::seek`].
+    pub fn seek(&self, to: time::Duration) -> GameResult {
+        let iteration = self.controls.iteration();
+        let from = match self.loop_region {
+            _ if iteration == 0 || !self.repeat() => self.skip_duration,
+            Some((start, _)) => start,
+            None => time::Duration::ZERO,
+        };
+        self.controls.seek(to, self.decode(from + to, iteration)?);
+        Ok(())
+    }
+
+    /// Starts a fade of the sound playing, `over` the clock rather than the time of the sound.
+    fn fade(&self, from: Option<f32>, to: f32, over: time::Duration, then: AfterFade) {
+        self.controls.fade(Fade {
+            from,
+            to,
+            over: over.mul_f32(self.speed.max(0.0)),
+            then,
+        });
+    }
+
+    /// Returns how long the sound plays once, see [`SoundSource::duration`].
+    pub fn duration(&self) -> Option<time::Duration> {
+        *self.duration.get_or_init(|| {
+            use rodio::Source;
+            let decoder = self.data.decoder().ok()?;
+            if let Some(duration) = decoder.total_duration() {
+                return Some(duration);
+            }
+            let frame_rate = decoder.channels() as u128 * decoder.sample_rate() as u128;
+            let samples = decoder.count() as u128;
+            let nanos = samples * 1_000_000_000 / frame_rate.max(1);
+            Some(time::Duration::from_nanos(nanos as u64))
+        })
+    }
+
+    /// Separates the sounds played so far from the ones played next, which start at 0.
+    fn reset(&mut self) {
+        self.controls = Arc::new(Controls::default());
+        self.controls.set_pitch_ratio(self.pitch_ratio());
+        self.controls.set_pan(self.pan);
+        self.controls.set_filters(self.lowpass, self.highpass);
+        self.controls.set_reverb_send(self.reverb_send);
+        self.controls.set_bus_output(self.bus_output());
+        self.controls.set_repeats(self.repeat_count);
+    }
+}
+
+/// Returns the loop of an Ogg Vorbis file from its `LOOPSTART`, and `LOOPEND` or `LOOPLENGTH`
+/// comments, as used by RPG Maker.
+fn ogg_loop_region(data: &SourceData) -> Option<(time::Duration, Option<time::Duration>)> {
+    let mut magic = [0; 4];
+    data.reader().read_exact(&mut magic).ok()?;
+    if &magic != b"OggS" {
+        return None;
+    }
+    let reader = lewton::inside_ogg::OggStreamReader::new(data.reader()).ok()?;
+    loop_region_from_tags(
+        &reader.comment_hdr.comment_list,
+        reader.ident_hdr.audio_sample_rate,
+    )
+}
+
+fn loop_region_from_tags(
+    tags: &[(String, String)],
+    sample_rate: u32,
+) -> Option<(time::Duration, Option<time::Duration>)> {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+    };
+    let start = tag("LOOPSTART")?;
+    let end = tag("LOOPEND").or_else(|| Some(start + tag("LOOPLENGTH")?));
+    let to_duration = |samples: u64| {
+        let nanos = samples as u128 * 1_000_000_000 / sample_rate.max(1) as u128;
+        time::Duration::from_nanos(nanos as u64)
+    };
+    Some((
+        to_duration(start),
+        end.filter(|&end| end > start).map(to_duration),
+    ))
+}
+
+/// A source of audio data that is connected to an output
+/// channel and ready to play.  It will stop playing when
+/// dropped.
+// TODO LATER: Check and see if this matches Love2d's semantics!
+// It's an in-memory SoundData structure, unless it's streamed from its file.
+pub struct Source {
+    sink: rodio::Sink,
+    state: SourceState,
+}
+
+impl Source {
+    /// Create a new `Source` from the given file.
+    pub fn new<P: AsRef<path::Path>>(ctxs: &impl Has<AudioContext>, path: P) -> GameResult<Self> {
+        let audio = ctxs.retrieve();
+        let path = path.as_ref();
+        let data = SoundData::new(&audio.fs, path)?;
+        Source::from_data(audio, data)
+    }
+
+    /// Creates a new `Source` using the given `SoundData` object.
+    pub fn from_data(audio: &impl Has<AudioContext>, data: SoundData) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        let data = SourceData::Memory(data);
+        data.check("the given audio data")?;
+        let sink = audio.output.sink();
+        Ok(Source {
+            sink,
+            state: SourceState::new(data, audio.mixer.clone(), audio.reverb.clone()),
+        })
+    }
+
+    /// Creates a new `Source` which streams the file at the given path, decoding it bit by bit
+    /// as it plays instead of loading it all at once. This is meant for long music, which would
+    /// take a while to load and a lot of memory to keep.
+    ///
+    /// The file stays open for as long as the source, or any sound it plays, is around, and is
+    /// read and decoded on the audio thread. Seeking and [`set_start()`](#method.set_start)
+    /// still decode up to the new position on the calling thread, and a repeating source decodes
+    /// its loop again every time around rather than keeping it.
+    ///
+    /// Files in a mounted zip archive can't be read from while playing, so their compressed bytes
+    /// are read into memory when opened, though they're still decoded as they play.
+    pub fn from_path_streamed<P: AsRef<path::Path>>(
+        ctxs: &impl Has<AudioContext>,
+        path: P,
+    ) -> GameResult<Self> {
+        let audio = ctxs.retrieve();
+        let file = audio.fs.open(path.as_ref())?;
+        let data = SourceData::Streamed(StreamedFile::new(file));
+        data.check(&format!("the audio file {:?}", path.as_ref()))?;
+        let sink = audio.output.sink();
+        Ok(Source {
+            sink,
+            state: SourceState::new(data, audio.mixer.clone(), audio.reverb.clone()),
+        })
+    }
+
+    /// Creates a new `Source` which plays the samples `generator` makes up as it plays, without
+    /// end, e.g. for the hum of an engine changing with its speed, at `sample_rate` with
+    /// `channels` interleaved.
+    ///
+    /// The generator is called on the audio thread with a buffer of 512 frames, set to silence,
+    /// to fill with samples from -1 to 1, every time the sound played all of the last one.
+    /// Taking too long skips the sound, so it should neither allocate, nor lock what the game
+    /// thread may hold, nor do anything else which could block; sharing its settings in atomics
+    /// keeps it from waiting on the game. The sounds the source plays share the generator, each
+    /// taking turns to call it. Volume, panning, filters, buses and the rest apply as for any
+    /// other source, apart from seeking and looping, there being nowhere to seek or loop to.
+    ///
+    /// Returns an error if `sample_rate` or `channels` is 0.
+    pub fn from_generator(
+        audio: &impl Has<AudioContext>,
+        sample_rate: u32,
+        channels: u16,
+        generator: impl FnMut(&mut [f32]) + Send + 'static,
+    ) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        if sample_rate == 0 || channels == 0 {
+            return Err(GameError::AudioError(format!(
+                "Can't generate a sound of {} channels at {}Hz",
+                channels, sample_rate
+            )));
+        }
+        let data =
+            SourceData::Generated(Generator::new(channels, sample_rate, Box::new(generator)));
+        let sink = audio.output.sink();
+        Ok(Source {
+            sink,
+            state: SourceState::new(data, audio.mixer.clone(), audio.reverb.clone()),
+        })
+    }
+
+    /// Queues `next` to play right after the sound playing ends, or the one queued before,
+    /// starting on the very next sample, e.g. for the parts of music, or the tracks of an album.
+    ///
+    /// The sounds queued are converted to the channels and sample rate of the one playing, and
+    /// [`on_finished()`](SoundSource::on_finished) tells when each one ends. They play with the
+    /// settings of the source, and [`elapsed()`](SoundSource::elapsed) starts over for each, but
+    /// seeking plays the sound of the source itself again. They're played once, by the sound of
+    /// the source which finishes next, so a sound queued while the source is stopped plays after
+    /// the next [`play()`](SoundSource::play) is done, and a repeating sound never gets to them.
+    ///
+    /// Returns an error if `next` can't be decoded.
+    pub fn queue(&mut self, next: SoundData) -> GameResult {
+        self.state.queue(next, false)
+    }
+
+    /// Plays `next` after the sound playing instead of the sound queued next, if any, as long as
+    /// that hasn't started yet, e.g. to branch music, see [`queue()`](#method.queue).
+    pub fn replace_next(&mut self, next: SoundData) -> GameResult {
+        self.state.queue(next, true)
+    }
+
+    /// Gets how many sounds are waiting in the [queue](#method.queue).
+    pub fn queued_len(&self) -> usize {
+        self.state.queued_len()
+    }
+
+    /// Removes the sounds waiting in the [queue](#method.queue), so they don't play.
+    pub fn clear_queue(&mut self) {
+        self.state.clear_queue()
+    }
+}
+
+impl SoundSource for Source {
+    fn play_later(&self) -> GameResult {
+        // Creating a new Decoder each time seems a little messy,
+        // since it may do checking and data-type detection that is
+        // redundant, but it's not super expensive.
+        // See https://github.com/ggez/ggez/issues/98 for discussion
+        if self.stopped() {
+            self.state.controls.restart();
+        }
+        let playback = self.state.playback()?;
+        let panned = Pan::new(playback, self.state.controls.clone());
+        self.sink.append(self.state.output(panned));
+        Ok(())
+    }
+
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()?;
+
+        let new_sink = audio.output.sink();
+        let old_sink = mem::replace(&mut self.sink, new_sink);
+        old_sink.detach();
+        self.state.reset();
+
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+    fn set_start(&mut self, dur: time::Duration) {
+        self.state.set_start(dur)
+    }
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+    fn set_speed(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+    fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.state.set_pitch_semitones(semitones)
+    }
+    fn set_lowpass(&mut self, cutoff_hz: Option<f32>) {
+        self.state.set_lowpass(cutoff_hz)
+    }
+    fn lowpass(&self) -> Option<f32> {
+        self.state.lowpass()
+    }
+    fn set_highpass(&mut self, cutoff_hz: Option<f32>) {
+        self.state.set_highpass(cutoff_hz)
+    }
+    fn highpass(&self) -> Option<f32> {
+        self.state.highpass()
+    }
+    fn set_pan(&mut self, pan: f32) {
+        self.state.set_pan(pan)
+    }
+    fn pan(&self) -> f32 {
+        self.state.pan()
+    }
+    fn set_reverb_send(&mut self, amount: f32) {
+        self.state.set_reverb_send(amount)
+    }
+    fn reverb_send(&self) -> f32 {
+        self.state.reverb_send()
+    }
+    fn on_finished(&self) -> FinishedToken {
+        self.state.on_finished(false)
+    }
+    fn on_loop(&self) -> FinishedToken {
+        self.state.on_finished(true)
+    }
+    fn repeat(&self) -> bool {
+        self.state.repeat()
+    }
+
+    fn set_repeat_count(&mut self, count: u32) {
+        self.state.set_repeat_count(count)
+    }
+
+    fn repeat_count(&self) -> u32 {
+        self.state.repeat_count()
+    }
+
+    fn iteration(&self) -> u32 {
+        self.state.iteration()
+    }
+
+    fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        self.state.set_loop_region(start, end)
+    }
+
+    fn clear_loop_region(&mut self) {
+        self.state.clear_loop_region()
+    }
+
+    fn loop_region(&self) -> Option<(time::Duration, Option<time::Duration>)> {
+        self.state.loop_region()
+    }
+    fn pause(&self) {
+        // pausing an empty sink would pause the next sound played
+        if !self.stopped() {
+            self.sink.pause()
+        }
+    }
+    fn resume(&self) {
+        self.sink.play();
+        self.state.controls.resume();
+    }
+
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        // Sinks cannot be reused after calling `.stop()`. See
+        // https://github.com/tomaka/rodio/issues/171 for information.
+        // To stop the current sound we have to drop the old sink and
+        // create a new one in its place, playing into the output of
+        // the context, so it's on whichever device the rest of the
+        // sound is.
+
+        // We also need to carry over information from the previous sink.
+        let volume = self.volume();
+
+        self.sink = audio.output.sink();
+        self.state.reset();
+
+        // Restore information from the previous link.
+        self.set_volume(volume);
+        Ok(())
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value);
+        self.state.controls.set_volume(value);
+    }
+
+    fn set_bus(&mut self, name: &str) -> GameResult {
+        self.state.set_bus(name)
+    }
+
+    fn clear_bus(&mut self) {
+        self.state.clear_bus()
+    }
+
+    fn bus(&self) -> Option<&str> {
+        self.state.bus()
+    }
+
+    fn fade_to(&mut self, volume: f32, over: time::Duration, then: AfterFade) {
+        if !self.stopped() {
+            self.state.fade(None, volume, over, then);
+        }
+    }
+
+    fn fade_in(&mut self, over: time::Duration) {
+        if !self.stopped() {
+            self.state.fade(Some(0.0), 1.0, over, AfterFade::Continue);
+        }
+    }
+
+    fn paused(&self) -> bool {
+        (self.sink.is_paused() || self.state.controls.paused()) && !self.stopped()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.state.elapsed()
+    }
+
+    fn seek(&mut self, to: time::Duration) -> GameResult {
+        if self.stopped() {
+            return Ok(());
+        }
+        self.state.seek(to)
+    }
+
+    fn duration(&self) -> Option<time::Duration> {
+        self.state.duration()
+    }
+
+    fn set_query_interval(&mut self, _t: time::Duration) {}
+}
+
+impl fmt::Debug for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Audio source: {:p}>", self)
+    }
+}
+
+/// A source of audio data located in space relative to a listener's ears.
+/// Will stop playing when dropped.
+pub struct SpatialSource {
+    sink: rodio::Sink,
+    state: SourceState,
+    placement: Placement,
+    listener: Arc<Shared<Listener>>,
+}
+
+impl SpatialSource {
+    /// Create a new `SpatialSource` from the given file.
+    pub fn new<P: AsRef<path::Path>>(
+        fs: &impl Has<Filesystem>,
+        audio: &impl Has<AudioContext>,
+        path: P,
+    ) -> GameResult<Self> {
+        let path = path.as_ref();
+        let data = SoundData::new(fs, path)?;
+        SpatialSource::from_data(audio, data)
+    }
+
+    /// Creates a new `SpatialSource` using the given `SoundData` object.
+    pub fn from_data(audio: &impl Has<AudioContext>, data: SoundData) -> GameResult<Self> {
+        let audio = audio.retrieve();
+        let data = SourceData::Memory(data);
+        data.check("the given audio data")?;
+        let sink = audio.output.sink();
+        let source = SpatialSource {
+            sink,
+            state: SourceState::new(data, audio.mixer.clone(), audio.reverb.clone()),
+            placement: Placement {
+                attenuation: audio.default_attenuation,
+                ..Placement::default()
+            },
+            listener: audio.shared_listener.clone(),
+        };
+        source.place();
+        Ok(source)
+    }
+
+    // Moves the sound playing to the placement.
+    fn place(&self) {
+        self.state.controls.placement().set(self.placement);
+    }
+}
+
+impl SoundSource for SpatialSource {
+    /// Plays the `SpatialSource`; waits until done if the sound is currently playing.
+    fn play_later(&self) -> GameResult {
+        // Creating a new Decoder each time seems a little messy,
+        // since it may do checking and data-type detection that is
+        // redundant, but it's not super expensive.
+        // See https://github.com/ggez/ggez/issues/98 for discussion
+        if self.stopped() {
+            self.state.controls.restart();
+        }
+        let controls = &self.state.controls;
+        let playback = Doppler::new(
+            self.state.playback()?,
+            controls.clone(),
+            self.listener.clone(),
+        );
+        let spatialized = Spatialize::new(playback, controls.clone());
+        self.sink.append(self.state.output(spatialized));
+        Ok(())
+    }
+
+    fn play_detached(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        self.stop(audio)?;
+        self.play_later()?;
+
+        let new_sink = audio.output.sink();
+        let old_sink = mem::replace(&mut self.sink, new_sink);
+        old_sink.detach();
+        self.state.reset();
+        self.place();
+
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, repeat: bool) {
+        self.state.set_repeat(repeat)
+    }
+
+    fn set_fade_in(&mut self, dur: time::Duration) {
+        self.state.set_fade_in(dur)
+    }
+
+    fn set_start(&mut self, dur: time::Duration) {
+        self.state.set_start(dur)
+    }
+
+    fn set_pitch(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+
+    fn set_speed(&mut self, ratio: f32) {
+        self.state.set_pitch(ratio)
+    }
+
+    fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.state.set_pitch_semitones(semitones)
+    }
+    fn set_lowpass(&mut self, cutoff_hz: Option<f32>) {
+        self.state.set_lowpass(cutoff_hz)
+    }
+    fn lowpass(&self) -> Option<f32> {
+        self.state.lowpass()
+    }
+    fn set_highpass(&mut self, cutoff_hz: Option<f32>) {
+        self.state.set_highpass(cutoff_hz)
+    }
+    fn highpass(&self) -> Option<f32> {
+        self.state.highpass()
+    }
+    /// Does nothing, as the positions pan the sound.
+    fn set_pan(&mut self, _pan: f32) {}
+    fn pan(&self) -> f32 {
+        0.0
+    }
+    fn set_reverb_send(&mut self, amount: f32) {
+        self.state.set_reverb_send(amount)
+    }
+    fn reverb_send(&self) -> f32 {
+        self.state.reverb_send()
+    }
+    fn on_finished(&self) -> FinishedToken {
+        self.state.on_finished(false)
+    }
+    fn on_loop(&self) -> FinishedToken {
+        self.state.on_finished(true)
+    }
+
+    fn repeat(&self) -> bool {
+        self.state.repeat()
+    }
+
+    fn set_repeat_count(&mut self, count: u32) {
+        self.state.set_repeat_count(count)
+    }
+
+    fn repeat_count(&self) -> u32 {
+        self.state.repeat_count()
+    }
+
+    fn iteration(&self) -> u32 {
+        self.state.iteration()
+    }
+
+    fn set_loop_region(
+        &mut self,
+        start: time::Duration,
+        end: Option<time::Duration>,
+    ) -> GameResult {
+        self.state.set_loop_region(start, end)
+    }
+
+    fn clear_loop_region(&mut self) {
+        self.state.clear_loop_region()
+    }
+
+    fn loop_region(&self) -> Option<(time::Duration, Option<time::Duration>)> {
+        self.state.loop_region()
+    }
+
+    fn pause(&self) {
+        // pausing an empty sink would pause the next sound played
+        if !self.stopped() {
+            self.sink.pause()
+        }
+    }
+
+    fn resume(&self) {
+        self.sink.play();
+        self.state.controls.resume();
+    }
+
+    fn stop(&mut self, audio: &impl Has<AudioContext>) -> GameResult {
+        let audio = audio.retrieve();
+        // Sinks cannot be reused after calling `.stop()`. See
+        // https://github.com/tomaka/rodio/issues/171 for information.
+        // To stop the current sound we have to drop the old sink and
+        // create a new one in its place, playing into the output of
+        // the context, so it's on whichever device the rest of the
+        // sound is.
+
+        // We also need to carry over information from the previous sink.
+        let volume = self.volume();
+
+        self.sink = audio.output.sink();
+        self.state.reset();
+        self.place();
+
+        // Restore information from the previous link.
+        self.set_volume(volume);
+        Ok(())
+    }
+
+    fn stopped(&self) -> bool {
+        self.sink.empty()
+    }
+
+    fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
+    fn set_volume(&mut self, value: f32) {
+        self.sink.set_volume(value);
+        self.state.controls.set_volume(value);
+    }
+
+    fn set_bus(&mut self, name: &str) -> GameResult {
+        self.state.set_bus(name)
+    }
+
+    fn clear_bus(&mut self) {
+        self.state.clear_bus()
+    }
+
+    fn bus(&self) -> Option<&str> {
+        self.state.bus()
+    }
+
+    fn fade_to(&mut self, volume: f32, over: time::Duration, then: AfterFade) {
+        if !self.stopped() {
+            self.state.fade(None, volume, over, then);
+        }
+    }
+
+    fn fade_in(&mut self, over: time::Duration) {
+        if !self.stopped() {
+            self.state.fade(Some(0.0), 1.0, over, AfterFade::Continue);
+        }
+    }
+
+    fn paused(&self) -> bool {
+        (self.sink.is_paused() || self.state.controls.paused()) && !self.stopped()
+    }
+
+    fn playing(&self) -> bool {
+        !self.paused() && !self.stopped()
+    }
+
+    fn elapsed(&self) -> time::Duration {
+        self.state.elapsed()
+    }
+
+    fn seek(&mut self, to: time::Duration) -> GameResult {
+        if self.stopped() {
+            return Ok(());
+        }
+        self.state.seek(to)
+    }
+
+    fn duration(&self) -> Option<time::Duration> {
+        self.state.duration()
+    }
+
+    fn set_query_interval(&mut self, _t: time::Duration) {}
+}
+
+impl SpatialSource {
+    /// Set location of the sound.
+    pub fn set_position<P>(&mut self, pos: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        self.placement.emitter = pos.into().into();
+        self.place();
+    }
+
+    /// Set locations of the listener's ears
+    pub fn set_ears<P>(&mut self, left: P, right: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        self.placement.left_ear = left.into().into();
+        self.placement.right_ear = right.into().into();
+        self.place();
+    }
+
+    /// Sets the velocity of the sound, in the units of the positions per second, for the
+    /// Doppler effect, see [`AudioContext::set_doppler_factor`], which is off by default.
+    ///
+    /// This doesn't move the sound, so [`set_position()`](#method.set_position) is still up to
+    /// the game every frame.
+    pub fn set_velocity<V>(&mut self, velocity: V)
+    where
+        V: Into<mint::Vector3<f32>>,
+    {
+        self.placement.velocity = velocity.into().into();
+        self.place();
+    }
+
+    /// Gets the velocity of the sound, see [`set_velocity()`](#method.set_velocity).
+    pub fn velocity(&self) -> mint::Vector3<f32> {
+        self.placement.velocity.into()
+    }
+
+    /// Sets how the volume falls off with the distance from each ear, right away while playing,
+    /// starting with the [default](AudioContext::set_default_attenuation) of the context.
+    ///
+    /// The volume of each ear is that of the attenuation, times up to half more for the closer
+    /// ear, so it's panned as well. It moves there over 10ms when the positions change, to not
+    /// click.
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.placement.attenuation = attenuation;
+        self.place();
+    }
+
+    /// Gets how the volume falls off with the distance, see
+    /// [`set_attenuation()`](#method.set_attenuation).
+    pub fn attenuation(&self) -> Attenuation {
+        self.placement.attenuation
+    }
+
+    /// Clamps the distances from the ears to between `min` and `max` before attenuating,
+    /// so the sound doesn't get any louder closer than `min`, or any quieter further than `max`.
+    ///
+    /// They're 0 and infinity at first.
+    pub fn set_min_max_distance(&mut self, min: f32, max: f32) {
+        self.placement.min_distance = min.max(0.0);
+        self.placement.max_distance = max.max(self.placement.min_distance);
+        self.place();
+    }
+
+    /// Gets the distances the ones from the ears are clamped to, see
+    /// [`set_min_max_distance()`](#method.set_min_max_distance).
+    pub fn min_max_distance(&self) -> (f32, f32) {
+        (self.placement.min_distance, self.placement.max_distance)
+    }
+}
+
+impl fmt::Debug for SpatialSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Spatial audio source: {:p}>", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(bytes: &[u8]) -> SourceState {
+        SourceState::new(
+            SourceData::Memory(SoundData::from_bytes(bytes)),
+            Arc::default(),
+            Arc::default(),
+        )
+    }
+
+    #[test]
+    fn duration_and_seeking() {
+        let wav = state(include_bytes!("../../resources/pew.wav"));
+        let ogg = state(include_bytes!("../../resources/pew.ogg"));
+        let duration = wav.duration().unwrap();
+        assert!(duration > time::Duration::ZERO);
+        // the ogg one doesn't know its duration without decoding it
+        let ogg_duration = ogg.duration().unwrap();
+        assert!((duration.as_secs_f32() - ogg_duration.as_secs_f32()).abs() < 0.05);
+        let flac = state(include_bytes!("../../resources/pew.flac"));
+        let flac_duration = flac.duration().unwrap();
+        assert!((duration.as_secs_f32() - flac_duration.as_secs_f32()).abs() < 0.05);
+        assert!(flac.decode(duration / 2, 0).unwrap().count() > 0);
+
+        let all = wav.decode(time::Duration::ZERO, 0).unwrap().count();
+        let half = wav.decode(duration / 2, 0).unwrap().count();
+        assert!((half as f32 / all as f32 - 0.5).abs() < 0.01);
+        assert_eq!(wav.decode(duration * 2, 0).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn streaming() {
+        let path = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/pew.ogg");
+        let file = crate::filesystem::File::VfsFile(Box::new(std::fs::File::open(path).unwrap()));
+        let mut streamed = SourceState::new(
+            SourceData::Streamed(StreamedFile::new(file)),
+            Arc::default(),
+            Arc::default(),
+        );
+        let memory = state(include_bytes!("../../resources/pew.ogg"));
+        assert_eq!(streamed.duration(), memory.duration());
+        let decoded: Vec<_> = streamed.decode(time::Duration::ZERO, 0).unwrap().collect();
+        assert_eq!(
+            decoded,
+            memory
+                .decode(time::Duration::ZERO, 0)
+                .unwrap()
+                .collect::<Vec<_>>()
+        );
+
+        // looping decodes the file again every time around
+        let duration = streamed.duration().unwrap();
+        streamed
+            .set_loop_region(duration / 2, Some(duration * 3 / 4))
+            .unwrap();
+        let looped: Vec<_> = streamed
+            .decode(time::Duration::ZERO, 0)
+            .unwrap()
+            .take(decoded.len() * 2)
+            .collect();
+        assert_eq!(looped.len(), decoded.len() * 2);
+        let seam = looped.len() * 3 / 8;
+        assert_eq!(looped[..seam], decoded[..seam]);
+    }
+
+    #[test]
+    fn loop_regions() {
+        let mut wav = state(include_bytes!("../../resources/pew.wav"));
+        let duration = wav.duration().unwrap();
+        assert!(wav
+            .set_loop_region(duration / 2, Some(duration / 4))
+            .is_err());
+        assert!(wav
+            .set_loop_region(duration / 2, Some(duration * 2))
+            .is_err());
+        assert!(!wav.repeat());
+        wav.set_loop_region(duration / 2, None).unwrap();
+        assert!(wav.repeat());
+        assert!(
+            wav.decode(time::Duration::ZERO, 0)
+                .unwrap()
+                .take(1_000_000)
+                .count()
+                == 1_000_000
+        );
+
+        let tags = |tags: &[(&str, &str)]| {
+            let tags: Vec<_> = tags
+                .iter()
+                .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+                .collect();
+            loop_region_from_tags(&tags, 44100)
+        };
+        let second = time::Duration::from_secs(1);
+        assert_eq!(
+            tags(&[("LOOPSTART", "44100"), ("LOOPEND", "88200")]),
+            Some((second, Some(second * 2)))
+        );
+        assert_eq!(
+            tags(&[("loopstart", "44100"), ("LOOPLENGTH", "44100")]),
+            Some((second, Some(second * 2)))
+        );
+        assert_eq!(
+            tags(&[("LOOPSTART", "0")]),
+            Some((time::Duration::ZERO, None))
+        );
+        assert_eq!(tags(&[("TITLE", "Song")]), None);
+        assert_eq!(
+            state(include_bytes!("../../resources/pew.ogg")).loop_region(),
+            None
+        );
+    }
+
+    #[test]
+    fn from_samples() {
+        let samples: Vec<_> = (0..200).map(|i| (i as f32 / 100.0) - 1.0).collect();
+        let data = SoundData::from_samples(1000, 2, &samples);
+        let state = state(data.as_ref());
+        assert_eq!(state.duration(), Some(time::Duration::from_millis(100)));
+        let decoded: Vec<_> = state.decode(time::Duration::ZERO, 0).unwrap().collect();
+        // through the 16 bits rodio decodes to
+        assert_eq!(decoded.len(), samples.len());
+        assert!(decoded
+            .iter()
+            .zip(&samples)
+            .all(|(decoded, sample)| (decoded - sample).abs() < 1e-4));
+    }
+
+    #[test]
+    fn finished_tokens() {
+        let mut wav = state(include_bytes!("../../resources/pew.wav"));
+        let finished = wav.on_finished(false);
+        let looped = wav.on_finished(true);
+        assert!(!finished.finished());
+        let _ = wav.playback().unwrap().count();
+        let _ = wav.playback().unwrap().count();
+        // once for both sounds, and not again
+        assert!(finished.finished());
+        assert!(!finished.finished());
+        assert!(!looped.finished());
+        // a token made afterwards doesn't see the sounds before
+        assert!(!wav.on_finished(false).finished());
+
+        wav.set_repeat(true);
+        let duration = wav.duration().unwrap();
+        let samples = wav.decode(time::Duration::ZERO, 0).unwrap();
+        let frames = duration.as_secs_f32() * samples.sample_rate() as f32;
+        let channels = samples.channels() as usize;
+        let _ = samples.take((frames * 2.5) as usize * channels).count();
+        assert!(looped.finished());
+        assert!(!finished.finished());
+    }
+}
@@ -0,0 +1,332 @@
+//! Sounds played once, which the context keeps until they finish.
+
+use std::sync::Arc;
+
+use super::{AudioContext, FinishedToken, SoundData, SoundSource, Source, SpatialSource};
+use crate::error::GameResult;
+
+/// A sound played with [`AudioContext::play_once`], to change or stop it while it plays, or a
+/// source given to [`AudioContext::notify_finished`].
+///
+/// It can be dropped whenever, without stopping the sound, and does nothing once the sound
+/// finished or was stopped, including to make way for another, or for a source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SoundHandle(u64);
+
+impl SoundHandle {
+    /// Returns whether the sound is still playing.
+    pub fn playing(self, audio: &AudioContext) -> bool {
+        audio
+            .one_shots
+            .sounds
+            .iter()
+            .any(|shot| shot.id == self.0 && !shot.source.stopped())
+    }
+
+    /// Sets the volume of the sound, see [`SoundSource::set_volume`].
+    pub fn set_volume(self, audio: &mut AudioContext, volume: f32) {
+        if let Some(source) = audio.one_shots.get(self) {
+            source.set_volume(volume);
+        }
+    }
+
+    /// Shifts the pitch of the sound, see [`SoundSource::set_pitch_semitones`].
+    pub fn set_pitch_semitones(self, audio: &mut AudioContext, semitones: f32) {
+        if let Some(source) = audio.one_shots.get(self) {
+            source.set_pitch_semitones(semitones);
+        }
+    }
+
+    /// Pans the sound, see [`SoundSource::set_pan`], which a spatial one ignores.
+    pub fn set_pan(self, audio: &mut AudioContext, pan: f32) {
+        if let Some(source) = audio.one_shots.get(self) {
+            source.set_pan(pan);
+        }
+    }
+
+    /// Moves a sound played with [`AudioContext::play_once_at`], see
+    /// [`SpatialSource::set_position`], which does nothing for any other.
+    pub fn set_position<P>(self, audio: &mut AudioContext, position: P)
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        if let Some(OneShotSource::Spatial(source)) = audio.one_shots.get(self) {
+            source.set_position(position);
+        }
+    }
+
+    /// Stops the sound.
+    pub fn stop(self, audio: &mut AudioContext) -> GameResult {
+        audio.with_one_shots(|one_shots, audio| match one_shots.get(self) {
+            Some(source) => source.stop(audio),
+            None => Ok(()),
+        })
+    }
+}
+
+enum OneShotSource {
+    Plain(Source),
+    Spatial(SpatialSource),
+}
+
+impl OneShotSource {
+    fn stopped(&self) -> bool {
+        match self {
+            OneShotSource::Plain(source) => source.stopped(),
+            OneShotSource::Spatial(source) => source.stopped(),
+        }
+    }
+
+    fn stop(&mut self, audio: &AudioContext) -> GameResult {
+        match self {
+            OneShotSource::Plain(source) => source.stop(audio),
+            OneShotSource::Spatial(source) => source.stop(audio),
+        }
+    }
+
+    fn play(&mut self, audio: &AudioContext) -> GameResult {
+        match self {
+            OneShotSource::Plain(source) => source.play(audio),
+            OneShotSource::Spatial(source) => source.play(audio),
+        }
+    }
+
+    fn on_finished(&self) -> FinishedToken {
+        match self {
+            OneShotSource::Plain(source) => source.on_finished(),
+            OneShotSource::Spatial(source) => source.on_finished(),
+        }
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        match self {
+            OneShotSource::Plain(source) => source.set_volume(volume),
+            OneShotSource::Spatial(source) => source.set_volume(volume),
+        }
+    }
+
+    fn set_pitch(&mut self, ratio: f32) {
+        match self {
+            OneShotSource::Plain(source) => source.set_pitch(ratio),
+            OneShotSource::Spatial(source) => source.set_pitch(ratio),
+        }
+    }
+
+    fn set_pitch_semitones(&mut self, semitones: f32) {
+        match self {
+            OneShotSource::Plain(source) => source.set_pitch_semitones(semitones),
+            OneShotSource::Spatial(source) => source.set_pitch_semitones(semitones),
+        }
+    }
+
+    fn set_pan(&mut self, pan: f32) {
+        if let OneShotSource::Plain(source) = self {
+            source.set_pan(pan);
+        }
+    }
+}
+
+struct OneShot {
+    id: u64,
+    data: SoundData,
+    source: OneShotSource,
+    finished: FinishedToken,
+}
+
+impl OneShot {
+    fn plays(&self, data: &SoundData, spatial: bool) -> bool {
+        Arc::ptr_eq(&self.data.0, &data.0)
+            && matches!(self.source, OneShotSource::Spatial(_)) == spatial
+    }
+}
+
+/// How a sound is played once, see [`AudioContext::play_once_with`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct OneShotSettings {
+    pub volume: f32,
+    pub pitch: f32,
+    pub pan: f32,
+    pub position: Option<mint::Point3<f32>>,
+}
+
+/// The sounds played once, and finished ones kept to play the same sound again, with the
+/// sources to report finishing with an event.
+pub(crate) struct OneShots {
+    // from the one started longest ago to the newest
+    sounds: Vec<OneShot>,
+    notified: Vec<(SoundHandle, FinishedToken)>,
+    // finished but not reported yet, as the source was reused
+    finished: Vec<SoundHandle>,
+    next_id: u64,
+    max_sounds: usize,
+    max_per_sound: usize,
+}
+
+impl Default for OneShots {
+    fn default() -> Self {
+        OneShots {
+            sounds: Vec::new(),
+            notified: Vec::new(),
+            finished: Vec::new(),
+            next_id: 0,
+            max_sounds: 32,
+            max_per_sound: 8,
+        }
+    }
+}
+
+/// Where the next sound goes, out of sounds which play the same one or not, and are playing
+/// or finished, from the oldest.
+#[derive(Debug, PartialEq, Eq)]
+enum Slot {
+    // stopping the one playing there, if it is
+    Take(usize),
+    New,
+}
+
+fn choose_slot(sounds: &[(bool, bool)], max_sounds: usize, max_per_sound: usize) -> Slot {
+    let position = |f: fn(&(bool, bool)) -> bool| sounds.iter().position(f).map(Slot::Take);
+    let count = |f: fn(&&(bool, bool)) -> bool| sounds.iter().filter(f).count();
+    if count(|&&(same, playing)| same && playing) >= max_per_sound {
+        position(|&(same, playing)| same && playing)
+    } else if count(|&&(_, playing)| playing) >= max_sounds {
+        position(|&(_, playing)| playing)
+    } else {
+        position(|&(same, playing)| same && !playing)
+    }
+    .unwrap_or(Slot::New)
+}
+
+impl OneShots {
+    pub fn max_sounds(&self) -> usize {
+        self.max_sounds
+    }
+
+    pub fn set_max_sounds(&mut self, max: usize) {
+        self.max_sounds = max.max(1);
+    }
+
+    pub fn max_per_sound(&self) -> usize {
+        self.max_per_sound
+    }
+
+    pub fn set_max_per_sound(&mut self, max: usize) {
+        self.max_per_sound = max.max(1);
+    }
+
+    fn next_handle(&mut self) -> SoundHandle {
+        self.next_id += 1;
+        SoundHandle(self.next_id)
+    }
+
+    pub fn notify(&mut self, finished: FinishedToken) -> SoundHandle {
+        let handle = self.next_handle();
+        self.notified.push((handle, finished));
+        handle
+    }
+
+    /// Returns the sounds which finished since the last call, forgetting the sources dropped.
+    pub fn take_finished(&mut self) -> Vec<SoundHandle> {
+        let mut finished = std::mem::take(&mut self.finished);
+        finished.extend(
+            self.sounds
+                .iter()
+                .filter(|shot| shot.finished.finished())
+                .map(|shot| SoundHandle(shot.id)),
+        );
+        self.notified.retain(|(handle, token)| {
+            if token.finished() {
+                finished.push(*handle);
+            }
+            !token.orphaned()
+        });
+        finished
+    }
+
+    fn get(&mut self, handle: SoundHandle) -> Option<&mut OneShotSource> {
+        self.sounds
+            .iter_mut()
+            .find(|shot| shot.id == handle.0 && !shot.source.stopped())
+            .map(|shot| &mut shot.source)
+    }
+
+    pub fn play(
+        &mut self,
+        audio: &AudioContext,
+        data: &SoundData,
+        settings: OneShotSettings,
+    ) -> GameResult<SoundHandle> {
+        let spatial = settings.position.is_some();
+        let sounds: Vec<_> = self
+            .sounds
+            .iter()
+            .map(|shot| (shot.plays(data, spatial), !shot.source.stopped()))
+            .collect();
+        let taken = match choose_slot(&sounds, self.max_sounds, self.max_per_sound) {
+            Slot::Take(index) => Some(self.sounds.remove(index)),
+            Slot::New => None,
+        };
+        if let Some(shot) = &taken {
+            if shot.finished.finished() {
+                self.finished.push(SoundHandle(shot.id));
+            }
+        }
+        let mut shot = match taken {
+            Some(shot) if shot.plays(data, spatial) => shot,
+            // what's taken is dropped, stopping it, if it plays another sound
+            _ => {
+                // keeping no more finished sounds around than may play at once
+                while self.sounds.len() >= self.max_sounds {
+                    let oldest = self
+                        .sounds
+                        .iter()
+                        .position(|shot| shot.source.stopped())
+                        .unwrap_or(0);
+                    let _ = self.sounds.remove(oldest);
+                }
+                let source = if spatial {
+                    OneShotSource::Spatial(SpatialSource::from_data(audio, data.clone())?)
+                } else {
+                    OneShotSource::Plain(Source::from_data(audio, data.clone())?)
+                };
+                OneShot {
+                    id: 0,
+                    data: data.clone(),
+                    finished: source.on_finished(),
+                    source,
+                }
+            }
+        };
+        shot.source.set_volume(settings.volume);
+        shot.source.set_pitch(settings.pitch);
+        shot.source.set_pan(settings.pan);
+        if let (OneShotSource::Spatial(source), Some(position)) =
+            (&mut shot.source, settings.position)
+        {
+            source.set_position(position);
+        }
+        shot.source.play(audio)?;
+        let handle = self.next_handle();
+        shot.id = handle.0;
+        shot.finished = shot.source.on_finished();
+        self.sounds.push(shot);
+        Ok(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots() {
+        // (the same sound, playing)
+        let sounds = [(false, true), (true, false), (true, true), (true, true)];
+        assert_eq!(choose_slot(&sounds, 8, 8), Slot::Take(1));
+        assert_eq!(choose_slot(&sounds[2..], 8, 8), Slot::New);
+        // the oldest of the same sound once there are too many of it, or of any
+        assert_eq!(choose_slot(&sounds, 8, 2), Slot::Take(2));
+        assert_eq!(choose_slot(&sounds, 3, 8), Slot::Take(0));
+        assert_eq!(choose_slot(&[(false, true)], 1, 8), Slot::Take(0));
+    }
+}
@@ -0,0 +1,1079 @@
+//! The sound played by a source, as the audio thread pulls its samples, and the controls
+//! the game thread changes it with.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+
+use super::mixer::{cutoff_bits, cutoff_from_bits, BusOutput};
+use super::spatial::Placement;
+use super::AfterFade;
+
+/// Decoded samples, with the same channels and sample rate throughout.
+pub(crate) type Samples = Box<dyn rodio::Source<Item = f32> + Send>;
+
+// How long seeking crossfades from the old position to the new one, so it doesn't click.
+const SEEK_FADE: Duration = Duration::from_millis(5);
+
+/// A change the game thread makes, for the audio thread to pick up.
+struct Pending<T> {
+    pending: AtomicBool,
+    value: Mutex<Option<T>>,
+}
+
+impl<T> Default for Pending<T> {
+    fn default() -> Self {
+        Pending {
+            pending: AtomicBool::new(false),
+            value: Mutex::new(None),
+        }
+    }
+}
+
+impl<T> Pending<T> {
+    fn set(&self, value: T) {
+        *self.value.lock().unwrap_or_else(PoisonError::into_inner) = Some(value);
+        self.pending.store(true, Ordering::Release);
+    }
+
+    fn take(&self) -> Option<T> {
+        if !self.pending.swap(false, Ordering::Acquire) {
+            return None;
+        }
+        // never wait for the game thread on the audio thread, try again with the next frame
+        match self.value.try_lock() {
+            Ok(mut value) => value.take(),
+            Err(_) => {
+                self.pending.store(true, Ordering::Release);
+                None
+            }
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::Relaxed)
+    }
+}
+
+/// A setting the game thread changes, for every sound playing to pick up.
+#[derive(Default)]
+pub(crate) struct Shared<T> {
+    value: Mutex<T>,
+    changes: AtomicU32,
+}
+
+impl<T: Clone> Shared<T> {
+    pub fn set(&self, value: T) {
+        *self.value.lock().unwrap_or_else(PoisonError::into_inner) = value;
+        let _ = self.changes.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns the count of changes to start from, to pick up the current value at first.
+    pub fn unseen(&self) -> u32 {
+        self.changes.load(Ordering::Relaxed).wrapping_sub(1)
+    }
+
+    /// Returns the value if it changed since `seen`, without blocking.
+    pub fn changed(&self, seen: &mut u32) -> Option<T> {
+        let changes = self.changes.load(Ordering::Acquire);
+        if changes == *seen {
+            return None;
+        }
+        let value = self.value.try_lock().ok()?.clone();
+        *seen = changes;
+        Some(value)
+    }
+}
+
+/// A change of the fade volume, see [`SoundSource::fade_to`](super::SoundSource::fade_to).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Fade {
+    /// The volume to jump to first, rather than fading from the current one.
+    pub from: Option<f32>,
+    pub to: f32,
+    /// How long the fade takes, in the time of the sound.
+    pub over: Duration,
+    pub then: AfterFade,
+}
+
+/// What's shared between a source and the sounds it plays.
+pub(crate) struct Controls {
+    // the elapsed time in nanoseconds
+    elapsed: AtomicU64,
+    // the elapsed time to seek to, with the samples from there
+    seek: Pending<(Duration, Samples)>,
+    fade: Pending<Fade>,
+    // the bits of the fade volume, of the factor the pitch is shifted by, and of the pan
+    fade_volume: AtomicU32,
+    pitch_ratio: AtomicU32,
+    pan: AtomicU32,
+    // the cutoffs of the filters, 0 when off
+    lowpass: AtomicU32,
+    highpass: AtomicU32,
+    // the volume of the sink, and how much of the sound goes into the reverb
+    volume: AtomicU32,
+    reverb_send: AtomicU32,
+    // how many more times the sound repeats after playing once, `u32::MAX` for ever, and how
+    // many times it went around
+    repeats: AtomicU32,
+    iteration: AtomicU32,
+    // paused or stopped by a fade
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    // the volume of the bus, and where a spatial sound plays
+    bus_output: Shared<Arc<BusOutput>>,
+    placement: Shared<Placement>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Controls {
+            elapsed: AtomicU64::new(0),
+            seek: Pending::default(),
+            fade: Pending::default(),
+            fade_volume: AtomicU32::new(1.0f32.to_bits()),
+            pitch_ratio: AtomicU32::new(1.0f32.to_bits()),
+            pan: AtomicU32::new(0.0f32.to_bits()),
+            lowpass: AtomicU32::new(cutoff_bits(None)),
+            highpass: AtomicU32::new(cutoff_bits(None)),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            reverb_send: AtomicU32::new(0.0f32.to_bits()),
+            repeats: AtomicU32::new(u32::MAX),
+            iteration: AtomicU32::new(0),
+            paused: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            bus_output: Shared::default(),
+            placement: Shared::default(),
+        }
+    }
+}
+
+impl Controls {
+    /// Returns the time played, as of the last samples pulled by the audio thread.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed.load(Ordering::Relaxed))
+    }
+
+    /// Makes the playing sound continue with `samples`, which start at `elapsed`.
+    pub fn seek(&self, elapsed: Duration, samples: Samples) {
+        self.seek.set((elapsed, samples));
+    }
+
+    /// Starts a fade, replacing the one in progress.
+    pub fn fade(&self, fade: Fade) {
+        self.fade.set(fade);
+    }
+
+    /// Returns the volume the sound is faded to right now.
+    pub fn fade_volume(&self) -> f32 {
+        f32::from_bits(self.fade_volume.load(Ordering::Relaxed))
+    }
+
+    /// Returns the factor the frequencies are shifted by, without changing the tempo.
+    pub fn pitch_ratio(&self) -> f32 {
+        f32::from_bits(self.pitch_ratio.load(Ordering::Relaxed))
+    }
+
+    /// Shifts the frequencies of the sound playing by `ratio`.
+    pub fn set_pitch_ratio(&self, ratio: f32) {
+        self.pitch_ratio.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns where the sound is panned to, from -1 on the left to 1 on the right.
+    pub fn pan(&self) -> f32 {
+        f32::from_bits(self.pan.load(Ordering::Relaxed))
+    }
+
+    /// Pans the sound playing to `pan`.
+    pub fn set_pan(&self, pan: f32) {
+        self.pan.store(pan.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the cutoff of the low-pass filter, if any.
+    pub fn lowpass(&self) -> Option<f32> {
+        cutoff_from_bits(self.lowpass.load(Ordering::Relaxed))
+    }
+
+    /// Returns the cutoff of the high-pass filter, if any.
+    pub fn highpass(&self) -> Option<f32> {
+        cutoff_from_bits(self.highpass.load(Ordering::Relaxed))
+    }
+
+    /// Filters the sound playing.
+    pub fn set_filters(&self, lowpass: Option<f32>, highpass: Option<f32>) {
+        self.lowpass.store(cutoff_bits(lowpass), Ordering::Relaxed);
+        self.highpass
+            .store(cutoff_bits(highpass), Ordering::Relaxed);
+    }
+
+    /// Returns the volume the sound is played at, before the fade and the bus.
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns how much of the sound goes into the reverb, from 0 to 1.
+    pub fn reverb_send(&self) -> f32 {
+        f32::from_bits(self.reverb_send.load(Ordering::Relaxed))
+    }
+
+    pub fn set_reverb_send(&self, send: f32) {
+        self.reverb_send.store(send.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns how many more times the sound repeats after playing once.
+    pub fn repeats(&self) -> u32 {
+        self.repeats.load(Ordering::Relaxed)
+    }
+
+    pub fn set_repeats(&self, repeats: u32) {
+        self.repeats.store(repeats, Ordering::Relaxed);
+    }
+
+    /// Returns how many times the sound went around its loop.
+    pub fn iteration(&self) -> u32 {
+        self.iteration.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether a fade paused the sound.
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Continues a sound paused by a fade.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Lets sounds play again after a fade stopped the ones before.
+    pub fn restart(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+
+    /// Makes the sounds playing follow another volume, that of the bus they play into.
+    pub fn set_bus_output(&self, output: Arc<BusOutput>) {
+        self.bus_output.set(output);
+    }
+
+    /// Returns the volume and filters of the bus.
+    pub fn bus_output(&self) -> &Shared<Arc<BusOutput>> {
+        &self.bus_output
+    }
+
+    /// Returns where a spatial sound plays relative to the ears.
+    pub fn placement(&self) -> &Shared<Placement> {
+        &self.placement
+    }
+}
+
+impl fmt::Debug for Controls {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Controls")
+            .field("elapsed", &self.elapsed())
+            .field("seek_pending", &self.seek.is_pending())
+            .field("fade_volume", &self.fade_volume())
+            .field("pitch_ratio", &self.pitch_ratio())
+            .field("pan", &self.pan())
+            .field("lowpass", &self.lowpass())
+            .field("highpass", &self.highpass())
+            .field("repeats", &self.repeats())
+            .field("iteration", &self.iteration())
+            .field("paused", &self.paused())
+            .field("stopped", &self.stopped.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Counts the times the sounds of a source played to their end, and went around their loop,
+/// see [`SoundSource::on_finished`](super::SoundSource::on_finished).
+#[derive(Debug, Default)]
+pub(crate) struct Events {
+    finished: AtomicU32,
+    looped: AtomicU32,
+}
+
+impl Events {
+    pub fn finished(&self) -> u32 {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    pub fn looped(&self) -> u32 {
+        self.looped.load(Ordering::Relaxed)
+    }
+}
+
+/// Sounds to play right after the one playing, see [`Source::queue`](super::Source::queue).
+#[derive(Default)]
+pub(crate) struct Queue {
+    sounds: Mutex<VecDeque<Samples>>,
+}
+
+impl Queue {
+    fn sounds(&self) -> std::sync::MutexGuard<'_, VecDeque<Samples>> {
+        self.sounds.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn push(&self, samples: Samples) {
+        self.sounds().push_back(samples);
+    }
+
+    /// Replaces the sound to play next, or queues it if there's none.
+    pub fn replace_next(&self, samples: Samples) {
+        let mut sounds = self.sounds();
+        let _ = sounds.pop_front();
+        sounds.push_front(samples);
+    }
+
+    pub fn clear(&self) {
+        self.sounds().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.sounds().len()
+    }
+}
+
+impl fmt::Debug for Queue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Queue").field("len", &self.len()).finish()
+    }
+}
+
+/// The samples of a playing sound, counting how far it got.
+pub(crate) struct Playback {
+    samples: Samples,
+    // the samples from before seeking, while fading them out
+    old_samples: Option<Samples>,
+    fade_frames: u32,
+    fade_left: u32,
+    controls: Arc<Controls>,
+    channels: u16,
+    sample_rate: u32,
+    // the channel of the next sample
+    channel: u16,
+    // the elapsed time when `frames` was 0, which starts over every time around the loop
+    start: Duration,
+    frames: u64,
+    iteration: u32,
+    fade_volume: f32,
+    // the change of the fade volume per frame, for the frames left of the fade
+    fade_step: f32,
+    fade_to: f32,
+    fade_frames_left: u64,
+    after_fade: AfterFade,
+    bus_output: Arc<BusOutput>,
+    bus_seen: u32,
+    // the volume of the bus for the current frame
+    bus_volume: f32,
+    events: Option<Arc<Events>>,
+    queue: Option<Arc<Queue>>,
+    ended: bool,
+}
+
+impl Playback {
+    /// Plays `samples`, continuing the elapsed time of `controls`.
+    pub fn new(samples: Samples, controls: Arc<Controls>) -> Self {
+        let channels = samples.channels().max(1);
+        let sample_rate = samples.sample_rate().max(1);
+        let fade_frames = (SEEK_FADE.as_secs_f32() * sample_rate as f32).max(1.0) as u32;
+        let fade_volume = controls.fade_volume();
+        let bus_seen = controls.bus_output.unseen();
+        Playback {
+            samples,
+            old_samples: None,
+            fade_frames,
+            fade_left: 0,
+            start: controls.elapsed(),
+            iteration: controls.iteration(),
+            controls,
+            channels,
+            sample_rate,
+            channel: 0,
+            frames: 0,
+            fade_volume,
+            fade_step: 0.0,
+            fade_to: 1.0,
+            fade_frames_left: 0,
+            after_fade: AfterFade::Continue,
+            bus_output: Arc::default(),
+            bus_seen,
+            bus_volume: 1.0,
+            events: None,
+            queue: None,
+            ended: false,
+        }
+    }
+
+    /// Counts the sound as finished in `events` when it plays to its end, rather than stopping.
+    pub fn with_events(mut self, events: Arc<Events>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Continues with the sounds of `queue` once the samples end, converted to their format.
+    pub fn with_queue(mut self, queue: Arc<Queue>) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    // Counts the sound as finished, and continues with the next one queued, if any.
+    fn finish(&mut self) -> Option<f32> {
+        loop {
+            if self.ended {
+                return None;
+            }
+            if let Some(events) = &self.events {
+                let _ = events.finished.fetch_add(1, Ordering::Relaxed);
+            }
+            let next = match self
+                .queue
+                .as_ref()
+                .and_then(|queue| queue.sounds().pop_front())
+            {
+                Some(next) => next,
+                None => {
+                    self.ended = true;
+                    return None;
+                }
+            };
+            self.samples =
+                if next.channels() == self.channels && next.sample_rate() == self.sample_rate {
+                    next
+                } else {
+                    Box::new(rodio::source::UniformSourceIterator::new(
+                        next,
+                        self.channels,
+                        self.sample_rate,
+                    ))
+                };
+            // the elapsed time of the next sound starts over
+            self.start = Duration::ZERO;
+            self.frames = 0;
+            self.publish_elapsed();
+            if let Some(sample) = self.samples.next() {
+                return Some(sample);
+            }
+        }
+    }
+
+    fn start_fade(&mut self, fade: Fade) {
+        if let Some(from) = fade.from {
+            self.fade_volume = from;
+        }
+        let frames = (fade.over.as_secs_f64() * self.sample_rate as f64).round() as u64;
+        self.fade_to = fade.to;
+        self.after_fade = fade.then;
+        self.fade_frames_left = frames;
+        if frames == 0 {
+            self.end_fade();
+        } else {
+            self.fade_step = (fade.to - self.fade_volume) / frames as f32;
+        }
+    }
+
+    fn end_fade(&mut self) {
+        self.fade_volume = self.fade_to;
+        self.fade_frames_left = 0;
+        let stopping = match self.after_fade {
+            AfterFade::Continue => return,
+            AfterFade::Pause => &self.controls.paused,
+            AfterFade::Stop => &self.controls.stopped,
+        };
+        stopping.store(true, Ordering::Relaxed);
+        // back to the full volume for playing again
+        self.fade_volume = 1.0;
+        self.after_fade = AfterFade::Continue;
+    }
+
+    fn publish_elapsed(&self) {
+        let played = self.frames as u128 * 1_000_000_000 / self.sample_rate as u128;
+        let nanos = self.start.as_nanos() + played;
+        self.controls
+            .elapsed
+            .store(nanos.min(u64::MAX as u128) as u64, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for Playback {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            if let Some((elapsed, samples)) = self.controls.seek.take() {
+                self.old_samples = Some(mem::replace(&mut self.samples, samples));
+                self.fade_left = self.fade_frames;
+                self.start = elapsed;
+                self.frames = 0;
+                self.publish_elapsed();
+            }
+            if let Some(fade) = self.controls.fade.take() {
+                self.start_fade(fade);
+            }
+            if let Some(output) = self.controls.bus_output.changed(&mut self.bus_seen) {
+                self.bus_output = output;
+            }
+            self.bus_volume = self.bus_output.volume();
+            if self.controls.stopped.load(Ordering::Relaxed) {
+                return None;
+            }
+            if self.controls.paused() {
+                // keep the sink busy, as a paused sink would
+                return Some(0.0);
+            }
+        }
+        let sample = match &mut self.old_samples {
+            Some(old_samples) => {
+                let old = old_samples.next().unwrap_or(0.0);
+                let new = self.samples.next().unwrap_or(0.0);
+                let t = self.fade_left as f32 / self.fade_frames as f32;
+                old * t + new * (1.0 - t)
+            }
+            None => match self.samples.next() {
+                Some(sample) => sample,
+                None => self.finish()?,
+            },
+        } * self.fade_volume
+            * self.bus_volume;
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.channel = 0;
+            // the loop starts over right before the first sample of a frame
+            let iteration = self.controls.iteration();
+            if iteration != self.iteration {
+                self.iteration = iteration;
+                self.start = Duration::ZERO;
+                self.frames = 0;
+            }
+            self.frames += 1;
+            if self.old_samples.is_some() {
+                self.fade_left -= 1;
+                if self.fade_left == 0 {
+                    self.old_samples = None;
+                }
+            }
+            if self.fade_frames_left > 0 {
+                self.fade_volume += self.fade_step;
+                self.fade_frames_left -= 1;
+                if self.fade_frames_left == 0 {
+                    self.end_fade();
+                }
+            }
+            self.publish_elapsed();
+            self.controls
+                .fade_volume
+                .store(self.fade_volume.to_bits(), Ordering::Relaxed);
+        }
+        Some(sample)
+    }
+}
+
+impl rodio::Source for Playback {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Decodes the samples of a sound again from the start of its loop on.
+pub(crate) type Reopen = Arc<dyn Fn() -> Option<Samples> + Send + Sync>;
+
+enum LoopBody {
+    // recorded the first time around
+    Recorded(Vec<f32>),
+    // decoded again every time around, with the decoder for the next time around seeked to the
+    // start of the loop on another thread
+    Reopened {
+        reopen: Reopen,
+        next: Option<mpsc::Receiver<Option<Samples>>>,
+    },
+}
+
+/// Samples which play from the beginning up to the end of a loop, then repeat the loop.
+///
+/// The loop is recorded the first time it plays, so it repeats exactly, without decoding again,
+/// unless it's [reopened](Looped::reopened) every time around.
+pub(crate) struct Looped {
+    samples: Samples,
+    channels: u16,
+    sample_rate: u32,
+    // the sample of `samples` next, and where the loop starts and ends in samples
+    position: u64,
+    start: u64,
+    end: Option<u64>,
+    body: LoopBody,
+    // the sample of the recorded body next, once it repeats
+    repeating: Option<usize>,
+    // the length of the loop in samples, once it's been around once
+    loop_len: Option<u64>,
+    events: Option<Arc<Events>>,
+    // how many times to repeat, and how many times it went around
+    controls: Option<Arc<Controls>>,
+    iteration: u32,
+    done: bool,
+}
+
+impl Looped {
+    /// Plays `samples` from `position` on, looping from `start` to `end`, or to the end of
+    /// the samples. A position after the end lands inside the loop.
+    pub fn new(
+        samples: Samples,
+        position: Duration,
+        start: Duration,
+        end: Option<Duration>,
+    ) -> Self {
+        Self::with_body(
+            samples,
+            position,
+            start,
+            end,
+            LoopBody::Recorded(Vec::new()),
+        )
+    }
+
+    /// Like [`Looped::new`], but decodes the loop again with `reopen` every time around, rather
+    /// than keeping it in memory, for streamed sounds.
+    pub fn reopened(
+        samples: Samples,
+        position: Duration,
+        start: Duration,
+        end: Option<Duration>,
+        reopen: Reopen,
+    ) -> Self {
+        let next = Some(prepare(reopen.clone()));
+        Self::with_body(
+            samples,
+            position,
+            start,
+            end,
+            LoopBody::Reopened { reopen, next },
+        )
+    }
+
+    fn with_body(
+        samples: Samples,
+        position: Duration,
+        start: Duration,
+        end: Option<Duration>,
+        body: LoopBody,
+    ) -> Self {
+        let channels = samples.channels().max(1);
+        let sample_rate = samples.sample_rate().max(1);
+        let to_samples = |time: Duration| {
+            let frames = (time.as_nanos() * sample_rate as u128 + 500_000_000) / 1_000_000_000;
+            frames as u64 * channels as u64
+        };
+        let start = to_samples(start);
+        let mut looped = Looped {
+            samples,
+            channels,
+            sample_rate,
+            position: 0,
+            start,
+            end: end.map(to_samples).filter(|&end| end > start),
+            body,
+            repeating: None,
+            loop_len: None,
+            events: None,
+            controls: None,
+            iteration: 0,
+            done: false,
+        };
+        looped.skip_samples(to_samples(position));
+        looped
+    }
+
+    /// Counts every time around the loop from now on in `events`.
+    pub fn with_events(mut self, events: Arc<Events>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Repeats as many times as `controls` says from now on, having gone around `iteration`
+    /// times already, counting the times around in it.
+    pub fn with_controls(mut self, controls: Arc<Controls>, iteration: u32) -> Self {
+        self.controls = Some(controls);
+        self.iteration = iteration;
+        self
+    }
+
+    // Goes around the loop, unless it repeated enough, returning whether it does.
+    fn loop_around(&mut self) -> bool {
+        if let Some(controls) = &self.controls {
+            let repeats = controls.repeats();
+            if repeats != u32::MAX && self.iteration >= repeats {
+                return false;
+            }
+            self.iteration = self.iteration.saturating_add(1);
+            controls.iteration.store(self.iteration, Ordering::Relaxed);
+        }
+        if let Some(events) = &self.events {
+            let _ = events.looped.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    }
+
+    fn skip_samples(&mut self, mut count: u64) {
+        while count > 0 {
+            if let (Some(index), LoopBody::Recorded(body)) = (&mut self.repeating, &self.body) {
+                let len = body.len() as u64;
+                *index = ((*index as u64 + count) % len) as usize;
+                return;
+            }
+            if let Some(len) = self.loop_len {
+                // the same place in the loop, without decoding it over and over
+                count %= len;
+                if count == 0 {
+                    return;
+                }
+            }
+            if self.next().is_none() {
+                return;
+            }
+            count -= 1;
+        }
+    }
+}
+
+// Opens the samples for the next time around the loop on another thread, since seeking a decoder
+// can take a while.
+fn prepare(reopen: Reopen) -> mpsc::Receiver<Option<Samples>> {
+    let (sender, receiver) = mpsc::sync_channel(1);
+    // there are no threads in browsers
+    #[cfg(target_arch = "wasm32")]
+    let _ = sender.send(reopen());
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = thread::spawn(move || sender.send(reopen()));
+    receiver
+}
+
+impl Iterator for Looped {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            if self.done {
+                return None;
+            }
+            if let (Some(index), LoopBody::Recorded(body)) = (&mut self.repeating, &self.body) {
+                // counting the time around as it starts, as for the first time
+                if *index == body.len() {
+                    if !self.loop_around() {
+                        self.done = true;
+                        return None;
+                    }
+                    self.repeating = Some(0);
+                    continue;
+                }
+                let sample = body[*index];
+                *index += 1;
+                return Some(sample);
+            }
+            let sample = match self.end {
+                Some(end) if self.position >= end => None,
+                _ => self.samples.next(),
+            };
+            if let Some(sample) = sample {
+                if let LoopBody::Recorded(body) = &mut self.body {
+                    if self.position >= self.start {
+                        body.push(sample);
+                    }
+                }
+                self.position += 1;
+                return Some(sample);
+            }
+            // an empty loop doesn't repeat
+            if self.position <= self.start || !self.loop_around() {
+                self.done = true;
+                return None;
+            }
+            match &mut self.body {
+                LoopBody::Recorded(body) => {
+                    // a partial frame at the end would swap the channels every time
+                    let frames = body.len() - body.len() % self.channels as usize;
+                    body.truncate(frames.max(1));
+                    self.loop_len = Some(body.len() as u64);
+                    self.repeating = Some(0);
+                }
+                LoopBody::Reopened { reopen, next } => {
+                    let samples = match next.take() {
+                        Some(next) => next.recv().ok().flatten(),
+                        None => reopen(),
+                    }?;
+                    *next = Some(prepare(reopen.clone()));
+                    self.loop_len = Some(self.position - self.start);
+                    self.samples = samples;
+                    self.position = self.start;
+                }
+            }
+        }
+    }
+}
+
+impl rodio::Source for Looped {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn samples(value: f32, frames: usize) -> Samples {
+        // stereo at 1000 Hz, so each frame is a millisecond
+        Box::new(SamplesBuffer::new(2, 1000, vec![value; frames * 2]))
+    }
+
+    fn counting(frames: usize) -> Samples {
+        // each frame is the number of the frame on the left, and its negative on the right
+        let samples: Vec<_> = (0..frames)
+            .flat_map(|frame| [frame as f32, -(frame as f32)])
+            .collect();
+        Box::new(SamplesBuffer::new(2, 1000, samples))
+    }
+
+    fn left(samples: impl Iterator<Item = f32>, frames: usize) -> Vec<f32> {
+        samples.step_by(2).take(frames).collect()
+    }
+
+    #[test]
+    fn elapsed() {
+        let controls = Arc::new(Controls::default());
+        let mut playback = Playback::new(samples(0.5, 100), controls.clone());
+        assert_eq!(playback.by_ref().take(21).count(), 21);
+        assert_eq!(controls.elapsed(), Duration::from_millis(10));
+        assert_eq!(playback.count(), 179);
+        assert_eq!(controls.elapsed(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn seeking() {
+        let controls = Arc::new(Controls::default());
+        let mut playback = Playback::new(samples(1.0, 100), controls.clone());
+        let _ = playback.by_ref().take(20).count();
+        controls.seek(Duration::from_millis(50), samples(0.0, 50));
+        let played: Vec<_> = playback.collect();
+        assert_eq!(played.len(), 100);
+        // fading over 5 frames, rather than jumping from 1 to 0
+        assert_eq!(played[0], 1.0);
+        assert!(played[4] > 0.0 && played[4] < 1.0);
+        assert!(played[10..].iter().all(|&sample| sample == 0.0));
+        assert_eq!(controls.elapsed(), Duration::from_millis(100));
+
+        // seeking past the end stops
+        let mut playback = Playback::new(samples(1.0, 100), controls.clone());
+        controls.seek(Duration::from_millis(200), samples(0.0, 0));
+        assert_eq!(playback.by_ref().count(), 10);
+    }
+
+    #[test]
+    fn looping() {
+        let ms = Duration::from_millis;
+        // an intro of 2 frames, then looping 2 to 5
+        let looped = Looped::new(counting(10), ms(0), ms(2), Some(ms(5)));
+        assert_eq!(left(looped, 10), [0., 1., 2., 3., 4., 2., 3., 4., 2., 3.]);
+        // the channels stay in order at the seam
+        let looped = Looped::new(counting(10), ms(0), ms(2), Some(ms(5)));
+        assert_eq!(looped.skip(10).take(2).collect::<Vec<_>>(), [2., -2.]);
+        // repeating to the end
+        let looped = Looped::new(counting(4), ms(0), ms(0), None);
+        assert_eq!(left(looped, 6), [0., 1., 2., 3., 0., 1.]);
+        // starting inside or after the loop
+        let looped = Looped::new(counting(10), ms(3), ms(2), Some(ms(5)));
+        assert_eq!(left(looped, 4), [3., 4., 2., 3.]);
+        // 5 frames until the loop, then 331 times around it and 2 frames in
+        let looped = Looped::new(counting(10), ms(1000), ms(2), Some(ms(5)));
+        assert_eq!(left(looped, 3), [4., 2., 3.]);
+
+        // decoding the loop again every time around, as streamed sounds do
+        let reopen: Reopen = Arc::new(|| {
+            let mut samples = counting(10);
+            let _ = samples.by_ref().take(4).count();
+            Some(samples)
+        });
+        let looped = Looped::reopened(counting(10), ms(0), ms(2), Some(ms(5)), reopen.clone());
+        assert_eq!(left(looped, 10), [0., 1., 2., 3., 4., 2., 3., 4., 2., 3.]);
+        let looped = Looped::reopened(counting(10), ms(1000), ms(2), Some(ms(5)), reopen.clone());
+        assert_eq!(left(looped, 3), [4., 2., 3.]);
+        let looped = Looped::reopened(counting(2), ms(0), ms(2), None, reopen);
+        assert_eq!(left(looped, 10), [0., 1.]);
+    }
+
+    #[test]
+    fn events() {
+        let ms = Duration::from_millis;
+        let events = Arc::new(Events::default());
+        let controls = Arc::new(Controls::default());
+        let playback = Playback::new(samples(1.0, 10), controls.clone());
+        assert_eq!(playback.with_events(events.clone()).count(), 20);
+        assert_eq!(events.finished(), 1);
+
+        // not when stopped by a fade
+        let mut playback = Playback::new(samples(1.0, 100), controls.clone());
+        playback = playback.with_events(events.clone());
+        controls.fade(Fade {
+            from: None,
+            to: 0.0,
+            over: ms(10),
+            then: AfterFade::Stop,
+        });
+        let _ = playback.count();
+        assert_eq!(events.finished(), 1);
+
+        // every time around, as it starts, but not when starting inside the loop
+        let looped = Looped::new(counting(10), ms(1000), ms(2), Some(ms(5)));
+        let _ = looped.with_events(events.clone()).take(20).count();
+        assert_eq!(events.looped(), 3);
+        let reopen: Reopen = Arc::new(|| Some(counting(3)));
+        let looped = Looped::reopened(counting(3), ms(0), ms(0), None, reopen);
+        let _ = looped.with_events(events.clone()).take(18).count();
+        assert_eq!(events.looped(), 5);
+    }
+
+    #[test]
+    fn repeat_count() {
+        let ms = Duration::from_millis;
+        let controls = Arc::new(Controls::default());
+        controls.set_repeats(2);
+        // the intro once, then the loop 3 times
+        let looped =
+            Looped::new(counting(10), ms(0), ms(2), Some(ms(5))).with_controls(controls.clone(), 0);
+        assert_eq!(
+            left(looped, 20),
+            [0., 1., 2., 3., 4., 2., 3., 4., 2., 3., 4.]
+        );
+        assert_eq!(controls.iteration(), 2);
+
+        // changing the count for the times around left
+        let controls = Arc::new(Controls::default());
+        controls.set_repeats(5);
+        let reopen: Reopen = Arc::new(|| Some(counting(3)));
+        let mut looped = Looped::reopened(counting(3), ms(0), ms(0), None, reopen)
+            .with_controls(controls.clone(), 0);
+        let played: Vec<_> = looped.by_ref().take(8).collect();
+        assert_eq!(left(played.into_iter(), 4), [0., 1., 2., 0.]);
+        controls.set_repeats(1);
+        assert_eq!(left(looped, 10), [1., 2.]);
+
+        // the elapsed time starts over every time around
+        let controls = Arc::new(Controls::default());
+        let looped =
+            Looped::new(samples(1.0, 10), ms(0), ms(0), None).with_controls(controls.clone(), 0);
+        let mut playback = Playback::new(Box::new(looped), controls.clone());
+        let _ = playback.by_ref().take(2 * 25).count();
+        assert_eq!(controls.iteration(), 2);
+        assert_eq!(controls.elapsed(), ms(5));
+    }
+
+    #[test]
+    fn queue() {
+        let events = Arc::new(Events::default());
+        let controls = Arc::new(Controls::default());
+        let queue = Arc::new(Queue::default());
+        queue.push(samples(0.5, 100));
+        queue.replace_next(counting(3));
+        // mono at another rate, converted to the format of the first
+        queue.push(Box::new(SamplesBuffer::new(1, 500, vec![0.25; 50])));
+        assert_eq!(queue.len(), 2);
+        let playback = Playback::new(samples(1.0, 2), controls.clone())
+            .with_events(events.clone())
+            .with_queue(queue.clone());
+        let played: Vec<_> = playback.collect();
+        // right after one another, without a gap
+        assert_eq!(played[..10], [1., 1., 1., 1., 0., -0., 1., -1., 2., -2.]);
+        // 100 frames, but for the last one or two resampling drops
+        assert!((196..=200).contains(&(played.len() - 10)));
+        assert!(played[10..].iter().all(|&sample| sample == 0.25));
+        assert_eq!(events.finished(), 3);
+        assert!(controls.elapsed() >= Duration::from_millis(98));
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn bus_volume() {
+        let controls = Arc::new(Controls::default());
+        controls.set_bus_output(Arc::new(BusOutput::new(0.5)));
+        let mut playback = Playback::new(samples(1.0, 100), controls.clone());
+        assert_eq!(playback.by_ref().take(20).collect::<Vec<_>>(), [0.5; 20]);
+        // changing the volume of the bus, or the bus, while playing
+        let mixer = &mut crate::audio::mixer::Mixer::default();
+        mixer.create_bus("music");
+        mixer.set_bus_volume("music", 0.25).unwrap();
+        controls.set_bus_output(mixer.output(Some("music")).unwrap());
+        assert_eq!(playback.by_ref().take(20).collect::<Vec<_>>(), [0.25; 20]);
+        mixer.set_master_volume(2.0);
+        assert_eq!(playback.by_ref().take(20).collect::<Vec<_>>(), [0.5; 20]);
+    }
+
+    #[test]
+    fn fading() {
+        let controls = Arc::new(Controls::default());
+        let mut playback = Playback::new(samples(1.0, 100), controls.clone());
+        controls.fade(Fade {
+            from: None,
+            to: 0.5,
+            over: Duration::from_millis(10),
+            then: AfterFade::Continue,
+        });
+        let played: Vec<_> = playback.by_ref().take(40).collect();
+        assert_eq!(played[0], 1.0);
+        assert!((played[10] - 0.75).abs() < 1e-6);
+        assert_eq!(played[20..], [0.5; 20]);
+        assert_eq!(controls.fade_volume(), 0.5);
+
+        // a new fade starts from the current volume
+        controls.fade(Fade {
+            from: None,
+            to: 0.0,
+            over: Duration::from_millis(10),
+            then: AfterFade::Pause,
+        });
+        let _ = playback.by_ref().take(20).count();
+        assert!(controls.paused());
+        assert_eq!(controls.elapsed(), Duration::from_millis(30));
+        assert_eq!(playback.next(), Some(0.0));
+        assert_eq!(controls.elapsed(), Duration::from_millis(30));
+        controls.resume();
+        assert_eq!(playback.by_ref().take(2).collect::<Vec<_>>(), [1.0, 1.0]);
+
+        controls.fade(Fade {
+            from: Some(0.0),
+            to: 0.0,
+            over: Duration::ZERO,
+            then: AfterFade::Stop,
+        });
+        assert_eq!(playback.next(), None);
+    }
+}
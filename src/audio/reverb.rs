@@ -0,0 +1,422 @@
+//! A reverb the sources send some of their sound into, after Freeverb.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::playback::{Controls, Shared};
+
+// The delays of the comb and allpass filters in samples at 44.1kHz, and how many more the ones
+// of each channel after the first have.
+const COMB_DELAYS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_DELAYS: [usize; 4] = [556, 441, 341, 225];
+const STEREO_SPREAD: usize = 23;
+const INPUT_GAIN: f32 = 0.015;
+// How long the reverb takes to fade out when it's turned off, rather than stopping dead.
+const FADE_OUT: Duration = Duration::from_millis(50);
+
+/// The settings of the reverb, see [`AudioContext::set_reverb`](super::AudioContext::set_reverb).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReverbParams {
+    /// How big the room sounds, from 0 to 1, which makes the reverb ring for longer.
+    pub room_size: f32,
+    /// How much the walls absorb the high frequencies, from 0 to 1, which makes it sound darker.
+    pub damping: f32,
+    /// The volume of the reverb, from 0 to 1.
+    pub wet: f32,
+    /// The volume of the sound of the sources sending all of it into the reverb, from 0 to 1,
+    /// and for those sending some, partly so.
+    pub dry: f32,
+}
+
+impl ReverbParams {
+    /// A small, quite dead room.
+    pub const SMALL_ROOM: ReverbParams = ReverbParams {
+        room_size: 0.3,
+        damping: 0.7,
+        wet: 0.2,
+        dry: 1.0,
+    };
+
+    /// A big hall, ringing for a while.
+    pub const HALL: ReverbParams = ReverbParams {
+        room_size: 0.8,
+        damping: 0.4,
+        wet: 0.3,
+        dry: 0.9,
+    };
+
+    /// A cave with hard walls, ringing for long and bright.
+    pub const CAVE: ReverbParams = ReverbParams {
+        room_size: 0.95,
+        damping: 0.1,
+        wet: 0.45,
+        dry: 0.7,
+    };
+}
+
+/// What's shared between the reverb and the sources sending into it.
+pub(crate) struct ReverbBus {
+    // the format the sources are converted to, that of the output
+    channels: u16,
+    sample_rate: u32,
+    on: AtomicBool,
+    // counting the times it's been turned on or off, to end the reverb playing before
+    generation: AtomicU32,
+    params: Shared<Option<ReverbParams>>,
+    dry: AtomicU32,
+    // the sum of what the sources sent since the reverb last took it
+    sent: AtomicU32,
+}
+
+impl Default for ReverbBus {
+    fn default() -> Self {
+        ReverbBus::new(2, 44100)
+    }
+}
+
+impl ReverbBus {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        ReverbBus {
+            channels: channels.max(1),
+            sample_rate: sample_rate.max(1),
+            on: AtomicBool::new(false),
+            generation: AtomicU32::new(0),
+            params: Shared::default(),
+            dry: AtomicU32::new(1.0f32.to_bits()),
+            sent: AtomicU32::new(0.0f32.to_bits()),
+        }
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn is_on(&self) -> bool {
+        self.on.load(Ordering::Relaxed)
+    }
+
+    /// Changes the reverb, returning the reverb to play if it was off.
+    pub fn set(self: &Arc<Self>, params: Option<ReverbParams>) -> Option<ReverbReturn> {
+        let was_on = self.is_on();
+        if let Some(params) = params {
+            self.params.set(Some(params));
+            self.dry.store(params.dry.to_bits(), Ordering::Relaxed);
+        }
+        self.on.store(params.is_some(), Ordering::Relaxed);
+        if was_on == params.is_some() {
+            return None;
+        }
+        let generation = self
+            .generation
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1);
+        params.map(|_| ReverbReturn::new(self.clone(), generation))
+    }
+
+    fn send(&self, sample: f32) {
+        let _ = self
+            .sent
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sent| {
+                Some((f32::from_bits(sent) + sample).to_bits())
+            });
+    }
+
+    fn take(&self) -> f32 {
+        f32::from_bits(self.sent.swap(0.0f32.to_bits(), Ordering::Relaxed))
+    }
+}
+
+impl fmt::Debug for ReverbBus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReverbBus")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .field("on", &self.is_on())
+            .finish()
+    }
+}
+
+/// Sends some of a sound, which must be in the format of the bus, into the reverb, turning its
+/// own sound down by the dry volume.
+pub(crate) struct ReverbSend<S> {
+    input: S,
+    bus: Arc<ReverbBus>,
+    controls: Arc<Controls>,
+}
+
+impl<S> ReverbSend<S> {
+    pub fn new(input: S, bus: Arc<ReverbBus>, controls: Arc<Controls>) -> Self {
+        ReverbSend {
+            input,
+            bus,
+            controls,
+        }
+    }
+}
+
+impl<S> Iterator for ReverbSend<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        let send = self.controls.reverb_send();
+        if send == 0.0 || !self.bus.is_on() {
+            return Some(sample);
+        }
+        // after the volume of the sink, as that comes after this
+        self.bus.send(sample * send * self.controls.volume());
+        let dry = f32::from_bits(self.bus.dry.load(Ordering::Relaxed));
+        Some(sample * (1.0 + send * (dry - 1.0)))
+    }
+}
+
+impl<S> rodio::Source for ReverbSend<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filtered: f32,
+}
+
+impl Comb {
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filtered = output * (1.0 - damping) + self.filtered * damping;
+        self.buffer[self.index] = input + self.filtered * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// The reverb of what the sources sent, played on its own.
+pub(crate) struct ReverbReturn {
+    bus: Arc<ReverbBus>,
+    generation: u32,
+    params_seen: u32,
+    feedback: f32,
+    damping: f32,
+    wet: f32,
+    // the filters of each channel
+    combs: Vec<Vec<Comb>>,
+    allpasses: Vec<Vec<Allpass>>,
+    frame: Vec<f32>,
+    channel: usize,
+    // the frames left of fading out, once turned off
+    fade_out: Option<u32>,
+    fade_frames: u32,
+}
+
+impl ReverbReturn {
+    fn new(bus: Arc<ReverbBus>, generation: u32) -> Self {
+        let channels = bus.channels as usize;
+        let scale = bus.sample_rate as f32 / 44100.0;
+        let delay = |samples: usize, channel: usize| {
+            vec![0.0; (((samples + channel * STEREO_SPREAD) as f32 * scale) as usize).max(1)]
+        };
+        let combs = (0..channels)
+            .map(|channel| {
+                COMB_DELAYS
+                    .iter()
+                    .map(|&samples| Comb {
+                        buffer: delay(samples, channel),
+                        index: 0,
+                        filtered: 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let allpasses = (0..channels)
+            .map(|channel| {
+                ALLPASS_DELAYS
+                    .iter()
+                    .map(|&samples| Allpass {
+                        buffer: delay(samples, channel),
+                        index: 0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let fade_frames = (FADE_OUT.as_secs_f32() * bus.sample_rate as f32).max(1.0) as u32;
+        let params_seen = bus.params.unseen();
+        let mut reverb = ReverbReturn {
+            bus,
+            generation,
+            params_seen,
+            feedback: 0.0,
+            damping: 0.0,
+            wet: 0.0,
+            combs,
+            allpasses,
+            frame: vec![0.0; channels],
+            channel: 0,
+            fade_out: None,
+            fade_frames,
+        };
+        reverb.update();
+        reverb
+    }
+
+    fn update(&mut self) {
+        if let Some(Some(params)) = self.bus.params.changed(&mut self.params_seen) {
+            self.feedback = params.room_size.clamp(0.0, 1.0) * 0.28 + 0.7;
+            self.damping = params.damping.clamp(0.0, 1.0) * 0.4;
+            self.wet = params.wet.max(0.0) * 3.0;
+        }
+    }
+
+    fn process_frame(&mut self) -> bool {
+        self.update();
+        if self.fade_out.is_none() && self.bus.generation.load(Ordering::Relaxed) != self.generation
+        {
+            self.fade_out = Some(self.fade_frames);
+        }
+        let mut volume = self.wet;
+        let input = match &mut self.fade_out {
+            Some(0) => return false,
+            Some(left) => {
+                *left -= 1;
+                volume *= *left as f32 / self.fade_frames as f32;
+                0.0
+            }
+            None => self.bus.take() * INPUT_GAIN,
+        };
+        let (feedback, damping) = (self.feedback, self.damping);
+        for ((sample, combs), allpasses) in self
+            .frame
+            .iter_mut()
+            .zip(&mut self.combs)
+            .zip(&mut self.allpasses)
+        {
+            let mut output: f32 = combs
+                .iter_mut()
+                .map(|comb| comb.process(input, feedback, damping))
+                .sum();
+            for allpass in allpasses.iter_mut() {
+                output = allpass.process(output);
+            }
+            *sample = output * volume;
+        }
+        true
+    }
+}
+
+impl Iterator for ReverbReturn {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 && !self.process_frame() {
+            return None;
+        }
+        let sample = self.frame[self.channel];
+        self.channel = (self.channel + 1) % self.frame.len();
+        Some(sample)
+    }
+}
+
+impl rodio::Source for ReverbReturn {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.bus.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.bus.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn reverb() {
+        let bus = Arc::new(ReverbBus::new(2, 8000));
+        let controls = Arc::new(Controls::default());
+        let click = || {
+            let mut samples = vec![0.0; 32000];
+            samples[0] = 1.0;
+            samples[1] = 1.0;
+            SamplesBuffer::new(2, 8000, samples)
+        };
+        // passing straight through while off or not sending
+        let dry: Vec<_> = ReverbSend::new(click(), bus.clone(), controls.clone()).collect();
+        assert_eq!(dry, click().collect::<Vec<_>>());
+        let mut reverb = bus.set(Some(ReverbParams::CAVE)).unwrap();
+        assert!(bus.set(Some(ReverbParams::HALL)).is_none());
+        let dry: Vec<_> = ReverbSend::new(click(), bus.clone(), controls.clone()).collect();
+        assert_eq!(dry, click().collect::<Vec<_>>());
+
+        // pulled together, as the mixer does
+        controls.set_reverb_send(1.0);
+        controls.set_volume(0.5);
+        let mut send = ReverbSend::new(click(), bus.clone(), controls.clone());
+        let mut tail = Vec::new();
+        while let Some(sample) = send.next() {
+            tail.push(reverb.next().unwrap());
+            if tail.len() == 1 {
+                assert_eq!(sample, ReverbParams::HALL.dry);
+            }
+        }
+        // the hall rings for a while after the click, and goes silent
+        let loudness = |samples: &[f32]| samples.iter().map(|sample| sample.abs()).sum::<f32>();
+        assert_eq!(loudness(&tail[..200]), 0.0);
+        assert!(loudness(&tail[200..4000]) > 0.1);
+        assert!(loudness(&tail[200..4000]) > 10.0 * loudness(&tail[28000..]));
+
+        // fading out when turned off
+        assert!(bus.set(None).is_none());
+        assert_eq!(reverb.by_ref().count(), 800);
+        let dry: Vec<_> = ReverbSend::new(click(), bus, controls).collect();
+        assert_eq!(dry, click().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,557 @@
+//! Placing sounds in space relative to the listener's ears.
+
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::playback::{Controls, Shared};
+
+// How long the volume of each ear takes to change all the way as things move, to not click.
+const MOVE_TIME: Duration = Duration::from_millis(10);
+// About how long the Doppler shift takes to follow changes of the velocities, to not zipper.
+const DOPPLER_TIME: Duration = Duration::from_millis(30);
+
+/// How the volume of a [`SpatialSource`](super::SpatialSource) falls off with its distance from
+/// each ear, see [`SpatialSource::set_attenuation`](super::SpatialSource::set_attenuation).
+///
+/// The distances are in the units of the positions, whatever those are in the game, so these
+/// are all relative to the distances given.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attenuation {
+    /// The same volume at any distance.
+    None,
+    /// The full volume up to `min`, falling linearly to silence at `max`.
+    Linear {
+        /// The distance up to which the sound is at the full volume.
+        min: f32,
+        /// The distance from which the sound is silent.
+        max: f32,
+    },
+    /// The full volume up to `ref_dist`, then `ref_dist / (ref_dist + rolloff * (distance - ref_dist))`,
+    /// halving at twice the distance with a `rolloff` of 1, as in OpenAL.
+    InverseDistance {
+        /// The distance up to which the sound is at the full volume, which should be positive.
+        ref_dist: f32,
+        /// How fast the volume falls off.
+        rolloff: f32,
+    },
+    /// The full volume up to `ref_dist`, then `(distance / ref_dist)` to the power of `-rolloff`,
+    /// so a `rolloff` of 2 is the inverse square law.
+    Exponential {
+        /// The distance up to which the sound is at the full volume, which should be positive.
+        ref_dist: f32,
+        /// How fast the volume falls off.
+        rolloff: f32,
+    },
+}
+
+impl Default for Attenuation {
+    /// The inverse square law from a distance of 1 on, as spatial sources used to have.
+    fn default() -> Self {
+        Attenuation::Exponential {
+            ref_dist: 1.0,
+            rolloff: 2.0,
+        }
+    }
+}
+
+impl Attenuation {
+    /// Returns the volume, from 0 to 1, a sound has at `distance`.
+    pub fn volume(&self, distance: f32) -> f32 {
+        let distance = distance.max(0.0);
+        match *self {
+            Attenuation::None => 1.0,
+            Attenuation::Linear { min, max } => {
+                if distance <= min {
+                    1.0
+                } else if distance >= max {
+                    0.0
+                } else {
+                    1.0 - (distance - min) / (max - min)
+                }
+            }
+            Attenuation::InverseDistance { ref_dist, rolloff } => {
+                let ref_dist = ref_dist.max(f32::MIN_POSITIVE);
+                let distance = distance.max(ref_dist);
+                ref_dist / (ref_dist + rolloff.max(0.0) * (distance - ref_dist))
+            }
+            Attenuation::Exponential { ref_dist, rolloff } => {
+                let ref_dist = ref_dist.max(f32::MIN_POSITIVE);
+                (distance.max(ref_dist) / ref_dist).powf(-rolloff.max(0.0))
+            }
+        }
+    }
+}
+
+/// Where a spatial sound plays, and how it's attenuated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Placement {
+    pub emitter: [f32; 3],
+    pub left_ear: [f32; 3],
+    pub right_ear: [f32; 3],
+    pub attenuation: Attenuation,
+    // what the distances are clamped to before attenuating
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub velocity: [f32; 3],
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            emitter: [0.0, 0.0, 0.0],
+            left_ear: [-1.0, 0.0, 0.0],
+            right_ear: [1.0, 0.0, 0.0],
+            attenuation: Attenuation::default(),
+            min_distance: 0.0,
+            max_distance: f32::INFINITY,
+            velocity: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+/// What's shared between all spatial sources for the Doppler effect, see
+/// [`AudioContext::set_doppler_factor`](super::AudioContext::set_doppler_factor).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Listener {
+    pub velocity: [f32; 3],
+    pub speed_of_sound: f32,
+    pub doppler_factor: f32,
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Listener {
+            velocity: [0.0, 0.0, 0.0],
+            speed_of_sound: 343.3,
+            doppler_factor: 0.0,
+        }
+    }
+}
+
+impl Placement {
+    /// Returns the volumes of the left and right ears.
+    pub fn volumes(&self) -> [f32; 2] {
+        let left = distance(self.left_ear, self.emitter);
+        let right = distance(self.right_ear, self.emitter);
+        let between = distance(self.left_ear, self.right_ear).max(f32::EPSILON);
+        // louder in the closer ear, by at most half, as rodio does
+        let side = |near: f32, far: f32| (((far - near) / between + 1.0) / 4.0 + 0.5).min(1.0);
+        let attenuate = |distance: f32| {
+            let distance =
+                distance.clamp(self.min_distance, self.max_distance.max(self.min_distance));
+            self.attenuation.volume(distance)
+        };
+        [
+            side(left, right) * attenuate(left),
+            side(right, left) * attenuate(right),
+        ]
+    }
+
+    /// Returns the factor the Doppler effect changes the speed and pitch by, as in OpenAL, with
+    /// the listener between the ears.
+    pub fn doppler_ratio(&self, listener: &Listener) -> f32 {
+        if listener.doppler_factor <= 0.0 {
+            return 1.0;
+        }
+        let ears = [0, 1, 2].map(|i| (self.left_ear[i] + self.right_ear[i]) / 2.0);
+        let towards = [0, 1, 2].map(|i| ears[i] - self.emitter[i]);
+        let distance = distance(ears, self.emitter);
+        if distance == 0.0 {
+            return 1.0;
+        }
+        let speed_of_sound = listener.speed_of_sound.max(f32::MIN_POSITIVE);
+        let factor = listener.doppler_factor;
+        // the speeds away from the source, no faster than sound
+        let limit = speed_of_sound / factor;
+        let listener_speed = (dot(towards, listener.velocity) / distance).min(limit);
+        let source_speed = (dot(towards, self.velocity) / distance).min(limit);
+        let ratio =
+            (speed_of_sound - factor * listener_speed) / (speed_of_sound - factor * source_speed);
+        if ratio.is_nan() {
+            1.0
+        } else {
+            ratio.clamp(1.0 / 16.0, 16.0)
+        }
+    }
+}
+
+/// Changes the speed and pitch of a sound by the Doppler effect of its [`Placement`], resampling
+/// as it plays, or passing it through while the effect is off.
+pub(crate) struct Doppler<S> {
+    input: S,
+    controls: Arc<Controls>,
+    listener: Arc<Shared<Listener>>,
+    placement: Placement,
+    placement_seen: u32,
+    listener_state: Listener,
+    listener_seen: u32,
+    // the ratio of the current frame, following the target smoothly
+    ratio: f32,
+    smoothing: f32,
+    // interpolating from `previous` to `next` at `t` while resampling, into `frame`
+    resampling: bool,
+    previous: Vec<f32>,
+    next: Vec<f32>,
+    frame: Vec<f32>,
+    t: f32,
+    ended: bool,
+    // the channel of the next sample, and whether its frame comes from `frame`
+    channel: usize,
+    resampled: bool,
+}
+
+impl<S> Doppler<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub fn new(input: S, controls: Arc<Controls>, listener: Arc<Shared<Listener>>) -> Self {
+        let channels = input.channels().max(1) as usize;
+        let frames = DOPPLER_TIME.as_secs_f32() * input.sample_rate() as f32;
+        // starting at the ratio right away
+        let mut placement_seen = controls.placement().unseen();
+        let placement = controls
+            .placement()
+            .changed(&mut placement_seen)
+            .unwrap_or_default();
+        let mut listener_seen = listener.unseen();
+        let listener_state = listener.changed(&mut listener_seen).unwrap_or_default();
+        Doppler {
+            input,
+            controls,
+            listener,
+            ratio: placement.doppler_ratio(&listener_state),
+            placement,
+            placement_seen,
+            listener_state,
+            listener_seen,
+            smoothing: 1.0 - (-1.0 / frames.max(1.0)).exp(),
+            resampling: false,
+            previous: vec![0.0; channels],
+            next: vec![0.0; channels],
+            frame: vec![0.0; channels],
+            t: 0.0,
+            ended: false,
+            channel: 0,
+            resampled: false,
+        }
+    }
+
+    // Reads the next frame into `next`, returning whether there was one.
+    fn read_next(&mut self) -> bool {
+        for sample in &mut self.next {
+            match self.input.next() {
+                Some(next) => *sample = next,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Works out the frame to play next, returning whether there is one.
+    fn resample(&mut self) -> bool {
+        if let Some(placement) = self.controls.placement().changed(&mut self.placement_seen) {
+            self.placement = placement;
+        }
+        if let Some(listener) = self.listener.changed(&mut self.listener_seen) {
+            self.listener_state = listener;
+        }
+        let target = self.placement.doppler_ratio(&self.listener_state);
+        self.ratio += (target - self.ratio) * self.smoothing;
+        let off = self.listener_state.doppler_factor <= 0.0;
+        if !self.resampling {
+            if off || self.ratio == 1.0 {
+                self.ratio = 1.0;
+                self.resampled = false;
+                return true;
+            }
+            if !self.read_next() {
+                return false;
+            }
+            self.previous.copy_from_slice(&self.next);
+            self.ended = !self.read_next();
+            self.t = 0.0;
+            self.resampling = true;
+        }
+        if self.ended {
+            return false;
+        }
+        self.resampled = true;
+        if off && (self.ratio - 1.0).abs() < 1e-3 {
+            // play the frame read ahead, then pass the rest through
+            self.frame.copy_from_slice(&self.next);
+            self.ratio = 1.0;
+            self.resampling = false;
+            return true;
+        }
+        for ((frame, previous), next) in self.frame.iter_mut().zip(&self.previous).zip(&self.next) {
+            *frame = previous + (next - previous) * self.t;
+        }
+        self.t += self.ratio;
+        while self.t >= 1.0 {
+            self.t -= 1.0;
+            mem::swap(&mut self.previous, &mut self.next);
+            if !self.read_next() {
+                self.ended = true;
+                break;
+            }
+        }
+        true
+    }
+}
+
+impl<S> Iterator for Doppler<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 && !self.resample() {
+            return None;
+        }
+        let sample = if self.resampled {
+            self.frame[self.channel]
+        } else {
+            self.input.next()?
+        };
+        self.channel = (self.channel + 1) % self.frame.len();
+        Some(sample)
+    }
+}
+
+impl<S> rodio::Source for Doppler<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Plays a sound from its [`Placement`], mixed down to one channel and played to the ears
+/// at their volumes.
+pub(crate) struct Spatialize<S> {
+    input: S,
+    controls: Arc<Controls>,
+    seen: u32,
+    // the volumes of the ears for the current frame, moving up to `step` a frame to the targets
+    volumes: [f32; 2],
+    targets: [f32; 2],
+    step: f32,
+    // the channel of the next sample, and the mixed sample of the frame
+    channel: usize,
+    sample: f32,
+}
+
+impl<S> Spatialize<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    pub fn new(input: S, controls: Arc<Controls>) -> Self {
+        let mut seen = controls.placement().unseen();
+        let targets = controls
+            .placement()
+            .changed(&mut seen)
+            .unwrap_or_default()
+            .volumes();
+        let step = 1.0 / (MOVE_TIME.as_secs_f32() * input.sample_rate() as f32).max(1.0);
+        Spatialize {
+            input,
+            controls,
+            seen,
+            volumes: targets,
+            targets,
+            step,
+            channel: 0,
+            sample: 0.0,
+        }
+    }
+}
+
+impl<S> Iterator for Spatialize<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            if let Some(placement) = self.controls.placement().changed(&mut self.seen) {
+                self.targets = placement.volumes();
+            }
+            for (volume, target) in self.volumes.iter_mut().zip(self.targets) {
+                *volume += (target - *volume).clamp(-self.step, self.step);
+            }
+            self.sample = self.input.next()?;
+            for _ in 1..self.input.channels() {
+                self.sample += self.input.next().unwrap_or(0.0);
+            }
+        }
+        let sample = self.sample * self.volumes[self.channel];
+        self.channel = (self.channel + 1) % 2;
+        Some(sample)
+    }
+}
+
+impl<S> rodio::Source for Spatialize<S>
+where
+    S: rodio::Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn doppler() {
+        let listener = Listener {
+            doppler_factor: 1.0,
+            speed_of_sound: 100.0,
+            ..Listener::default()
+        };
+        let coming = Placement {
+            emitter: [0.0, 100.0, 0.0],
+            velocity: [0.0, -50.0, 0.0],
+            ..Placement::default()
+        };
+        assert_eq!(coming.doppler_ratio(&listener), 2.0);
+        let going = Placement {
+            velocity: [0.0, 100.0, 0.0],
+            ..coming
+        };
+        assert_eq!(going.doppler_ratio(&listener), 0.5);
+        // passing by sideways doesn't change the pitch
+        let passing = Placement {
+            velocity: [100.0, 0.0, 0.0],
+            ..coming
+        };
+        assert_eq!(passing.doppler_ratio(&listener), 1.0);
+        let off = Listener {
+            doppler_factor: 0.0,
+            ..listener
+        };
+        assert_eq!(coming.doppler_ratio(&off), 1.0);
+
+        // coming twice as fast
+        let counting = || {
+            let samples: Vec<_> = (0..10_000).flat_map(|i| [i as f32, -(i as f32)]).collect();
+            SamplesBuffer::new(2, 1000, samples)
+        };
+        let controls = Arc::new(Controls::default());
+        let shared = Arc::new(Shared::default());
+        let passed: Vec<_> = Doppler::new(counting(), controls.clone(), shared.clone()).collect();
+        assert_eq!(passed, counting().collect::<Vec<_>>());
+        controls.placement().set(coming);
+        shared.set(listener);
+        let shifted: Vec<_> = Doppler::new(counting(), controls, shared).collect();
+        assert_eq!(shifted.len(), 10_000);
+        let step = shifted[2] - shifted[0];
+        assert!((step - 2.0).abs() < 1e-3, "{}", step);
+        assert_eq!(shifted[3], -shifted[2]);
+    }
+
+    #[test]
+    fn attenuation() {
+        let linear = Attenuation::Linear {
+            min: 10.0,
+            max: 100.0,
+        };
+        assert_eq!(linear.volume(5.0), 1.0);
+        assert_eq!(linear.volume(55.0), 0.5);
+        assert!(linear.volume(99.999) < 1e-3);
+        assert_eq!(linear.volume(100.0), 0.0);
+        assert_eq!(linear.volume(1000.0), 0.0);
+
+        let inverse = Attenuation::InverseDistance {
+            ref_dist: 2.0,
+            rolloff: 1.0,
+        };
+        assert_eq!(inverse.volume(1.0), 1.0);
+        assert_eq!(inverse.volume(4.0), 0.5);
+        let square = Attenuation::default();
+        assert_eq!(square.volume(0.5), 1.0);
+        assert_eq!(square.volume(4.0), 1.0 / 16.0);
+        assert_eq!(Attenuation::None.volume(1e9), 1.0);
+    }
+
+    #[test]
+    fn spatialize() {
+        let ones = || SamplesBuffer::new(1, 1000, vec![1.0; 100]);
+        let controls = Arc::new(Controls::default());
+        let placement = Placement {
+            emitter: [0.0, 50.0, 0.0],
+            attenuation: Attenuation::Linear {
+                min: 1.0,
+                max: 50.0,
+            },
+            ..Placement::default()
+        };
+        controls.placement().set(placement);
+        // at the max distance from both ears, or a little further
+        let far: Vec<_> = Spatialize::new(ones(), controls.clone()).collect();
+        assert_eq!(far.len(), 200);
+        assert!(far.iter().all(|&sample| sample.abs() < 1e-3));
+
+        // close by on the right, fading in over 10ms while playing
+        let mut spatialized = Spatialize::new(ones(), controls.clone());
+        let _ = spatialized.by_ref().take(2).count();
+        controls.placement().set(Placement {
+            emitter: [1.0, 0.0, 0.0],
+            ..placement
+        });
+        let near: Vec<_> = spatialized.collect();
+        assert!(near[1] > 0.0 && near[1] < 0.2);
+        let left = 0.5 * (1.0 - 1.0 / 49.0);
+        assert!((near[20] - left).abs() < 1e-6 && (near[21] - 1.0).abs() < 1e-6);
+
+        // the max distance clamps the ones further away
+        controls.placement().set(Placement {
+            max_distance: 25.5,
+            ..placement
+        });
+        let clamped: Vec<_> = Spatialize::new(ones(), controls).collect();
+        assert!((clamped[0] - 0.75 * 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,329 @@
+//! Reading sounds from their files while they play.
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use super::SoundData;
+use crate::error::{GameError, GameResult};
+use crate::filesystem::File;
+
+/// An open file shared by the decoders of a streamed source, each of which reads it from its own
+/// position, so the loop can be decoded again while the sound plays.
+#[derive(Clone, Debug)]
+pub(crate) struct StreamedFile {
+    file: Arc<Mutex<File>>,
+    position: u64,
+}
+
+impl StreamedFile {
+    pub fn new(file: File) -> Self {
+        StreamedFile {
+            file: Arc::new(Mutex::new(file)),
+            position: 0,
+        }
+    }
+}
+
+impl Read for StreamedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = file.seek(SeekFrom::Start(self.position))?;
+        let read = file.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for StreamedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+                file.seek(SeekFrom::End(0))?.checked_add_signed(offset)
+            }
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Tried to seek before the start of the file",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// Fills buffers with the samples of a sound, see [`Source::from_generator`](super::Source::from_generator).
+pub(crate) type GeneratorFn = Box<dyn FnMut(&mut [f32]) + Send>;
+
+// How many frames a generator fills at a time.
+const GENERATOR_FRAMES: usize = 512;
+
+/// A sound made up by a function as it plays, shared by the sounds of a source.
+#[derive(Clone)]
+pub(crate) struct Generator {
+    generate: Arc<Mutex<GeneratorFn>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Generator {
+    pub fn new(channels: u16, sample_rate: u32, generate: GeneratorFn) -> Self {
+        Generator {
+            generate: Arc::new(Mutex::new(generate)),
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Starts generating samples, without end.
+    pub fn samples(&self) -> Generated {
+        Generated {
+            generator: self.clone(),
+            buffer: vec![0.0; GENERATOR_FRAMES * self.channels as usize],
+            next: GENERATOR_FRAMES * self.channels as usize,
+        }
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Generator")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .finish()
+    }
+}
+
+/// The samples of a [`Generator`].
+pub(crate) struct Generated {
+    generator: Generator,
+    buffer: Vec<f32>,
+    // the sample of the buffer next, filling it again at the end
+    next: usize,
+}
+
+impl Iterator for Generated {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.next == self.buffer.len() {
+            self.buffer.fill(0.0);
+            let mut generate = self
+                .generator
+                .generate
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            generate(&mut self.buffer);
+            self.next = 0;
+        }
+        let sample = self.buffer[self.next];
+        self.next += 1;
+        Some(sample)
+    }
+}
+
+impl rodio::Source for Generated {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.generator.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.generator.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// The encoded sound a source plays, or what makes it up.
+#[derive(Clone, Debug)]
+pub(crate) enum SourceData {
+    Memory(SoundData),
+    Streamed(StreamedFile),
+    Generated(Generator),
+}
+
+impl SourceData {
+    /// Reads the data from the start.
+    pub fn reader(&self) -> SourceReader {
+        match self {
+            SourceData::Memory(data) => SourceReader::Memory(io::Cursor::new(data.clone())),
+            SourceData::Streamed(file) => {
+                let mut file = file.clone();
+                file.position = 0;
+                SourceReader::Streamed(io::BufReader::new(file))
+            }
+            // with nothing to decode
+            SourceData::Generated(_) => {
+                SourceReader::Memory(io::Cursor::new(SoundData::from_bytes(&[])))
+            }
+        }
+    }
+
+    /// Creates a new decoder of the data.
+    pub fn decoder(&self) -> Result<rodio::Decoder<SourceReader>, rodio::decoder::DecoderError> {
+        rodio::Decoder::new(self.reader())
+    }
+
+    pub fn is_streamed(&self) -> bool {
+        matches!(self, SourceData::Streamed(_))
+    }
+
+    /// Returns an error naming the format of the data if it can't be decoded, with `what` being
+    /// what the data is to the user.
+    pub fn check(&self, what: &str) -> GameResult {
+        if self.decoder().is_ok() {
+            return Ok(());
+        }
+        let mut header = Vec::new();
+        let _ = self.reader().take(12).read_to_end(&mut header);
+        let message = match detect_format(&header) {
+            Some((format, Some((feature, false)))) => format!(
+                "Could not decode {}, which is {}, as ggez was built without the `{}` feature",
+                what, format, feature
+            ),
+            Some((format, Some(_))) => format!(
+                "Could not decode {}, which is {} but broken or of an unsupported codec",
+                what, format
+            ),
+            Some((format, None)) => format!(
+                "Could not decode {}, which is {}, as ggez doesn't support it",
+                what, format
+            ),
+            None => format!("Could not decode {}, which is of an unknown format", what),
+        };
+        Err(GameError::AudioError(message))
+    }
+}
+
+/// Recognizes the format of a sound from its first bytes, returning its name and the feature
+/// decoding it, if any does, with whether it's enabled.
+fn detect_format(header: &[u8]) -> Option<(&'static str, Option<(&'static str, bool)>)> {
+    let vorbis = Some(("audio-vorbis", cfg!(feature = "audio-vorbis")));
+    let wav = Some(("audio-wav", cfg!(feature = "audio-wav")));
+    let flac = Some(("audio-flac", cfg!(feature = "audio-flac")));
+    let mp3 = Some(("audio-mp3", cfg!(feature = "audio-mp3")));
+    let format = match header {
+        [b'O', b'g', b'g', b'S', ..] => ("Ogg", vorbis),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => ("WAV", wav),
+        [b'f', b'L', b'a', b'C', ..] => ("FLAC", flac),
+        [b'I', b'D', b'3', ..] => ("MP3", mp3),
+        // the sync bits of an MPEG audio frame, without tags before it
+        [0xff, second, ..] if second & 0xe0 == 0xe0 => ("MP3", mp3),
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => ("MP4/AAC", None),
+        [b'F', b'O', b'R', b'M', ..] => ("AIFF", None),
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => ("Matroska/WebM", None),
+        _ => return None,
+    };
+    Some(format)
+}
+
+pub(crate) enum SourceReader {
+    Memory(io::Cursor<SoundData>),
+    Streamed(io::BufReader<StreamedFile>),
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SourceReader::Memory(reader) => reader.read(buf),
+            SourceReader::Streamed(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for SourceReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            SourceReader::Memory(reader) => reader.seek(pos),
+            SourceReader::Streamed(reader) => reader.seek(pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let error = |bytes: &[u8]| {
+            let data = SourceData::Memory(SoundData::from_bytes(bytes));
+            match data.check("the sound") {
+                Err(GameError::AudioError(message)) => message,
+                other => panic!("{:?}", other),
+            }
+        };
+        assert_eq!(
+            error(b"OggS and then garbage"),
+            "Could not decode the sound, which is Ogg but broken or of an unsupported codec"
+        );
+        assert_eq!(
+            error(b"RIFF\0\0\0\0WAVEfmt "),
+            "Could not decode the sound, which is WAV but broken or of an unsupported codec"
+        );
+        assert_eq!(
+            error(b"\0\0\0\x20ftypM4A "),
+            "Could not decode the sound, which is MP4/AAC, as ggez doesn't support it"
+        );
+        assert_eq!(
+            error(b"hello"),
+            "Could not decode the sound, which is of an unknown format"
+        );
+        assert_eq!(detect_format(&[0xff, 0xfb, 0x90]).unwrap().0, "MP3");
+        let wav = SourceData::Memory(SoundData::from_bytes(include_bytes!(
+            "../../resources/pew.wav"
+        )));
+        assert!(wav.check("the sound").is_ok());
+    }
+
+    #[test]
+    fn generator() {
+        let mut phase = 0.0;
+        let generator = Generator::new(
+            2,
+            1000,
+            Box::new(move |buffer: &mut [f32]| {
+                for frame in buffer.chunks_mut(2) {
+                    frame[0] = phase;
+                    phase += 1.0;
+                }
+            }),
+        );
+        let first: Vec<_> = generator.samples().take(1030).collect();
+        assert_eq!(first[..4], [0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(first[1028], 514.0);
+        // the sounds of a source share the generator
+        assert_eq!(generator.samples().next(), Some(1024.0));
+    }
+
+    #[test]
+    fn streamed_file() {
+        let path = std::env::temp_dir().join("ggez_streamed_file_test");
+        std::fs::write(&path, b"0123456789").unwrap();
+        let file = File::VfsFile(Box::new(std::fs::File::open(&path).unwrap()));
+        let mut first = StreamedFile::new(file);
+        let mut second = first.clone();
+
+        // clones read from their own positions
+        let mut buf = [0; 4];
+        first.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"0123");
+        assert_eq!(second.seek(SeekFrom::End(-3)).unwrap(), 7);
+        second.read_exact(&mut buf[..3]).unwrap();
+        assert_eq!(&buf[..3], b"789");
+        first.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"4567");
+        assert!(first.seek(SeekFrom::Current(-9)).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -5,12 +5,16 @@
 //! which specifies hardware setup stuff, mostly video display settings.
 //!
 //! By default a ggez game will search its resource paths for a `/conf.toml`
-//! file and load values from it when the [`Context`](../struct.Context.html) is created.  This file
-//! must be complete (ie you cannot just fill in some fields and have the
-//! rest be default) and provides a nice way to specify settings that
-//! can be tweaked such as window resolution, multisampling options, etc.
-//! If no file is found, it will create a `Conf` object from the settings
-//! passed to the [`ContextBuilder`](../struct.ContextBuilder.html).
+//! file and load values from it when the [`Context`](../struct.Context.html) is created.  This
+//! provides a nice way to specify settings that can be tweaked such as window resolution,
+//! multisampling options, etc.  Fields missing from the file are taken from the settings
+//! passed to the [`ContextBuilder`](../struct.ContextBuilder.html), as is everything if no
+//! file is found.
+//!
+//! Keys in the file which aren't used, e.g. as they're misspelled, are warned about with where
+//! they are, and files written by older versions of ggez, with an older
+//! [`conf_version`](struct.Conf.html#structfield.conf_version), have the fields which were
+//! renamed since translated.
 
 use std::convert::TryFrom;
 use std::io;
@@ -56,6 +60,11 @@ pub enum FullscreenType {
 ///     visible: true,
 ///     transparent: false,
 ///     resize_on_scale_factor_change: false,
+///     monitor: None,
+///     minimize_on_focus_loss: false,
+///     position: None,
+///     centered: false,
+///     always_on_top: false,
 ///     logical_size: None,
 /// }
 /// # , WindowMode::default());}
@@ -79,21 +88,32 @@ pub struct WindowMode {
     /// Whether or not to show window decorations
     #[default = false]
     pub borderless: bool,
-    /// Whether or not the window should be transparent
+    /// Whether or not the window should be transparent, i.e. let regions of the frame with an alpha below 1
+    /// (such as a [`Canvas`](crate::graphics::Canvas) cleared to [`Color::new(0., 0., 0., 0.)`](crate::graphics::Color::new))
+    /// show what's behind it. Platform support varies and this can only be set when creating the window,
+    /// as the way the window surface is composited has to be picked before the first frame.
+    ///
+    /// Where it isn't supported the window is opaque instead, see
+    /// [`GraphicsContext::is_transparent`](crate::graphics::GraphicsContext::is_transparent).
+    /// With [`borderless`](#structfield.borderless) too, only what's drawn shows.
     #[default = false]
     pub transparent: bool,
-    /// Minimum width for resizable windows; 1 is the technical minimum,
+    /// Minimum width for resizable windows in physical pixels; 1 is the technical minimum,
     /// as wgpu will panic on a width of 0.
+    ///
+    /// The limits apply to windowed mode, from when the window is created and again each time it
+    /// returns to it, with sizes outside of them clamped into them with a warning logged.
     #[default = 1.0]
     pub min_width: f32,
-    /// Minimum height for resizable windows; 1 is the technical minimum,
+    /// Minimum height for resizable windows in physical pixels; 1 is the technical minimum,
     /// as wgpu will panic on a height of 0.
     #[default = 1.0]
     pub min_height: f32,
-    /// Maximum width for resizable windows; 0 means no limit
+    /// Maximum width for resizable windows in physical pixels; 0 means no limit, as does a
+    /// `max_height` of 0. A max below the min is raised to it.
     #[default = 0.0]
     pub max_width: f32,
-    /// Maximum height for resizable windows; 0 means no limit
+    /// Maximum height for resizable windows in physical pixels; 0 means no limit
     #[default = 0.0]
     pub max_height: f32,
     /// Whether or not the window is resizable
@@ -113,6 +133,37 @@ pub struct WindowMode {
     /// For more context on this take a look at [this conversation](https://github.com/ggez/ggez/pull/949#issuecomment-854731226).
     #[default = false]
     pub resize_on_scale_factor_change: bool,
+    /// Index of the monitor (see [`GraphicsContext::monitors`](crate::graphics::GraphicsContext::monitors))
+    /// the window goes fullscreen on, or is centered on in windowed mode.
+    /// `None` uses the monitor the window currently is on.
+    ///
+    /// Falls back to the primary monitor if there is no monitor with this index (anymore).
+    #[default(None)]
+    pub monitor: Option<usize>,
+    /// Whether to minimize the window when it loses focus (e.g. on alt-tab) in exclusive fullscreen.
+    ///
+    /// Note that some platforms minimize exclusive fullscreen windows on their own regardless.
+    #[default = false]
+    pub minimize_on_focus_loss: bool,
+    /// Initial position of the window's top-left corner (including the outer frame) in logical pixels,
+    /// on the virtual desktop spanning all monitors, see
+    /// [`GraphicsContext::window_position`](crate::graphics::GraphicsContext::window_position).
+    /// `None` lets the OS decide, or centers the window if [`centered`](#structfield.centered) is set.
+    ///
+    /// It's used when creating the window, and again whenever it returns to windowed mode from fullscreen.
+    /// Wayland doesn't let windows position themselves, so it's ignored there, with a debug message logged.
+    #[default(None)]
+    pub position: Option<(f32, f32)>,
+    /// Whether to center the window on its [`monitor`](#structfield.monitor) when it's created and
+    /// whenever it returns to windowed mode from fullscreen, unless [`position`](#structfield.position)
+    /// is set. As with `position`, it's ignored on Wayland.
+    #[default = false]
+    #[serde(default)]
+    pub centered: bool,
+    /// Whether the window floats above all other windows, see
+    /// [`GraphicsContext::set_window_level`](crate::graphics::GraphicsContext::set_window_level).
+    #[default = false]
+    pub always_on_top: bool,
     // logical_size is serialized as a table, so it must be at the end of the struct for toml
     /// Window height/width but allows LogicalSize for high DPI systems. If Some will be used instead of width/height.
     #[default(None)]
@@ -154,6 +205,7 @@ impl WindowMode {
     }
 
     /// Set whether a window should be transparent.
+    #[must_use]
     pub fn transparent(mut self, transparent: bool) -> Self {
         self.transparent = transparent;
         self
@@ -201,6 +253,67 @@ impl WindowMode {
         self
     }
 
+    /// Set the index of the monitor to use, see [`WindowMode::monitor`](#structfield.monitor).
+    #[must_use]
+    pub fn monitor(mut self, monitor: impl Into<Option<usize>>) -> Self {
+        self.monitor = monitor.into();
+        self
+    }
+
+    /// Set whether to minimize the window when it loses focus in exclusive fullscreen.
+    #[must_use]
+    pub fn minimize_on_focus_loss(mut self, minimize_on_focus_loss: bool) -> Self {
+        self.minimize_on_focus_loss = minimize_on_focus_loss;
+        self
+    }
+
+    /// Set the initial window position, in logical pixels.
+    #[must_use]
+    pub fn position(mut self, x: f32, y: f32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Set whether to center the window on its monitor.
+    #[must_use]
+    pub fn centered(mut self, centered: bool) -> Self {
+        self.centered = centered;
+        self
+    }
+
+    /// Set whether the window floats above all other windows.
+    #[must_use]
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    // The min and max inner sizes of windowed windows, in physical pixels, with a max below the
+    // min raised to it
+    pub(crate) fn size_limits(&self) -> (PhysicalSize<f64>, Option<PhysicalSize<f64>>) {
+        let min = PhysicalSize::new(
+            f64::from(self.min_width.max(1.0)),
+            f64::from(self.min_height.max(1.0)),
+        );
+        let max = (self.max_width > 0.0 && self.max_height > 0.0).then(|| {
+            PhysicalSize::new(
+                f64::from(self.max_width).max(min.width),
+                f64::from(self.max_height).max(min.height),
+            )
+        });
+        (min, max)
+    }
+
+    // Clamps `size`, in physical pixels, into the size limits
+    pub(crate) fn clamp_size(&self, size: PhysicalSize<f64>) -> PhysicalSize<f64> {
+        let (min, max) = self.size_limits();
+        let max = max.unwrap_or_else(|| PhysicalSize::new(f64::INFINITY, f64::INFINITY));
+        PhysicalSize::new(
+            size.width.clamp(min.width, max.width),
+            size.height.clamp(min.height, max.height),
+        )
+    }
+
     // Use logical_size if set, else convert width/height to PhysicalSize
     pub(crate) fn actual_size(&self) -> GameResult<winit::dpi::Size> {
         let actual_size: winit::dpi::Size = if let Some(logical_size) = self.logical_size {
@@ -245,19 +358,36 @@ pub struct WindowSetup {
     /// The window title.
     #[default(String::from("An easy, good game"))]
     pub title: String,
-    /// Number of samples to use for multisample anti-aliasing.
+    /// Number of samples to use for multisample anti-aliasing of the frame drawn to by
+    /// [`Canvas::from_frame`](crate::graphics::Canvas::from_frame).
+    ///
+    /// If the adapter doesn't support that many for the window surface, fewer are used, with
+    /// a warning logged; [`GraphicsContext::samples`](crate::graphics::GraphicsContext::samples)
+    /// returns how many are.
     #[default(NumSamples::One)]
     pub samples: NumSamples,
     /// Whether or not to enable vsync.
+    ///
+    /// This picks the initial present mode, which can be changed at runtime with
+    /// [`GraphicsContext::set_present_mode`](crate::graphics::GraphicsContext::set_present_mode).
     #[default = true]
     pub vsync: bool,
     /// A file path to the window's icon.
     /// It takes a path rooted in the `resources` directory (see the [`filesystem`](../filesystem/index.html)
     /// module for details), and an empty string results in a blank/default icon.
+    ///
+    /// It's set before the window is first shown, so the default icon never flashes up. An
+    /// icon which is missing or can't be decoded is logged as a warning, and the default one
+    /// is used instead. It can be left out of `conf.toml`, for none.
     #[default(String::new())]
+    #[serde(default)]
     pub icon: String,
     /// Whether or not to enable sRGB (gamma corrected color)
     /// handling on the display.
+    ///
+    /// This picks an sRGB or a linear format for the window surface, or the one it prefers,
+    /// with a warning logged, if it has none of those;
+    /// [`GraphicsContext::srgb`](crate::graphics::GraphicsContext::srgb) returns which it is.
     #[default = true]
     pub srgb: bool,
 }
@@ -301,6 +431,9 @@ impl WindowSetup {
 
 /// Possible graphics backends.
 /// The default is `Primary`.
+///
+/// If the one picked isn't available, e.g. as the drivers for it are broken, the others are
+/// tried, with a warning logged, unless [`AdapterSetup::strict`] is set.
 #[derive(
     Debug,
     Copy,
@@ -336,6 +469,81 @@ pub enum Backend {
     BrowserWebGpu,
 }
 
+/// Whether to prefer a graphics adapter which uses less power or one which is faster, on
+/// machines with more than one, such as laptops with an integrated and a discrete GPU.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    smart_default::SmartDefault,
+)]
+pub enum PowerPreference {
+    /// Prefer an adapter which uses less power, usually an integrated GPU.
+    LowPower,
+    /// Prefer a faster adapter, usually a discrete GPU.
+    #[default]
+    HighPerformance,
+}
+
+/// A builder structure containing which graphics adapter to pick, set at init time.
+///
+/// Defaults:
+///
+/// ```rust
+/// # use ggez::conf::*;
+/// # fn main() { assert_eq!(
+/// AdapterSetup {
+///     power_preference: PowerPreference::HighPerformance,
+///     name: None,
+///     strict: false,
+/// }
+/// # , AdapterSetup::default()); }
+/// ```
+#[derive(
+    Debug, Clone, smart_default::SmartDefault, serde::Serialize, serde::Deserialize, PartialEq, Eq,
+)]
+#[serde(default)]
+pub struct AdapterSetup {
+    /// Whether to prefer an adapter which uses less power or a faster one.
+    pub power_preference: PowerPreference,
+    /// Only use an adapter whose name has this in it, ignoring case, e.g. `"nvidia"`.
+    ///
+    /// Of those which do, the one the power preference prefers is used.
+    pub name: Option<String>,
+    /// Whether to fail with
+    /// [`GameError::GraphicsInitializationError`](crate::GameError::GraphicsInitializationError)
+    /// when the [`Backend`] or the adapter named aren't available, rather than falling back to
+    /// the others with a warning logged.
+    pub strict: bool,
+}
+
+impl AdapterSetup {
+    /// Set whether to prefer an adapter which uses less power or a faster one.
+    #[must_use]
+    pub fn power_preference(mut self, power_preference: PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Set what the name of the adapter to use has to have in it.
+    #[must_use]
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Set whether to fail rather than fall back when the backend or adapter aren't available.
+    #[must_use]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
 /// The possible number of samples for multisample anti-aliasing.
 #[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub enum NumSamples {
@@ -386,9 +594,11 @@ impl From<NumSamples> for u8 {
 /// # use ggez::conf::*;
 /// # fn main() { assert_eq!(
 /// Conf {
+///     conf_version: CONF_VERSION,
 ///     window_mode: WindowMode::default(),
 ///     window_setup: WindowSetup::default(),
 ///     backend: Backend::default(),
+///     adapter: AdapterSetup::default(),
 /// }
 /// # , Conf::default()); }
 /// ```
@@ -396,12 +606,21 @@ impl From<NumSamples> for u8 {
     serde::Serialize, serde::Deserialize, Debug, PartialEq, smart_default::SmartDefault, Clone,
 )]
 pub struct Conf {
+    /// The version of the layout of the file it was read from, [`CONF_VERSION`] once it's read,
+    /// as the fields which were renamed since are translated. Files without one are taken to
+    /// be written before there were versions, with version 0.
+    #[default(CONF_VERSION)]
+    #[serde(default)]
+    pub conf_version: u32,
     /// Window setting information that can be set at runtime
     pub window_mode: WindowMode,
     /// Window setting information that must be set at init-time
     pub window_setup: WindowSetup,
     /// Graphics backend configuration
     pub backend: Backend,
+    /// Which graphics adapter to pick
+    #[serde(default)]
+    pub adapter: AdapterSetup,
 }
 
 impl Conf {
@@ -411,19 +630,152 @@ impl Conf {
     }
 
     /// Load a TOML file from the given `Read` and attempts to parse
-    /// a `Conf` from it.
+    /// a `Conf` from it, with the fields missing from it taken from `Conf::default()`.
     pub fn from_toml_file<R: io::Read>(file: &mut R) -> GameResult<Conf> {
+        Conf::from_toml_file_with_defaults(file, &Conf::default())
+    }
+
+    /// Load a TOML file from the given `Read` and attempts to parse
+    /// a `Conf` from it, with the fields missing from it taken from `defaults`.
+    ///
+    /// Keys which aren't used are ignored with a warning saying where they are, and errors say
+    /// where in the file it's wrong. Fields renamed since the file's
+    /// [`conf_version`](#structfield.conf_version) are translated.
+    pub fn from_toml_file_with_defaults<R: io::Read>(
+        file: &mut R,
+        defaults: &Conf,
+    ) -> GameResult<Conf> {
         let mut s = String::new();
         let _ = file.read_to_string(&mut s)?;
-        let decoded = toml::from_str(&s)?;
-        Ok(decoded)
+        let mut read: toml::value::Table = toml::from_str(&s)?;
+        let version = match read.remove("conf_version") {
+            None => 0,
+            Some(toml::Value::Integer(version)) if (0..=i64::from(u32::MAX)).contains(&version) => {
+                version as u32
+            }
+            Some(version) => {
+                return Err(GameError::ConfigError(format!(
+                    "Invalid conf_version {}{}",
+                    version,
+                    located(&s, "conf_version")
+                )))
+            }
+        };
+        if version > CONF_VERSION {
+            warn!(
+                "The conf file has conf_version {}, newer than this version of ggez's {}",
+                version, CONF_VERSION
+            );
+        }
+        migrate(version, &mut read);
+
+        let mut conf = toml::Value::try_from(defaults)?;
+        merge(conf.as_table_mut().unwrap(/* a struct */), read);
+        let mut unused = Vec::new();
+        let mut conf: Conf = serde_ignored::deserialize(conf, |path| unused.push(path.to_string()))
+            .map_err(|e: toml::de::Error| {
+                // the merged value doesn't know where in the file it's wrong, but which key
+                let message = e.to_string();
+                let location = match message.rsplit_once("for key `") {
+                    Some((_, key)) => located(&s, key.trim_end_matches('`')),
+                    None => String::new(),
+                };
+                GameError::ConfigError(format!("TOML decode error: {}{}", message, location))
+            })?;
+        for path in unused {
+            warn!(
+                "Unknown key `{}` in the conf file{}, ignoring it",
+                path,
+                located(&s, &path)
+            );
+        }
+        conf.conf_version = CONF_VERSION;
+        Ok(conf)
+    }
+
+    /// Sets the fields named by the keys of `overrides`, with dots between the tables and the
+    /// fields in them as in `conf.toml`, e.g. `("window_mode.width", "1280")`, to their values,
+    /// e.g. to run windowed at 1280x720 with vsync off for a test without editing `conf.toml`.
+    ///
+    /// The values are read as TOML, or as strings if they aren't valid TOML, so
+    /// `("window_mode.fullscreen_type", "Desktop")` works as well as with `"\"Desktop\""`, but
+    /// strings which are valid TOML of another type, such as a title of `1942`, have to be
+    /// quoted. Keys which aren't fields and values of the wrong type return an error naming the
+    /// key and, for the latter, the type expected; the overrides before it are still applied.
+    pub fn apply_overrides(
+        &mut self,
+        overrides: impl IntoIterator<Item = (String, String)>,
+    ) -> GameResult {
+        for (key, value) in overrides {
+            let invalid = |why: String| {
+                GameError::ConfigError(format!("Invalid conf override {}={}: {}", key, value, why))
+            };
+            let mut conf = toml::Value::try_from(&*self)?;
+            let mut table = conf.as_table_mut().unwrap(/* a struct */);
+            let mut path = key.split('.').peekable();
+            while let Some(name) = path.next() {
+                if path.peek().is_none() {
+                    let _ = table.insert(name.to_owned(), override_value(&value));
+                    break;
+                }
+                table = match table
+                    .entry(name)
+                    .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+                {
+                    toml::Value::Table(table) => table,
+                    _ => return Err(invalid(format!("`{}` isn't a table", name))),
+                };
+            }
+            let mut unused = Vec::new();
+            let conf: Conf = serde_ignored::deserialize(conf, |path| unused.push(path.to_string()))
+                .map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+            if let Some(path) = unused.first() {
+                return Err(invalid(format!("there is no key `{}`", path)));
+            }
+            *self = conf;
+        }
+        Ok(())
+    }
+
+    /// Returns `Conf::default()` with the overrides of [`env_overrides`] applied, e.g. the
+    /// width of the window set with `GGEZ_WINDOW_MODE__WIDTH=1280` for a `prefix` of `"GGEZ"`.
+    pub fn from_env(prefix: &str) -> GameResult<Conf> {
+        let mut conf = Conf::default();
+        conf.apply_overrides(env_overrides(prefix))?;
+        Ok(conf)
+    }
+
+    /// Returns `Conf::default()` with the overrides of [`arg_overrides`] applied, along with
+    /// the arguments which weren't overrides.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> GameResult<(Conf, Vec<String>)> {
+        let (overrides, rest) = arg_overrides(args)?;
+        let mut conf = Conf::default();
+        conf.apply_overrides(overrides)?;
+        Ok((conf, rest))
     }
 
     /// Saves the `Conf` to the given `Write` object,
-    /// formatted as TOML.
+    /// formatted as TOML, with comments saying what each of the fields is.
     pub fn to_toml_file<W: io::Write>(&self, file: &mut W) -> GameResult {
-        let s = toml::to_vec(self)?;
-        file.write_all(&s)?;
+        let s = toml::to_string(self)?;
+        let mut table = "";
+        for line in s.lines() {
+            let path = match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                Some(header) => {
+                    table = header;
+                    header.to_owned()
+                }
+                None => match line.split_once(" = ") {
+                    Some((key, _)) if table.is_empty() => key.to_owned(),
+                    Some((key, _)) => format!("{}.{}", table, key),
+                    None => String::new(),
+                },
+            };
+            if let Some((_, doc)) = FIELD_DOCS.iter().find(|(field, _)| *field == path) {
+                writeln!(file, "# {}", doc)?;
+            }
+            writeln!(file, "{}", line)?;
+        }
         Ok(())
     }
 
@@ -440,6 +792,271 @@ impl Conf {
         self.backend = backend;
         self
     }
+
+    /// Sets which graphics adapter to pick
+    #[must_use]
+    pub fn adapter(mut self, adapter: AdapterSetup) -> Self {
+        self.adapter = adapter;
+        self
+    }
+}
+
+/// The version of the layout of the conf files this version of ggez writes, see
+/// [`Conf::conf_version`].
+pub const CONF_VERSION: u32 = 1;
+
+// Fields which were moved or renamed, by the `conf_version` they were in first, so they're
+// translated in files written before.
+const RENAMED: &[(u32, &str, &str)] = &[
+    // which can be changed at runtime since ggez 0.5
+    (1, "window_setup.resizable", "window_mode.resizable"),
+];
+
+// Fields which aren't used anymore, by the `conf_version` they were gone in, so files written
+// before are read without warnings about them.
+const REMOVED: &[(u32, &str)] = &[(1, "window_setup.allow_highdpi")];
+
+// What's written above each of the fields by `Conf::to_toml_file`.
+const FIELD_DOCS: &[(&str, &str)] = &[
+    (
+        "conf_version",
+        "The version of the layout of this file, so older ones can still be read",
+    ),
+    (
+        "window_mode",
+        "Window settings which can be changed while the game runs",
+    ),
+    ("window_mode.width", "Window width in physical pixels"),
+    ("window_mode.height", "Window height in physical pixels"),
+    ("window_mode.maximized", "Whether to maximize the window: true or false"),
+    (
+        "window_mode.fullscreen_type",
+        "\"Windowed\", \"True\" (exclusive) or \"Desktop\" (borderless) fullscreen",
+    ),
+    (
+        "window_mode.borderless",
+        "Whether to leave out the window decorations: true or false",
+    ),
+    (
+        "window_mode.transparent",
+        "Whether what's behind the window shows through where the frame is transparent",
+    ),
+    (
+        "window_mode.min_width",
+        "Minimum width of resizable windows, at least 1",
+    ),
+    (
+        "window_mode.min_height",
+        "Minimum height of resizable windows, at least 1",
+    ),
+    (
+        "window_mode.max_width",
+        "Maximum width of resizable windows, 0 for no limit",
+    ),
+    (
+        "window_mode.max_height",
+        "Maximum height of resizable windows, 0 for no limit",
+    ),
+    ("window_mode.resizable", "Whether the window can be resized: true or false"),
+    ("window_mode.visible", "Whether the window is shown: true or false"),
+    (
+        "window_mode.resize_on_scale_factor_change",
+        "Whether the window is resized when its DPI scale factor changes: true or false",
+    ),
+    (
+        "window_mode.monitor",
+        "Index of the monitor for fullscreen and centering, left out for the current one",
+    ),
+    (
+        "window_mode.minimize_on_focus_loss",
+        "Whether to minimize the window in exclusive fullscreen when it loses focus",
+    ),
+    (
+        "window_mode.position",
+        "Initial position of the window in logical pixels, as [x, y]",
+    ),
+    (
+        "window_mode.centered",
+        "Whether to center the window on its monitor: true or false",
+    ),
+    (
+        "window_mode.always_on_top",
+        "Whether the window floats above all others: true or false",
+    ),
+    (
+        "window_mode.logical_size",
+        "Window size in logical pixels, used instead of width and height if there",
+    ),
+    (
+        "window_setup",
+        "Window settings which can only be set when the game starts",
+    ),
+    ("window_setup.title", "The window title"),
+    (
+        "window_setup.samples",
+        "Samples for multisample anti-aliasing: \"One\" or \"Four\"",
+    ),
+    ("window_setup.vsync", "Whether to wait for vsync: true or false"),
+    (
+        "window_setup.icon",
+        "Path of the window icon in the resources, empty for the default one",
+    ),
+    (
+        "window_setup.srgb",
+        "Whether colors are gamma corrected when displayed: true or false",
+    ),
+    ("backend", "The graphics backend"),
+    (
+        "backend.type",
+        "\"All\", \"OnlyPrimary\", \"Vulkan\", \"Metal\", \"Dx12\", \"Dx11\", \"Gl\" or \"BrowserWebGpu\"",
+    ),
+    ("adapter", "Which graphics adapter to use"),
+    (
+        "adapter.power_preference",
+        "\"LowPower\" or \"HighPerformance\"",
+    ),
+    (
+        "adapter.name",
+        "Only use an adapter with this in its name, ignoring case",
+    ),
+    (
+        "adapter.strict",
+        "Whether to fail rather than fall back if the backend or adapter isn't available",
+    ),
+];
+
+/// Overrides of config options, as pairs of dotted keys and values, see
+/// [`Conf::apply_overrides`].
+pub type ConfOverrides = Vec<(String, String)>;
+
+/// Returns the conf overrides, for [`Conf::apply_overrides`], set with the environment variables
+/// whose names start with `prefix` and `_`, e.g. `GGEZ_WINDOW_MODE__WIDTH=1280` for
+/// `window_mode.width` with a `prefix` of `"GGEZ"`, with `__` between the tables and the fields.
+pub fn env_overrides(prefix: &str) -> ConfOverrides {
+    let prefix = format!("{}_", prefix);
+    let mut overrides: Vec<_> = std::env::vars()
+        .filter_map(|(name, value)| {
+            let key = name
+                .strip_prefix(&prefix)?
+                .to_lowercase()
+                .replace("__", ".");
+            Some((key, value))
+        })
+        .collect();
+    // the same whatever order the environment is in
+    overrides.sort();
+    overrides
+}
+
+/// Returns the conf overrides, for [`Conf::apply_overrides`], given to the game as pairs of
+/// arguments such as `--ggez window_mode.width=1280`, along with the other arguments, e.g.
+/// `std::env::args()`'s, in order.
+pub fn arg_overrides(
+    args: impl IntoIterator<Item = String>,
+) -> GameResult<(ConfOverrides, Vec<String>)> {
+    let mut overrides = Vec::new();
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg != "--ggez" {
+            rest.push(arg);
+            continue;
+        }
+        match args.next().as_deref().and_then(|arg| arg.split_once('=')) {
+            Some((key, value)) => overrides.push((key.to_owned(), value.to_owned())),
+            None => {
+                return Err(GameError::ConfigError(String::from(
+                    "Expected key=value after --ggez",
+                )))
+            }
+        }
+    }
+    Ok((overrides, rest))
+}
+
+// Reads the value of an override as TOML, or as a string if it isn't valid TOML.
+fn override_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()))
+}
+
+// Translates the fields of a conf file written with `version` which were renamed since.
+fn migrate(version: u32, conf: &mut toml::value::Table) {
+    for &(since, from, to) in RENAMED {
+        if version < since {
+            if let Some(value) = take(conf, from) {
+                let (table, key) = to.split_once('.').unwrap(/* in a table */);
+                if let toml::Value::Table(table) = conf
+                    .entry(table)
+                    .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+                {
+                    let _ = table.entry(key).or_insert(value);
+                }
+            }
+        }
+    }
+    for &(since, path) in REMOVED {
+        if version < since {
+            let _ = take(conf, path);
+        }
+    }
+    if version < 1 {
+        // before wgpu, the backends were OpenGL and OpenGL ES, with their versions
+        if let Some(toml::Value::Table(backend)) = conf.get_mut("backend") {
+            if let Some("OpenGL" | "OpenGLES") = backend.get("type").and_then(|t| t.as_str()) {
+                backend.clear();
+                let _ = backend.insert(String::from("type"), toml::Value::from("Gl"));
+            }
+        }
+    }
+}
+
+// Takes the field at `path`, e.g. `"window_setup.resizable"`, out of `conf`.
+fn take(conf: &mut toml::value::Table, path: &str) -> Option<toml::Value> {
+    let (table, key) = path.split_once('.')?;
+    conf.get_mut(table)?.as_table_mut()?.remove(key)
+}
+
+// Puts what's in `over` into `base`, table by table.
+fn merge(base: &mut toml::value::Table, over: toml::value::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge(base, over),
+            (_, value) => {
+                let _ = base.insert(key, value);
+            }
+        }
+    }
+}
+
+// Where the key at `path`, e.g. `"window_mode.width"`, is in the TOML `text`, as " at line N",
+// or nothing if it can't be found.
+fn located(text: &str, path: &str) -> String {
+    let mut table = String::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table = header.trim().to_owned();
+            if table == path {
+                return format!(" at line {}", i + 1);
+            }
+        } else if let Some((key, _)) = line.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            let found = if table.is_empty() {
+                key == path
+            } else {
+                path.strip_prefix(table.as_str())
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    == Some(key)
+            };
+            if found {
+                return format!(" at line {}", i + 1);
+            }
+        }
+    }
+    String::new()
 }
 
 #[cfg(test)]
@@ -457,4 +1074,205 @@ mod tests {
         let c2 = conf::Conf::from_toml_file(&mut reader).unwrap();
         assert_eq!(c1, c2);
     }
+
+    #[test]
+    fn headless_window_icon() {
+        let mut c1 = conf::Conf::new();
+        c1.window_setup = c1.window_setup.icon("/icon.png");
+        let mut writer = Vec::new();
+        c1.to_toml_file(&mut writer).unwrap();
+        let text = String::from_utf8(writer).unwrap();
+        assert!(text.contains("icon = \"/icon.png\""));
+        let c2 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert_eq!(c1, c2);
+
+        // written before there was an icon
+        let text = text.replace("icon = \"/icon.png\"", "");
+        let c3 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert_eq!(c3.window_setup.icon, "");
+    }
+
+    #[test]
+    fn headless_window_position() {
+        let mut c1 = conf::Conf::new();
+        c1.window_mode = c1.window_mode.position(-120.5, 40.0).centered(true);
+        let mut writer = Vec::new();
+        c1.to_toml_file(&mut writer).unwrap();
+        let text = String::from_utf8(writer).unwrap();
+        assert!(text.contains("position = [-120.5, 40.0]"));
+        let c2 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert_eq!(c1, c2);
+
+        // written before windows could be centered
+        let text = text.replace("centered = true", "");
+        let c3 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert!(!c3.window_mode.centered);
+    }
+
+    #[test]
+    fn headless_adapter_setup() {
+        let c1 = conf::Conf::new().backend(conf::Backend::Gl).adapter(
+            conf::AdapterSetup::default()
+                .power_preference(conf::PowerPreference::LowPower)
+                .name("intel")
+                .strict(true),
+        );
+        let mut writer = Vec::new();
+        c1.to_toml_file(&mut writer).unwrap();
+        let text = String::from_utf8(writer).unwrap();
+        assert!(text.contains("[adapter]"));
+        let c2 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert_eq!(c1, c2);
+
+        // written before the adapter could be picked, which is the last table
+        let (text, _) = text.split_once("[adapter]").unwrap();
+        let c3 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert_eq!(c3.adapter, conf::AdapterSetup::default());
+    }
+
+    #[test]
+    fn headless_partial_and_old_files() {
+        let defaults =
+            conf::Conf::new().window_mode(conf::WindowMode::default().dimensions(1024., 768.));
+
+        // missing fields are taken from the defaults, one by one
+        let text = "[window_mode]\nwidth = 640\nfullscren_type = \"True\"\n\n[window_setup]\ntitle = \"Hi\"\n";
+        let c1 = conf::Conf::from_toml_file_with_defaults(&mut text.as_bytes(), &defaults).unwrap();
+        assert_eq!(c1.window_mode.width, 640.);
+        assert_eq!(c1.window_mode.height, 768.);
+        assert_eq!(
+            c1.window_mode.fullscreen_type,
+            conf::FullscreenType::Windowed
+        );
+        assert_eq!(c1.window_setup.title, "Hi");
+        assert_eq!(c1.backend, conf::Backend::All);
+        assert_eq!(c1.conf_version, conf::CONF_VERSION);
+        assert_eq!(
+            super::located(text, "window_mode.fullscren_type"),
+            " at line 3"
+        );
+
+        // errors say where in the file it's wrong
+        let text = "[window_mode]\nwidth = \"big\"\n";
+        let e = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap_err();
+        assert!(e.to_string().contains("at line 2"), "{}", e);
+        let text = "[window_mode]\nwidth 640\n";
+        let e = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap_err();
+        assert!(e.to_string().contains("line 2"), "{}", e);
+
+        // written before there were versions, and before wgpu
+        let text = "[window_setup]\nresizable = true\nallow_highdpi = true\n\n[backend]\ntype = \"OpenGL\"\nmajor = 3\nminor = 2\n";
+        let c2 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert!(c2.window_mode.resizable);
+        assert_eq!(c2.backend, conf::Backend::Gl);
+        // but not once they were renamed
+        let text = format!(
+            "conf_version = {}\n{}",
+            conf::CONF_VERSION,
+            text.replace("OpenGL", "Gl")
+        );
+        let c3 = conf::Conf::from_toml_file(&mut text.as_bytes()).unwrap();
+        assert!(!c3.window_mode.resizable);
+    }
+
+    #[test]
+    fn headless_commented_file() {
+        let c1 = conf::Conf::new();
+        let mut writer = Vec::new();
+        c1.to_toml_file(&mut writer).unwrap();
+        let text = String::from_utf8(writer).unwrap();
+        assert!(text.starts_with("# The version of the layout of this file"));
+        assert!(text.contains("# Window width in physical pixels\nwidth = 800.0\n"));
+        // every field is described
+        let keys = text
+            .lines()
+            .filter(|line| line.contains(" = ") || line.starts_with('['));
+        let docs = text.lines().filter(|line| line.starts_with('#'));
+        assert_eq!(keys.count(), docs.count());
+    }
+
+    #[test]
+    fn headless_overrides() {
+        let pairs = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+        let mut c1 = conf::Conf::new();
+        c1.apply_overrides(pairs(&[
+            ("window_mode.width", "1280"),
+            ("window_mode.fullscreen_type", "Desktop"),
+            ("window_mode.position", "[10, 20]"),
+            ("window_setup.vsync", "false"),
+            ("window_setup.title", "\"1942\""),
+            ("adapter.name", "nvidia"),
+        ]))
+        .unwrap();
+        assert_eq!(c1.window_mode.width, 1280.);
+        assert_eq!(
+            c1.window_mode.fullscreen_type,
+            conf::FullscreenType::Desktop
+        );
+        assert_eq!(c1.window_mode.position, Some((10., 20.)));
+        assert!(!c1.window_setup.vsync);
+        assert_eq!(c1.window_setup.title, "1942");
+        assert_eq!(c1.adapter.name.as_deref(), Some("nvidia"));
+
+        let e = c1
+            .apply_overrides(pairs(&[("window_setup.vsync", "sometimes")]))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            e.contains("window_setup.vsync") && e.contains("bool"),
+            "{}",
+            e
+        );
+        let e = c1
+            .apply_overrides(pairs(&[("window_mode.widht", "1280")]))
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("window_mode.widht"), "{}", e);
+        assert!(c1
+            .apply_overrides(pairs(&[("window_mode.width.px", "1280")]))
+            .is_err());
+
+        let args = ["game", "--ggez", "window_mode.height=720", "--level", "2"];
+        let (c2, rest) = conf::Conf::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(c2.window_mode.height, 720.);
+        assert_eq!(rest, ["game", "--level", "2"]);
+        assert!(conf::arg_overrides(vec![String::from("--ggez")]).is_err());
+
+        std::env::set_var("GGEZTEST_WINDOW_SETUP__SRGB", "false");
+        let c3 = conf::Conf::from_env("GGEZTEST").unwrap();
+        assert!(!c3.window_setup.srgb);
+    }
+
+    #[test]
+    fn headless_size_limits() {
+        use winit::dpi::PhysicalSize;
+
+        let mode = conf::WindowMode::default()
+            .min_dimensions(320., 240.)
+            .max_dimensions(1024., 768.);
+        let clamp = |width, height| mode.clamp_size(PhysicalSize::new(width, height));
+        assert_eq!(clamp(100., 2000.), PhysicalSize::new(320., 768.));
+        assert_eq!(clamp(2000., 100.), PhysicalSize::new(1024., 240.));
+        assert_eq!(clamp(640., 480.), PhysicalSize::new(640., 480.));
+
+        // without a max only the min applies, and a max below the min is raised to it
+        let mode = mode.max_dimensions(0., 0.);
+        assert_eq!(
+            mode.clamp_size(PhysicalSize::new(100., 5000.)),
+            PhysicalSize::new(320., 5000.)
+        );
+        let mode = mode.max_dimensions(200., 800.);
+        assert_eq!(
+            mode.size_limits(),
+            (
+                PhysicalSize::new(320., 240.),
+                Some(PhysicalSize::new(320., 800.))
+            )
+        );
+    }
 }
@@ -1,18 +1,45 @@
 //! The `context` module contains functions and traits related to using the `Context` type.
 
+use std::any::Any;
 use std::fmt;
-/// We re-export winit so it's easy for people to use the same version as we are
-/// without having to mess around figuring it out.
+use std::sync::mpsc;
+/// The winit crate, of the version ggez uses, so integrations get the very types ggez's
+/// window and events are made of without guessing the version in their own `Cargo.toml`.
+///
+/// Everything of winit's which ggez's API takes or returns, like the window of
+/// [`GraphicsContext::window()`](crate::graphics::GraphicsContext::window), the events of
+/// [`EventHandler::raw_window_event`](crate::event::EventHandler::raw_window_event) and the
+/// cursor icons of [`MouseContext`](crate::input::mouse::MouseContext), is of this crate:
+///
+/// ```rust,no_run
+/// use ggez::winit::window::CursorIcon;
+///
+/// # fn main() -> ggez::GameResult {
+/// let (mut ctx, _event_loop) = ggez::ContextBuilder::new("game", "me").build()?;
+/// // the same type as `ggez::input::mouse::CursorIcon`
+/// ctx.mouse.set_cursor_icon(CursorIcon::Hand);
+/// assert_eq!(ctx.mouse.cursor_type(), CursorIcon::Hand);
+/// let window: &ggez::winit::window::Window = ctx.gfx.window();
+/// window.set_ime_allowed(true);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The re-export is exempt from semver: it's whichever winit ggez is built with, so a minor
+/// version of ggez may move it to a new winit, breaking code using it directly.
 pub use winit;
 
 #[cfg(feature = "audio")]
 use crate::audio;
 use crate::conf;
 use crate::error::GameResult;
+use crate::event::{EventSender, ExitReason, LoopMode, UnfocusedBehavior};
 use crate::filesystem::Filesystem;
 use crate::graphics;
 use crate::graphics::GraphicsContext;
 use crate::input;
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad_backend::GamepadBackend;
 use crate::timer;
 
 /// A `Context` is an object that holds on to global resources.
@@ -49,6 +76,10 @@ pub struct Context {
     pub keyboard: input::keyboard::KeyboardContext,
     /// Mouse input context.
     pub mouse: input::mouse::MouseContext,
+    /// Touch input context.
+    pub touch: input::touch::TouchContext,
+    /// Input recording and playback.
+    pub recording: input::recording::RecordingContext,
     /// Gamepad input context.
     #[cfg(feature = "gamepad")]
     pub gamepad: input::gamepad::GamepadContext,
@@ -66,6 +97,61 @@ pub struct Context {
     ///
     /// It's exposed here for people who want to roll their own event loop.
     pub quit_requested: bool,
+    // why, if the game is quitting, and whether `quit_event` cancelled it
+    exit_reason: Option<ExitReason>,
+    pub(crate) quit_cancelled: bool,
+    unfocused_behavior: UnfocusedBehavior,
+    // between `suspend_event` and `resume_event`
+    suspended: bool,
+    // on Android the app starts suspended, until its window is there to draw in, which isn't
+    // a resume
+    pub(crate) launching: bool,
+    loop_mode: LoopMode,
+    // another frame should run right after this one, when waiting for events
+    pub(crate) redraw_requested: bool,
+    // wakes the event loop for the events sent from other threads, if there is one
+    event_proxy: Option<winit::event_loop::EventLoopProxy<()>>,
+    // the channel those events are sent through, of the one type there is
+    user_events: Option<Box<dyn Any>>,
+    modules: ModuleReport,
+}
+
+/// Whether a part of ggez needing a device started, see [`Context::module_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleStatus {
+    /// It started and works.
+    Available,
+    /// There are no devices for it in a context made by [`ContextBuilder::headless`].
+    Headless,
+    /// It couldn't start, why being the error's message, and was left without a device since
+    /// it wasn't required, as with [`ContextBuilder::require_audio`].
+    Failed(String),
+    /// The feature it needs isn't enabled.
+    Disabled,
+}
+
+impl ModuleStatus {
+    /// Returns whether the module is [`Available`](ModuleStatus::Available).
+    pub fn is_available(&self) -> bool {
+        *self == ModuleStatus::Available
+    }
+}
+
+/// Which of the parts of ggez needing a device started, e.g. to tell players "audio
+/// unavailable", see [`Context::module_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleReport {
+    /// Whether sound plays on an output device.
+    pub audio: ModuleStatus,
+    /// Whether gamepads are read.
+    pub gamepad: ModuleStatus,
+}
+
+// The channel user events are sent through, kept in an `Any` since the type of them is only
+// known when they're sent or received.
+struct UserEvents<T> {
+    sender: mpsc::Sender<T>,
+    receiver: mpsc::Receiver<T>,
 }
 
 impl Context {
@@ -74,8 +160,237 @@ impl Context {
     /// returns `Ok(false)`, then [`Context.continuing`](struct.Context.html#structfield.continuing)
     /// is set to `false` and the loop breaks.
     pub fn request_quit(&mut self) {
+        self.request_quit_with(ExitReason::UserAction);
+    }
+
+    /// Like [`request_quit`](#method.request_quit), with why the game is quitting, which
+    /// [`event::run()`](crate::event::run) exits the process with the code of, e.g.
+    /// `ExitReason::Code(2)` for a launcher to see that something went wrong.
+    ///
+    /// The state is dropped before the process exits, so saves in its `Drop` are flushed.
+    pub fn request_quit_with(&mut self, reason: ExitReason) {
+        self.set_exit_reason(reason);
         self.quit_requested = true;
     }
+
+    /// Cancels quitting, from [`quit_event`](crate::event::EventHandler::quit_event), as if it
+    /// returned `Ok(true)`, or a quit requested for the next frame.
+    pub fn cancel_quit(&mut self) {
+        self.quit_cancelled = true;
+        self.quit_requested = false;
+        self.exit_reason = None;
+    }
+
+    /// Returns why the game is quitting, e.g. in
+    /// [`quit_event`](crate::event::EventHandler::quit_event), or `None` if it isn't.
+    pub fn exit_reason(&self) -> Option<&ExitReason> {
+        self.exit_reason.as_ref()
+    }
+
+    /// Sets what [`event::run()`](crate::event::run) does while the window isn't focused, e.g.
+    /// pausing the game and drawing at 10 FPS, which takes effect the next time it loses focus.
+    ///
+    /// The frame rate is set with
+    /// [`TimeContext::set_unfocused_target_fps`](crate::timer::TimeContext::set_unfocused_target_fps),
+    /// which can also be set on its own.
+    pub fn set_unfocused_behavior(&mut self, behavior: UnfocusedBehavior) {
+        self.unfocused_behavior = behavior;
+        self.time.set_unfocused_target_fps(behavior.target_fps);
+    }
+
+    /// Returns what [`event::run()`](crate::event::run) does while the window isn't focused.
+    pub fn unfocused_behavior(&self) -> UnfocusedBehavior {
+        self.unfocused_behavior
+    }
+
+    /// Sets whether [`event::run()`](crate::event::run) runs frames one after the other, or waits
+    /// for events in between, e.g. in an editor, to not draw an unchanged UI over and over again.
+    pub fn set_loop_mode(&mut self, mode: LoopMode) {
+        self.loop_mode = mode;
+    }
+
+    /// Returns whether [`event::run()`](crate::event::run) waits for events in between frames.
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+
+    /// Runs another frame right after this one rather than waiting for an event, in
+    /// [`LoopMode::WaitForEvents`], e.g. on every frame of an animation until it's done.
+    /// Frames run one after the other anyway in [`LoopMode::Continuous`].
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    /// Returns whether the audio and gamepads started, which they may not have if they weren't
+    /// required, see [`ContextBuilder::require_audio`] and [`ContextBuilder::require_gamepad`].
+    pub fn module_status(&self) -> &ModuleReport {
+        &self.modules
+    }
+
+    /// Returns the audio context if sound plays on a device, or `None` if it failed to start or
+    /// the context is headless. [`Context::audio`] is there either way, playing nowhere then.
+    #[cfg(feature = "audio")]
+    pub fn audio_opt(&self) -> Option<&audio::AudioContext> {
+        self.modules.audio.is_available().then_some(&self.audio)
+    }
+
+    /// Returns the audio context mutably if sound plays on a device, see
+    /// [`audio_opt`](Self::audio_opt).
+    #[cfg(feature = "audio")]
+    pub fn audio_opt_mut(&mut self) -> Option<&mut audio::AudioContext> {
+        self.modules.audio.is_available().then_some(&mut self.audio)
+    }
+
+    /// Returns the gamepad context if gamepads are read, or `None` if reading them failed to
+    /// start. [`Context::gamepad`] is there either way, without any gamepads then.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_opt(&self) -> Option<&input::gamepad::GamepadContext> {
+        self.modules.gamepad.is_available().then_some(&self.gamepad)
+    }
+
+    /// Returns the gamepad context mutably if gamepads are read, see
+    /// [`gamepad_opt`](Self::gamepad_opt).
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_opt_mut(&mut self) -> Option<&mut input::gamepad::GamepadContext> {
+        self.modules
+            .gamepad
+            .is_available()
+            .then_some(&mut self.gamepad)
+    }
+
+    /// Borrows the sub-contexts separately, so each can be handed to what uses it at the same
+    /// time, e.g. the graphics to a render system and the audio to a sound system of an ECS,
+    /// rather than the whole context to each in turn:
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::{Canvas, Color};
+    /// # fn f(ctx: &mut ggez::Context, sound: &mut ggez::audio::Source) -> ggez::GameResult {
+    /// use ggez::audio::SoundSource;
+    ///
+    /// let parts = ctx.split();
+    /// let canvas = Canvas::from_frame(&*parts.gfx, Color::BLACK);
+    /// sound.set_volume(parts.mouse.position().x / parts.gfx.drawable_size().0);
+    /// sound.play(&*parts.audio)?;
+    /// canvas.finish(parts.gfx)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The parts are also a [`Has`] of the graphics, filesystem and audio contexts, for what
+    /// takes them together.
+    pub fn split(&mut self) -> ContextParts<'_> {
+        ContextParts {
+            fs: &mut self.fs,
+            gfx: &mut self.gfx,
+            time: &self.time,
+            #[cfg(feature = "audio")]
+            audio: &mut self.audio,
+            keyboard: &mut self.keyboard,
+            mouse: &mut self.mouse,
+            touch: &mut self.touch,
+            #[cfg(feature = "gamepad")]
+            gamepad: &mut self.gamepad,
+        }
+    }
+
+    /// Returns whether the app is suspended, between
+    /// [`suspend_event`](crate::event::EventHandler::suspend_event) and
+    /// [`resume_event`](crate::event::EventHandler::resume_event).
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    // Lets go of the window surface and pauses the sound as the app is suspended.
+    pub(crate) fn suspend(&mut self) {
+        self.suspended = true;
+        self.gfx.suspend();
+        #[cfg(feature = "audio")]
+        self.audio.set_suspended(true);
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.suspended = false;
+        self.gfx.resume();
+        #[cfg(feature = "audio")]
+        self.audio.set_suspended(false);
+        // the time suspended isn't owed to updates, so the game doesn't fast-forward
+        self.time.skip_paused_time();
+    }
+
+    // Whether the game is paused as the window isn't focused.
+    pub(crate) fn updates_paused(&self) -> bool {
+        self.unfocused_behavior.pause_updates && !self.time.is_focused()
+    }
+
+    pub(crate) fn set_exit_reason(&mut self, reason: ExitReason) {
+        self.exit_reason = Some(reason);
+    }
+
+    pub(crate) fn take_exit_reason(&mut self) -> Option<ExitReason> {
+        self.exit_reason.take()
+    }
+
+    /// Returns an [`EventSender`] to send events of type `T` from other threads with, which
+    /// wake the event loop and are passed to
+    /// [`EventHandler::user_event`](crate::event::EventHandler::user_event).
+    ///
+    /// There is one type of them per game, which the event handler takes, so asking for a
+    /// sender of another type stops the earlier senders from sending.
+    pub fn event_sender<T: Send + 'static>(&mut self) -> EventSender<T> {
+        let sender = self.user_events_channel::<T>().sender.clone();
+        EventSender::new(sender, self.event_proxy.clone())
+    }
+
+    /// Returns the events of type `T` sent with an [`EventSender`] and not received yet.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](crate::event::run) calls it whenever the event loop is woken by one,
+    /// with an `Event::UserEvent`. You only need to call this function if you're writing your
+    /// own custom event loop.
+    pub fn user_events<T: Send + 'static>(&mut self) -> Vec<T> {
+        self.user_events_channel::<T>()
+            .receiver
+            .try_iter()
+            .collect()
+    }
+
+    /// Ends the frame in progress, if there is one, and begins the next, doing what
+    /// [`event::run()`](crate::event::run) does between frames: time is ticked with the time
+    /// that passed, input pressed before is no longer "just pressed", and key and button
+    /// repeats are updated.
+    ///
+    /// This is for contexts made with [`ContextBuilder::headless()`], or your own event loop.
+    /// What's drawn in a frame is only done at its end, so read the pixels of an image drawn
+    /// to, with [`Image::to_pixels()`](crate::graphics::Image::to_pixels), after this.
+    pub fn advance_frame(&mut self) -> GameResult {
+        if self.gfx.fcx.is_some() {
+            self.gfx.end_frame()?;
+        }
+        self.mouse.reset_delta();
+        self.keyboard.save_keyboard_state();
+        self.mouse.save_mouse_state();
+        self.touch.save_touch_state();
+        #[cfg(feature = "gamepad")]
+        self.gamepad.save_gamepad_state();
+
+        self.time.tick();
+        self.keyboard.update_repeats(self.time.unscaled_delta());
+        #[cfg(feature = "gamepad")]
+        self.gamepad.update_repeats(self.time.unscaled_delta());
+        self.gfx.begin_frame()
+    }
+
+    fn user_events_channel<T: Send + 'static>(&mut self) -> &UserEvents<T> {
+        let is_t = matches!(&self.user_events, Some(events) if events.is::<UserEvents<T>>());
+        if !is_t {
+            let (sender, receiver) = mpsc::channel::<T>();
+            self.user_events = Some(Box::new(UserEvents { sender, receiver }));
+        }
+        self.user_events
+            .as_ref()
+            .and_then(|events| events.downcast_ref())
+            .expect("the user events were just made of this type")
+    }
 }
 
 // This is ugly and hacky but greatly improves ergonomics.
@@ -158,6 +473,71 @@ impl HasMut<GraphicsContext> for Context {
     }
 }
 
+// The graphics context keeps the filesystem it loads images and shaders from.
+impl Has<Filesystem> for GraphicsContext {
+    #[inline]
+    fn retrieve(&self) -> &Filesystem {
+        &self.fs
+    }
+}
+
+/// The sub-contexts of a [`Context`], borrowed separately, see [`Context::split`].
+pub struct ContextParts<'a> {
+    /// Filesystem state.
+    pub fs: &'a mut Filesystem,
+    /// Graphics state.
+    pub gfx: &'a mut GraphicsContext,
+    /// Timer state, which only the event loop changes.
+    pub time: &'a timer::TimeContext,
+    /// Audio context.
+    #[cfg(feature = "audio")]
+    pub audio: &'a mut audio::AudioContext,
+    /// Keyboard input context.
+    pub keyboard: &'a mut input::keyboard::KeyboardContext,
+    /// Mouse input context.
+    pub mouse: &'a mut input::mouse::MouseContext,
+    /// Touch input context.
+    pub touch: &'a mut input::touch::TouchContext,
+    /// Gamepad input context.
+    #[cfg(feature = "gamepad")]
+    pub gamepad: &'a mut input::gamepad::GamepadContext,
+}
+
+impl Has<Filesystem> for ContextParts<'_> {
+    #[inline]
+    fn retrieve(&self) -> &Filesystem {
+        self.fs
+    }
+}
+
+impl Has<GraphicsContext> for ContextParts<'_> {
+    #[inline]
+    fn retrieve(&self) -> &GraphicsContext {
+        self.gfx
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Has<audio::AudioContext> for ContextParts<'_> {
+    #[inline]
+    fn retrieve(&self) -> &audio::AudioContext {
+        self.audio
+    }
+}
+
+impl HasMut<GraphicsContext> for ContextParts<'_> {
+    #[inline]
+    fn retrieve_mut(&mut self) -> &mut GraphicsContext {
+        self.gfx
+    }
+}
+
+impl fmt::Debug for ContextParts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<ContextParts: {:p}>", self)
+    }
+}
+
 impl fmt::Debug for Context {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "<Context: {:p}>", self)
@@ -167,37 +547,196 @@ impl fmt::Debug for Context {
 impl Context {
     /// Tries to create a new Context using settings from the given [`Conf`](../conf/struct.Conf.html) object.
     /// Usually called by [`ContextBuilder::build()`](struct.ContextBuilder.html#method.build).
-    fn from_conf(
+    ///
+    /// Without an event loop the context is headless, see
+    /// [`ContextBuilder::headless()`](struct.ContextBuilder.html#method.headless).
+    async fn from_conf(
         conf: conf::Conf,
+        surface_config: graphics::SurfaceConfigOverride,
         fs: Filesystem,
-    ) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        #[cfg_attr(
+            not(any(feature = "audio", feature = "gamepad")),
+            allow(unused_variables)
+        )]
+        module_options: ModuleOptions,
+        events_loop: Option<&winit::event_loop::EventLoop<()>>,
+    ) -> GameResult<Context> {
+        #[cfg_attr(not(any(feature = "audio", feature = "gamepad")), allow(unused_mut))]
+        let mut modules = ModuleReport {
+            audio: ModuleStatus::Disabled,
+            gamepad: ModuleStatus::Disabled,
+        };
         #[cfg(feature = "audio")]
-        let audio_context = audio::AudioContext::new(&fs)?;
-        let events_loop = winit::event_loop::EventLoop::new();
+        let audio_context = match (module_options.audio_backend, events_loop) {
+            (Some(backend), _) => Some(audio::AudioContext::with_backend(&fs, backend)),
+            (None, Some(_)) => Some(audio::AudioContext::new(&fs)),
+            (None, None) => None,
+        };
+        #[cfg(feature = "audio")]
+        let audio_context = match audio_context {
+            Some(Ok(audio)) => {
+                modules.audio = ModuleStatus::Available;
+                audio
+            }
+            Some(Err(e)) if !module_options.require_audio => {
+                warn!(
+                    "Going on without sound, as the audio failed to start: {:#}",
+                    e
+                );
+                modules.audio = ModuleStatus::Failed(format!("{:#}", e));
+                audio::AudioContext::unavailable(&fs, &e)
+            }
+            Some(Err(e)) => return Err(e),
+            None => {
+                modules.audio = ModuleStatus::Headless;
+                audio::AudioContext::headless(&fs)
+            }
+        };
         let timer_context = timer::TimeContext::new();
-        let graphics_context = graphics::context::GraphicsContext::new(&events_loop, &conf, &fs)?;
+        let graphics_context =
+            graphics::context::GraphicsContext::new(events_loop, &conf, &surface_config, &fs)
+                .await?;
+        let mouse_context = match &graphics_context.window {
+            Some(window) => input::mouse::MouseContext::with_window(window.clone()),
+            None => input::mouse::MouseContext::new(),
+        };
+        #[cfg(feature = "gamepad")]
+        let gamepad_context = {
+            let no_gamepads = || {
+                input::gamepad::GamepadContext::with_backend(Box::new(
+                    input::gamepad_backend::MockBackend::new(),
+                ))
+            };
+            match (module_options.gamepad_backend, events_loop) {
+                (Some(backend), _) => {
+                    modules.gamepad = ModuleStatus::Available;
+                    input::gamepad::GamepadContext::with_backend(backend)
+                }
+                (None, Some(_)) => match input::gamepad::GamepadContext::new() {
+                    Ok(gamepad) => {
+                        modules.gamepad = ModuleStatus::Available;
+                        gamepad
+                    }
+                    Err(e) if !module_options.require_gamepad => {
+                        warn!(
+                            "Going on without gamepads, as they failed to start: {:#}",
+                            e
+                        );
+                        modules.gamepad = ModuleStatus::Failed(format!("{:#}", e));
+                        no_gamepads()
+                    }
+                    Err(e) => return Err(e),
+                },
+                (None, None) => {
+                    modules.gamepad = ModuleStatus::Headless;
+                    no_gamepads()
+                }
+            }
+        };
 
+        let launching = cfg!(target_os = "android") && events_loop.is_some();
         let ctx = Context {
             conf,
             fs,
             gfx: graphics_context,
             continuing: true,
             quit_requested: false,
+            exit_reason: None,
+            quit_cancelled: false,
+            unfocused_behavior: UnfocusedBehavior::default(),
+            suspended: launching,
+            launching,
+            loop_mode: LoopMode::default(),
+            redraw_requested: false,
+            event_proxy: events_loop.map(winit::event_loop::EventLoop::create_proxy),
+            user_events: None,
             time: timer_context,
             #[cfg(feature = "audio")]
             audio: audio_context,
             keyboard: input::keyboard::KeyboardContext::new(),
-            mouse: input::mouse::MouseContext::new(),
+            mouse: mouse_context,
+            touch: input::touch::TouchContext::new(),
+            recording: input::recording::RecordingContext::new(),
             #[cfg(feature = "gamepad")]
-            gamepad: input::gamepad::GamepadContext::new()?,
+            gamepad: gamepad_context,
+            modules,
         };
 
-        Ok((ctx, events_loop))
+        Ok(ctx)
     }
 }
 
 use std::borrow::Cow;
 use std::path;
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+use std::sync::{Arc, Mutex};
+
+// How the parts of ggez needing a device are started, as set on the `ContextBuilder`.
+struct ModuleOptions {
+    #[cfg(feature = "gamepad")]
+    gamepad_backend: Option<Box<dyn GamepadBackend + Send>>,
+    #[cfg(feature = "audio")]
+    audio_backend: Option<Box<dyn audio::AudioBackend + Send>>,
+    // whether failing to start is an error, rather than going on without it
+    #[cfg(feature = "audio")]
+    require_audio: bool,
+    #[cfg(feature = "gamepad")]
+    require_gamepad: bool,
+}
+
+// A backend given to a `ContextBuilder`, shared by its clones until one builds a context.
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+pub(crate) struct BackendSlot<B: ?Sized>(Option<Arc<Mutex<Option<Box<B>>>>>);
+
+#[cfg(feature = "gamepad")]
+pub(crate) type GamepadBackendSlot = BackendSlot<dyn GamepadBackend + Send>;
+
+#[cfg(feature = "audio")]
+pub(crate) type AudioBackendSlot = BackendSlot<dyn audio::AudioBackend + Send>;
+
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+impl<B: ?Sized> BackendSlot<B> {
+    fn new(backend: Box<B>) -> Self {
+        BackendSlot(Some(Arc::new(Mutex::new(Some(backend)))))
+    }
+
+    fn take(&self) -> Option<Box<B>> {
+        self.0.as_ref()?.lock().ok()?.take()
+    }
+}
+
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+impl<B: ?Sized> Clone for BackendSlot<B> {
+    fn clone(&self) -> Self {
+        BackendSlot(self.0.clone())
+    }
+}
+
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+impl<B: ?Sized> Default for BackendSlot<B> {
+    fn default() -> Self {
+        BackendSlot(None)
+    }
+}
+
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+impl<B: ?Sized> fmt::Debug for BackendSlot<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("BackendSlot")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "audio", feature = "gamepad"))]
+impl<B: ?Sized> PartialEq for BackendSlot<B> {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
 
 /// A builder object for creating a [`Context`](struct.Context.html).
 #[derive(Debug, Clone, PartialEq)]
@@ -209,7 +748,22 @@ pub struct ContextBuilder {
     pub(crate) resources_zip_name: String,
     pub(crate) paths: Vec<path::PathBuf>,
     pub(crate) memory_zip_files: Vec<Cow<'static, [u8]>>,
+    #[cfg(feature = "embed")]
+    pub(crate) embedded: Vec<&'static include_dir::Dir<'static>>,
     pub(crate) load_conf_file: bool,
+    pub(crate) conf_overrides: conf::ConfOverrides,
+    pub(crate) surface_config: graphics::SurfaceConfigOverride,
+    pub(crate) sandboxed_user_dirs: bool,
+    pub(crate) panic_dialog: bool,
+    pub(crate) web_element: Option<String>,
+    #[cfg(feature = "gamepad")]
+    pub(crate) gamepad_backend: GamepadBackendSlot,
+    #[cfg(feature = "audio")]
+    pub(crate) audio_backend: AudioBackendSlot,
+    #[cfg(feature = "audio")]
+    pub(crate) require_audio: bool,
+    #[cfg(feature = "gamepad")]
+    pub(crate) require_gamepad: bool,
 }
 
 impl ContextBuilder {
@@ -223,7 +777,22 @@ impl ContextBuilder {
             resources_zip_name: "resources.zip".to_string(),
             paths: vec![],
             memory_zip_files: vec![],
+            #[cfg(feature = "embed")]
+            embedded: vec![],
             load_conf_file: true,
+            conf_overrides: vec![],
+            surface_config: graphics::SurfaceConfigOverride::default(),
+            sandboxed_user_dirs: false,
+            panic_dialog: false,
+            web_element: None,
+            #[cfg(feature = "gamepad")]
+            gamepad_backend: GamepadBackendSlot::default(),
+            #[cfg(feature = "audio")]
+            audio_backend: AudioBackendSlot::default(),
+            #[cfg(feature = "audio")]
+            require_audio: true,
+            #[cfg(feature = "gamepad")]
+            require_gamepad: true,
         }
     }
 
@@ -248,10 +817,45 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets which graphics adapter to pick, e.g. by its name on machines with more than one.
+    #[must_use]
+    pub fn adapter(mut self, adapter: conf::AdapterSetup) -> Self {
+        self.conf.adapter = adapter;
+        self
+    }
+
+    /// Sets the format, alpha mode and frame latency of the window surface, beyond what the
+    /// conf sets, e.g. for HDR or latency-sensitive games. Without it, or with its settings left
+    /// `None`, the surface is configured as it would be otherwise.
+    ///
+    /// Building the context fails if the format or alpha mode aren't supported, with an error
+    /// listing those which are. The settings in effect are returned by
+    /// [`GraphicsContext::surface_config`](crate::graphics::GraphicsContext::surface_config).
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::{ImageFormat, SurfaceConfigOverride};
+    /// # fn main() -> ggez::GameResult {
+    /// let (ctx, event_loop) = ggez::ContextBuilder::new("rhythm", "ggez")
+    ///     .surface_config(SurfaceConfigOverride {
+    ///         format: Some(ImageFormat::Rgba16Float),
+    ///         desired_maximum_frame_latency: Some(1),
+    ///         ..Default::default()
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn surface_config(mut self, surface_config: graphics::SurfaceConfigOverride) -> Self {
+        self.surface_config = surface_config;
+        self
+    }
+
     /// Sets all the config options, overriding any previous
     /// ones from [`window_setup()`](#method.window_setup),
-    /// [`window_mode()`](#method.window_mode), and
-    /// [`backend()`](#method.backend).  These are used as
+    /// [`window_mode()`](#method.window_mode),
+    /// [`backend()`](#method.backend), and
+    /// [`adapter()`](#method.adapter).  These are used as
     /// defaults and are overridden by any external config
     /// file found.
     #[must_use]
@@ -307,6 +911,26 @@ impl ContextBuilder {
         self
     }
 
+    /// Add files compiled into the executable with `include_dir!`, whose contents will be
+    /// searched for resources after the resource directories, as
+    /// [`Filesystem::mount_embedded`](crate::filesystem::Filesystem::mount_embedded) does,
+    /// e.g. for a build of the game in a single file.
+    ///
+    /// ```ignore
+    /// use ggez::include_dir::{self, include_dir, Dir};
+    ///
+    /// static RESOURCES: Dir = include_dir!("$CARGO_MANIFEST_DIR/resources");
+    /// let _ = ggez::ContextBuilder::new("game", "author")
+    ///     .add_embedded(&RESOURCES)
+    ///     .build();
+    /// ```
+    #[cfg(feature = "embed")]
+    #[must_use]
+    pub fn add_embedded(mut self, dir: &'static include_dir::Dir<'static>) -> Self {
+        self.embedded.push(dir);
+        self
+    }
+
     /// Specifies whether or not to load the `conf.toml` file if it
     /// exists and use its settings to override the provided values.
     /// Defaults to `true` which is usually what you want, but being
@@ -317,33 +941,290 @@ impl ContextBuilder {
         self
     }
 
+    /// Applies `overrides` to the config options with [`Conf::apply_overrides`](conf::Conf::apply_overrides),
+    /// after those of the `conf.toml` file, e.g. those set with environment variables and
+    /// arguments:
+    ///
+    /// ```rust,no_run
+    /// # use ggez::{conf, ContextBuilder, GameResult};
+    /// # fn main() -> GameResult {
+    /// let (overrides, _args) = conf::arg_overrides(std::env::args())?;
+    /// let overrides = conf::env_overrides("GGEZ").into_iter().chain(overrides);
+    /// let (ctx, event_loop) = ContextBuilder::new("game", "author")
+    ///     .with_conf_overrides(overrides)
+    ///     .build()?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// Invalid overrides make building the context fail.
+    #[must_use]
+    pub fn with_conf_overrides(
+        mut self,
+        overrides: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        self.conf_overrides.extend(overrides);
+        self
+    }
+
+    /// Keeps what's written to the user directories in memory rather than on disk, e.g. for
+    /// tests which save and load, or for a guest mode in which playing leaves nothing behind.
+    /// Defaults to `false`.
+    ///
+    /// The files in the user directories aren't read either, and
+    /// [`Filesystem::memory_snapshot`](crate::filesystem::Filesystem::memory_snapshot) with
+    /// `"/"` returns what was written. Nor is the `crash.txt` of
+    /// [`panic_dialog`](#method.panic_dialog) saved.
+    #[must_use]
+    pub fn sandboxed_user_dirs(mut self, sandboxed: bool) -> Self {
+        self.sandboxed_user_dirs = sandboxed;
+        self
+    }
+
+    /// Shows panics in a message box of the operating system, with where their details were
+    /// saved, in a `crash.txt` in the [user data directory](crate::filesystem::Filesystem::user_data_dir),
+    /// so players of a shipped game see what went wrong rather than the window just vanishing,
+    /// and have something to report. Off by default.
+    ///
+    /// This replaces the panic hook as the context is built, calling the one there was from the
+    /// new one, so the panic is still printed to stderr. It doesn't use the context, so it works
+    /// however the game panicked. See [`show_error_dialog`](crate::show_error_dialog) to show
+    /// errors the same way.
+    #[must_use]
+    pub fn panic_dialog(mut self, panic_dialog: bool) -> Self {
+        self.panic_dialog = panic_dialog;
+        self
+    }
+
+    /// Sets whether building the context fails if the audio can't start, e.g. as there's no
+    /// output device or its driver is broken. Without it being required the game goes on
+    /// without sound, [`Context::audio`] playing nowhere, as in a headless context, which
+    /// [`Context::module_status`] and [`Context::audio_opt`] tell. Defaults to `true`.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn require_audio(mut self, require: bool) -> Self {
+        self.require_audio = require;
+        self
+    }
+
+    /// Sets whether building the context fails if reading gamepads can't start. Without it
+    /// being required the game goes on without gamepads, which [`Context::module_status`] and
+    /// [`Context::gamepad_opt`] tell. Defaults to `true`.
+    #[cfg(feature = "gamepad")]
+    #[must_use]
+    pub fn require_gamepad(mut self, require: bool) -> Self {
+        self.require_gamepad = require;
+        self
+    }
+
+    /// Sets where the gamepads come from, instead of gilrs, e.g. a
+    /// [`MockBackend`](crate::input::gamepad_backend::MockBackend) to test without hardware.
+    ///
+    /// Clones of the builder share the backend, and only the first one to build a context gets it,
+    /// the others use gilrs.
+    #[cfg(feature = "gamepad")]
+    #[must_use]
+    pub fn gamepad_backend(mut self, backend: Box<dyn GamepadBackend + Send>) -> Self {
+        self.gamepad_backend = BackendSlot::new(backend);
+        self
+    }
+
+    /// Sets what sound plays through, instead of rodio, e.g. a backend playing through kira, or
+    /// a [`NullAudioBackend`](crate::audio::NullAudioBackend) to play nowhere. The `audio`
+    /// module works the same whatever it is, see [`AudioBackend`](crate::audio::AudioBackend).
+    ///
+    /// It's used by a [`headless()`](#method.headless) context too, rather than playing
+    /// nowhere. Clones of the builder share the backend, and only the first one to build a
+    /// context gets it, the others use rodio.
+    #[cfg(feature = "audio")]
+    #[must_use]
+    pub fn audio_backend(mut self, backend: Box<dyn audio::AudioBackend + Send>) -> Self {
+        self.audio_backend = BackendSlot::new(backend);
+        self
+    }
+
+    /// Sets the id of the HTML element the game's canvas is put in on the web, e.g. a `<div>`
+    /// sized for it. Defaults to `None`, for the page's `<body>`. Elsewhere it's unused.
+    #[must_use]
+    pub fn web_element(mut self, id: impl ToString) -> Self {
+        self.web_element = Some(id.to_string());
+        self
+    }
+
     /// Build the `Context`.
+    ///
+    /// On the web, where the graphics can't be waited for, use
+    /// [`build_async()`](#method.build_async) instead.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn build(self) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
-        let fs = Filesystem::new(
+        let events_loop = winit::event_loop::EventLoop::new();
+        let ctx = pollster::block_on(self.build_with(Some(&events_loop)))?;
+        Ok((ctx, events_loop))
+    }
+
+    /// Builds the `Context` as [`build()`](#method.build) does, awaiting the graphics adapter
+    /// and device rather than blocking the thread until they're there, which is how it's done
+    /// on the web, where the browser hands them out asynchronously:
+    ///
+    /// ```rust,ignore
+    /// #[wasm_bindgen(start)]
+    /// pub fn start() {
+    ///     wasm_bindgen_futures::spawn_local(async {
+    ///         let (mut ctx, event_loop) = ContextBuilder::new("game", "me")
+    ///             .web_element("game")
+    ///             .build_async()
+    ///             .await
+    ///             .unwrap();
+    ///         let state = MainState::new(&mut ctx);
+    ///         event::run(ctx, event_loop, state)
+    ///     });
+    /// }
+    /// ```
+    ///
+    /// There the canvas of the window is put in the element of the
+    /// [`web_element()`](#method.web_element) id.
+    pub async fn build_async(self) -> GameResult<(Context, winit::event_loop::EventLoop<()>)> {
+        let events_loop = winit::event_loop::EventLoop::new();
+        #[cfg(target_arch = "wasm32")]
+        let web_element = self.web_element.clone();
+        let ctx = self.build_with(Some(&events_loop)).await?;
+        #[cfg(target_arch = "wasm32")]
+        append_canvas(&ctx, web_element.as_deref())?;
+        Ok((ctx, events_loop))
+    }
+
+    /// Builds a `Context` without a window, an event loop, or audio and gamepad devices, e.g. to
+    /// test drawing code in CI, where there are none.
+    ///
+    /// Graphics use a GPU without a surface, or a software one if there is no GPU, and draw into
+    /// the frame, which is the size the window would have been, and any
+    /// [`Canvas::from_image`](crate::graphics::Canvas::from_image). Sound plays nowhere unless
+    /// an [`audio_backend()`](#method.audio_backend) is given, and there are no gamepads unless
+    /// a [`gamepad_backend()`](#method.gamepad_backend) is given.
+    /// Instead of [`event::run()`](crate::event::run), call
+    /// [`Context::advance_frame()`] between frames.
+    ///
+    /// What touches the window doesn't work: [`GraphicsContext::window()`] and
+    /// [`AudioContext::device()`](crate::audio::AudioContext::device) panic, and e.g.
+    /// [`GraphicsContext::window_position()`], [`GraphicsContext::set_window_icon()`],
+    /// [`GraphicsContext::set_mode_exclusive()`], [`GraphicsContext::set_window_opacity()`],
+    /// [`MouseContext::set_cursor_grab()`](crate::input::mouse::MouseContext::set_cursor_grab)
+    /// and [`AudioContext::set_output_device()`](crate::audio::AudioContext::set_output_device)
+    /// return errors. Those which only change the window, like
+    /// [`GraphicsContext::set_window_title()`], do nothing, and there are no monitors.
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::{Canvas, Color, Image, ImageFormat};
+    /// # fn main() -> ggez::GameResult {
+    /// let mut ctx = ggez::ContextBuilder::new("test", "ggez").headless()?;
+    /// let image = Image::new_canvas_image(&ctx, ImageFormat::Rgba8UnormSrgb, 64, 64, 1);
+    /// let canvas = Canvas::from_image(&ctx, image.clone(), Color::RED);
+    /// canvas.finish(&mut ctx)?;
+    /// // what's drawn is done at the end of the frame
+    /// ctx.advance_frame()?;
+    /// assert_eq!(&image.to_pixels(&ctx)?[..4], &[255, 0, 0, 255]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ggez::test`](crate::test) does that, and compares what's drawn with golden images.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn headless(self) -> GameResult<Context> {
+        let mut ctx = pollster::block_on(self.build_with(None))?;
+        ctx.gfx.begin_frame()?;
+        Ok(ctx)
+    }
+
+    async fn build_with(
+        self,
+        events_loop: Option<&winit::event_loop::EventLoop<()>>,
+    ) -> GameResult<Context> {
+        let mut fs = Filesystem::new(
             self.game_id.as_ref(),
             self.author.as_ref(),
             &self.resources_dir_name,
             &self.resources_zip_name,
         )?;
+        if self.sandboxed_user_dirs {
+            fs.sandbox_user_dirs();
+        }
+
+        if self.panic_dialog {
+            let crash_dir = (!self.sandboxed_user_dirs).then(|| fs.user_data_dir().to_owned());
+            crate::dialog::install_panic_hook(self.game_id.clone(), crash_dir);
+        }
 
         for path in &self.paths {
             fs.mount(path, true);
         }
 
         for zipfile_bytes in self.memory_zip_files {
-            fs.add_zip_file(std::io::Cursor::new(zipfile_bytes))?;
+            fs.mount_zip(zipfile_bytes, true)?;
         }
 
-        let config = if self.load_conf_file {
-            fs.read_config().unwrap_or(self.conf)
+        #[cfg(feature = "embed")]
+        for dir in self.embedded {
+            fs.mount_embedded(dir);
+        }
+
+        let mut config = if self.load_conf_file {
+            match fs.read_config_with_defaults(&self.conf) {
+                Ok(conf) => conf,
+                // rather than not starting over a typo in it, but not silently either
+                Err(e) if fs.is_file("/conf.toml") => {
+                    warn!("Could not load the conf file, using the defaults: {:#}", e);
+                    self.conf
+                }
+                Err(_) => self.conf,
+            }
         } else {
             self.conf
         };
+        config.apply_overrides(self.conf_overrides)?;
 
-        Context::from_conf(config, fs)
+        Context::from_conf(
+            config,
+            self.surface_config,
+            fs,
+            ModuleOptions {
+                #[cfg(feature = "gamepad")]
+                gamepad_backend: self.gamepad_backend.take(),
+                #[cfg(feature = "audio")]
+                audio_backend: self.audio_backend.take(),
+                #[cfg(feature = "audio")]
+                require_audio: self.require_audio,
+                #[cfg(feature = "gamepad")]
+                require_gamepad: self.require_gamepad,
+            },
+            events_loop,
+        )
+        .await
     }
 }
 
+// Puts the canvas winit draws the window in into the page, which it isn't in to begin with.
+#[cfg(target_arch = "wasm32")]
+fn append_canvas(ctx: &Context, id: Option<&str>) -> GameResult {
+    use crate::GameError;
+    use winit::platform::web::WindowExtWebSys;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| GameError::WindowError(String::from("there is no page to draw in")))?;
+    let parent: web_sys::Element = match id {
+        Some(id) => document.get_element_by_id(id).ok_or_else(|| {
+            GameError::WindowError(format!("there is no element with the id {:?}", id))
+        })?,
+        None => document
+            .body()
+            .ok_or_else(|| GameError::WindowError(String::from("the page has no body")))?
+            .into(),
+    };
+    let _ = parent
+        .append_child(&ctx.gfx.window().canvas())
+        .map_err(|e| GameError::WindowError(format!("could not add the canvas: {:?}", e)))?;
+    Ok(())
+}
+
 /// Terminates the [`ggez::event::run()`](crate::event::run) loop _without_ requesting a
 /// [`quit_event`](crate::event::EventHandler::quit_event). [`Context.continuing`](struct.Context.html#structfield.continuing)
 /// is set to `false` and the loop breaks.
@@ -359,7 +1240,9 @@ pub fn quit(ctx: &mut Context) {
 mod tests {
     use crate::{
         context::{Has, HasMut},
+        filesystem::Filesystem,
         graphics::GraphicsContext,
+        test::headless_context,
         ContextBuilder,
     };
 
@@ -374,5 +1257,80 @@ mod tests {
         fn takes_mut_gfx(_gfx: &mut impl HasMut<GraphicsContext>) {}
         takes_mut_gfx(&mut ctx);
         takes_mut_gfx(&mut ctx.gfx);
+
+        // the graphics context brings its filesystem, e.g. for fonts
+        fn takes_fs(_fs: &impl Has<Filesystem>) {}
+        takes_fs(&ctx.gfx);
+
+        // the parts are borrowed at the same time
+        let mut parts = ctx.split();
+        takes_gfx(&parts);
+        takes_fs(&parts);
+        takes_mut_gfx(&mut parts);
+        takes_mut_gfx(parts.gfx);
+        parts.mouse.set_cursor_hidden(true);
+        #[cfg(feature = "audio")]
+        parts
+            .audio
+            .set_master_volume(parts.time.delta().as_secs_f32());
+    }
+
+    #[test]
+    fn headless() {
+        use crate::graphics::{Canvas, Color, Image, ImageFormat};
+
+        let mut ctx =
+            match headless_context(ContextBuilder::new("test", "ggez").with_conf_file(false)) {
+                Some(ctx) => ctx,
+                None => return,
+            };
+        assert!(ctx.gfx.is_headless());
+        assert!(!ctx.gfx.is_transparent());
+        assert_eq!(ctx.gfx.drawable_size(), (800.0, 600.0));
+        assert!(ctx.gfx.window_position().is_err());
+        #[cfg(feature = "audio")]
+        {
+            assert_eq!(ctx.module_status().audio, super::ModuleStatus::Headless);
+            assert!(ctx.audio_opt().is_none());
+            assert!(ctx.audio.try_device().is_err());
+        }
+
+        let image = Image::new_canvas_image(&ctx, ImageFormat::Rgba8UnormSrgb, 64, 8, 1);
+        let canvas = Canvas::from_image(&ctx, image.clone(), Color::RED);
+        canvas.finish(&mut ctx).unwrap();
+        ctx.advance_frame().unwrap();
+        let pixels = image.to_pixels(&ctx).unwrap();
+        assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+        assert!(ctx.gfx.srgb());
+        assert_eq!(ctx.gfx.samples(), crate::conf::NumSamples::One);
+    }
+
+    #[test]
+    fn surface_config() {
+        use crate::graphics::{ImageFormat, SurfaceConfigOverride};
+
+        let mut ctx = match headless_context(
+            ContextBuilder::new("test", "ggez")
+                .with_conf_file(false)
+                .surface_config(SurfaceConfigOverride {
+                    format: Some(ImageFormat::Rgba16Float),
+                    desired_maximum_frame_latency: Some(2),
+                    ..Default::default()
+                }),
+        ) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let config = ctx.gfx.surface_config();
+        assert_eq!(config.format, ImageFormat::Rgba16Float);
+        assert_eq!(config.desired_maximum_frame_latency, Some(2));
+        assert!(!ctx.gfx.srgb());
+        for _ in 0..4 {
+            ctx.advance_frame().unwrap();
+        }
+
+        assert!(ctx.gfx.set_desired_maximum_frame_latency(Some(0)).is_err());
+        ctx.gfx.set_desired_maximum_frame_latency(None).unwrap();
+        assert_eq!(ctx.gfx.surface_config().desired_maximum_frame_latency, None);
     }
 }
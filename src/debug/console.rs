@@ -0,0 +1,606 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::context::Context;
+use crate::error::{GameError, GameResult};
+use crate::graphics::{Canvas, Color, DrawParam, Quad, Text, TextFragment};
+use crate::input::keyboard::{KeyCode, KeyInput};
+
+const DEFAULT_SCROLLBACK: usize = 500;
+const PADDING: f32 = 6.0;
+// how much of the window's height the console takes when open
+const HEIGHT: f32 = 0.5;
+
+const BACKGROUND: Color = Color::new(0.05, 0.05, 0.1, 0.85);
+const OUTPUT: Color = Color::new(0.85, 0.85, 0.85, 1.0);
+const INPUT: Color = Color::WHITE;
+
+fn level_color(level: Level) -> Color {
+    match level {
+        Level::Error => Color::new(1.0, 0.35, 0.3, 1.0),
+        Level::Warn => Color::new(1.0, 0.8, 0.3, 1.0),
+        Level::Info => Color::new(0.8, 0.9, 1.0, 1.0),
+        Level::Debug | Level::Trace => Color::new(0.6, 0.6, 0.6, 1.0),
+    }
+}
+
+// A line of the scrollback, with the level it was logged at, or `None` for what was typed and
+// what the commands printed.
+#[derive(Debug)]
+struct Line {
+    level: Option<Level>,
+    text: String,
+}
+
+// The scrollback, shared with the logger, which may log from any thread.
+#[derive(Debug)]
+struct Scrollback {
+    lines: VecDeque<Line>,
+    capacity: usize,
+    // changes whenever a line is added, so the text is only laid out again then
+    version: u64,
+}
+
+impl Scrollback {
+    fn push(&mut self, level: Option<Level>, text: &str) {
+        for text in text.lines() {
+            if self.lines.len() >= self.capacity {
+                let _ = self.lines.pop_front();
+            }
+            self.lines.push_back(Line {
+                level,
+                text: text.to_owned(),
+            });
+        }
+        self.version += 1;
+    }
+}
+
+type SharedScrollback = Arc<Mutex<Scrollback>>;
+
+fn lock(scrollback: &SharedScrollback) -> std::sync::MutexGuard<'_, Scrollback> {
+    scrollback.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// The `log` logger installed by `Console::install_logger`.
+struct ConsoleLogger {
+    scrollback: SharedScrollback,
+    level: LevelFilter,
+    forward: Option<Box<dyn Log>>,
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+            || matches!(&self.forward, Some(forward) if forward.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= self.level {
+            let text = format!("[{} {}] {}", record.level(), record.target(), record.args());
+            lock(&self.scrollback).push(Some(record.level()), &text);
+        }
+        if let Some(forward) = &self.forward {
+            forward.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(forward) = &self.forward {
+            forward.flush();
+        }
+    }
+}
+
+type Command = Box<dyn FnMut(&[&str]) -> String>;
+
+/// A drop-down console showing what was logged, in which commands registered by the game can
+/// be run, for debugging where there's no terminal to see the log in, such as fullscreen or on
+/// players' machines. It's toggled with the backtick key by default.
+///
+/// Like the [`Overlay`](super::Overlay) it's kept in the game's state and drawn last thing,
+/// and the game passes it the keyboard events. While it's open it takes all of the keyboard
+/// input, so the event methods return whether the game should ignore the event. Keys and text
+/// polled from [`ctx.keyboard`](crate::input::keyboard::KeyboardContext) aren't held back
+/// though, so games polling them should check [`is_open`](Self::is_open) first.
+///
+/// ```rust,no_run
+/// # use ggez::debug::Console;
+/// # fn main() -> ggez::GameResult {
+/// let mut console = Console::new();
+/// console.install_logger(log::LevelFilter::Info, None)?;
+/// console.register("give", |args| format!("gave {}", args.join(" ")));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Besides typing, Up and Down go through what was run before, Tab completes the name of a
+/// command, Page Up and Page Down scroll and Escape closes it.
+pub struct Console {
+    open: bool,
+    toggle_key: Option<KeyCode>,
+    scrollback: SharedScrollback,
+    commands: BTreeMap<String, Command>,
+
+    input: String,
+    // a byte offset into `input`, always on a character boundary
+    cursor: usize,
+    history: Vec<String>,
+    // which line of the history is being shown in the input, if one is
+    history_pos: Option<usize>,
+    // how many lines it's scrolled up from the bottom
+    scroll: usize,
+    // the tick the console was toggled in, to not take the toggle key's character as text
+    toggled_tick: Option<usize>,
+    ime_was_allowed: bool,
+
+    font: Option<String>,
+    text: Text,
+    line_height: Option<f32>,
+    // what the text was laid out from, so it's only built again when that changes
+    text_state: Option<(u64, usize, usize)>,
+    input_changed: bool,
+}
+
+impl Console {
+    /// Creates a closed console toggled with the backtick key, without any commands.
+    pub fn new() -> Self {
+        Console {
+            open: false,
+            toggle_key: Some(KeyCode::Grave),
+            scrollback: Arc::new(Mutex::new(Scrollback {
+                lines: VecDeque::new(),
+                capacity: DEFAULT_SCROLLBACK,
+                version: 0,
+            })),
+            commands: BTreeMap::new(),
+            input: String::new(),
+            cursor: 0,
+            history: Vec::new(),
+            history_pos: None,
+            scroll: 0,
+            toggled_tick: None,
+            ime_was_allowed: false,
+            font: None,
+            text: Text::default(),
+            line_height: None,
+            text_state: None,
+            input_changed: true,
+        }
+    }
+
+    /// Installs a logger which shows the messages logged with the `log` crate up to `level` in
+    /// the console, passing every message on to `forward` as well if it's given, such as a
+    /// logger printing to the terminal.
+    ///
+    /// Fails if a logger was installed before, as there can only be one. `tracing` events get
+    /// here too with the `log` feature of `tracing` enabled and no subscriber installed.
+    pub fn install_logger(&self, level: LevelFilter, forward: Option<Box<dyn Log>>) -> GameResult {
+        let max_level = if forward.is_some() {
+            LevelFilter::Trace
+        } else {
+            level
+        };
+        let logger = ConsoleLogger {
+            scrollback: self.scrollback.clone(),
+            level,
+            forward,
+        };
+        // it stays for as long as the game runs, like `log::set_boxed_logger` leaves it
+        log::set_logger(Box::leak(Box::new(logger))).map_err(|error| {
+            GameError::CustomError(format!("Could not install the console's logger: {}", error))
+        })?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+
+    /// Registers `name` as a command, which runs `command` with the words typed after the name
+    /// and prints what it returns, unless that's empty. It replaces the command registered with
+    /// the name before, if there was one.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        command: impl FnMut(&[&str]) -> String + 'static,
+    ) {
+        let _ = self.commands.insert(name.into(), Box::new(command));
+    }
+
+    /// Removes the command registered with `name`.
+    pub fn unregister(&mut self, name: &str) {
+        let _ = self.commands.remove(name);
+    }
+
+    /// Returns the names of the registered commands, in alphabetical order.
+    pub fn commands(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(String::as_str)
+    }
+
+    /// Runs `line` as if it was typed into the console.
+    pub fn run(&mut self, line: &str) {
+        self.print(&format!("> {}", line));
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return,
+        };
+        let args: Vec<&str> = words.collect();
+        let output = match self.commands.get_mut(name) {
+            Some(command) => command(&args),
+            None => format!("Unknown command `{}`", name),
+        };
+        if !output.is_empty() {
+            self.print(&output);
+        }
+    }
+
+    /// Adds `text` to the scrollback, as the commands' output is.
+    pub fn print(&self, text: &str) {
+        lock(&self.scrollback).push(None, text);
+    }
+
+    /// Removes everything from the scrollback.
+    pub fn clear(&mut self) {
+        let mut scrollback = lock(&self.scrollback);
+        scrollback.lines.clear();
+        scrollback.version += 1;
+        self.scroll = 0;
+    }
+
+    /// Sets how many lines are kept in the scrollback, 500 by default.
+    pub fn set_scrollback(&mut self, lines: usize) {
+        let mut scrollback = lock(&self.scrollback);
+        scrollback.capacity = lines.max(1);
+        while scrollback.lines.len() > scrollback.capacity {
+            let _ = scrollback.lines.pop_front();
+        }
+        scrollback.version += 1;
+    }
+
+    /// Returns whether the console is open.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens or closes the console. While it's open input methods are allowed, for typing
+    /// Chinese, Japanese or Korean, whatever the game allowed before.
+    pub fn set_open(&mut self, ctx: &mut Context, open: bool) {
+        if self.open == open {
+            return;
+        }
+        self.open = open;
+        self.toggled_tick = Some(ctx.time.ticks());
+        if open {
+            self.ime_was_allowed = ctx.keyboard.is_ime_allowed();
+            ctx.keyboard.set_ime_allowed(true);
+        } else {
+            ctx.keyboard.set_ime_allowed(self.ime_was_allowed);
+        }
+    }
+
+    /// Returns the key which toggles the console.
+    pub fn toggle_key(&self) -> Option<KeyCode> {
+        self.toggle_key
+    }
+
+    /// Sets the key which toggles the console in [`key_down_event`](Self::key_down_event), or
+    /// `None` for it to be only opened by the game. It's the backtick key by default.
+    pub fn set_toggle_key(&mut self, key: Option<KeyCode>) {
+        self.toggle_key = key;
+    }
+
+    /// Sets the font of the text, by the name it was added to the graphics with, or `None` for
+    /// the default font.
+    pub fn set_font(&mut self, font: Option<String>) {
+        self.font = font;
+        self.line_height = None;
+        self.text_state = None;
+    }
+
+    /// Toggles the console with the toggle key and edits the input while it's open, returning
+    /// whether the game should ignore the key. To be called from
+    /// [`EventHandler::key_down_event`](crate::event::EventHandler::key_down_event) with the
+    /// same arguments.
+    pub fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> bool {
+        if !repeat && input.keycode.is_some() && input.keycode == self.toggle_key {
+            let open = !self.open;
+            self.set_open(ctx, open);
+            return true;
+        }
+        if !self.open {
+            return false;
+        }
+
+        let key = match input.keycode {
+            Some(key) => key,
+            None => return true,
+        };
+        match key {
+            KeyCode::Return | KeyCode::NumpadEnter => {
+                let line = std::mem::take(&mut self.input);
+                self.cursor = 0;
+                self.history_pos = None;
+                self.scroll = 0;
+                if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                }
+                self.run(&line);
+            }
+            KeyCode::Escape => self.set_open(ctx, false),
+            KeyCode::Back => {
+                if let Some((i, _)) = self.input[..self.cursor].char_indices().next_back() {
+                    let _ = self.input.remove(i);
+                    self.cursor = i;
+                }
+            }
+            KeyCode::Delete if self.cursor < self.input.len() => {
+                let _ = self.input.remove(self.cursor);
+            }
+            KeyCode::Left => {
+                if let Some((i, _)) = self.input[..self.cursor].char_indices().next_back() {
+                    self.cursor = i;
+                }
+            }
+            KeyCode::Right => {
+                if let Some(ch) = self.input[self.cursor..].chars().next() {
+                    self.cursor += ch.len_utf8();
+                }
+            }
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.input.len(),
+            KeyCode::Up => {
+                let pos = match self.history_pos {
+                    Some(pos) => pos.saturating_sub(1),
+                    None => self.history.len().saturating_sub(1),
+                };
+                if let Some(line) = self.history.get(pos) {
+                    self.history_pos = Some(pos);
+                    self.set_input(line.clone());
+                }
+            }
+            KeyCode::Down => {
+                if let Some(pos) = self.history_pos {
+                    match self.history.get(pos + 1) {
+                        Some(line) => {
+                            self.history_pos = Some(pos + 1);
+                            self.set_input(line.clone());
+                        }
+                        None => {
+                            self.history_pos = None;
+                            self.set_input(String::new());
+                        }
+                    }
+                }
+            }
+            KeyCode::Tab => self.complete(),
+            KeyCode::PageUp => self.scroll += 10,
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+            _ => (),
+        }
+        self.input_changed = true;
+        true
+    }
+
+    /// Types `character` into the input while the console is open, returning whether the game
+    /// should ignore it. To be called from
+    /// [`EventHandler::text_input_event`](crate::event::EventHandler::text_input_event).
+    pub fn text_input_event(&mut self, ctx: &Context, character: char) -> bool {
+        // the character of the toggle key itself, which comes right after it
+        if self.toggled_tick == Some(ctx.time.ticks()) {
+            return true;
+        }
+        if !self.open {
+            return false;
+        }
+        if !character.is_control() {
+            self.input.insert(self.cursor, character);
+            self.cursor += character.len_utf8();
+            self.input_changed = true;
+        }
+        true
+    }
+
+    /// Types text committed by an input method into the input while the console is open,
+    /// returning whether the game should ignore it. To be called from
+    /// [`EventHandler::ime_commit_event`](crate::event::EventHandler::ime_commit_event).
+    pub fn ime_commit_event(&mut self, text: &str) -> bool {
+        if !self.open {
+            return false;
+        }
+        self.input.insert_str(self.cursor, text);
+        self.cursor += text.len();
+        self.input_changed = true;
+        true
+    }
+
+    fn set_input(&mut self, input: String) {
+        self.cursor = input.len();
+        self.input = input;
+    }
+
+    // Completes the command's name being typed as far as the registered names agree, listing
+    // them if there's more than one.
+    fn complete(&mut self) {
+        if self.input.contains(char::is_whitespace) {
+            return;
+        }
+        let matching: Vec<&str> = self
+            .commands
+            .keys()
+            .map(String::as_str)
+            .filter(|name| name.starts_with(self.input.as_str()))
+            .collect();
+        let completed = match matching.as_slice() {
+            [] => return,
+            [name] => format!("{} ", name),
+            [first, rest @ ..] => {
+                let mut common = rest.iter().fold(first.len(), |common, name| {
+                    let same = first.bytes().zip(name.bytes()).take_while(|(a, b)| a == b);
+                    same.count().min(common)
+                });
+                while !first.is_char_boundary(common) {
+                    common -= 1;
+                }
+                self.print(&matching.join("  "));
+                first[..common].to_owned()
+            }
+        };
+        self.set_input(completed);
+    }
+
+    /// Draws the console over the top of the window if it's open, with a canvas of its own so
+    /// the game's canvases are left as they are. It's to be called after the game's canvases are
+    /// finished, to be drawn over them.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        if !self.open {
+            return Ok(());
+        }
+
+        let line_height = match self.line_height {
+            Some(height) => height,
+            None => {
+                let mut line = Text::new("Mg");
+                if let Some(font) = &self.font {
+                    let _ = line.set_font(font.clone());
+                }
+                let height = line.measure(ctx)?.y;
+                self.line_height = Some(height);
+                height
+            }
+        };
+        let (width, screen_height) = ctx.gfx.drawable_size();
+        let height = (screen_height * HEIGHT).floor();
+        let rows = ((height - PADDING * 2.0) / line_height).floor().max(1.0) as usize - 1;
+
+        let version = {
+            let scrollback = lock(&self.scrollback);
+            self.scroll = self.scroll.min(scrollback.lines.len().saturating_sub(rows));
+            scrollback.version
+        };
+        let state = (version, self.scroll, rows);
+        if self.input_changed || self.text_state != Some(state) {
+            self.build_text(rows);
+            self.text_state = Some(state);
+            self.input_changed = false;
+        }
+
+        let mut canvas = Canvas::from_frame(ctx, None);
+        canvas.draw(
+            &Quad,
+            DrawParam::new().scale([width, height]).color(BACKGROUND),
+        );
+        canvas.draw(&self.text, DrawParam::new().dest([PADDING, PADDING]));
+        canvas.finish(ctx)
+    }
+
+    fn build_text(&mut self, rows: usize) {
+        let scrollback = lock(&self.scrollback);
+        let end = scrollback.lines.len() - self.scroll;
+        let start = end.saturating_sub(rows);
+
+        let mut text = Text::default();
+        // empty rows first to keep the input at the bottom
+        let blank = rows - (end - start);
+        if blank > 0 {
+            let _ = text.add("\n".repeat(blank));
+        }
+        for line in scrollback.lines.range(start..end) {
+            let color = line.level.map_or(OUTPUT, level_color);
+            let _ = text.add(TextFragment::new(format!("{}\n", line.text)).color(color));
+        }
+        let (before, after) = self.input.split_at(self.cursor);
+        let _ = text.add(TextFragment::new(format!("> {}|{}", before, after)).color(INPUT));
+        if let Some(font) = &self.font {
+            let _ = text.set_font(font.clone());
+        }
+        self.text = text;
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Console::new()
+    }
+}
+
+impl fmt::Debug for Console {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Console")
+            .field("open", &self.open)
+            .field("toggle_key", &self.toggle_key)
+            .field("commands", &self.commands.keys().collect::<Vec<_>>())
+            .field("input", &self.input)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn lines(console: &Console) -> Vec<String> {
+        lock(&console.scrollback)
+            .lines
+            .iter()
+            .map(|line| line.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn commands() {
+        let mut console = Console::new();
+        let given = Rc::new(Cell::new(0));
+        let count = given.clone();
+        console.register("give", move |args| {
+            count.set(count.get() + args.len());
+            String::new()
+        });
+        console.register("echo", |args| args.join(" "));
+
+        console.run("give sword shield");
+        assert_eq!(given.get(), 2);
+        console.run("echo  hello   there");
+        console.run("jump");
+        assert_eq!(
+            lines(&console),
+            [
+                "> give sword shield",
+                "> echo  hello   there",
+                "hello there",
+                "> jump",
+                "Unknown command `jump`"
+            ]
+        );
+
+        console.set_scrollback(2);
+        assert_eq!(lines(&console), ["> jump", "Unknown command `jump`"]);
+    }
+
+    #[test]
+    fn completion() {
+        let mut console = Console::new();
+        for name in ["spawn", "speed", "spectate", "quit"] {
+            console.register(name, |_| String::new());
+        }
+
+        console.set_input("q".to_owned());
+        console.complete();
+        assert_eq!(console.input, "quit ");
+
+        console.set_input("sp".to_owned());
+        console.complete();
+        assert_eq!(console.input, "sp");
+        assert_eq!(lines(&console), ["spawn  spectate  speed"]);
+
+        console.set_input("spe".to_owned());
+        console.complete();
+        assert_eq!(console.input, "spe");
+        console.set_input("spec".to_owned());
+        console.complete();
+        assert_eq!(console.input, "spectate ");
+    }
+}
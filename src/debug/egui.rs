@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::num::NonZeroU32;
+
+use egui::epaint::{ClippedPrimitive, ImageData, Primitive};
+use egui::{TextureId, TexturesDelta};
+
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::event::winit_event::WindowEvent;
+use crate::event::EventConsumed;
+use crate::graphics::{
+    BlendMode, Canvas, DrawParam, Image, ImageFormat, Mesh, MeshData, Rect, Vertex,
+};
+
+/// Runs an [egui](https://docs.rs/egui) UI over the game, with egui's winit integration for its
+/// input and the meshes it tessellates drawn with ggez's canvases, with its own textures.
+///
+/// It's given every event of the window in
+/// [`EventHandler::raw_window_event`](crate::event::EventHandler::raw_window_event), built in
+/// [`EventHandler::update`](crate::event::EventHandler::update) with [`Egui::run`] and drawn
+/// last thing in [`EventHandler::draw`](crate::event::EventHandler::draw), after the game's
+/// canvas is finished:
+///
+/// ```rust,no_run
+/// use ggez::debug::Egui;
+/// use ggez::event::{self, winit_event::WindowEvent, EventConsumed};
+/// use ggez::graphics::{Canvas, Color};
+/// use ggez::{Context, GameResult};
+///
+/// struct MainState {
+///     egui: Egui,
+///     speed: f32,
+/// }
+///
+/// impl event::EventHandler for MainState {
+///     fn update(&mut self, ctx: &mut Context) -> GameResult {
+///         let speed = &mut self.speed;
+///         self.egui.run(ctx, |egui_ctx| {
+///             ggez::egui::Window::new("Settings").show(egui_ctx, |ui| {
+///                 ui.add(ggez::egui::Slider::new(speed, 0.0..=10.0).text("speed"));
+///             });
+///         });
+///         Ok(())
+///     }
+///
+///     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+///         let canvas = Canvas::from_frame(ctx, Color::BLACK);
+///         canvas.finish(ctx)?;
+///         self.egui.draw(ctx)
+///     }
+///
+///     fn raw_window_event(
+///         &mut self,
+///         _ctx: &mut Context,
+///         event: &WindowEvent<'_>,
+///     ) -> GameResult<EventConsumed> {
+///         Ok(self.egui.raw_window_event(event))
+///     }
+/// }
+///
+/// fn main() -> GameResult {
+///     let (ctx, event_loop) = ggez::ContextBuilder::new("egui", "ggez").build()?;
+///     let egui = Egui::new(&ctx);
+///     event::run(ctx, event_loop, MainState { egui, speed: 1.0 })
+/// }
+/// ```
+///
+/// The events egui takes for itself, such as key presses while one of its text fields has the
+/// keyboard focus or clicks on its windows, are [consumed](EventConsumed::Consumed), so the
+/// game's own input callbacks don't see them. Polled input, like
+/// [`KeyboardContext::is_key_pressed`](crate::input::keyboard::KeyboardContext::is_key_pressed),
+/// still does, which [`Egui::wants_keyboard_input`] and [`Egui::wants_pointer_input`] tell the
+/// game to ignore.
+///
+/// egui lays out in points, which are logical pixels, so the UI keeps its apparent size on
+/// monitors with different scale factors, and its clipping rectangles become
+/// [scissor rectangles](Canvas::set_scissor_rect) in physical pixels.
+///
+/// For drawing with egui's own wgpu renderer instead, `egui-wgpu`, the device and queue are in
+/// [`GraphicsContext::wgpu`](crate::graphics::GraphicsContext::wgpu), the format it's to draw
+/// in is [`GraphicsContext::surface_format`](crate::graphics::GraphicsContext::surface_format)
+/// and the view to draw to is that of [`GraphicsContext::frame`](crate::graphics::GraphicsContext::frame).
+pub struct Egui {
+    egui_ctx: egui::Context,
+    state: egui_winit::State,
+    textures: HashMap<TextureId, Image>,
+    // tessellated by `run`, and drawn by the next `draw`
+    primitives: Vec<ClippedPrimitive>,
+    textures_delta: TexturesDelta,
+}
+
+impl Egui {
+    /// Creates the egui context and its winit input state, for the main window.
+    pub fn new(ctx: &Context) -> Self {
+        let egui_ctx = egui::Context::default();
+        // copied text goes to ggez's clipboard in `run`, see `KeyboardContext::set_clipboard_text`
+        let mut state = egui_winit::State::new_with_wayland_display(None);
+        state
+            .set_max_texture_side(ctx.gfx.wgpu().device.limits().max_texture_dimension_2d as usize);
+        state.set_pixels_per_point(ctx.gfx.scale_factor());
+        Egui {
+            egui_ctx,
+            state,
+            textures: HashMap::new(),
+            primitives: Vec::new(),
+            textures_delta: TexturesDelta::default(),
+        }
+    }
+
+    /// Returns the egui context, e.g. to change its style or read its memory.
+    pub fn egui_ctx(&self) -> &egui::Context {
+        &self.egui_ctx
+    }
+
+    /// Gives egui an event of the main window, returning whether egui took it for itself. To be
+    /// called from [`EventHandler::raw_window_event`](crate::event::EventHandler::raw_window_event)
+    /// with the event, returning what this returns.
+    pub fn raw_window_event(&mut self, event: &WindowEvent<'_>) -> EventConsumed {
+        if self.state.on_event(&self.egui_ctx, event).consumed {
+            EventConsumed::Consumed
+        } else {
+            EventConsumed::NotConsumed
+        }
+    }
+
+    /// Returns whether egui is using the keyboard, e.g. for a focused text field, so the game
+    /// should ignore the keys it polls.
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.egui_ctx.wants_keyboard_input()
+    }
+
+    /// Returns whether the mouse is over one of egui's windows or dragging something of egui's,
+    /// so the game should ignore the mouse buttons it polls.
+    pub fn wants_pointer_input(&self) -> bool {
+        self.egui_ctx.wants_pointer_input()
+    }
+
+    /// Builds the UI for this frame with `ui`, from the input given to egui since the last time,
+    /// and tessellates it for [`Egui::draw`]. To be called once a frame, e.g. from
+    /// [`EventHandler::update`](crate::event::EventHandler::update).
+    pub fn run(&mut self, ctx: &mut Context, ui: impl FnOnce(&egui::Context)) {
+        let raw_input = if ctx.gfx.is_headless() {
+            let (width, height) = ctx.gfx.drawable_size();
+            egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(width, height),
+                )),
+                pixels_per_point: Some(1.0),
+                ..Default::default()
+            }
+        } else {
+            self.state.take_egui_input(ctx.gfx.window())
+        };
+        let mut output = self.egui_ctx.run(raw_input, ui);
+        let copied = std::mem::take(&mut output.platform_output.copied_text);
+        if !copied.is_empty() {
+            let _ = ctx.keyboard.set_clipboard_text(&copied);
+        }
+        if !ctx.gfx.is_headless() {
+            self.state.handle_platform_output(
+                ctx.gfx.window(),
+                &self.egui_ctx,
+                output.platform_output,
+            );
+        }
+        self.primitives = self.egui_ctx.tessellate(output.shapes);
+        self.textures_delta.append(output.textures_delta);
+    }
+
+    /// Draws the UI last built with [`Egui::run`] on the frame, with a canvas of its own so the
+    /// game's canvases are left as they are. It's to be called after the game's canvases are
+    /// finished, to be drawn over them.
+    ///
+    /// egui's textures are uploaded first, and the ones it's done with are freed after.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let delta = std::mem::take(&mut self.textures_delta);
+        for (id, image_delta) in &delta.set {
+            self.set_texture(ctx, *id, image_delta);
+        }
+
+        let pixels_per_point = self.egui_ctx.pixels_per_point();
+        let (width, height) = ctx.gfx.drawable_size();
+        let mut canvas = Canvas::from_frame(ctx, None);
+        canvas.set_screen_coordinates(Rect::new(
+            0.0,
+            0.0,
+            width / pixels_per_point,
+            height / pixels_per_point,
+        ));
+        // egui's vertex colors and textures are premultiplied
+        canvas.set_blend_mode(BlendMode::PREMULTIPLIED);
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &self.primitives
+        {
+            let mesh = match primitive {
+                Primitive::Mesh(mesh) if !mesh.indices.is_empty() => mesh,
+                // paint callbacks are for egui's own renderers
+                _ => continue,
+            };
+            let image = match self.textures.get(&mesh.texture_id) {
+                Some(image) => image.clone(),
+                None => continue,
+            };
+            let min = (clip_rect.min * pixels_per_point).round();
+            let max = (clip_rect.max * pixels_per_point).round();
+            let (x, y) = (min.x.clamp(0.0, width), min.y.clamp(0.0, height));
+            let clip = Rect::new(x, y, max.x.min(width) - x, max.y.min(height) - y);
+            // the clipping rectangle is off screen or empty
+            if canvas.set_scissor_rect(clip).is_err() {
+                continue;
+            }
+            let vertices: Vec<Vertex> = mesh
+                .vertices
+                .iter()
+                .map(|vertex| Vertex {
+                    position: [vertex.pos.x, vertex.pos.y],
+                    uv: [vertex.uv.x, vertex.uv.y],
+                    color: egui::Rgba::from(vertex.color).to_array(),
+                })
+                .collect();
+            let mesh = Mesh::from_data(
+                ctx,
+                MeshData {
+                    vertices: &vertices,
+                    indices: &mesh.indices,
+                },
+            );
+            canvas.draw_textured_mesh(mesh, image, DrawParam::default());
+        }
+
+        // the screen coordinates of the game's canvas are kept for converting mouse positions,
+        // rather than egui's points
+        let screen_coords = ctx.gfx.screen_coords;
+        let res = canvas.finish(ctx);
+        ctx.gfx.screen_coords = screen_coords;
+
+        for id in &delta.free {
+            let _ = self.textures.remove(id);
+        }
+        res
+    }
+
+    fn set_texture(&mut self, ctx: &Context, id: TextureId, delta: &egui::epaint::ImageDelta) {
+        let [width, height] = delta.image.size();
+        let pixels: Vec<u8> = match &delta.image {
+            ImageData::Color(image) => image
+                .pixels
+                .iter()
+                .flat_map(|color| color.to_array())
+                .collect(),
+            ImageData::Font(image) => image
+                .srgba_pixels(1.0)
+                .flat_map(|color| color.to_array())
+                .collect(),
+        };
+        let (width, height) = (width as u32, height as u32);
+
+        match (delta.pos, self.textures.get(&id)) {
+            // a part of a texture made before, such as the font atlas growing
+            (Some([x, y]), Some(image)) => {
+                ctx.gfx.wgpu().queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture: image.wgpu().0,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d {
+                            x: x as u32,
+                            y: y as u32,
+                            z: 0,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    &pixels,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(4 * width),
+                        rows_per_image: None,
+                    },
+                    wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+            _ => {
+                let image =
+                    Image::from_pixels(ctx, &pixels, ImageFormat::Rgba8UnormSrgb, width, height);
+                let _ = self.textures.insert(id, image);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Egui {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Egui")
+            .field("textures", &self.textures.len())
+            .field("primitives", &self.primitives.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,58 @@
+//! Tools for seeing what's going on while playing the game: an [`Overlay`] with a graph of the
+//! frame times, how much was drawn and values of the game's own, toggled with F3, and a
+//! [`Console`] showing the log, in which the game's commands can be run. With the `egui`
+//! feature, there's also `Egui`, for building UIs of the game's own with
+//! [egui](https://docs.rs/egui).
+//!
+//! They're kept in the game's state, given the key presses and drawn last thing in
+//! [`EventHandler::draw`](crate::event::EventHandler::draw), after the game's canvas is finished.
+//!
+//! ```rust,no_run
+//! use ggez::debug::Overlay;
+//! use ggez::graphics::{Canvas, Color};
+//! use ggez::input::keyboard::KeyInput;
+//! use ggez::{event, Context, GameResult};
+//! use std::cell::Cell;
+//! use std::rc::Rc;
+//!
+//! struct MainState {
+//!     overlay: Overlay,
+//!     entities: Rc<Cell<usize>>,
+//! }
+//!
+//! impl event::EventHandler for MainState {
+//!     fn update(&mut self, _ctx: &mut Context) -> GameResult {
+//!         self.entities.set(self.entities.get() + 1);
+//!         Ok(())
+//!     }
+//!
+//!     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+//!         let canvas = Canvas::from_frame(ctx, Color::BLACK);
+//!         canvas.finish(ctx)?;
+//!         self.overlay.draw(ctx)
+//!     }
+//!
+//!     fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+//!         let _ = self.overlay.key_down_event(input, repeat);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! fn main() -> GameResult {
+//!     let (ctx, event_loop) = ggez::ContextBuilder::new("overlay", "ggez").build()?;
+//!     let mut overlay = Overlay::new();
+//!     let entities = Rc::new(Cell::new(0));
+//!     let count = entities.clone();
+//!     overlay.watch("entities", move || count.get().to_string());
+//!     event::run(ctx, event_loop, MainState { overlay, entities })
+//! }
+//! ```
+
+mod console;
+#[cfg(feature = "egui")]
+mod egui;
+mod overlay;
+
+#[cfg(feature = "egui")]
+pub use self::egui::Egui;
+pub use self::{console::*, overlay::*};
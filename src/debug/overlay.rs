@@ -0,0 +1,316 @@
+use instant::Instant;
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+use crate::context::Context;
+use crate::error::GameResult;
+use crate::graphics::{Canvas, Color, DrawParam, FrameStats, InstanceArray, Text};
+use crate::input::keyboard::{KeyCode, KeyInput};
+use crate::timer::FrameTimeStats;
+
+// how many of the latest frames the graph shows, and its size in pixels
+const GRAPH_FRAMES: usize = 120;
+const BAR_WIDTH: f32 = 2.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+// the frame time at the top of the graph, so a 60 fps frame is a third of the way up
+const GRAPH_TOP: Duration = Duration::from_millis(50);
+const TARGET_FRAME: Duration = Duration::from_micros(16_667);
+// sorting the whole frame time history every frame would be a waste, so the percentiles are
+// only worked out this often
+const STATS_INTERVAL: Duration = Duration::from_millis(500);
+const PADDING: f32 = 6.0;
+
+const BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+const BAR: Color = Color::new(0.3, 0.8, 0.3, 1.0);
+const SPIKE: Color = Color::new(0.9, 0.3, 0.2, 1.0);
+const OWN_TIME: Color = Color::new(0.8, 0.3, 0.9, 1.0);
+const TARGET_LINE: Color = Color::new(1.0, 1.0, 1.0, 0.4);
+
+/// Which corner of the window the [`Overlay`] is drawn in.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Corner {
+    /// The top left corner.
+    #[default]
+    TopLeft,
+    /// The top right corner.
+    TopRight,
+    /// The bottom left corner.
+    BottomLeft,
+    /// The bottom right corner.
+    BottomRight,
+}
+
+/// Draws a graph of the frame times, the frames per second, how many draw calls and instances
+/// the last frame took and the values being watched, over the game.
+///
+/// Its own time is the purple top of each bar in the graph, so what the game itself takes can
+/// be told apart, and its own draws aren't counted. Bars of frames longer than 60 fps allows
+/// are red.
+///
+/// It's hidden to begin with, until the toggle key is pressed.
+pub struct Overlay {
+    visible: bool,
+    toggle_key: Option<KeyCode>,
+    corner: Corner,
+    watches: Vec<(String, Box<dyn FnMut() -> String>)>,
+
+    // made the first time it's drawn, and reused after
+    bars: Option<InstanceArray>,
+    text: Text,
+    // how long drawing the overlay took each frame, lined up with the latest frame times
+    own_times: VecDeque<Duration>,
+    own_time: Duration,
+    own_stats: FrameStats,
+    stats: FrameTimeStats,
+    stats_updated: Option<Duration>,
+}
+
+impl Overlay {
+    /// Creates a hidden overlay in the top left corner, toggled with F3.
+    pub fn new() -> Self {
+        Overlay {
+            visible: false,
+            toggle_key: Some(KeyCode::F3),
+            corner: Corner::default(),
+            watches: Vec::new(),
+            bars: None,
+            text: Text::new(""),
+            own_times: VecDeque::with_capacity(GRAPH_FRAMES),
+            own_time: Duration::ZERO,
+            own_stats: FrameStats::default(),
+            stats: FrameTimeStats::default(),
+            stats_updated: None,
+        }
+    }
+
+    /// Returns whether the overlay is shown.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the overlay.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Shows the overlay if it's hidden and hides it if it's shown.
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Returns the key which toggles the overlay.
+    pub fn toggle_key(&self) -> Option<KeyCode> {
+        self.toggle_key
+    }
+
+    /// Sets the key which toggles the overlay in [`key_down_event`](Self::key_down_event), or
+    /// `None` for it to be only toggled by the game. It's F3 by default.
+    pub fn set_toggle_key(&mut self, key: Option<KeyCode>) {
+        self.toggle_key = key;
+    }
+
+    /// Returns which corner the overlay is drawn in.
+    pub fn corner(&self) -> Corner {
+        self.corner
+    }
+
+    /// Sets which corner the overlay is drawn in.
+    pub fn set_corner(&mut self, corner: Corner) {
+        self.corner = corner;
+    }
+
+    /// Sets the font of the text, by the name it was added to the graphics with, or `None` for
+    /// the default font.
+    pub fn set_font(&mut self, font: Option<String>) {
+        self.text.fragments_mut()[0].font = font;
+    }
+
+    /// Shows `value` with `name` in the overlay, called every frame it's shown, replacing the
+    /// watch which had the name before if there was one.
+    pub fn watch(&mut self, name: impl Into<String>, value: impl FnMut() -> String + 'static) {
+        let name = name.into();
+        self.unwatch(&name);
+        self.watches.push((name, Box::new(value)));
+    }
+
+    /// Stops showing the value watched with `name`.
+    pub fn unwatch(&mut self, name: &str) {
+        self.watches.retain(|(watched, _)| watched != name);
+    }
+
+    /// Toggles the overlay if the toggle key was pressed, returning whether it was. To be called
+    /// from [`EventHandler::key_down_event`](crate::event::EventHandler::key_down_event) with
+    /// the same arguments.
+    pub fn key_down_event(&mut self, input: KeyInput, repeat: bool) -> bool {
+        let pressed = !repeat && input.keycode.is_some() && input.keycode == self.toggle_key;
+        if pressed {
+            self.toggle();
+        }
+        pressed
+    }
+
+    /// Draws the overlay on the frame if it's shown, with a canvas of its own so the game's
+    /// canvases are left as they are. It's to be called after the game's canvases are finished,
+    /// to be drawn over them.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let start = Instant::now();
+        // the latest frame time is of the frame before, which the time taken then is part of
+        if self.own_times.len() == GRAPH_FRAMES {
+            let _ = self.own_times.pop_front();
+        }
+        self.own_times.push_back(self.own_time);
+        self.own_time = Duration::ZERO;
+        if !self.visible {
+            self.own_stats = FrameStats::default();
+            return Ok(());
+        }
+
+        let now = ctx.time.time_since_start();
+        let stale = match self.stats_updated {
+            Some(updated) => now - updated >= STATS_INTERVAL,
+            None => true,
+        };
+        if stale {
+            self.stats = ctx.time.frame_time_stats();
+            self.stats_updated = Some(now);
+        }
+
+        self.write_text(ctx);
+        let text_size = self.text.measure(ctx)?;
+        let frame_times = ctx.time.frame_times();
+        let shown = frame_times.len().min(GRAPH_FRAMES);
+        let graph_width = GRAPH_FRAMES as f32 * BAR_WIDTH;
+        let width = graph_width.max(text_size.x) + PADDING * 2.0;
+        let height = GRAPH_HEIGHT + text_size.y + PADDING * 3.0;
+        let (screen_w, screen_h) = ctx.gfx.drawable_size();
+        let x = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => 0.0,
+            Corner::TopRight | Corner::BottomRight => screen_w - width,
+        };
+        let y = match self.corner {
+            Corner::TopLeft | Corner::TopRight => 0.0,
+            Corner::BottomLeft | Corner::BottomRight => screen_h - height,
+        };
+
+        let bar_height =
+            |time: Duration| (time.as_secs_f32() / GRAPH_TOP.as_secs_f32()).min(1.0) * GRAPH_HEIGHT;
+        let graph_bottom = y + PADDING + GRAPH_HEIGHT;
+        let rect = |x: f32, y: f32, w: f32, h: f32, color: Color| {
+            DrawParam::new().dest([x, y]).scale([w, h]).color(color)
+        };
+        // the newest frame on the right
+        let bars = frame_times[frame_times.len() - shown..]
+            .iter()
+            .rev()
+            .zip(
+                self.own_times
+                    .iter()
+                    .rev()
+                    .map(Some)
+                    .chain(std::iter::repeat(None)),
+            )
+            .enumerate()
+            .flat_map(|(i, (&time, own))| {
+                let bar_x = x + PADDING + graph_width - (i + 1) as f32 * BAR_WIDTH;
+                let total = bar_height(time);
+                let own = bar_height(own.copied().unwrap_or_default()).min(total);
+                let color = if time > TARGET_FRAME { SPIKE } else { BAR };
+                [
+                    rect(bar_x, graph_bottom - total, BAR_WIDTH, total - own, color),
+                    rect(bar_x, graph_bottom - total, BAR_WIDTH, own, OWN_TIME),
+                ]
+            });
+        let background = rect(x, y, width, height, BACKGROUND);
+        let target_line = rect(
+            x + PADDING,
+            graph_bottom - bar_height(TARGET_FRAME),
+            graph_width,
+            1.0,
+            TARGET_LINE,
+        );
+        let instances = self
+            .bars
+            .get_or_insert_with(|| InstanceArray::new(&ctx.gfx, None));
+        instances.set(
+            std::iter::once(background)
+                .chain(bars)
+                .chain(std::iter::once(target_line)),
+        );
+
+        let before = ctx.gfx.drawing_stats;
+        let mut canvas = Canvas::from_frame(ctx, None);
+        canvas.draw(instances, DrawParam::new());
+        canvas.draw(
+            &self.text,
+            DrawParam::new().dest([x + PADDING, graph_bottom + PADDING]),
+        );
+        canvas.finish(ctx)?;
+        let after = ctx.gfx.drawing_stats;
+        self.own_stats = FrameStats {
+            draw_calls: after.draw_calls - before.draw_calls,
+            instances: after.instances - before.instances,
+        };
+        self.own_time = start.elapsed();
+        Ok(())
+    }
+
+    fn write_text(&mut self, ctx: &Context) {
+        // what the overlay drew last frame is left out, that frame's stats being the ones shown
+        let drawn = ctx.gfx.frame_stats();
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        let text = &mut self.text.fragments_mut()[0].text;
+        text.clear();
+        // writing to a `String` can't fail
+        let _ = write!(
+            text,
+            "{:.0} fps, {:.2} ms\n\
+             p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms\n\
+             {} draw calls, {} instances",
+            ctx.time.fps(),
+            ms(ctx.time.unscaled_delta()),
+            ms(self.stats.p50),
+            ms(self.stats.p95),
+            ms(self.stats.p99),
+            ms(self.stats.max),
+            drawn.draw_calls.saturating_sub(self.own_stats.draw_calls),
+            drawn.instances.saturating_sub(self.own_stats.instances),
+        );
+        for entry in ctx.time.profile_report() {
+            let _ = write!(
+                text,
+                "\n{}: {:.2} ms over {}",
+                entry.name,
+                ms(entry.total),
+                entry.count
+            );
+        }
+        for (name, value) in &mut self.watches {
+            let _ = write!(text, "\n{}: {}", name, value());
+        }
+    }
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Overlay::new()
+    }
+}
+
+impl fmt::Debug for Overlay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Overlay")
+            .field("visible", &self.visible)
+            .field("toggle_key", &self.toggle_key)
+            .field("corner", &self.corner)
+            .field(
+                "watches",
+                &self
+                    .watches
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
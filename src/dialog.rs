@@ -0,0 +1,136 @@
+//! Error message boxes, for telling players what went wrong when the game can't go on.
+
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+
+use crate::error::GameError;
+
+/// Shows `error` in a message box of the operating system titled `title`, waiting until it's
+/// closed, e.g. from [`EventHandler::on_error`](crate::event::EventHandler::on_error) before
+/// aborting the game, so players see what went wrong rather than the window just vanishing.
+///
+/// This doesn't use the graphics, so it works whatever state they're in. Where there is no way
+/// to show a message box, e.g. on Linux without `zenity`, `kdialog` or `xmessage`, the error
+/// is printed to stderr instead.
+pub fn show_error_dialog(title: &str, error: &GameError) {
+    // with all of the context added to it
+    let message = format!("{:#}", error);
+    if !show(title, &message) {
+        eprintln!("{}: {}", title, message);
+    }
+}
+
+// Makes panics show a message box, see `ContextBuilder::panic_dialog`, with the details saved
+// to `crash.txt` in `crash_dir` too if there is one.
+pub(crate) fn install_panic_hook(game_id: String, crash_dir: Option<PathBuf>) {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // printed to stderr as usual
+        previous(info);
+
+        let report = info.to_string();
+        let saved = crash_dir.as_ref().and_then(|crash_dir| {
+            let crash_file = crash_dir.join("crash.txt");
+            fs::create_dir_all(crash_dir)
+                .and_then(|()| fs::write(&crash_file, &report))
+                .ok()
+                .map(|()| crash_file)
+        });
+        let message = match &saved {
+            Some(crash_file) => format!(
+                "{}\n\nThe details were saved to {}",
+                report,
+                crash_file.display()
+            ),
+            None => report,
+        };
+        let title = format!("{} crashed", game_id);
+        if !show(&title, &message) {
+            if let Some(crash_file) = saved {
+                eprintln!("The details were saved to {}", crash_file.display());
+            }
+        }
+    }));
+}
+
+// Shows a message box, returning whether there was a way to.
+#[cfg(target_os = "windows")]
+#[allow(unsafe_code)]
+fn show(title: &str, message: &str) -> bool {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        MessageBoxW, MB_ICONERROR, MB_OK, MB_TOPMOST,
+    };
+
+    let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain(Some(0)).collect() };
+    let (title, message) = (wide(title), wide(message));
+    // no owner window, so it works whatever state the game's window is in
+    let result = unsafe {
+        MessageBoxW(
+            0,
+            message.as_ptr(),
+            title.as_ptr(),
+            MB_OK | MB_ICONERROR | MB_TOPMOST,
+        )
+    };
+    result != 0
+}
+
+#[cfg(target_os = "macos")]
+fn show(title: &str, message: &str) -> bool {
+    // AppleScript strings, which the text is passed into as arguments to not have to escape it
+    std::process::Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display alert (item 1 of argv) message (item 2 of argv) as critical",
+            "-e",
+            "end run",
+            title,
+            message,
+        ])
+        .status()
+        .is_ok()
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+fn show(title: &str, message: &str) -> bool {
+    use std::process::Command;
+
+    // whichever of the usual dialog programs is installed
+    let text = format!("{}\n\n{}", title, message);
+    [
+        Command::new("zenity").args([
+            "--error",
+            "--no-markup",
+            "--title",
+            title,
+            "--text",
+            message,
+        ]),
+        Command::new("kdialog").args(["--title", title, "--error", message]),
+        Command::new("xmessage").args(["-center", &text]),
+    ]
+    .iter_mut()
+    .any(|command| command.status().is_ok())
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+fn show(_title: &str, _message: &str) -> bool {
+    false
+}
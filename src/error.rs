@@ -1,4 +1,5 @@
 //! Error types and conversion functions.
+use std::backtrace::Backtrace;
 use std::error::Error;
 use std::fmt;
 use std::string::FromUtf8Error;
@@ -11,6 +12,10 @@ pub enum GameError {
     GraphicsInitializationError,
     /// An error in the filesystem layout
     FilesystemError(String),
+    /// Something was going to be written to where nothing can be, such as a zip archive, a
+    /// directory mounted read-only, or a path none of the writeable mounts have, rather than
+    /// writing it failing, e.g. as the disk is full.
+    ReadOnlyError(String),
     /// An error in the config file
     ConfigError(String),
     /// Happens when an `winit::event_loop::EventLoopProxy` attempts to
@@ -41,12 +46,16 @@ pub enum GameError {
     VideoError(String),
     /// Something went wrong with the `gilrs` gamepad-input library.
     GamepadError(String),
+    /// The clipboard couldn't be accessed, or didn't contain text.
+    ClipboardError(String),
     /// Something went wrong with the `lyon` shape-tesselation library.
     LyonError(String),
     /// Something went wrong when drawing text.
     GlyphBrushError(glyph_brush::BrushError),
     /// Attempted to draw text with a non-existent font name.
     FontSelectError(String),
+    /// Something went wrong when parsing text markup.
+    TextMarkupError(String),
     /// Something went wrong when asynchronously mapping a GPU buffer.
     BufferAsyncError(wgpu::BufferAsyncError),
     /// Deadlock when trying to lock a mutex.
@@ -57,12 +66,115 @@ pub enum GameError {
     ///
     /// Please include an informative message with the error.
     CustomError(String),
+    /// An error with what was being done when it happened, such as loading which file, added
+    /// with [`GameError::context`] or [`ResultExt`].
+    ///
+    /// It's displayed as just the context, as the error is its [`source`](Error::source),
+    /// unless it's displayed with `{:#}`, which shows the whole chain of them.
+    /// [`root`](GameError::root) gets the error without any context, to match on.
+    Context {
+        /// What was being done.
+        context: String,
+        /// The error which happened.
+        source: Box<GameError>,
+        /// Where the first context was added, with the `backtrace` feature enabled, if
+        /// `RUST_BACKTRACE` is set.
+        backtrace: Option<Box<Backtrace>>,
+    },
+}
+
+impl GameError {
+    /// Adds what was being done when the error happened, e.g.
+    /// `error.context("loading the level \"intro\"")`, see [`GameError::Context`].
+    pub fn context(self, context: impl Into<String>) -> GameError {
+        let backtrace = match self {
+            GameError::Context { .. } => None,
+            _ => capture_backtrace(),
+        };
+        GameError::Context {
+            context: context.into(),
+            source: Box::new(self),
+            backtrace,
+        }
+    }
+
+    /// Returns the error without any context added, e.g. to check whether it was a
+    /// `GameError::ResourceNotFound`.
+    pub fn root(&self) -> &GameError {
+        match self {
+            GameError::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// Returns the backtrace captured when context was first added to the error, with the
+    /// `backtrace` feature enabled and `RUST_BACKTRACE` set.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            GameError::Context {
+                source, backtrace, ..
+            } => source.backtrace().or(backtrace.as_deref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "backtrace")]
+fn capture_backtrace() -> Option<Box<Backtrace>> {
+    use std::backtrace::BacktraceStatus;
+
+    let backtrace = Backtrace::capture();
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(Box::new(backtrace)),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "backtrace"))]
+fn capture_backtrace() -> Option<Box<Backtrace>> {
+    None
+}
+
+/// Adds context to the errors of results, like [`GameError::context`] does, for any error
+/// which converts into a `GameError`.
+///
+/// ```rust
+/// use ggez::{GameResult, ResultExt};
+///
+/// fn load_level(name: &str) -> GameResult<String> {
+///     std::fs::read_to_string(format!("levels/{}.txt", name))
+///         .with_context(|| format!("loading the level {:?}", name))
+/// }
+///
+/// let error = load_level("intro").unwrap_err();
+/// assert_eq!(error.to_string(), "loading the level \"intro\"");
+/// assert!(format!("{:#}", error).starts_with("loading the level \"intro\": "));
+/// assert!(matches!(error.root(), ggez::GameError::IOError(_)));
+/// ```
+pub trait ResultExt<T> {
+    /// Adds `context` to the error, if there is one.
+    fn context(self, context: impl Into<String>) -> GameResult<T>;
+
+    /// Adds the context returned by `context` to the error, if there is one, which is only
+    /// called then, to not have to format it otherwise.
+    fn with_context<S: Into<String>>(self, context: impl FnOnce() -> S) -> GameResult<T>;
+}
+
+impl<T, E: Into<GameError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> GameResult<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<S: Into<String>>(self, context: impl FnOnce() -> S) -> GameResult<T> {
+        self.map_err(|e| e.into().context(context()))
+    }
 }
 
 impl fmt::Display for GameError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             GameError::ConfigError(ref s) => write!(f, "Config error: {}", s),
+            GameError::ReadOnlyError(ref s) => write!(f, "Read-only: {}", s),
             GameError::ResourceLoadError(ref s) => write!(f, "Error loading resource: {}", s),
             GameError::ResourceNotFound(ref s, ref paths) => write!(
                 f,
@@ -76,14 +188,26 @@ impl fmt::Display for GameError {
             }
             GameError::GlyphBrushError(ref e) => write!(f, "Text rendering error: {}", e),
             GameError::FontSelectError(ref e) => write!(f, "No such font '{}'", e),
+            GameError::TextMarkupError(ref e) => write!(f, "Text markup error: {}", e),
             GameError::BufferAsyncError(ref e) => write!(f, "Async buffer map error: {}", e),
+            GameError::Context {
+                ref context,
+                ref source,
+                ..
+            } => {
+                if f.alternate() {
+                    write!(f, "{}: {:#}", context, source)
+                } else {
+                    write!(f, "{}", context)
+                }
+            }
             _ => write!(f, "GameError {:?}", self),
         }
     }
 }
 
 impl Error for GameError {
-    fn cause(&self) -> Option<&dyn Error> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match *self {
             GameError::RequestDeviceError(ref e) => Some(e),
             GameError::WindowCreationError(ref e) => Some(&**e),
@@ -91,6 +215,7 @@ impl Error for GameError {
             GameError::FontError(ref e) => Some(e),
             GameError::GlyphBrushError(ref e) => Some(e),
             GameError::BufferAsyncError(ref e) => Some(e),
+            GameError::Context { ref source, .. } => Some(&**source),
             _ => None,
         }
     }
@@ -163,6 +288,22 @@ impl From<gilrs::Error> for GameError {
     }
 }
 
+#[cfg(feature = "gamepad")]
+impl From<gilrs::ff::Error> for GameError {
+    fn from(s: gilrs::ff::Error) -> GameError {
+        let errstr = format!("Gamepad force feedback error: {}", s);
+        GameError::GamepadError(errstr)
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+impl From<arboard::Error> for GameError {
+    fn from(s: arboard::Error) -> GameError {
+        let errstr = format!("Clipboard error: {}", s);
+        GameError::ClipboardError(errstr)
+    }
+}
+
 impl From<lyon::lyon_tessellation::TessellationError> for GameError {
     fn from(s: lyon::lyon_tessellation::TessellationError) -> GameError {
         let errstr = format!(
@@ -10,6 +10,11 @@
 //! source code for this module, or the [`eventloop`
 //! example](https://github.com/ggez/ggez/blob/master/examples/eventloop.rs).
 
+use instant::Instant;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::mpsc;
+use std::time::Duration;
 use winit::{self, dpi};
 
 /// A mouse button.
@@ -25,20 +30,148 @@ pub use gilrs::Button;
 /// `winit` events; nested in a module for re-export neatness.
 pub mod winit_event {
     pub use super::winit::event::{
-        DeviceEvent, ElementState, Event, KeyboardInput, ModifiersState, MouseScrollDelta,
-        TouchPhase, WindowEvent,
+        AxisId, DeviceEvent, DeviceId, ElementState, Event, Force, Ime, KeyboardInput,
+        ModifiersState, MouseButton, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode,
+        WindowEvent,
     };
 }
+#[cfg(feature = "audio")]
+use crate::audio::SoundHandle;
+use crate::graphics::WindowId;
 #[cfg(feature = "gamepad")]
 pub use crate::input::gamepad::GamepadId;
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::GamepadInfo;
+use crate::input::gesture::Gesture;
 use crate::input::keyboard::{KeyCode, KeyInput, KeyMods};
+pub use crate::input::mouse::ScrollDelta;
+pub use crate::input::pen::PenEvent;
+use crate::input::pen::{self, PenPhase};
+#[cfg(feature = "gamepad")]
+use crate::input::recording::RecordedEvent;
 use crate::GameError;
 
 use self::winit_event::*;
 /// `winit` event loop.
 pub use winit::event_loop::{ControlFlow, EventLoop};
+use winit::event_loop::{EventLoopProxy, EventLoopWindowTarget};
 
 use crate::context::Context;
+use crate::GameResult;
+
+/// What [`event::run()`](fn.run.html) does while the window isn't focused, set with
+/// [`Context::set_unfocused_behavior`](crate::Context::set_unfocused_behavior). By default it
+/// does nothing, for games that see to it themselves in
+/// [`focus_event`](trait.EventHandler.html#method.focus_event).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct UnfocusedBehavior {
+    /// Stops calling [`update`](trait.EventHandler.html#tymethod.update) and
+    /// [`fixed_update`](trait.EventHandler.html#method.fixed_update), pausing the game while
+    /// drawing goes on. The time while paused doesn't count: the first frame after has a short
+    /// delta and no fixed updates to catch up on.
+    pub pause_updates: bool,
+    /// The frame rate to cap the game at, see
+    /// [`TimeContext::set_unfocused_target_fps`](crate::timer::TimeContext::set_unfocused_target_fps).
+    pub target_fps: Option<u32>,
+    /// Mutes all the sound, keeping the master volume and the buses as they are. Does nothing
+    /// without the `audio` feature.
+    pub mute_audio: bool,
+}
+
+/// Whether [`event::run()`](fn.run.html) runs frames one after the other, or waits for events
+/// in between, set with [`Context::set_loop_mode`](crate::Context::set_loop_mode).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Runs frames as fast as it can, or at the frame rate the game is capped at, for games
+    /// and anything else always moving. This is how it starts.
+    #[default]
+    Continuous,
+    /// Waits for an event to run a frame, e.g. input or an [`EventSender`] sending one, for
+    /// tools which would otherwise draw the same thing over and over again. The time spent
+    /// waiting doesn't count, so the frame after it has a short delta and no fixed updates
+    /// to catch up on.
+    ///
+    /// [`Context::request_redraw`](crate::Context::request_redraw) runs another frame right
+    /// after the current one, e.g. on every frame of an animation, until it's done.
+    WaitForEvents {
+        /// Runs a frame anyway after this long without events, e.g. for a blinking cursor.
+        max_wait: Option<Duration>,
+    },
+}
+
+/// Why the game is quitting, given to [`Context::request_quit_with`](crate::Context::request_quit_with)
+/// and seen in [`EventHandler::quit_event`](trait.EventHandler.html#method.quit_event) through
+/// [`Context::exit_reason`](crate::Context::exit_reason).
+#[derive(Debug)]
+pub enum ExitReason {
+    /// The player quit, e.g. by closing the window. The process exits with code 0.
+    UserAction,
+    /// The process exits with the given code, e.g. non-zero for a launcher to see that
+    /// something went wrong.
+    Code(i32),
+    /// Something went wrong that the game can't go on from. The error is logged and the process
+    /// exits with code 1.
+    Error(GameError),
+}
+
+impl ExitReason {
+    /// Returns the code the process exits with for this reason.
+    pub fn code(&self) -> i32 {
+        match self {
+            ExitReason::UserAction => 0,
+            ExitReason::Code(code) => *code,
+            ExitReason::Error(_) => 1,
+        }
+    }
+}
+
+/// Sends events from other threads to
+/// [`EventHandler::user_event`](trait.EventHandler.html#method.user_event), waking the event
+/// loop, made with [`Context::event_sender`](crate::Context::event_sender).
+pub struct EventSender<T> {
+    sender: mpsc::Sender<T>,
+    // `None` in a headless context, which has no event loop to wake
+    proxy: Option<EventLoopProxy<()>>,
+}
+
+impl<T> EventSender<T> {
+    pub(crate) fn new(sender: mpsc::Sender<T>, proxy: Option<EventLoopProxy<()>>) -> Self {
+        EventSender { sender, proxy }
+    }
+
+    /// Sends `event` to the event loop, waking it.
+    ///
+    /// Returns an error if the event loop ended, or a sender of another type of events was
+    /// made since this one.
+    pub fn send(&self, event: T) -> GameResult {
+        self.sender.send(event).map_err(|_| {
+            GameError::EventLoopError(String::from(
+                "The event loop no longer takes events of this type",
+            ))
+        })?;
+        match &self.proxy {
+            Some(proxy) => proxy
+                .send_event(())
+                .map_err(|e| GameError::EventLoopError(e.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> Self {
+        EventSender {
+            sender: self.sender.clone(),
+            proxy: self.proxy.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for EventSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventSender").finish()
+    }
+}
 
 /// Used in [`EventHandler::on_error()`](trait.EventHandler.html#method.on_error)
 /// to specify where an error originated
@@ -46,17 +179,23 @@ use crate::context::Context;
 pub enum ErrorOrigin {
     /// error originated in `update()`
     Update,
+    /// error originated in `fixed_update()`
+    FixedUpdate,
     /// error originated in `draw()`
     Draw,
     /// error originated in `mouse_button_down_event()`
     MouseButtonDownEvent,
     /// error originated in `mouse_button_up_event()`
     MouseButtonUpEvent,
+    /// error originated in `mouse_click_event()`
+    MouseClickEvent,
     /// error originated in `mouse_motion_event()`
     MouseMotionEvent,
+    /// error originated in `raw_mouse_motion_event()`
+    RawMouseMotionEvent,
     /// error originated in `mouse_enter_or_leave()`
     MouseEnterOrLeave,
-    /// error originated in `mouse_wheel_event()`
+    /// error originated in `mouse_wheel_event()` or `mouse_scroll_event()`
     MouseWheelEvent,
     /// error originated in `key_down_event()`
     KeyDownEvent,
@@ -64,20 +203,143 @@ pub enum ErrorOrigin {
     KeyUpEvent,
     /// error originated in `text_input_event()`
     TextInputEvent,
+    /// error originated in `ime_preedit_event()`, `ime_commit_event()` or `ime_enabled_event()`
+    ImeEvent,
     /// error originated in `touch_event()`
     TouchEvent,
+    /// error originated in `pen_event()`
+    PenEvent,
+    /// error originated in `pinch_event()`, `rotate_event()` or `pan_event()`
+    GestureEvent,
+    /// error originated in `file_drop_event()`, `file_hover_event()` or `file_hover_cancelled_event()`
+    FileDropEvent,
     /// error originated in `gamepad_button_down_event()`
     GamepadButtonDownEvent,
     /// error originated in `gamepad_button_up_event()`
     GamepadButtonUpEvent,
     /// error originated in `gamepad_axis_event()`
     GamepadAxisEvent,
+    /// error originated in `gamepad_connected_event()`
+    GamepadConnectedEvent,
+    /// error originated in `gamepad_disconnected_event()`
+    GamepadDisconnectedEvent,
+    /// error originated in `audio_finished_event()`
+    AudioFinishedEvent,
+    /// error originated in `audio_device_changed_event()`
+    AudioDeviceChangedEvent,
     /// error originated in `focus_event()`
     FocusEvent,
+    /// error originated in `suspend_event()`
+    SuspendEvent,
+    /// error originated in `resume_event()`
+    ResumeEvent,
     /// error originated in `quit_event()`
     QuitEvent,
     /// error originated in `resize_event()`
     ResizeEvent,
+    /// error originated in `window_moved_event()`
+    WindowMovedEvent,
+    /// error originated in `scale_factor_changed_event()`
+    ScaleFactorChangedEvent,
+    /// error originated in `window_minimized_event()` or `window_maximized_event()`
+    WindowStateEvent,
+    /// error originated in `raw_window_event()`
+    RawWindowEvent,
+    /// error originated in `secondary_window_event()`
+    SecondaryWindowEvent,
+    /// error originated in `user_event()`
+    UserEvent,
+}
+
+/// What to do about an error, as decided by
+/// [`EventHandler::on_error()`](trait.EventHandler.html#method.on_error).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ErrorAction {
+    /// Carry on with the game as if the callback had succeeded.
+    ///
+    /// The rest of the frame runs as usual: an error in `update()` is still followed by
+    /// `draw()`, and an error in `draw()` still ends the frame, presenting whatever was drawn
+    /// to it before the error. [`Canvas::finish`](crate::graphics::Canvas::finish) failing
+    /// part way through keeps the draws it encoded before the one that failed, so the
+    /// frame may come out incomplete for that one frame.
+    Continue,
+    /// Stop the event loop, ending the game.
+    Abort,
+}
+
+/// Whether [`EventHandler::raw_window_event()`](trait.EventHandler.html#method.raw_window_event)
+/// took the event for itself, so ggez shouldn't pass it to the other callbacks.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum EventConsumed {
+    /// The event goes on to the callbacks ggez translates it to, as usual.
+    #[default]
+    NotConsumed,
+    /// The callbacks aren't called for the event, e.g. for a key typed into a text field of
+    /// a UI drawn over the game.
+    Consumed,
+}
+
+/// An event of a secondary window, created with [`GraphicsContext::create_window`](crate::graphics::GraphicsContext::create_window).
+///
+/// Positions are in physical pixels, relative to the window's top-left corner.
+#[derive(Debug, Copy, Clone)]
+pub enum SecondaryWindowEvent {
+    /// The window was resized to the given physical size.
+    Resized {
+        /// The new width.
+        width: f32,
+        /// The new height.
+        height: f32,
+    },
+    /// The user asked to close the window. The window is closed once this event has been handled.
+    CloseRequested,
+    /// The window gained (`true`) or lost (`false`) focus.
+    Focused(bool),
+    /// The mouse entered (`true`) or left (`false`) the window area.
+    MouseEnterOrLeave(bool),
+    /// The mouse was moved.
+    MouseMotion {
+        /// The new x position.
+        x: f32,
+        /// The new y position.
+        y: f32,
+    },
+    /// A mouse button was pressed.
+    MouseButtonDown {
+        /// The pressed button.
+        button: MouseButton,
+        /// The x position of the cursor.
+        x: f32,
+        /// The y position of the cursor.
+        y: f32,
+    },
+    /// A mouse button was released.
+    MouseButtonUp {
+        /// The released button.
+        button: MouseButton,
+        /// The x position of the cursor.
+        x: f32,
+        /// The y position of the cursor.
+        y: f32,
+    },
+    /// The mousewheel was scrolled, see [`EventHandler::mouse_wheel_event`].
+    MouseWheel {
+        /// The horizontal scroll amount.
+        x: f32,
+        /// The vertical scroll amount.
+        y: f32,
+    },
+    /// A keyboard button was pressed.
+    KeyDown {
+        /// The pressed key.
+        input: KeyInput,
+        /// Whether this is a key repeat.
+        repeated: bool,
+    },
+    /// A keyboard button was released.
+    KeyUp(KeyInput),
+    /// A unicode character was received, see [`EventHandler::text_input_event`].
+    TextInput(char),
 }
 
 /// A trait defining event callbacks.  This is your primary interface with
@@ -92,8 +354,9 @@ pub enum ErrorOrigin {
 /// override the methods you want to use.
 ///
 /// For the error type simply choose the default [`GameError`](../error/enum.GameError.html),
-/// or something more generic, if your situation requires it.
-pub trait EventHandler<E = GameError>
+/// or something more generic, if your situation requires it. `T` is the type of the events sent
+/// from other threads with an [`EventSender`], if there are any.
+pub trait EventHandler<E = GameError, T = ()>
 where
     E: std::fmt::Debug,
 {
@@ -101,6 +364,15 @@ where
     /// This should be where the game's logic takes place.
     fn update(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
+    /// Called a fixed number of times a second, set with
+    /// [`TimeContext::set_fixed_update_rate`](crate::timer::TimeContext::set_fixed_update_rate),
+    /// for game logic which should advance by the same time every step, e.g. physics, rather
+    /// than by however long the last frame took. It's called before
+    /// [`update()`](#tymethod.update), as many times as are due in a frame, which may be none.
+    fn fixed_update(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called to do the drawing of your game.
     /// You probably want to start this with
     /// [`graphics::clear()`](../graphics/fn.clear.html) and end it
@@ -109,6 +381,8 @@ where
     fn draw(&mut self, _ctx: &mut Context) -> Result<(), E>;
 
     /// A mouse button was pressed
+    ///
+    /// When exactly that happened is [`MouseContext::button_event_time`](crate::input::mouse::MouseContext::button_event_time).
     fn mouse_button_down_event(
         &mut self,
         _ctx: &mut Context,
@@ -119,6 +393,20 @@ where
         Ok(())
     }
 
+    /// A mouse button was pressed, after [`mouse_button_down_event`](EventHandler::mouse_button_down_event),
+    /// with the number of clicks in a row: 1 for a single click, 2 for a double click and so on.
+    /// See [`MouseContext::last_click`](crate::input::mouse::MouseContext::last_click).
+    fn mouse_click_event(
+        &mut self,
+        _ctx: &mut Context,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+        _count: u32,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
     /// A mouse button was released
     fn mouse_button_up_event(
         &mut self,
@@ -143,7 +431,21 @@ where
         Ok(())
     }
 
-    /// mouse entered or left window area
+    /// The mouse itself was moved by `dx` and `dy`, without acceleration and in no defined unit.
+    /// Unlike [`mouse_motion_event`](EventHandler::mouse_motion_event) this keeps being reported while the cursor is locked
+    /// with [`MouseContext::set_cursor_grab`](crate::input::mouse::MouseContext::set_cursor_grab), so use it for controlling a camera.
+    ///
+    /// Only reported while the window is focused, and if enabled with
+    /// [`MouseContext::set_raw_input`](crate::input::mouse::MouseContext::set_raw_input).
+    /// See [`MouseContext::raw_delta`](crate::input::mouse::MouseContext::raw_delta) for more.
+    fn raw_mouse_motion_event(&mut self, _ctx: &mut Context, _dx: f64, _dy: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The mouse cursor entered (`true`) or left (`false`) the window area.
+    ///
+    /// This is independent of [`focus_event`](EventHandler::focus_event). On leaving,
+    /// [`MouseContext::position`](crate::input::mouse::MouseContext::position) keeps the last known position.
     fn mouse_enter_or_leave(&mut self, _ctx: &mut Context, _entered: bool) -> Result<(), E> {
         Ok(())
     }
@@ -154,6 +456,20 @@ where
         Ok(())
     }
 
+    /// Something was scrolled, by lines for mouse wheels or by pixels for touchpads, see [`ScrollDelta`].
+    /// Called after [`mouse_wheel_event`](EventHandler::mouse_wheel_event), which doesn't tell them apart.
+    ///
+    /// Touchpads on some platforms report when the scroll started and ended as `phase`, which allows
+    /// for kinetic scrolling, otherwise it's always `TouchPhase::Moved`.
+    fn mouse_scroll_event(
+        &mut self,
+        _ctx: &mut Context,
+        _delta: ScrollDelta,
+        _phase: TouchPhase,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
     /// A keyboard button was pressed.
     ///
     /// The default implementation of this will call [`ctx.request_quit()`](crate::Context::request_quit)
@@ -182,39 +498,140 @@ where
         Ok(())
     }
 
+    /// The text being composed with an input method changed, which should be shown in the text
+    /// field without being part of its text yet. `cursor` is the byte range of the selection
+    /// within `text`, or `None` if the cursor should be hidden. An empty `text` clears the composition.
+    ///
+    /// Input methods, which are used for Chinese, Japanese or Korean and for dead keys on some
+    /// platforms, need to be enabled with [`KeyboardContext::set_ime_allowed`](crate::input::keyboard::KeyboardContext::set_ime_allowed).
+    fn ime_preedit_event(
+        &mut self,
+        _ctx: &mut Context,
+        _text: &str,
+        _cursor: Option<(usize, usize)>,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The input method committed some text, which should be inserted into the text field.
+    /// This text is also part of [`KeyboardContext::text_entered`](crate::input::keyboard::KeyboardContext::text_entered),
+    /// but isn't passed to `text_input_event`.
+    fn ime_commit_event(&mut self, _ctx: &mut Context, _text: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The input method was enabled (`true`) or disabled (`false`), for example when the
+    /// user switched between keyboard layouts. While it's enabled, keys that are part of
+    /// a composition aren't reported as key or text input events.
+    fn ime_enabled_event(&mut self, _ctx: &mut Context, _enabled: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A file was dropped onto the window, at the given position in window pixels. If several
+    /// files are dropped at once, this is called once for each of them.
+    ///
+    /// Since the mouse isn't tracked on all platforms while dragging, the position is the last
+    /// known mouse position. The path is a path of the OS, not of the [`filesystem`](crate::filesystem).
+    /// Read it with `std::fs` functions and load the bytes with, for example, `Image::from_bytes`
+    /// or mount its directory with [`Filesystem::mount`](crate::filesystem::Filesystem::mount).
+    fn file_drop_event(
+        &mut self,
+        _ctx: &mut Context,
+        _path: std::path::PathBuf,
+        _x: f32,
+        _y: f32,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A file is being dragged over the window, called once for each file when the drag enters it.
+    /// Follows with [`file_drop_event`](EventHandler::file_drop_event) or with
+    /// [`file_hover_cancelled_event`](EventHandler::file_hover_cancelled_event).
+    fn file_hover_event(&mut self, _ctx: &mut Context, _path: std::path::PathBuf) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The files being dragged over the window left it without being dropped.
+    fn file_hover_cancelled_event(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
     /// An event from a touchscreen has been triggered; it provides the x and y location
-    /// inside the window as well as the state of the tap (such as Started, Moved, Ended, etc)
-    /// By default, touch events will trigger mouse behavior
+    /// inside the window in physical pixels as well as the state of the tap (such as Started, Moved, Ended, etc).
+    /// `id` identifies the finger while it touches the screen, see also
+    /// [`TouchContext::touches`](crate::input::touch::TouchContext::touches).
+    ///
+    /// By default, the touch which started while no other finger was touching the screen triggers mouse behavior.
     fn touch_event(
         &mut self,
         ctx: &mut Context,
         phase: TouchPhase,
-        x: f64,
-        y: f64,
+        id: u64,
+        x: f32,
+        y: f32,
     ) -> Result<(), E> {
-        ctx.mouse.handle_move(x as f32, y as f32);
+        if !ctx.touch.is_primary(id) {
+            return Ok(());
+        }
+        ctx.mouse.handle_move(x, y);
 
         match phase {
             TouchPhase::Started => {
                 ctx.mouse.set_button(MouseButton::Left, true);
-                self.mouse_button_down_event(ctx, MouseButton::Left, x as f32, y as f32)?;
+                self.mouse_button_down_event(ctx, MouseButton::Left, x, y)?;
             }
             TouchPhase::Moved => {
                 let diff = ctx.mouse.last_delta();
-                self.mouse_motion_event(ctx, x as f32, y as f32, diff.x, diff.y)?;
+                self.mouse_motion_event(ctx, x, y, diff.x, diff.y)?;
             }
             TouchPhase::Ended | TouchPhase::Cancelled => {
                 ctx.mouse.set_button(MouseButton::Left, false);
-                self.mouse_button_up_event(ctx, MouseButton::Left, x as f32, y as f32)?;
+                self.mouse_button_up_event(ctx, MouseButton::Left, x, y)?;
             }
         }
 
         Ok(())
     }
 
+    /// A pen moved above or touched the surface of a tablet or screen.
+    /// These are synthesized from the mouse as well, see [`pen`].
+    fn pen_event(&mut self, _ctx: &mut Context, _event: PenEvent) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Two fingers on a touchscreen moved apart or together, changing their distance by
+    /// `scale_delta` as a factor, around `center` in physical pixels. See [`gesture`](crate::input::gesture).
+    fn pinch_event(
+        &mut self,
+        _ctx: &mut Context,
+        _scale_delta: f32,
+        _center: mint::Point2<f32>,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Two fingers on a touchscreen rotated by `angle_delta` radians clockwise,
+    /// around `center` in physical pixels. See [`gesture`](crate::input::gesture).
+    fn rotate_event(
+        &mut self,
+        _ctx: &mut Context,
+        _angle_delta: f32,
+        _center: mint::Point2<f32>,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Two fingers on a touchscreen moved together by `delta` in physical pixels.
+    /// See [`gesture`](crate::input::gesture).
+    fn pan_event(&mut self, _ctx: &mut Context, _delta: mint::Vector2<f32>) -> Result<(), E> {
+        Ok(())
+    }
+
     /// A gamepad button was pressed; `id` identifies which gamepad.
     /// Use [`input::gamepad()`](../input/fn.gamepad.html) to get more info about
     /// the gamepad.
+    ///
+    /// When exactly that happened is [`GamepadContext::event_time`](crate::input::gamepad::GamepadContext::event_time).
     #[cfg(feature = "gamepad")]
     fn gamepad_button_down_event(
         &mut self,
@@ -241,6 +658,9 @@ where
     /// A gamepad axis moved; `id` identifies which gamepad.
     /// Use [`input::gamepad()`](../input/fn.gamepad.html) to get more info about
     /// the gamepad.
+    ///
+    /// Analog triggers are reported as `Axis::LeftZ` and `Axis::RightZ` from 0 to 1 on every gamepad,
+    /// see [`GamepadContext::button_value`](crate::input::gamepad::GamepadContext::button_value).
     #[cfg(feature = "gamepad")]
     fn gamepad_axis_event(
         &mut self,
@@ -252,13 +672,81 @@ where
         Ok(())
     }
 
+    /// A gamepad has been connected. Gamepads connected when the game starts are reported
+    /// before the first update as well.
+    ///
+    /// A gamepad connected again can have another `id`; its
+    /// [`persistent_id`](crate::input::gamepad::GamepadInfo::persistent_id) stays the same,
+    /// also between sessions.
+    #[cfg(feature = "gamepad")]
+    fn gamepad_connected_event(
+        &mut self,
+        _ctx: &mut Context,
+        _id: GamepadId,
+        _info: GamepadInfo,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A gamepad has been disconnected, e.g. to pause the game. It's no longer in
+    /// [`GamepadContext::gamepads`](crate::input::gamepad::GamepadContext::gamepads),
+    /// but `id` can still be looked up.
+    #[cfg(feature = "gamepad")]
+    fn gamepad_disconnected_event(&mut self, _ctx: &mut Context, _id: GamepadId) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// A sound played with [`AudioContext::play_once`](crate::audio::AudioContext::play_once),
+    /// or of a source given to
+    /// [`AudioContext::notify_finished`](crate::audio::AudioContext::notify_finished), played to
+    /// its end, as of the audio thread before the update this comes before.
+    #[cfg(feature = "audio")]
+    fn audio_finished_event(&mut self, _ctx: &mut Context, _sound: SoundHandle) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// The sound moved to the output device named `device` on its own, as the default device
+    /// changed, or the one chosen with
+    /// [`AudioContext::set_output_device`](crate::audio::AudioContext::set_output_device) was
+    /// unplugged, e.g. to show it in an options menu.
+    #[cfg(feature = "audio")]
+    fn audio_device_changed_event(&mut self, _ctx: &mut Context, _device: &str) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Called when the window is shown or hidden.
     fn focus_event(&mut self, _ctx: &mut Context, _gained: bool) -> Result<(), E> {
         Ok(())
     }
 
-    /// Called upon a quit event.  If it returns true,
-    /// the game does not exit (the quit event is cancelled).
+    /// Called when the app is suspended, e.g. as it's sent to the background on a phone, or a
+    /// laptop's lid is closed on some platforms, to save the game in case it's killed.
+    ///
+    /// The window surface is let go of and sound is paused until
+    /// [`resume_event`](#method.resume_event). In between, `update()` and `draw()` aren't
+    /// called, and the loop waits for events rather than running frames. On mobile platforms the
+    /// native window goes away too, so handles taken with
+    /// [`GraphicsContext::window_handle`](crate::graphics::GraphicsContext::window_handle) mustn't
+    /// be used until they're taken again after resuming.
+    fn suspend_event(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the app is resumed after [`suspend_event`](#method.suspend_event), with
+    /// the window surface created again and sound playing on. The time spent suspended isn't
+    /// counted in the next frame's delta.
+    ///
+    /// This isn't called as the game starts, although winit reports that as the app resuming too.
+    fn resume_event(&mut self, _ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called upon a quit event, when the window is closed or
+    /// [`Context::request_quit`](crate::Context::request_quit) was called, with why in
+    /// [`Context::exit_reason`](crate::Context::exit_reason).  If it returns true, or
+    /// [`Context::cancel_quit`](crate::Context::cancel_quit) is called in it,
+    /// the game does not exit (the quit event is cancelled), e.g. to ask about unsaved changes
+    /// first. Closing the window again calls this again.
     fn quit_event(&mut self, _ctx: &mut Context) -> Result<bool, E> {
         debug!("quit_event() callback called, quitting...");
         Ok(false)
@@ -266,242 +754,848 @@ where
 
     /// Called when the user resizes the window, or when it is resized
     /// via [`graphics::set_mode()`](../graphics/fn.set_mode.html).
+    ///
+    /// The size is in physical pixels, like the default screen coordinates of a
+    /// [`Canvas`](crate::graphics::Canvas). To lay out in logical pixels instead, set the canvas' screen coordinates to
+    /// [`GraphicsContext::window_logical_size`](crate::graphics::GraphicsContext::window_logical_size).
     fn resize_event(&mut self, _ctx: &mut Context, _width: f32, _height: f32) -> Result<(), E> {
         Ok(())
     }
 
+    /// Called when the scale factor of the window changes, usually because it was moved to a monitor with
+    /// a different DPI. `new_physical_size` is the size the window has from now on, see
+    /// [`WindowMode::resize_on_scale_factor_change`](crate::conf::WindowMode::resize_on_scale_factor_change).
+    ///
+    /// Mouse positions stay in physical pixels, so they change along with the scale factor; divide them by
+    /// `scale` to get logical pixels. A [`resize_event`](EventHandler::resize_event) follows if the physical size changed.
+    fn scale_factor_changed_event(
+        &mut self,
+        _ctx: &mut Context,
+        _scale: f32,
+        _new_physical_size: (u32, u32),
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the window is moved, e.g. dragged by the user, with the new position of its
+    /// top-left corner in physical pixels (see
+    /// [`GraphicsContext::window_position`](crate::graphics::GraphicsContext::window_position)).
+    ///
+    /// Never called on Wayland.
+    fn window_moved_event(&mut self, _ctx: &mut Context, _x: i32, _y: i32) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the window is minimized (`true`) or restored (`false`), e.g. to pause the game and its audio.
+    /// Restoring the window is followed by a [`resize_event`](EventHandler::resize_event) with its size.
+    ///
+    /// See [`GraphicsContext::is_minimized`](crate::graphics::GraphicsContext::is_minimized) for platform differences.
+    fn window_minimized_event(&mut self, _ctx: &mut Context, _minimized: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called when the window is maximized (`true`) or leaves the maximized state (`false`).
+    fn window_maximized_event(&mut self, _ctx: &mut Context, _maximized: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Called with every event of the main window as it comes from winit, before ggez handles
+    /// it, e.g. for UI libraries like egui, which need all of them. The winit types are
+    /// re-exported in [`winit_event`](crate::event::winit_event), and all of winit as
+    /// [`ggez::winit`](crate::winit), so they're of the version ggez uses.
+    ///
+    /// Returning [`EventConsumed::Consumed`] keeps the callbacks ggez translates the event to,
+    /// such as `key_down_event()` for a key press, from being called for it. ggez still
+    /// updates its own state from it, so e.g. the window still resizes and polled input like
+    /// [`KeyboardContext::is_key_pressed`](crate::input::keyboard::KeyboardContext::is_key_pressed)
+    /// still sees the key.
+    ///
+    /// Events come in the order winit delivers them, each right before its translated callbacks,
+    /// and all the events of a frame before its `update()` and `draw()`. Input played back from
+    /// a [recording](crate::input::recording) comes here too, before the frame's `update()`. Events
+    /// of secondary windows only go to
+    /// [`secondary_window_event()`](#method.secondary_window_event).
+    fn raw_window_event(
+        &mut self,
+        _ctx: &mut Context,
+        _event: &winit_event::WindowEvent<'_>,
+    ) -> Result<EventConsumed, E> {
+        Ok(EventConsumed::NotConsumed)
+    }
+
+    /// An event happened in a secondary window, created with
+    /// [`GraphicsContext::create_window`](crate::graphics::GraphicsContext::create_window).
+    ///
+    /// Input in secondary windows is only reported here, not through the other callbacks, though
+    /// the keyboard state in [`KeyboardContext`](crate::input::keyboard::KeyboardContext) is shared
+    /// between all windows. Closing a secondary window doesn't quit the game.
+    fn secondary_window_event(
+        &mut self,
+        _ctx: &mut Context,
+        _window: WindowId,
+        _event: SecondaryWindowEvent,
+    ) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// An event was sent with an [`EventSender`], e.g. from a thread loading assets.
+    ///
+    /// Sending one wakes the event loop, and events come in the order they were sent, between
+    /// frames with the window's events, so all the ones sent before a frame starts are handled
+    /// before its [`update()`](#tymethod.update) and [`draw()`](#tymethod.draw).
+    fn user_event(&mut self, _ctx: &mut Context, _event: T) -> Result<(), E> {
+        Ok(())
+    }
+
     /// Something went wrong, causing a `GameError` (or some other kind of error, depending on what you specified).
-    /// If this returns true, the error was fatal, so the event loop ends, aborting the game.
-    fn on_error(&mut self, _ctx: &mut Context, _origin: ErrorOrigin, _e: E) -> bool {
-        true
+    ///
+    /// The error has already been logged by the time this is called. Return
+    /// [`ErrorAction::Continue`] to recover from it, e.g. by showing a placeholder for an asset
+    /// that failed to load, or [`ErrorAction::Abort`] to end the event loop, which is
+    /// what the default implementation does.
+    fn on_error(&mut self, _ctx: &mut Context, _origin: ErrorOrigin, _e: E) -> ErrorAction {
+        ErrorAction::Abort
     }
 }
 
 /// Runs the game's main loop, calling event callbacks on the given state
 /// object as events occur.
 ///
-/// It does not try to do any type of framerate limiting.  See the
+/// It does not limit the frame rate unless asked to with
+/// [`TimeContext::set_target_fps`](crate::timer::TimeContext::set_target_fps).  See the
 /// documentation for the [`timer`](../timer/index.html) module for more info.
-#[allow(clippy::needless_return)] // necessary as the returns used here are actually necessary to break early from the event loop
-pub fn run<S: 'static, E>(mut ctx: Context, event_loop: EventLoop<()>, mut state: S) -> !
+///
+/// When the game quits, the state is dropped and the process exits with the code of the
+/// [`ExitReason`] given to [`Context::request_quit_with`](crate::Context::request_quit_with),
+/// or 0. To run code after that, e.g. to save, use [`run_return()`] instead.
+///
+/// In browsers the loop is the browser's: frames are run in its animation frames, at the
+/// monitor's refresh rate with [`LoopMode::Continuous`], and this hands control back to it
+/// right away rather than looping, which winit does by throwing a JavaScript exception, so
+/// nothing after it runs. See [`ContextBuilder::build_async`](crate::ContextBuilder::build_async)
+/// for starting the game there.
+pub fn run<S: 'static, E, T>(mut ctx: Context, event_loop: EventLoop<()>, mut state: S) -> !
+where
+    S: EventHandler<E, T>,
+    E: std::fmt::Debug,
+    T: Send + 'static,
+{
+    event_loop.run(move |event, target, control_flow| {
+        run_event(&mut ctx, &mut state, event, target, control_flow)
+    })
+}
+
+/// Runs the game's main loop as [`run()`] does, but returns when the game quits, with the
+/// context, the event loop and the state, instead of exiting the process.
+///
+/// Whatever needs to be done after the game, e.g. saving or printing statistics, can then be
+/// done with the state, and the process exit code is up to the caller, e.g. the one of
+/// [`Context::exit_reason`](crate::Context::exit_reason). The window stays open until the
+/// context is dropped.
+///
+/// This uses winit's `run_return`, which isn't there on iOS and the web.
+#[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+pub fn run_return<S, E, T>(
+    mut ctx: Context,
+    mut event_loop: EventLoop<()>,
+    mut state: S,
+) -> (Context, EventLoop<()>, S)
+where
+    S: EventHandler<E, T> + 'static,
+    E: std::fmt::Debug,
+    T: Send + 'static,
+{
+    use winit::platform::run_return::EventLoopExtRunReturn;
+
+    let _ = event_loop.run_return(|event, target, control_flow| {
+        run_event(&mut ctx, &mut state, event, target, control_flow)
+    });
+    (ctx, event_loop, state)
+}
+
+/// A game's main loop which runs a frame at a time, for embedding a game in an application
+/// running its own loop, e.g. a tool showing a preview of it.
+///
+/// Each [`step()`](#method.step) handles the events which arrived since the last one, and
+/// updates and draws once, as a frame of [`run()`] would. The time keeps working if the steps
+/// are irregular, [`TimeContext::delta`](crate::timer::TimeContext::delta) being the time
+/// since the last one.
+///
+/// Dropping the loop drops the state, then the context with its window, its surface and its
+/// audio streams. To give the game a chance to refuse quitting, e.g. to save, call
+/// [`Context::request_quit`](crate::Context::request_quit) and step until it exits.
+///
+/// This uses winit's `run_return`, which isn't there on iOS and the web.
+#[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+pub struct GameLoop<S, E = GameError, T = ()> {
+    // dropped in this order, the window before the event loop it belongs to
+    state: S,
+    ctx: Context,
+    event_loop: EventLoop<()>,
+    exit_code: Option<i32>,
+    _handler: PhantomData<fn(E, T)>,
+}
+
+/// What the host of a [`GameLoop`] should do after a step.
+#[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoopControl {
+    /// Keep stepping.
+    Continue,
+    /// The game quit, with this exit code, see [`ExitReason::code`].
+    Exit(i32),
+}
+
+#[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+impl<S, E, T> GameLoop<S, E, T>
 where
-    S: EventHandler<E>,
+    S: EventHandler<E, T> + 'static,
     E: std::fmt::Debug,
+    T: Send + 'static,
 {
-    event_loop.run(move |mut event, _, control_flow| {
-        let ctx = &mut ctx;
-        let state = &mut state;
-
-        if ctx.quit_requested {
-            let res = state.quit_event(ctx);
-            ctx.quit_requested = false;
-            if let Ok(false) = res {
-                ctx.continuing = false;
-            } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
+    /// Makes a loop for `state`, which starts running at the first step.
+    pub fn new(ctx: Context, event_loop: EventLoop<()>, state: S) -> Self {
+        GameLoop {
+            state,
+            ctx,
+            event_loop,
+            exit_code: None,
+            _handler: PhantomData,
+        }
+    }
+
+    /// Handles the events which arrived since the last step, then updates and draws once.
+    ///
+    /// Returns [`LoopControl::Exit`] once the game quit, after which stepping does nothing.
+    pub fn step(&mut self) -> LoopControl {
+        use winit::platform::run_return::EventLoopExtRunReturn;
+
+        if let Some(code) = self.exit_code {
+            return LoopControl::Exit(code);
+        }
+        let ctx = &mut self.ctx;
+        let state = &mut self.state;
+        let exit_code = &mut self.exit_code;
+        let mut frame_done = false;
+        let _ = self.event_loop.run_return(|event, target, control_flow| {
+            // the loop only stops between calls, and isn't destroyed
+            if let Event::LoopDestroyed = event {
+                return;
+            }
+            if frame_done || exit_code.is_some() {
+                *control_flow = ControlFlow::ExitWithCode(exit_code.unwrap_or(0));
                 return;
             }
+            frame_done = matches!(event, Event::MainEventsCleared);
+            run_event(ctx, state, event, target, control_flow);
+            if let ControlFlow::ExitWithCode(code) = *control_flow {
+                *exit_code = Some(code);
+            } else if frame_done {
+                *control_flow = ControlFlow::Exit;
+            }
+        });
+        match self.exit_code {
+            Some(code) => LoopControl::Exit(code),
+            None => LoopControl::Continue,
         }
-        if !ctx.continuing {
-            *control_flow = ControlFlow::Exit;
+    }
+
+    /// Returns the context.
+    pub fn ctx(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// Returns the context, e.g. to ask the game to quit.
+    pub fn ctx_mut(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+
+    /// Returns the state of the game.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the state of the game, e.g. to change it from the host.
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
+}
+
+#[cfg(not(any(target_os = "ios", target_arch = "wasm32")))]
+impl<S, E, T> fmt::Debug for GameLoop<S, E, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GameLoop")
+            .field("ctx", &self.ctx)
+            .field("exit_code", &self.exit_code)
+            .finish()
+    }
+}
+
+// Handles one event of the main loop, quitting first if that was asked for.
+fn run_event<S, E, T>(
+    ctx: &mut Context,
+    state: &mut S,
+    event: Event<()>,
+    target: &EventLoopWindowTarget<()>,
+    control_flow: &mut ControlFlow,
+) where
+    S: EventHandler<E, T> + 'static,
+    E: std::fmt::Debug,
+    T: Send + 'static,
+{
+    if let Err(e) = ctx.gfx.create_pending_windows(target) {
+        error!(
+            "Error on GraphicsContext::create_pending_windows(): {:?}",
+            e
+        );
+        eprintln!(
+            "Error on GraphicsContext::create_pending_windows(): {:?}",
+            e
+        );
+    }
+
+    if ctx.quit_requested {
+        ctx.quit_requested = false;
+        if ask_to_quit(ctx, state, control_flow) {
             return;
         }
+    }
+    if !ctx.continuing {
+        if let Some(ExitReason::Error(e)) = ctx.exit_reason() {
+            error!("Quitting because of an error: {:?}", e);
+            eprintln!("Quitting because of an error: {:?}", e);
+        }
+        *control_flow = ControlFlow::ExitWithCode(ctx.exit_reason().map_or(0, ExitReason::code));
+        return;
+    }
 
-        *control_flow = ControlFlow::Poll;
+    *control_flow = waiting(ctx);
 
-        process_event(ctx, &mut event);
-        match event {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::Resized(logical_size) => {
-                    // let actual_size = logical_size;
-                    let res = state.resize_event(
-                        ctx,
-                        logical_size.width as f32,
-                        logical_size.height as f32,
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::CloseRequested => {
-                    let res = state.quit_event(ctx);
-                    if let Ok(false) = state.quit_event(ctx) {
-                        ctx.continuing = false;
-                    } else if catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent) {
-                        return;
-                    }
-                }
-                WindowEvent::Focused(gained) => {
-                    let res = state.focus_event(ctx, gained);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::FocusEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::ReceivedCharacter(ch) => {
-                    let res = state.text_input_event(ctx, ch);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::ModifiersChanged(mods) => {
-                    ctx.keyboard.set_modifiers(KeyMods::from(mods))
-                }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: keycode,
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    let repeat = ctx.keyboard.is_key_repeated();
-                    let res = state.key_down_event(
-                        ctx,
-                        KeyInput {
-                            scancode,
-                            keycode,
-                            mods: ctx.keyboard.active_mods(),
-                        },
-                        repeat,
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyDownEvent) {
-                        return;
-                    };
+    // live input is ignored while recorded input is played back
+    if ctx.recording.suppresses(&event) {
+        return;
+    }
+    ctx.recording
+        .record_event(&event, &ctx.time, ctx.gfx.window().id());
+    if let Event::MainEventsCleared = event {
+        for event in ctx
+            .recording
+            .playback_events(&ctx.time, ctx.gfx.window().id())
+        {
+            if let Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } = event
+            {
+                ctx.gfx.window().set_inner_size(size);
+                continue;
+            }
+            handle_event(ctx, state, event, target, control_flow);
+            if *control_flow == ControlFlow::Exit {
+                return;
+            }
+        }
+    }
+    // the phases of the frame are timed on their own
+    let _scope =
+        (!matches!(event, Event::MainEventsCleared)).then(|| crate::timer::scope("events"));
+    handle_event(ctx, state, event, target, control_flow);
+}
+
+// Processes an event and passes it to the event handler.
+#[allow(clippy::needless_return)] // necessary as the returns used here are actually necessary to break early from the event loop
+fn handle_event<S, E, T>(
+    ctx: &mut Context,
+    state: &mut S,
+    mut event: Event<()>,
+    target: &EventLoopWindowTarget<()>,
+    control_flow: &mut ControlFlow,
+) where
+    S: EventHandler<E, T> + 'static,
+    E: std::fmt::Debug,
+    T: Send + 'static,
+{
+    // ggez's own handling of the event still happens if the raw callback consumed it
+    let consumed = match &event {
+        Event::WindowEvent { event, window_id } if *window_id == ctx.gfx.window().id() => {
+            let res = state.raw_window_event(ctx, event);
+            let consumed = matches!(res, Ok(EventConsumed::Consumed));
+            if catch_error(ctx, res, state, control_flow, ErrorOrigin::RawWindowEvent) {
+                return;
+            }
+            consumed
+        }
+        _ => false,
+    };
+    process_event(ctx, &mut event);
+    if consumed {
+        return;
+    }
+    match event {
+        Event::WindowEvent { event, window_id } if window_id != ctx.gfx.window().id() => {
+            if let Some((window, event)) = secondary_window_event(ctx, window_id, &event) {
+                let res = state.secondary_window_event(ctx, window, event);
+                if let SecondaryWindowEvent::CloseRequested = event {
+                    let _ = ctx.gfx.close_window(window);
                 }
-                WindowEvent::KeyboardInput {
-                    input:
-                        KeyboardInput {
-                            state: ElementState::Released,
-                            virtual_keycode: keycode,
-                            scancode,
-                            ..
-                        },
-                    ..
-                } => {
-                    let res = state.key_up_event(
-                        ctx,
-                        KeyInput {
-                            scancode,
-                            keycode,
-                            mods: ctx.keyboard.active_mods(),
-                        },
-                    );
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
-                        return;
-                    };
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::SecondaryWindowEvent,
+                ) {
+                    return;
+                };
+            }
+        }
+        Event::WindowEvent { event, .. } => match event {
+            // may have been reported already, see below
+            WindowEvent::Resized(logical_size)
+                if logical_size != ctx.gfx.reported_size
+                    && !ctx.gfx.reported_minimized
+                    && !ctx.gfx.is_minimized() =>
+            {
+                ctx.gfx.reported_size = logical_size;
+                let res =
+                    state.resize_event(ctx, logical_size.width as f32, logical_size.height as f32);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
+                    return;
+                };
+            }
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                new_inner_size,
+            } => {
+                let size = (new_inner_size.width, new_inner_size.height);
+                let res = state.scale_factor_changed_event(ctx, scale_factor as f32, size);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::ScaleFactorChangedEvent,
+                ) {
+                    return;
+                };
+            }
+            WindowEvent::Moved(position) => {
+                let res = state.window_moved_event(ctx, position.x, position.y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::WindowMovedEvent) {
+                    return;
+                };
+            }
+            WindowEvent::CloseRequested => {
+                ctx.set_exit_reason(ExitReason::UserAction);
+                if ask_to_quit(ctx, state, control_flow) {
+                    return;
                 }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    let (x, y) = match delta {
-                        MouseScrollDelta::LineDelta(x, y) => (x, y),
-                        MouseScrollDelta::PixelDelta(pos) => {
-                            let scale_factor = ctx.gfx.window.scale_factor();
-                            let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
-                            (x, y)
+            }
+            WindowEvent::DroppedFile(path) => {
+                let pos = ctx.mouse.position();
+                let res = state.file_drop_event(ctx, path, pos.x, pos.y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::FileDropEvent) {
+                    return;
+                };
+            }
+            WindowEvent::HoveredFile(path) => {
+                let res = state.file_hover_event(ctx, path);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::FileDropEvent) {
+                    return;
+                };
+            }
+            WindowEvent::HoveredFileCancelled => {
+                let res = state.file_hover_cancelled_event(ctx);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::FileDropEvent) {
+                    return;
+                };
+            }
+            WindowEvent::Focused(gained) => {
+                let res = state.focus_event(ctx, gained);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::FocusEvent) {
+                    return;
+                };
+            }
+            WindowEvent::ReceivedCharacter(ch) => {
+                let res = state.text_input_event(ctx, ch);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::TextInputEvent) {
+                    return;
+                };
+            }
+            WindowEvent::Ime(ime) => {
+                let res = match ime {
+                    Ime::Enabled => state.ime_enabled_event(ctx, true),
+                    Ime::Preedit(text, cursor) => state.ime_preedit_event(ctx, &text, cursor),
+                    Ime::Commit(text) => state.ime_commit_event(ctx, &text),
+                    Ime::Disabled => state.ime_enabled_event(ctx, false),
+                };
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::ImeEvent) {
+                    return;
+                };
+            }
+            WindowEvent::ModifiersChanged(mods) => ctx.keyboard.set_modifiers(KeyMods::from(mods)),
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: keycode,
+                        scancode,
+                        ..
+                    },
+                ..
+            } => {
+                let repeat = ctx.keyboard.is_key_repeated();
+                let res = state.key_down_event(
+                    ctx,
+                    KeyInput {
+                        scancode,
+                        keycode,
+                        mods: ctx.keyboard.active_mods(),
+                        time: Instant::now(),
+                    },
+                    repeat,
+                );
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyDownEvent) {
+                    return;
+                };
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Released,
+                        virtual_keycode: keycode,
+                        scancode,
+                        ..
+                    },
+                ..
+            } => {
+                let res = state.key_up_event(
+                    ctx,
+                    KeyInput {
+                        scancode,
+                        keycode,
+                        mods: ctx.keyboard.active_mods(),
+                        time: Instant::now(),
+                    },
+                );
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::KeyUpEvent) {
+                    return;
+                };
+            }
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let (x, y) = wheel_offset(delta, ctx.gfx.window().scale_factor());
+                let res = state.mouse_wheel_event(ctx, x, y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
+                    return;
+                };
+                let res = state.mouse_scroll_event(ctx, ScrollDelta::from(delta), phase);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
+                    return;
+                };
+            }
+            WindowEvent::MouseInput {
+                state: element_state,
+                button,
+                ..
+            } => {
+                let position = ctx.mouse.position();
+                match element_state {
+                    ElementState::Pressed => {
+                        let res =
+                            state.mouse_button_down_event(ctx, button, position.x, position.y);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::MouseButtonDownEvent,
+                        ) {
+                            return;
+                        };
+                        if button == MouseButton::Left {
+                            let event = PenEvent::from_mouse(&ctx.mouse, PenPhase::Down);
+                            let res = state.pen_event(ctx, event);
+                            if catch_error(ctx, res, state, control_flow, ErrorOrigin::PenEvent) {
+                                return;
+                            };
                         }
-                    };
-                    let res = state.mouse_wheel_event(ctx, x, y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseWheelEvent) {
-                        return;
-                    };
-                }
-                WindowEvent::MouseInput {
-                    state: element_state,
-                    button,
-                    ..
-                } => {
-                    let position = ctx.mouse.position();
-                    match element_state {
-                        ElementState::Pressed => {
-                            let res =
-                                state.mouse_button_down_event(ctx, button, position.x, position.y);
+                        if let Some(click) = ctx.mouse.last_click() {
+                            let res = state.mouse_click_event(
+                                ctx,
+                                button,
+                                position.x,
+                                position.y,
+                                click.count,
+                            );
                             if catch_error(
                                 ctx,
                                 res,
                                 state,
                                 control_flow,
-                                ErrorOrigin::MouseButtonDownEvent,
+                                ErrorOrigin::MouseClickEvent,
                             ) {
                                 return;
                             };
                         }
-                        ElementState::Released => {
-                            let res =
-                                state.mouse_button_up_event(ctx, button, position.x, position.y);
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::MouseButtonUpEvent,
-                            ) {
+                    }
+                    ElementState::Released => {
+                        let res = state.mouse_button_up_event(ctx, button, position.x, position.y);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::MouseButtonUpEvent,
+                        ) {
+                            return;
+                        };
+                        if button == MouseButton::Left {
+                            let event = PenEvent::from_mouse(&ctx.mouse, PenPhase::Up);
+                            let res = state.pen_event(ctx, event);
+                            if catch_error(ctx, res, state, control_flow, ErrorOrigin::PenEvent) {
                                 return;
                             };
                         }
                     }
                 }
-                WindowEvent::CursorMoved { .. } => {
-                    let position = ctx.mouse.position();
-                    let delta = ctx.mouse.last_delta();
-                    let res =
-                        state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseMotionEvent) {
+            }
+            WindowEvent::CursorMoved { .. } => {
+                let position = ctx.mouse.position();
+                let delta = ctx.mouse.last_delta();
+                let res = state.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::MouseMotionEvent) {
+                    return;
+                };
+                let event = PenEvent::from_mouse(&ctx.mouse, pen::mouse_motion_phase(&ctx.mouse));
+                let res = state.pen_event(ctx, event);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::PenEvent) {
+                    return;
+                };
+            }
+            WindowEvent::CursorEntered { .. } | WindowEvent::CursorLeft { .. } => {
+                let entered = ctx.mouse.is_cursor_in_window();
+                let res = state.mouse_enter_or_leave(ctx, entered);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::MouseEnterOrLeave,
+                ) {
+                    return;
+                };
+            }
+            WindowEvent::Touch(touch) => {
+                let (x, y) = (touch.location.x as f32, touch.location.y as f32);
+                let res = state.touch_event(ctx, touch.phase, touch.id, x, y);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
+                    return;
+                };
+                if let Some(event) = PenEvent::from_touch(&touch) {
+                    let res = state.pen_event(ctx, event);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::PenEvent) {
                         return;
                     };
                 }
-                WindowEvent::Touch(touch) => {
-                    let res =
-                        state.touch_event(ctx, touch.phase, touch.location.x, touch.location.y);
-                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::TouchEvent) {
+            }
+            _x => {
+                // trace!("ignoring window event {:?}", x);
+            }
+        },
+        Event::DeviceEvent {
+            event: DeviceEvent::MouseMotion { delta },
+            ..
+        } => {
+            if ctx.mouse.wants_raw_motion() {
+                let res = state.raw_mouse_motion_event(ctx, delta.0, delta.1);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::RawMouseMotionEvent,
+                ) {
+                    return;
+                };
+            }
+        }
+        Event::DeviceEvent { .. } => (),
+        Event::Resumed => {
+            if ctx.is_suspended() {
+                let launching = std::mem::take(&mut ctx.launching);
+                ctx.resume();
+                if !launching {
+                    let res = state.resume_event(ctx);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResumeEvent) {
                         return;
                     };
                 }
-                _x => {
-                    // trace!("ignoring window event {:?}", x);
+            }
+        }
+        Event::Suspended => {
+            if !ctx.is_suspended() {
+                ctx.suspend();
+                let res = state.suspend_event(ctx);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::SuspendEvent) {
+                    return;
+                };
+            }
+        }
+        Event::NewEvents(
+            winit::event::StartCause::WaitCancelled { .. }
+            | winit::event::StartCause::ResumeTimeReached { .. },
+        ) => {
+            // the time the loop waited for events isn't owed to updates
+            ctx.time.skip_paused_time();
+        }
+        Event::NewEvents(_) => (),
+        Event::UserEvent(()) => {
+            for event in ctx.user_events::<T>() {
+                let res = state.user_event(ctx, event);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::UserEvent) {
+                    return;
+                };
+            }
+        }
+        Event::MainEventsCleared if ctx.is_suspended() => (),
+        Event::MainEventsCleared => {
+            // If you are writing your own event loop, make sure
+            // you include `timer_context.tick()` and
+            // `ctx.process_event()` calls.  These update ggez's
+            // internal state however necessary.
+            ctx.time.tick();
+            ctx.redraw_requested = false;
+            ctx.keyboard.update_repeats(ctx.time.unscaled_delta());
+            #[cfg(feature = "gamepad")]
+            ctx.gamepad.update_repeats(ctx.time.unscaled_delta());
+
+            for gesture in ctx.touch.recognize_gestures().to_vec() {
+                let res = match gesture {
+                    Gesture::Pinch { scale, center } => state.pinch_event(ctx, scale, center),
+                    Gesture::Rotate { angle, center } => state.rotate_event(ctx, angle, center),
+                    Gesture::Pan { delta } => state.pan_event(ctx, delta),
+                };
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::GestureEvent) {
+                    return;
+                };
+            }
+
+            let minimized = ctx.gfx.is_minimized();
+            if minimized != ctx.gfx.reported_minimized {
+                ctx.gfx.reported_minimized = minimized;
+                let res = state.window_minimized_event(ctx, minimized);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::WindowStateEvent) {
+                    return;
+                };
+                if !minimized {
+                    // resizes aren't reported while minimized, so report the restored size in any case
+                    let size = ctx.gfx.window().inner_size();
+                    ctx.gfx.reported_size = size;
+                    let res = state.resize_event(ctx, size.width as f32, size.height as f32);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
+                        return;
+                    };
                 }
-            },
-            Event::DeviceEvent { .. } => (),
-            Event::Resumed => (),
-            Event::Suspended => (),
-            Event::NewEvents(_) => (),
-            Event::UserEvent(_) => (),
-            Event::MainEventsCleared => {
-                // If you are writing your own event loop, make sure
-                // you include `timer_context.tick()` and
-                // `ctx.process_event()` calls.  These update ggez's
-                // internal state however necessary.
-                ctx.time.tick();
-
-                // Handle gamepad events if necessary.
-                #[cfg(feature = "gamepad")]
-                while let Some(gilrs::Event { id, event, .. }) = ctx.gamepad.next_event() {
-                    match event {
-                        gilrs::EventType::ButtonPressed(button, _) => {
-                            let res = state.gamepad_button_down_event(ctx, button, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadButtonDownEvent,
-                            ) {
-                                return;
-                            };
-                        }
-                        gilrs::EventType::ButtonReleased(button, _) => {
-                            let res = state.gamepad_button_up_event(ctx, button, GamepadId(id));
-                            if catch_error(
-                                ctx,
-                                res,
-                                state,
-                                control_flow,
-                                ErrorOrigin::GamepadButtonUpEvent,
-                            ) {
-                                return;
+            }
+            if ctx.gfx.maximized != ctx.gfx.reported_maximized {
+                let maximized = ctx.gfx.maximized;
+                ctx.gfx.reported_maximized = maximized;
+                let res = state.window_maximized_event(ctx, maximized);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::WindowStateEvent) {
+                    return;
+                };
+            }
+
+            // Some size changes, like switching video modes, aren't reliably reported by the OS.
+            let size = ctx.gfx.window().inner_size();
+            if size != ctx.gfx.reported_size && !minimized {
+                ctx.gfx.reported_size = size;
+                let res = state.resize_event(ctx, size.width as f32, size.height as f32);
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::ResizeEvent) {
+                    return;
+                };
+            }
+
+            // Handle gamepad events if necessary.
+            #[cfg(feature = "gamepad")]
+            let gamepad_events = ctx.recording.gamepad_events(&ctx.time, &mut ctx.gamepad);
+            #[cfg(feature = "gamepad")]
+            for (id, connected) in ctx.gamepad.take_connection_events() {
+                let res = if connected {
+                    let info = ctx.gamepad.gamepad_info(id);
+                    state.gamepad_connected_event(ctx, id, info)
+                } else {
+                    state.gamepad_disconnected_event(ctx, id)
+                };
+                let origin = if connected {
+                    ErrorOrigin::GamepadConnectedEvent
+                } else {
+                    ErrorOrigin::GamepadDisconnectedEvent
+                };
+                if catch_error(ctx, res, state, control_flow, origin) {
+                    return;
+                };
+            }
+            #[cfg(feature = "gamepad")]
+            for (event, time) in gamepad_events {
+                ctx.gamepad.set_event_time(time);
+                match event {
+                    RecordedEvent::GamepadButton {
+                        id,
+                        button,
+                        pressed: true,
+                    } => {
+                        ctx.gamepad.set_button(id, button, true);
+                        let res = state.gamepad_button_down_event(ctx, button, id);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::GamepadButtonDownEvent,
+                        ) {
+                            return;
+                        };
+                    }
+                    RecordedEvent::GamepadButton {
+                        id,
+                        button,
+                        pressed: false,
+                    } => {
+                        ctx.gamepad.set_button(id, button, false);
+                        let res = state.gamepad_button_up_event(ctx, button, id);
+                        if catch_error(
+                            ctx,
+                            res,
+                            state,
+                            control_flow,
+                            ErrorOrigin::GamepadButtonUpEvent,
+                        ) {
+                            return;
+                        };
+                    }
+                    RecordedEvent::GamepadAxis { id, axis, value } => {
+                        ctx.gamepad.set_axis(id, axis, value);
+                        let value = if ctx.gamepad.process_axis_events() {
+                            ctx.gamepad.axis_value(id, axis)
+                        } else {
+                            ctx.gamepad.raw_axis_value(id, axis)
+                        };
+                        let res = state.gamepad_axis_event(ctx, axis, value, id);
+                        if catch_error(ctx, res, state, control_flow, ErrorOrigin::GamepadAxisEvent)
+                        {
+                            return;
+                        };
+                    }
+                    RecordedEvent::GamepadButtonValue { id, button, value } => {
+                        // triggers are reported as axes too
+                        if let Some(axis) = ctx.gamepad.set_button_value(id, button, value) {
+                            let value = if ctx.gamepad.process_axis_events() {
+                                ctx.gamepad.axis_value(id, axis)
+                            } else {
+                                ctx.gamepad.raw_axis_value(id, axis)
                             };
-                        }
-                        gilrs::EventType::AxisChanged(axis, value, _) => {
-                            let res = state.gamepad_axis_event(ctx, axis, value, GamepadId(id));
+                            let res = state.gamepad_axis_event(ctx, axis, value, id);
                             if catch_error(
                                 ctx,
                                 res,
@@ -512,67 +1606,172 @@ where
                                 return;
                             };
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
+            }
 
-                let res = state.update(ctx);
-                if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
+            #[cfg(feature = "audio")]
+            if let Some(device) = ctx.audio.follow_output_device() {
+                let res = state.audio_device_changed_event(ctx, &device);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::AudioDeviceChangedEvent,
+                ) {
                     return;
                 };
+            }
 
-                if let Err(e) = ctx.gfx.begin_frame() {
-                    error!("Error on GraphicsContext::begin_frame(): {:?}", e);
-                    eprintln!("Error on GraphicsContext::begin_frame(): {:?}", e);
-                    *control_flow = ControlFlow::Exit;
-                }
+            #[cfg(feature = "audio")]
+            for sound in ctx.audio.finished_sounds() {
+                let res = state.audio_finished_event(ctx, sound);
+                if catch_error(
+                    ctx,
+                    res,
+                    state,
+                    control_flow,
+                    ErrorOrigin::AudioFinishedEvent,
+                ) {
+                    return;
+                };
+            }
 
-                if let Err(e) = state.draw(ctx) {
-                    error!("Error on EventHandler::draw(): {:?}", e);
-                    eprintln!("Error on EventHandler::draw(): {:?}", e);
-                    if state.on_error(ctx, ErrorOrigin::Draw, e) {
-                        *control_flow = ControlFlow::Exit;
+            if !ctx.updates_paused() {
+                while ctx.time.take_fixed_update() {
+                    let _scope = crate::timer::scope("fixed_update");
+                    let res = state.fixed_update(ctx);
+                    if catch_error(ctx, res, state, control_flow, ErrorOrigin::FixedUpdate) {
                         return;
-                    }
+                    };
                 }
 
-                if let Err(e) = ctx.gfx.end_frame() {
-                    error!("Error on GraphicsContext::end_frame(): {:?}", e);
-                    eprintln!("Error on GraphicsContext::end_frame(): {:?}", e);
+                let res = {
+                    let _scope = crate::timer::scope("update");
+                    state.update(ctx)
+                };
+                if catch_error(ctx, res, state, control_flow, ErrorOrigin::Update) {
+                    return;
+                };
+            }
+
+            // windows created during `update` are available to draw to right away
+            if let Err(e) = ctx.gfx.create_pending_windows(target) {
+                error!(
+                    "Error on GraphicsContext::create_pending_windows(): {:?}",
+                    e
+                );
+                eprintln!(
+                    "Error on GraphicsContext::create_pending_windows(): {:?}",
+                    e
+                );
+            }
+
+            if let Err(e) = ctx.gfx.begin_frame() {
+                error!("Error on GraphicsContext::begin_frame(): {:?}", e);
+                eprintln!("Error on GraphicsContext::begin_frame(): {:?}", e);
+                *control_flow = ControlFlow::Exit;
+            }
+
+            let res = {
+                let _scope = crate::timer::scope("draw");
+                state.draw(ctx)
+            };
+            if let Err(e) = res {
+                error!("Error on EventHandler::draw(): {:?}", e);
+                eprintln!("Error on EventHandler::draw(): {:?}", e);
+                if state.on_error(ctx, ErrorOrigin::Draw, e) == ErrorAction::Abort {
                     *control_flow = ControlFlow::Exit;
+                    return;
                 }
+            }
 
-                // reset the mouse delta for the next frame
-                // necessary because it's calculated cumulatively each cycle
-                ctx.mouse.reset_delta();
-
-                // Copy the state of the keyboard into the KeyboardContext
-                // and the mouse into the MouseContext
-                ctx.keyboard.save_keyboard_state();
-                ctx.mouse.save_mouse_state();
+            let res = {
+                let _scope = crate::timer::scope("present");
+                ctx.gfx.end_frame()
+            };
+            if let Err(e) = res {
+                error!("Error on GraphicsContext::end_frame(): {:?}", e);
+                eprintln!("Error on GraphicsContext::end_frame(): {:?}", e);
+                *control_flow = ControlFlow::Exit;
             }
-            Event::RedrawRequested(_) => (),
-            Event::RedrawEventsCleared => (),
-            Event::LoopDestroyed => (),
+
+            // reset the mouse delta for the next frame
+            // necessary because it's calculated cumulatively each cycle
+            ctx.mouse.reset_delta();
+
+            // Copy the state of the keyboard into the KeyboardContext
+            // and the mouse into the MouseContext
+            ctx.keyboard.save_keyboard_state();
+            ctx.mouse.save_mouse_state();
+            ctx.touch.save_touch_state();
+            #[cfg(feature = "gamepad")]
+            ctx.gamepad.save_gamepad_state();
+
+            ctx.time.wait_for_next_frame();
         }
-    })
+        Event::RedrawRequested(_) => (),
+        Event::RedrawEventsCleared => (),
+        Event::LoopDestroyed => (),
+    }
 }
 
-fn catch_error<T, E, S: 'static>(
+// How the loop waits for events after this one, which is what it does once the last event
+// before waiting is handled.
+fn waiting(ctx: &Context) -> ControlFlow {
+    if ctx.is_suspended() {
+        // there are no frames to run while suspended
+        return ControlFlow::Wait;
+    }
+    match ctx.loop_mode() {
+        LoopMode::Continuous => ControlFlow::Poll,
+        LoopMode::WaitForEvents { .. } if ctx.redraw_requested => ControlFlow::Poll,
+        LoopMode::WaitForEvents {
+            max_wait: Some(max_wait),
+        } => ControlFlow::WaitUntil(Instant::now() + max_wait),
+        LoopMode::WaitForEvents { max_wait: None } => ControlFlow::Wait,
+    }
+}
+
+// Calls `quit_event`, stopping the loop unless it cancels quitting. Returns whether the error it
+// returned was fatal.
+fn ask_to_quit<S, E, T>(ctx: &mut Context, state: &mut S, control_flow: &mut ControlFlow) -> bool
+where
+    S: EventHandler<E, T> + 'static,
+    E: std::fmt::Debug,
+{
+    ctx.quit_cancelled = false;
+    let res = state.quit_event(ctx);
+    match res {
+        Ok(false) if !ctx.quit_cancelled => {
+            ctx.continuing = false;
+            false
+        }
+        Ok(_) => {
+            let _ = ctx.take_exit_reason();
+            false
+        }
+        Err(_) => catch_error(ctx, res, state, control_flow, ErrorOrigin::QuitEvent),
+    }
+}
+
+fn catch_error<R, E, T, S: 'static>(
     ctx: &mut Context,
-    event_result: Result<T, E>,
+    event_result: Result<R, E>,
     state: &mut S,
     control_flow: &mut ControlFlow,
     origin: ErrorOrigin,
 ) -> bool
 where
-    S: EventHandler<E>,
+    S: EventHandler<E, T>,
     E: std::fmt::Debug,
 {
     if let Err(e) = event_result {
         error!("Error on EventHandler {:?}: {:?}", origin, e);
         eprintln!("Error on EventHandler {:?}: {:?}", origin, e);
-        if state.on_error(ctx, origin, e) {
+        if state.on_error(ctx, origin, e) == ErrorAction::Abort {
             *control_flow = ControlFlow::Exit;
             return true;
         }
@@ -584,11 +1783,74 @@ where
 /// state it needs to, such as detecting window resizes.  If you are
 /// rolling your own event loop, you should call this on the events
 /// you receive before processing them yourself.
-pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
-    if let winit_event::Event::WindowEvent { event, .. } = event {
+pub fn process_event(ctx: &mut Context, event: &mut winit_event::Event<()>) {
+    ctx.keyboard.apply_ime(ctx.gfx.window());
+    if let winit_event::Event::WindowEvent { event, window_id } = event {
+        if *window_id != ctx.gfx.window().id() {
+            match event {
+                winit_event::WindowEvent::Resized(_) => {
+                    ctx.gfx.resize_secondary_window(*window_id);
+                }
+                winit_event::WindowEvent::CursorMoved {
+                    position: physical_position,
+                    ..
+                } => {
+                    if let Some(window) = ctx.gfx.secondary_window_mut(*window_id) {
+                        window.cursor = mint::Point2 {
+                            x: physical_position.x as f32,
+                            y: physical_position.y as f32,
+                        };
+                    }
+                }
+                winit_event::WindowEvent::ModifiersChanged(_)
+                | winit_event::WindowEvent::KeyboardInput { .. } => {
+                    process_keyboard_event(ctx, event)
+                }
+                winit_event::WindowEvent::ReceivedCharacter(ch) => ctx.keyboard.push_text(*ch),
+                _ => (),
+            }
+            return;
+        }
+
         match event {
             winit_event::WindowEvent::Resized(physical_size) => {
+                ctx.gfx.minimized = physical_size.width == 0 || physical_size.height == 0;
+                ctx.gfx.maximized = ctx.gfx.window().is_maximized();
                 ctx.gfx.resize(*physical_size);
+                ctx.gfx.snap_to_resize_increments();
+                // AppKit resets the cursor to winit's icon along with the window's size
+                #[cfg(target_os = "macos")]
+                ctx.mouse.restore_cursor_image();
+            }
+            winit_event::WindowEvent::Focused(false) => {
+                // keys released while the window is unfocused are never reported, so they'd get stuck
+                ctx.keyboard.release_all();
+                ctx.touch.cancel_all();
+                ctx.mouse.set_window_focused(false);
+                ctx.time.set_focused(false);
+                #[cfg(feature = "audio")]
+                if ctx.unfocused_behavior().mute_audio {
+                    ctx.audio.set_unfocused_muted(true);
+                }
+                if ctx.gfx.window_mode.minimize_on_focus_loss
+                    && ctx.gfx.current_video_mode().is_some()
+                {
+                    ctx.gfx.set_minimized(true);
+                }
+            }
+            winit_event::WindowEvent::Focused(true) => {
+                ctx.gfx.minimized = false;
+                ctx.mouse.set_window_focused(true);
+                ctx.time.set_focused(true);
+                if ctx.unfocused_behavior().pause_updates {
+                    ctx.time.skip_paused_time();
+                }
+                #[cfg(feature = "audio")]
+                ctx.audio.set_unfocused_muted(false);
+                if ctx.gfx.attention_requested {
+                    ctx.gfx.attention_requested = false;
+                    ctx.gfx.window().request_user_attention(None);
+                }
             }
             winit_event::WindowEvent::CursorMoved {
                 position: physical_position,
@@ -597,46 +1859,177 @@ pub fn process_event(ctx: &mut Context, event: &mut winit::event::Event<()>) {
                 ctx.mouse
                     .handle_move(physical_position.x as f32, physical_position.y as f32);
             }
-            winit_event::WindowEvent::MouseInput { button, state, .. } => {
-                let pressed = match state {
-                    winit_event::ElementState::Pressed => true,
-                    winit_event::ElementState::Released => false,
+            winit_event::WindowEvent::CursorEntered { .. } => {
+                ctx.mouse.set_cursor_in_window(true);
+            }
+            winit_event::WindowEvent::CursorLeft { .. } => {
+                ctx.mouse.set_cursor_in_window(false);
+            }
+            winit_event::WindowEvent::Touch(touch) => {
+                let position = mint::Point2 {
+                    x: touch.location.x as f32,
+                    y: touch.location.y as f32,
                 };
-                ctx.mouse.set_button(*button, pressed);
+                let force = touch.force.map(|force| force.normalized() as f32);
+                ctx.touch
+                    .handle_touch(touch.id, touch.phase, position, force);
             }
-            winit_event::WindowEvent::ModifiersChanged(mods) => {
-                ctx.keyboard.set_modifiers(KeyMods::from(*mods))
+            winit_event::WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = wheel_offset(*delta, ctx.gfx.window().scale_factor());
+                ctx.mouse.handle_wheel(x, y);
+                ctx.mouse.handle_scroll(ScrollDelta::from(*delta));
             }
-            winit_event::WindowEvent::KeyboardInput {
-                input:
-                    winit::event::KeyboardInput {
-                        state,
-                        scancode,
-                        virtual_keycode: keycode,
-                        ..
-                    },
-                ..
-            } => {
+            winit_event::WindowEvent::MouseInput { button, state, .. } => {
                 let pressed = match state {
                     winit_event::ElementState::Pressed => true,
                     winit_event::ElementState::Released => false,
                 };
-                ctx.keyboard.set_scancode(*scancode, pressed);
-                if let Some(key) = keycode {
-                    ctx.keyboard.set_key(*key, pressed);
-                }
+                ctx.mouse.set_button(*button, pressed);
+            }
+            winit_event::WindowEvent::ModifiersChanged(_)
+            | winit_event::WindowEvent::KeyboardInput { .. } => process_keyboard_event(ctx, event),
+            winit_event::WindowEvent::ReceivedCharacter(ch) => ctx.keyboard.push_text(*ch),
+            winit_event::WindowEvent::Ime(Ime::Commit(text)) => {
+                text.chars().for_each(|ch| ctx.keyboard.push_text(ch))
             }
             winit_event::WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                 if !ctx.conf.window_mode.resize_on_scale_factor_change {
-                    // actively set the new_inner_size to be the desired size
+                    // actively set the new_inner_size to be the current size
                     // to stop winit from resizing our window
-                    **new_inner_size = winit::dpi::PhysicalSize::<u32>::from([
-                        ctx.conf.window_mode.width,
-                        ctx.conf.window_mode.height,
-                    ]);
+                    **new_inner_size = ctx.gfx.window().inner_size();
+                } else if ctx.gfx.window().fullscreen().is_none() {
+                    // the same logical size, but still within the limits, which are physical
+                    let mode = &ctx.gfx.window_mode;
+                    **new_inner_size = mode.clamp_size(new_inner_size.cast()).cast();
                 }
             }
             _ => (),
         }
+    } else if let winit_event::Event::DeviceEvent {
+        event: winit_event::DeviceEvent::MouseMotion { delta },
+        ..
+    } = event
+    {
+        ctx.mouse.handle_raw_motion(delta.0, delta.1);
+    };
+}
+
+// The scroll amount passed to `mouse_wheel_event`, in lines or logical pixels.
+fn wheel_offset(delta: MouseScrollDelta, scale_factor: f64) -> (f32, f32) {
+    match delta {
+        MouseScrollDelta::LineDelta(x, y) => (x, y),
+        MouseScrollDelta::PixelDelta(pos) => {
+            let dpi::LogicalPosition { x, y } = pos.to_logical::<f32>(scale_factor);
+            (x, y)
+        }
+    }
+}
+
+// The keyboard state is shared between all windows.
+fn process_keyboard_event(ctx: &mut Context, event: &winit_event::WindowEvent) {
+    match event {
+        winit_event::WindowEvent::ModifiersChanged(mods) => {
+            ctx.keyboard.set_modifiers(KeyMods::from(*mods))
+        }
+        winit_event::WindowEvent::KeyboardInput {
+            input:
+                winit::event::KeyboardInput {
+                    state,
+                    scancode,
+                    virtual_keycode: keycode,
+                    ..
+                },
+            ..
+        } => {
+            let pressed = match state {
+                winit_event::ElementState::Pressed => true,
+                winit_event::ElementState::Released => false,
+            };
+            ctx.keyboard.set_scancode(*scancode, pressed);
+            if let Some(key) = keycode {
+                ctx.keyboard.set_key(*key, pressed);
+                ctx.keyboard.learn_scancode(*scancode, *key);
+            }
+        }
+        _ => (),
+    }
+}
+
+// Translates an event of a secondary window, if it is one ggez reports.
+fn secondary_window_event(
+    ctx: &Context,
+    window_id: winit::window::WindowId,
+    event: &winit_event::WindowEvent,
+) -> Option<(WindowId, SecondaryWindowEvent)> {
+    let window = ctx
+        .gfx
+        .windows
+        .iter()
+        .find(|window| window.window.id() == window_id)?;
+    let cursor = window.cursor;
+
+    let event = match event {
+        WindowEvent::Resized(size) => SecondaryWindowEvent::Resized {
+            width: size.width as f32,
+            height: size.height as f32,
+        },
+        WindowEvent::CloseRequested => SecondaryWindowEvent::CloseRequested,
+        WindowEvent::Focused(gained) => SecondaryWindowEvent::Focused(*gained),
+        WindowEvent::CursorEntered { .. } => SecondaryWindowEvent::MouseEnterOrLeave(true),
+        WindowEvent::CursorLeft { .. } => SecondaryWindowEvent::MouseEnterOrLeave(false),
+        WindowEvent::CursorMoved { .. } => SecondaryWindowEvent::MouseMotion {
+            x: cursor.x,
+            y: cursor.y,
+        },
+        WindowEvent::MouseInput {
+            state: ElementState::Pressed,
+            button,
+            ..
+        } => SecondaryWindowEvent::MouseButtonDown {
+            button: *button,
+            x: cursor.x,
+            y: cursor.y,
+        },
+        WindowEvent::MouseInput {
+            state: ElementState::Released,
+            button,
+            ..
+        } => SecondaryWindowEvent::MouseButtonUp {
+            button: *button,
+            x: cursor.x,
+            y: cursor.y,
+        },
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (x, y) = wheel_offset(*delta, window.window.scale_factor());
+            SecondaryWindowEvent::MouseWheel { x, y }
+        }
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state,
+                    virtual_keycode: keycode,
+                    scancode,
+                    ..
+                },
+            ..
+        } => {
+            let input = KeyInput {
+                scancode: *scancode,
+                keycode: *keycode,
+                mods: ctx.keyboard.active_mods(),
+                time: Instant::now(),
+            };
+            match state {
+                ElementState::Pressed => SecondaryWindowEvent::KeyDown {
+                    input,
+                    repeated: ctx.keyboard.is_key_repeated(),
+                },
+                ElementState::Released => SecondaryWindowEvent::KeyUp(input),
+            }
+        }
+        WindowEvent::ReceivedCharacter(ch) => SecondaryWindowEvent::TextInput(*ch),
+        _ => return None,
     };
+
+    Some((window.id, event))
 }
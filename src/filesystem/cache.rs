@@ -0,0 +1,205 @@
+//! Data the game makes from its assets and can make again, such as mipmaps or compiled
+//! shaders, kept in the user's cache directory.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::{File, Filesystem, OpenOptions};
+use crate::error::{GameError, GameResult, ResultExt};
+use crate::vfs::{self, VFile, VFS};
+
+// Where temporary files are made in the cache.
+const TEMP_DIR: &str = "/temp";
+
+// For the names of temporary files, so each is made new.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// What's in the cache directory, shared by the clones of a [`Filesystem`].
+pub(crate) type Cache = Arc<Mutex<Box<dyn VFS>>>;
+
+pub(crate) fn physical_cache(dir: &Path) -> Cache {
+    Arc::new(Mutex::new(Box::new(vfs::PhysicalFS::new(dir, false))))
+}
+
+pub(crate) fn memory_cache() -> Cache {
+    Arc::new(Mutex::new(Box::new(
+        vfs::MemoryFS::new(Path::new("/")).unwrap(/* valid prefix */),
+    )))
+}
+
+// A temporary file, removed once it's dropped.
+struct TempFile {
+    // taken when it's dropped, to be closed before it's removed
+    file: Option<Box<dyn VFile>>,
+    path: PathBuf,
+    cache: Cache,
+}
+
+impl TempFile {
+    fn file(&mut self) -> &mut dyn VFile {
+        self.file.as_deref_mut().unwrap(/* only taken when dropped */)
+    }
+}
+
+impl io::Read for TempFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file().read(buf)
+    }
+}
+
+impl io::Write for TempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file().flush()
+    }
+}
+
+impl io::Seek for TempFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.file().seek(pos)
+    }
+}
+
+impl fmt::Debug for TempFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<TempFile {:?}>", self.path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        drop(self.file.take());
+        let cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        // unless the cache was cleared since
+        if !cache.exists(&self.path) {
+            return;
+        }
+        if let Err(e) = cache.rm(&self.path) {
+            warn!("Could not remove temporary file {:?}: {}", self.path, e);
+        }
+    }
+}
+
+impl Filesystem {
+    fn cache(&self) -> MutexGuard<'_, Box<dyn VFS>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return the full path to the user cache directory, e.g. `~/.cache/<gameid>/` on Linux,
+    /// for data the game makes from its assets and can make again if it's deleted. It's only
+    /// created once something is written to it.
+    ///
+    /// With [`ContextBuilder::sandboxed_user_dirs`](crate::ContextBuilder::sandboxed_user_dirs)
+    /// what's cached is kept in memory instead.
+    pub fn cache_dir(&self) -> &Path {
+        &self.user_cache_dir
+    }
+
+    /// Opens the file at `path`, e.g. `"/shaders/sprite.bin"`, in the cache directory with
+    /// the given [`OpenOptions`], creating the directories it's in if it's created.
+    ///
+    /// As with the other user directories, paths with `..` in them are rejected, so nothing
+    /// outside of the cache directory can be opened.
+    pub fn open_cache<P: AsRef<Path>>(&self, path: P, options: OpenOptions) -> GameResult<File> {
+        let path = path.as_ref();
+        let cache = self.cache();
+        (|| {
+            if options.creates() {
+                if let Some(parent) = path.parent() {
+                    cache.mkdir(parent)?;
+                }
+            }
+            cache.open_options(path, options).map(File::VfsFile)
+        })()
+        .with_context(|| format!("opening {:?} in the cache", path))
+    }
+
+    /// Creates a new, empty file in the cache directory to be read and written for scratch
+    /// work, with a name starting with `prefix`, returning it and its path as
+    /// [`open_cache`](#method.open_cache) opens it. The file is removed when it's dropped.
+    pub fn create_temp_file(&self, prefix: &str) -> GameResult<(File, PathBuf)> {
+        let valid = |c: char| c.is_alphanumeric() || c == '-' || c == '_' || c == '.';
+        if !prefix.chars().all(valid) {
+            return Err(GameError::FilesystemError(format!(
+                "Invalid prefix for a temporary file: {:?}",
+                prefix
+            )));
+        }
+        let cache = self.cache();
+        let file = (|| -> GameResult<TempFile> {
+            cache.mkdir(Path::new(TEMP_DIR))?;
+            // different from those of files other instances of the game left behind
+            let path = loop {
+                let path = Path::new(TEMP_DIR).join(format!(
+                    "{}{}-{}.tmp",
+                    prefix,
+                    std::process::id(),
+                    TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+                ));
+                if !cache.exists(&path) {
+                    break path;
+                }
+            };
+            let options = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true);
+            let file = cache.open_options(&path, options)?;
+            Ok(TempFile {
+                file: Some(file),
+                path,
+                cache: self.cache.clone(),
+            })
+        })()
+        .context("creating a temporary file in the cache")?;
+        let path = file.path.clone();
+        Ok((File::VfsFile(Box::new(file)), path))
+    }
+
+    /// Returns how many bytes the files in the cache directory take, e.g. to show next to a
+    /// button for [`clear_cache`](#method.clear_cache) in the game's settings.
+    pub fn cache_size(&self) -> GameResult<u64> {
+        let cache = self.cache();
+        let root = Path::new("/");
+        if !cache.exists(root) {
+            return Ok(0);
+        }
+        let mut size = 0;
+        let mut dirs = vec![root.to_owned()];
+        while let Some(dir) = dirs.pop() {
+            for entry in cache.read_dir_with_metadata(&dir)? {
+                let (path, metadata) = entry?;
+                if metadata.is_dir() {
+                    dirs.push(path);
+                } else {
+                    size += metadata.len();
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    /// Removes everything in the cache directory. What can't be removed, such as temporary
+    /// files which are still open on Windows, is left, with a warning logged.
+    pub fn clear_cache(&self) -> GameResult {
+        let cache = self.cache();
+        let root = Path::new("/");
+        if !cache.exists(root) {
+            return Ok(());
+        }
+        for entry in cache.read_dir(root)? {
+            let path = entry?;
+            if let Err(e) = cache.rmrf(&path) {
+                warn!("Could not remove {:?} from the cache: {}", path, e);
+            }
+        }
+        Ok(())
+    }
+}
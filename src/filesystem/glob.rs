@@ -0,0 +1,185 @@
+//! Listing directories recursively, and finding files by glob patterns.
+
+use std::path::{Component, Path, PathBuf};
+
+use super::{Filesystem, InternalClone};
+use crate::error::{GameError, GameResult};
+use crate::vfs::VFS;
+
+// Goes through a directory and everything in it depth first, in sorted order, only reading
+// the directories as it gets to them.
+struct Walk {
+    fs: Filesystem,
+    // the entries not gone through yet of each directory it's in
+    stack: Vec<std::vec::IntoIter<PathBuf>>,
+    // how many directories deep it goes
+    max_depth: usize,
+}
+
+impl Walk {
+    fn new(fs: &Filesystem, dir: &Path) -> Self {
+        let mut walk = Walk {
+            fs: InternalClone::clone(fs),
+            stack: Vec::new(),
+            max_depth: usize::MAX,
+        };
+        walk.enter(dir);
+        walk
+    }
+
+    fn enter(&mut self, dir: &Path) {
+        let mut entries: Vec<PathBuf> = match self.fs.vfs().read_dir(dir) {
+            Ok(entries) => entries.filter_map(Result::ok).collect(),
+            Err(_) => Vec::new(),
+        };
+        entries.sort();
+        self.stack.push(entries.into_iter());
+    }
+}
+
+impl Iterator for Walk {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            let entries = self.stack.last_mut()?;
+            match entries.next() {
+                Some(path) => {
+                    if self.stack.len() < self.max_depth && self.fs.is_dir(&path) {
+                        self.enter(&path);
+                    }
+                    return Some(path);
+                }
+                None => {
+                    let _ = self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+// Whether the `name` of a file or directory matches the `pattern` for one, with `*` for any
+// characters and `?` for one.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_name(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_name(rest, &name[1..]),
+    }
+}
+
+// Whether the components of a path match those of a pattern, with `**` for any number of
+// components.
+fn matches_components(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first[..] == ['*', '*'] => {
+            (0..=path.len()).any(|skip| matches_components(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                matches_name(first, name) && matches_components(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn components(path: &Path) -> Option<Vec<Vec<char>>> {
+    let mut components = path.components();
+    if components.next() != Some(Component::RootDir) {
+        return None;
+    }
+    components
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str().map(|name| name.chars().collect()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Filesystem {
+    /// Returns all of the files and directories in the directory at `path`, and in those
+    /// directories, and so on, across all of the mounts as
+    /// [`read_dir`](#method.read_dir) does. They're sorted, with each directory followed by
+    /// what's in it, so assets can be loaded in the same order every time.
+    ///
+    /// The directories are only read as the iterator gets to them.
+    pub fn read_dir_recursive<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> GameResult<Box<dyn Iterator<Item = PathBuf>>> {
+        let path = path.as_ref();
+        if !self.is_dir(path) {
+            return Err(GameError::FilesystemError(format!(
+                "Cannot list {:?}, which isn't a directory",
+                path
+            )));
+        }
+        Ok(Box::new(Walk::new(self, path)))
+    }
+
+    /// Returns the files and directories whose paths match `pattern`, e.g.
+    /// `"/sprites/**/*.png"`, in the order of
+    /// [`read_dir_recursive`](#method.read_dir_recursive). In it `*` stands for any
+    /// characters other than `/`, `?` for one, and `**` for any number of directories.
+    pub fn glob(&self, pattern: &str) -> GameResult<Box<dyn Iterator<Item = PathBuf>>> {
+        let pattern_components = components(Path::new(pattern)).ok_or_else(|| {
+            GameError::FilesystemError(format!(
+                "Invalid glob pattern {:?}: must be an absolute path with no references to \
+                 parent directories",
+                pattern
+            ))
+        })?;
+        // only going through the directory the wildcards start in
+        let literal = pattern_components
+            .iter()
+            .take_while(|c| !c.iter().any(|&c| c == '*' || c == '?'))
+            .count();
+        let mut dir = PathBuf::from("/");
+        for component in &pattern_components[..literal] {
+            dir.push(component.iter().collect::<String>());
+        }
+        if literal == pattern_components.len() {
+            return Ok(Box::new(self.exists(&dir).then_some(dir).into_iter()));
+        }
+        if !self.is_dir(&dir) {
+            return Ok(Box::new(std::iter::empty()));
+        }
+        let mut walk = Walk::new(self, &dir);
+        if !pattern_components.iter().any(|c| c[..] == ['*', '*']) {
+            walk.max_depth = pattern_components.len() - literal;
+        }
+        let matching = walk.filter(move |path| match components(path) {
+            Some(path) => matches_components(&pattern_components, &path),
+            None => false,
+        });
+        Ok(Box::new(matching))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        matches_components(
+            &components(Path::new(pattern)).unwrap(),
+            &components(Path::new(path)).unwrap(),
+        )
+    }
+
+    #[test]
+    fn patterns() {
+        assert!(matches("/*.png", "/player.png"));
+        assert!(!matches("/*.png", "/sprites/player.png"));
+        assert!(matches("/sprites/**/*.png", "/sprites/player.png"));
+        assert!(matches("/sprites/**/*.png", "/sprites/a/b/player.png"));
+        assert!(!matches("/sprites/**/*.png", "/sounds/player.png"));
+        assert!(matches("/level?.txt", "/level1.txt"));
+        assert!(!matches("/level?.txt", "/level10.txt"));
+        assert!(matches("/**", "/a/b"));
+        assert!(matches("/ñ*ú", "/ñandú"));
+    }
+}
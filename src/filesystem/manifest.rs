@@ -0,0 +1,351 @@
+//! Manifests of the game's files, with their sizes and hashes, to check at startup that the
+//! files shipped with it weren't corrupted or tampered with.
+//!
+//! A manifest is generated for the game's resources when it's built, e.g. with
+//! `cargo run --example manifest --features manifest -- resources manifest.toml`, shipped with
+//! it, and checked with [`Manifest::verify`] when it starts:
+//!
+//! ```rust,ignore
+//! use ggez::filesystem::manifest::Manifest;
+//!
+//! let manifest: Manifest = toml::from_str(&manifest_text)?;
+//! let report = manifest.verify(&ctx.fs)?;
+//! if !report.is_ok() {
+//!     println!("Some of the game's files are damaged: {:?}", report.modified);
+//! }
+//! ```
+//!
+//! This can tell when files were damaged or modded, but not stop anyone who can edit the
+//! game's files from editing the manifest too.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::Hasher as _;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use super::{File, Filesystem};
+use crate::error::{GameError, GameResult, ResultExt};
+use crate::vfs::{self, VFS};
+
+// How much of a file is hashed at a time, so large ones aren't read into memory whole.
+const CHUNK: usize = 64 * 1024;
+
+/// How the files in a [`Manifest`] are hashed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    /// The 64 bit XXH3, which is fast, and enough to tell when files were damaged.
+    XxHash3,
+    /// SHA-256, which is slower, but which files can't be made to match on purpose.
+    Sha256,
+}
+
+enum Hasher {
+    XxHash3(Box<twox_hash::XxHash3_64>),
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::XxHash3 => Hasher::XxHash3(Box::default()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::XxHash3(hasher) => hasher.write(bytes),
+            Hasher::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    // The hash, in hexadecimal.
+    fn finish(self) -> String {
+        match self {
+            Hasher::XxHash3(hasher) => format!("{:016x}", hasher.finish()),
+            Hasher::Sha256(hasher) => hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        }
+    }
+}
+
+/// A file in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Its size in bytes.
+    pub len: u64,
+    /// Its hash, in hexadecimal.
+    pub hash: String,
+}
+
+/// How far [`Manifest::verify_with_progress`] got, e.g. for a progress bar.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VerifyProgress {
+    /// How many of the files in the manifest were checked.
+    pub files_done: usize,
+    /// How many files there are in the manifest.
+    pub files_total: usize,
+    /// How many bytes of them were hashed, or skipped as they were missing.
+    pub bytes_done: u64,
+    /// How many bytes all of them take, as the manifest says.
+    pub bytes_total: u64,
+}
+
+impl VerifyProgress {
+    /// How far it got, from 0.0 to 1.0, by the bytes checked.
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total == 0 {
+            return if self.files_done == self.files_total {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        self.bytes_done as f32 / self.bytes_total as f32
+    }
+}
+
+/// What [`Manifest::verify`] found, with paths as they'd be opened.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// The files in the manifest which aren't in the filesystem.
+    pub missing: Vec<String>,
+    /// The files whose size or contents aren't as the manifest says.
+    pub modified: Vec<String>,
+    /// The files in the directory the manifest was generated for which aren't in it.
+    pub extra: Vec<String>,
+}
+
+impl VerifyReport {
+    /// Returns whether all of the files are as the manifest says, with none missing and none
+    /// extra.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// The sizes and hashes of all of the files in a directory, made with
+/// [`generate`](#method.generate), and written and read with `serde`, e.g. as TOML.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// How the files are hashed.
+    pub algorithm: HashAlgorithm,
+    /// The directory it was generated for, e.g. `"/"`.
+    pub dir: String,
+    /// The files in it, and in the directories in it, by their paths as they'd be opened.
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Hashes all of the files in the directory at `dir`, e.g. `"/"` or `"/levels"`, and the
+    /// directories in it, across all of the mounts of `fs`, as they'd be opened.
+    pub fn generate(fs: &Filesystem, dir: &str, algorithm: HashAlgorithm) -> GameResult<Self> {
+        let dir = vfs_dir(dir)?;
+        let files = fs
+            .read_dir_recursive(&dir)?
+            .filter(|path| fs.is_file(path))
+            .collect();
+        Manifest::generate_from(dir, files, |path| fs.open(path), algorithm)
+    }
+
+    /// Hashes all of the files in the directory `dir` on disk, e.g. the game's `resources/`
+    /// directory before it's shipped, with paths as they'd be opened once it's mounted, so
+    /// without a [`Context`](crate::Context).
+    pub fn generate_dir(dir: &Path, algorithm: HashAlgorithm) -> GameResult<Self> {
+        if !dir.is_dir() {
+            return Err(GameError::FilesystemError(format!(
+                "Cannot generate a manifest for {:?}, which isn't a directory",
+                dir
+            )));
+        }
+        let physfs = vfs::PhysicalFS::new(dir, true);
+        let mut files = Vec::new();
+        list_files(&physfs, Path::new("/"), &mut files)?;
+        let open = |path: &Path| physfs.open(path).map(File::VfsFile);
+        Manifest::generate_from(PathBuf::from("/"), files, open, algorithm)
+    }
+
+    fn generate_from(
+        dir: PathBuf,
+        files: Vec<PathBuf>,
+        open: impl Fn(&Path) -> GameResult<File>,
+        algorithm: HashAlgorithm,
+    ) -> GameResult<Self> {
+        let mut manifest = Manifest {
+            algorithm,
+            dir: path_string(&dir)?,
+            files: BTreeMap::new(),
+        };
+        for path in files {
+            let (len, hash) = open(&path)
+                .and_then(|file| hash(file, algorithm, |_| true))
+                .with_context(|| format!("hashing {:?}", path))?;
+            let hash = hash.unwrap(/* never cancelled */);
+            let _ = manifest
+                .files
+                .insert(path_string(&path)?, ManifestEntry { len, hash });
+        }
+        Ok(manifest)
+    }
+
+    /// Checks the files in `fs` against the manifest, as they'd be opened, reading them a
+    /// chunk at a time.
+    ///
+    /// Files which can't be read are reported as modified, with a warning logged.
+    pub fn verify(&self, fs: &Filesystem) -> GameResult<VerifyReport> {
+        self.verify_with_progress(fs, |_| true)
+    }
+
+    /// Checks the files in `fs` as [`verify`](#method.verify) does, calling `progress` as it
+    /// goes, which can return `false` to stop, in which case it returns an error.
+    pub fn verify_with_progress(
+        &self,
+        fs: &Filesystem,
+        mut progress: impl FnMut(&VerifyProgress) -> bool,
+    ) -> GameResult<VerifyReport> {
+        let cancelled =
+            || GameError::FilesystemError(String::from("Verifying the files was cancelled"));
+        let mut report = VerifyReport::default();
+        let mut done = VerifyProgress {
+            files_done: 0,
+            files_total: self.files.len(),
+            bytes_done: 0,
+            bytes_total: self.files.values().map(|entry| entry.len).sum(),
+        };
+        if !progress(&done) {
+            return Err(cancelled());
+        }
+        for (path, entry) in &self.files {
+            let before = done.bytes_done;
+            if !fs.is_file(path) {
+                report.missing.push(path.clone());
+            } else {
+                let hashed = fs.open(path).and_then(|file| {
+                    hash(file, self.algorithm, |read| {
+                        // up to its size in the manifest, even if it's larger
+                        done.bytes_done = (before + read).min(before + entry.len);
+                        progress(&done)
+                    })
+                });
+                match hashed {
+                    Ok((_, None)) => return Err(cancelled()),
+                    Ok((len, Some(hash))) => {
+                        if len != entry.len || hash != entry.hash {
+                            report.modified.push(path.clone());
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Could not verify {:?}: {}", path, e);
+                        report.modified.push(path.clone());
+                    }
+                }
+            }
+            done.files_done += 1;
+            done.bytes_done = before + entry.len;
+            if !progress(&done) {
+                return Err(cancelled());
+            }
+        }
+        let dir = vfs_dir(&self.dir)?;
+        if fs.is_dir(&dir) {
+            let listed: BTreeSet<&str> = self.files.keys().map(String::as_str).collect();
+            for path in fs.read_dir_recursive(&dir)? {
+                if fs.is_file(&path) {
+                    let path = path_string(&path)?;
+                    if !listed.contains(path.as_str()) {
+                        report.extra.push(path);
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+// Hashes what's read from `file` a chunk at a time, calling `read` with how much was after
+// each, which can return `false` to stop, in which case the hash is `None`.
+fn hash(
+    mut file: File,
+    algorithm: HashAlgorithm,
+    mut read: impl FnMut(u64) -> bool,
+) -> GameResult<(u64, Option<String>)> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buf = vec![0; CHUNK];
+    let mut len = 0;
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buf[..n]);
+        len += n as u64;
+        if !read(len) {
+            return Ok((len, None));
+        }
+    }
+    Ok((len, Some(hasher.finish())))
+}
+
+// Lists the files in the directory at `dir`, and in the directories in it, sorted.
+fn list_files(vfs: &dyn VFS, dir: &Path, files: &mut Vec<PathBuf>) -> GameResult {
+    let mut entries = vfs.read_dir(dir)?.collect::<GameResult<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if vfs.metadata(&entry)?.is_dir() {
+            list_files(vfs, &entry, files)?;
+        } else {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn vfs_dir(dir: &str) -> GameResult<PathBuf> {
+    match vfs::sanitize_path(Path::new(dir)) {
+        Some(relative) => Ok(Path::new("/").join(relative)),
+        None => Err(GameError::FilesystemError(format!(
+            "Invalid directory for a manifest: {:?}",
+            dir
+        ))),
+    }
+}
+
+// The path as it's written in manifests, with `/` on every platform.
+fn path_string(path: &Path) -> GameResult<String> {
+    let mut string = String::new();
+    for component in path.components() {
+        match component {
+            Component::RootDir => (),
+            Component::Normal(name) => match name.to_str() {
+                Some(name) => {
+                    string.push('/');
+                    string.push_str(name);
+                }
+                None => {
+                    return Err(GameError::FilesystemError(format!(
+                        "Cannot put {:?} in a manifest, its name isn't valid UTF-8",
+                        path
+                    )))
+                }
+            },
+            _ => {
+                return Err(GameError::FilesystemError(format!(
+                    "Invalid path for a manifest: {:?}",
+                    path
+                )))
+            }
+        }
+    }
+    if string.is_empty() {
+        string.push('/');
+    }
+    Ok(string)
+}
@@ -0,0 +1,2175 @@
+//! A cross-platform interface to the filesystem.
+//!
+//! This module provides access to files in specific places:
+//!
+//! * The `resources/` subdirectory in the same directory as the
+//! program executable, if any,
+//! * The `resources.zip` file in the same
+//! directory as the program executable, if any,
+//! * The root folder of the  game's "save" directory which is in a
+//! platform-dependent location,
+//! such as `~/.local/share/<gameid>/` on Linux.  The `gameid`
+//! is the the string passed to
+//! [`ContextBuilder::new()`](../struct.ContextBuilder.html#method.new).
+//! Some platforms such as Windows also incorporate the `author` string into
+//! the path.
+//!
+//! These locations will be searched for files in the order listed, and the first file
+//! found used.  That allows game assets to be easily distributed as an archive
+//! file, but locally overridden for testing or modding simply by putting
+//! altered copies of them in the game's `resources/` directory.  It
+//! is loosely based off of the `PhysicsFS` library.
+//!
+//! Paths are absolute, starting from the root of all of these, e.g. `/sprites/hero.png`, and
+//! are read the same way for each of them: `\` separates the names in them as `/` does, so
+//! ones written on Windows work everywhere, `.` and empty names are skipped, and paths with
+//! `..` in them are rejected rather than resolved, so nothing outside of the mounts can be
+//! reached. Whether case matters depends on the platform for directories, and it always does
+//! in zip archives, unless
+//! [`Filesystem::set_case_insensitive`](struct.Filesystem.html#method.set_case_insensitive)
+//! is on.
+//!
+//! The game's own settings and saves can be read and written in the user's directories with
+//! [`Filesystem::read_user_file`](struct.Filesystem.html#method.read_user_file) and
+//! [`Filesystem::write_user_file`](struct.Filesystem.html#method.write_user_file), and
+//! what it makes from its assets and can make again cached with
+//! [`Filesystem::open_cache`](struct.Filesystem.html#method.open_cache).
+//!
+//! More directories and zip archives can be mounted with
+//! [`Filesystem::mount`](struct.Filesystem.html#method.mount),
+//! [`Filesystem::mount_zip`](struct.Filesystem.html#method.mount_zip) and, to put them ahead of
+//! the game's own files as mods are,
+//! [`Filesystem::mount_with_priority`](struct.Filesystem.html#method.mount_with_priority).
+//!
+//! See the source of the [`files` example](https://github.com/ggez/ggez/blob/master/examples/files.rs) for more details.
+//!
+//! The names of `resources/` and `resources.zip` can be changed with the methods
+//! [`resources_dir_name`](../struct.ContextBuilder.html#method.resources_dir_name)
+//! and
+//! [`resources_zip_name`](../struct.ContextBuilder.html#method.resources_zip_name)
+//!  of ContextBuilder.
+//!
+//! Note that the file lookups WILL follow symlinks!  This module's
+//! directory isolation is intended for convenience, not security, so
+//! don't assume it will be secure.
+
+use crate::{
+    conf,
+    vfs::{self, OverlayFS, VFS},
+    Context, GameError, GameResult, ResultExt,
+};
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use directories::ProjectDirs;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use std::env;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fmt, io,
+    io::SeekFrom,
+    ops::DerefMut,
+    path,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+pub use crate::vfs::OpenOptions;
+
+pub use self::save_slots::{SaveSlotInfo, SaveSlots};
+pub use self::user_files::{Migrate, UserDir};
+mod cache;
+mod glob;
+#[cfg(feature = "manifest")]
+pub mod manifest;
+mod save_slots;
+mod user_files;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+mod watch;
+#[cfg(target_arch = "wasm32")]
+mod web;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+pub use self::watch::WatchHandle;
+#[cfg(target_arch = "wasm32")]
+pub use self::web::fetch;
+#[cfg(all(feature = "hot-reload", target_arch = "wasm32"))]
+pub use self::web::WatchHandle;
+
+const CONFIG_NAME: &str = "/conf.toml";
+
+/// A structure that contains the filesystem state and cache.
+#[derive(Debug)]
+pub struct Filesystem {
+    vfs: Arc<Mutex<vfs::OverlayFS>>,
+    resources_dir: path::PathBuf,
+    zip_dir: path::PathBuf,
+    user_config_dir: path::PathBuf,
+    user_data_dir: path::PathBuf,
+    user_cache_dir: path::PathBuf,
+    cache: cache::Cache,
+    // whether the user directories were replaced with memory
+    sandboxed: bool,
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    watcher: Arc<watch::FileWatcher>,
+}
+
+/// This is the same as [`std::clone::Clone`] but only accessible to ggez
+/// via a non-public implementation.
+///
+/// We don't need to expose the fact that [`Filesystem`] can be cloned to user,
+/// in case we want to change this in the future.
+pub(crate) trait InternalClone {
+    fn clone(&self) -> Self;
+}
+
+impl InternalClone for Filesystem {
+    fn clone(&self) -> Self {
+        Filesystem {
+            vfs: self.vfs.clone(),
+            resources_dir: self.resources_dir.clone(),
+            zip_dir: self.zip_dir.clone(),
+            user_config_dir: self.user_config_dir.clone(),
+            user_data_dir: self.user_data_dir.clone(),
+            user_cache_dir: self.user_cache_dir.clone(),
+            cache: self.cache.clone(),
+            sandboxed: self.sandboxed,
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            watcher: self.watcher.clone(),
+        }
+    }
+}
+
+/// A zip archive to mount with [`Filesystem::mount_zip`], either a file on disk or bytes in
+/// memory, such as ones embedded in the executable with `include_bytes!`.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ZipSource {
+    /// The (absolute) path of an archive on disk.
+    Path(path::PathBuf),
+    /// The bytes of an archive.
+    Bytes(Cow<'static, [u8]>),
+}
+
+impl fmt::Debug for ZipSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ZipSource::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            // rather than what are likely to be megabytes of them
+            ZipSource::Bytes(bytes) => write!(f, "Bytes(<{} bytes>)", bytes.len()),
+        }
+    }
+}
+
+impl From<&path::Path> for ZipSource {
+    fn from(path: &path::Path) -> Self {
+        ZipSource::Path(path.to_owned())
+    }
+}
+
+impl From<path::PathBuf> for ZipSource {
+    fn from(path: path::PathBuf) -> Self {
+        ZipSource::Path(path)
+    }
+}
+
+impl From<&str> for ZipSource {
+    fn from(path: &str) -> Self {
+        ZipSource::Path(path.into())
+    }
+}
+
+impl From<Vec<u8>> for ZipSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        ZipSource::Bytes(bytes.into())
+    }
+}
+
+impl From<&'static [u8]> for ZipSource {
+    fn from(bytes: &'static [u8]) -> Self {
+        ZipSource::Bytes(bytes.into())
+    }
+}
+
+impl<const N: usize> From<&'static [u8; N]> for ZipSource {
+    fn from(bytes: &'static [u8; N]) -> Self {
+        ZipSource::Bytes(bytes[..].into())
+    }
+}
+
+impl From<Cow<'static, [u8]>> for ZipSource {
+    fn from(bytes: Cow<'static, [u8]>) -> Self {
+        ZipSource::Bytes(bytes)
+    }
+}
+
+/// One of the places the filesystem looks for files in, as listed by
+/// [`Filesystem::list_mounts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountInfo {
+    /// Where it is on disk, or `None` for a zip archive mounted from bytes, or for files
+    /// embedded in the executable or kept in memory.
+    pub path: Option<path::PathBuf>,
+    /// Mounts with a higher priority are searched first. The ones the filesystem starts with,
+    /// and the ones added with `.mount()` and `.mount_zip()`, have priority 0.
+    pub priority: i32,
+    /// Whether it's a zip archive rather than a directory.
+    pub archive: bool,
+    /// Whether nothing can be written to it.
+    pub readonly: bool,
+}
+
+impl MountInfo {
+    fn new(priority: i32, vfs: &dyn VFS) -> Self {
+        MountInfo {
+            path: vfs.to_path_buf(),
+            priority,
+            archive: vfs.is_archive(),
+            readonly: vfs.is_readonly(),
+        }
+    }
+}
+
+/// What [`Filesystem::metadata`] tells about a file or directory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// Whether it's a directory rather than a file.
+    pub is_dir: bool,
+    /// The size of the file in bytes. What it is for a directory depends on the platform.
+    pub len: u64,
+    /// When it was last modified, if that's known. Files in zip archives only have the date
+    /// and time without a time zone, which is taken to be UTC, and those without one, or
+    /// embedded with `include_dir`, have `None`.
+    pub modified: Option<SystemTime>,
+    /// When it was created, where the platform keeps track, and for files in memory.
+    pub created: Option<SystemTime>,
+}
+
+impl FileMetadata {
+    fn new(metadata: &dyn vfs::VMetadata) -> Self {
+        FileMetadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified(),
+            created: metadata.created(),
+        }
+    }
+
+    /// Whether it's a file rather than a directory.
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+}
+
+/// Represents a file, either in the filesystem, or in the resources zip file,
+/// or whatever.
+#[derive(Debug)]
+pub enum File {
+    /// A wrapper for a VFile trait object.
+    VfsFile(Box<dyn vfs::VFile>),
+}
+
+impl io::Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            File::VfsFile(ref mut f) => f.read(buf),
+        }
+    }
+}
+
+impl io::Write for File {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            File::VfsFile(ref mut f) => f.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            File::VfsFile(ref mut f) => f.flush(),
+        }
+    }
+}
+
+impl io::Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match *self {
+            File::VfsFile(ref mut f) => f.seek(pos),
+        }
+    }
+}
+
+/// Part of a file, opened with [`Filesystem::open_range`], which reads and seeks as if it
+/// were all there was, e.g. for one of the sounds packed into a bank.
+#[derive(Debug)]
+pub struct FileRegion(vfs::Region<File>);
+
+impl io::Read for FileRegion {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl io::Seek for FileRegion {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+/// A file, or files, being read on the threads loading assets, by
+/// [`Filesystem::read_async`] and the like. Dropping it before then stops them being read.
+pub struct FsReadHandle<T = Vec<u8>> {
+    shared: Arc<ReadShared<T>>,
+}
+
+struct ReadShared<T> {
+    result: Mutex<Option<GameResult<T>>>,
+    finished: AtomicBool,
+    read: Condvar,
+    cancelled: AtomicBool,
+}
+
+impl<T: Send + 'static> FsReadHandle<T> {
+    fn new() -> Self {
+        FsReadHandle {
+            shared: Arc::new(ReadShared {
+                result: Mutex::new(None),
+                finished: AtomicBool::new(false),
+                read: Condvar::new(),
+                cancelled: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Returns whether the reading finished, or failed.
+    pub fn is_done(&self) -> bool {
+        self.shared.finished.load(Ordering::Acquire)
+    }
+
+    /// Returns what was read once it's done, or the error, after which it returns `None`
+    /// again. Returns `None` while it's being read.
+    pub fn poll(&mut self) -> Option<GameResult<T>> {
+        self.shared
+            .result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Waits for the reading to finish, returning what was read or the error, as
+    /// [`poll()`](#method.poll) does.
+    pub fn wait(self) -> GameResult<T> {
+        let mut result = self
+            .shared
+            .result
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while !self.is_done() {
+            result = self
+                .shared
+                .read
+                .wait(result)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        result.take().unwrap_or_else(|| {
+            Err(GameError::FilesystemError(String::from(
+                "The result of the read was taken already",
+            )))
+        })
+    }
+
+    fn finish(shared: &ReadShared<T>, result: GameResult<T>) {
+        let mut lock = shared.result.lock().unwrap_or_else(PoisonError::into_inner);
+        *lock = Some(result);
+        // set while locked, so a wait can't miss it
+        shared.finished.store(true, Ordering::Release);
+        drop(lock);
+        shared.read.notify_all();
+    }
+}
+
+impl<T> Drop for FsReadHandle<T> {
+    fn drop(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> fmt::Debug for FsReadHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FsReadHandle")
+            .field("done", &self.shared.finished.load(Ordering::Acquire))
+            .finish()
+    }
+}
+
+impl Filesystem {
+    /// Create a new `Filesystem` instance, using the given `id` and (on
+    /// some platforms) the `author` as a portion of the user
+    /// directory path.  This function is called automatically by
+    /// ggez, the end user should never need to call it.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    pub fn new(
+        id: &str,
+        author: &str,
+        resources_dir_name: &str,
+        resources_zip_name: &str,
+    ) -> GameResult<Filesystem> {
+        let mut root_path = env::current_exe()?;
+
+        // Ditch the filename (if any)
+        if root_path.file_name().is_some() {
+            let _ = root_path.pop();
+        }
+
+        // Set up VFS to merge resource path, root path, and zip path.
+        let mut overlay = vfs::OverlayFS::new();
+
+        let mut resources_path;
+        let mut resources_zip_path;
+        let user_data_path;
+        let user_config_path;
+        let user_cache_path;
+
+        let project_dirs = match ProjectDirs::from("", author, id) {
+            Some(dirs) => dirs,
+            None => {
+                return Err(GameError::FilesystemError(String::from(
+                    "No valid home directory path could be retrieved.",
+                )));
+            }
+        };
+
+        // <game exe root>/resources/
+        {
+            resources_path = root_path.clone();
+            resources_path.push(resources_dir_name);
+            trace!("Resources path: {:?}", resources_path);
+            let physfs = vfs::PhysicalFS::new(&resources_path, true);
+            overlay.push_back(Box::new(physfs));
+        }
+
+        // <root>/resources.zip
+        {
+            resources_zip_path = root_path;
+            resources_zip_path.push(resources_zip_name);
+            if resources_zip_path.exists() {
+                trace!("Resources zip file: {:?}", resources_zip_path);
+                let zipfs = vfs::ZipFS::new(&resources_zip_path)?;
+                overlay.push_back(Box::new(zipfs));
+            } else {
+                trace!("No resources zip file found");
+            }
+        }
+
+        // Per-user data dir,
+        // ~/.local/share/whatever/
+        {
+            user_data_path = project_dirs.data_local_dir();
+            trace!("User-local data path: {:?}", user_data_path);
+            let physfs = vfs::PhysicalFS::new(user_data_path, true);
+            overlay.push_back(Box::new(physfs));
+        }
+
+        // Writeable local dir, ~/.config/whatever/
+        // Save game dir is read-write
+        {
+            user_config_path = project_dirs.config_dir();
+            trace!("User-local configuration path: {:?}", user_config_path);
+            let physfs = vfs::PhysicalFS::new(user_config_path, false);
+            overlay.push_back(Box::new(physfs));
+        }
+
+        // Cache dir, ~/.cache/whatever/, which isn't mounted as it's only for what the
+        // game makes itself
+        {
+            user_cache_path = project_dirs.cache_dir();
+            trace!("User-local cache path: {:?}", user_cache_path);
+        }
+
+        let fs = Filesystem {
+            vfs: Arc::new(Mutex::new(overlay)),
+            resources_dir: resources_path,
+            zip_dir: resources_zip_path,
+            user_config_dir: user_config_path.to_path_buf(),
+            user_data_dir: user_data_path.to_path_buf(),
+            user_cache_dir: user_cache_path.to_path_buf(),
+            cache: cache::physical_cache(user_cache_path),
+            sandboxed: false,
+            #[cfg(feature = "hot-reload")]
+            watcher: Arc::new(watch::FileWatcher::new()),
+        };
+
+        Ok(fs)
+    }
+
+    /// Create a new `Filesystem` instance.  This function is called
+    /// automatically by ggez, the end user should never need to call it.
+    ///
+    /// On Android the resources are the assets packed in the APK, where a `resources.zip` is
+    /// mounted too, and the user directories are in the app's files directory, which is only
+    /// its own.
+    #[cfg(target_os = "android")]
+    pub fn new(
+        _id: &str,
+        _author: &str,
+        resources_dir_name: &str,
+        resources_zip_name: &str,
+    ) -> GameResult<Filesystem> {
+        let activity = ndk_glue::native_activity();
+        let mut overlay = vfs::OverlayFS::new();
+
+        // the APK's assets, which the resources directory is packed as
+        let assets = vfs::AssetFS::new(activity.asset_manager());
+        let resources_zip_path = path::PathBuf::from(resources_zip_name);
+        if assets.exists(&resources_zip_path) {
+            trace!("Resources zip file: {:?} in the APK", resources_zip_path);
+            let zip = assets.open(&resources_zip_path)?;
+            overlay.push_back(Box::new(vfs::ZipFS::from_read(zip)?));
+        }
+        trace!("Resources: the APK's assets");
+        overlay.push_front(Box::new(assets));
+
+        // /data/data/<package>/files/, and the cache next to it, which Android clears when
+        // it's short on space
+        let files_path = activity.internal_data_path().to_owned();
+        let user_data_path = files_path.join("data");
+        let user_config_path = files_path.join("config");
+        let user_cache_path = files_path.with_file_name("cache");
+        trace!("User-local data path: {:?}", user_data_path);
+        overlay.push_back(Box::new(vfs::PhysicalFS::new(&user_data_path, true)));
+        trace!("User-local configuration path: {:?}", user_config_path);
+        overlay.push_back(Box::new(vfs::PhysicalFS::new(&user_config_path, false)));
+        trace!("User-local cache path: {:?}", user_cache_path);
+
+        Ok(Filesystem {
+            vfs: Arc::new(Mutex::new(overlay)),
+            resources_dir: path::PathBuf::from(resources_dir_name),
+            zip_dir: resources_zip_path,
+            user_config_dir: user_config_path,
+            user_data_dir: user_data_path,
+            cache: cache::physical_cache(&user_cache_path),
+            user_cache_dir: user_cache_path,
+            sandboxed: false,
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            watcher: Arc::new(watch::FileWatcher::new()),
+        })
+    }
+
+    /// Create a new `Filesystem` instance.  This function is called
+    /// automatically by ggez, the end user should never need to call it.
+    ///
+    /// On the web there's neither a directory next to the executable nor a home directory, so
+    /// the resources are mounted by the game, e.g. with [`mount_zip`](#method.mount_zip) from
+    /// bytes compiled in or [`fetch`]ed from the server, and the user directories are kept in
+    /// memory as with
+    /// [`ContextBuilder::sandboxed_user_dirs`](crate::ContextBuilder::sandboxed_user_dirs).
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(
+        _id: &str,
+        _author: &str,
+        resources_dir_name: &str,
+        resources_zip_name: &str,
+    ) -> GameResult<Filesystem> {
+        let mut overlay = vfs::OverlayFS::new();
+        overlay.push_back(Box::new(
+            vfs::MemoryFS::new(path::Path::new("/")).unwrap(/* valid prefix */),
+        ));
+        Ok(Filesystem {
+            vfs: Arc::new(Mutex::new(overlay)),
+            resources_dir: path::PathBuf::from(resources_dir_name),
+            zip_dir: path::PathBuf::from(resources_zip_name),
+            user_config_dir: path::PathBuf::new(),
+            user_data_dir: path::PathBuf::new(),
+            user_cache_dir: path::PathBuf::new(),
+            cache: cache::memory_cache(),
+            sandboxed: true,
+        })
+    }
+
+    fn vfs(&self) -> impl DerefMut<Target = OverlayFS> + '_ {
+        self.vfs.lock().unwrap()
+    }
+
+    /// Opens the given `path` and returns the resulting `File`
+    /// in read-only mode.
+    ///
+    /// Files are read as they're needed rather than all at once, so large ones such as music
+    /// can be streamed. Seeking in them is immediate for files in directories, in memory,
+    /// compiled in, or stored uncompressed in zip archives mounted with
+    /// [`mount_zip`](#method.mount_zip). In deflated files of those, seeking forward
+    /// decompresses what's skipped and seeking back starts over from their start, so
+    /// archives of assets which are streamed should store them uncompressed. Files in archives
+    /// added with [`add_zip_file`](#method.add_zip_file) are read into memory when opened.
+    pub fn open<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
+        self.vfs().open(path.as_ref()).map(|f| File::VfsFile(f))
+    }
+
+    /// Opens the `len` bytes from `offset` on of the file at `path`, as
+    /// [`open`](#method.open) does, to read them as if they were the whole file. Fails if the
+    /// file is shorter than that.
+    pub fn open_range<P: AsRef<path::Path>>(
+        &self,
+        path: P,
+        offset: u64,
+        len: u64,
+    ) -> GameResult<FileRegion> {
+        let path = path.as_ref();
+        let mut file = self.open(path)?;
+        let file_len = io::Seek::seek(&mut file, SeekFrom::End(0))?;
+        match offset.checked_add(len) {
+            Some(end) if end <= file_len => Ok(FileRegion(vfs::Region::new(file, offset, len)?)),
+            _ => Err(GameError::FilesystemError(format!(
+                "Cannot open {} bytes from {} of {:?}, which is {} bytes long",
+                len, offset, path, file_len
+            ))),
+        }
+    }
+
+    /// Opens a file in the user directory with the given
+    /// [`filesystem::OpenOptions`](struct.OpenOptions.html).
+    /// Note that even if you open a file read-write, it can only
+    /// write to files in the "user" directory.
+    ///
+    /// Appending, creating files only if they don't exist yet, truncating them, and reading
+    /// and writing them at once work as they do with `std::fs::OpenOptions`, in directories
+    /// and in memory. Several files open to append to the same file each add what they write
+    /// to the end of it. When there's nowhere writeable to open the file in, as the mounts
+    /// which have it are zip archives, embedded or read-only, this returns a
+    /// `GameError::ReadOnlyError`, rather than the error writing failed with otherwise.
+    pub fn open_options<P: AsRef<path::Path>>(
+        &self,
+        path: P,
+        options: OpenOptions,
+    ) -> GameResult<File> {
+        self.vfs()
+            .open_options(path.as_ref(), options)
+            .map(|f| File::VfsFile(f))
+            .with_context(|| format!("opening {:?}", path.as_ref()))
+    }
+
+    /// Creates a new file in the user directory and opens it
+    /// to be written to, truncating it if it already exists.
+    pub fn create<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
+        self.vfs()
+            .create(path.as_ref())
+            .map(|f| File::VfsFile(f))
+            .with_context(|| format!("creating {:?}", path.as_ref()))
+    }
+
+    /// Opens a file in the user directory to append to, e.g. a log, creating it if it
+    /// doesn't exist yet.
+    pub fn append<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
+        self.vfs()
+            .append(path.as_ref())
+            .map(File::VfsFile)
+            .with_context(|| format!("appending to {:?}", path.as_ref()))
+    }
+
+    /// Writes `bytes` to the file at `path` in the user directory, creating the directories
+    /// it's in, so that it's left with either its old contents or the new ones if the game is
+    /// closed or the power goes out partway, e.g. for saved games. They're written to another
+    /// file in the same directory, flushed to the disk, and renamed over it.
+    ///
+    /// See [`SaveSlots`] for keeping backups of saves too.
+    pub fn write_atomic<P: AsRef<path::Path>>(&self, path: P, bytes: &[u8]) -> GameResult {
+        self.vfs()
+            .write_atomic(path.as_ref(), bytes)
+            .with_context(|| format!("writing {:?}", path.as_ref()))
+    }
+
+    /// Create an empty directory in the user dir
+    /// with the given name.  Any parents to that directory
+    /// that do not exist will be created.
+    pub fn create_dir<P: AsRef<path::Path>>(&self, path: P) -> GameResult {
+        self.vfs()
+            .mkdir(path.as_ref())
+            .with_context(|| format!("creating the directory {:?}", path.as_ref()))
+    }
+
+    /// Deletes the specified file in the user dir.
+    pub fn delete<P: AsRef<path::Path>>(&self, path: P) -> GameResult {
+        self.vfs()
+            .rm(path.as_ref())
+            .with_context(|| format!("deleting {:?}", path.as_ref()))
+    }
+
+    /// Deletes the specified directory in the user dir,
+    /// and all its contents!
+    pub fn delete_dir<P: AsRef<path::Path>>(&self, path: P) -> GameResult {
+        self.vfs()
+            .rmrf(path.as_ref())
+            .with_context(|| format!("deleting the directory {:?}", path.as_ref()))
+    }
+
+    /// Check whether a file or directory exists.
+    pub fn exists<P: AsRef<path::Path>>(&self, path: P) -> bool {
+        self.vfs().exists(path.as_ref())
+    }
+
+    /// Check whether a path points at a file.
+    pub fn is_file<P: AsRef<path::Path>>(&self, path: P) -> bool {
+        self.vfs()
+            .metadata(path.as_ref())
+            .map(|m| m.is_file())
+            .unwrap_or(false)
+    }
+
+    /// Check whether a path points at a directory.
+    pub fn is_dir<P: AsRef<path::Path>>(&self, path: P) -> bool {
+        self.vfs()
+            .metadata(path.as_ref())
+            .map(|m| m.is_dir())
+            .unwrap_or(false)
+    }
+
+    /// Returns the size of the file or directory at `path`, when it was modified and created,
+    /// and whether it's a directory, e.g. to tell whether an asset changed since it was
+    /// imported, or to show when a save was last played. It's that of the first mount with
+    /// something there, as [`resolve`](#method.resolve) returns.
+    pub fn metadata<P: AsRef<path::Path>>(&self, path: P) -> GameResult<FileMetadata> {
+        self.vfs()
+            .metadata(path.as_ref())
+            .map(|metadata| FileMetadata::new(&*metadata))
+    }
+
+    /// Returns the files and directories in the directory at `path` as
+    /// [`read_dir`](#method.read_dir) does, with their metadata, which on some platforms
+    /// comes along with them rather than taking another call for each.
+    pub fn read_dir_with_metadata<P: AsRef<path::Path>>(
+        &self,
+        path: P,
+    ) -> GameResult<Box<dyn Iterator<Item = (path::PathBuf, FileMetadata)>>> {
+        let entries = self
+            .vfs()
+            .read_dir_with_metadata(path.as_ref())
+            .with_context(|| format!("listing the directory {:?}", path.as_ref()))?
+            .filter_map(|entry| match entry {
+                Ok((path, metadata)) => Some((path, FileMetadata::new(&*metadata))),
+                Err(e) => {
+                    warn!("Could not read the metadata of a file: {}", e);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    /// Returns a list of all files and directories in the resource directory,
+    /// in no particular order, with each path listed once even if several
+    /// mounts have something there.
+    ///
+    /// Lists the base directory if an empty path is given.
+    pub fn read_dir<P: AsRef<path::Path>>(
+        &self,
+        path: P,
+    ) -> GameResult<Box<dyn Iterator<Item = path::PathBuf>>> {
+        let itr = self
+            .vfs()
+            .read_dir(path.as_ref())
+            .with_context(|| format!("listing the directory {:?}", path.as_ref()))?
+            .map(|fname| {
+                fname.expect("Could not read file in read_dir()?  Should never happen, I hope!")
+            });
+        Ok(Box::new(itr))
+    }
+
+    // Reads the whole file at `path`, without adding it to the errors.
+    pub(crate) fn read_file(&self, path: &path::Path) -> GameResult<Vec<u8>> {
+        let mut file = self.open(path)?;
+        let mut bytes = Vec::new();
+        let _ = io::Read::read_to_end(&mut file, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Starts reading the whole file at `path` on the threads loading assets, which are also
+    /// the ones [`loading`](crate::loading) uses, returning a handle to get the contents from
+    /// once they're read, e.g. to stream in the chunks of a level without stalling a frame.
+    ///
+    /// There are only a few of those threads, so reading many files at once queues them up
+    /// rather than reading them all at the same time. Files in zip archives are read one at
+    /// a time.
+    pub fn read_async<P: AsRef<path::Path>>(&self, path: P) -> FsReadHandle {
+        self.spawn_read(path.as_ref(), Ok)
+    }
+
+    /// Starts reading the whole file at `path` as UTF-8 text, as
+    /// [`read_async`](#method.read_async) does.
+    pub fn read_to_string_async<P: AsRef<path::Path>>(&self, path: P) -> FsReadHandle<String> {
+        self.spawn_read(path.as_ref(), |bytes| {
+            String::from_utf8(bytes)
+                .map_err(|e| GameError::FilesystemError(format!("Invalid UTF-8: {}", e)))
+        })
+    }
+
+    /// Starts reading the whole files at `paths`, as [`read_async`](#method.read_async)
+    /// does, with their contents in the same order once all of them are read, or the error of
+    /// the first of them which failed.
+    pub fn read_all_async<P: AsRef<path::Path>>(
+        &self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> FsReadHandle<Vec<Vec<u8>>> {
+        let paths: Vec<path::PathBuf> = paths.into_iter().map(|p| p.as_ref().into()).collect();
+        let handle = FsReadHandle::new();
+        if paths.is_empty() {
+            FsReadHandle::finish(&handle.shared, Ok(Vec::new()));
+            return handle;
+        }
+        let contents = Arc::new(Mutex::new(
+            (0..paths.len()).map(|_| None).collect::<Vec<_>>(),
+        ));
+        let remaining = Arc::new(AtomicUsize::new(paths.len()));
+        for (i, path) in paths.into_iter().enumerate() {
+            let fs = InternalClone::clone(self);
+            let shared = handle.shared.clone();
+            let contents = contents.clone();
+            let remaining = remaining.clone();
+            crate::loading::run(Box::new(move || {
+                let result = if shared.cancelled.load(Ordering::Relaxed) {
+                    Err(GameError::FilesystemError(String::from("Cancelled")))
+                } else {
+                    fs.read_file(&path)
+                        .with_context(|| format!("reading {:?}", path))
+                };
+                contents.lock().unwrap_or_else(PoisonError::into_inner)[i] = Some(result);
+                if remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                    let contents = std::mem::take(
+                        &mut *contents.lock().unwrap_or_else(PoisonError::into_inner),
+                    );
+                    let result = contents.into_iter().flatten().collect();
+                    FsReadHandle::finish(&shared, result);
+                }
+            }));
+        }
+        handle
+    }
+
+    fn spawn_read<T: Send + 'static>(
+        &self,
+        path: &path::Path,
+        convert: fn(Vec<u8>) -> GameResult<T>,
+    ) -> FsReadHandle<T> {
+        let handle = FsReadHandle::new();
+        let fs = InternalClone::clone(self);
+        let shared = handle.shared.clone();
+        let path = path.to_owned();
+        crate::loading::run(Box::new(move || {
+            if shared.cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let result = fs
+                .read_file(&path)
+                .and_then(convert)
+                .with_context(|| format!("reading {:?}", path));
+            FsReadHandle::finish(&shared, result);
+        }));
+        handle
+    }
+
+    fn write_to_string(&self) -> String {
+        use std::fmt::Write;
+        let mut s = String::new();
+        for vfs in self.vfs().roots() {
+            write!(s, "Source {:?}", vfs).expect("Could not write to string; should never happen?");
+            match vfs.read_dir(path::Path::new("/")) {
+                Ok(files) => {
+                    for itm in files {
+                        write!(s, "  {:?}", itm)
+                            .expect("Could not write to string; should never happen?");
+                    }
+                }
+                Err(e) => write!(s, " Could not read source: {:?}", e)
+                    .expect("Could not write to string; should never happen?"),
+            }
+        }
+        s
+    }
+
+    /// Prints the contents of all data directories
+    /// to standard output.  Useful for debugging.
+    pub fn print_all(&self) {
+        println!("{}", self.write_to_string());
+    }
+
+    /// Adds the given (absolute) path of a directory or zip archive to the places it will search
+    /// to look for resources, read-only, before all of those with a lower `priority` and after
+    /// those with the same or a higher one. The ones the filesystem starts with have priority
+    /// 0, so a mods directory mounted with priority 1 overrides the game's own files.
+    ///
+    /// This, and `.unmount()`, take effect for the files opened from then on, so mods can be
+    /// enabled and disabled while the game runs.
+    pub fn mount_with_priority(&self, path: &path::Path, priority: i32) -> GameResult {
+        let vfs: Box<dyn VFS> = if path.is_file() {
+            Box::new(vfs::ZipFS::new(path).with_context(|| format!("mounting {:?}", path))?)
+        } else {
+            Box::new(vfs::PhysicalFS::new(path, true))
+        };
+        trace!("Mounting new path with priority {}: {:?}", priority, vfs);
+        self.vfs().insert(vfs, priority);
+        Ok(())
+    }
+
+    /// Removes the directory or zip archive at the given path from the places it will search
+    /// to look for resources, returning whether it was mounted.
+    pub fn unmount(&self, path: &path::Path) -> bool {
+        trace!("Unmounting path: {:?}", path);
+        self.vfs().remove(path)
+    }
+
+    /// Returns all of the places it searches to look for resources, in the order they're
+    /// searched in.
+    pub fn list_mounts(&self) -> Vec<MountInfo> {
+        self.vfs()
+            .mounts()
+            .map(|(priority, vfs)| MountInfo::new(priority, vfs))
+            .collect()
+    }
+
+    /// Sets whether paths which aren't found as they're given are looked for in another case,
+    /// e.g. for assets named `sprites/hero.png` but loaded as `"/Sprites/Hero.PNG"`, which
+    /// works on Windows but not once the game is shipped for Linux. A warning is logged the
+    /// first time each one is found in another case, so they can be fixed. Off by default.
+    ///
+    /// What's in the directories on the way is read once, then kept until the mounts change
+    /// or something is written through the filesystem, so looking up thousands of files
+    /// doesn't read them over and over; files added to them in other ways might not be found
+    /// in another case until then.
+    pub fn set_case_insensitive(&self, case_insensitive: bool) {
+        self.vfs().set_case_insensitive(case_insensitive);
+    }
+
+    /// Returns the mount that a file or directory would be found in, the first with something
+    /// at the given path, to tell e.g. which mod a file came from.
+    pub fn resolve<P: AsRef<path::Path>>(&self, path: P) -> Option<MountInfo> {
+        self.vfs()
+            .resolve(path.as_ref())
+            .map(|(priority, vfs)| MountInfo::new(priority, vfs))
+    }
+
+    /// Outputs the contents of all data directories,
+    /// using the "info" log level of the [`log`](https://docs.rs/log/) crate.
+    /// Useful for debugging.
+    pub fn log_all(&self) {
+        info!("{}", self.write_to_string());
+    }
+
+    /// Adds the given (absolute) path to the list of directories
+    /// it will search to look for resources.
+    ///
+    /// You probably shouldn't use this in the general case, since it is
+    /// harder than it looks to make it bulletproof across platforms.
+    /// But it can be very nice for debugging and dev purposes, such as
+    /// by pushing `$CARGO_MANIFEST_DIR/resources` to it
+    pub fn mount(&self, path: &path::Path, readonly: bool) {
+        let physfs = vfs::PhysicalFS::new(path, readonly);
+        trace!("Mounting new path: {:?}", physfs);
+        self.vfs().push_back(Box::new(physfs));
+    }
+
+    /// Adds any object that implements Read + Seek as a zip file.
+    ///
+    /// Note: This is not intended for system files for the same reasons as
+    /// for `.mount()`. Rather, it can be used to read zip files from sources
+    /// such as `std::io::Cursor::new(includes_bytes!(...))` in order to embed
+    /// resources into the game's executable.
+    pub fn add_zip_file<R: io::Read + io::Seek + Send + 'static>(&self, reader: R) -> GameResult {
+        let zipfs = vfs::ZipFS::from_read(reader)?;
+        trace!("Adding zip file from reader");
+        self.vfs().push_back(Box::new(zipfs));
+        Ok(())
+    }
+
+    /// Adds files compiled into the executable with `include_dir!`, e.g. for a game jam build
+    /// in a single file, to the list of places it will search to look for resources. As with
+    /// `.mount()`, whatever was mounted earlier, such as the `resources/` directory, takes
+    /// precedence over them, so loose files can still override them during development.
+    /// Nothing can be written to them.
+    ///
+    /// Everything in the directory is compiled in as it is, uncompressed, so the executable
+    /// grows by the size of it, and it's all loaded into memory with the executable.
+    ///
+    /// ```rust,ignore
+    /// use ggez::include_dir::{self, include_dir, Dir};
+    ///
+    /// static RESOURCES: Dir = include_dir!("$CARGO_MANIFEST_DIR/resources");
+    /// ctx.fs.mount_embedded(&RESOURCES);
+    /// ```
+    #[cfg(feature = "embed")]
+    pub fn mount_embedded(&self, dir: &'static include_dir::Dir<'static>) {
+        trace!("Mounting embedded files");
+        self.vfs().push_back(Box::new(vfs::EmbeddedFS::new(dir)));
+    }
+
+    /// Adds the contents of a zip archive, from a file on disk or from bytes, to the list of
+    /// places it will search to look for resources, e.g. to ship the game's assets as a single
+    /// `assets.pck` rather than a tree of loose files.
+    ///
+    /// Its files and directories can then be opened and listed like any others, and as with
+    /// `.mount()`, whatever was mounted earlier, such as the `resources/` directory, takes
+    /// precedence over it, so loose files still override the ones in the archive. Archives are
+    /// never written to, so `readonly` has to be `true`.
+    pub fn mount_zip(&self, zip: impl Into<ZipSource>, readonly: bool) -> GameResult {
+        let zip = zip.into();
+        if !readonly {
+            return Err(GameError::FilesystemError(format!(
+                "Cannot mount zip archive {:?} writeable, zip archives are read-only",
+                zip
+            )));
+        }
+        let zipfs = match zip {
+            ZipSource::Path(path) => {
+                vfs::ZipFS::new(&path).with_context(|| format!("mounting {:?}", path))?
+            }
+            ZipSource::Bytes(bytes) => vfs::ZipFS::from_bytes(bytes)?,
+        };
+        trace!("Mounting zip archive: {:?}", zipfs);
+        self.vfs().push_back(Box::new(zipfs));
+        Ok(())
+    }
+
+    /// Adds a writeable mount kept entirely in memory for the files and directories under
+    /// `prefix`, e.g. `"/saves"`, or `"/"` for all of them, so tests can save and load without
+    /// leaving files behind in the user directories.
+    ///
+    /// It's searched before the mounts there already are, so what's written under `prefix`
+    /// goes to it rather than to disk, and hides files at the same paths elsewhere. What's in
+    /// it can be looked at with [`memory_snapshot`](#method.memory_snapshot), and is gone
+    /// when the game closes.
+    pub fn mount_memory(&self, prefix: &str) -> GameResult {
+        let memoryfs = vfs::MemoryFS::new(path::Path::new(prefix)).ok_or_else(|| {
+            GameError::FilesystemError(format!(
+                "Invalid prefix {:?} to mount memory at: must be an absolute path with no \
+                 references to parent directories",
+                prefix
+            ))
+        })?;
+        trace!("Mounting memory: {:?}", memoryfs);
+        self.vfs().push_front(Box::new(memoryfs));
+        Ok(())
+    }
+
+    /// Returns the contents of the files in the memory mounted at `prefix` by
+    /// [`mount_memory`](#method.mount_memory), or in place of the user directories by
+    /// [`ContextBuilder::sandboxed_user_dirs`](crate::ContextBuilder::sandboxed_user_dirs) at
+    /// `"/"`, by their paths, or `None` if there is no such mount.
+    pub fn memory_snapshot(&self, prefix: &str) -> Option<BTreeMap<path::PathBuf, Vec<u8>>> {
+        let prefix = path::Path::new("/").join(vfs::sanitize_path(path::Path::new(prefix))?);
+        self.vfs()
+            .roots()
+            .filter_map(|vfs| vfs.as_memory())
+            .find(|memoryfs| memoryfs.prefix() == prefix)
+            .map(vfs::MemoryFS::snapshot)
+    }
+
+    // Replaces the mounts of the user directories with one in memory, see
+    // `ContextBuilder::sandboxed_user_dirs`.
+    pub(crate) fn sandbox_user_dirs(&mut self) {
+        // as they are on the web already
+        if self.sandboxed {
+            return;
+        }
+        trace!("Keeping the user directories in memory");
+        let mut vfs = self.vfs();
+        let _ = vfs.remove(&self.user_data_dir);
+        let _ = vfs.remove(&self.user_config_dir);
+        vfs.push_back(Box::new(
+            vfs::MemoryFS::new(path::Path::new("/")).unwrap(/* valid prefix */),
+        ));
+        drop(vfs);
+        self.cache = cache::memory_cache();
+        self.sandboxed = true;
+    }
+
+    /// Looks for a file named `/conf.toml` in any resource directory and
+    /// loads it if it finds it.
+    /// If it can't read it for some reason, returns an error.
+    pub fn read_config(&self) -> GameResult<conf::Conf> {
+        self.read_config_with_defaults(&conf::Conf::default())
+    }
+
+    /// Looks for a file named `/conf.toml` in any resource directory and loads it as
+    /// [`read_config`](#method.read_config) does, with the fields missing from it taken from
+    /// `defaults`.
+    pub fn read_config_with_defaults(&self, defaults: &conf::Conf) -> GameResult<conf::Conf> {
+        let conf_path = path::Path::new(CONFIG_NAME);
+        if self.is_file(conf_path) {
+            let mut file = self.open(conf_path)?;
+            let c = conf::Conf::from_toml_file_with_defaults(&mut file, defaults)
+                .with_context(|| format!("reading {:?}", conf_path))?;
+            Ok(c)
+        } else {
+            Err(GameError::ConfigError(String::from(
+                "Config file not found",
+            )))
+        }
+    }
+
+    /// Takes a `Conf` object and saves it to the user directory,
+    /// overwriting any file already there.
+    pub fn write_config(&self, conf: &conf::Conf) -> GameResult {
+        let conf_path = path::Path::new(CONFIG_NAME);
+        let mut file = self.create(conf_path)?;
+        conf.to_toml_file(&mut file)?;
+        if self.is_file(conf_path) {
+            Ok(())
+        } else {
+            Err(GameError::ConfigError(format!(
+                "Failed to write config file at {}",
+                conf_path.to_string_lossy()
+            )))
+        }
+    }
+
+    /// Returns the full path to the resource directory
+    /// (even if it doesn't exist)
+    pub fn resources_dir(&self) -> &path::Path {
+        &self.resources_dir
+    }
+
+    /// Get a reference to the filesystem's zip dir.
+    pub fn zip_dir(&self) -> &path::Path {
+        &self.zip_dir
+    }
+
+    /// Return the full path to the user config directory.
+    pub fn user_config_dir(&self) -> &path::Path {
+        &self.user_config_dir
+    }
+
+    /// Return the full path to the user data directory.
+    pub fn user_data_dir(&self) -> &path::Path {
+        &self.user_data_dir
+    }
+}
+
+/// Opens the given path and returns the resulting `File`
+/// in read-only mode.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.open` instead")]
+pub fn open<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult<File> {
+    ctx.fs.open(path)
+}
+
+/// Opens a file in the user directory with the given `filesystem::OpenOptions`.
+/// Note that even if you open a file read-only, it can only access
+/// files in the user directory.
+#[deprecated(note = "Use `ctx.fs.open_options` instead")]
+pub fn open_options<P: AsRef<path::Path>>(
+    ctx: &Context,
+    path: P,
+    options: OpenOptions,
+) -> GameResult<File> {
+    ctx.fs.open_options(path, options)
+}
+
+/// Creates a new file in the user directory and opens it
+/// to be written to, truncating it if it already exists.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.create` instead")]
+pub fn create<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult<File> {
+    ctx.fs.create(path)
+}
+
+/// Create an empty directory in the user dir
+/// with the given name.  Any parents to that directory
+/// that do not exist will be created.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.create_dir` instead")]
+pub fn create_dir<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult {
+    ctx.fs.create_dir(path.as_ref())
+}
+
+/// Deletes the specified file in the user dir.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.delete` instead")]
+pub fn delete<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult {
+    ctx.fs.delete(path.as_ref())
+}
+
+/// Deletes the specified directory in the user dir,
+/// and all its contents!
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.delete_dir` instead")]
+pub fn delete_dir<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult {
+    ctx.fs.delete_dir(path.as_ref())
+}
+
+/// Check whether a file or directory exists.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.exists` instead")]
+pub fn exists<P: AsRef<path::Path>>(ctx: &Context, path: P) -> bool {
+    ctx.fs.exists(path.as_ref())
+}
+
+/// Check whether a path points at a file.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.is_file` instead")]
+pub fn is_file<P: AsRef<path::Path>>(ctx: &Context, path: P) -> bool {
+    ctx.fs.is_file(path)
+}
+
+/// Check whether a path points at a directory.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.is_dir` instead")]
+pub fn is_dir<P: AsRef<path::Path>>(ctx: &Context, path: P) -> bool {
+    ctx.fs.is_dir(path)
+}
+
+/// Return the full path to the user data directory.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.user_data_dir` instead")]
+pub fn user_data_dir(ctx: &Context) -> &path::Path {
+    ctx.fs.user_data_dir()
+}
+
+/// Return the full path to the user config directory.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.user_config_dir` instead")]
+pub fn user_config_dir(ctx: &Context) -> &path::Path {
+    ctx.fs.user_config_dir()
+}
+
+/// Returns the full path to the resource directory
+/// (even if it doesn't exist)
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.resources_dir` instead")]
+pub fn resources_dir(ctx: &Context) -> &path::Path {
+    ctx.fs.resources_dir()
+}
+
+/// Return the full path to the user data directory
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.zip_dir` instead")]
+pub fn zip_dir(ctx: &Context) -> &path::Path {
+    ctx.fs.zip_dir()
+}
+
+/// Returns a list of all files and directories in the resource directory,
+/// in no particular order.
+///
+/// Lists the base directory if an empty path is given.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.read_dir` instead")]
+pub fn read_dir<P: AsRef<path::Path>>(
+    ctx: &Context,
+    path: P,
+) -> GameResult<Box<dyn Iterator<Item = path::PathBuf>>> {
+    ctx.fs.read_dir(path)
+}
+
+/// Prints the contents of all data directories.
+/// Useful for debugging.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.print_all` instead")]
+pub fn print_all(ctx: &Context) {
+    ctx.fs.print_all()
+}
+
+/// Outputs the contents of all data directories,
+/// using the "info" log level of the `log` crate.
+/// Useful for debugging.
+///
+/// See the [`logging` example](https://github.com/ggez/ggez/blob/master/examples/eventloop.rs)
+/// for how to collect log information.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.log_all` instead")]
+pub fn log_all(ctx: &Context) {
+    ctx.fs.log_all()
+}
+
+/// Adds the given (absolute) path to the list of directories
+/// it will search to look for resources.
+///
+/// You probably shouldn't use this in the general case, since it is
+/// harder than it looks to make it bulletproof across platforms.
+/// But it can be very nice for debugging and dev purposes, such as
+/// by pushing `$CARGO_MANIFEST_DIR/resources` to it
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.mount` instead")]
+pub fn mount(ctx: &mut Context, path: &path::Path, readonly: bool) {
+    ctx.fs.mount(path, readonly)
+}
+
+/// Looks for a file named `/conf.toml` in any resource directory and
+/// loads it if it finds it.
+/// If it can't read it for some reason, returns an error.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.read_config` instead")]
+pub fn read_config(ctx: &Context) -> GameResult<conf::Conf> {
+    ctx.fs.read_config()
+}
+
+/// Takes a `Conf` object and saves it to the user directory,
+/// overwriting any file already there.
+#[deprecated(since = "0.8.0", note = "Use `ctx.fs.write_config` instead")]
+pub fn write_config(ctx: &Context, conf: &conf::Conf) -> GameResult {
+    ctx.fs.write_config(conf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conf;
+    use crate::error::*;
+    use crate::filesystem::*;
+    use std::io::{Read, Seek, Write};
+    use std::path;
+
+    fn dummy_fs_for_tests() -> Filesystem {
+        let mut path = path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("resources");
+        let physfs = vfs::PhysicalFS::new(&path, false);
+        let mut ofs = vfs::OverlayFS::new();
+        ofs.push_front(Box::new(physfs));
+        Filesystem {
+            vfs: Arc::new(Mutex::new(ofs)),
+
+            resources_dir: "".into(),
+            zip_dir: "".into(),
+            user_config_dir: "".into(),
+            user_data_dir: "".into(),
+            user_cache_dir: "".into(),
+            cache: cache::memory_cache(),
+            sandboxed: false,
+            #[cfg(feature = "hot-reload")]
+            watcher: Arc::new(watch::FileWatcher::new()),
+        }
+    }
+
+    #[test]
+    fn headless_test_file_exists() {
+        let f = dummy_fs_for_tests();
+
+        let tile_file = path::Path::new("/tile.png");
+        assert!(f.exists(tile_file));
+        assert!(f.is_file(tile_file));
+
+        let tile_file = path::Path::new("/oglebog.png");
+        assert!(!f.exists(tile_file));
+        assert!(!f.is_file(tile_file));
+        assert!(!f.is_dir(tile_file));
+    }
+
+    #[test]
+    fn headless_test_read_dir() {
+        let f = dummy_fs_for_tests();
+
+        let dir_contents_size = f.read_dir("/").unwrap().count();
+        assert!(dir_contents_size > 0);
+    }
+
+    #[test]
+    fn headless_test_read_dir_recursive() {
+        let f = dummy_fs_for_tests();
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let options = zip::write::FileOptions::default();
+            for name in [
+                "tile.png",
+                "sprites/b.png",
+                "sprites/a/c.png",
+                "sprites/a/d.txt",
+            ] {
+                zip_archive.start_file(name, options).unwrap();
+            }
+            zip_archive.finish().unwrap().into_inner()
+        };
+        f.mount_zip(bytes, true).unwrap();
+
+        let all: Vec<_> = f.read_dir_recursive("/sprites").unwrap().collect();
+        assert_eq!(
+            all,
+            [
+                "/sprites/a",
+                "/sprites/a/c.png",
+                "/sprites/a/d.txt",
+                "/sprites/b.png"
+            ]
+            .map(path::PathBuf::from)
+        );
+        assert!(f.read_dir_recursive("/tile.png").is_err());
+        // shadowed by the one in the resources directory
+        let everything: Vec<_> = f.read_dir_recursive("/").unwrap().collect();
+        assert_eq!(
+            everything
+                .iter()
+                .filter(|p| p.as_path() == path::Path::new("/tile.png"))
+                .count(),
+            1
+        );
+        let mut sorted = everything.clone();
+        sorted.sort();
+        assert_eq!(everything, sorted);
+
+        let pngs: Vec<_> = f.glob("/sprites/**/*.png").unwrap().collect();
+        assert_eq!(
+            pngs,
+            ["/sprites/a/c.png", "/sprites/b.png"].map(path::PathBuf::from)
+        );
+        let top: Vec<_> = f.glob("/sprites/*.png").unwrap().collect();
+        assert_eq!(top, [path::PathBuf::from("/sprites/b.png")]);
+        assert_eq!(f.glob("/sprites/b.png").unwrap().count(), 1);
+        assert_eq!(f.glob("/nowhere/*").unwrap().count(), 0);
+        assert!(f.glob("sprites/*").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "embed")]
+    fn headless_test_embedded() {
+        use crate::include_dir::{self, include_dir, Dir};
+        static EMBEDDED: Dir = include_dir!("$CARGO_MANIFEST_DIR/src/graphics");
+
+        let f = dummy_fs_for_tests();
+        f.mount_embedded(&EMBEDDED);
+        assert!(f.is_dir("/shader"));
+        assert!(f.is_file("/shader/draw.wgsl"));
+        assert!(f.exists("/"));
+        assert!(!f.exists("/shader/missing.wgsl"));
+        let mut shader = String::new();
+        let _ = f
+            .open("/shader/draw.wgsl")
+            .unwrap()
+            .read_to_string(&mut shader)
+            .unwrap();
+        assert_eq!(shader, include_str!("../graphics/shader/draw.wgsl"));
+        assert!(f
+            .read_dir("/shader")
+            .unwrap()
+            .any(|p| p == path::Path::new("/shader/draw.wgsl")));
+        let mut from_embedded = f.resolve("/shader/draw.wgsl").unwrap();
+        assert!(from_embedded.readonly && from_embedded.path.is_none());
+        // still behind the resources directory
+        from_embedded = f.resolve("/tile.png").unwrap();
+        assert!(from_embedded.path.is_some());
+
+        let e = f.open_options("/mod.rs", OpenOptions::new().write(true));
+        assert!(format!("{:#}", e.unwrap_err()).contains("read-only"));
+        assert!(f.delete_dir("/shader").is_err());
+    }
+
+    #[test]
+    fn headless_test_create_delete_file() {
+        let fs = dummy_fs_for_tests();
+        let test_file = path::Path::new("/testfile.txt");
+        let bytes = b"test";
+
+        {
+            let mut file = fs.create(test_file).unwrap();
+            let _ = file.write(bytes).unwrap();
+        }
+        {
+            let mut buffer = Vec::new();
+            let mut file = fs.open(test_file).unwrap();
+            let _ = file.read_to_end(&mut buffer).unwrap();
+            assert_eq!(bytes, buffer.as_slice());
+        }
+
+        fs.delete(test_file).unwrap();
+    }
+
+    #[test]
+    fn headless_test_file_not_found() {
+        let fs = dummy_fs_for_tests();
+        {
+            let rel_file = "testfile.txt";
+            match fs.open(rel_file) {
+                Err(GameError::ResourceNotFound(_, _)) => (),
+                Err(e) => panic!("Invalid error for opening file with relative path: {:?}", e),
+                Ok(f) => panic!("Should have gotten an error but instead got {:?}!", f),
+            }
+        }
+
+        {
+            // This absolute path should work on Windows too since we
+            // completely remove filesystem roots.
+            match fs.open("/ooglebooglebarg.txt") {
+                Err(GameError::ResourceNotFound(_, _)) => (),
+                Err(e) => panic!("Invalid error for opening nonexistent file: {}", e),
+                Ok(f) => panic!("Should have gotten an error but instead got {:?}", f),
+            }
+        }
+    }
+
+    #[test]
+    fn headless_test_mount_zip() {
+        let f = dummy_fs_for_tests();
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let options = zip::write::FileOptions::default();
+            // also in the resources directory, which was mounted first
+            zip_archive.start_file("tile.png", options).unwrap();
+            let _ = zip_archive.write(b"not a png").unwrap();
+            zip_archive.start_file("pack/level.txt", options).unwrap();
+            let _ = zip_archive.write(b"level").unwrap();
+            zip_archive.finish().unwrap().into_inner()
+        };
+        assert!(f.mount_zip(bytes.clone(), false).is_err());
+        f.mount_zip(bytes, true).unwrap();
+
+        assert!(f.is_dir("/pack"));
+        assert!(f
+            .read_dir("/")
+            .unwrap()
+            .any(|p| p == path::Path::new("/pack")));
+        let mut level = String::new();
+        let _ = f
+            .open("/pack/level.txt")
+            .unwrap()
+            .read_to_string(&mut level)
+            .unwrap();
+        assert_eq!(level, "level");
+
+        let mut tile = Vec::new();
+        let _ = f.open("/tile.png").unwrap().read_to_end(&mut tile).unwrap();
+        assert_ne!(tile, b"not a png");
+    }
+
+    #[test]
+    fn headless_test_cache() {
+        let dir = std::env::temp_dir().join("ggez_test_cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut physical = dummy_fs_for_tests();
+        physical.cache = cache::physical_cache(&dir);
+        for f in [physical, dummy_fs_for_tests()] {
+            assert_eq!(f.cache_size().unwrap(), 0);
+            let write = OpenOptions::new().write(true).create(true).truncate(true);
+            let _ = f
+                .open_cache("/shaders/sprite.bin", write)
+                .unwrap()
+                .write(b"compiled")
+                .unwrap();
+            let mut contents = String::new();
+            let _ = f
+                .open_cache("/shaders/sprite.bin", OpenOptions::new().read(true))
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            assert_eq!(contents, "compiled");
+            // only in the cache
+            assert!(!f.exists("/shaders/sprite.bin"));
+            assert!(f.open_cache("/../escaped.bin", write).is_err());
+            assert!(f.open_cache("/shaders/../../escaped.bin", write).is_err());
+            assert!(!dir.join("../escaped.bin").exists());
+
+            let (mut temp, path) = f.create_temp_file("mips").unwrap();
+            let (_other, other_path) = f.create_temp_file("mips").unwrap();
+            assert_ne!(path, other_path);
+            assert!(path
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("mips"));
+            let _ = temp.write(b"scratch").unwrap();
+            let _ = temp.seek(SeekFrom::Start(0)).unwrap();
+            contents.clear();
+            let _ = temp.read_to_string(&mut contents).unwrap();
+            assert_eq!(contents, "scratch");
+            temp.flush().unwrap();
+            assert_eq!(f.cache_size().unwrap(), 15);
+            drop(temp);
+            assert!(f.open_cache(&path, OpenOptions::new().read(true)).is_err());
+            assert_eq!(f.cache_size().unwrap(), 8);
+            assert!(f.create_temp_file("../mips").is_err());
+
+            f.clear_cache().unwrap();
+            assert_eq!(f.cache_size().unwrap(), 0);
+            assert!(f
+                .open_cache("/shaders/sprite.bin", OpenOptions::new().read(true))
+                .is_err());
+        }
+        assert!(dir.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn headless_test_manifest() {
+        use crate::filesystem::manifest::{HashAlgorithm, Manifest};
+
+        let dir = std::env::temp_dir().join("ggez_test_manifest");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("levels")).unwrap();
+        std::fs::write(dir.join("abc.txt"), "abc").unwrap();
+        std::fs::write(dir.join("levels").join("1.txt"), "level").unwrap();
+        let manifest = Manifest::generate_dir(&dir, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(
+            manifest.files.keys().collect::<Vec<_>>(),
+            ["/abc.txt", "/levels/1.txt"]
+        );
+        assert_eq!(
+            manifest.files["/abc.txt"].hash,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let text = toml::to_string(&manifest).unwrap();
+        assert_eq!(toml::from_str::<Manifest>(&text).unwrap(), manifest);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let f = dummy_fs_for_tests();
+        f.mount_memory("/pack").unwrap();
+        for (path, contents) in [
+            ("/pack/a.txt", "a"),
+            ("/pack/b/c.txt", "c"),
+            ("/pack/d.txt", "d"),
+        ] {
+            f.write_atomic(path, contents.as_bytes()).unwrap();
+        }
+        let manifest = Manifest::generate(&f, "/pack", HashAlgorithm::XxHash3).unwrap();
+        assert_eq!(manifest.dir, "/pack");
+        assert_eq!(manifest.files.len(), 3);
+        assert!(manifest.verify(&f).unwrap().is_ok());
+
+        let mut calls = 0;
+        assert!(manifest
+            .verify_with_progress(&f, |_| {
+                calls += 1;
+                calls < 2
+            })
+            .is_err());
+        let mut last = None;
+        let _ = manifest
+            .verify_with_progress(&f, |progress| {
+                last = Some(*progress);
+                true
+            })
+            .unwrap();
+        let last = last.unwrap();
+        assert_eq!((last.files_done, last.bytes_done), (3, 3));
+        assert_eq!(last.fraction(), 1.0);
+
+        f.write_atomic("/pack/a.txt", b"modded").unwrap();
+        f.delete("/pack/d.txt").unwrap();
+        f.write_atomic("/pack/b/e.txt", b"extra").unwrap();
+        let report = manifest.verify(&f).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.modified, ["/pack/a.txt"]);
+        assert_eq!(report.missing, ["/pack/d.txt"]);
+        assert_eq!(report.extra, ["/pack/b/e.txt"]);
+    }
+
+    #[test]
+    fn headless_test_open_options() {
+        let resources = path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let dir = std::env::temp_dir().join("ggez_test_open_options");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut ofs = vfs::OverlayFS::new();
+        ofs.push_back(Box::new(vfs::PhysicalFS::new(&resources, true)));
+        ofs.push_back(Box::new(vfs::PhysicalFS::new(&dir, false)));
+        let physical = Filesystem {
+            vfs: Arc::new(Mutex::new(ofs)),
+            ..dummy_fs_for_tests()
+        };
+        let mut ofs = vfs::OverlayFS::new();
+        ofs.push_back(Box::new(vfs::PhysicalFS::new(&resources, true)));
+        ofs.push_back(Box::new(
+            vfs::MemoryFS::new(path::Path::new("/logs")).unwrap(),
+        ));
+        let memory = Filesystem {
+            vfs: Arc::new(Mutex::new(ofs)),
+            ..dummy_fs_for_tests()
+        };
+        for (f, log) in [(&physical, "/log.txt"), (&memory, "/logs/log.txt")] {
+            let read = |f: &Filesystem| {
+                let mut contents = String::new();
+                let _ = f.open(log).unwrap().read_to_string(&mut contents).unwrap();
+                contents
+            };
+            let mut first = f.append(log).unwrap();
+            let mut second = f.append(log).unwrap();
+            let _ = first.write(b"one ").unwrap();
+            let _ = second.write(b"two ").unwrap();
+            let _ = first.write(b"three").unwrap();
+            drop((first, second));
+            assert_eq!(read(f), "one two three");
+
+            let create_new = OpenOptions::new().write(true).create_new(true);
+            let error = f.open_options(log, create_new).unwrap_err();
+            assert!(
+                matches!(error.root(), GameError::IOError(e) if e.kind() == io::ErrorKind::AlreadyExists),
+                "{:?}",
+                error
+            );
+
+            let read_write = OpenOptions::new().read(true).write(true);
+            let mut file = f.open_options(log, read_write).unwrap();
+            assert_eq!(file.seek(SeekFrom::End(-5)).unwrap(), 8);
+            let _ = file.write(b"THREE").unwrap();
+            let _ = file.seek(SeekFrom::Start(0)).unwrap();
+            let mut contents = String::new();
+            let _ = file.read_to_string(&mut contents).unwrap();
+            assert_eq!(contents, "one two THREE");
+            drop(file);
+
+            let truncate = OpenOptions::new().write(true).truncate(true);
+            drop(f.open_options(log, truncate).unwrap());
+            assert_eq!(read(f), "");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // outside of /logs, in the read-only resources and a zip archive
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let options = zip::write::FileOptions::default();
+            zip_archive.start_file("pack/level.txt", options).unwrap();
+            let _ = zip_archive.write(b"level").unwrap();
+            zip_archive.finish().unwrap().into_inner()
+        };
+        memory.mount_zip(bytes, true).unwrap();
+        let truncate = OpenOptions::new().write(true).truncate(true);
+        for error in [
+            memory.append("/tile.png").unwrap_err(),
+            memory.open_options("/tile.png", truncate).unwrap_err(),
+            memory.create("/pack/level.txt").unwrap_err(),
+            memory.create("/elsewhere.txt").unwrap_err(),
+            memory.create_dir("/sprites").unwrap_err(),
+            memory.delete("/pack/level.txt").unwrap_err(),
+            memory
+                .write_atomic("/pack/level.txt", b"modded")
+                .unwrap_err(),
+        ] {
+            assert!(
+                matches!(error.root(), GameError::ReadOnlyError(_)),
+                "{:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn headless_test_open_range() {
+        let f = dummy_fs_for_tests();
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let options = zip::write::FileOptions::default();
+            zip_archive.start_file("sounds.bank", options).unwrap();
+            let _ = zip_archive.write(b"headerfirst secondfooter").unwrap();
+            zip_archive.finish().unwrap().into_inner()
+        };
+        f.mount_zip(bytes, true).unwrap();
+
+        let mut second = f.open_range("/sounds.bank", 12, 6).unwrap();
+        let mut contents = String::new();
+        let _ = second.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "second");
+        assert_eq!(second.seek(SeekFrom::End(-3)).unwrap(), 3);
+        contents.clear();
+        let _ = second.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "ond");
+
+        assert!(f.open_range("/sounds.bank", 20, 4).is_ok());
+        assert!(f.open_range("/sounds.bank", 20, 5).is_err());
+        assert!(f.open_range("/sounds.bank", u64::MAX, 2).is_err());
+        assert!(f.open_range("/missing.bank", 0, 0).is_err());
+    }
+
+    #[test]
+    fn headless_test_metadata() {
+        let f = dummy_fs_for_tests();
+        let resources = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let metadata = f.metadata("/player.png").unwrap();
+        let physical = std::fs::metadata(resources.join("player.png")).unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len, physical.len());
+        assert_eq!(metadata.modified, physical.modified().ok());
+        assert!(f.metadata("/").unwrap().is_dir);
+        assert!(f.metadata("/missing.png").is_err());
+
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let time = zip::DateTime::from_date_and_time(2024, 2, 29, 23, 59, 58).unwrap();
+            let options = zip::write::FileOptions::default();
+            zip_archive
+                .start_file("dated.txt", options.last_modified_time(time))
+                .unwrap();
+            let _ = zip_archive.write(b"dated").unwrap();
+            zip_archive.start_file("pack/undated.txt", options).unwrap();
+            zip_archive.finish().unwrap().into_inner()
+        };
+        f.mount_zip(bytes, true).unwrap();
+        let dated = f.metadata("/dated.txt").unwrap();
+        assert_eq!(dated.len, 5);
+        assert_eq!(
+            dated.modified,
+            Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_709_251_198))
+        );
+        assert_eq!(dated.created, None);
+        assert_eq!(f.metadata("/pack/undated.txt").unwrap().modified, None);
+        assert!(f.metadata("/pack").unwrap().is_dir);
+
+        f.mount_memory("/saves").unwrap();
+        f.create("/saves/slot.txt")
+            .unwrap()
+            .write_all(b"save")
+            .unwrap();
+        let saved = f.metadata("/saves/slot.txt").unwrap();
+        assert_eq!(saved.len, 4);
+        assert!(saved.modified.is_some() && saved.created.is_some());
+
+        let listed: Vec<_> = f.read_dir_with_metadata("/").unwrap().collect();
+        assert_eq!(listed.len(), f.read_dir("/").unwrap().count());
+        for (path, metadata) in listed {
+            assert_eq!(metadata, f.metadata(&path).unwrap());
+        }
+    }
+
+    #[test]
+    fn headless_test_mount_priority() {
+        let f = dummy_fs_for_tests();
+        let mod_path = std::env::temp_dir().join("ggez_test_mod.zip");
+        {
+            let file = std::fs::File::create(&mod_path).unwrap();
+            let mut zip_archive = zip::ZipWriter::new(file);
+            let options = zip::write::FileOptions::default();
+            zip_archive.start_file("tile.png", options).unwrap();
+            let _ = zip_archive.write(b"modded").unwrap();
+            let _ = zip_archive.finish().unwrap();
+        }
+        let read_tile = || {
+            let mut tile = Vec::new();
+            let _ = f.open("/tile.png").unwrap().read_to_end(&mut tile).unwrap();
+            tile
+        };
+
+        f.mount_with_priority(&mod_path, 1).unwrap();
+        assert_eq!(read_tile(), b"modded");
+        let mounts = f.list_mounts();
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts[0].priority, 1);
+        let resolved = f.resolve("/tile.png").unwrap();
+        assert_eq!(resolved.path.as_deref(), Some(mod_path.as_path()));
+        assert!(resolved.archive && resolved.readonly);
+        assert_eq!(
+            f.read_dir("/")
+                .unwrap()
+                .filter(|p| p == path::Path::new("/tile.png"))
+                .count(),
+            1
+        );
+
+        assert!(f.unmount(&mod_path));
+        assert!(!f.unmount(&mod_path));
+        assert_ne!(read_tile(), b"modded");
+        assert!(!f.resolve("/tile.png").unwrap().archive);
+        assert!(f.resolve("/oglebog.png").is_none());
+        std::fs::remove_file(&mod_path).unwrap();
+    }
+
+    #[test]
+    fn headless_test_read_async() {
+        let f = dummy_fs_for_tests();
+        let bytes = f.read_async("/cube.wgsl").wait().unwrap();
+        assert_eq!(bytes, f.read_file(path::Path::new("/cube.wgsl")).unwrap());
+
+        let mut text = f.read_to_string_async("/cube.wgsl");
+        while !text.is_done() {
+            std::thread::yield_now();
+        }
+        assert!(text.poll().unwrap().unwrap().contains("fn"));
+        assert!(text.poll().is_none());
+
+        let all = f
+            .read_all_async(["/cube.wgsl", "/tile.png"])
+            .wait()
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0], bytes);
+        let e = f
+            .read_all_async(["/cube.wgsl", "/oglebog.png"])
+            .wait()
+            .unwrap_err();
+        assert!(format!("{:#}", e).contains("/oglebog.png"), "{:#}", e);
+        assert!(f
+            .read_all_async(Vec::<&str>::new())
+            .wait()
+            .unwrap()
+            .is_empty());
+
+        // dropping it before it's read is fine too
+        drop(f.read_async("/tile.png"));
+    }
+
+    #[test]
+    #[cfg(feature = "hot-reload")]
+    fn headless_test_watch() {
+        let dir = std::env::temp_dir().join("ggez_test_watch");
+        std::fs::create_dir_all(dir.join("levels")).unwrap();
+        let fs = dummy_fs_for_tests();
+        fs.mount(&dir, true);
+
+        let handle = fs.watch("/levels").unwrap();
+        assert!(fs.watch("/no_such_level").is_err());
+        std::fs::write(dir.join("levels").join("1.txt"), "level 1").unwrap();
+        let start = std::time::Instant::now();
+        let mut changed = Vec::new();
+        while changed.is_empty() && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            changed = fs.take_changed_paths();
+        }
+        assert_eq!(changed, ["/levels/1.txt"]);
+        assert!(fs.take_changed_paths().is_empty());
+
+        drop(handle);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn headless_test_user_files() {
+        #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Settings {
+            volume: f32,
+            name: String,
+        }
+
+        impl Migrate for Settings {
+            const VERSION: u32 = 2;
+
+            fn migrate(old_version: u32, value: toml::Value) -> GameResult<Self> {
+                assert_eq!(old_version, 1);
+                Ok(Settings {
+                    volume: value["loudness"].as_float().unwrap() as f32,
+                    name: String::from("migrated"),
+                })
+            }
+        }
+
+        let dir = std::env::temp_dir().join("ggez_test_user_files");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut f = dummy_fs_for_tests();
+        f.user_config_dir = dir.join("config");
+        f.user_data_dir = dir.join("data");
+
+        let settings = Settings {
+            volume: 0.5,
+            name: String::from("ñandú"),
+        };
+        assert_eq!(
+            f.read_user_file::<Settings>(UserDir::Config, "settings.toml")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            f.read_user_file_or_default::<Settings>(UserDir::Data, "saves/1.toml")
+                .unwrap(),
+            Settings::default()
+        );
+        f.write_user_file(UserDir::Data, "saves/1.toml", &settings)
+            .unwrap();
+        assert_eq!(
+            f.read_user_file::<Settings>(UserDir::Data, "saves/1.toml")
+                .unwrap(),
+            Some(settings)
+        );
+        assert!(f.write_user_file(UserDir::Data, "../1.toml", &0).is_err());
+        assert!(f.write_user_file(UserDir::Data, "1.json", &0).is_err());
+
+        // a corrupt file says where it's wrong, and isn't changed
+        std::fs::create_dir_all(dir.join("config")).unwrap();
+        let corrupt = dir.join("config").join("corrupt.toml");
+        std::fs::write(&corrupt, "volume = 0.5\nname = \n").unwrap();
+        let e = f
+            .read_user_file::<Settings>(UserDir::Config, "corrupt.toml")
+            .unwrap_err();
+        assert!(format!("{:#}", e).contains("line 2"), "{:#}", e);
+        assert_eq!(
+            std::fs::read_to_string(&corrupt).unwrap(),
+            "volume = 0.5\nname = \n"
+        );
+
+        let old = dir.join("config").join("old.toml");
+        std::fs::write(&old, "version = 1\nloudness = 0.25\n").unwrap();
+        let migrated = f
+            .read_versioned_user_file::<Settings>(UserDir::Config, "old.toml")
+            .unwrap()
+            .unwrap();
+        assert_eq!(migrated.volume, 0.25);
+        f.write_versioned_user_file(UserDir::Config, "old.toml", &migrated)
+            .unwrap();
+        assert!(std::fs::read_to_string(&old)
+            .unwrap()
+            .contains("version = 2"));
+        assert_eq!(
+            f.read_versioned_user_file::<Settings>(UserDir::Config, "old.toml")
+                .unwrap(),
+            Some(migrated)
+        );
+
+        #[cfg(feature = "ron-config")]
+        {
+            f.write_user_file(UserDir::Config, "settings.ron", &Settings::default())
+                .unwrap();
+            assert_eq!(
+                f.read_user_file::<Settings>(UserDir::Config, "settings.ron")
+                    .unwrap(),
+                Some(Settings::default())
+            );
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn headless_test_mount_memory() {
+        let resources = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources");
+        let f = dummy_fs_for_tests();
+        assert!(f.mount_memory("saves").is_err());
+        f.mount_memory("/saves").unwrap();
+        assert_eq!(f.memory_snapshot("/saves"), Some(BTreeMap::new()));
+        assert_eq!(f.memory_snapshot("/elsewhere"), None);
+
+        f.create_dir("/saves/slot1").unwrap();
+        f.create("/saves/slot1/game.txt")
+            .unwrap()
+            .write_all(b"level 1")
+            .unwrap();
+        f.open_options(
+            "/saves/slot1/game.txt",
+            OpenOptions::new().append(true).create(true),
+        )
+        .unwrap()
+        .write_all(b", level 2")
+        .unwrap();
+        assert!(f.is_dir("/saves/slot1"));
+        assert_eq!(
+            f.read_dir("/saves").unwrap().collect::<Vec<_>>(),
+            vec![path::PathBuf::from("/saves/slot1")]
+        );
+        let snapshot = f.memory_snapshot("/saves").unwrap();
+        assert_eq!(
+            snapshot[path::Path::new("/saves/slot1/game.txt")],
+            b"level 1, level 2"
+        );
+        // nothing was written to the resources directory
+        assert!(!resources.join("saves").exists());
+        // which is still read from outside of it
+        assert!(f.is_file("/player.png"));
+
+        f.delete("/saves/slot1/game.txt").unwrap();
+        assert!(!f.exists("/saves/slot1/game.txt"));
+        f.delete_dir("/saves/slot1").unwrap();
+        assert_eq!(f.memory_snapshot("/saves"), Some(BTreeMap::new()));
+
+        // guest mode, keeping the user directories in memory, with the resources read-only as
+        // they usually are
+        let mut ofs = vfs::OverlayFS::new();
+        ofs.push_back(Box::new(vfs::PhysicalFS::new(&resources, true)));
+        let mut f = Filesystem {
+            vfs: Arc::new(Mutex::new(ofs)),
+            ..dummy_fs_for_tests()
+        };
+        f.sandbox_user_dirs();
+        let settings = BTreeMap::from([(String::from("volume"), 0.5)]);
+        f.write_user_file(UserDir::Config, "settings.toml", &settings)
+            .unwrap();
+        assert_eq!(
+            f.read_user_file(UserDir::Data, "settings.toml").unwrap(),
+            Some(settings)
+        );
+        assert_eq!(
+            f.read_user_file::<BTreeMap<String, f64>>(UserDir::Data, "missing.toml")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            f.memory_snapshot("/").unwrap()[path::Path::new("/settings.toml")],
+            b"volume = 0.5\n"
+        );
+    }
+
+    #[test]
+    fn headless_test_save_slots() {
+        let f = dummy_fs_for_tests();
+        f.mount_memory("/saves").unwrap();
+        let slots = SaveSlots::new(&f, "/saves")
+            .unwrap()
+            .with_version(3)
+            .with_max_slots(2);
+        assert_eq!(slots.load("1").unwrap(), None);
+        assert!(slots.save("../1", b"escape").is_err());
+
+        slots.save_with_summary("1", b"first", "Chapter 1").unwrap();
+        slots
+            .save_with_summary("1", b"second", "Chapter 2")
+            .unwrap();
+        slots.save("autosave", b"auto").unwrap();
+        assert!(slots.save("3", b"one too many").is_err());
+        assert_eq!(slots.load("1").unwrap().as_deref(), Some(&b"second"[..]));
+
+        let list = slots.list().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "1");
+        assert_eq!(list[0].summary, "Chapter 2");
+        assert_eq!(list[0].version, 3);
+        assert_eq!(list[0].len, 6);
+        assert!(!list[0].from_backup);
+        assert!(list[0].saved_at <= SystemTime::now());
+        assert_eq!(list[1].name, "autosave");
+
+        // a save broken partway falls back to the one before
+        f.create("/saves/1.sav")
+            .unwrap()
+            .write_all(b"GGEZSAVE garbage")
+            .unwrap();
+        assert_eq!(slots.load("1").unwrap().as_deref(), Some(&b"first"[..]));
+        let info = slots.info("1").unwrap().unwrap();
+        assert!(info.from_backup);
+        assert_eq!(info.summary, "Chapter 1");
+        // which is kept when saving over the broken one
+        slots.save("1", b"third").unwrap();
+        f.create("/saves/1.sav")
+            .unwrap()
+            .write_all(b"truncat")
+            .unwrap();
+        assert_eq!(slots.load("1").unwrap().as_deref(), Some(&b"first"[..]));
+
+        slots.delete("1").unwrap();
+        assert_eq!(slots.load("1").unwrap(), None);
+        assert_eq!(slots.list().unwrap().len(), 1);
+        slots.delete("1").unwrap();
+        assert_eq!(
+            f.memory_snapshot("/saves")
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![path::Path::new("/saves/autosave.sav")]
+        );
+    }
+
+    #[test]
+    fn headless_test_case_insensitive() {
+        let f = dummy_fs_for_tests();
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let options = zip::write::FileOptions::default();
+            zip_archive.start_file("Levels/One.txt", options).unwrap();
+            let _ = zip_archive.write(b"one").unwrap();
+            zip_archive.finish().unwrap().into_inner()
+        };
+        f.mount_zip(bytes, true).unwrap();
+        assert!(f.exists("/Levels/One.txt"));
+        assert!(!f.exists("/levels/one.TXT"));
+        // the same in any mount
+        assert!(f.exists("\\Levels\\.\\One.txt"));
+        assert!(!f.exists("/Levels/../Levels/One.txt"));
+
+        f.set_case_insensitive(true);
+        let mut level = String::new();
+        let _ = f
+            .open("/levels/one.TXT")
+            .unwrap()
+            .read_to_string(&mut level)
+            .unwrap();
+        assert_eq!(level, "one");
+        assert!(f.is_file("/PLAYER.png"));
+        assert_eq!(
+            f.resolve("/levels/ONE.txt").map(|mount| mount.archive),
+            Some(true)
+        );
+        assert_eq!(
+            f.read_dir("/LEVELS").unwrap().collect::<Vec<_>>(),
+            vec![path::PathBuf::from("/Levels/One.txt")]
+        );
+        assert!(!f.exists("/levels/two.txt"));
+
+        // and it's forgotten what's in the directories when they change
+        f.mount_memory("/Saves").unwrap();
+        assert!(!f.exists("/saves/slot.sav"));
+        let _ = f.create("/Saves/slot.sav").unwrap();
+        assert!(f.exists("/saves/SLOT.sav"));
+
+        f.set_case_insensitive(false);
+        assert!(!f.exists("/levels/one.TXT"));
+    }
+
+    #[test]
+    fn headless_test_write_config() {
+        let f = dummy_fs_for_tests();
+        let conf = conf::Conf::new();
+        // The config file should end up in
+        // the resources directory with this
+        match f.write_config(&conf) {
+            Ok(_) => (),
+            Err(e) => panic!("{:?}", e),
+        }
+        // Remove the config file!
+        f.delete(CONFIG_NAME).unwrap();
+    }
+}
@@ -0,0 +1,330 @@
+//! Saved games in named slots, each kept with a backup of the save before it.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::{Filesystem, InternalClone};
+use crate::error::{GameError, GameResult, ResultExt};
+
+// What save files start with, so other files aren't taken for them.
+const MAGIC: &[u8; 8] = b"GGEZSAVE";
+// The longest summary, so a corrupt header isn't read as having one gigabytes long.
+const SUMMARY_LIMIT: usize = 64 * 1024;
+const EXTENSION: &str = ".sav";
+const BACKUP_EXTENSION: &str = ".sav.bak";
+
+// Written after the magic, with the numbers in little endian and the summary after its length.
+struct Header {
+    version: u32,
+    // since the Unix epoch
+    saved_at: Duration,
+    summary: String,
+    len: u64,
+    checksum: u32,
+}
+
+impl Header {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.saved_at.as_secs().to_le_bytes());
+        bytes.extend_from_slice(&self.saved_at.subsec_nanos().to_le_bytes());
+        bytes.extend_from_slice(&(self.summary.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(self.summary.as_bytes());
+        bytes.extend_from_slice(&self.len.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+
+    // Reads a header from the start of `bytes`, advancing it past the header.
+    fn decode(bytes: &mut &[u8]) -> Result<Self, &'static str> {
+        fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], &'static str> {
+            if bytes.len() < n {
+                return Err("the header was cut short");
+            }
+            let (taken, rest) = bytes.split_at(n);
+            *bytes = rest;
+            Ok(taken)
+        }
+        fn take_u32(bytes: &mut &[u8]) -> Result<u32, &'static str> {
+            Ok(u32::from_le_bytes(take(bytes, 4)?.try_into().unwrap()))
+        }
+        fn take_u64(bytes: &mut &[u8]) -> Result<u64, &'static str> {
+            Ok(u64::from_le_bytes(take(bytes, 8)?.try_into().unwrap()))
+        }
+
+        let version = take_u32(bytes)?;
+        let secs = take_u64(bytes)?;
+        let nanos = take_u32(bytes)?;
+        if nanos >= 1_000_000_000 {
+            return Err("the save time is invalid");
+        }
+        let summary_len = take_u64(bytes)?;
+        if summary_len > SUMMARY_LIMIT as u64 {
+            return Err("the summary is too long");
+        }
+        let summary = std::str::from_utf8(take(bytes, summary_len as usize)?)
+            .map_err(|_| "the summary isn't UTF-8")?
+            .to_owned();
+        Ok(Header {
+            version,
+            saved_at: Duration::new(secs, nanos),
+            summary,
+            len: take_u64(bytes)?,
+            checksum: take_u32(bytes)?,
+        })
+    }
+}
+
+// Splits a save file into its header and the data saved, checking they're as written.
+fn decode(bytes: &[u8]) -> GameResult<(Header, &[u8])> {
+    let corrupt = |why: &str| GameError::FilesystemError(format!("Corrupt save file: {}", why));
+    let mut rest = match bytes.strip_prefix(MAGIC) {
+        Some(rest) => rest,
+        None => return Err(corrupt("not a save file")),
+    };
+    let header = Header::decode(&mut rest).map_err(corrupt)?;
+    if rest.len() as u64 != header.len {
+        return Err(corrupt("it was cut short"));
+    }
+    if crc32fast::hash(rest) != header.checksum {
+        return Err(corrupt("the checksum doesn't match"));
+    }
+    Ok((header, rest))
+}
+
+/// What's known about a saved game from [`SaveSlots::list`] or [`SaveSlots::info`], e.g. to
+/// show "Slot 3 — 2.1 MB, last played Tuesday" in a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSlotInfo {
+    /// The name of the slot.
+    pub name: String,
+    /// When it was saved.
+    pub saved_at: SystemTime,
+    /// What it was saved with by [`SaveSlots::save_with_summary`], e.g. `"Chapter 2, 3 hours"`.
+    pub summary: String,
+    /// The version of the game's saves it was saved with, see [`SaveSlots::with_version`].
+    pub version: u32,
+    /// The size in bytes of what was saved.
+    pub len: u64,
+    /// Whether it's the backup of the save before, as the last one couldn't be read.
+    pub from_backup: bool,
+}
+
+/// Saved games in named slots in a directory of the user directory, made with
+/// [`SaveSlots::new`].
+///
+/// Each save is written with [`Filesystem::write_atomic`], with when it was saved, a summary
+/// and the version of the game's saves, and a checksum. The save it replaces is kept as a
+/// backup, which is loaded instead if the last save can't be read or is corrupt, so players
+/// lose at most one save to a crash or a bad disk.
+#[derive(Debug)]
+pub struct SaveSlots {
+    fs: Filesystem,
+    dir: PathBuf,
+    version: u32,
+    max_slots: Option<usize>,
+}
+
+impl SaveSlots {
+    /// Keeps the saves in the directory `dir`, e.g. `"/saves"`, of the user directory, or in
+    /// memory with [`ContextBuilder::sandboxed_user_dirs`](crate::ContextBuilder::sandboxed_user_dirs).
+    pub fn new(fs: &Filesystem, dir: &str) -> GameResult<Self> {
+        match crate::vfs::sanitize_path(Path::new(dir)) {
+            Some(relative) => Ok(SaveSlots {
+                fs: InternalClone::clone(fs),
+                dir: Path::new("/").join(relative),
+                version: 0,
+                max_slots: None,
+            }),
+            None => Err(GameError::FilesystemError(format!(
+                "Invalid directory for saves: {:?}",
+                dir
+            ))),
+        }
+    }
+
+    /// Sets the version saves are saved with, to tell when loading them whether they have to
+    /// be converted from what an older version of the game saved. Defaults to 0.
+    #[must_use]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets how many slots there can be, saving to another one than those there are failing
+    /// once there are that many. Defaults to no limit.
+    #[must_use]
+    pub fn with_max_slots(mut self, max_slots: usize) -> Self {
+        self.max_slots = Some(max_slots);
+        self
+    }
+
+    /// Saves `data` to `slot`, e.g. `"1"` or `"autosave"`, with an empty summary.
+    pub fn save(&self, slot: &str, data: &[u8]) -> GameResult {
+        self.save_with_summary(slot, data, "")
+    }
+
+    /// Saves `data` to `slot`, with `summary` to show for it in the list of saves.
+    ///
+    /// Names of slots can have letters, digits, spaces, `-` and `_` in them.
+    pub fn save_with_summary(&self, slot: &str, data: &[u8], summary: &str) -> GameResult {
+        let path = self.path(slot, EXTENSION)?;
+        self.save_to(slot, &path, data, summary)
+            .with_context(|| format!("saving slot {:?}", slot))
+    }
+
+    fn save_to(&self, slot: &str, path: &Path, data: &[u8], summary: &str) -> GameResult {
+        if summary.len() > SUMMARY_LIMIT {
+            return Err(GameError::FilesystemError(format!(
+                "The summary of a save can be {} bytes long at most",
+                SUMMARY_LIMIT
+            )));
+        }
+        if let Some(max_slots) = self.max_slots {
+            let names = self.slot_names()?;
+            if !names.contains(slot) && names.len() >= max_slots {
+                return Err(GameError::FilesystemError(format!(
+                    "All {} save slots are in use",
+                    max_slots
+                )));
+            }
+        }
+        // keeping the save it replaces, unless that's corrupt and the backup isn't
+        if let Ok(old) = self.fs.read_file(path) {
+            if decode(&old).is_ok() {
+                self.fs
+                    .write_atomic(self.path(slot, BACKUP_EXTENSION)?, &old)?;
+            }
+        }
+        let header = Header {
+            version: self.version,
+            saved_at: crate::timer::system_time_now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default(),
+            summary: summary.to_owned(),
+            len: data.len() as u64,
+            checksum: crc32fast::hash(data),
+        };
+        let mut bytes = MAGIC.to_vec();
+        header.encode(&mut bytes);
+        bytes.extend_from_slice(data);
+        self.fs.write_atomic(path, &bytes)
+    }
+
+    /// Loads what was saved to `slot`, or its backup if that can't be read, returning `None`
+    /// if nothing was.
+    pub fn load(&self, slot: &str) -> GameResult<Option<Vec<u8>>> {
+        Ok(self.read(slot)?.map(|(_, data)| data))
+    }
+
+    /// Returns what's known about the save in `slot`, from its backup if that's what
+    /// [`load`](#method.load) would load, or `None` if there is none.
+    pub fn info(&self, slot: &str) -> GameResult<Option<SaveSlotInfo>> {
+        Ok(self.read(slot)?.map(|(info, _)| info))
+    }
+
+    /// Deletes the save in `slot` and its backup, if there are any.
+    pub fn delete(&self, slot: &str) -> GameResult {
+        for extension in [EXTENSION, BACKUP_EXTENSION] {
+            let path = self.path(slot, extension)?;
+            if self.fs.exists(&path) {
+                self.fs.delete(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns what's known about each of the saves, sorted by the names of their slots, as
+    /// [`info`](#method.info) does. Slots which can't be read are left out, with a warning
+    /// logged.
+    ///
+    /// This reads each of them whole, to check they can be loaded.
+    pub fn list(&self) -> GameResult<Vec<SaveSlotInfo>> {
+        let mut infos = Vec::new();
+        for slot in self.slot_names()? {
+            match self.info(&slot) {
+                Ok(Some(info)) => infos.push(info),
+                Ok(None) => (),
+                Err(e) => warn!("Could not read save slot {:?}: {:#}", slot, e),
+            }
+        }
+        Ok(infos)
+    }
+
+    fn path(&self, slot: &str, extension: &str) -> GameResult<PathBuf> {
+        let valid = |c: char| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_';
+        if slot.is_empty() || !slot.chars().all(valid) {
+            return Err(GameError::FilesystemError(format!(
+                "Invalid name for a save slot: {:?}",
+                slot
+            )));
+        }
+        Ok(self.dir.join(format!("{}{}", slot, extension)))
+    }
+
+    fn slot_names(&self) -> GameResult<BTreeSet<String>> {
+        if !self.fs.is_dir(&self.dir) {
+            return Ok(BTreeSet::new());
+        }
+        Ok(self
+            .fs
+            .read_dir(&self.dir)?
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                let slot = name
+                    .strip_suffix(EXTENSION)
+                    .or_else(|| name.strip_suffix(BACKUP_EXTENSION))?;
+                Some(slot.to_owned())
+            })
+            .filter(|slot| self.path(slot, EXTENSION).is_ok())
+            .collect())
+    }
+
+    fn read_file(&self, path: &Path) -> GameResult<Option<Vec<u8>>> {
+        match self.fs.read_file(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(GameError::ResourceNotFound(..)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read(&self, slot: &str) -> GameResult<Option<(SaveSlotInfo, Vec<u8>)>> {
+        let info = |header: Header, from_backup| SaveSlotInfo {
+            name: slot.to_owned(),
+            saved_at: SystemTime::UNIX_EPOCH
+                .checked_add(header.saved_at)
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+            summary: header.summary,
+            version: header.version,
+            len: header.len,
+            from_backup,
+        };
+        let path = self.path(slot, EXTENSION)?;
+        let failed = match self.read_file(&path) {
+            Ok(Some(bytes)) => match decode(&bytes) {
+                Ok((header, data)) => return Ok(Some((info(header, false), data.to_vec()))),
+                Err(e) => Some(e),
+            },
+            Ok(None) => None,
+            Err(e) => Some(e),
+        };
+        let backup = self.path(slot, BACKUP_EXTENSION)?;
+        match self.read_file(&backup)? {
+            Some(bytes) => {
+                if let Some(e) = &failed {
+                    warn!(
+                        "Could not load save slot {:?}, loading its backup: {}",
+                        slot, e
+                    );
+                }
+                let (header, data) =
+                    decode(&bytes).with_context(|| format!("loading {:?}", backup))?;
+                Ok(Some((info(header, true), data.to_vec())))
+            }
+            None => match failed {
+                Some(e) => Err(e).with_context(|| format!("loading {:?}", path)),
+                None => Ok(None),
+            },
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Reading and writing the game's own settings and saves, as TOML or RON, in the user
+//! directories.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::Filesystem;
+use crate::error::{GameError, GameResult, ResultExt};
+
+/// Which of the user's directories a file is read from or written to, by
+/// [`Filesystem::read_user_file`] and the like.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UserDir {
+    /// [`Filesystem::user_config_dir`], for settings.
+    Config,
+    /// [`Filesystem::user_data_dir`], for saved games.
+    Data,
+}
+
+/// Something written with the version of its layout by [`Filesystem::write_versioned_user_file`],
+/// so files written by older versions of the game can still be read after it changed, with
+/// [`Filesystem::read_versioned_user_file`].
+pub trait Migrate: Serialize + DeserializeOwned {
+    /// The version it's written with. It's written as a `version` field next to its own, so it
+    /// has to be a struct or a map without one.
+    const VERSION: u32;
+
+    /// Makes it from what was read from a file with an older `old_version`, which is 0 for
+    /// files written without one, with the `version` field taken out.
+    fn migrate(old_version: u32, value: toml::Value) -> GameResult<Self>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Format {
+    Toml,
+    #[cfg(feature = "ron-config")]
+    Ron,
+}
+
+impl Format {
+    fn of(name: &str) -> GameResult<Self> {
+        match Path::new(name).extension().and_then(|e| e.to_str()) {
+            Some("toml") => Ok(Format::Toml),
+            #[cfg(feature = "ron-config")]
+            Some("ron") => Ok(Format::Ron),
+            _ => Err(GameError::ConfigError(format!(
+                "Unknown format for {:?}, which has to end with .toml{}",
+                name,
+                if cfg!(feature = "ron-config") {
+                    " or .ron"
+                } else {
+                    ""
+                }
+            ))),
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(self, text: &str) -> GameResult<T> {
+        match self {
+            // the errors say where in the file it's wrong
+            Format::Toml => Ok(toml::from_str(text)?),
+            #[cfg(feature = "ron-config")]
+            Format::Ron => ron::from_str(text)
+                .map_err(|e| GameError::ConfigError(format!("RON decode error: {}", e))),
+        }
+    }
+
+    fn write<T: Serialize>(self, value: &T) -> GameResult<String> {
+        match self {
+            // as a value, which puts the tables after the rest as TOML needs them to be
+            Format::Toml => Ok(toml::to_string_pretty(&toml::Value::try_from(value)?)?),
+            #[cfg(feature = "ron-config")]
+            Format::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                .map_err(|e| GameError::ConfigError(format!("RON encode error: {}", e))),
+        }
+    }
+}
+
+impl Filesystem {
+    // Where the file is on disk, or in the filesystem when the user directories are kept in
+    // memory instead.
+    fn user_file_path(&self, dir: UserDir, name: &str) -> GameResult<PathBuf> {
+        let dir = match dir {
+            _ if self.sandboxed => Path::new("/"),
+            UserDir::Config => &self.user_config_dir,
+            UserDir::Data => &self.user_data_dir,
+        };
+        match crate::vfs::sanitize_path(&Path::new("/").join(name)) {
+            Some(relative) if !name.is_empty() => Ok(dir.join(relative)),
+            _ => Err(GameError::FilesystemError(format!(
+                "Invalid name for a user file: {:?}",
+                name
+            ))),
+        }
+    }
+
+    fn read_user_text(&self, path: &Path) -> GameResult<Option<String>> {
+        if self.sandboxed {
+            return match self.read_file(path) {
+                Ok(bytes) => String::from_utf8(bytes)
+                    .map(Some)
+                    .map_err(|_| GameError::ConfigError(String::from("Not valid UTF-8 text"))),
+                Err(GameError::ResourceNotFound(..)) => Ok(None),
+                Err(e) => Err(e),
+            };
+        }
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(GameError::from(e)),
+        }
+    }
+
+    /// Reads the file `name`, e.g. `"settings.toml"`, in the user's directory `dir`, as
+    /// TOML, or RON if it ends with `.ron` and the `ron-config` feature is enabled. Returns
+    /// `None` if there is no such file.
+    ///
+    /// Files which can't be parsed return an error saying where in them they're wrong.
+    pub fn read_user_file<T: DeserializeOwned>(
+        &self,
+        dir: UserDir,
+        name: &str,
+    ) -> GameResult<Option<T>> {
+        let format = Format::of(name)?;
+        let path = self.user_file_path(dir, name)?;
+        self.read_user_text(&path)
+            .and_then(|text| text.map(|text| format.parse(&text)).transpose())
+            .with_context(|| format!("reading {:?}", path))
+    }
+
+    /// Reads the file `name` in the user's directory `dir` as
+    /// [`read_user_file`](#method.read_user_file) does, returning the default if there is no
+    /// such file.
+    pub fn read_user_file_or_default<T: DeserializeOwned + Default>(
+        &self,
+        dir: UserDir,
+        name: &str,
+    ) -> GameResult<T> {
+        Ok(self.read_user_file(dir, name)?.unwrap_or_default())
+    }
+
+    /// Writes `value` to the file `name` in the user's directory `dir`, in the format
+    /// [`read_user_file`](#method.read_user_file) reads it in, creating the directory if
+    /// necessary.
+    ///
+    /// It's written to another file first, which is then renamed, so the old file is left as
+    /// it was if it fails partway, e.g. when the game is closed. With
+    /// [`ContextBuilder::sandboxed_user_dirs`](crate::ContextBuilder::sandboxed_user_dirs)
+    /// it's kept in memory instead.
+    pub fn write_user_file<T: Serialize>(&self, dir: UserDir, name: &str, value: &T) -> GameResult {
+        let format = Format::of(name)?;
+        let path = self.user_file_path(dir, name)?;
+        format
+            .write(value)
+            .and_then(|text| {
+                if self.sandboxed {
+                    Ok(self.create(&path)?.write_all(text.as_bytes())?)
+                } else {
+                    Ok(crate::vfs::write_atomically(&path, text.as_bytes())?)
+                }
+            })
+            .with_context(|| format!("writing {:?}", path))
+    }
+
+    /// Reads the file `name` in the user's directory `dir` as
+    /// [`read_user_file`](#method.read_user_file) does, migrating it with
+    /// [`Migrate::migrate`] if it was written with an older version.
+    pub fn read_versioned_user_file<T: Migrate>(
+        &self,
+        dir: UserDir,
+        name: &str,
+    ) -> GameResult<Option<T>> {
+        let value = match self.read_user_file::<toml::Value>(dir, name)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let path = self.user_file_path(dir, name)?;
+        from_versioned(value)
+            .map(Some)
+            .with_context(|| format!("reading {:?}", path))
+    }
+
+    /// Writes `value` to the file `name` in the user's directory `dir` as
+    /// [`write_user_file`](#method.write_user_file) does, with its version.
+    pub fn write_versioned_user_file<T: Migrate>(
+        &self,
+        dir: UserDir,
+        name: &str,
+        value: &T,
+    ) -> GameResult {
+        let mut value = toml::Value::try_from(value)?;
+        match value.as_table_mut() {
+            Some(table) => {
+                let _ = table.insert(
+                    String::from("version"),
+                    toml::Value::Integer(i64::from(T::VERSION)),
+                );
+            }
+            None => {
+                return Err(GameError::ConfigError(format!(
+                    "Cannot write a version with {:?}, which isn't a struct or a map",
+                    name
+                )))
+            }
+        }
+        self.write_user_file(dir, name, &value)
+    }
+}
+
+fn from_versioned<T: Migrate>(mut value: toml::Value) -> GameResult<T> {
+    let version = match value
+        .as_table_mut()
+        .and_then(|table| table.remove("version"))
+    {
+        None => 0,
+        Some(toml::Value::Integer(version)) if (0..=i64::from(u32::MAX)).contains(&version) => {
+            version as u32
+        }
+        Some(version) => {
+            return Err(GameError::ConfigError(format!(
+                "Invalid version {}",
+                version
+            )))
+        }
+    };
+    match version.cmp(&T::VERSION) {
+        std::cmp::Ordering::Equal => Ok(value.try_into()?),
+        std::cmp::Ordering::Less => T::migrate(version, value),
+        std::cmp::Ordering::Greater => Err(GameError::ConfigError(format!(
+            "Written with version {}, newer than this game's {}",
+            version,
+            T::VERSION
+        ))),
+    }
+}
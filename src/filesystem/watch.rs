@@ -0,0 +1,272 @@
+//! Watching the files in mounted directories for changes, with `notify`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::Filesystem;
+use crate::error::{GameError, GameResult};
+
+// How long a file has to go unchanged for its changes to be reported, since editors often
+// write files several times over when saving them.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+struct Watched {
+    // the mount it's in, as mounted
+    mount: PathBuf,
+    // and as the paths of the changes start
+    root: PathBuf,
+    // how many handles watch it
+    count: usize,
+}
+
+#[derive(Default)]
+struct State {
+    // by the physical paths watched
+    watched: HashMap<PathBuf, Watched>,
+    // the changes not reported yet, by the mount root and the path in the filesystem, with
+    // when the file last changed
+    changes: HashMap<(PathBuf, String), Instant>,
+}
+
+impl State {
+    fn changed(&mut self, path: &Path, now: Instant) {
+        let watched = match self
+            .watched
+            .iter()
+            .find(|(watched, _)| path.starts_with(watched))
+        {
+            Some((_, watched)) => watched,
+            None => return,
+        };
+        if let Some(vfs_path) = to_vfs_path(&watched.root, path) {
+            let _ = self.changes.insert((watched.mount.clone(), vfs_path), now);
+        }
+    }
+}
+
+// Turns the physical `path` in the mount at `root` into the path of it in the filesystem.
+fn to_vfs_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut vfs_path = String::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(name) => {
+                vfs_path.push('/');
+                vfs_path.push_str(name.to_str()?);
+            }
+            _ => return None,
+        }
+    }
+    if vfs_path.is_empty() {
+        vfs_path.push('/');
+    }
+    Some(vfs_path)
+}
+
+/// The files being watched by a [`Filesystem`], shared by its clones.
+pub(crate) struct FileWatcher {
+    // started the first time something is watched
+    notify: Mutex<Option<RecommendedWatcher>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl FileWatcher {
+    pub(crate) fn new() -> Self {
+        FileWatcher {
+            notify: Mutex::new(None),
+            state: Arc::default(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn watch(&self, mount: &Path, root: &Path, path: &Path) -> GameResult {
+        {
+            let mut state = self.state();
+            if let Some(watched) = state.watched.get_mut(path) {
+                watched.count += 1;
+                return Ok(());
+            }
+        }
+        let mut notify = self.notify.lock().unwrap_or_else(PoisonError::into_inner);
+        if notify.is_none() {
+            let state = self.state.clone();
+            let watcher =
+                notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(e) => {
+                            warn!("Error watching files: {}", e);
+                            return;
+                        }
+                    };
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        return;
+                    }
+                    let now = Instant::now();
+                    let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                    for path in &event.paths {
+                        state.changed(path, now);
+                    }
+                })
+                .map_err(|e| GameError::FilesystemError(format!("Could not watch files: {}", e)))?;
+            *notify = Some(watcher);
+        }
+        notify
+            .as_mut()
+            .unwrap(/* started above */)
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| GameError::FilesystemError(format!("Could not watch {:?}: {}", path, e)))?;
+        let _ = self.state().watched.insert(
+            path.to_owned(),
+            Watched {
+                mount: mount.to_owned(),
+                root: root.to_owned(),
+                count: 1,
+            },
+        );
+        Ok(())
+    }
+
+    fn unwatch(&self, path: &Path) {
+        let mut state = self.state();
+        let count = match state.watched.get_mut(path) {
+            Some(watched) => {
+                watched.count -= 1;
+                watched.count
+            }
+            None => return,
+        };
+        if count == 0 {
+            let _ = state.watched.remove(path);
+            drop(state);
+            if let Some(notify) = self
+                .notify
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_mut()
+            {
+                let _ = notify.unwatch(path);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FileWatcher")
+            .field("watched", &self.state().watched.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Files being watched for changes, made with [`Filesystem::watch`]. They stop being watched
+/// when it's dropped.
+#[derive(Debug)]
+pub struct WatchHandle {
+    watcher: Arc<FileWatcher>,
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            self.watcher.unwatch(path);
+        }
+    }
+}
+
+impl Filesystem {
+    /// Starts watching the file or directory, and everything in it, at `path` for changes, in
+    /// each of the directories mounted now which have it. The paths of the files which
+    /// changed are returned by [`take_changed_paths`](#method.take_changed_paths), e.g. to
+    /// reload images and shaders while the game runs as they're edited.
+    ///
+    /// Files in zip archives can't be watched. It stops watching when the handle is dropped.
+    pub fn watch<P: AsRef<Path>>(&self, path: P) -> GameResult<WatchHandle> {
+        let path = path.as_ref();
+        let physical: Vec<(PathBuf, PathBuf, PathBuf)> = self
+            .list_mounts()
+            .into_iter()
+            .filter(|mount| !mount.archive)
+            .filter_map(|mount| mount.path)
+            .filter_map(|mount| {
+                let physical = crate::vfs::sanitize_path(path).map(|p| mount.join(p))?;
+                // as the paths of the changes are, on some platforms
+                let physical = physical.canonicalize().ok()?;
+                let root = mount.canonicalize().ok()?;
+                Some((mount, root, physical))
+            })
+            .collect();
+        if physical.is_empty() {
+            return Err(GameError::FilesystemError(format!(
+                "Nothing to watch at {:?} in any mounted directory",
+                path
+            )));
+        }
+        let mut handle = WatchHandle {
+            watcher: self.watcher.clone(),
+            paths: Vec::new(),
+        };
+        for (mount, root, physical) in physical {
+            trace!("Watching {:?}", physical);
+            self.watcher.watch(&mount, &root, &physical)?;
+            handle.paths.push(physical);
+        }
+        Ok(handle)
+    }
+
+    /// Returns the paths, as they'd be opened, of the watched files which changed since the
+    /// last time, once they've stopped changing for a moment, e.g. to check every frame.
+    ///
+    /// Changes to files which are hidden by ones in a mount searched before them, so wouldn't
+    /// be opened, aren't returned. Files which were deleted are.
+    pub fn take_changed_paths(&self) -> Vec<String> {
+        let now = Instant::now();
+        let settled: Vec<(PathBuf, String)> = {
+            let mut state = self.watcher.state();
+            let settled: Vec<_> = state
+                .changes
+                .iter()
+                .filter(|(_, &changed)| now.duration_since(changed) >= DEBOUNCE)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in &settled {
+                let _ = state.changes.remove(key);
+            }
+            settled
+        };
+        let changed: BTreeSet<String> = settled
+            .into_iter()
+            .filter(|(root, vfs_path)| match self.resolve(vfs_path) {
+                Some(mount) => mount.path.as_ref() == Some(root),
+                None => true,
+            })
+            .map(|(_, vfs_path)| vfs_path)
+            .collect();
+        changed.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vfs_paths() {
+        let root = Path::new("/game/resources");
+        assert_eq!(
+            to_vfs_path(root, &root.join("sprites").join("player.png")).as_deref(),
+            Some("/sprites/player.png")
+        );
+        assert_eq!(to_vfs_path(root, root).as_deref(), Some("/"));
+        assert_eq!(to_vfs_path(root, Path::new("/elsewhere/player.png")), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Loading the game's files on the web, where there's no filesystem to read them from.
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+#[cfg(feature = "hot-reload")]
+use crate::filesystem::Filesystem;
+use crate::{GameError, GameResult};
+
+fn js_error(url: &str, error: wasm_bindgen::JsValue) -> GameError {
+    GameError::FilesystemError(format!("Fetching {:?} failed: {:?}", url, error))
+}
+
+/// Downloads the file at `url`, relative to the page the game runs in, e.g. a zip archive of
+/// the game's resources to mount with
+/// [`Filesystem::mount_zip`](crate::filesystem::Filesystem::mount_zip):
+///
+/// ```rust,ignore
+/// let resources = ggez::filesystem::fetch("resources.zip").await?;
+/// ctx.fs.mount_zip(resources, true)?;
+/// ```
+///
+/// Fails when the server answers with an error status rather than the file. Only on the web,
+/// where files can't be read from disk.
+pub async fn fetch(url: &str) -> GameResult<Vec<u8>> {
+    let window = web_sys::window()
+        .ok_or_else(|| GameError::FilesystemError(String::from("No browser window to fetch in")))?;
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(|e| js_error(url, e))?;
+    let response: web_sys::Response = response.dyn_into().map_err(|e| js_error(url, e))?;
+    if !response.ok() {
+        return Err(GameError::FilesystemError(format!(
+            "Fetching {:?} failed: {} {}",
+            url,
+            response.status(),
+            response.status_text()
+        )));
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(|e| js_error(url, e))?)
+        .await
+        .map_err(|e| js_error(url, e))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// Keeps watching files for changes until it's dropped, see
+/// [`Filesystem::watch`](crate::filesystem::Filesystem::watch). There are no files to watch on
+/// the web, so there's never one there.
+#[cfg(feature = "hot-reload")]
+#[derive(Debug)]
+pub struct WatchHandle {
+    _private: (),
+}
+
+#[cfg(feature = "hot-reload")]
+impl Filesystem {
+    /// Starts watching the file or directory at `path` for changes. There are no files to watch
+    /// on the web, where the resources come from memory, so this always fails there.
+    pub fn watch<P: AsRef<std::path::Path>>(&self, path: P) -> GameResult<WatchHandle> {
+        Err(GameError::FilesystemError(format!(
+            "Cannot watch {:?}, files can't be watched on the web",
+            path.as_ref()
+        )))
+    }
+
+    /// Returns the paths of the watched files which changed since the last time, which on the
+    /// web is never any.
+    pub fn take_changed_paths(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,376 @@
+use super::{Canvas, Rect};
+use glam::{Affine2, Mat4, Vec2};
+use mint::{Point2, Vector2};
+
+/// A simple 2D camera, describing which part of the world is visible on a [`Canvas`].
+///
+/// The camera is centered on `position` (in world coordinates), magnified by `zoom` and rotated
+/// by `rotation` radians around its center. The `viewport` is the size of the area the camera
+/// renders into, in screen coordinates; this is usually the drawable size of the window or the
+/// size of the canvas image.
+///
+/// Call [`Camera2D::apply`] on a canvas to draw everything afterwards through the camera, and use
+/// [`Camera2D::screen_to_world`] to map mouse positions back into the world.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera2D {
+    position: Vec2,
+    zoom: f32,
+    rotation: f32,
+    viewport: Vec2,
+
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_remaining: f32,
+    shake_offset: Vec2,
+    shake_seed: u32,
+}
+
+impl Camera2D {
+    /// Creates a new camera looking at the world origin, with a zoom of 1 and no rotation.
+    pub fn new(viewport: impl Into<Vector2<f32>>) -> Self {
+        let viewport: Vector2<f32> = viewport.into();
+        Camera2D {
+            position: Vec2::ZERO,
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: viewport.into(),
+
+            shake_intensity: 0.0,
+            shake_duration: 0.0,
+            shake_remaining: 0.0,
+            shake_offset: Vec2::ZERO,
+            shake_seed: 0x9E37_79B9,
+        }
+    }
+
+    /// Returns the world position at the center of the camera.
+    #[inline]
+    pub fn position(&self) -> Point2<f32> {
+        self.position.into()
+    }
+
+    /// Sets the world position at the center of the camera.
+    #[inline]
+    pub fn set_position(&mut self, position: impl Into<Point2<f32>>) {
+        let position: Point2<f32> = position.into();
+        self.position = position.into();
+    }
+
+    /// Returns the zoom factor. Values larger than 1 magnify the world.
+    #[inline]
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets the zoom factor, keeping the center of the camera in place.
+    ///
+    /// Non-positive or non-finite zoom factors are ignored.
+    #[inline]
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if zoom.is_finite() && zoom > 0.0 {
+            self.zoom = zoom;
+        }
+    }
+
+    /// Returns the rotation of the camera, in radians.
+    #[inline]
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Sets the rotation of the camera, in radians. Rotating the camera clockwise makes the
+    /// world appear to rotate counter-clockwise.
+    #[inline]
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+
+    /// Returns the size of the area the camera renders into, in screen coordinates.
+    #[inline]
+    pub fn viewport(&self) -> Vector2<f32> {
+        self.viewport.into()
+    }
+
+    /// Sets the size of the area the camera renders into, in screen coordinates.
+    ///
+    /// This should be updated whenever the window is resized.
+    #[inline]
+    pub fn set_viewport(&mut self, viewport: impl Into<Vector2<f32>>) {
+        let viewport: Vector2<f32> = viewport.into();
+        self.viewport = viewport.into();
+    }
+
+    /// Multiplies the zoom by `factor`, while keeping the world point `point` at the same place
+    /// on the screen; e.g. pass the world position under the mouse cursor for zooming towards it.
+    pub fn zoom_to(&mut self, point: impl Into<Point2<f32>>, factor: f32) {
+        let new_zoom = self.zoom * factor;
+        if !new_zoom.is_finite() || new_zoom <= 0.0 {
+            return;
+        }
+        let point: Point2<f32> = point.into();
+        let point = Vec2::from(point);
+        self.position = point - (point - self.position) * (self.zoom / new_zoom);
+        self.zoom = new_zoom;
+    }
+
+    /// Moves the camera towards `target` (a world position).
+    ///
+    /// `lerp_factor` is the fraction of the remaining distance travelled per call, so `1.0` snaps
+    /// to the target immediately while smaller values smooth the movement out. If a `deadzone`
+    /// is given (in screen coordinates), the camera only moves when the target leaves it, and
+    /// then just far enough to bring it back inside.
+    pub fn follow(
+        &mut self,
+        target: impl Into<Point2<f32>>,
+        lerp_factor: f32,
+        deadzone: impl Into<Option<Rect>>,
+    ) {
+        let target: Point2<f32> = target.into();
+        let target = Vec2::from(target);
+        let lerp_factor = lerp_factor.clamp(0.0, 1.0);
+
+        let delta = match deadzone.into() {
+            None => target - self.position,
+            Some(deadzone) => {
+                let screen = self.view_transform(false).transform_point2(target);
+                let excess = |v: f32, min: f32, max: f32| {
+                    if v < min {
+                        v - min
+                    } else if v > max {
+                        v - max
+                    } else {
+                        0.0
+                    }
+                };
+                let screen_delta = Vec2::new(
+                    excess(screen.x, deadzone.left(), deadzone.right()),
+                    excess(screen.y, deadzone.top(), deadzone.bottom()),
+                );
+                // screen space deltas map back into the world without the translation
+                self.view_transform(false)
+                    .inverse()
+                    .transform_vector2(screen_delta)
+            }
+        };
+
+        self.position += delta * lerp_factor;
+    }
+
+    /// Moves the camera so that the visible area stays within `world_bounds`.
+    ///
+    /// If the visible area is larger than the bounds along an axis, the camera is centered on
+    /// the bounds along that axis instead.
+    pub fn clamp_to(&mut self, world_bounds: Rect) {
+        let half = self.half_extents();
+        let clamp_axis = |pos: f32, half: f32, min: f32, max: f32| {
+            if max - min <= half * 2.0 {
+                (min + max) / 2.0
+            } else {
+                pos.clamp(min + half, max - half)
+            }
+        };
+        self.position = Vec2::new(
+            clamp_axis(
+                self.position.x,
+                half.x,
+                world_bounds.left(),
+                world_bounds.right(),
+            ),
+            clamp_axis(
+                self.position.y,
+                half.y,
+                world_bounds.top(),
+                world_bounds.bottom(),
+            ),
+        );
+    }
+
+    /// Returns the axis-aligned bounding box of the area of the world visible through the
+    /// camera, ignoring screen shake.
+    pub fn visible_rect(&self) -> Rect {
+        let half = self.half_extents();
+        Rect::new(
+            self.position.x - half.x,
+            self.position.y - half.y,
+            half.x * 2.0,
+            half.y * 2.0,
+        )
+    }
+
+    /// Starts shaking the camera by up to `intensity` (in screen coordinates), decaying
+    /// linearly to zero over `duration` seconds.
+    ///
+    /// The shake only advances when [`Camera2D::update`] is called.
+    pub fn shake(&mut self, intensity: f32, duration: f32) {
+        if duration <= 0.0 {
+            return;
+        }
+        self.shake_intensity = intensity;
+        self.shake_duration = duration;
+        self.shake_remaining = duration;
+    }
+
+    /// Returns `true` if the camera is currently shaking.
+    #[inline]
+    pub fn is_shaking(&self) -> bool {
+        self.shake_remaining > 0.0
+    }
+
+    /// Advances the screen shake by `dt` seconds, picking a new random offset.
+    ///
+    /// This is usually called once per update with the frame's delta time.
+    pub fn update(&mut self, dt: f32) {
+        if self.shake_remaining <= 0.0 {
+            self.shake_offset = Vec2::ZERO;
+            return;
+        }
+
+        self.shake_remaining = (self.shake_remaining - dt).max(0.0);
+        let magnitude = self.shake_intensity * (self.shake_remaining / self.shake_duration);
+        let x = self.next_random();
+        let y = self.next_random();
+        self.shake_offset = Vec2::new(x, y) * magnitude;
+    }
+
+    /// Sets the projection of `canvas` so that everything drawn afterwards is seen through the
+    /// camera, including the current screen shake offset.
+    ///
+    /// This overrides any projection previously set on the canvas, and
+    /// [`Canvas::screen_coordinates`] will return `None` afterwards. On frame canvases drawing at
+    /// a virtual resolution, the viewport is fit into the letterboxed area like the canvas'
+    /// screen coordinates would be.
+    pub fn apply(&self, canvas: &mut Canvas) {
+        let screen = canvas.screen_projection(self.viewport_rect());
+        canvas.set_projection(screen * self.view_matrix());
+    }
+
+    /// Returns the projection matrix [`Camera2D::apply`] sets on canvases without a virtual resolution.
+    pub fn projection(&self) -> mint::ColumnMatrix4<f32> {
+        (super::internal_canvas::screen_to_mat(self.viewport_rect()) * self.view_matrix()).into()
+    }
+
+    /// Converts a world position into screen coordinates (relative to the viewport), the same
+    /// way drawing through [`Camera2D::apply`] does.
+    pub fn world_to_screen(&self, point: impl Into<Point2<f32>>) -> Point2<f32> {
+        let point: Point2<f32> = point.into();
+        self.view_transform(true)
+            .transform_point2(point.into())
+            .into()
+    }
+
+    /// Converts screen coordinates (relative to the viewport) into a world position, e.g. to
+    /// find out what is under the mouse cursor. This is the inverse of
+    /// [`Camera2D::world_to_screen`].
+    pub fn screen_to_world(&self, point: impl Into<Point2<f32>>) -> Point2<f32> {
+        let point: Point2<f32> = point.into();
+        self.view_transform(true)
+            .inverse()
+            .transform_point2(point.into())
+            .into()
+    }
+
+    fn viewport_rect(&self) -> Rect {
+        Rect::new(0.0, 0.0, self.viewport.x, self.viewport.y)
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        let view = self.view_transform(true);
+        Mat4::from_cols(
+            view.matrix2.x_axis.extend(0.0).extend(0.0),
+            view.matrix2.y_axis.extend(0.0).extend(0.0),
+            glam::Vec4::Z,
+            view.translation.extend(0.0).extend(1.0),
+        )
+    }
+
+    pub(crate) fn view_transform(&self, shake: bool) -> Affine2 {
+        let offset = if shake { self.shake_offset } else { Vec2::ZERO };
+        Affine2::from_translation(self.viewport / 2.0 + offset)
+            * Affine2::from_scale(Vec2::splat(self.zoom))
+            * Affine2::from_angle(-self.rotation)
+            * Affine2::from_translation(-self.position)
+    }
+
+    fn half_extents(&self) -> Vec2 {
+        let half = self.viewport / (2.0 * self.zoom);
+        let (sin, cos) = self.rotation.sin_cos();
+        Vec2::new(
+            cos.abs() * half.x + sin.abs() * half.y,
+            sin.abs() * half.x + cos.abs() * half.y,
+        )
+    }
+
+    // xorshift32, mapped to [-1, 1]; plenty for shaking a camera.
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.shake_seed;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.shake_seed = x;
+        (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: Point2<f32>, b: Point2<f32>) -> bool {
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+    }
+
+    #[test]
+    fn world_screen_round_trip() {
+        let mut camera = Camera2D::new([800.0, 600.0]);
+        camera.set_position([100.0, -50.0]);
+        camera.set_zoom(2.0);
+        camera.set_rotation(0.7);
+
+        assert!(approx_eq(
+            camera.world_to_screen([100.0, -50.0]),
+            Point2 { x: 400.0, y: 300.0 }
+        ));
+        let world = Point2 { x: 37.0, y: 12.5 };
+        assert!(approx_eq(
+            camera.screen_to_world(camera.world_to_screen(world)),
+            world
+        ));
+    }
+
+    #[test]
+    fn zoom_to_keeps_point_fixed() {
+        let mut camera = Camera2D::new([800.0, 600.0]);
+        camera.set_rotation(0.3);
+        let point = Point2 { x: 250.0, y: 80.0 };
+        let before = camera.world_to_screen(point);
+        camera.zoom_to(point, 3.0);
+        assert!(approx_eq(camera.world_to_screen(point), before));
+        assert!((camera.zoom() - 3.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn follow_and_clamp() {
+        let mut camera = Camera2D::new([100.0, 100.0]);
+        camera.follow([20.0, 0.0], 1.0, Rect::new(40.0, 40.0, 20.0, 20.0));
+        assert!(approx_eq(camera.position(), Point2 { x: 10.0, y: 0.0 }));
+
+        camera.clamp_to(Rect::new(0.0, 0.0, 1000.0, 1000.0));
+        assert!(approx_eq(camera.position(), Point2 { x: 50.0, y: 50.0 }));
+        camera.clamp_to(Rect::new(0.0, 0.0, 50.0, 1000.0));
+        assert!(approx_eq(camera.position(), Point2 { x: 25.0, y: 50.0 }));
+    }
+
+    #[test]
+    fn shake_decays() {
+        let mut camera = Camera2D::new([100.0, 100.0]);
+        camera.shake(10.0, 1.0);
+        camera.update(0.5);
+        assert!(camera.is_shaking());
+        camera.update(0.5);
+        assert!(!camera.is_shaking());
+        assert!(approx_eq(
+            camera.world_to_screen([0.0, 0.0]),
+            Point2 { x: 50.0, y: 50.0 }
+        ));
+    }
+}
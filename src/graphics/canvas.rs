@@ -6,10 +6,14 @@ use crate::{
 };
 
 use super::{
-    gpu::arc::{ArcBindGroup, ArcBindGroupLayout},
+    gpu::{
+        arc::{ArcBindGroup, ArcBindGroupLayout},
+        text::Extra,
+    },
     internal_canvas::{screen_to_mat, InstanceArrayView, InternalCanvas},
-    BlendMode, Color, DrawParam, Drawable, GraphicsContext, Image, InstanceArray, Mesh, Rect,
-    Sampler, ScreenImage, Shader, ShaderParams, Text, WgpuContext, ZIndex,
+    BlendMode, Color, DrawParam, Drawable, GlyphInfo, GlyphTransform, GraphicsContext, Image,
+    InstanceArray, Mesh, Rect, Sampler, ScreenImage, Shader, ShaderParams, Text, Transform,
+    WgpuContext, WindowId, ZIndex,
 };
 use std::{collections::BTreeMap, sync::Arc};
 
@@ -31,6 +35,8 @@ pub struct Canvas {
     original_state: DrawState,
     screen: Option<Rect>,
     defaults: DefaultResources,
+    frame: bool,
+    viewport: Option<FrameViewport>,
 
     target: Image,
     resolve: Option<Image>,
@@ -108,7 +114,57 @@ impl Canvas {
         } else {
             (gfx.frame_image.clone().unwrap(), None)
         };
-        Canvas::new(gfx, target, resolve, clear.into())
+        let mut canvas = Canvas::new(gfx, target, resolve, clear.into());
+        canvas.frame = true;
+
+        if let Some(virtual_resolution) = gfx.virtual_resolution {
+            let (width, height) = virtual_resolution.size;
+            let pixels = virtual_resolution.mode.viewport(
+                virtual_resolution.size,
+                (canvas.target.width(), canvas.target.height()),
+            );
+            let screen = Rect::new(0., 0., width as f32, height as f32);
+
+            canvas.viewport = Some(FrameViewport {
+                pixels,
+                screen,
+                clear: canvas.clear,
+            });
+            if canvas.clear.is_some() {
+                canvas.clear = Some(gfx.letterbox_color);
+            }
+
+            let scissor_rect = (
+                pixels.x as u32,
+                pixels.y as u32,
+                pixels.w as u32,
+                pixels.h as u32,
+            );
+            canvas.state.scissor_rect = scissor_rect;
+            canvas.original_state.scissor_rect = scissor_rect;
+            canvas.set_screen_coordinates(screen);
+        }
+
+        canvas
+    }
+
+    /// Create a new [Canvas] that renders directly to the surface of a secondary window,
+    /// see [`GraphicsContext::create_window`].
+    ///
+    /// Fails if the window has not been created yet or has been closed.
+    pub fn from_window_frame(
+        gfx: &impl Has<GraphicsContext>,
+        window: WindowId,
+        clear: impl Into<Option<Color>>,
+    ) -> GameResult<Self> {
+        let gfx = gfx.retrieve();
+        let target = gfx
+            .windows
+            .iter()
+            .find(|w| w.id == window)
+            .map(|w| w.frame.clone())
+            .ok_or_else(|| GameError::RenderError(format!("{:?} is not open (yet)", window)))?;
+        Ok(Canvas::new(gfx, target, None, clear.into()))
     }
 
     fn new(
@@ -147,6 +203,8 @@ impl Canvas {
             original_state: state,
             screen: Some(screen),
             defaults,
+            frame: false,
+            viewport: None,
 
             target,
             resolve,
@@ -285,12 +343,32 @@ impl Canvas {
     ///
     /// The `Rect`'s x and y will define the top-left corner of the screen,
     /// and that plus its w and h will define the bottom-right corner.
+    ///
+    /// If a virtual resolution is set (see [`GraphicsContext::set_virtual_resolution`]), the
+    /// `Rect` is mapped onto the letterboxed area of frame canvases rather than the whole target.
     #[inline]
     pub fn set_screen_coordinates(&mut self, rect: Rect) {
-        self.set_projection(screen_to_mat(rect));
+        self.set_projection(self.screen_projection(rect));
         self.screen = Some(rect);
     }
 
+    // The projection mapping `rect` onto the area of the target that is drawn to.
+    pub(crate) fn screen_projection(&self, rect: Rect) -> glam::Mat4 {
+        match &self.viewport {
+            Some(viewport) => {
+                let sx = rect.w / viewport.pixels.w;
+                let sy = rect.h / viewport.pixels.h;
+                screen_to_mat(Rect::new(
+                    rect.x - viewport.pixels.x * sx,
+                    rect.y - viewport.pixels.y * sy,
+                    self.target.width() as f32 * sx,
+                    self.target.height() as f32 * sy,
+                ))
+            }
+            None => screen_to_mat(rect),
+        }
+    }
+
     /// Returns the boudns of the screen viewport, iff the projection was last set with
     /// `set_screen_coordinates`. If the last projection was set with `set_projection` or
     /// `mul_projection`, `None` will be returned.
@@ -389,6 +467,7 @@ impl Canvas {
     /// Finish drawing with this canvas and submit all the draw calls.
     #[inline]
     pub fn finish(mut self, gfx: &mut impl HasMut<GraphicsContext>) -> GameResult {
+        let _scope = crate::timer::scope("canvas finish");
         let gfx = gfx.retrieve_mut();
         self.finalize(gfx)
     }
@@ -398,6 +477,47 @@ impl Canvas {
         &self.defaults
     }
 
+    /// Draws a `Text`, transforming each of its glyphs with `effect`.
+    ///
+    /// `effect` is called once per glyph with information about its position after alignment and wrapping,
+    /// allowing effects such as wavy or shaking text. Only the glyphs are transformed, the layout itself is
+    /// computed as usual. Color glyphs (see [`GraphicsContext::set_fallback_fonts`]) are not drawn by this method.
+    ///
+    /// ```rust,no_run
+    /// # use ggez::graphics::{Canvas, GlyphTransform, Text};
+    /// # fn draw(ctx: &ggez::Context, canvas: &mut Canvas, time: f32) -> ggez::GameResult {
+    /// let text = Text::new("Wavy text!");
+    /// canvas.draw_text_with(ctx, &text, [100., 100.], |glyph| GlyphTransform {
+    ///     offset: [0., (time * 5. + glyph.index as f32 * 0.5).sin() * 4.].into(),
+    ///     ..Default::default()
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_text_with(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        text: &Text,
+        param: impl Into<DrawParam>,
+        effect: impl FnMut(GlyphInfo) -> GlyphTransform,
+    ) -> GameResult {
+        let gfx = gfx.retrieve();
+        let mut param = param.into();
+
+        if let Transform::Values { dest, offset, .. } = &mut param.transform {
+            if offset.x > 0. || offset.y > 0. {
+                let bounds = text.measure(gfx)?;
+                dest.x -= offset.x * bounds.x;
+                dest.y -= offset.y * bounds.y;
+                *offset = mint::Point2 { x: 0., y: 0. };
+            }
+        }
+
+        let (glyphs, extras) = text.effect_glyphs(gfx, param, effect)?;
+        self.push_draw(Draw::PositionedText { glyphs, extras }, param);
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn push_draw(&mut self, draw: Draw, param: DrawParam) {
         self.draws.entry(param.z).or_default().push(DrawCommand {
@@ -408,6 +528,22 @@ impl Canvas {
     }
 
     fn finalize(&mut self, gfx: &mut GraphicsContext) -> GameResult {
+        let target_rect = Rect::new(
+            0.,
+            0.,
+            self.target.width() as f32,
+            self.target.height() as f32,
+        );
+
+        if self.frame {
+            // remembered for converting mouse positions, see `GraphicsContext::window_to_screen_coords`
+            gfx.screen_coords = Some(self.screen.unwrap_or_else(|| {
+                self.viewport
+                    .as_ref()
+                    .map_or(target_rect, |viewport| viewport.screen)
+            }));
+        }
+
         let mut canvas = if let Some(resolve) = &self.resolve {
             InternalCanvas::from_msaa(gfx, self.clear, &self.target, resolve)?
         } else {
@@ -435,6 +571,28 @@ impl Canvas {
             canvas.set_scissor_rect(state.scissor_rect);
         }
 
+        if let Some(FrameViewport {
+            pixels,
+            clear: Some(color),
+            ..
+        }) = self.viewport
+        {
+            // the target was cleared to the letterbox color, so fill in the actual clear color
+            canvas.set_blend_mode(BlendMode::REPLACE);
+            canvas.set_projection(screen_to_mat(target_rect));
+            canvas.draw_mesh(
+                &self.defaults.mesh,
+                &self.defaults.image,
+                DrawParam::new()
+                    .dest([pixels.x, pixels.y])
+                    .scale([pixels.w, pixels.h])
+                    .color(color),
+                false,
+            );
+            canvas.set_blend_mode(state.blend_mode);
+            canvas.set_projection(state.projection);
+        }
+
         for draws in self.draws.values() {
             for draw in draws {
                 // track state and apply to InternalCanvas if changed
@@ -491,6 +649,9 @@ impl Canvas {
                         scale,
                     } => canvas.draw_mesh_instances(mesh, instances, draw.param, *scale)?,
                     Draw::BoundedText { text } => canvas.draw_bounded_text(text, draw.param)?,
+                    Draw::PositionedText { glyphs, extras } => {
+                        canvas.draw_positioned_text(glyphs.clone(), extras.clone())
+                    }
                 }
             }
         }
@@ -501,6 +662,14 @@ impl Canvas {
     }
 }
 
+// The letterboxed area of a frame canvas drawing at a virtual resolution.
+#[derive(Debug, Copy, Clone)]
+struct FrameViewport {
+    pixels: Rect,
+    screen: Rect,
+    clear: Option<Color>,
+}
+
 #[derive(Debug, Clone)]
 struct DrawState {
     shader: Shader,
@@ -529,6 +698,10 @@ pub(crate) enum Draw {
     BoundedText {
         text: Text,
     },
+    PositionedText {
+        glyphs: Vec<glyph_brush::SectionGlyph>,
+        extras: Vec<Extra>,
+    },
 }
 
 // Stores *everything* you need to know to draw something.
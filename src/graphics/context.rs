@@ -10,13 +10,18 @@ use super::{
     image::{Image, ImageFormat},
     mesh::{Mesh, Vertex},
     sampler::{Sampler, SamplerCache},
-    text::FontData,
-    MeshData, ScreenImage,
+    text::{FontCacheConfig, FontCacheStats, FontData, Text},
+    window::{
+        drag_resize_window, select_monitor, set_window_opacity, MonitorInfo, PendingWindow,
+        ResizeDirection, SecondaryWindow, Taskbar, UserAttentionType, VideoMode, WindowHandle,
+        WindowId, WindowLevel,
+    },
+    Color, DrawParam, FitMode, MeshData, Rect, ScreenImage,
 };
 use crate::{
-    conf::{self, Backend, Conf, FullscreenType, WindowMode},
+    conf::{self, Backend, Conf, FullscreenType, NumSamples, WindowMode, WindowSetup},
     context::Has,
-    error::GameResult,
+    error::{GameResult, ResultExt},
     filesystem::{Filesystem, InternalClone},
     graphics::gpu::{bind_group::BindGroupLayoutBuilder, pipeline::RenderPipelineInfo},
     GameError,
@@ -24,7 +29,11 @@ use crate::{
 use ::image as imgcrate;
 use crevice::std140::AsStd140;
 use glyph_brush::FontId;
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::Path,
+    sync::Arc,
+};
 use typed_arena::Arena as TypedArena;
 use winit::{
     self,
@@ -35,8 +44,8 @@ pub(crate) struct FrameContext {
     pub cmd: wgpu::CommandEncoder,
     pub present: Image,
     pub arenas: FrameArenas,
-    pub frame: wgpu::SurfaceTexture,
-    pub frame_view: wgpu::TextureView,
+    // `None` while the window is minimized
+    pub frame: Option<(wgpu::SurfaceTexture, wgpu::TextureView)>,
 }
 
 #[derive(Default)]
@@ -51,18 +60,94 @@ pub(crate) struct FrameArenas {
 #[allow(missing_docs)]
 pub struct WgpuContext {
     pub instance: wgpu::Instance,
-    pub surface: wgpu::Surface,
+    pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
 }
 
+/// What the canvases drew in a frame, see [`GraphicsContext::frame_stats`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// How many draw calls were made on the GPU. Draws of the same mesh with
+    /// [`InstanceArray`](crate::graphics::InstanceArray)s are one draw call, as is each run of
+    /// text.
+    pub draw_calls: u32,
+    /// How many meshes, instances and glyphs were drawn.
+    pub instances: u32,
+}
+
+/// How frames are presented to the window, i.e. the vsync behaviour.
+/// See [`GraphicsContext::set_present_mode`].
+pub type PresentMode = wgpu::PresentMode;
+
+/// How the window surface composites with what's behind the window, see [`SurfaceConfigOverride`].
+pub type CompositeAlphaMode = wgpu::CompositeAlphaMode;
+
+/// Settings of the window surface beyond what the conf has, given to
+/// [`ContextBuilder::surface_config`](crate::ContextBuilder::surface_config), e.g. a float
+/// format for HDR or fewer frames queued for less input latency.
+///
+/// The settings left `None` are as they would be without the override. The format and alpha
+/// mode have to be ones the window surface supports, or building the context fails with an
+/// error listing those it does.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SurfaceConfigOverride {
+    /// The image format of the window surface, e.g. [`ImageFormat::Rgba16Float`], instead of
+    /// the one picked for [`WindowSetup::srgb`].
+    pub format: Option<ImageFormat>,
+    /// How the window surface composites with what's behind the window, instead of the one
+    /// picked for [`WindowMode::transparent`].
+    pub alpha_mode: Option<CompositeAlphaMode>,
+    /// How many frames may be queued for the GPU at most before the next one waits for the
+    /// oldest of them to be drawn, see [`GraphicsContext::set_desired_maximum_frame_latency`].
+    pub desired_maximum_frame_latency: Option<u32>,
+}
+
+/// How the window surface is configured, as returned by [`GraphicsContext::surface_config`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SurfaceConfig {
+    /// The image format of the window surface.
+    pub format: ImageFormat,
+    /// How the window surface composites with what's behind the window.
+    pub alpha_mode: CompositeAlphaMode,
+    /// How frames are presented to the window.
+    pub present_mode: PresentMode,
+    /// How many frames may be queued for the GPU, or `None` for as many as the graphics backend
+    /// queues on its own.
+    pub desired_maximum_frame_latency: Option<u32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct VirtualResolution {
+    pub size: (u32, u32),
+    pub mode: FitMode,
+}
+
+// The windowed state to return to when leaving fullscreen via `toggle_fullscreen`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct WindowedGeometry {
+    pub size: dpi::PhysicalSize<u32>,
+    pub position: Option<PhysicalPosition<i32>>,
+    pub maximized: bool,
+}
+
 /// A concrete graphics context for WGPU rendering.
+///
+/// In a context made by [`ContextBuilder::headless`](crate::ContextBuilder::headless) there is
+/// no window, and what would change it does nothing, or returns an error if it returns a result.
 #[allow(missing_debug_implementations)]
 pub struct GraphicsContext {
     pub(crate) wgpu: Arc<WgpuContext>,
 
-    pub(crate) window: winit::window::Window,
+    // `None` in a headless context, whose frame is `headless_size`
+    pub(crate) window: Option<Arc<winit::window::Window>>,
+    headless_size: dpi::PhysicalSize<u32>,
+    // `None` in a headless context and while the app is suspended
+    surface: Option<wgpu::Surface>,
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
+    // the submissions of the frames queued for the GPU, which are only kept with a latency limit
+    max_frame_latency: Option<u32>,
+    frames_in_flight: VecDeque<wgpu::SubmissionIndex>,
 
     pub(crate) bind_group_cache: BindGroupCache,
     pub(crate) pipeline_cache: PipelineCache,
@@ -75,8 +160,30 @@ pub struct GraphicsContext {
     pub(crate) frame_msaa_image: Option<Image>,
 
     pub(crate) fcx: Option<FrameContext>,
+    // what the frame in progress drew so far, and what the last one drew
+    pub(crate) drawing_stats: FrameStats,
+    last_frame_stats: FrameStats,
     pub(crate) text: TextRenderer,
     pub(crate) fonts: HashMap<String, FontId>,
+    pub(crate) screen_coords: Option<Rect>,
+    pub(crate) virtual_resolution: Option<VirtualResolution>,
+    pub(crate) letterbox_color: Color,
+    pub(crate) video_mode: Option<VideoMode>,
+    pub(crate) surface_outdated: bool,
+    pub(crate) resize_increments: Option<dpi::LogicalSize<f32>>,
+    pub(crate) windowed_geometry: Option<WindowedGeometry>,
+    pub(crate) window_opacity: f32,
+    pub(crate) attention_requested: bool,
+    pub(crate) taskbar_progress: Option<f32>,
+    pub(crate) taskbar: Taskbar,
+    pub(crate) reported_size: dpi::PhysicalSize<u32>,
+    pub(crate) minimized: bool,
+    pub(crate) maximized: bool,
+    pub(crate) reported_minimized: bool,
+    pub(crate) reported_maximized: bool,
+    pub(crate) windows: Vec<SecondaryWindow>,
+    pub(crate) pending_windows: Vec<PendingWindow>,
+    next_window_id: u32,
     pub(crate) staging_belt: wgpu::util::StagingBelt,
     pub(crate) uniform_arena: GrowingBufferArena,
 
@@ -93,137 +200,269 @@ pub struct GraphicsContext {
 }
 
 impl GraphicsContext {
+    // Async as the adapter and device are only handed out asynchronously on the web, and
+    // blocked on elsewhere.
     #[allow(unsafe_code)]
-    pub(crate) fn new(
-        event_loop: &winit::event_loop::EventLoop<()>,
+    pub(crate) async fn new(
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
         conf: &Conf,
+        surface_override: &SurfaceConfigOverride,
         filesystem: &Filesystem,
     ) -> GameResult<Self> {
-        if conf.backend == Backend::All {
-            match Self::new_from_instance(
-                wgpu::Instance::new(wgpu::Backends::PRIMARY),
-                event_loop,
-                conf,
-                filesystem,
-            ) {
-                Ok(o) => Ok(o),
-                Err(GameError::GraphicsInitializationError) => {
-                    println!(
-                        "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
-                    );
-                    warn!(
-                        "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
-                    );
-
-                    Self::new_from_instance(
-                        wgpu::Instance::new(wgpu::Backends::SECONDARY),
-                        event_loop,
-                        conf,
-                        filesystem,
-                    )
-                }
-                Err(e) => Err(e),
+        let backends = match conf.backend {
+            Backend::All => {
+                return Self::new_any_backend(event_loop, conf, surface_override, filesystem).await
             }
-        } else {
-            let instance = wgpu::Instance::new(match conf.backend {
-                Backend::All => unreachable!(),
-                Backend::OnlyPrimary => wgpu::Backends::PRIMARY,
-                Backend::Vulkan => wgpu::Backends::VULKAN,
-                Backend::Metal => wgpu::Backends::METAL,
-                Backend::Dx12 => wgpu::Backends::DX12,
-                Backend::Dx11 => wgpu::Backends::DX11,
-                Backend::Gl => wgpu::Backends::GL,
-                Backend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
-            });
-
-            Self::new_from_instance(instance, event_loop, conf, filesystem)
+            Backend::OnlyPrimary => wgpu::Backends::PRIMARY,
+            Backend::Vulkan => wgpu::Backends::VULKAN,
+            Backend::Metal => wgpu::Backends::METAL,
+            Backend::Dx12 => wgpu::Backends::DX12,
+            Backend::Dx11 => wgpu::Backends::DX11,
+            Backend::Gl => wgpu::Backends::GL,
+            Backend::BrowserWebGpu => wgpu::Backends::BROWSER_WEBGPU,
+        };
+        match Self::new_from_instance(
+            wgpu::Instance::new(backends),
+            event_loop,
+            conf,
+            surface_override,
+            filesystem,
+        )
+        .await
+        {
+            Err(GameError::GraphicsInitializationError) if !conf.adapter.strict => {
+                warn!(
+                    "Failed to initialize graphics with the {:?} backend, trying the others",
+                    conf.backend
+                );
+                Self::new_any_backend(event_loop, conf, surface_override, filesystem).await
+            }
+            result => result,
         }
     }
 
-    #[allow(unsafe_code)]
-    pub(crate) fn new_from_instance(
-        instance: wgpu::Instance,
-        event_loop: &winit::event_loop::EventLoop<()>,
+    // Tries the primary backends, and then the secondary ones.
+    async fn new_any_backend(
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
         conf: &Conf,
+        surface_override: &SurfaceConfigOverride,
         filesystem: &Filesystem,
     ) -> GameResult<Self> {
-        let mut window_builder = winit::window::WindowBuilder::new()
-            .with_title(conf.window_setup.title.clone())
-            .with_inner_size(conf.window_mode.actual_size().unwrap())
-            .with_resizable(conf.window_mode.resizable)
-            .with_visible(conf.window_mode.visible)
-            .with_transparent(conf.window_mode.transparent);
-
-        #[cfg(target_os = "windows")]
+        match Self::new_from_instance(
+            wgpu::Instance::new(wgpu::Backends::PRIMARY),
+            event_loop,
+            conf,
+            surface_override,
+            filesystem,
+        )
+        .await
         {
-            use winit::platform::windows::WindowBuilderExtWindows;
-            window_builder = window_builder.with_drag_and_drop(false);
+            Ok(o) => Ok(o),
+            Err(GameError::GraphicsInitializationError) => {
+                println!(
+                    "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
+                );
+                warn!(
+                    "Failed to initialize graphics, trying secondary backends.. Please mention this if you encounter any bugs!"
+                );
+
+                Self::new_from_instance(
+                    wgpu::Instance::new(wgpu::Backends::SECONDARY),
+                    event_loop,
+                    conf,
+                    surface_override,
+                    filesystem,
+                )
+                .await
+            }
+            Err(e) => Err(e),
         }
+    }
 
-        window_builder = if !conf.window_setup.icon.is_empty() {
-            let icon = load_icon(conf.window_setup.icon.as_ref(), filesystem)?;
-            window_builder.with_window_icon(Some(icon))
-        } else {
-            window_builder
+    #[allow(unsafe_code)]
+    pub(crate) async fn new_from_instance(
+        instance: wgpu::Instance,
+        event_loop: Option<&winit::event_loop::EventLoop<()>>,
+        conf: &Conf,
+        surface_override: &SurfaceConfigOverride,
+        filesystem: &Filesystem,
+    ) -> GameResult<Self> {
+        // without an event loop there is no window, and the frame is only drawn to
+        let (window, surface) = match event_loop {
+            Some(event_loop) => {
+                let window_builder =
+                    window_builder(&conf.window_mode, &conf.window_setup, filesystem)?;
+                let window = Arc::new(window_builder.build(event_loop)?);
+                // the position was given to the builder too, so the window doesn't jump there
+                let _ = place_window(&window, &conf.window_mode);
+                // on Android the window only has a native window once the app is first
+                // resumed, when the surface is made, see `Context::from_conf`
+                #[cfg(target_os = "android")]
+                let surface = None;
+                #[cfg(not(target_os = "android"))]
+                let surface = Some(unsafe { instance.create_surface(&*window) });
+                (Some(window), surface)
+            }
+            None => (None, None),
         };
+        let maximized = window.as_ref().is_some_and(|window| window.is_maximized());
 
-        let window = window_builder.build(event_loop)?;
-        let surface = unsafe { instance.create_surface(&window) };
-
-        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        }))
+        let power_preference = match conf.adapter.power_preference {
+            conf::PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            conf::PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        };
+        let named = match &conf.adapter.name {
+            Some(name) => {
+                match named_adapter(&instance, surface.as_ref(), name, power_preference) {
+                    Some(adapter) => Some(adapter),
+                    None if conf.adapter.strict => {
+                        warn!("There is no graphics adapter named like {:?}", name);
+                        return Err(GameError::GraphicsInitializationError);
+                    }
+                    None => {
+                        warn!(
+                            "There is no graphics adapter named like {:?}, using another one",
+                            name
+                        );
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        let request_adapter = |force_fallback_adapter| {
+            instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference,
+                force_fallback_adapter,
+                compatible_surface: surface.as_ref(),
+            })
+        };
+        // a headless context may run where there is no GPU, e.g. in CI, but a software one
+        let adapter = match named {
+            Some(adapter) => Some(adapter),
+            None => match request_adapter(false).await {
+                None if surface.is_none() => request_adapter(true).await,
+                adapter => adapter,
+            },
+        }
         .ok_or(GameError::GraphicsInitializationError)?;
+        let info = adapter.get_info();
+        info!(
+            "Using the graphics adapter {:?} ({:?}, {:?}), with the driver {:?} {:?}",
+            info.name, info.backend, info.device_type, info.driver, info.driver_info
+        );
 
         // One instance is 96 bytes, and we allow 1 million of them, for a total of 96MB (default being 128MB).
         const MAX_INSTANCES: u32 = 1_000_000;
         const INSTANCE_BUFFER_SIZE: u32 = 96 * MAX_INSTANCES;
 
-        let (device, queue) = pollster::block_on(adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                features: wgpu::Features::default(),
-                limits: wgpu::Limits {
-                    // 1st: DrawParams
-                    // 2nd: Texture + Sampler
-                    // 3rd: InstanceArray
-                    // 4th: ShaderParams
-                    max_bind_groups: 4,
-                    // InstanceArray uses 2 storage buffers.
-                    max_storage_buffers_per_shader_stage: 2,
-                    max_storage_buffer_binding_size: INSTANCE_BUFFER_SIZE,
-                    max_texture_dimension_1d: 8192,
-                    max_texture_dimension_2d: 8192,
-                    ..wgpu::Limits::downlevel_webgl2_defaults()
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    features: wgpu::Features::default(),
+                    limits: wgpu::Limits {
+                        // 1st: DrawParams
+                        // 2nd: Texture + Sampler
+                        // 3rd: InstanceArray
+                        // 4th: ShaderParams
+                        max_bind_groups: 4,
+                        // InstanceArray uses 2 storage buffers.
+                        max_storage_buffers_per_shader_stage: 2,
+                        max_storage_buffer_binding_size: INSTANCE_BUFFER_SIZE,
+                        max_texture_dimension_1d: 8192,
+                        max_texture_dimension_2d: 8192,
+                        ..wgpu::Limits::downlevel_webgl2_defaults()
+                    },
                 },
-            },
-            None,
-        ))?;
+                None,
+            )
+            .await?;
 
         let wgpu = Arc::new(WgpuContext {
             instance,
-            surface,
+            adapter,
             device,
             queue,
         });
 
-        let size = window.inner_size();
+        let headless_size = conf.window_mode.actual_size()?.to_physical(1.0);
+        let size = window
+            .as_ref()
+            .map_or(headless_size, |window| window.inner_size());
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: wgpu.surface.get_supported_formats(&adapter)[0],
+            format: match (&surface, surface_override.format) {
+                (Some(surface), Some(format)) => {
+                    let supported = surface.get_supported_formats(&wgpu.adapter);
+                    if !supported.contains(&format) {
+                        return Err(GameError::RenderError(format!(
+                            "the window surface doesn't support the {:?} format, only {:?}",
+                            format, supported
+                        )));
+                    }
+                    format
+                }
+                (Some(surface), None) => supported_format(
+                    &surface.get_supported_formats(&wgpu.adapter),
+                    conf.window_setup.srgb,
+                ),
+                // the frame of a headless context is only drawn to
+                (None, Some(format)) => {
+                    let usages = wgpu
+                        .adapter
+                        .get_texture_format_features(format)
+                        .allowed_usages;
+                    if !usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+                        return Err(GameError::RenderError(format!(
+                            "the graphics adapter can't draw to images of the {:?} format",
+                            format
+                        )));
+                    }
+                    format
+                }
+                (None, None) if conf.window_setup.srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+                (None, None) => wgpu::TextureFormat::Rgba8Unorm,
+            },
             width: size.width,
             height: size.height,
-            present_mode: if conf.window_setup.vsync {
-                wgpu::PresentMode::Fifo
-            } else {
-                wgpu::PresentMode::Mailbox
+            present_mode: supported_present_mode(
+                &wgpu.adapter,
+                surface.as_ref(),
+                if conf.window_setup.vsync {
+                    PresentMode::Fifo
+                } else {
+                    PresentMode::Mailbox
+                },
+            ),
+            alpha_mode: match (&surface, surface_override.alpha_mode) {
+                (Some(surface), Some(alpha_mode)) => {
+                    let supported = surface.get_supported_alpha_modes(&wgpu.adapter);
+                    if !supported.contains(&alpha_mode) {
+                        return Err(GameError::RenderError(format!(
+                            "the window surface doesn't support the {:?} alpha mode, only {:?}",
+                            alpha_mode, supported
+                        )));
+                    }
+                    alpha_mode
+                }
+                (None, Some(alpha_mode)) => alpha_mode,
+                (_, None) => supported_alpha_mode(
+                    &wgpu.adapter,
+                    surface.as_ref(),
+                    conf.window_mode.transparent,
+                ),
             },
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
         };
+        if surface_override.desired_maximum_frame_latency == Some(0) {
+            return Err(GameError::RenderError(String::from(
+                "the maximum frame latency must be at least 1 frame",
+            )));
+        }
 
-        wgpu.surface.configure(&wgpu.device, &surface_config);
+        if let Some(surface) = &surface {
+            surface.configure(&wgpu.device, &surface_config);
+        }
 
         let mut bind_group_cache = BindGroupCache::new();
         let pipeline_cache = PipelineCache::new();
@@ -333,7 +572,11 @@ impl GraphicsContext {
             wgpu,
 
             window,
+            headless_size,
+            surface,
             surface_config,
+            max_frame_latency: surface_override.desired_maximum_frame_latency,
+            frames_in_flight: VecDeque::new(),
 
             bind_group_cache,
             pipeline_cache,
@@ -346,8 +589,29 @@ impl GraphicsContext {
             frame_msaa_image: None,
 
             fcx: None,
+            drawing_stats: FrameStats::default(),
+            last_frame_stats: FrameStats::default(),
             text,
             fonts: HashMap::new(),
+            screen_coords: None,
+            virtual_resolution: None,
+            letterbox_color: Color::BLACK,
+            video_mode: None,
+            surface_outdated: false,
+            resize_increments: None,
+            windowed_geometry: None,
+            window_opacity: 1.0,
+            attention_requested: false,
+            taskbar_progress: None,
+            taskbar: Taskbar::default(),
+            reported_size: size,
+            minimized: false,
+            maximized,
+            reported_minimized: false,
+            reported_maximized: maximized,
+            windows: Vec::new(),
+            pending_windows: Vec::new(),
+            next_window_id: 0,
             staging_belt,
             uniform_arena,
             draw_shader,
@@ -365,12 +629,17 @@ impl GraphicsContext {
         this.set_window_mode(&conf.window_mode)?;
 
         this.frame = Some(ScreenImage::new(&this, None, 1., 1., 1));
+        let samples = supported_samples(
+            &this.wgpu.adapter,
+            this.surface_config.format,
+            conf.window_setup.samples,
+        );
         this.frame_msaa = Some(ScreenImage::new(
             &this,
             None,
             1.,
             1.,
-            u8::from(conf.window_setup.samples).into(),
+            u8::from(samples).into(),
         ));
         this.update_frame_image();
 
@@ -382,12 +651,53 @@ impl GraphicsContext {
         Ok(this)
     }
 
+    /// Returns what the canvases drew in the last frame, e.g. to see how many draw calls a scene
+    /// takes. This doesn't count drawing the frame onto the window.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.last_frame_stats
+    }
+
     /// Returns a reference to the underlying WGPU context.
     #[inline]
     pub fn wgpu(&self) -> &WgpuContext {
         &self.wgpu
     }
 
+    /// Returns the WGPU surface of the main window, which there is none of in a headless
+    /// context and while the app is suspended, see
+    /// [`EventHandler::suspend_event`](crate::event::EventHandler::suspend_event).
+    #[inline]
+    pub fn surface(&self) -> Option<&wgpu::Surface> {
+        self.surface.as_ref()
+    }
+
+    /// Returns whether there is no window, in a context made by
+    /// [`ContextBuilder::headless`](crate::ContextBuilder::headless).
+    #[inline]
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    // The main window, or an error in a headless context.
+    pub(crate) fn main_window(&self) -> GameResult<&winit::window::Window> {
+        self.window.as_deref().ok_or_else(|| {
+            GameError::WindowError(String::from("there is no window in a headless context"))
+        })
+    }
+
+    // The size of the main window's drawable, or of the frame in a headless context.
+    pub(crate) fn inner_size(&self) -> dpi::PhysicalSize<u32> {
+        self.window
+            .as_ref()
+            .map_or(self.headless_size, |window| window.inner_size())
+    }
+
+    fn window_scale_factor(&self) -> f64 {
+        self.window
+            .as_ref()
+            .map_or(1.0, |window| window.scale_factor())
+    }
+
     /// Sets the image that will be presented to the screen at the end of the frame.
     pub fn present(&mut self, image: &Image) -> GameResult {
         if let Some(fcx) = &mut self.fcx {
@@ -403,39 +713,565 @@ impl GraphicsContext {
     /// Adds a new `font` with a given `name`.
     #[allow(unused_results)]
     pub fn add_font(&mut self, name: &str, font: FontData) {
-        let id = self.text.glyph_brush.borrow_mut().add_font(font.font);
+        let id = self.text.add_font(font.font);
         self.fonts.insert(name.to_string(), id);
     }
 
+    /// Sets the fonts, by name, that are used for characters missing from a text's font, tried in the given order.
+    ///
+    /// This is most commonly used to route emoji to a color emoji font. Color glyphs stored as bitmap strikes
+    /// (CBDT/CBLC and sbix, as used by Noto Color Emoji and Apple Color Emoji) are drawn in color, tinted only
+    /// by the alpha of the text color. Layered (COLR) and SVG glyphs are not supported in color.
+    /// Color glyphs are cached in an atlas of 1024x1024 pixels of their own, which starts over
+    /// when it's full.
+    pub fn set_fallback_fonts(&mut self, names: &[&str]) -> GameResult {
+        self.text.fallbacks = names
+            .iter()
+            .map(|name| {
+                self.fonts
+                    .get(*name)
+                    .copied()
+                    .ok_or_else(|| GameError::FontSelectError(name.to_string()))
+            })
+            .collect::<GameResult<_>>()?;
+        Ok(())
+    }
+
+    /// Returns statistics about the glyph cache used for text rendering.
+    ///
+    /// This is cheap enough to be queried (and displayed) every frame.
+    pub fn font_cache_stats(&self) -> FontCacheStats {
+        self.text.stats()
+    }
+
+    /// Returns the current configuration of the glyph cache.
+    pub fn font_cache_config(&self) -> FontCacheConfig {
+        self.text.cache_config
+    }
+
+    /// Configures the glyph cache used for text rendering.
+    ///
+    /// If the current cache size lies outside of the new limits the cache is rebuilt,
+    /// which means every glyph has to be rasterized again.
+    pub fn set_font_cache_config(&mut self, config: FontCacheConfig) -> GameResult {
+        let limit = self.wgpu.device.limits().max_texture_dimension_2d;
+        let (w, h) = config.initial_size;
+        let (max_w, max_h) = config.max_size;
+        if w == 0 || h == 0 || w > max_w || h > max_h {
+            return Err(GameError::RenderError(format!(
+                "invalid font cache size: initial size {}x{} must be non-zero and not exceed the maximum size {}x{}",
+                w, h, max_w, max_h
+            )));
+        }
+        if max_w > limit || max_h > limit {
+            return Err(GameError::RenderError(format!(
+                "font cache maximum size {}x{} exceeds the texture size limit of {}",
+                max_w, max_h, limit
+            )));
+        }
+
+        self.text.set_config(&self.wgpu.device, config);
+        Ok(())
+    }
+
+    /// Rasterizes the glyphs of `text` into the glyph cache ahead of time,
+    /// so drawing it for the first time doesn't cause a hitch.
+    ///
+    /// Useful during loading screens. Glyphs stay cached until the cache runs out of space.
+    pub fn warm_up(&mut self, text: &Text) -> GameResult {
+        self.text.queue(
+            &text.as_section(&self.text, &self.fonts, DrawParam::default())?,
+            text.hinting(),
+        );
+        let _ = self
+            .text
+            .process_queued(&self.wgpu.device, &self.wgpu.queue, None);
+        Ok(())
+    }
+
     /// Returns the size of the window’s underlying drawable in physical pixels as (width, height).
     pub fn drawable_size(&self) -> (f32, f32) {
-        let size = self.window.inner_size();
+        let size = self.inner_size();
         (size.width as f32, size.height as f32)
     }
 
+    /// Returns the size of the window's underlying drawable in logical pixels as (width, height),
+    /// i.e. [`GraphicsContext::drawable_size`] divided by [`GraphicsContext::scale_factor`].
+    ///
+    /// Laying out UI in logical pixels keeps it the same apparent size across monitors with different DPI.
+    pub fn window_logical_size(&self) -> (f32, f32) {
+        let size: dpi::LogicalSize<f32> = self.inner_size().to_logical(self.window_scale_factor());
+        (size.width, size.height)
+    }
+
+    /// Returns the scale factor of the window, i.e. the number of physical pixels per logical pixel.
+    ///
+    /// This changes when the window moves to a monitor with a different DPI, see
+    /// [`EventHandler::scale_factor_changed_event`](crate::event::EventHandler::scale_factor_changed_event).
+    /// It is 1 in a headless context.
+    pub fn scale_factor(&self) -> f32 {
+        self.window_scale_factor() as f32
+    }
+
+    /// Returns the screen coordinates of the last [`Canvas`](crate::graphics::Canvas) created with
+    /// `Canvas::from_frame` and finished, as set by `Canvas::set_screen_coordinates`.
+    ///
+    /// If that canvas used a custom projection, or no frame has been drawn yet, this is the
+    /// default coordinate system of one unit per physical pixel.
+    pub fn screen_coordinates(&self) -> Rect {
+        self.screen_coords.unwrap_or_else(|| self.window_viewport())
+    }
+
+    /// Converts a position in physical window pixels, such as the mouse position reported by
+    /// [`EventHandler`](crate::event::EventHandler) callbacks or [`MouseContext::position`](crate::input::mouse::MouseContext::position),
+    /// into the screen coordinates of the last frame canvas (see [`GraphicsContext::screen_coordinates`]).
+    ///
+    /// This takes the window's current inner size into account, so the result stays correct after
+    /// the window was resized or moved to a monitor with a different scale factor.
+    pub fn window_to_screen_coords(
+        &self,
+        physical: impl Into<mint::Point2<f32>>,
+    ) -> mint::Point2<f32> {
+        self.window_to_screen_coords_in(physical, self.screen_coordinates())
+    }
+
+    /// Converts a position in the screen coordinates of the last frame canvas into physical window pixels.
+    ///
+    /// This is the inverse of [`GraphicsContext::window_to_screen_coords`].
+    pub fn screen_to_window_coords(
+        &self,
+        point: impl Into<mint::Point2<f32>>,
+    ) -> mint::Point2<f32> {
+        self.screen_to_window_coords_in(point, self.screen_coordinates())
+    }
+
+    /// Like [`GraphicsContext::window_to_screen_coords`], but for an explicit `screen` coordinate
+    /// system instead of the one of the last frame canvas.
+    pub fn window_to_screen_coords_in(
+        &self,
+        physical: impl Into<mint::Point2<f32>>,
+        screen: Rect,
+    ) -> mint::Point2<f32> {
+        remap(physical.into(), self.window_viewport(), screen)
+    }
+
+    /// Like [`GraphicsContext::screen_to_window_coords`], but for an explicit `screen` coordinate
+    /// system instead of the one of the last frame canvas.
+    pub fn screen_to_window_coords_in(
+        &self,
+        point: impl Into<mint::Point2<f32>>,
+        screen: Rect,
+    ) -> mint::Point2<f32> {
+        remap(point.into(), screen, self.window_viewport())
+    }
+
+    // The area of the window, in physical pixels, the frame is displayed in.
+    fn window_viewport(&self) -> Rect {
+        let size = self.inner_size();
+        match &self.virtual_resolution {
+            Some(virtual_resolution) => virtual_resolution
+                .mode
+                .viewport(virtual_resolution.size, (size.width, size.height)),
+            None => Rect::new(0., 0., size.width as f32, size.height as f32),
+        }
+    }
+
+    /// Sets a virtual resolution of `width` x `height` that canvases created with
+    /// `Canvas::from_frame` draw at, independently of the actual window size.
+    ///
+    /// Such canvases start out with screen coordinates of `(0, 0, width, height)`, which are fit
+    /// into the window according to `mode`. Any space left over is filled with bars (see
+    /// [`GraphicsContext::set_letterbox_color`]) and clipped with a scissor rectangle. The mapping is
+    /// recomputed for every frame, so resizing the window needs no extra handling, and
+    /// [`GraphicsContext::window_to_screen_coords`] maps mouse positions into the virtual resolution.
+    pub fn set_virtual_resolution(&mut self, width: u32, height: u32, mode: FitMode) -> GameResult {
+        if width == 0 || height == 0 {
+            return Err(GameError::RenderError(format!(
+                "virtual resolution must be non-zero; actual values: {}, {}",
+                width, height
+            )));
+        }
+        self.virtual_resolution = Some(VirtualResolution {
+            size: (width, height),
+            mode,
+        });
+        Ok(())
+    }
+
+    /// Removes the virtual resolution, going back to drawing at the window's resolution.
+    pub fn reset_virtual_resolution(&mut self) {
+        self.virtual_resolution = None;
+    }
+
+    /// Returns the virtual resolution as set by [`GraphicsContext::set_virtual_resolution`], if any.
+    pub fn virtual_resolution(&self) -> Option<(u32, u32, FitMode)> {
+        self.virtual_resolution.map(|virtual_resolution| {
+            let (w, h) = virtual_resolution.size;
+            (w, h, virtual_resolution.mode)
+        })
+    }
+
+    /// Sets the color of the bars around a letterboxed virtual resolution. Defaults to black.
+    ///
+    /// The bars are only cleared when the frame canvas is created with a clear color.
+    pub fn set_letterbox_color(&mut self, color: Color) {
+        self.letterbox_color = color;
+    }
+
     /// Sets the window size (in physical pixels) / resolution to the specified width and height.
     ///
+    /// In windowed mode, sizes outside of the [`WindowMode`]'s min and max dimensions are
+    /// clamped into them, with a warning logged.
+    ///
     /// Note:   These dimensions are only interpreted as resolutions in true fullscreen mode.
     ///         If the selected resolution is not supported this function will return an Error.
     pub fn set_drawable_size(&mut self, width: f32, height: f32) -> GameResult {
         self.set_mode(self.window_mode.dimensions(width, height))
     }
 
+    /// Creates a new secondary window, with its own surface to draw to using `Canvas::from_window_frame`.
+    ///
+    /// The window is created the next time the event loop runs, which is before the next call
+    /// to `draw` when called from `update`. Events of the window are delivered to
+    /// [`EventHandler::secondary_window_event`](crate::event::EventHandler::secondary_window_event).
+    /// Secondary windows are always windowed and don't use multisampling; `mode.fullscreen_type`
+    /// and `setup.samples` are ignored.
+    pub fn create_window(&mut self, mode: WindowMode, setup: WindowSetup) -> WindowId {
+        let id = WindowId(self.next_window_id);
+        self.next_window_id += 1;
+        self.pending_windows.push(PendingWindow { id, mode, setup });
+        id
+    }
+
+    /// Closes a secondary window. This does not affect the main window or any other window.
+    pub fn close_window(&mut self, id: WindowId) -> GameResult {
+        if let Some(index) = self.windows.iter().position(|window| window.id == id) {
+            let _ = self.windows.remove(index);
+            Ok(())
+        } else if let Some(index) = self
+            .pending_windows
+            .iter()
+            .position(|window| window.id == id)
+        {
+            let _ = self.pending_windows.remove(index);
+            Ok(())
+        } else {
+            Err(GameError::WindowError(format!(
+                "no open window with {:?}",
+                id
+            )))
+        }
+    }
+
+    /// Returns the Winit window of a secondary window, if it has been created and not been closed yet.
+    pub fn secondary_window(&self, id: WindowId) -> Option<&crate::winit::window::Window> {
+        self.windows
+            .iter()
+            .find(|window| window.id == id)
+            .map(|window| &window.window)
+    }
+
+    /// Returns the ids of all secondary windows that have been created and not been closed yet.
+    pub fn secondary_windows(&self) -> Vec<WindowId> {
+        self.windows.iter().map(|window| window.id).collect()
+    }
+
+    /// Creates the windows requested with [`GraphicsContext::create_window`].
+    ///
+    /// The only situation you need to call this in is when you are rolling your own event loop.
+    #[allow(unsafe_code)]
+    pub fn create_pending_windows(
+        &mut self,
+        target: &winit::event_loop::EventLoopWindowTarget<()>,
+    ) -> GameResult {
+        while !self.pending_windows.is_empty() {
+            let pending = self.pending_windows.remove(0);
+            let window = window_builder(&pending.mode, &pending.setup, &self.fs)?.build(target)?;
+            let surface = unsafe { self.wgpu.instance.create_surface(&window) };
+
+            let size = window.inner_size();
+            let surface_config = wgpu::SurfaceConfiguration {
+                width: size.width.max(1),
+                height: size.height.max(1),
+                present_mode: supported_present_mode(
+                    &self.wgpu.adapter,
+                    Some(&surface),
+                    if pending.setup.vsync {
+                        PresentMode::Fifo
+                    } else {
+                        PresentMode::Mailbox
+                    },
+                ),
+                alpha_mode: supported_alpha_mode(
+                    &self.wgpu.adapter,
+                    Some(&surface),
+                    pending.mode.transparent,
+                ),
+                ..self.surface_config.clone()
+            };
+            surface.configure(&self.wgpu.device, &surface_config);
+
+            let frame = Image::new_canvas_image(
+                self,
+                surface_config.format,
+                surface_config.width,
+                surface_config.height,
+                1,
+            );
+
+            self.windows.push(SecondaryWindow {
+                id: pending.id,
+                window,
+                surface,
+                surface_config,
+                frame,
+                cursor: mint::Point2 { x: 0., y: 0. },
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn secondary_window_mut(
+        &mut self,
+        id: winit::window::WindowId,
+    ) -> Option<&mut SecondaryWindow> {
+        self.windows
+            .iter_mut()
+            .find(|window| window.window.id() == id)
+    }
+
+    pub(crate) fn resize_secondary_window(&mut self, id: winit::window::WindowId) {
+        let index = match self
+            .windows
+            .iter()
+            .position(|window| window.window.id() == id)
+        {
+            Some(index) => index,
+            None => return,
+        };
+        let _ = self.wgpu.device.poll(wgpu::Maintain::Wait);
+
+        let size = self.windows[index].window.inner_size();
+        let mut surface_config = self.windows[index].surface_config.clone();
+        surface_config.width = size.width.max(1);
+        surface_config.height = size.height.max(1);
+        self.windows[index]
+            .surface
+            .configure(&self.wgpu.device, &surface_config);
+
+        let frame = Image::new_canvas_image(
+            self,
+            surface_config.format,
+            surface_config.width,
+            surface_config.height,
+            1,
+        );
+        let window = &mut self.windows[index];
+        window.surface_config = surface_config;
+        window.frame = frame;
+    }
+
+    /// Returns the present mode currently used by the window surface.
+    ///
+    /// This may differ from the one requested, see [`GraphicsContext::set_present_mode`].
+    #[inline]
+    pub fn present_mode(&self) -> PresentMode {
+        self.surface_config.present_mode
+    }
+
+    /// Returns how the window surface is configured, including what was set with
+    /// [`ContextBuilder::surface_config`](crate::ContextBuilder::surface_config).
+    pub fn surface_config(&self) -> SurfaceConfig {
+        SurfaceConfig {
+            format: self.surface_config.format,
+            alpha_mode: self.surface_config.alpha_mode,
+            present_mode: self.surface_config.present_mode,
+            desired_maximum_frame_latency: self.max_frame_latency,
+        }
+    }
+
+    /// Sets how many frames may be queued for the GPU at most, or `None` to leave it to the
+    /// graphics backend, as by default. With a limit, ending a frame waits for the GPU to draw
+    /// the frames queued before it until there are fewer than that many, which lowers the latency
+    /// between input and what's shown, e.g. for rhythm games, at the cost of a steady frame rate
+    /// when frames take the GPU long.
+    ///
+    /// This takes effect right away, and unlike the other surface settings, doesn't reconfigure
+    /// the surface, so it's cheap to change while the game runs. Returns an error for a limit of 0.
+    pub fn set_desired_maximum_frame_latency(&mut self, latency: Option<u32>) -> GameResult {
+        if latency == Some(0) {
+            return Err(GameError::RenderError(String::from(
+                "the maximum frame latency must be at least 1 frame",
+            )));
+        }
+        self.max_frame_latency = latency;
+        if latency.is_none() {
+            self.frames_in_flight.clear();
+        }
+        Ok(())
+    }
+
+    /// Returns the present modes supported by the window surface, which are none in a headless
+    /// context.
+    pub fn available_present_modes(&self) -> Vec<PresentMode> {
+        match &self.surface {
+            Some(surface) => surface.get_supported_present_modes(&self.wgpu.adapter),
+            None => Vec::new(),
+        }
+    }
+
+    /// Sets how frames are presented to the window, taking effect on the next frame. This is what
+    /// the `vsync` conf setting maps onto: `true` requests [`PresentMode::Fifo`], `false` [`PresentMode::Mailbox`].
+    ///
+    /// - `Fifo` is vsync and is supported everywhere.
+    /// - `FifoRelaxed` is vsync, but frames arriving late are presented immediately (tearing).
+    ///   Falls back to `Fifo`.
+    /// - `Mailbox` renders without waiting for vsync, only presenting the most recent frame, without tearing.
+    ///   Falls back to `Immediate`, then `Fifo`.
+    /// - `Immediate` presents frames right away, possibly tearing. Falls back to `Mailbox`, then `Fifo`.
+    ///
+    /// The present mode actually used can be queried with [`GraphicsContext::present_mode`].
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> GameResult {
+        let mode = supported_present_mode(&self.wgpu.adapter, self.surface.as_ref(), mode);
+        if mode != self.surface_config.present_mode {
+            self.surface_config.present_mode = mode;
+            // reconfiguring the surface while a frame is in flight would invalidate it
+            self.surface_outdated = true;
+        }
+        Ok(())
+    }
+
+    /// Sets the opacity of the whole window, clamped to `0.0..=1.0`, e.g. for ghosted overlays.
+    ///
+    /// Unlike a [transparent](crate::conf::WindowMode::transparent()) window this fades everything uniformly,
+    /// including decorations, and doesn't need any changes to rendering. Supported on Windows, macOS and
+    /// X11 (with a compositing window manager); an error is returned on other platforms, including Wayland.
+    pub fn set_window_opacity(&mut self, opacity: f32) -> GameResult {
+        let opacity = opacity.clamp(0.0, 1.0);
+        set_window_opacity(self.main_window()?, opacity)?;
+        self.window_opacity = opacity;
+        Ok(())
+    }
+
+    /// Returns the opacity of the window, as set by [`GraphicsContext::set_window_opacity`].
+    #[inline]
+    pub fn window_opacity(&self) -> f32 {
+        self.window_opacity
+    }
+
+    /// Lets the user move the window with the mouse, as if its title bar was dragged.
+    ///
+    /// This is meant for borderless windows with a custom title bar and must be called while the left
+    /// mouse button is held down, e.g. from [`EventHandler::mouse_button_down_event`](crate::event::EventHandler::mouse_button_down_event);
+    /// platforms ignore it otherwise. Returns an error on iOS, Android and the web.
+    pub fn begin_window_drag(&self) -> GameResult {
+        self.main_window()?
+            .drag_window()
+            .map_err(|e| GameError::WindowError(e.to_string()))
+    }
+
+    /// Lets the user resize the window with the mouse from the given edge or corner, as if its border was dragged.
+    ///
+    /// Like [`GraphicsContext::begin_window_drag`] this must be called while the left mouse button is held down.
+    /// Supported on Windows and X11; an error is returned on other platforms, including Wayland and macOS.
+    pub fn begin_window_resize(&self, direction: ResizeDirection) -> GameResult {
+        drag_resize_window(self.main_window()?, direction)
+    }
+
+    /// Asks the user to pay attention to the window, e.g. when it's their turn in a multiplayer game.
+    /// This flashes the taskbar entry on Windows and X11, and bounces the dock icon on macOS.
+    /// It has no effect if the window already has focus.
+    ///
+    /// The request is cleared automatically once the window gains focus.
+    pub fn request_user_attention(&mut self, attention: UserAttentionType) {
+        self.attention_requested = true;
+        if let Some(window) = &self.window {
+            window.request_user_attention(Some(attention));
+        }
+    }
+
+    /// Shows a progress bar on the window's taskbar entry, with `progress` in `0.0..=1.0`. `None` removes it.
+    ///
+    /// This is the taskbar button on Windows, the dock icon on macOS, and the launcher entry on Linux
+    /// desktops following Unity's LauncherEntry API, like KDE Plasma or GNOME with Dash to Dock, which
+    /// find the app by a desktop file named after its executable. Being purely cosmetic, it never
+    /// fails: where it can't be shown, like on the web or without a desktop shell listening, that's
+    /// noted in the log, and the value is still stored (see [`GraphicsContext::taskbar_progress`]).
+    pub fn set_taskbar_progress(&mut self, progress: Option<f32>) {
+        let progress = progress.map(|progress| progress.clamp(0.0, 1.0));
+        if progress == self.taskbar_progress {
+            return;
+        }
+        if let Some(window) = &self.window {
+            if let Err(e) = self.taskbar.set_progress(window, progress) {
+                warn!("{}", e);
+            }
+        }
+        self.taskbar_progress = progress;
+    }
+
+    /// Returns the taskbar progress set with [`GraphicsContext::set_taskbar_progress`].
+    #[inline]
+    pub fn taskbar_progress(&self) -> Option<f32> {
+        self.taskbar_progress
+    }
+
+    /// Sets whether the window receives mouse input (`true`, the default) or lets it pass through
+    /// to whatever is behind it (`false`). Keyboard input is received either way while the window is focused.
+    ///
+    /// Combined with a [transparent](crate::conf::WindowMode::transparent()) window this allows for click-through overlays.
+    /// Click-through is not supported on iOS, Android, the web and Wayland (X11, Windows and macOS work);
+    /// an error is returned there instead.
+    pub fn set_cursor_hittest(&self, hittest: bool) -> GameResult {
+        self.main_window()?
+            .set_cursor_hittest(hittest)
+            .map_err(|e| GameError::WindowError(e.to_string()))
+    }
+
     /// Sets the window title.
     pub fn set_window_title(&self, title: &str) {
-        self.window.set_title(title);
+        if let Some(window) = &self.window {
+            window.set_title(title);
+        }
     }
 
-    /// Returns the position of the system window, including the outer frame.
+    /// Returns the position of the system window's top-left corner, including the outer frame.
+    ///
+    /// The position is in physical pixels on the virtual desktop spanning all monitors, so it is relative
+    /// to the primary monitor's top-left corner and may be negative for monitors left of or above it
+    /// (see [`MonitorInfo::position`]).
+    ///
+    /// Wayland doesn't expose global window positions, so an error is returned there.
     pub fn window_position(&self) -> GameResult<PhysicalPosition<i32>> {
-        self.window
+        self.main_window()?
             .outer_position()
             .map_err(|e| GameError::WindowError(e.to_string()))
     }
 
-    /// Sets the window position.
-    pub fn set_window_position(&self, position: impl Into<winit::dpi::Position>) -> GameResult {
-        self.window.set_outer_position(position);
+    /// Sets the window position, in the same coordinate space as [`GraphicsContext::window_position`].
+    ///
+    /// Wayland doesn't allow windows to position themselves, so an error is returned there.
+    pub fn set_window_position(
+        &self,
+        position: impl Into<crate::winit::dpi::Position>,
+    ) -> GameResult {
+        let window = self.main_window()?;
+        if on_wayland(window) {
+            return Err(GameError::WindowError(String::from(
+                "window positions are not supported on Wayland",
+            )));
+        }
+        window.set_outer_position(position);
+        Ok(())
+    }
+
+    /// Centers the window on the monitor it currently is on. This has no effect in fullscreen.
+    pub fn center_window(&self) -> GameResult {
+        let window = self.main_window()?;
+        let monitor = select_monitor(window, None).ok_or_else(|| {
+            GameError::WindowError(String::from("no monitor available to center the window on"))
+        })?;
+        center_on(window, &monitor);
         Ok(())
     }
 
@@ -443,26 +1279,150 @@ impl GraphicsContext {
     /// including borders, titlebar, etc.
     /// Returns zeros if the window doesn't exist.
     pub fn size(&self) -> (f32, f32) {
-        let size = self.window.outer_size();
-        (size.width as f32, size.height as f32)
+        match &self.window {
+            Some(window) => {
+                let size = window.outer_size();
+                (size.width as f32, size.height as f32)
+            }
+            None => (0.0, 0.0),
+        }
     }
 
     /// Returns an iterator providing all resolutions supported by the current monitor.
-    pub fn supported_resolutions(&self) -> impl Iterator<Item = winit::dpi::PhysicalSize<u32>> {
+    pub fn supported_resolutions(
+        &self,
+    ) -> impl Iterator<Item = crate::winit::dpi::PhysicalSize<u32>> {
         self.window
-            .current_monitor()
-            .unwrap()
-            .video_modes()
+            .as_deref()
+            .and_then(|window| select_monitor(window, None))
+            .into_iter()
+            .flat_map(|monitor| monitor.video_modes())
             .map(|vm| vm.size())
     }
 
-    /// Returns a reference to the Winit window.
+    /// Returns information about all monitors connected to the system.
+    ///
+    /// The index of a monitor in this list is used to select it, e.g. with [`GraphicsContext::set_fullscreen_on`].
+    pub fn monitors(&self) -> Vec<MonitorInfo> {
+        match &self.window {
+            Some(window) => window
+                .available_monitors()
+                .enumerate()
+                .map(|(index, monitor)| MonitorInfo::new(index, &monitor))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Sets the window to fullscreen (or back) on the monitor with the given index.
+    ///
+    /// If there is no such monitor, the primary monitor is used.
+    pub fn set_fullscreen_on(
+        &mut self,
+        fullscreen: conf::FullscreenType,
+        monitor: usize,
+    ) -> GameResult {
+        let window_mode = self
+            .window_mode
+            .fullscreen_type(fullscreen)
+            .monitor(monitor);
+        self.set_mode(window_mode)
+    }
+
+    /// Switches to exclusive fullscreen on the monitor with the given index, using exactly the given video
+    /// mode (one of [`MonitorInfo::video_modes`]), e.g. to select a specific refresh rate.
+    ///
+    /// The surface is reconfigured for the new resolution, and a resize event is emitted once the
+    /// window has its new size. See [`WindowMode::minimize_on_focus_loss`](crate::conf::WindowMode::minimize_on_focus_loss)
+    /// for what happens when the window loses focus.
+    pub fn set_mode_exclusive(&mut self, monitor: usize, video_mode: VideoMode) -> GameResult {
+        let monitor_handle = self
+            .main_window()?
+            .available_monitors()
+            .nth(monitor)
+            .ok_or_else(|| {
+                GameError::WindowError(format!("there is no monitor with index {}", monitor))
+            })?;
+        if !monitor_handle
+            .video_modes()
+            .any(|v_mode| VideoMode::from(v_mode) == video_mode)
+        {
+            return Err(GameError::WindowError(format!(
+                "video mode {:?} is not supported by monitor {}",
+                video_mode, monitor
+            )));
+        }
+
+        let previous = self.video_mode.replace(video_mode);
+        let window_mode = self
+            .window_mode
+            .fullscreen_type(FullscreenType::True)
+            .dimensions(video_mode.size.width as f32, video_mode.size.height as f32)
+            .monitor(monitor);
+        let result = self.set_mode(window_mode);
+        if result.is_err() {
+            self.video_mode = previous;
+        }
+        result
+    }
+
+    /// Returns the video mode currently used in exclusive fullscreen, or `None` when not in exclusive fullscreen.
+    pub fn current_video_mode(&self) -> Option<VideoMode> {
+        match self.window.as_ref().and_then(|window| window.fullscreen()) {
+            Some(winit::window::Fullscreen::Exclusive(v_mode)) => Some(v_mode.into()),
+            _ => None,
+        }
+    }
+
+    /// Centers the window on the monitor with the given index.
+    ///
+    /// If there is no such monitor, the primary monitor is used. This has no effect in fullscreen.
+    pub fn center_on_monitor(&self, monitor: usize) -> GameResult {
+        let window = self.main_window()?;
+        let monitor = select_monitor(window, Some(monitor)).ok_or_else(|| {
+            GameError::WindowError(String::from("no monitor available to center the window on"))
+        })?;
+        center_on(window, &monitor);
+        Ok(())
+    }
+
+    /// Returns the platform's handles of the main window, for native APIs which need them, like
+    /// dialogs parented to the window, see [`WindowHandle`] for how long they're valid.
+    ///
+    /// Returns an error in a headless context, and while the app is
+    /// [suspended](crate::event::EventHandler::suspend_event), as there's no native window on
+    /// mobile platforms then.
+    pub fn window_handle(&self) -> GameResult<WindowHandle<'_>> {
+        let window = self.main_window()?;
+        if self.surface.is_none() {
+            return Err(GameError::WindowError(String::from(
+                "the window has no native handle while the app is suspended",
+            )));
+        }
+        Ok(WindowHandle { window })
+    }
+
+    /// Returns a reference to the Winit window, of the version re-exported as
+    /// [`ggez::winit`](crate::winit), e.g. for egui's or accesskit's winit integration.
+    ///
+    /// This accessor is kept across versions, but what the window is follows the winit ggez
+    /// is built with, as the [re-export](crate::winit) does.
+    ///
+    /// # Panics
+    ///
+    /// In a headless context, where there is no window, see [`GraphicsContext::is_headless`].
     #[inline]
-    pub fn window(&self) -> &winit::window::Window {
-        &self.window
+    pub fn window(&self) -> &crate::winit::window::Window {
+        self.window
+            .as_deref()
+            .expect("there is no window in a headless context")
     }
 
-    /// Sets the window icon. `None` for path removes the icon.
+    /// Sets the window icon, loading it from an image file through the filesystem.
+    /// `None` for path removes the icon.
+    ///
+    /// On platforms without runtime window icons (see [`GraphicsContext::set_window_icon_rgba`]),
+    /// the image is still loaded, but only a warning is logged. Returns an error if it can't be.
     pub fn set_window_icon<P: AsRef<Path>>(
         &self,
         filesystem: &impl Has<Filesystem>,
@@ -473,22 +1433,301 @@ impl GraphicsContext {
             Some(p) => Some(load_icon(p.as_ref(), filesystem)?),
             None => None,
         };
-        self.window.set_window_icon(icon);
+        self.apply_window_icon(icon)
+    }
+
+    /// Sets the window icon to the contents of `image`, e.g. one rendered at runtime.
+    ///
+    /// This reads the pixels back from the GPU, which is slow. Only `Rgba8` and `Bgra8` images are supported.
+    pub fn set_window_icon_image(&self, image: &Image) -> GameResult {
+        let mut pixels = image.to_pixels(self)?;
+        match image.format() {
+            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {}
+            ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            format => {
+                return Err(GameError::RenderError(format!(
+                    "cannot use an image with format {:?} as the window icon",
+                    format
+                )))
+            }
+        }
+        self.set_window_icon_rgba(&pixels, image.width(), image.height())
+    }
+
+    /// Sets the window icon from raw, non-premultiplied RGBA8 pixels, avoiding a GPU readback.
+    ///
+    /// Runtime window icons are not supported on macOS (where the dock icon comes from the application
+    /// bundle), iOS, Android, the web and Wayland; a warning is logged there instead. Returns an
+    /// error if the pixels aren't `width` by `height` RGBA8 ones.
+    pub fn set_window_icon_rgba(&self, rgba: &[u8], width: u32, height: u32) -> GameResult {
+        let icon = winit::window::Icon::from_rgba(rgba.to_vec(), width, height)
+            .map_err(|e| GameError::ResourceLoadError(format!("Could not load icon: {:?}", e)))?;
+        self.apply_window_icon(Some(icon))
+    }
+
+    fn apply_window_icon(&self, icon: Option<winit::window::Icon>) -> GameResult {
+        #[cfg(any(
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "android",
+            target_arch = "wasm32"
+        ))]
+        {
+            let _ = icon;
+            warn!("Window icons cannot be set at runtime on this platform; ignoring the icon");
+            Ok(())
+        }
+
+        #[cfg(not(any(
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "android",
+            target_arch = "wasm32"
+        )))]
+        {
+            let window = self.main_window()?;
+            if on_wayland(window) {
+                warn!("Window icons are not supported on Wayland; ignoring the icon");
+                return Ok(());
+            }
+
+            window.set_window_icon(icon);
+            Ok(())
+        }
+    }
+
+    /// Maximizes the window (`true`) or restores it to its previous size (`false`).
+    pub fn set_maximized(&self, maximized: bool) {
+        if let Some(window) = &self.window {
+            window.set_maximized(maximized);
+        }
+    }
+
+    /// Returns whether the window is maximized.
+    pub fn is_maximized(&self) -> bool {
+        self.window
+            .as_ref()
+            .is_some_and(|window| window.is_maximized())
+    }
+
+    /// Minimizes the window (`true`) or restores it (`false`).
+    ///
+    /// Frames aren't presented while the window is minimized; `draw` is still called, though.
+    pub fn set_minimized(&mut self, minimized: bool) {
+        self.minimized = minimized;
+        if let Some(window) = &self.window {
+            window.set_minimized(minimized);
+        }
+    }
+
+    /// Returns whether the window is minimized.
+    ///
+    /// Windows reports minimized windows as having a size of zero, other platforms don't report minimization
+    /// at all. There, this only reflects [`GraphicsContext::set_minimized`] and minimizing due to
+    /// [`WindowMode::minimize_on_focus_loss`](crate::conf::WindowMode::minimize_on_focus_loss),
+    /// until the window regains focus.
+    pub fn is_minimized(&self) -> bool {
+        let size = self.inner_size();
+        self.minimized || size.width == 0 || size.height == 0
+    }
+
+    /// Sets where the window is stacked relative to other windows.
+    /// The level is kept when switching to fullscreen and back.
+    ///
+    /// `AlwaysOnTop` is ignored on iOS, Android, the web and Wayland. `AlwaysOnBottom` is not supported on
+    /// any platform yet, so an error is returned for it.
+    pub fn set_window_level(&mut self, level: WindowLevel) -> GameResult {
+        let always_on_top = match level {
+            WindowLevel::Normal => false,
+            WindowLevel::AlwaysOnTop => true,
+            WindowLevel::AlwaysOnBottom => {
+                return Err(GameError::WindowError(String::from(
+                    "keeping windows below all others is not supported",
+                )))
+            }
+        };
+        self.window_mode.always_on_top = always_on_top;
+        if let Some(window) = &self.window {
+            window.set_always_on_top(always_on_top);
+        }
         Ok(())
     }
 
+    /// Returns where the window is stacked relative to other windows.
+    pub fn window_level(&self) -> WindowLevel {
+        if self.window_mode.always_on_top {
+            WindowLevel::AlwaysOnTop
+        } else {
+            WindowLevel::Normal
+        }
+    }
+
     /// Sets the window to fullscreen or back.
     pub fn set_fullscreen(&mut self, fullscreen: conf::FullscreenType) -> GameResult {
         let window_mode = self.window_mode.fullscreen_type(fullscreen);
         self.set_mode(window_mode)
     }
 
+    /// Returns the current fullscreen type of the window.
+    #[inline]
+    pub fn fullscreen_type(&self) -> conf::FullscreenType {
+        self.window_mode.fullscreen_type
+    }
+
+    /// Switches between windowed mode and the given fullscreen type, as commonly bound to Alt+Enter.
+    ///
+    /// Entering fullscreen remembers the window's size, position and maximized state,
+    /// which are restored when toggling back. Both directions are reported through
+    /// [`EventHandler::resize_event`](crate::event::EventHandler::resize_event).
+    pub fn toggle_fullscreen(&mut self, fullscreen: conf::FullscreenType) -> GameResult {
+        if self.window_mode.fullscreen_type != conf::FullscreenType::Windowed
+            || fullscreen == conf::FullscreenType::Windowed
+        {
+            self.set_fullscreen(conf::FullscreenType::Windowed)?;
+            if let (Some(geometry), Some(window)) = (self.windowed_geometry.take(), &self.window) {
+                window.set_inner_size(geometry.size);
+                if let Some(position) = geometry.position {
+                    window.set_outer_position(position);
+                }
+                window.set_maximized(geometry.maximized);
+            }
+            Ok(())
+        } else {
+            let geometry = WindowedGeometry {
+                size: self.inner_size(),
+                position: self.window_position().ok(),
+                maximized: self.is_maximized(),
+            };
+            self.set_fullscreen(fullscreen)?;
+            self.windowed_geometry = Some(geometry);
+            Ok(())
+        }
+    }
+
     /// Sets whether or not the window is resizable.
     pub fn set_resizable(&mut self, resizable: bool) -> GameResult {
         let window_mode = self.window_mode.resizable(resizable);
         self.set_mode(window_mode)
     }
 
+    /// Sets the minimum and maximum size of the window's drawable area, in logical pixels.
+    /// `None` removes the respective limit.
+    ///
+    /// If the current size lies outside the new limits, the window is resized to fit them,
+    /// which is reported through [`EventHandler::resize_event`](crate::event::EventHandler::resize_event).
+    pub fn set_window_size_limits(
+        &mut self,
+        min: Option<(f32, f32)>,
+        max: Option<(f32, f32)>,
+    ) -> GameResult {
+        let min = min.map(|(w, h)| dpi::LogicalSize::new(w, h));
+        let max = max.map(|(w, h)| dpi::LogicalSize::new(w, h));
+        if let Some(min) = min {
+            if min.width < 1.0 || min.height < 1.0 {
+                return Err(GameError::WindowError(format!(
+                    "minimum window size needs to be at least 1x1; actual value: {}x{}",
+                    min.width, min.height
+                )));
+            }
+        }
+        if let (Some(min), Some(max)) = (min, max) {
+            if max.width < min.width || max.height < min.height {
+                return Err(GameError::WindowError(format!(
+                    "maximum window size {}x{} is smaller than the minimum size {}x{}",
+                    max.width, max.height, min.width, min.height
+                )));
+            }
+        }
+
+        // keep the window mode in sync, so that `set_mode` doesn't revert the limits
+        let scale_factor = self.window_scale_factor();
+        let min_physical: dpi::PhysicalSize<f32> = min
+            .unwrap_or_else(|| dpi::LogicalSize::new(1.0, 1.0))
+            .to_physical(scale_factor);
+        let max_physical: dpi::PhysicalSize<f32> = max
+            .unwrap_or_else(|| dpi::LogicalSize::new(0.0, 0.0))
+            .to_physical(scale_factor);
+        self.window_mode = self
+            .window_mode
+            .min_dimensions(min_physical.width.max(1.0), min_physical.height.max(1.0))
+            .max_dimensions(max_physical.width, max_physical.height);
+
+        let window = match &self.window {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        window.set_min_inner_size(min);
+        window.set_max_inner_size(max);
+
+        let size: dpi::LogicalSize<f32> = window.inner_size().to_logical(scale_factor);
+        let mut clamped = size;
+        if let Some(min) = min {
+            clamped.width = clamped.width.max(min.width);
+            clamped.height = clamped.height.max(min.height);
+        }
+        if let Some(max) = max {
+            clamped.width = clamped.width.min(max.width);
+            clamped.height = clamped.height.min(max.height);
+        }
+        if clamped != size {
+            window.set_inner_size(clamped);
+        }
+        Ok(())
+    }
+
+    /// Makes the window's drawable area only take sizes which are multiples of `increments`
+    /// (in logical pixels) when resized, like terminal emulators snapping to whole character cells.
+    /// `None` allows any size again.
+    ///
+    /// The size is snapped after each resize, so the window may briefly show in-between sizes while dragging.
+    pub fn set_resize_increments(&mut self, increments: Option<(f32, f32)>) -> GameResult {
+        let increments = increments.map(|(w, h)| dpi::LogicalSize::new(w, h));
+        if let Some(increments) = increments {
+            if increments.width < 1.0 || increments.height < 1.0 {
+                return Err(GameError::WindowError(format!(
+                    "resize increments need to be at least 1x1; actual value: {}x{}",
+                    increments.width, increments.height
+                )));
+            }
+        }
+        self.resize_increments = increments;
+        self.snap_to_resize_increments();
+        Ok(())
+    }
+
+    pub(crate) fn snap_to_resize_increments(&self) {
+        let increments = match self.resize_increments {
+            Some(increments) => increments,
+            None => return,
+        };
+        let window = match &self.window {
+            Some(window) => window,
+            None => return,
+        };
+        if window.is_maximized() || window.fullscreen().is_some() {
+            return;
+        }
+        let increments: dpi::PhysicalSize<f64> =
+            increments.cast::<f64>().to_physical(window.scale_factor());
+        let size = window.inner_size();
+        // the half pixel keeps rounding errors from dropping a whole increment
+        let snap = |length: u32, increment: f64| {
+            let count = ((f64::from(length) + 0.5) / increment).floor().max(1.0);
+            (count * increment).round() as u32
+        };
+        let snapped = dpi::PhysicalSize::new(
+            snap(size.width, increments.width),
+            snap(size.height, increments.height),
+        );
+        if snapped != size {
+            window.set_inner_size(snapped);
+        }
+    }
+
     /// Sets the window mode, such as the size and other properties.
     ///
     /// Setting the window mode may have side effects, such as clearing
@@ -516,12 +1755,50 @@ impl GraphicsContext {
         self.frame_image.as_ref().unwrap(/* invariant */)
     }
 
+    /// Returns what's known about the graphics adapter in use, such as its name, its backend
+    /// and its driver, e.g. to show in a graphics options screen or in bug reports.
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.wgpu.adapter.get_info()
+    }
+
+    /// Returns whether the window is transparent, i.e. whether regions of the frame with an alpha
+    /// below 1 show what's behind it, as asked for with [`WindowMode::transparent`].
+    ///
+    /// This is false if the graphics backend can't composite the window surface with alpha, in which
+    /// case the window is opaque, and in a headless context.
+    pub fn is_transparent(&self) -> bool {
+        self.window.is_some()
+            && !matches!(
+                self.surface_config.alpha_mode,
+                wgpu::CompositeAlphaMode::Auto | wgpu::CompositeAlphaMode::Opaque
+            )
+    }
+
     /// Returns the image format of the window surface.
     #[inline]
     pub fn surface_format(&self) -> ImageFormat {
         self.surface_config.format
     }
 
+    /// Returns the number of samples the frame is drawn with by [`Canvas::from_frame`](crate::graphics::Canvas::from_frame).
+    ///
+    /// This may be fewer than [`WindowSetup::samples`] asked for, if the adapter doesn't
+    /// support that many.
+    pub fn samples(&self) -> NumSamples {
+        let samples = self.frame_msaa_image.as_ref().unwrap(/* invariant */).samples();
+        NumSamples::try_from(samples as u8).unwrap(/* only ever made from a NumSamples */)
+    }
+
+    /// Returns whether the window surface is sRGB, so colors are gamma corrected when they're
+    /// displayed.
+    ///
+    /// This may differ from [`WindowSetup::srgb`], if the window surface only supports the
+    /// other.
+    #[inline]
+    pub fn srgb(&self) -> bool {
+        self.surface_config.format.describe().srgb
+    }
+
     /// Returns the current [`wgpu::CommandEncoder`] if there is a frame in progress.
     pub fn commands(&mut self) -> Option<&mut wgpu::CommandEncoder> {
         self.fcx.as_mut().map(|fcx| &mut fcx.cmd)
@@ -537,23 +1814,36 @@ impl GraphicsContext {
             )));
         }
 
-        let size = self.window.inner_size();
-        let frame = match self.wgpu.surface.get_current_texture() {
-            Ok(frame) => Ok(frame),
-            Err(_) => {
-                self.surface_config.width = size.width.max(1);
-                self.surface_config.height = size.height.max(1);
-                self.wgpu
-                    .surface
-                    .configure(&self.wgpu.device, &self.surface_config);
-                self.wgpu.surface.get_current_texture().map_err(|_| {
-                    GameError::RenderError(String::from("failed to get next swapchain image"))
-                })
+        // some platforms fail to hand out swapchain images while minimized, and nothing would be visible anyway
+        let frame = if self.is_minimized() || self.surface.is_none() {
+            None
+        } else {
+            let size = self.inner_size();
+            if (self.surface_config.width, self.surface_config.height)
+                != (size.width.max(1), size.height.max(1))
+                || self.surface_outdated
+            {
+                // e.g. right after a video mode change, before the resize event arrived
+                self.resize(size);
             }
-        }?;
-        let frame_view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+
+            let surface = self.surface.as_ref().unwrap(/* see above */);
+            let frame = match surface.get_current_texture() {
+                Ok(frame) => Ok(frame),
+                Err(_) => {
+                    self.surface_config.width = size.width.max(1);
+                    self.surface_config.height = size.height.max(1);
+                    surface.configure(&self.wgpu.device, &self.surface_config);
+                    surface.get_current_texture().map_err(|_| {
+                        GameError::RenderError(String::from("failed to get next swapchain image"))
+                    })
+                }
+            }?;
+            let frame_view = frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            Some((frame, frame_view))
+        };
 
         self.fcx = Some(FrameContext {
             cmd: self
@@ -563,10 +1853,10 @@ impl GraphicsContext {
             present: self.frame().clone(),
             arenas: FrameArenas::default(),
             frame,
-            frame_view,
         });
 
         self.uniform_arena.free();
+        self.drawing_stats = FrameStats::default();
         self.text.free();
 
         Ok(())
@@ -577,61 +1867,62 @@ impl GraphicsContext {
     /// The only situation you need to call this in is when you are rolling your own event loop.
     pub fn end_frame(&mut self) -> GameResult {
         if let Some(mut fcx) = self.fcx.take() {
-            let mut present_pass = fcx.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &fcx.frame_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
-
-            let sampler = &mut self
-                .sampler_cache
-                .get(&self.wgpu.device, Sampler::default());
-
-            let (bind, layout) = BindGroupBuilder::new()
-                .image(&fcx.present.view, wgpu::ShaderStages::FRAGMENT)
-                .sampler(sampler, wgpu::ShaderStages::FRAGMENT)
-                .create(&self.wgpu.device, &mut self.bind_group_cache);
-
-            let layout = self.pipeline_cache.layout(&self.wgpu.device, &[layout]);
-            let copy = self.pipeline_cache.render_pipeline(
-                &self.wgpu.device,
-                &layout,
-                RenderPipelineInfo {
-                    vs: self.copy_shader.clone(),
-                    fs: self.copy_shader.clone(),
-                    vs_entry: "vs_main".into(),
-                    fs_entry: "fs_main".into(),
-                    samples: 1,
-                    format: self.surface_config.format,
-                    blend: None,
-                    depth: false,
-                    vertices: false,
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    vertex_layout: Vertex::layout(),
-                },
-            );
-
-            let copy = fcx.arenas.render_pipelines.alloc(copy);
-            let bind = fcx.arenas.bind_groups.alloc(bind);
-
-            present_pass.set_pipeline(copy);
-            present_pass.set_bind_group(0, bind, &[]);
-            present_pass.draw(0..3, 0..1);
+            let present = fcx.present.clone();
+            if let Some((_, view)) = &fcx.frame {
+                self.copy_to_surface(&mut fcx.cmd, &fcx.arenas, &present, view);
+            }
 
-            std::mem::drop(present_pass);
+            let mut secondary_frames = Vec::with_capacity(self.windows.len());
+            for i in 0..self.windows.len() {
+                let window = &mut self.windows[i];
+                let frame = match window.surface.get_current_texture() {
+                    Ok(frame) => frame,
+                    Err(_) => {
+                        window
+                            .surface
+                            .configure(&self.wgpu.device, &window.surface_config);
+                        match window.surface.get_current_texture() {
+                            Ok(frame) => frame,
+                            Err(e) => {
+                                warn!(
+                                    "Failed to get next swapchain image of {:?}: {}",
+                                    window.id, e
+                                );
+                                continue;
+                            }
+                        }
+                    }
+                };
+                let view = frame
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                let source = window.frame.clone();
+                self.copy_to_surface(&mut fcx.cmd, &fcx.arenas, &source, &view);
+                secondary_frames.push(frame);
+            }
 
             self.staging_belt.finish();
-            let _ = self.wgpu.queue.submit([fcx.cmd.finish()]);
-            fcx.frame.present();
+            let submission = self.wgpu.queue.submit([fcx.cmd.finish()]);
+            if let Some(latency) = self.max_frame_latency {
+                self.frames_in_flight.push_back(submission);
+                // the frame after waits for the oldest one queued to be drawn
+                while self.frames_in_flight.len() > latency as usize {
+                    let oldest = self.frames_in_flight.pop_front().unwrap(/* not empty */);
+                    let _ = self
+                        .wgpu
+                        .device
+                        .poll(wgpu::Maintain::WaitForSubmissionIndex(oldest));
+                }
+            }
+            if let Some((frame, _)) = fcx.frame {
+                frame.present();
+            }
+            for frame in secondary_frames {
+                frame.present();
+            }
 
             self.staging_belt.recall();
+            self.last_frame_stats = self.drawing_stats;
 
             Ok(())
         } else {
@@ -641,14 +1932,88 @@ impl GraphicsContext {
         }
     }
 
+    // Draws `source` onto the whole of a swapchain image.
+    fn copy_to_surface<'a>(
+        &mut self,
+        cmd: &'a mut wgpu::CommandEncoder,
+        arenas: &'a FrameArenas,
+        source: &Image,
+        target: &'a wgpu::TextureView,
+    ) {
+        let mut present_pass = cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        let sampler = &mut self
+            .sampler_cache
+            .get(&self.wgpu.device, Sampler::default());
+
+        let (bind, layout) = BindGroupBuilder::new()
+            .image(&source.view, wgpu::ShaderStages::FRAGMENT)
+            .sampler(sampler, wgpu::ShaderStages::FRAGMENT)
+            .create(&self.wgpu.device, &mut self.bind_group_cache);
+
+        let layout = self.pipeline_cache.layout(&self.wgpu.device, &[layout]);
+        let copy = self.pipeline_cache.render_pipeline(
+            &self.wgpu.device,
+            &layout,
+            RenderPipelineInfo {
+                vs: self.copy_shader.clone(),
+                fs: self.copy_shader.clone(),
+                vs_entry: "vs_main".into(),
+                fs_entry: "fs_main".into(),
+                samples: 1,
+                format: self.surface_config.format,
+                blend: None,
+                depth: false,
+                vertices: false,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                vertex_layout: Vertex::layout(),
+            },
+        );
+
+        let copy = arenas.render_pipelines.alloc(copy);
+        let bind = arenas.bind_groups.alloc(bind);
+
+        present_pass.set_pipeline(copy);
+        present_pass.set_bind_group(0, bind, &[]);
+        present_pass.draw(0..3, 0..1);
+    }
+
+    // Drops the surface of the main window, which the system may take away while the app is
+    // suspended. Frames have nothing to present to until it's resumed.
+    pub(crate) fn suspend(&mut self) {
+        self.surface = None;
+    }
+
+    // Creates the surface of the main window again after the app was suspended.
+    #[allow(unsafe_code)]
+    pub(crate) fn resume(&mut self) {
+        if let Some(window) = &self.window {
+            let surface = unsafe { self.wgpu.instance.create_surface(&**window) };
+            self.surface = Some(surface);
+            self.resize(self.inner_size());
+        }
+    }
+
     pub(crate) fn resize(&mut self, _new_size: dpi::PhysicalSize<u32>) {
-        let size = self.window.inner_size();
+        self.surface_outdated = false;
+        let size = self.inner_size();
         let _ = self.wgpu.device.poll(wgpu::Maintain::Wait);
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
-        self.wgpu
-            .surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
         self.update_frame_image();
     }
 
@@ -667,49 +2032,88 @@ impl GraphicsContext {
     }
 
     pub(crate) fn set_window_mode(&mut self, mode: &WindowMode) -> GameResult {
-        let window = &mut self.window;
-
         // TODO LATER: find out if single-dimension constraints are possible?
-        let min_dimensions = if mode.min_width >= 1.0 && mode.min_height >= 1.0 {
-            Some(dpi::PhysicalSize {
-                width: f64::from(mode.min_width),
-                height: f64::from(mode.min_height),
-            })
-        } else {
+        if mode.min_width < 1.0 || mode.min_height < 1.0 {
             return Err(GameError::WindowError(format!(
                 "window min_width and min_height need to be at least 1; actual values: {}, {}",
                 mode.min_width, mode.min_height
             )));
+        }
+        let (min_dimensions, max_dimensions) = mode.size_limits();
+        let windowed_size = |scale_factor| -> GameResult<dpi::PhysicalSize<f64>> {
+            let size = mode.actual_size()?.to_physical(scale_factor);
+            let clamped = mode.clamp_size(size);
+            if clamped != size {
+                warn!(
+                    "The window size {}x{} is outside of its min and max dimensions, using {}x{}",
+                    size.width, size.height, clamped.width, clamped.height
+                );
+            }
+            Ok(clamped)
         };
-        window.set_min_inner_size(min_dimensions);
-
-        let max_dimensions = if mode.max_width > 0.0 && mode.max_height > 0.0 {
-            Some(dpi::PhysicalSize {
-                width: f64::from(mode.max_width),
-                height: f64::from(mode.max_height),
-            })
-        } else {
-            None
+        let window = match &self.window {
+            Some(window) => window,
+            None => {
+                // the frame takes the size a window would
+                self.headless_size = match mode.fullscreen_type {
+                    FullscreenType::Windowed => windowed_size(1.0)?.cast(),
+                    _ => mode.actual_size()?.to_physical(1.0),
+                };
+                self.surface_config.width = self.headless_size.width;
+                self.surface_config.height = self.headless_size.height;
+                return Ok(());
+            }
         };
-        window.set_max_inner_size(max_dimensions);
         window.set_visible(mode.visible);
 
         match mode.fullscreen_type {
             FullscreenType::Windowed => {
+                let was_fullscreen = window.fullscreen().is_some();
                 window.set_fullscreen(None);
                 window.set_decorations(!mode.borderless);
-                window.set_inner_size(mode.actual_size()?);
+                // after leaving fullscreen, which some platforms reset them for
+                window.set_min_inner_size(Some(min_dimensions));
+                window.set_max_inner_size(max_dimensions);
+                window.set_inner_size(windowed_size(window.scale_factor())?);
                 window.set_resizable(mode.resizable);
                 window.set_maximized(mode.maximized);
+                let placed = was_fullscreen && place_window(window, mode);
+                if !placed && mode.monitor.is_some() {
+                    // only move the window if it isn't on the requested monitor already
+                    if let Some(monitor) = select_monitor(window, mode.monitor) {
+                        if window.current_monitor().as_ref() != Some(&monitor) {
+                            center_on(window, &monitor);
+                        }
+                    }
+                }
+            }
+            // browsers only let pages fill the screen, which is what the desktop fullscreen does
+            #[cfg(target_arch = "wasm32")]
+            FullscreenType::True => {
+                return Err(GameError::WindowError(String::from(
+                    "exclusive fullscreen isn't available on the web, use FullscreenType::Desktop",
+                )));
             }
+            #[cfg(not(target_arch = "wasm32"))]
             FullscreenType::True => {
-                if let Some(monitor) = window.current_monitor() {
-                    let v_modes = monitor.video_modes();
+                // which would keep the window from taking the size of the resolution
+                window.set_min_inner_size(None::<dpi::Size>);
+                window.set_max_inner_size(None::<dpi::Size>);
+                if let Some(monitor) = select_monitor(window, mode.monitor) {
+                    let resolution = (mode.width as u32, mode.height as u32);
+                    // prefer the video mode chosen with `set_mode_exclusive`, if the resolution still matches
+                    let preferred = self
+                        .video_mode
+                        .filter(|v_mode| (v_mode.size.width, v_mode.size.height) == resolution);
+                    let mut v_modes: Vec<_> = monitor.video_modes().collect();
+                    if let Some(preferred) = preferred {
+                        v_modes.sort_by_key(|v_mode| VideoMode::from(v_mode.clone()) != preferred);
+                    }
                     // try to find a video mode with a matching resolution
                     let mut match_found = false;
                     for v_mode in v_modes {
                         let size = v_mode.size();
-                        if (size.width, size.height) == (mode.width as u32, mode.height as u32) {
+                        if (size.width, size.height) == resolution {
                             window
                                 .set_fullscreen(Some(winit::window::Fullscreen::Exclusive(v_mode)));
                             match_found = true;
@@ -725,28 +2129,268 @@ impl GraphicsContext {
                 }
             }
             FullscreenType::Desktop => {
+                window.set_min_inner_size(None::<dpi::Size>);
+                window.set_max_inner_size(None::<dpi::Size>);
                 window.set_fullscreen(None);
                 window.set_decorations(false);
-                if let Some(monitor) = window.current_monitor() {
+                if let Some(monitor) = select_monitor(window, mode.monitor) {
                     window.set_inner_size(monitor.size());
                     window.set_outer_position(monitor.position());
                 }
             }
         }
 
+        // some platforms reset the window level when leaving fullscreen
+        window.set_always_on_top(mode.always_on_top);
+
         let size = window.inner_size();
         assert!(size.width > 0 && size.height > 0);
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
 
-        self.wgpu
-            .surface
-            .configure(&self.wgpu.device, &self.surface_config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.wgpu.device, &self.surface_config);
+        }
 
         Ok(())
     }
 }
 
+// Returns `mode` if the surface supports it, or the closest supported present mode otherwise.
+fn supported_present_mode(
+    adapter: &wgpu::Adapter,
+    surface: Option<&wgpu::Surface>,
+    mode: PresentMode,
+) -> PresentMode {
+    let supported = match surface {
+        Some(surface) => surface.get_supported_present_modes(adapter),
+        None => return PresentMode::Fifo,
+    };
+    let fallbacks: &[PresentMode] = match mode {
+        PresentMode::Mailbox => &[PresentMode::Mailbox, PresentMode::Immediate],
+        PresentMode::Immediate => &[PresentMode::Immediate, PresentMode::Mailbox],
+        mode => &[mode],
+    };
+    fallbacks
+        .iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(PresentMode::Fifo)
+}
+
+// Picks the adapter whose name has `name` in it, ignoring case, which can draw to the surface,
+// preferring integrated or discrete GPUs as the power preference does.
+fn named_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface>,
+    name: &str,
+    power_preference: wgpu::PowerPreference,
+) -> Option<wgpu::Adapter> {
+    let name = name.to_lowercase();
+    let preferred = match power_preference {
+        wgpu::PowerPreference::LowPower => wgpu::DeviceType::IntegratedGpu,
+        wgpu::PowerPreference::HighPerformance => wgpu::DeviceType::DiscreteGpu,
+    };
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .filter(|adapter| adapter.get_info().name.to_lowercase().contains(&name))
+        .filter(|adapter| surface.is_none_or(|surface| adapter.is_surface_supported(surface)))
+        .max_by_key(|adapter| adapter.get_info().device_type == preferred)
+}
+
+// Picks the first of the surface's formats which is sRGB, or linear, as requested, falling back
+// to the one the surface prefers.
+fn supported_format(formats: &[wgpu::TextureFormat], srgb: bool) -> wgpu::TextureFormat {
+    match formats.iter().find(|format| format.describe().srgb == srgb) {
+        Some(format) => *format,
+        None => {
+            warn!(
+                "The window surface has no {} format, using {:?}",
+                if srgb { "sRGB" } else { "linear" },
+                formats[0]
+            );
+            formats[0]
+        }
+    }
+}
+
+// Picks the most samples up to those requested which the adapter supports for the format,
+// falling back to fewer with a warning.
+fn supported_samples(
+    adapter: &wgpu::Adapter,
+    format: wgpu::TextureFormat,
+    requested: NumSamples,
+) -> NumSamples {
+    let multisample = adapter
+        .get_texture_format_features(format)
+        .flags
+        .contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE);
+    // wgpu supports no other sample counts than 1 and 4 yet, and 1 always
+    let supported: &[u8] = if multisample { &[1, 4] } else { &[1] };
+    let samples = fallback_samples(requested, supported);
+    if samples != requested {
+        warn!(
+            "{} samples for MSAA are not supported with {:?}, using {}",
+            u8::from(requested),
+            format,
+            u8::from(samples)
+        );
+    } else {
+        info!("Using {} samples for MSAA", u8::from(samples));
+    }
+    samples
+}
+
+// The most samples up to `requested` of the sample counts in `supported`, trying each count
+// `NumSamples` has from the most down, and 1 if none of them are supported.
+fn fallback_samples(requested: NumSamples, supported: &[u8]) -> NumSamples {
+    [NumSamples::Four, NumSamples::One]
+        .into_iter()
+        .find(|&samples| {
+            u8::from(samples) <= u8::from(requested) && supported.contains(&u8::from(samples))
+        })
+        .unwrap_or(NumSamples::One)
+}
+
+// Picks a compositing mode which lets transparent regions of the frame show through, if requested and supported.
+fn supported_alpha_mode(
+    adapter: &wgpu::Adapter,
+    surface: Option<&wgpu::Surface>,
+    transparent: bool,
+) -> wgpu::CompositeAlphaMode {
+    if !transparent {
+        return wgpu::CompositeAlphaMode::Auto;
+    }
+    let supported = match surface {
+        Some(surface) => surface.get_supported_alpha_modes(adapter),
+        None => return wgpu::CompositeAlphaMode::Auto,
+    };
+    // canvases blend into a premultiplied result when cleared to transparent
+    [
+        wgpu::CompositeAlphaMode::PreMultiplied,
+        wgpu::CompositeAlphaMode::PostMultiplied,
+        wgpu::CompositeAlphaMode::Inherit,
+    ]
+    .into_iter()
+    .find(|mode| supported.contains(mode))
+    .unwrap_or_else(|| {
+        warn!("Transparent windows are not supported by the graphics backend");
+        wgpu::CompositeAlphaMode::Auto
+    })
+}
+
+fn on_wayland(window: &winit::window::Window) -> bool {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use winit::platform::unix::WindowExtUnix;
+        window.wayland_surface().is_some()
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let _ = window;
+        false
+    }
+}
+
+// Moves the window to `mode.position`, or centers it if `mode.centered` is set, returning
+// whether either is set.
+fn place_window(window: &winit::window::Window, mode: &WindowMode) -> bool {
+    if mode.position.is_none() && !mode.centered {
+        return false;
+    }
+    if on_wayland(window) {
+        debug!("Windows can't position themselves on Wayland, leaving it to the compositor");
+        return true;
+    }
+    match mode.position {
+        Some((x, y)) => window.set_outer_position(dpi::LogicalPosition::new(x, y)),
+        None => {
+            if let Some(monitor) = select_monitor(window, mode.monitor) {
+                center_on(window, &monitor);
+            }
+        }
+    }
+    true
+}
+
+fn center_on(window: &winit::window::Window, monitor: &winit::monitor::MonitorHandle) {
+    let size = window.outer_size();
+    let monitor_size = monitor.size();
+    let position = monitor.position();
+    window.set_outer_position(PhysicalPosition::new(
+        position.x + (monitor_size.width as i32 - size.width as i32) / 2,
+        position.y + (monitor_size.height as i32 - size.height as i32) / 2,
+    ));
+}
+
+fn window_builder(
+    mode: &WindowMode,
+    setup: &WindowSetup,
+    filesystem: &Filesystem,
+) -> GameResult<winit::window::WindowBuilder> {
+    let mut window_builder = winit::window::WindowBuilder::new()
+        .with_title(setup.title.clone())
+        .with_inner_size(mode.actual_size()?)
+        .with_resizable(mode.resizable)
+        .with_visible(mode.visible)
+        .with_transparent(mode.transparent)
+        .with_decorations(!mode.borderless)
+        .with_always_on_top(mode.always_on_top)
+        .with_min_inner_size(mode.size_limits().0);
+    if let Some((x, y)) = mode.position {
+        window_builder = window_builder.with_position(dpi::LogicalPosition::new(x, y));
+    }
+    if let (_, Some(max)) = mode.size_limits() {
+        window_builder = window_builder.with_max_inner_size(max);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::WindowBuilderExtWindows;
+        window_builder = window_builder.with_drag_and_drop(false);
+    }
+
+    // with the default icon, rather than not starting, if it can't be loaded
+    if !setup.icon.is_empty() {
+        match load_icon(setup.icon.as_ref(), filesystem) {
+            Ok(icon) => window_builder = window_builder.with_window_icon(Some(icon)),
+            Err(e) => warn!("Could not load the window icon {:?}: {:#}", setup.icon, e),
+        }
+    }
+
+    Ok(window_builder)
+}
+
+// Maps `point` from the coordinate system spanned by `from` into the one spanned by `to`.
+fn remap(point: mint::Point2<f32>, from: Rect, to: Rect) -> mint::Point2<f32> {
+    let x = if from.w == 0. {
+        0.
+    } else {
+        (point.x - from.x) / from.w
+    };
+    let y = if from.h == 0. {
+        0.
+    } else {
+        (point.y - from.y) / from.h
+    };
+    mint::Point2 {
+        x: to.x + x * to.w,
+        y: to.y + y * to.h,
+    }
+}
+
 // This is kinda awful 'cause it copies a couple times,
 // but still better than
 // having `winit` try to do the image loading for us.
@@ -760,11 +2404,41 @@ pub(crate) fn load_icon(
 
     let mut buf = Vec::new();
     let mut reader = filesystem.open(icon_file)?;
-    let _ = reader.read_to_end(&mut buf)?;
-    let i = imgcrate::load_from_memory(&buf)?;
+    let _ = reader
+        .read_to_end(&mut buf)
+        .with_context(|| format!("reading the window icon {:?}", icon_file))?;
+    let i = imgcrate::load_from_memory(&buf)
+        .with_context(|| format!("loading the window icon {:?}", icon_file))?;
     let image_data = i.to_rgba8();
     Icon::from_rgba(image_data.to_vec(), i.width(), i.height()).map_err(|e| {
         let msg = format!("Could not load icon: {:?}", e);
         GameError::ResourceLoadError(msg)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_fallback_samples() {
+        assert_eq!(
+            fallback_samples(NumSamples::Four, &[1, 4]),
+            NumSamples::Four
+        );
+        assert_eq!(fallback_samples(NumSamples::Four, &[1, 2]), NumSamples::One);
+        assert_eq!(fallback_samples(NumSamples::One, &[1, 4]), NumSamples::One);
+        assert_eq!(fallback_samples(NumSamples::Four, &[]), NumSamples::One);
+    }
+
+    #[test]
+    fn headless_supported_format() {
+        use wgpu::TextureFormat::{Bgra8Unorm, Bgra8UnormSrgb, Rgba16Float};
+
+        let formats = [Bgra8Unorm, Bgra8UnormSrgb];
+        assert_eq!(supported_format(&formats, true), Bgra8UnormSrgb);
+        assert_eq!(supported_format(&formats, false), Bgra8Unorm);
+        // the surface's preferred one, if it has none of them
+        assert_eq!(supported_format(&[Rgba16Float], true), Rgba16Float);
+    }
+}
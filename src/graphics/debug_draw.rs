@@ -0,0 +1,385 @@
+use super::{
+    Camera2D, Canvas, Color, DrawMode, DrawParam, LinearColor, Mesh, MeshData, Rect, Vertex,
+};
+use glam::{Affine2, Vec2};
+
+// the circle segments per pixel of radius on screen, and their bounds
+const SEGMENTS_PER_PIXEL: f32 = 0.5;
+const MIN_SEGMENTS: u32 = 8;
+const MAX_SEGMENTS: u32 = 64;
+
+/// Batches lines, circles, polygons, rectangles and points into one mesh and draws it with one
+/// draw call, e.g. for showing colliders, paths and other debug shapes every frame.
+///
+/// Shapes are filled or drawn as wireframes by the [mode](DebugDraw::set_mode) they're added
+/// with, whose stroke width is also that of lines. Lines are always drawn as lines.
+///
+/// Positions are in world coordinates, converted to screen coordinates with the
+/// [transform](DebugDraw::set_transform), such as that of a [camera](DebugDraw::set_camera), as
+/// they're added. Line widths and point sizes stay in screen pixels whatever the zoom, so the
+/// shapes are to be drawn on a canvas in screen coordinates, not through the camera.
+///
+/// ```rust,no_run
+/// # use ggez::graphics::*;
+/// # use ggez::{Context, GameResult};
+/// # fn t(ctx: &mut Context, camera: &Camera2D, debug: &mut DebugDraw) -> GameResult {
+/// let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+/// debug.set_camera(camera);
+/// debug.set_mode(DrawMode::stroke(1.0));
+/// debug.circle([10.0, 20.0], 5.0, Color::GREEN);
+/// debug.aabb(Rect::new(0.0, 0.0, 40.0, 30.0), Color::YELLOW);
+/// debug.line([0.0, 0.0], [40.0, 30.0], Color::RED);
+/// debug.draw(&mut canvas);
+/// canvas.finish(ctx)
+/// # }
+/// ```
+///
+/// Filled polygons are expected to be convex, as they're drawn as triangle fans.
+#[derive(Debug, Clone)]
+pub struct DebugDraw {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    transform: Affine2,
+    // how much the transform scales, for the number of circle segments
+    scale: f32,
+    filled: bool,
+    line_width: f32,
+}
+
+impl DebugDraw {
+    /// Creates a batch without shapes, drawing wireframes with lines of 1 pixel, and without
+    /// a transform.
+    pub fn new() -> Self {
+        DebugDraw {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            transform: Affine2::IDENTITY,
+            scale: 1.0,
+            filled: false,
+            line_width: 1.0,
+        }
+    }
+
+    /// Sets whether the shapes added after are filled or wireframes, and the width of the lines
+    /// of wireframes and [lines](DebugDraw::line) in screen pixels.
+    pub fn set_mode(&mut self, mode: DrawMode) {
+        match mode {
+            DrawMode::Fill(_) => self.filled = true,
+            DrawMode::Stroke(options) => {
+                self.filled = false;
+                self.line_width = options.line_width;
+            }
+        }
+    }
+
+    /// Returns the mode shapes are added with.
+    pub fn mode(&self) -> DrawMode {
+        if self.filled {
+            DrawMode::fill()
+        } else {
+            DrawMode::stroke(self.line_width)
+        }
+    }
+
+    /// Sets the transform from world to screen coordinates of the shapes added after, as a
+    /// column-major 3x3 matrix.
+    pub fn set_transform(&mut self, transform: impl Into<mint::ColumnMatrix3<f32>>) {
+        self.transform = Affine2::from_mat3(glam::Mat3::from(transform.into()));
+        self.scale = self.transform.matrix2.determinant().abs().sqrt();
+    }
+
+    /// Sets the transform from world to screen coordinates to that of `camera`, as in
+    /// [`Camera2D::world_to_screen`].
+    pub fn set_camera(&mut self, camera: &Camera2D) {
+        self.transform = camera.view_transform(true);
+        self.scale = camera.zoom();
+    }
+
+    /// Removes the transform, so positions are in screen coordinates.
+    pub fn reset_transform(&mut self) {
+        self.transform = Affine2::IDENTITY;
+        self.scale = 1.0;
+    }
+
+    /// Returns whether there are no shapes to draw.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Removes the shapes added since the last draw.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Adds a line from `a` to `b`.
+    pub fn line(
+        &mut self,
+        a: impl Into<mint::Point2<f32>>,
+        b: impl Into<mint::Point2<f32>>,
+        color: Color,
+    ) {
+        let a = self.to_screen(a);
+        let b = self.to_screen(b);
+        self.segment(a, b, self.line_width, color.into());
+    }
+
+    /// Adds a circle.
+    pub fn circle(&mut self, center: impl Into<mint::Point2<f32>>, radius: f32, color: Color) {
+        let center = self.to_screen(center);
+        let radius = radius * self.scale;
+        let segments =
+            ((radius * SEGMENTS_PER_PIXEL) as u32).clamp(MIN_SEGMENTS, MAX_SEGMENTS) as usize;
+        let points = (0..segments).map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        });
+        let color = color.into();
+        if self.filled {
+            self.fan(points, color);
+        } else {
+            self.ring(points.collect::<Vec<_>>(), color);
+        }
+    }
+
+    /// Adds a polygon through `points`, which is closed.
+    pub fn polygon<P>(&mut self, points: &[P], color: Color)
+    where
+        P: Into<mint::Point2<f32>> + Copy,
+    {
+        if points.len() < 2 {
+            return;
+        }
+        let points: Vec<Vec2> = points.iter().map(|&point| self.to_screen(point)).collect();
+        let color = color.into();
+        if self.filled {
+            self.fan(points.into_iter(), color);
+        } else {
+            self.ring(points, color);
+        }
+    }
+
+    /// Adds a rectangle, which is axis-aligned in world coordinates.
+    pub fn aabb(&mut self, rect: Rect, color: Color) {
+        self.polygon(
+            &[
+                [rect.left(), rect.top()],
+                [rect.right(), rect.top()],
+                [rect.right(), rect.bottom()],
+                [rect.left(), rect.bottom()],
+            ],
+            color,
+        );
+    }
+
+    /// Adds a point, drawn as a filled square `size` pixels wide on screen.
+    pub fn point(&mut self, point: impl Into<mint::Point2<f32>>, size: f32, color: Color) {
+        let point = self.to_screen(point);
+        let half = size / 2.0;
+        let color = color.into();
+        self.quad(
+            [
+                point + Vec2::new(-half, -half),
+                point + Vec2::new(half, -half),
+                point + Vec2::new(half, half),
+                point + Vec2::new(-half, half),
+            ],
+            color,
+        );
+    }
+
+    /// Draws the shapes added since the last draw on `canvas` with one draw call, and removes
+    /// them, so the next frame's are added from scratch.
+    pub fn draw(&mut self, canvas: &mut Canvas) {
+        if !self.is_empty() {
+            let mesh = Mesh::from_data_wgpu(&canvas.wgpu, self.mesh_data());
+            canvas.draw(&mesh, DrawParam::default());
+        }
+        self.clear();
+    }
+
+    /// Returns the vertices and indices of the shapes added since the last draw, in screen
+    /// coordinates.
+    pub fn mesh_data(&self) -> MeshData<'_> {
+        MeshData {
+            vertices: &self.vertices,
+            indices: &self.indices,
+        }
+    }
+
+    fn to_screen(&self, point: impl Into<mint::Point2<f32>>) -> Vec2 {
+        let point: mint::Point2<f32> = point.into();
+        self.transform.transform_point2(point.into())
+    }
+
+    fn vertex(&mut self, position: Vec2, color: LinearColor) -> u32 {
+        let index = self.vertices.len() as u32;
+        self.vertices.push(Vertex {
+            position: position.into(),
+            uv: [0.0, 0.0],
+            color: color.into(),
+        });
+        index
+    }
+
+    fn quad(&mut self, corners: [Vec2; 4], color: LinearColor) {
+        let first = self.vertices.len() as u32;
+        for corner in corners {
+            let _ = self.vertex(corner, color);
+        }
+        self.indices
+            .extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    fn segment(&mut self, a: Vec2, b: Vec2, width: f32, color: LinearColor) {
+        let normal = (b - a).perp().normalize_or_zero() * (width / 2.0);
+        self.quad([a + normal, b + normal, b - normal, a - normal], color);
+    }
+
+    fn fan(&mut self, points: impl Iterator<Item = Vec2>, color: LinearColor) {
+        let first = self.vertices.len() as u32;
+        for point in points {
+            let index = self.vertex(point, color);
+            if index >= first + 2 {
+                self.indices.extend_from_slice(&[first, index - 1, index]);
+            }
+        }
+    }
+
+    fn ring(&mut self, points: Vec<Vec2>, color: LinearColor) {
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            self.segment(a, b, self.line_width, color);
+        }
+    }
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws what rapier's `DebugRenderPipeline` renders, its colliders, joints and the like, in
+/// its colors, with the [transform](DebugDraw::set_transform) of the batch, so a camera's.
+#[cfg(feature = "rapier")]
+impl rapier2d::pipeline::DebugRenderBackend for DebugDraw {
+    fn draw_line(
+        &mut self,
+        _object: rapier2d::pipeline::DebugRenderObject,
+        a: rapier2d::math::Point<rapier2d::math::Real>,
+        b: rapier2d::math::Point<rapier2d::math::Real>,
+        color: [f32; 4],
+    ) {
+        self.line([a.x, a.y], [b.x, b.y], hsla_to_color(color));
+    }
+}
+
+// rapier's debug colors are hue in degrees, saturation, lightness and alpha
+#[cfg(feature = "rapier")]
+fn hsla_to_color([h, s, l, a]: [f32; 4]) -> Color {
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    Color::new(r + m, g + m, b + m, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes() {
+        let mut debug = DebugDraw::new();
+        debug.line([0.0, 0.0], [10.0, 0.0], Color::WHITE);
+        assert_eq!(debug.mesh_data().vertices.len(), 4);
+        assert_eq!(debug.mesh_data().indices.len(), 6);
+        // a line 1 pixel wide
+        let ys: Vec<f32> = debug.vertices.iter().map(|v| v.position[1]).collect();
+        assert_eq!(ys, [0.5, 0.5, -0.5, -0.5]);
+
+        debug.clear();
+        debug.set_mode(DrawMode::fill());
+        debug.polygon(
+            &[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+            Color::WHITE,
+        );
+        assert_eq!(debug.mesh_data().vertices.len(), 4);
+        assert_eq!(debug.mesh_data().indices, &[0, 1, 2, 0, 2, 3]);
+
+        // wireframes are a quad per edge
+        debug.clear();
+        debug.set_mode(DrawMode::stroke(2.0));
+        debug.aabb(Rect::new(0.0, 0.0, 4.0, 4.0), Color::WHITE);
+        assert_eq!(debug.mesh_data().indices.len(), 4 * 6);
+    }
+
+    #[test]
+    fn transform() {
+        let mut debug = DebugDraw::new();
+        let mut camera = Camera2D::new([800.0, 600.0]);
+        camera.set_position([100.0, 100.0]);
+        camera.set_zoom(2.0);
+        debug.set_camera(&camera);
+        // points keep their size on screen
+        debug.point([100.0, 100.0], 4.0, Color::WHITE);
+        let positions: Vec<[f32; 2]> = debug.vertices.iter().map(|v| v.position).collect();
+        assert_eq!(
+            positions,
+            [
+                [398.0, 298.0],
+                [402.0, 298.0],
+                [402.0, 302.0],
+                [398.0, 302.0]
+            ]
+        );
+
+        debug.clear();
+        debug.set_transform(glam::Mat3::from_scale(Vec2::splat(3.0)));
+        debug.point([1.0, 1.0], 2.0, Color::WHITE);
+        assert_eq!(debug.vertices[0].position, [2.0, 2.0]);
+    }
+
+    #[cfg(feature = "rapier")]
+    #[test]
+    fn rapier_colors() {
+        assert_eq!(hsla_to_color([0.0, 1.0, 0.5, 1.0]), Color::RED);
+        assert_eq!(
+            hsla_to_color([120.0, 1.0, 0.5, 0.5]),
+            Color::new(0.0, 1.0, 0.0, 0.5)
+        );
+        assert_eq!(hsla_to_color([200.0, 0.0, 1.0, 1.0]), Color::WHITE);
+    }
+
+    #[test]
+    fn stress() {
+        let mut debug = DebugDraw::new();
+        let start = std::time::Instant::now();
+        for i in 0..5000 {
+            let x = (i % 100) as f32 * 8.0;
+            let y = (i / 100) as f32 * 12.0;
+            match i % 5 {
+                0 => debug.line([x, y], [x + 6.0, y + 6.0], Color::RED),
+                1 => debug.circle([x, y], 4.0, Color::GREEN),
+                2 => debug.polygon(&[[x, y], [x + 4.0, y], [x + 2.0, y + 4.0]], Color::BLUE),
+                3 => debug.aabb(Rect::new(x, y, 5.0, 5.0), Color::YELLOW),
+                _ => debug.point([x, y], 3.0, Color::WHITE),
+            }
+        }
+        let elapsed = start.elapsed();
+        assert!(!debug.is_empty());
+        // unoptimized builds are too slow to say anything
+        if !cfg!(debug_assertions) {
+            assert!(elapsed.as_millis() < 10, "{:?}", elapsed);
+        }
+    }
+}
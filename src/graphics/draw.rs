@@ -5,7 +5,12 @@ use crate::context::Has;
 ///
 /// This can either be a set of individual components, or
 /// a single `Matrix4` transform.
+///
+/// With the `serde` feature it's serialized as an enum with the points and vectors as
+/// `[x, y]` arrays, e.g. `{ "Values": { "dest": [13.0, 37.0], "rotation": 0.0, ... } }`, and
+/// the matrix as an array of its columns.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Transform {
     /// Transform made of individual values
     Values {
@@ -121,7 +126,26 @@ pub type ZIndex = i32;
 /// ```
 ///
 /// As a shortcut, it also implements [`From` for `Into<Point2<f32>>`](#impl-From<P>).
+///
+/// With the `serde` feature it's serialized as a struct of its fields, any of which can be left
+/// out to keep their default:
+///
+/// ```json
+/// {
+///     "src": { "x": 0.0, "y": 0.0, "w": 1.0, "h": 1.0 },
+///     "color": { "r": 1.0, "g": 1.0, "b": 1.0, "a": 1.0 },
+///     "transform": {
+///         "Values": { "dest": [13.0, 37.0], "rotation": 0.0, "scale": [1.0, 1.0], "offset": [0.0, 0.0] }
+///     },
+///     "z": 0
+/// }
+/// ```
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct DrawParam {
     /// A portion of the drawable to clip, as a fraction of the whole image.
     /// Defaults to the whole image (\[0.0, 0.0\] to \[1.0, 1.0\]) if omitted.
@@ -295,6 +319,8 @@ pub trait Drawable {
 }
 
 #[derive(Debug, Copy, Clone, crevice::std140::AsStd140)]
+// uploaded with `bytemuck`, so in the order of the fields in `draw.wgsl`
+#[repr(C)]
 pub(crate) struct DrawUniforms {
     pub color: mint::Vector4<f32>,
     pub src_rect: mint::Vector4<f32>,
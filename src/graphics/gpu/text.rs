@@ -3,11 +3,22 @@ use super::{
     bind_group::BindGroupBuilder,
     growing::GrowingBufferArena,
 };
-use crate::graphics::{context::FrameArenas, LinearColor};
+use crate::graphics::{
+    context::FrameArenas,
+    text::{FontCacheConfig, FontCacheGrowth, FontCacheStats, TextHinting},
+    Color, DrawParam, Image, ImageFormat, LinearColor, Rect, WgpuContext,
+};
 use crevice::std140::AsStd140;
-use glyph_brush::{GlyphBrush, GlyphBrushBuilder};
+use glyph_brush::{
+    ab_glyph::{Font, FontArc, GlyphId, GlyphImageFormat},
+    FontId, GlyphBrush, GlyphBrushBuilder, GlyphCruncher, GlyphPositioner,
+};
 use ordered_float::OrderedFloat;
-use std::{cell::RefCell, num::NonZeroU32};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    num::NonZeroU32,
+};
 
 pub(crate) struct TextRenderer {
     // RefCell to make various getter not take &mut.
@@ -17,23 +28,145 @@ pub(crate) struct TextRenderer {
     pub cache_bind: ArcBindGroup,
     pub cache_bind_layout: ArcBindGroupLayout,
     pub cache_size: (u32, u32),
+    pub cache_config: FontCacheConfig,
     pub verts: GrowingBufferArena,
+    // fonts tried, in order, for characters missing from a fragment's font.
+    pub fallbacks: Vec<FontId>,
+    // fonts containing color bitmap glyphs, and the glyphs decoded so far.
+    pub color_fonts: HashSet<FontId>,
+    color_glyphs: ColorGlyphCache,
+    // counters for the frame in progress, moved into `last_stats` by `free`.
+    stats: CacheCounters,
+    last_stats: CacheCounters,
+    // glyphs and pixels uploaded since the atlas was last rebuilt.
+    cached_glyphs: u32,
+    occupied_pixels: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CacheCounters {
+    rasterizations: u32,
+    evictions: u32,
 }
 
 impl TextRenderer {
     pub fn new(device: &wgpu::Device, cache_bind_layout: ArcBindGroupLayout) -> Self {
-        let cache_size = (1024, 1024);
+        let cache_config = FontCacheConfig::default();
+        let cache_size = cache_config.initial_size;
 
         let glyph_brush = GlyphBrushBuilder::using_fonts(vec![])
             .cache_redraws(false)
             .initial_cache_size(cache_size)
             .build();
 
+        let (cache, cache_view, cache_bind) =
+            Self::create_cache(device, &cache_bind_layout, cache_size);
+
+        let verts = GrowingBufferArena::new(
+            device,
+            1,
+            wgpu::BufferDescriptor {
+                label: None,
+                size: 2048 * std::mem::size_of::<TextVertex>() as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
+                mapped_at_creation: false,
+            },
+        );
+
+        TextRenderer {
+            glyph_brush: RefCell::new(glyph_brush),
+            cache,
+            cache_view,
+            cache_bind,
+            cache_bind_layout,
+            cache_size,
+            cache_config,
+            verts,
+            stats: CacheCounters::default(),
+            last_stats: CacheCounters::default(),
+            cached_glyphs: 0,
+            occupied_pixels: 0,
+            fallbacks: Vec::new(),
+            color_fonts: HashSet::new(),
+            color_glyphs: ColorGlyphCache::default(),
+        }
+    }
+
+    pub fn add_font(&mut self, font: FontArc) -> FontId {
+        // only bitmap strikes (CBDT/CBLC and sbix) are supported for color glyphs
+        let has_color = font
+            .codepoint_ids()
+            .any(|(id, _)| font.glyph_raster_image2(id, u16::MAX).is_some());
+
+        let id = self.glyph_brush.borrow_mut().add_font(font);
+        if has_color {
+            let _ = self.color_fonts.insert(id);
+        }
+        id
+    }
+
+    /// Returns the images and draw params of all color glyphs within `section`.
+    ///
+    /// Color glyphs are drawn as separate images on top of the regular glyphs, tinted only by the alpha of the text color.
+    pub fn color_glyphs(
+        &mut self,
+        wgpu: &WgpuContext,
+        section: &glyph_brush::Section<'_, Extra>,
+    ) -> Vec<(Image, DrawParam)> {
+        let mut brush = self.glyph_brush.borrow_mut();
+        let glyphs = brush
+            .glyphs(section)
+            .filter(|sg| self.color_fonts.contains(&sg.font_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let fonts = brush.fonts();
+
+        glyphs
+            .into_iter()
+            .filter_map(|sg| {
+                let font = &fonts[sg.font_id.0];
+                let glyph = &sg.glyph;
+                // the strike closest to the pixels per em we're drawing at
+                let ppem =
+                    glyph.scale.y * font.units_per_em().unwrap_or(1.) / font.height_unscaled();
+                let size = ppem.round().clamp(1., f32::from(u16::MAX)) as u16;
+
+                let color_glyph =
+                    self.color_glyphs
+                        .get(wgpu, (sg.font_id, glyph.id, size), font)?;
+
+                let scale = ppem / color_glyph.pixels_per_em;
+                let extra = &section.text[sg.section_index].extra;
+                let transform = extra.transform
+                    * glam::Mat4::from_translation(glam::vec3(
+                        glyph.position.x + color_glyph.origin.x * scale,
+                        glyph.position.y + color_glyph.origin.y * scale,
+                        0.,
+                    ))
+                    * glam::Mat4::from_scale(glam::vec3(scale, scale, 1.));
+
+                let alpha = extra.color.a;
+                Some((
+                    color_glyph.page.clone(),
+                    DrawParam::default()
+                        .src(color_glyph.src)
+                        .transform(transform)
+                        .color(Color::new(1., 1., 1., alpha)),
+                ))
+            })
+            .collect()
+    }
+
+    fn create_cache(
+        device: &wgpu::Device,
+        cache_bind_layout: &ArcBindGroupLayout,
+        size: (u32, u32),
+    ) -> (ArcTexture, ArcTextureView, ArcBindGroup) {
         let cache = ArcTexture::new(device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size: wgpu::Extent3d {
-                width: cache_size.0,
-                height: cache_size.1,
+                width: size.0,
+                height: size.1,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -49,154 +182,455 @@ impl TextRenderer {
         let cache_bind = BindGroupBuilder::new().image(&cache_view, wgpu::ShaderStages::FRAGMENT);
         let cache_bind = ArcBindGroup::new(device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
-            layout: &cache_bind_layout,
+            layout: cache_bind_layout,
             entries: cache_bind.entries(),
         }));
 
-        let verts = GrowingBufferArena::new(
-            device,
-            1,
-            wgpu::BufferDescriptor {
-                label: None,
-                size: 2048 * std::mem::size_of::<TextVertex>() as u64,
-                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
-                mapped_at_creation: false,
-            },
+        (cache, cache_view, cache_bind)
+    }
+
+    /// Rebuilds the glyph cache texture with a new size, evicting every cached glyph.
+    fn resize_cache(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        self.cache_size = size;
+        self.glyph_brush
+            .borrow_mut()
+            .resize_texture(self.cache_size.0, self.cache_size.1);
+
+        let (cache, cache_view, cache_bind) =
+            Self::create_cache(device, &self.cache_bind_layout, self.cache_size);
+        self.cache = cache;
+        self.cache_view = cache_view;
+        self.cache_bind = cache_bind;
+
+        self.stats.evictions += self.cached_glyphs;
+        self.cached_glyphs = 0;
+        self.occupied_pixels = 0;
+        // color glyphs go with the rest
+        self.color_glyphs.clear();
+    }
+
+    pub fn set_config(&mut self, device: &wgpu::Device, config: FontCacheConfig) {
+        self.cache_config = config;
+
+        // keep whatever the atlas has grown to, as long as it's still within the new limits.
+        let clamp = |cur: u32, min: u32, max: u32| cur.max(min).min(max);
+        let size = (
+            clamp(self.cache_size.0, config.initial_size.0, config.max_size.0),
+            clamp(self.cache_size.1, config.initial_size.1, config.max_size.1),
         );
+        if size != self.cache_size {
+            self.resize_cache(device, size);
+        }
+    }
 
-        TextRenderer {
-            glyph_brush: RefCell::new(glyph_brush),
-            cache,
-            cache_view,
-            cache_bind,
-            cache_bind_layout,
-            cache_size,
-            verts,
+    pub fn stats(&self) -> FontCacheStats {
+        let area = u64::from(self.cache_size.0) * u64::from(self.cache_size.1);
+        FontCacheStats {
+            atlas_size: self.cache_size,
+            occupancy: (self.occupied_pixels as f64 / area as f64).min(1.) as f32,
+            rasterizations: self.last_stats.rasterizations,
+            evictions: self.last_stats.evictions,
+        }
+    }
+
+    pub fn queue(&self, section: &glyph_brush::Section<'_, Extra>, hinting: TextHinting) {
+        match hinting {
+            TextHinting::Subpixel => self.glyph_brush.borrow_mut().queue(section),
+            TextHinting::PixelSnapped => {
+                let layout = PixelSnapped(section.layout);
+                self.glyph_brush
+                    .borrow_mut()
+                    .queue_custom_layout(section, &layout)
+            }
         }
     }
 
-    pub fn queue(&self, section: glyph_brush::Section<'_, Extra>) {
-        self.glyph_brush.borrow_mut().queue(section);
+    pub fn queue_pre_positioned(&self, glyphs: Vec<glyph_brush::SectionGlyph>, extras: Vec<Extra>) {
+        // glyphs may have been moved arbitrarily, so never cull any of them
+        let bounds = glyph_brush::ab_glyph::Rect {
+            min: glyph_brush::ab_glyph::point(f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: glyph_brush::ab_glyph::point(f32::INFINITY, f32::INFINITY),
+        };
+        self.glyph_brush
+            .borrow_mut()
+            .queue_pre_positioned(glyphs, extras, bounds);
     }
 
-    #[allow(unsafe_code)]
-    pub(crate) fn draw_queued<'a>(
+    /// Rasterizes all queued glyphs into the cache, growing it if necessary, and returns the glyph vertices.
+    ///
+    /// If the glyphs don't fit even at the maximum cache size, the queue is dropped and no vertices are returned.
+    ///
+    /// `target` is the projection and the size in physical pixels of the render target,
+    /// used to snap glyphs of [`TextHinting::PixelSnapped`] text to whole pixels.
+    pub(crate) fn process_queued(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: Option<(glam::Mat4, (u32, u32))>,
+    ) -> Vec<TextVertex> {
+        loop {
+            let mut glyphs = 0;
+            let mut pixels = 0;
+
+            let res = self.glyph_brush.borrow_mut().process_queued(
+                |rect, data| {
+                    glyphs += 1;
+                    pixels += u64::from(rect.width()) * u64::from(rect.height());
+
+                    queue.write_texture(
+                        wgpu::ImageCopyTexture {
+                            texture: &self.cache,
+                            mip_level: 0,
+                            origin: wgpu::Origin3d {
+                                x: rect.min[0],
+                                y: rect.min[1],
+                                z: 0,
+                            },
+                            aspect: wgpu::TextureAspect::All,
+                        },
+                        data,
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: Some(NonZeroU32::new(rect.width()).unwrap()),
+                            rows_per_image: None,
+                        },
+                        wgpu::Extent3d {
+                            width: rect.width(),
+                            height: rect.height(),
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                },
+                |glyph| TextVertex {
+                    rect: {
+                        let rect = [
+                            glyph.pixel_coords.min.x,
+                            glyph.pixel_coords.min.y,
+                            glyph.pixel_coords.max.x,
+                            glyph.pixel_coords.max.y,
+                        ];
+                        match target {
+                            Some((proj, size)) if glyph.extra.snap => {
+                                snap_rect(rect, proj * glyph.extra.transform, size)
+                            }
+                            _ => rect,
+                        }
+                    },
+                    uv: [
+                        glyph.tex_coords.min.x,
+                        glyph.tex_coords.min.y,
+                        glyph.tex_coords.max.x,
+                        glyph.tex_coords.max.y,
+                    ],
+                    color: glyph.extra.color.into(),
+                    transform_c0: glyph.extra.transform.to_cols_array_2d()[0],
+                    transform_c1: glyph.extra.transform.to_cols_array_2d()[1],
+                    transform_c2: glyph.extra.transform.to_cols_array_2d()[2],
+                    transform_c3: glyph.extra.transform.to_cols_array_2d()[3],
+                },
+            );
+
+            self.stats.rasterizations += glyphs;
+            self.cached_glyphs += glyphs;
+            self.occupied_pixels += pixels;
+
+            match res {
+                Ok(glyph_brush::BrushAction::Draw(verts)) => return verts,
+                Err(glyph_brush::BrushError::TextureTooSmall { .. }) => {
+                    // grow the texture according to the configured policy
+                    let (w, h) = self.cache_size;
+                    let (max_w, max_h) = self.cache_config.max_size;
+                    let size = match self.cache_config.growth {
+                        FontCacheGrowth::Double => (w * 2, h * 2),
+                        FontCacheGrowth::Linear(step) => (w + step, h + step),
+                    };
+                    let size = (size.0.min(max_w), size.1.min(max_h));
+
+                    if size == self.cache_size {
+                        warn!(
+                            "Queued text does not fit into the glyph cache at its maximum size of {}x{}; dropping it",
+                            max_w, max_h
+                        );
+                        // glyph_brush keeps the queue around on error, so start over with an empty one.
+                        let mut brush = self.glyph_brush.borrow_mut();
+                        *brush = brush.to_builder().build();
+                        return Vec::new();
+                    }
+
+                    self.resize_cache(device, size);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    pub(crate) fn draw_vertices<'a>(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         arenas: &'a FrameArenas,
         pass: &mut wgpu::RenderPass<'a>,
+        verts: &[TextVertex],
     ) {
-        let res = self.glyph_brush.borrow_mut().process_queued(
-            |rect, pixels| {
-                queue.write_texture(
-                    wgpu::ImageCopyTexture {
-                        texture: &self.cache,
-                        mip_level: 0,
-                        origin: wgpu::Origin3d {
-                            x: rect.min[0],
-                            y: rect.min[1],
-                            z: 0,
-                        },
-                        aspect: wgpu::TextureAspect::All,
-                    },
-                    pixels,
-                    wgpu::ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(NonZeroU32::new(rect.width()).unwrap()),
-                        rows_per_image: None,
-                    },
-                    wgpu::Extent3d {
-                        width: rect.width(),
-                        height: rect.height(),
-                        depth_or_array_layers: 1,
-                    },
+        if verts.is_empty() {
+            return;
+        }
+
+        let verts_size = std::mem::size_of_val(verts);
+        let verts_alloc = self.verts.allocate(device, verts_size as u64);
+
+        queue.write_buffer(
+            &verts_alloc.buffer,
+            verts_alloc.offset,
+            bytemuck::cast_slice(verts),
+        );
+
+        let verts_buf = arenas.buffers.alloc(verts_alloc.buffer);
+        pass.set_vertex_buffer(0, verts_buf.slice(verts_alloc.offset..));
+
+        // N.B.: 1 glyph = 4 verts, then n glyphs = n instances.
+        // Also note that vertex data is stepped PER INSTANCE.
+        // Therefore we only store ONE VERTEX for ONE GLYPH (and in the vertex shader we generate the quad vertices on the fly).
+        pass.draw(0..4, 0..verts.len() as u32);
+    }
+
+    pub fn free(&mut self) {
+        self.verts.free();
+        self.last_stats = std::mem::take(&mut self.stats);
+    }
+}
+
+// The width and height of the RGBA atlas pages color glyphs are packed into.
+const COLOR_PAGE_SIZE: u32 = 1024;
+
+/// Color bitmap glyphs, decoded into an RGBA atlas page of their own, as the glyph cache only has
+/// coverage. When the page is full, or the glyph cache is rebuilt, every glyph is evicted and
+/// packed into a new page as it's drawn again.
+#[derive(Default)]
+struct ColorGlyphCache {
+    // the page glyphs are packed into, made when the first one is
+    page: Option<(Image, ShelfPacker)>,
+    // `None` for glyphs which aren't color glyphs, not to look them up again
+    glyphs: HashMap<(FontId, GlyphId, u16), Option<ColorGlyph>>,
+}
+
+impl ColorGlyphCache {
+    fn get(
+        &mut self,
+        wgpu: &WgpuContext,
+        key: (FontId, GlyphId, u16),
+        font: &FontArc,
+    ) -> Option<&ColorGlyph> {
+        if !self.glyphs.contains_key(&key) {
+            let glyph = self.pack(wgpu, font, key.1, key.2);
+            let _ = self.glyphs.insert(key, glyph);
+        }
+        self.glyphs[&key].as_ref()
+    }
+
+    fn pack(
+        &mut self,
+        wgpu: &WgpuContext,
+        font: &FontArc,
+        id: GlyphId,
+        size: u16,
+    ) -> Option<ColorGlyph> {
+        let raster = font.glyph_raster_image2(id, size)?;
+        if !matches!(raster.format, GlyphImageFormat::Png) {
+            return None;
+        }
+
+        let decoded = match ::image::load_from_memory(raster.data) {
+            Ok(decoded) => decoded.to_rgba8(),
+            Err(e) => {
+                warn!("Failed to decode color glyph {:?}: {}", id, e);
+                return None;
+            }
+        };
+        let (width, height) = decoded.dimensions();
+
+        // a pixel apart, so that sampling at the edges doesn't pick up the neighbours
+        let (padded_width, padded_height) = (width + 1, height + 1);
+        if padded_width > COLOR_PAGE_SIZE || padded_height > COLOR_PAGE_SIZE {
+            warn!(
+                "Color glyph {:?} of {}x{} pixels is too large for its atlas of {}x{}",
+                id, width, height, COLOR_PAGE_SIZE, COLOR_PAGE_SIZE
+            );
+            return None;
+        }
+        let (x, y) = match self
+            .page
+            .as_mut()
+            .and_then(|(_, packer)| packer.pack(padded_width, padded_height))
+        {
+            Some(position) => position,
+            None => {
+                // full, or not made yet; the old page lives on in draws already made with it
+                self.clear();
+                let mut packer = ShelfPacker::new((COLOR_PAGE_SIZE, COLOR_PAGE_SIZE));
+                let position = packer.pack(padded_width, padded_height)?;
+                let page = Image::new(
+                    wgpu,
+                    ImageFormat::Rgba8UnormSrgb,
+                    COLOR_PAGE_SIZE,
+                    COLOR_PAGE_SIZE,
+                    1,
+                    wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
                 );
+                self.page = Some((page, packer));
+                position
+            }
+        };
+        let page = &self.page.as_ref()?.0;
+
+        wgpu.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &page.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
             },
-            |glyph| TextVertex {
-                rect: [
-                    glyph.pixel_coords.min.x,
-                    glyph.pixel_coords.min.y,
-                    glyph.pixel_coords.max.x,
-                    glyph.pixel_coords.max.y,
-                ],
-                uv: [
-                    glyph.tex_coords.min.x,
-                    glyph.tex_coords.min.y,
-                    glyph.tex_coords.max.x,
-                    glyph.tex_coords.max.y,
-                ],
-                color: glyph.extra.color.into(),
-                transform_c0: glyph.extra.transform.to_cols_array_2d()[0],
-                transform_c1: glyph.extra.transform.to_cols_array_2d()[1],
-                transform_c2: glyph.extra.transform.to_cols_array_2d()[2],
-                transform_c3: glyph.extra.transform.to_cols_array_2d()[3],
+            decoded.as_raw(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(NonZeroU32::new(4 * width).unwrap()),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
             },
         );
 
-        match res {
-            Ok(glyph_brush::BrushAction::Draw(verts)) => {
-                let verts_size = verts.len() * std::mem::size_of::<TextVertex>();
-                let verts_alloc = self.verts.allocate(device, verts_size as u64);
+        let page_size = COLOR_PAGE_SIZE as f32;
+        Some(ColorGlyph {
+            page: page.clone(),
+            src: Rect::new(
+                x as f32 / page_size,
+                y as f32 / page_size,
+                width as f32 / page_size,
+                height as f32 / page_size,
+            ),
+            // the raster origin is the bottom left corner relative to the baseline, with y pointing up
+            origin: glyph_brush::ab_glyph::point(
+                raster.origin.x,
+                -(raster.origin.y + f32::from(raster.height)),
+            ),
+            pixels_per_em: f32::from(raster.pixels_per_em.max(1)),
+        })
+    }
 
-                queue.write_buffer(
-                    &verts_alloc.buffer,
-                    verts_alloc.offset,
-                    bytemuck::cast_slice(verts.as_slice()),
-                );
+    /// Evicts every glyph, and the page they're in.
+    fn clear(&mut self) {
+        self.page = None;
+        self.glyphs.clear();
+    }
+}
 
-                let verts_buf = arenas.buffers.alloc(verts_alloc.buffer);
-                pass.set_vertex_buffer(0, verts_buf.slice(verts_alloc.offset..));
+/// A decoded color bitmap glyph.
+struct ColorGlyph {
+    // the atlas page it's in, and where, in UV coordinates
+    page: Image,
+    src: Rect,
+    // offset of the image's top left from the glyph position, in pixels at the strike's size
+    origin: glyph_brush::ab_glyph::Point,
+    pixels_per_em: f32,
+}
 
-                // N.B.: 1 glyph = 4 verts, then n glyphs = n instances.
-                // Also note that vertex data is stepped PER INSTANCE.
-                // Therefore we only store ONE VERTEX for ONE GLYPH (and in the vertex shader we generate the quad vertices on the fly).
-                pass.draw(0..4, 0..verts.len() as u32);
-            }
-            Err(glyph_brush::BrushError::TextureTooSmall { suggested }) => {
-                // increase texture size as recommended by glyph_brush
+/// Packs rectangles into an area in rows ("shelves") from the top, each from left to right and
+/// as high as its highest rectangle.
+#[derive(Debug)]
+struct ShelfPacker {
+    size: (u32, u32),
+    // where the next rectangle goes, and the height of the row it's in
+    x: u32,
+    y: u32,
+    shelf_height: u32,
+}
 
-                self.cache_size = suggested;
-                self.glyph_brush
-                    .borrow_mut()
-                    .resize_texture(self.cache_size.0, self.cache_size.1);
-
-                self.cache = ArcTexture::new(device.create_texture(&wgpu::TextureDescriptor {
-                    label: None,
-                    size: wgpu::Extent3d {
-                        width: self.cache_size.0,
-                        height: self.cache_size.1,
-                        depth_or_array_layers: 1,
-                    },
-                    mip_level_count: 1,
-                    sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2,
-                    format: wgpu::TextureFormat::R8Unorm,
-                    usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
-                }));
-
-                self.cache_view = ArcTextureView::new(
-                    self.cache
-                        .create_view(&wgpu::TextureViewDescriptor::default()),
-                );
+impl ShelfPacker {
+    fn new(size: (u32, u32)) -> Self {
+        ShelfPacker {
+            size,
+            x: 0,
+            y: 0,
+            shelf_height: 0,
+        }
+    }
 
-                let cache_bind =
-                    BindGroupBuilder::new().image(&self.cache_view, wgpu::ShaderStages::FRAGMENT);
-                self.cache_bind =
-                    ArcBindGroup::new(device.create_bind_group(&wgpu::BindGroupDescriptor {
-                        label: None,
-                        layout: &self.cache_bind_layout,
-                        entries: cache_bind.entries(),
-                    }));
+    /// Returns where the top left of a rectangle of `width` and `height` goes, or `None` if it
+    /// doesn't fit in what's left.
+    fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > self.size.0 {
+            return None;
+        }
+        if self.x + width > self.size.0 {
+            // on to the next row
+            self.y += self.shelf_height;
+            self.x = 0;
+            self.shelf_height = 0;
+        }
+        if self.y + height > self.size.1 {
+            return None;
+        }
+        let position = (self.x, self.y);
+        self.x += width;
+        self.shelf_height = self.shelf_height.max(height);
+        Some(position)
+    }
+}
 
-                self.draw_queued(device, queue, arenas, pass)
-            }
-            _ => unreachable!(),
+/// Moves a glyph rect so that its top-left corner lands on a whole pixel of the render target.
+fn snap_rect(rect: [f32; 4], transform: glam::Mat4, (width, height): (u32, u32)) -> [f32; 4] {
+    let (width, height) = (width as f32, height as f32);
+    let clip = transform * glam::vec4(rect[0], rect[1], 0., 1.);
+    if clip.w == 0. {
+        return rect;
+    }
+    let ndc = clip.truncate() / clip.w;
+
+    // NDC -> pixels, round, then back again
+    let x = ((ndc.x + 1.) * 0.5 * width).round() / width * 2. - 1.;
+    let y = 1. - ((1. - ndc.y) * 0.5 * height).round() / height * 2.;
+
+    let inverse = transform.inverse();
+    if !inverse.is_finite() {
+        return rect;
+    }
+    let snapped = inverse * glam::vec4(x, y, ndc.z, 1.);
+    let dx = snapped.x / snapped.w - rect[0];
+    let dy = snapped.y / snapped.w - rect[1];
+
+    [rect[0] + dx, rect[1] + dy, rect[2] + dx, rect[3] + dy]
+}
+
+/// Layout which rounds the position of every glyph to whole pixels,
+/// so that glyphs are rasterized without a subpixel offset.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+struct PixelSnapped(glyph_brush::Layout<glyph_brush::BuiltInLineBreaker>);
+
+impl GlyphPositioner for PixelSnapped {
+    fn calculate_glyphs<F, S>(
+        &self,
+        fonts: &[F],
+        geometry: &glyph_brush::SectionGeometry,
+        sections: &[S],
+    ) -> Vec<glyph_brush::SectionGlyph>
+    where
+        F: glyph_brush::ab_glyph::Font,
+        S: glyph_brush::ToSectionText,
+    {
+        let mut glyphs = self.0.calculate_glyphs(fonts, geometry, sections);
+        for glyph in &mut glyphs {
+            glyph.glyph.position.x = glyph.glyph.position.x.round();
+            glyph.glyph.position.y = glyph.glyph.position.y.round();
         }
+        glyphs
     }
 
-    pub fn free(&mut self) {
-        self.verts.free();
+    fn bounds_rect(&self, geometry: &glyph_brush::SectionGeometry) -> glyph_brush::ab_glyph::Rect {
+        self.0.bounds_rect(geometry)
     }
 }
 
@@ -204,6 +638,7 @@ impl TextRenderer {
 pub(crate) struct Extra {
     pub color: LinearColor,
     pub transform: glam::Mat4,
+    pub snap: bool,
 }
 
 // hash is impl'd via OrderedFloat, but we still want to preserve the types
@@ -223,6 +658,8 @@ impl std::hash::Hash for Extra {
             .to_cols_array()
             .into_iter()
             .for_each(|x| OrderedFloat::from(x).hash(state));
+
+        self.snap.hash(state);
     }
 }
 
@@ -290,3 +727,22 @@ impl TextVertex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelf_packer() {
+        let mut packer = ShelfPacker::new((10, 10));
+        assert_eq!(packer.pack(4, 3), Some((0, 0)));
+        assert_eq!(packer.pack(4, 5), Some((4, 0)));
+        // the next row starts below the highest of the row before
+        assert_eq!(packer.pack(4, 2), Some((0, 5)));
+        assert_eq!(packer.pack(11, 1), None);
+        assert_eq!(packer.pack(6, 4), Some((4, 5)));
+        // full
+        assert_eq!(packer.pack(1, 2), None);
+        assert_eq!(packer.pack(1, 1), Some((0, 9)));
+    }
+}
@@ -3,7 +3,7 @@ use super::{
     gpu::arc::{ArcTexture, ArcTextureView},
     Canvas, Color, Draw, DrawParam, Drawable, Rect, WgpuContext,
 };
-use crate::{context::Has, Context, GameError, GameResult};
+use crate::{context::Has, GameError, GameResult, ResultExt};
 use image::ImageEncoder;
 use std::path::Path;
 use std::{io::Read, num::NonZeroU32};
@@ -16,6 +16,45 @@ pub type ImageFormat = wgpu::TextureFormat;
 /// Describes the format of an encoded image.
 pub type ImageEncodingFormat = ::image::ImageFormat;
 
+/// An image decoded into RGBA pixels, in sRGB, which can be made on any thread, e.g. by an
+/// [`AssetLoader`](crate::loading::AssetLoader), and only needs uploading to the GPU with
+/// [`Image::from_prepared`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedImage {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl PreparedImage {
+    /// Decodes an encoded image (e.g. PNG or JPEG).
+    pub fn from_bytes(encoded: &[u8]) -> GameResult<Self> {
+        let decoded = image::load_from_memory(encoded)
+            .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))?;
+        let rgba8 = decoded.to_rgba8();
+        Ok(PreparedImage {
+            width: rgba8.width(),
+            height: rgba8.height(),
+            pixels: rgba8.into_raw(),
+        })
+    }
+
+    /// Returns the width of the image.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the image.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the pixels of the image, 4 bytes each, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
 /// Handle to an image stored in GPU memory.
 #[derive(Debug, Clone)]
 pub struct Image {
@@ -121,30 +160,75 @@ impl Image {
     #[allow(unused_results)]
     pub fn from_path(gfx: &impl Has<GraphicsContext>, path: impl AsRef<Path>) -> GameResult<Self> {
         let gfx = gfx.retrieve();
+        let path = path.as_ref();
 
         let mut encoded = Vec::new();
-        gfx.fs.open(path)?.read_to_end(&mut encoded)?;
+        let _ = gfx
+            .fs
+            .open(path)?
+            .read_to_end(&mut encoded)
+            .with_context(|| format!("reading the image {:?}", path))?;
 
         Self::from_bytes(gfx, encoded.as_slice())
+            .with_context(|| format!("loading the image {:?}", path))
     }
 
     /// Creates a new image initialized with pixel data from a given encoded image (e.g. PNG or JPEG)
     pub fn from_bytes(gfx: &impl Has<GraphicsContext>, encoded: &[u8]) -> Result<Image, GameError> {
-        let decoded = image::load_from_memory(encoded)
-            .map_err(|_| GameError::ResourceLoadError(String::from("failed to load image")))?;
-        let rgba8 = decoded.to_rgba8();
-        let (width, height) = (rgba8.width(), rgba8.height());
+        Ok(Self::from_prepared(
+            gfx,
+            &PreparedImage::from_bytes(encoded)?,
+        ))
+    }
 
-        Ok(Self::from_pixels(
+    /// Creates a new image from one decoded already, e.g. on another thread, which only
+    /// uploads it to the GPU.
+    pub fn from_prepared(gfx: &impl Has<GraphicsContext>, prepared: &PreparedImage) -> Self {
+        Self::from_pixels(
             gfx,
-            rgba8.as_ref(),
+            &prepared.pixels,
             ImageFormat::Rgba8UnormSrgb,
-            width,
-            height,
-        ))
+            prepared.width,
+            prepared.height,
+        )
     }
 
-    fn new(
+    /// Creates a new image from one of the `image` crate, e.g. generated or processed with it.
+    ///
+    /// Images with integer channels, in 8 or 16 bits, of luma or RGB with or without alpha,
+    /// are converted to 8-bit sRGB RGBA, [`ImageFormat::Rgba8UnormSrgb`], as ggez draws images
+    /// in. Luma becomes grey, and images without alpha are opaque. Images with float channels
+    /// are converted to [`ImageFormat::Rgba32Float`], keeping their range.
+    ///
+    /// The `image` crate is re-exported as [`ggez::image_crate`](crate::image_crate), so the
+    /// image is of the version ggez uses.
+    pub fn from_dynamic_image(
+        gfx: &impl Has<GraphicsContext>,
+        image: &image::DynamicImage,
+    ) -> Self {
+        let (width, height) = (image.width(), image.height());
+        match image {
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => {
+                let rgba = image.to_rgba32f();
+                Self::from_pixels(
+                    gfx,
+                    bytemuck::cast_slice(rgba.as_raw()),
+                    ImageFormat::Rgba32Float,
+                    width,
+                    height,
+                )
+            }
+            _ => Self::from_pixels(
+                gfx,
+                image.to_rgba8().as_raw(),
+                ImageFormat::Rgba8UnormSrgb,
+                width,
+                height,
+            ),
+        }
+    }
+
+    pub(crate) fn new(
         wgpu: &WgpuContext,
         format: ImageFormat,
         width: u32,
@@ -211,10 +295,14 @@ impl Image {
         }
 
         let block_size = self.format.describe().block_size as u64;
+        // rows are copied at a multiple of the alignment wgpu requires, and cut to size after
+        let row_size = block_size * self.width as u64;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+        let padded_row_size = row_size + (align - row_size % align) % align;
 
         let buffer = gfx.wgpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: block_size * self.width as u64 * self.height as u64,
+            size: padded_row_size * self.height as u64,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         });
@@ -230,9 +318,7 @@ impl Image {
                     buffer: &buffer,
                     layout: wgpu::ImageDataLayout {
                         offset: 0,
-                        bytes_per_row: Some(
-                            NonZeroU32::new(block_size as u32 * self.width).unwrap(),
-                        ),
+                        bytes_per_row: Some(NonZeroU32::new(padded_row_size as u32).unwrap()),
                         rows_per_image: None,
                     },
                 },
@@ -258,16 +344,111 @@ impl Image {
             .expect("All senders dropped, this should not be possible.");
         map_result?;
 
-        let out = buffer.slice(..).get_mapped_range().to_vec();
+        let out = buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks_exact(padded_row_size as usize)
+            .flat_map(|row| &row[..row_size as usize])
+            .copied()
+            .collect();
         Ok(out)
     }
 
+    /// Reads the pixels of this image back from the GPU into an image of the `image` crate,
+    /// whose type follows the [format](Image::format): RGBA for the 8-bit RGBA and BGRA formats,
+    /// luma for the one channel formats and luma with alpha for the two channel ones, in 8 or
+    /// 16 bits, and 16-bit or float RGBA for those formats.
+    ///
+    /// Returns an error for the other formats, and like [`Image::to_pixels`], for multisampled
+    /// images.
+    ///
+    /// **This is a very expensive operation - call sparingly.**
+    pub fn to_dynamic_image(
+        &self,
+        gfx: &impl Has<GraphicsContext>,
+    ) -> GameResult<image::DynamicImage> {
+        use image::{DynamicImage, ImageBuffer, Pixel};
+
+        fn unsupported<T>(format: ImageFormat) -> GameResult<T> {
+            Err(GameError::RenderError(format!(
+                "cannot convert an image of the {:?} GPU image format to a DynamicImage",
+                format
+            )))
+        }
+
+        // checked before reading the pixels back, which is the expensive part
+        match self.format {
+            ImageFormat::Rgba8Unorm
+            | ImageFormat::Rgba8UnormSrgb
+            | ImageFormat::Bgra8Unorm
+            | ImageFormat::Bgra8UnormSrgb
+            | ImageFormat::R8Unorm
+            | ImageFormat::Rg8Unorm
+            | ImageFormat::R16Unorm
+            | ImageFormat::Rg16Unorm
+            | ImageFormat::Rgba16Unorm
+            | ImageFormat::Rgba32Float => {}
+            format => return unsupported(format),
+        }
+
+        let mut pixels = self.to_pixels(gfx)?;
+        let (width, height) = (self.width, self.height);
+        fn buffer<P: Pixel>(
+            width: u32,
+            height: u32,
+            subpixels: Vec<P::Subpixel>,
+        ) -> GameResult<ImageBuffer<P, Vec<P::Subpixel>>> {
+            ImageBuffer::from_raw(width, height, subpixels).ok_or_else(|| {
+                GameError::RenderError(String::from(
+                    "the pixels read back don't fill the image, this should not be possible",
+                ))
+            })
+        }
+        let wide = |pixels: &[u8]| -> Vec<u16> {
+            pixels
+                .chunks_exact(2)
+                .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+                .collect()
+        };
+
+        Ok(match self.format {
+            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {
+                DynamicImage::ImageRgba8(buffer(width, height, pixels)?)
+            }
+            ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                DynamicImage::ImageRgba8(buffer(width, height, pixels)?)
+            }
+            ImageFormat::R8Unorm => DynamicImage::ImageLuma8(buffer(width, height, pixels)?),
+            ImageFormat::Rg8Unorm => DynamicImage::ImageLumaA8(buffer(width, height, pixels)?),
+            ImageFormat::R16Unorm => {
+                DynamicImage::ImageLuma16(buffer(width, height, wide(&pixels))?)
+            }
+            ImageFormat::Rg16Unorm => {
+                DynamicImage::ImageLumaA16(buffer(width, height, wide(&pixels))?)
+            }
+            ImageFormat::Rgba16Unorm => {
+                DynamicImage::ImageRgba16(buffer(width, height, wide(&pixels))?)
+            }
+            ImageFormat::Rgba32Float => {
+                let floats = pixels
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect();
+                DynamicImage::ImageRgba32F(buffer(width, height, floats)?)
+            }
+            format => return unsupported(format),
+        })
+    }
+
     /// Encodes the `ImageView` to the given file format and return the encoded bytes.
     ///
     /// **This is a very expensive operation - call sparingly.**
     pub fn encode(
         &self,
-        ctx: &Context,
+        gfx: &impl Has<GraphicsContext>,
         format: ImageEncodingFormat,
         path: impl AsRef<std::path::Path>,
     ) -> GameResult {
@@ -283,8 +464,8 @@ impl Image {
             }
         };
 
-        let pixels = self.to_pixels(ctx)?;
-        let f = ctx.fs.create(path)?;
+        let pixels = self.to_pixels(gfx)?;
+        let f = gfx.retrieve().fs.create(path)?;
         let writer = &mut std::io::BufWriter::new(f);
 
         match format {
@@ -406,7 +587,7 @@ impl ScreenImage {
 
     fn size(gfx: &impl Has<GraphicsContext>, (width, height): (f32, f32)) -> (u32, u32) {
         let gfx = gfx.retrieve();
-        let size = gfx.window.inner_size();
+        let size = gfx.inner_size();
         let width = (size.width as f32 * width) as u32;
         let height = (size.height as f32 * height) as u32;
         (width.max(1), height.max(1))
@@ -422,3 +603,46 @@ impl ScreenImage {
         Image::new_canvas_image(gfx, format, width, height, samples)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::headless_context;
+    use crate::ContextBuilder;
+    use image::{DynamicImage, GrayImage, Rgba32FImage, RgbaImage};
+
+    #[test]
+    fn dynamic_image_round_trip() {
+        let ctx = match headless_context(ContextBuilder::new("test", "ggez").with_conf_file(false))
+        {
+            Some(ctx) => ctx,
+            None => return,
+        };
+
+        let rgba = RgbaImage::from_fn(64, 4, |x, y| image::Rgba([x as u8 * 4, y as u8, 7, 200]));
+        let image = Image::from_dynamic_image(&ctx, &DynamicImage::ImageRgba8(rgba.clone()));
+        assert_eq!(image.format(), ImageFormat::Rgba8UnormSrgb);
+        match image.to_dynamic_image(&ctx).unwrap() {
+            DynamicImage::ImageRgba8(read) => assert_eq!(read, rgba),
+            read => panic!("read back as {:?}", read.color()),
+        }
+
+        // luma is grey and opaque
+        let gray = GrayImage::from_fn(64, 4, |x, _| image::Luma([x as u8]));
+        let image = Image::from_dynamic_image(&ctx, &DynamicImage::ImageLuma8(gray));
+        let read = image.to_dynamic_image(&ctx).unwrap().into_rgba8();
+        assert_eq!(read.get_pixel(10, 2), &image::Rgba([10, 10, 10, 255]));
+
+        // floats keep their range
+        let floats = Rgba32FImage::from_fn(16, 4, |x, _| image::Rgba([x as f32, -1.0, 0.5, 1.0]));
+        let image = Image::from_dynamic_image(&ctx, &DynamicImage::ImageRgba32F(floats.clone()));
+        assert_eq!(image.format(), ImageFormat::Rgba32Float);
+        match image.to_dynamic_image(&ctx).unwrap() {
+            DynamicImage::ImageRgba32F(read) => assert_eq!(read, floats),
+            read => panic!("read back as {:?}", read.color()),
+        }
+
+        let half = Image::new_canvas_image(&ctx, ImageFormat::Rgba16Float, 32, 4, 1);
+        assert!(half.to_dynamic_image(&ctx).is_err());
+    }
+}
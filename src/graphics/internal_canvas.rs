@@ -1,12 +1,12 @@
 use super::{
-    context::{FrameArenas, GraphicsContext},
+    context::{FrameArenas, FrameStats, GraphicsContext},
     draw::{DrawParam, DrawUniforms},
     gpu::{
         arc::{ArcBindGroup, ArcBindGroupLayout, ArcBuffer, ArcShaderModule, ArcTextureView},
         bind_group::{BindGroupBuilder, BindGroupCache, BindGroupLayoutBuilder},
         growing::GrowingBufferArena,
         pipeline::{PipelineCache, RenderPipelineInfo},
-        text::{TextRenderer, TextVertex},
+        text::{Extra, TextRenderer, TextVertex},
     },
     image::Image,
     mesh::{Mesh, Vertex},
@@ -29,6 +29,8 @@ pub struct InternalCanvas<'a> {
     text_renderer: &'a mut TextRenderer,
     fonts: &'a HashMap<String, glyph_brush::FontId>,
     uniform_arena: &'a mut GrowingBufferArena,
+    rect_mesh: &'a Mesh,
+    stats: &'a mut FrameStats,
 
     shader: Shader,
     shader_bind_group: Option<(&'a wgpu::BindGroup, ArcBindGroupLayout)>,
@@ -51,6 +53,7 @@ pub struct InternalCanvas<'a> {
     text_sm: ArcShaderModule,
 
     transform: glam::Mat4,
+    target_size: (u32, u32),
     curr_image: Option<ArcTextureView>,
     curr_sampler: Sampler,
     next_sampler: Sampler,
@@ -67,7 +70,8 @@ impl<'a> InternalCanvas<'a> {
             return Err(GameError::RenderError(String::from("non-MSAA rendering requires an image with exactly 1 sample, for this image use Canvas::from_msaa instead")));
         }
 
-        Self::new(gfx, 1, image.format(), |cmd| {
+        let size = (image.width(), image.height());
+        Self::new(gfx, 1, image.format(), size, |cmd| {
             cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -110,7 +114,9 @@ impl<'a> InternalCanvas<'a> {
             )));
         }
 
-        Self::new(gfx, msaa_image.samples(), msaa_image.format(), |cmd| {
+        let size = (msaa_image.width(), msaa_image.height());
+        let samples = msaa_image.samples();
+        Self::new(gfx, samples, msaa_image.format(), size, |cmd| {
             cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -133,6 +139,7 @@ impl<'a> InternalCanvas<'a> {
         gfx: &'a mut GraphicsContext,
         samples: u32,
         format: wgpu::TextureFormat,
+        target_size: (u32, u32),
         create_pass: impl FnOnce(&'a mut wgpu::CommandEncoder) -> wgpu::RenderPass<'a>,
     ) -> GameResult<Self> {
         if gfx.fcx.is_none() {
@@ -150,6 +157,8 @@ impl<'a> InternalCanvas<'a> {
         let text_renderer = &mut gfx.text;
         let fonts = &gfx.fonts;
         let uniform_arena = &mut gfx.uniform_arena;
+        let rect_mesh = &gfx.rect_mesh;
+        let stats = &mut gfx.drawing_stats;
 
         let (arenas, mut pass) = {
             let fcx = gfx.fcx.as_mut().unwrap(/* see above */);
@@ -216,6 +225,8 @@ impl<'a> InternalCanvas<'a> {
             text_renderer,
             fonts,
             uniform_arena,
+            rect_mesh,
+            stats,
 
             shader,
             shader_bind_group: None,
@@ -238,6 +249,7 @@ impl<'a> InternalCanvas<'a> {
             text_sm: gfx.text_shader.clone(),
 
             transform,
+            target_size,
             curr_image: None,
             curr_sampler: Sampler::default(),
             next_sampler: Sampler::default(),
@@ -356,6 +368,8 @@ impl<'a> InternalCanvas<'a> {
             .set_index_buffer(mesh.inds.slice(..), wgpu::IndexFormat::Uint32);
 
         self.pass.draw_indexed(0..mesh.index_count as _, 0, 0..1);
+        self.stats.draw_calls += 1;
+        self.stats.instances += 1;
     }
 
     pub fn draw_mesh_instances(
@@ -441,6 +455,8 @@ impl<'a> InternalCanvas<'a> {
 
         self.pass
             .draw_indexed(0..mesh.index_count as _, 0, 0..instances.len as _);
+        self.stats.draw_calls += 1;
+        self.stats.instances += instances.len;
 
         Ok(())
     }
@@ -455,17 +471,36 @@ impl<'a> InternalCanvas<'a> {
             }
         }
 
-        self.text_renderer
-            .queue(text.as_section(self.fonts, param)?);
+        let section = text.as_section(self.text_renderer, self.fonts, param)?;
+        self.text_renderer.queue(&section, text.hinting());
 
         self.set_image(self.text_renderer.cache_view.clone());
         self.pass.set_bind_group(0, self.text_uniforms, &[]);
 
         self.queuing_text = true;
 
+        if !self.text_renderer.color_fonts.is_empty() {
+            for (image, param) in self.text_renderer.color_glyphs(self.wgpu, &section) {
+                self.draw_mesh(self.rect_mesh, &image, param, true);
+            }
+        }
+
         Ok(())
     }
 
+    pub fn draw_positioned_text(
+        &mut self,
+        glyphs: Vec<glyph_brush::SectionGlyph>,
+        extras: Vec<Extra>,
+    ) {
+        self.text_renderer.queue_pre_positioned(glyphs, extras);
+
+        self.set_image(self.text_renderer.cache_view.clone());
+        self.pass.set_bind_group(0, self.text_uniforms, &[]);
+
+        self.queuing_text = true;
+    }
+
     fn flush_text(&mut self) {
         if self.queuing_text {
             self.queuing_text = false;
@@ -475,12 +510,24 @@ impl<'a> InternalCanvas<'a> {
                 self.set_blend_mode(BlendMode::PREMULTIPLIED);
             }
             self.update_pipeline(ShaderType::Text);
-            self.text_renderer.draw_queued(
+            let verts = self.text_renderer.process_queued(
+                &self.wgpu.device,
+                &self.wgpu.queue,
+                Some((self.transform, self.target_size)),
+            );
+            // processing may have grown the glyph cache, replacing its texture
+            self.set_image(self.text_renderer.cache_view.clone());
+            self.text_renderer.draw_vertices(
                 &self.wgpu.device,
                 &self.wgpu.queue,
                 self.arenas,
                 &mut self.pass,
+                &verts,
             );
+            if !verts.is_empty() {
+                self.stats.draw_calls += 1;
+                self.stats.instances += verts.len() as u32;
+            }
             if premul {
                 self.set_blend_mode(BlendMode::ALPHA);
             }
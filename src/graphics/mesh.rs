@@ -3,7 +3,7 @@ use super::{
     Drawable, LinearColor, Rect, WgpuContext,
 };
 use crate::{context::Has, GameError, GameResult};
-use lyon::{math::Point as LPoint, path::Polygon, tessellation as tess};
+use lyon::{math::Point as LPoint, path::iterator::FromPolyline, tessellation as tess};
 use wgpu::util::DeviceExt;
 
 /// Vertex format uploaded to vertex buffers.
@@ -501,28 +501,22 @@ impl MeshBuilder {
         {
             assert!(points.len() > 1);
             let buffers = &mut self.buffer;
-            let points: Vec<LPoint> = points
-                .iter()
-                .cloned()
-                .map(|p| {
-                    let mint_point: mint::Point2<f32> = p.into();
-                    tess::math::point(mint_point.x, mint_point.y)
-                })
-                .collect();
-            let polygon = Polygon {
-                points: &points,
-                closed: is_closed,
-            };
+            // converted as they're tessellated, without collecting them first
+            let points = points.iter().cloned().map(|p| {
+                let mint_point: mint::Point2<f32> = p.into();
+                tess::math::point(mint_point.x, mint_point.y)
+            });
+            let events = FromPolyline::new(is_closed, points);
             match mode {
                 DrawMode::Fill(options) => {
                     let builder = &mut tess::BuffersBuilder::new(buffers, vb);
                     let tessellator = &mut tess::FillTessellator::new();
-                    tessellator.tessellate_polygon(polygon, &options, builder)?;
+                    tessellator.tessellate(events, &options, builder)?;
                 }
                 DrawMode::Stroke(options) => {
                     let builder = &mut tess::BuffersBuilder::new(buffers, vb);
                     let tessellator = &mut tess::StrokeTessellator::new();
-                    tessellator.tessellate_polygon(polygon, &options, builder)?;
+                    tessellator.tessellate(events, &options, builder)?;
                 }
             };
         }
@@ -18,11 +18,47 @@
 //! The module also handles the creation of [`Image`]s and other drawable objects and the screen
 //! coordinate system / projection matrix through [`Canvas`].
 //!
+//! Points, vectors and matrices are taken as anything that converts into the matching
+//! [`mint`] type, so the types of any math library with `mint` support, such as the
+//! re-exported [`glam`](crate::glam) or `nalgebra`, can be passed directly, and [`Rect`]s and
+//! [`Color`]s convert to and from them too:
+//!
+//! ```rust
+//! # use ggez::graphics::*;
+//! # use ggez::{Context, GameResult};
+//! use nalgebra::{Point2, Vector2, Vector4};
+//!
+//! # fn t(ctx: &mut Context, canvas: &mut Canvas, image: Image) -> GameResult {
+//! let body = Point2::new(120.0, 80.0);
+//! let velocity = Vector2::new(3.0, -1.5);
+//! let tint: mint::Vector4<f32> = Vector4::new(1.0, 0.5, 0.0, 1.0).into();
+//!
+//! canvas.draw(
+//!     &image,
+//!     DrawParam::new()
+//!         .dest(body)
+//!         .scale(Vector2::new(2.0, 2.0))
+//!         .color(tint),
+//! );
+//!
+//! let trail = [body, body - velocity * 4.0, body - velocity * 8.0];
+//! let trail = Mesh::new_line(ctx, &trail, 2.0, Color::WHITE)?;
+//! canvas.draw(&trail, DrawParam::new());
+//!
+//! let bounds = Rect::from_corners(body - Vector2::new(8.0, 8.0), body + Vector2::new(8.0, 8.0));
+//! let [top_left, _]: [mint::Point2<f32>; 2] = bounds.into();
+//! assert_eq!(Point2::from(top_left), Point2::new(112.0, 72.0));
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! [custom shader]:Canvas::set_shader
 //! [blend mode]:Canvas::set_blend_mode
 
+pub(crate) mod camera;
 pub(crate) mod canvas;
 pub(crate) mod context;
+pub(crate) mod debug_draw;
 pub(crate) mod draw;
 pub(crate) mod gpu;
 pub(crate) mod image;
@@ -33,11 +69,12 @@ pub(crate) mod sampler;
 pub(crate) mod shader;
 pub(crate) mod text;
 mod types;
+pub(crate) mod window;
 
 pub use lyon::tessellation::{FillOptions, FillRule, LineCap, LineJoin, StrokeOptions};
 pub use {
-    self::image::*, canvas::*, context::*, draw::*, instance::*, mesh::*, sampler::*, shader::*,
-    text::*, types::*,
+    self::image::*, camera::*, canvas::*, context::*, debug_draw::*, draw::*, instance::*, mesh::*,
+    sampler::*, shader::*, text::*, types::*, window::*,
 };
 
 /// Applies `DrawParam` to `Rect`.
@@ -101,7 +138,7 @@ pub fn set_window_icon<P: AsRef<Path>>(
 #[deprecated(since = "0.8.0", note = "Use `ctx.gfx.set_window_position` instead.")]
 pub fn set_window_position(
     ctx: &impl Has<GraphicsContext>,
-    position: impl Into<winit::dpi::Position>,
+    position: impl Into<crate::winit::dpi::Position>,
 ) -> GameResult {
     let gfx: &GraphicsContext = ctx.retrieve();
     gfx.set_window_position(position)
@@ -109,7 +146,7 @@ pub fn set_window_position(
 
 /// Returns a reference to the Winit window.
 #[deprecated(since = "0.8.0", note = "Use `ctx.gfx.window` instead.")]
-pub fn window(ctx: &impl Has<GraphicsContext>) -> &winit::window::Window {
+pub fn window(ctx: &impl Has<GraphicsContext>) -> &crate::winit::window::Window {
     let gfx: &GraphicsContext = ctx.retrieve();
     gfx.window()
 }
@@ -2,7 +2,11 @@ use super::gpu::arc::ArcSampler;
 use std::collections::HashMap;
 
 /// Sampler state that is used when sampling images on the GPU.
+///
+/// With the `serde` feature it's serialized as a struct of its fields, with the modes by name,
+/// e.g. `{ "clamp_u": "Clamp", ..., "mag": "Nearest", "min": "Linear" }`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sampler {
     /// Clamping mode in the U (x) direction.
     pub clamp_u: ClampMode,
@@ -76,6 +80,7 @@ impl From<FilterMode> for Sampler {
 
 /// Describes the clamping mode of a sampler, used when the shader writes to sample outside of texture boundaries.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClampMode {
     /// The corresponding texel at the nearest edge is sampled.
     Clamp,
@@ -97,6 +102,7 @@ impl From<ClampMode> for wgpu::AddressMode {
 
 /// Describes the filter mode of a sampler, used when magnification or minification of a texture occurs (i.e. scaling).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FilterMode {
     /// The nearest texel is sampled.
     Nearest,
@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 
 use crate::{
     context::{Has, HasMut},
-    GameError, GameResult,
+    GameError, GameResult, ResultExt,
 };
 
 use super::{
@@ -101,9 +101,15 @@ impl<'a> ShaderBuilder<'a> {
         };
         let load_resource = |path: &str| -> GameResult<Option<ArcShaderModule>> {
             let mut encoded = Vec::new();
-            _ = gfx.fs.open(path)?.read_to_end(&mut encoded)?;
+            _ = gfx
+                .fs
+                .open(path)?
+                .read_to_end(&mut encoded)
+                .with_context(|| format!("reading the shader {:?}", path))?;
             Ok(load(
-                &String::from_utf8(encoded).map_err(GameError::ShaderEncodingError)?,
+                &String::from_utf8(encoded)
+                    .map_err(GameError::ShaderEncodingError)
+                    .with_context(|| format!("loading the shader {:?}", path))?,
             ))
         };
         let load_any = |source| -> GameResult<Option<ArcShaderModule>> {
@@ -333,6 +339,9 @@ impl<Uniforms: AsStd140> Clone for ShaderParams<Uniforms> {
 pub use wgpu::{BlendComponent, BlendFactor, BlendOperation};
 
 /// Describes the blend mode used when drawing images.
+///
+/// With the `serde` feature it's serialized by the name of its constant, e.g. `"Alpha"` for
+/// [`BlendMode::ALPHA`]. Other blend modes can't be serialized.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BlendMode {
     /// The blend mode for the color channels.
@@ -472,4 +481,54 @@ impl BlendMode {
             operation: BlendOperation::Add,
         },
     };
+
+    // The names the blend modes above are serialized by.
+    #[cfg(feature = "serde")]
+    const NAMED: [(&'static str, BlendMode); 9] = [
+        ("Add", BlendMode::ADD),
+        ("Subtract", BlendMode::SUBTRACT),
+        ("Alpha", BlendMode::ALPHA),
+        ("Invert", BlendMode::INVERT),
+        ("Multiply", BlendMode::MULTIPLY),
+        ("Replace", BlendMode::REPLACE),
+        ("Lighten", BlendMode::LIGHTEN),
+        ("Darken", BlendMode::DARKEN),
+        ("Premultiplied", BlendMode::PREMULTIPLIED),
+    ];
+    #[cfg(feature = "serde")]
+    const NAMES: &'static [&'static str] = &[
+        "Add",
+        "Subtract",
+        "Alpha",
+        "Invert",
+        "Multiply",
+        "Replace",
+        "Lighten",
+        "Darken",
+        "Premultiplied",
+    ];
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BlendMode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match BlendMode::NAMED.iter().find(|(_, mode)| mode == self) {
+            Some((name, _)) => serializer.serialize_str(name),
+            None => Err(serde::ser::Error::custom(format!(
+                "Cannot serialize {:?}, only the named blend modes can be",
+                self
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BlendMode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match BlendMode::NAMED.iter().find(|(named, _)| *named == name) {
+            Some((_, mode)) => Ok(*mode),
+            None => Err(serde::de::Error::unknown_variant(&name, BlendMode::NAMES)),
+        }
+    }
 }
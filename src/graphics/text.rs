@@ -2,8 +2,11 @@ use super::{
     gpu::text::{Extra, TextRenderer},
     Canvas, Color, Draw, DrawParam, Drawable, GraphicsContext, Rect,
 };
-use crate::{context::Has, filesystem::Filesystem, GameError, GameResult};
-use glyph_brush::{ab_glyph, FontId, GlyphCruncher};
+use crate::{context::Has, filesystem::Filesystem, GameError, GameResult, ResultExt};
+use glyph_brush::{
+    ab_glyph::{self, Font},
+    FontId, GlyphCruncher,
+};
 use std::{collections::HashMap, io::Read, path::Path};
 
 /// Font data that can be used to create a new font in [super::context::GraphicsContext].
@@ -17,11 +20,16 @@ impl FontData {
     #[allow(unused_results)]
     pub fn from_path(fs: &impl Has<Filesystem>, path: impl AsRef<Path>) -> GameResult<Self> {
         let fs = fs.retrieve();
+        let path = path.as_ref();
 
         let mut bytes = vec![];
-        fs.open(path)?.read_to_end(&mut bytes)?;
+        let _ = fs
+            .open(path)?
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("reading the font {:?}", path))?;
         Ok(FontData {
-            font: ab_glyph::FontArc::try_from_vec(bytes)?,
+            font: ab_glyph::FontArc::try_from_vec(bytes)
+                .with_context(|| format!("loading the font {:?}", path))?,
         })
     }
 
@@ -43,7 +51,7 @@ impl FontData {
 pub use glyph_brush::ab_glyph::PxScale;
 
 /// Parameters of a single piece ("fragment") of text, including font, color, and size.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TextFragment {
     /// The text itself.
     pub text: String,
@@ -118,6 +126,7 @@ pub struct Text {
     bounds: mint::Vector2<f32>,
     scale: PxScale,
     font: String,
+    hinting: TextHinting,
 }
 
 impl Default for Text {
@@ -132,6 +141,7 @@ impl Default for Text {
             },
             scale: 16.0.into(),
             font: "LiberationMono-Regular".into(),
+            hinting: TextHinting::Subpixel,
         }
     }
 }
@@ -151,6 +161,35 @@ impl Text {
         text
     }
 
+    /// Creates a `Text` from a string containing markup tags, splitting it into [`TextFragment`]s.
+    ///
+    /// The supported tags are:
+    /// - `[color=...]...[/color]`, with a `#rrggbb` or `#rrggbbaa` hex value or one of the names
+    ///   `white`, `black`, `red`, `green`, `blue`, `cyan`, `magenta` and `yellow`.
+    /// - `[scale=...]...[/scale]`, with the pixel scale as a positive number.
+    /// - `[font=...]...[/font]`, with the name of a font added via [`GraphicsContext::add_font`].
+    ///
+    /// Tags can be nested and must be closed in reverse order. A literal `[` is written as `[[`.
+    ///
+    /// Text outside of any tag uses the font, scale and color of `default`, whose `text` is ignored.
+    /// Unknown, malformed or unbalanced tags result in a [`GameError::TextMarkupError`]
+    /// containing the byte offset of the offending tag.
+    ///
+    /// ```rust
+    /// # use ggez::graphics::{Color, Text, TextFragment};
+    /// # fn main() -> ggez::GameResult {
+    /// let text = Text::from_markup("Take the [color=#ff0000]red[/color] key", TextFragment::default())?;
+    /// assert_eq!(text.fragments()[1], TextFragment::new("red").color(Color::RED));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_markup(markup: &str, default: TextFragment) -> GameResult<Self> {
+        Ok(Text {
+            fragments: parse_markup(markup, &default)?,
+            ..Default::default()
+        })
+    }
+
     /// Appends a `TextFragment` to the `Text`.
     pub fn add(&mut self, fragment: impl Into<TextFragment>) -> &mut Self {
         self.fragments.push(fragment.into());
@@ -201,6 +240,18 @@ impl Text {
         self
     }
 
+    /// Specifies how glyphs are positioned relative to the pixel grid, see [`TextHinting`].
+    pub fn set_hinting(&mut self, hinting: TextHinting) -> &mut Self {
+        self.hinting = hinting;
+        self
+    }
+
+    /// Returns how glyphs are positioned relative to the pixel grid.
+    #[inline]
+    pub fn hinting(&self) -> TextHinting {
+        self.hinting
+    }
+
     /// Returns the string that the text represents.
     pub fn contents(&self) -> String {
         self.fragments.iter().map(|f| f.text.as_str()).collect()
@@ -212,11 +263,12 @@ impl Text {
         gfx: &impl Has<GraphicsContext>,
     ) -> GameResult<Vec<mint::Point2<f32>>> {
         let gfx = gfx.retrieve();
+        let section = self.as_section(&gfx.text, &gfx.fonts, DrawParam::default())?;
         Ok(gfx
             .text
             .glyph_brush
             .borrow_mut()
-            .glyphs(self.as_section(&gfx.fonts, DrawParam::default())?)
+            .glyphs(section)
             .map(|glyph| mint::Point2::<f32> {
                 x: glyph.glyph.position.x,
                 y: glyph.glyph.position.y,
@@ -236,10 +288,11 @@ impl Text {
         text: &TextRenderer,
         fonts: &HashMap<String, FontId>,
     ) -> GameResult<mint::Vector2<f32>> {
+        let section = self.as_section(text, fonts, DrawParam::default())?;
         Ok(text
             .glyph_brush
             .borrow_mut()
-            .glyph_bounds(self.as_section(fonts, DrawParam::default())?)
+            .glyph_bounds(section)
             .map(|rect| mint::Vector2::<f32> {
                 x: rect.width(),
                 y: rect.height(),
@@ -247,8 +300,159 @@ impl Text {
             .unwrap_or_else(|| mint::Vector2::<f32> { x: 0., y: 0. }))
     }
 
+    /// Lays out the text and applies `effect` to every glyph, returning glyphs ready to be queued pre-positioned.
+    pub(crate) fn effect_glyphs(
+        &self,
+        gfx: &GraphicsContext,
+        param: DrawParam,
+        mut effect: impl FnMut(GlyphInfo) -> GlyphTransform,
+    ) -> GameResult<(Vec<glyph_brush::SectionGlyph>, Vec<Extra>)> {
+        let runs = self.runs(&gfx.text, &gfx.fonts)?;
+        let section = self.as_section(&gfx.text, &gfx.fonts, param)?;
+        let mut brush = gfx.text.glyph_brush.borrow_mut();
+        let positioned = brush.glyphs(&section).cloned().collect::<Vec<_>>();
+        let fonts = brush.fonts();
+
+        // byte offset of each fragment within `contents()`
+        let starts = self
+            .fragments
+            .iter()
+            .scan(0, |start, f| {
+                let this = *start;
+                *start += f.text.len();
+                Some(this)
+            })
+            .collect::<Vec<_>>();
+
+        let mut glyphs = Vec::with_capacity(positioned.len());
+        let mut extras = Vec::with_capacity(positioned.len());
+        let mut line = 0;
+        let mut last_y = None;
+
+        for (index, mut glyph) in positioned.into_iter().enumerate() {
+            // all glyphs of a line share the same baseline
+            let y = glyph.glyph.position.y;
+            if matches!(last_y, Some(last) if y > last) {
+                line += 1;
+            }
+            last_y = Some(y);
+
+            let run = &runs[glyph.section_index];
+            let transform = effect(GlyphInfo {
+                index,
+                byte_index: starts[run.fragment] + run.start + glyph.byte_index,
+                fragment: run.fragment,
+                line,
+                position: mint::Point2 {
+                    x: glyph.glyph.position.x,
+                    y,
+                },
+            });
+
+            // scale and rotate around the center of the glyph
+            let bounds = fonts[glyph.font_id.0].glyph_bounds(&glyph.glyph);
+            let center = glam::vec3(
+                (bounds.min.x + bounds.max.x) / 2.,
+                (bounds.min.y + bounds.max.y) / 2.,
+                0.,
+            );
+            let offset = glam::vec3(transform.offset.x, transform.offset.y, 0.);
+            let local = glam::Mat4::from_translation(center + offset)
+                * glam::Mat4::from_rotation_z(transform.rotation)
+                * glam::Mat4::from_scale(glam::vec3(transform.scale.x, transform.scale.y, 1.))
+                * glam::Mat4::from_translation(-center);
+
+            let extra = section.text[glyph.section_index].extra;
+            extras.push(Extra {
+                color: transform.color.map(Into::into).unwrap_or(extra.color),
+                transform: extra.transform * local,
+                snap: extra.snap,
+            });
+
+            if extra.snap {
+                glyph.glyph.position.x = glyph.glyph.position.x.round();
+                glyph.glyph.position.y = glyph.glyph.position.y.round();
+            }
+            glyph.section_index = index;
+            glyphs.push(glyph);
+        }
+
+        Ok((glyphs, extras))
+    }
+
+    /// Splits the fragments into runs which are each rendered with a single font,
+    /// using the fallback fonts for characters the fragment's font doesn't contain.
+    fn runs<'a>(
+        &'a self,
+        text: &TextRenderer,
+        fonts: &HashMap<String, FontId>,
+    ) -> GameResult<Vec<TextRun<'a>>> {
+        let brush = text.glyph_brush.borrow();
+        let font_data = brush.fonts();
+        let mut runs = Vec::with_capacity(self.fragments.len());
+
+        for (fragment, frag) in self.fragments.iter().enumerate() {
+            let name = frag.font.as_ref().unwrap_or(&self.font);
+            let primary = *fonts
+                .get(name)
+                .ok_or_else(|| GameError::FontSelectError(name.clone()))?;
+
+            if text.fallbacks.is_empty() {
+                runs.push(TextRun {
+                    fragment,
+                    start: 0,
+                    text: &frag.text,
+                    font: primary,
+                });
+                continue;
+            }
+
+            let has_glyph = |font: FontId, c: char| font_data[font.0].glyph_id(c).0 != 0;
+            let font_for = |c: char| {
+                if c.is_whitespace() || c.is_control() || has_glyph(primary, c) {
+                    primary
+                } else {
+                    text.fallbacks
+                        .iter()
+                        .copied()
+                        .find(|font| has_glyph(*font, c))
+                        .unwrap_or(primary)
+                }
+            };
+
+            let mut start = 0;
+            let mut current = None;
+            for (i, c) in frag.text.char_indices() {
+                let font = font_for(c);
+                match current {
+                    Some(cur) if cur != font => {
+                        runs.push(TextRun {
+                            fragment,
+                            start,
+                            text: &frag.text[start..i],
+                            font: cur,
+                        });
+                        start = i;
+                        current = Some(font);
+                    }
+                    None => current = Some(font),
+                    _ => {}
+                }
+            }
+            runs.push(TextRun {
+                fragment,
+                start,
+                text: &frag.text[start..],
+                font: current.unwrap_or(primary),
+            });
+        }
+
+        Ok(runs)
+    }
+
     pub(crate) fn as_section<'a>(
         &'a self,
+        text: &TextRenderer,
         fonts: &HashMap<String, FontId>,
         param: DrawParam,
     ) -> GameResult<glyph_brush::Section<'a, Extra>> {
@@ -265,27 +469,189 @@ impl Text {
             .v_align(self.layout.v_align.into()),
 
             text: self
-                .fragments
-                .iter()
-                .map(|text| {
-                    let font = text.font.as_ref().unwrap_or(&self.font);
-                    Ok(glyph_brush::Text {
-                        text: &text.text,
-                        scale: text.scale.unwrap_or(self.scale),
-                        font_id: *fonts
-                            .get(font)
-                            .ok_or_else(|| GameError::FontSelectError(font.clone()))?,
+                .runs(text, fonts)?
+                .into_iter()
+                .map(|run| {
+                    let frag = &self.fragments[run.fragment];
+                    glyph_brush::Text {
+                        text: run.text,
+                        scale: frag.scale.unwrap_or(self.scale),
+                        font_id: run.font,
                         extra: Extra {
-                            color: text.color.unwrap_or(param.color).into(),
+                            color: frag.color.unwrap_or(param.color).into(),
                             transform: param.transform.to_bare_matrix().into(),
+                            snap: self.hinting == TextHinting::PixelSnapped,
                         },
-                    })
+                    }
                 })
-                .collect::<GameResult<Vec<_>>>()?,
+                .collect(),
         })
     }
 }
 
+/// A part of a fragment's text which is rendered with a single font.
+struct TextRun<'a> {
+    fragment: usize,
+    // byte offset of the run within the fragment's text
+    start: usize,
+    text: &'a str,
+    font: FontId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkupTag {
+    Color,
+    Scale,
+    Font,
+}
+
+impl MarkupTag {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "color" => Some(MarkupTag::Color),
+            "scale" => Some(MarkupTag::Scale),
+            "font" => Some(MarkupTag::Font),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            MarkupTag::Color => "color",
+            MarkupTag::Scale => "scale",
+            MarkupTag::Font => "font",
+        }
+    }
+}
+
+fn parse_markup_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let c = u32::from_str_radix(hex, 16).ok()?;
+        return match hex.len() {
+            6 => Some(Color::from_rgb_u32(c)),
+            8 => Some(Color::from_rgba_u32(c)),
+            _ => None,
+        };
+    }
+
+    match value {
+        "white" => Some(Color::WHITE),
+        "black" => Some(Color::BLACK),
+        "red" => Some(Color::RED),
+        "green" => Some(Color::GREEN),
+        "blue" => Some(Color::BLUE),
+        "cyan" => Some(Color::CYAN),
+        "magenta" => Some(Color::MAGENTA),
+        "yellow" => Some(Color::YELLOW),
+        _ => None,
+    }
+}
+
+fn parse_markup(markup: &str, default: &TextFragment) -> GameResult<Vec<TextFragment>> {
+    let err = |offset: usize, msg: String| {
+        GameError::TextMarkupError(format!("{} (at byte offset {})", msg, offset))
+    };
+
+    // each entry holds the tag, the offset it was opened at, and the fragment style inside of it
+    let mut stack: Vec<(MarkupTag, usize, TextFragment)> = Vec::new();
+    let mut fragments = Vec::new();
+    let mut current = String::new();
+    let mut rest = markup;
+
+    while let Some(start) = rest.find('[') {
+        current.push_str(&rest[..start]);
+        let offset = markup.len() - rest.len() + start;
+        let after = &rest[start + 1..];
+
+        if let Some(after) = after.strip_prefix('[') {
+            current.push('[');
+            rest = after;
+            continue;
+        }
+
+        let end = after
+            .find(']')
+            .ok_or_else(|| err(offset, String::from("unterminated tag")))?;
+        let tag = &after[..end];
+        rest = &after[end + 1..];
+
+        let style = stack.last().map(|(_, _, f)| f).unwrap_or(default);
+        if !current.is_empty() {
+            fragments.push(TextFragment {
+                text: std::mem::take(&mut current),
+                ..style.clone()
+            });
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            match stack.pop() {
+                Some((open, _, _)) if open.name() == name => {}
+                Some((open, open_offset, _)) => {
+                    return Err(err(
+                        offset,
+                        format!(
+                            "closing tag [/{}] does not match [{}] opened at byte offset {}",
+                            name,
+                            open.name(),
+                            open_offset
+                        ),
+                    ))
+                }
+                None => {
+                    return Err(err(
+                        offset,
+                        format!("closing tag [/{}] without an opening tag", name),
+                    ))
+                }
+            }
+            continue;
+        }
+
+        let (name, value) = tag
+            .split_once('=')
+            .ok_or_else(|| err(offset, format!("tag [{}] is missing a value", tag)))?;
+        let kind = MarkupTag::from_name(name)
+            .ok_or_else(|| err(offset, format!("unknown tag [{}]", name)))?;
+
+        let mut style = style.clone();
+        match kind {
+            MarkupTag::Color => {
+                style.color = Some(
+                    parse_markup_color(value)
+                        .ok_or_else(|| err(offset, format!("invalid color '{}'", value)))?,
+                );
+            }
+            MarkupTag::Scale => {
+                let scale = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|s| *s > 0.)
+                    .ok_or_else(|| err(offset, format!("invalid scale '{}'", value)))?;
+                style.scale = Some(scale.into());
+            }
+            MarkupTag::Font => style.font = Some(value.to_string()),
+        }
+        stack.push((kind, offset, style));
+    }
+
+    if let Some((open, open_offset, _)) = stack.pop() {
+        return Err(err(
+            open_offset,
+            format!("tag [{}] is never closed", open.name()),
+        ));
+    }
+
+    current.push_str(rest);
+    if !current.is_empty() {
+        fragments.push(TextFragment {
+            text: current,
+            ..default.clone()
+        });
+    }
+
+    Ok(fragments)
+}
+
 impl Drawable for Text {
     fn draw(&self, canvas: &mut Canvas, param: impl Into<DrawParam>) {
         canvas.push_draw(Draw::BoundedText { text: self.clone() }, param.into());
@@ -302,6 +668,61 @@ impl Drawable for Text {
     }
 }
 
+/// Information about a single glyph, passed to the effect callback of [`Canvas::draw_text_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphInfo {
+    /// Index of the glyph in layout order.
+    pub index: usize,
+    /// Byte index of the character this glyph belongs to within [`Text::contents`].
+    pub byte_index: usize,
+    /// Index of the [`TextFragment`] the glyph belongs to.
+    pub fragment: usize,
+    /// Index of the line the glyph is on, after wrapping.
+    pub line: usize,
+    /// Position of the glyph within the text, after alignment and wrapping.
+    pub position: mint::Point2<f32>,
+}
+
+/// Transformation applied to a single glyph by [`Canvas::draw_text_with`].
+///
+/// Scale and rotation are applied around the center of the glyph, before the text's [`DrawParam`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphTransform {
+    /// Offset the glyph is moved by.
+    pub offset: mint::Vector2<f32>,
+    /// Scale of the glyph.
+    pub scale: mint::Vector2<f32>,
+    /// Rotation of the glyph in radians.
+    pub rotation: f32,
+    /// Color overriding the glyph's fragment color, if any.
+    pub color: Option<Color>,
+}
+
+impl Default for GlyphTransform {
+    fn default() -> Self {
+        GlyphTransform {
+            offset: mint::Vector2 { x: 0., y: 0. },
+            scale: mint::Vector2 { x: 1., y: 1. },
+            rotation: 0.,
+            color: None,
+        }
+    }
+}
+
+/// Describes how the glyphs of a [`Text`] are positioned relative to the pixel grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TextHinting {
+    /// Glyphs are placed at their exact, possibly fractional, positions.
+    /// This keeps moving text smooth, but small text may look slightly blurry.
+    #[default]
+    Subpixel,
+    /// Glyphs are rasterized at whole pixel offsets and every glyph's final position on the
+    /// render target is rounded to whole physical pixels, keeping small text crisp.
+    ///
+    /// Note that moving or scrolling text will visibly step by whole pixels in this mode.
+    PixelSnapped,
+}
+
 /// Describes text alignment along a single axis.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TextAlign {
@@ -359,3 +780,124 @@ impl TextLayout {
         }
     }
 }
+
+/// Describes how the glyph cache grows once the queued text no longer fits into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontCacheGrowth {
+    /// Both dimensions of the cache are doubled.
+    Double,
+    /// Both dimensions of the cache are grown by the given amount of pixels.
+    Linear(u32),
+}
+
+/// Configuration of the glyph cache, the texture atlas that rasterized glyphs are stored in.
+///
+/// Set it with [`GraphicsContext::set_font_cache_config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontCacheConfig {
+    /// Size of the cache in pixels when it is created.
+    pub initial_size: (u32, u32),
+    /// Size in pixels the cache will never grow beyond.
+    /// Text that doesn't fit even at this size is not drawn and a warning is logged.
+    pub max_size: (u32, u32),
+    /// How the cache grows when it's full.
+    pub growth: FontCacheGrowth,
+}
+
+impl Default for FontCacheConfig {
+    fn default() -> Self {
+        FontCacheConfig {
+            initial_size: (1024, 1024),
+            max_size: (8192, 8192),
+            growth: FontCacheGrowth::Double,
+        }
+    }
+}
+
+/// Statistics of the glyph cache, as returned by [`GraphicsContext::font_cache_stats`].
+///
+/// Per-frame values refer to the last completed frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FontCacheStats {
+    /// Current size of the cache in pixels.
+    pub atlas_size: (u32, u32),
+    /// Estimated fraction of the cache that is covered by glyphs, between `0.0` and `1.0`.
+    pub occupancy: f32,
+    /// Number of glyphs rasterized and uploaded to the cache during the last frame.
+    pub rasterizations: u32,
+    /// Number of glyphs evicted from the cache during the last frame because it had to be rebuilt.
+    pub evictions: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_matches_fragments() {
+        let text = Text::from_markup(
+            "Take the [color=#ff0000]red[/color] key, [scale=24]big [font=Other]and[/font][/scale] done",
+            TextFragment::default().color(Color::WHITE),
+        )
+        .unwrap();
+
+        let mut expected = Text::default();
+        let _ = expected
+            .add(TextFragment::new("Take the ").color(Color::WHITE))
+            .add(TextFragment::new("red").color(Color::RED))
+            .add(TextFragment::new(" key, ").color(Color::WHITE))
+            .add(TextFragment::new("big ").color(Color::WHITE).scale(24.))
+            .add(
+                TextFragment::new("and")
+                    .color(Color::WHITE)
+                    .scale(24.)
+                    .font("Other"),
+            )
+            .add(TextFragment::new(" done").color(Color::WHITE));
+
+        assert_eq!(text.fragments(), expected.fragments());
+    }
+
+    #[test]
+    fn markup_escapes_and_colors() {
+        let text = Text::from_markup(
+            "[[not a tag] [color=blue]x[/color]",
+            TextFragment::default(),
+        )
+        .unwrap();
+        assert_eq!(text.contents(), "[not a tag] x");
+        assert_eq!(text.fragments()[1].color, Some(Color::BLUE));
+
+        let text =
+            Text::from_markup("[color=#00ff0080]x[/color]", TextFragment::default()).unwrap();
+        assert_eq!(
+            text.fragments()[0].color,
+            Some(Color::from_rgba(0, 255, 0, 128))
+        );
+    }
+
+    #[test]
+    fn markup_errors() {
+        let error_at = |markup: &str, offset: usize| match Text::from_markup(
+            markup,
+            TextFragment::default(),
+        ) {
+            Err(GameError::TextMarkupError(msg)) => {
+                assert!(
+                    msg.ends_with(&format!("(at byte offset {})", offset)),
+                    "{}",
+                    msg
+                )
+            }
+            other => panic!("expected a markup error for {:?}, got {:?}", markup, other),
+        };
+
+        error_at("abc [color=red]unclosed", 4);
+        error_at("abc [/color]", 4);
+        error_at("[color=red]a[scale=2]b[/color][/scale]", 22);
+        error_at("x [outline=2]y[/outline]", 2);
+        error_at("x [color=notacolor]y[/color]", 2);
+        error_at("x [scale=-1]y[/scale]", 2);
+        error_at("x [color=red", 2);
+    }
+}
@@ -6,6 +6,8 @@ use crate::graphics::{FillOptions, StrokeOptions};
 ///
 /// The origin of the rectangle is at the top-left,
 /// with x increasing to the right and y increasing down.
+///
+/// It's serialized with serde as a struct of its fields, e.g. `{ "x": 0.0, "y": 0.0, "w": 1.0, "h": 1.0 }`.
 #[derive(Copy, Clone, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Rect {
     /// X coordinate of the left edge of the rect.
@@ -47,6 +49,20 @@ impl Rect {
         }
     }
 
+    /// Create a new `Rect` from its top-left and bottom-right corners.
+    pub fn from_corners(
+        top_left: impl Into<mint::Point2<f32>>,
+        bottom_right: impl Into<mint::Point2<f32>>,
+    ) -> Self {
+        let (top_left, bottom_right) = (top_left.into(), bottom_right.into());
+        Rect::new(
+            top_left.x,
+            top_left.y,
+            bottom_right.x - top_left.x,
+            bottom_right.y - top_left.y,
+        )
+    }
+
     /// Create a new `Rect` with all values zero.
     pub const fn zero() -> Self {
         Self::new(0.0, 0.0, 0.0, 0.0)
@@ -258,6 +274,26 @@ impl From<Rect> for [f32; 4] {
     }
 }
 
+impl From<[mint::Point2<f32>; 2]> for Rect {
+    /// Turns the `[top_left, bottom_right]` corners of a rect into a `Rect`.
+    fn from([top_left, bottom_right]: [mint::Point2<f32>; 2]) -> Self {
+        Rect::from_corners(top_left, bottom_right)
+    }
+}
+
+impl From<Rect> for [mint::Point2<f32>; 2] {
+    /// Convert a `Rect` into its `[top_left, bottom_right]` corners.
+    fn from(rect: Rect) -> Self {
+        [
+            rect.point(),
+            mint::Point2 {
+                x: rect.right(),
+                y: rect.bottom(),
+            },
+        ]
+    }
+}
+
 /// A RGBA color in the `sRGB` color space represented as `f32`'s in the range `[0.0-1.0]`
 ///
 /// For convenience, several colors are provided:
@@ -269,7 +305,9 @@ impl From<Rect> for [f32; 4] {
 /// [`CYAN`](`Color::CYAN`)
 /// [`MAGENTA`](`Color::MAGENTA`)
 /// [`YELLOW`](`Color::YELLOW`)
-
+///
+/// It's serialized with serde as a struct of its components, e.g.
+/// `{ "r": 1.0, "g": 0.5, "b": 0.0, "a": 1.0 }`.
 #[derive(Copy, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Color {
     /// Red component
@@ -434,6 +472,14 @@ impl From<[f32; 4]> for Color {
     }
 }
 
+impl From<mint::Vector4<f32>> for Color {
+    /// Turns a `mint::Vector4` of `f32`'s, with `x, y, z, w` as `R, G, B, A`, into a `Color`
+    /// with no format changes. All inputs should be in the range `[0.0-1.0]`.
+    fn from(val: mint::Vector4<f32>) -> Self {
+        Color::new(val.x, val.y, val.z, val.w)
+    }
+}
+
 impl From<(f32, f32, f32)> for Color {
     /// Convert a `(R, G, B)` tuple of `f32`'s in the range `[0.0-1.0]` into a `Color`,
     /// with a value of 1.0 to for the alpha element (ie, no transparency.)
@@ -482,6 +528,19 @@ impl From<Color> for [f32; 4] {
     }
 }
 
+impl From<Color> for mint::Vector4<f32> {
+    /// Convert a `Color` into a `mint::Vector4` of `f32`'s in the range of `[0.0-1.0]`, with
+    /// `R, G, B, A` as `x, y, z, w`.
+    fn from(color: Color) -> Self {
+        mint::Vector4 {
+            x: color.r,
+            y: color.g,
+            z: color.b,
+            w: color.a,
+        }
+    }
+}
+
 /// A RGBA color in the *linear* color space,
 /// suitable for shoving into a shader.
 #[derive(Copy, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
@@ -575,6 +634,46 @@ impl DrawMode {
     }
 }
 
+/// Specifies how a virtual resolution is fit into the window,
+/// see [`GraphicsContext::set_virtual_resolution`](crate::graphics::GraphicsContext::set_virtual_resolution).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum FitMode {
+    /// Stretch the virtual resolution over the whole window, ignoring its aspect ratio.
+    Stretch,
+    /// Scale the virtual resolution as large as possible while preserving its aspect ratio,
+    /// filling the remaining space with bars.
+    Letterbox,
+    /// Like [`FitMode::Letterbox`], but only scale by whole numbers, keeping pixel art crisp.
+    ///
+    /// Falls back to scaling down when the window is smaller than the virtual resolution.
+    IntegerScale,
+}
+
+impl FitMode {
+    /// Returns the area, in pixels of a target of size `target`, that a virtual resolution of
+    /// size `virtual_size` is displayed in.
+    pub fn viewport(self, virtual_size: (u32, u32), target: (u32, u32)) -> Rect {
+        let (vw, vh) = (virtual_size.0.max(1) as f32, virtual_size.1.max(1) as f32);
+        let (tw, th) = (target.0 as f32, target.1 as f32);
+
+        let scale = match self {
+            FitMode::Stretch => return Rect::new(0., 0., tw, th),
+            FitMode::Letterbox => f32::min(tw / vw, th / vh),
+            FitMode::IntegerScale => {
+                let scale = f32::min(tw / vw, th / vh);
+                if scale >= 1. {
+                    scale.floor()
+                } else {
+                    scale
+                }
+            }
+        };
+
+        let (w, h) = ((vw * scale).round(), (vh * scale).round());
+        Rect::new(((tw - w) / 2.).floor(), ((th - h) / 2.).floor(), w, h)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -610,6 +709,86 @@ mod tests {
         assert_eq!(puce1, puce2);
         assert_eq!(puce1, puce3);
         assert_eq!(puce1, puce4);
+
+        let v: mint::Vector4<f32> = puce4.into();
+        assert_eq!(v, mint::Vector4::from([0.80, 0.53333336, 0.60, 1.0]));
+        assert_eq!(Color::from(v), puce4);
+    }
+
+    #[test]
+    fn headless_test_rect_corners() {
+        let r = Rect::new(16.0, 32.0, 64.0, 128.0);
+        let corners: [mint::Point2<f32>; 2] = r.into();
+        assert_eq!(corners, [[16.0, 32.0].into(), [80.0, 160.0].into()]);
+        assert_eq!(Rect::from(corners), r);
+        assert_eq!(Rect::from_corners(glam::vec2(16.0, 32.0), [80.0, 160.0]), r);
+    }
+
+    #[test]
+    fn headless_test_serde() {
+        let color = Color::new(1.0, 0.5, 0.0, 1.0);
+        let json = serde_json::to_string(&color).unwrap();
+        assert_eq!(json, r#"{"r":1.0,"g":0.5,"b":0.0,"a":1.0}"#);
+        assert_eq!(serde_json::from_str::<Color>(&json).unwrap(), color);
+        let ron = ron::to_string(&color).unwrap();
+        assert_eq!(ron, "(r:1.0,g:0.5,b:0.0,a:1.0)");
+        assert_eq!(ron::from_str::<Color>(&ron).unwrap(), color);
+
+        let rect = Rect::new(16.0, 32.0, 64.0, 128.0);
+        let json = serde_json::to_string(&rect).unwrap();
+        assert_eq!(json, r#"{"x":16.0,"y":32.0,"w":64.0,"h":128.0}"#);
+        assert_eq!(serde_json::from_str::<Rect>(&json).unwrap(), rect);
+        let ron = ron::to_string(&rect).unwrap();
+        assert_eq!(ron, "(x:16.0,y:32.0,w:64.0,h:128.0)");
+        assert_eq!(ron::from_str::<Rect>(&ron).unwrap(), rect);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn headless_test_serde_feature() {
+        use crate::graphics::{BlendMode, DrawParam, FilterMode, Sampler, Transform};
+
+        let param = DrawParam::new()
+            .dest([13.0, 37.0])
+            .rotation(0.5)
+            .color(Color::RED)
+            .z(2);
+        let json = serde_json::to_string(&param).unwrap();
+        assert!(json.contains(r#""dest":[13.0,37.0]"#));
+        assert_eq!(serde_json::from_str::<DrawParam>(&json).unwrap(), param);
+        let ron = ron::to_string(&param).unwrap();
+        assert_eq!(ron::from_str::<DrawParam>(&ron).unwrap(), param);
+        // fields left out keep their defaults
+        let partial: DrawParam = serde_json::from_str(r#"{"z":3}"#).unwrap();
+        assert_eq!(partial, DrawParam::new().z(3));
+
+        let matrix = DrawParam::new().transform(glam::Mat4::from_scale(glam::vec3(2.0, 2.0, 1.0)));
+        let json = serde_json::to_string(&matrix).unwrap();
+        assert_eq!(serde_json::from_str::<DrawParam>(&json).unwrap(), matrix);
+        let ron = ron::to_string(&matrix.transform).unwrap();
+        assert_eq!(ron::from_str::<Transform>(&ron).unwrap(), matrix.transform);
+
+        let sampler = Sampler::from(FilterMode::Nearest);
+        let json = serde_json::to_string(&sampler).unwrap();
+        assert!(json.contains(r#""mag":"Nearest""#));
+        assert_eq!(serde_json::from_str::<Sampler>(&json).unwrap(), sampler);
+        let ron = ron::to_string(&sampler).unwrap();
+        assert_eq!(ron::from_str::<Sampler>(&ron).unwrap(), sampler);
+
+        let json = serde_json::to_string(&BlendMode::PREMULTIPLIED).unwrap();
+        assert_eq!(json, r#""Premultiplied""#);
+        assert_eq!(
+            serde_json::from_str::<BlendMode>(&json).unwrap(),
+            BlendMode::PREMULTIPLIED
+        );
+        let ron = ron::to_string(&BlendMode::ALPHA).unwrap();
+        assert_eq!(ron::from_str::<BlendMode>(&ron).unwrap(), BlendMode::ALPHA);
+        assert!(serde_json::from_str::<BlendMode>(r#""Screen""#).is_err());
+        let custom = BlendMode {
+            alpha: BlendMode::ADD.color,
+            ..BlendMode::ALPHA
+        };
+        assert!(serde_json::to_string(&custom).is_err());
     }
 
     #[test]
@@ -733,6 +912,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn headless_test_fit_mode_viewport() {
+        assert_eq!(
+            FitMode::Stretch.viewport((640, 360), (800, 600)),
+            Rect::new(0., 0., 800., 600.)
+        );
+        assert_eq!(
+            FitMode::Letterbox.viewport((640, 360), (800, 600)),
+            Rect::new(0., 75., 800., 450.)
+        );
+        assert_eq!(
+            FitMode::IntegerScale.viewport((640, 360), (1920, 1200)),
+            Rect::new(0., 60., 1920., 1080.)
+        );
+        assert_eq!(
+            FitMode::IntegerScale.viewport((640, 360), (1600, 900)),
+            Rect::new(160., 90., 1280., 720.)
+        );
+    }
+
     #[test]
     fn headless_test_rect_rotate() {
         {
@@ -0,0 +1,938 @@
+use super::Image;
+use crate::conf::{WindowMode, WindowSetup};
+use crate::{GameError, GameResult};
+use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+pub use winit::window::UserAttentionType;
+
+/// Identifies a secondary window, created with [`GraphicsContext::create_window`](crate::graphics::GraphicsContext::create_window).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(pub(crate) u32);
+
+/// The platform's handles of the main window, such as its `HWND` on Windows, its `NSView` on
+/// macOS or its X11 window, for native APIs parenting dialogs or overlays to it, see
+/// [`GraphicsContext::window_handle`](crate::graphics::GraphicsContext::window_handle).
+///
+/// It implements the traits of [`raw_window_handle`](crate::raw_window_handle), of the version
+/// winit uses, so it can be passed to crates taking a `HasRawWindowHandle` directly. It borrows
+/// the context, as the handles are only valid while the window is. The raw handles taken from it
+/// must not be used after the context is dropped, nor on mobile platforms after the app is
+/// [suspended](crate::event::EventHandler::suspend_event), when the system takes the native
+/// window away, until it's [resumed](crate::event::EventHandler::resume_event), after which they
+/// have to be taken again.
+#[derive(Debug, Copy, Clone)]
+pub struct WindowHandle<'a> {
+    pub(crate) window: &'a winit::window::Window,
+}
+
+// The handles are those of winit's window, which is alive as long as this borrows it.
+#[allow(unsafe_code)]
+unsafe impl HasRawWindowHandle for WindowHandle<'_> {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.window.raw_window_handle()
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe impl HasRawDisplayHandle for WindowHandle<'_> {
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        self.window.raw_display_handle()
+    }
+}
+
+pub(crate) struct SecondaryWindow {
+    pub id: WindowId,
+    pub window: winit::window::Window,
+    pub surface: wgpu::Surface,
+    pub surface_config: wgpu::SurfaceConfiguration,
+    pub frame: Image,
+    pub cursor: mint::Point2<f32>,
+}
+
+// Windows can only be built while the event loop hands out its `EventLoopWindowTarget`,
+// so they are queued until then.
+pub(crate) struct PendingWindow {
+    pub id: WindowId,
+    pub mode: WindowMode,
+    pub setup: WindowSetup,
+}
+
+/// Where a window is stacked relative to other windows, see
+/// [`GraphicsContext::set_window_level`](crate::graphics::GraphicsContext::set_window_level).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum WindowLevel {
+    /// The window is stacked like any other window.
+    #[default]
+    Normal,
+    /// The window floats above all normal windows.
+    AlwaysOnTop,
+    /// The window stays below all normal windows.
+    AlwaysOnBottom,
+}
+
+/// The edge or corner of a window to resize from, see
+/// [`GraphicsContext::begin_window_resize`](crate::graphics::GraphicsContext::begin_window_resize).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum ResizeDirection {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+/// Information about a monitor, as returned by [`GraphicsContext::monitors`](crate::graphics::GraphicsContext::monitors).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    /// The index of the monitor, used to select it in [`WindowMode::monitor`](crate::conf::WindowMode::monitor).
+    pub index: usize,
+    /// A human-readable name of the monitor, if available.
+    pub name: Option<String>,
+    /// The position of the monitor's top-left corner on the desktop, in physical pixels.
+    pub position: PhysicalPosition<i32>,
+    /// The resolution of the monitor, in physical pixels.
+    pub size: PhysicalSize<u32>,
+    /// The scale factor of the monitor, i.e. the number of physical pixels per logical pixel.
+    pub scale_factor: f64,
+    pub(crate) video_modes: Vec<VideoMode>,
+}
+
+impl MonitorInfo {
+    pub(crate) fn new(index: usize, monitor: &winit::monitor::MonitorHandle) -> Self {
+        MonitorInfo {
+            index,
+            name: monitor.name(),
+            position: monitor.position(),
+            size: monitor.size(),
+            scale_factor: monitor.scale_factor(),
+            video_modes: monitor.video_modes().map(VideoMode::from).collect(),
+        }
+    }
+
+    /// Returns the video modes the monitor supports in exclusive fullscreen.
+    pub fn video_modes(&self) -> &[VideoMode] {
+        &self.video_modes
+    }
+}
+
+/// A video mode supported by a monitor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    /// The resolution, in physical pixels.
+    pub size: PhysicalSize<u32>,
+    /// The refresh rate, in millihertz.
+    pub refresh_rate_millihertz: u32,
+    /// The bit depth, in bits per pixel.
+    pub bit_depth: u16,
+}
+
+impl From<winit::monitor::VideoMode> for VideoMode {
+    fn from(mode: winit::monitor::VideoMode) -> Self {
+        VideoMode {
+            size: mode.size(),
+            refresh_rate_millihertz: mode.refresh_rate_millihertz(),
+            bit_depth: mode.bit_depth(),
+        }
+    }
+}
+
+// Returns the monitor with the given index, falling back to the primary monitor if it doesn't exist.
+// `None` picks the monitor the window is currently on.
+pub(crate) fn select_monitor(
+    window: &winit::window::Window,
+    index: Option<usize>,
+) -> Option<winit::monitor::MonitorHandle> {
+    let fallback = || {
+        window
+            .primary_monitor()
+            .or_else(|| window.current_monitor())
+            .or_else(|| window.available_monitors().next())
+    };
+    match index {
+        Some(index) => window.available_monitors().nth(index).or_else(|| {
+            warn!(
+                "There is no monitor with index {}, falling back to the primary monitor",
+                index
+            );
+            fallback()
+        }),
+        None => window.current_monitor().or_else(fallback),
+    }
+}
+
+// Sets the opacity of the whole window through the platform's window manager, as winit doesn't expose this.
+#[allow(unsafe_code)]
+pub(crate) fn set_window_opacity(window: &winit::window::Window, opacity: f32) -> GameResult {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetWindowLongW, SetLayeredWindowAttributes, SetWindowLongW, GWL_EXSTYLE, LWA_ALPHA,
+            WS_EX_LAYERED,
+        };
+        use winit::platform::windows::WindowExtWindows;
+
+        let hwnd = window.hwnd();
+        // only layered windows can be translucent
+        let ok = unsafe {
+            let style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+            let _ = SetWindowLongW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED as i32);
+            SetLayeredWindowAttributes(hwnd, 0, (opacity * 255.0).round() as u8, LWA_ALPHA)
+        };
+        if ok == 0 {
+            Err(GameError::WindowError(String::from(
+                "failed to set the window opacity",
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use objc::{msg_send, runtime::Object, sel, sel_impl};
+        use winit::platform::macos::WindowExtMacOS;
+
+        let ns_window = window.ns_window() as *mut Object;
+        unsafe {
+            let () = msg_send![ns_window, setAlphaValue: f64::from(opacity)];
+        }
+        Ok(())
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use std::{ffi::CString, os::raw::c_ulong};
+        use winit::platform::unix::WindowExtUnix;
+        use x11_dl::xlib;
+
+        let (display, xwindow) = match (window.xlib_display(), window.xlib_window()) {
+            (Some(display), Some(xwindow)) => (display as *mut xlib::Display, xwindow),
+            _ => {
+                return Err(GameError::WindowError(String::from(
+                    "window opacity is only supported on X11, not on Wayland",
+                )))
+            }
+        };
+        let xlib = xlib::Xlib::open().map_err(|e| GameError::WindowError(e.to_string()))?;
+        let name = CString::new("_NET_WM_WINDOW_OPACITY").unwrap();
+        unsafe {
+            // honored by compositing window managers
+            let atom = (xlib.XInternAtom)(display, name.as_ptr(), xlib::False);
+            if opacity >= 1.0 {
+                let _ = (xlib.XDeleteProperty)(display, xwindow, atom);
+            } else {
+                let value = (f64::from(opacity) * f64::from(u32::MAX)) as c_ulong;
+                let _ = (xlib.XChangeProperty)(
+                    display,
+                    xwindow,
+                    atom,
+                    xlib::XA_CARDINAL,
+                    32,
+                    xlib::PropModeReplace,
+                    &value as *const c_ulong as *const u8,
+                    1,
+                );
+            }
+            let _ = (xlib.XFlush)(display);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let _ = (window, opacity);
+        Err(GameError::WindowError(String::from(
+            "window opacity is not supported on this platform",
+        )))
+    }
+}
+
+// AppKit's geometry, which objc has no types for. CGFloat is an f64 on every Mac winit supports.
+#[cfg(target_os = "macos")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NSPoint {
+    x: f64,
+    y: f64,
+}
+
+#[cfg(target_os = "macos")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NSSize {
+    width: f64,
+    height: f64,
+}
+
+#[cfg(target_os = "macos")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NSRect {
+    origin: NSPoint,
+    size: NSSize,
+}
+
+// Windows: winit sets its cursor icon on every WM_SETCURSOR, so the window is subclassed to set
+// the cursor image instead, which is the subclass's data.
+#[cfg(target_os = "windows")]
+const CURSOR_SUBCLASS_ID: usize = 0x6767_657a;
+
+#[cfg(target_os = "windows")]
+#[allow(unsafe_code)]
+unsafe extern "system" fn cursor_subclass(
+    hwnd: windows_sys::Win32::Foundation::HWND,
+    message: u32,
+    wparam: windows_sys::Win32::Foundation::WPARAM,
+    lparam: windows_sys::Win32::Foundation::LPARAM,
+    _id: usize,
+    cursor: usize,
+) -> windows_sys::Win32::Foundation::LRESULT {
+    use windows_sys::Win32::UI::Shell::DefSubclassProc;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SetCursor, HTCLIENT, WM_NCDESTROY, WM_SETCURSOR,
+    };
+
+    match message {
+        // over the window itself, rather than its borders
+        WM_SETCURSOR if (lparam & 0xffff) as u32 == HTCLIENT => {
+            let _ = SetCursor(cursor as isize);
+            1
+        }
+        WM_NCDESTROY => {
+            remove_cursor_subclass(hwnd);
+            DefSubclassProc(hwnd, message, wparam, lparam)
+        }
+        _ => DefSubclassProc(hwnd, message, wparam, lparam),
+    }
+}
+
+// Removes the subclass setting the cursor image, if there is one, and destroys the cursor.
+#[cfg(target_os = "windows")]
+#[allow(unsafe_code)]
+unsafe fn remove_cursor_subclass(hwnd: windows_sys::Win32::Foundation::HWND) {
+    use windows_sys::Win32::UI::Shell::{GetWindowSubclass, RemoveWindowSubclass};
+    use windows_sys::Win32::UI::WindowsAndMessaging::DestroyCursor;
+
+    let mut cursor = 0;
+    if GetWindowSubclass(hwnd, Some(cursor_subclass), CURSOR_SUBCLASS_ID, &mut cursor) != 0 {
+        let _ = RemoveWindowSubclass(hwnd, Some(cursor_subclass), CURSOR_SUBCLASS_ID);
+        let _ = DestroyCursor(cursor as isize);
+    }
+}
+
+// Shows an RGBA image as the cursor over the window, which winit doesn't support yet.
+#[allow(unsafe_code)]
+pub(crate) fn set_custom_cursor(
+    window: &winit::window::Window,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    hotspot: (u32, u32),
+) -> GameResult {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Graphics::Gdi::{CreateBitmap, DeleteObject};
+        use windows_sys::Win32::UI::Shell::{GetWindowSubclass, SetWindowSubclass};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            CreateIconIndirect, DestroyCursor, SetCursor, ICONINFO,
+        };
+        use winit::platform::windows::WindowExtWindows;
+
+        let hwnd = window.hwnd();
+        // 32-bit cursors take straight BGRA, and ignore the mask, which still has to be there
+        let bgra: Vec<u8> = rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect();
+        // rows of the mask are padded to 16 bits
+        let mask = vec![0u8; ((width + 15) / 16 * 2 * height) as usize];
+        unsafe {
+            let color = CreateBitmap(width as i32, height as i32, 1, 32, bgra.as_ptr().cast());
+            let mask = CreateBitmap(width as i32, height as i32, 1, 1, mask.as_ptr().cast());
+            let info = ICONINFO {
+                fIcon: 0,
+                xHotspot: hotspot.0,
+                yHotspot: hotspot.1,
+                hbmMask: mask,
+                hbmColor: color,
+            };
+            let cursor = if color == 0 || mask == 0 {
+                0
+            } else {
+                CreateIconIndirect(&info)
+            };
+            // the cursor has its own copies
+            let _ = DeleteObject(color);
+            let _ = DeleteObject(mask);
+            if cursor == 0 {
+                return Err(GameError::WindowError(String::from(
+                    "failed to create the cursor image",
+                )));
+            }
+
+            let mut previous = 0;
+            let replaced = GetWindowSubclass(
+                hwnd,
+                Some(cursor_subclass),
+                CURSOR_SUBCLASS_ID,
+                &mut previous,
+            ) != 0;
+            // subclassing again with the same ID only replaces the data
+            if SetWindowSubclass(
+                hwnd,
+                Some(cursor_subclass),
+                CURSOR_SUBCLASS_ID,
+                cursor as usize,
+            ) == 0
+            {
+                let _ = DestroyCursor(cursor);
+                return Err(GameError::WindowError(String::from(
+                    "failed to set the cursor image",
+                )));
+            }
+            if replaced {
+                let _ = DestroyCursor(previous as isize);
+            }
+            // rather than on the next move
+            let _ = SetCursor(cursor);
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use objc::{class, msg_send, runtime::Object, runtime::NO, runtime::YES, sel, sel_impl};
+        use std::os::raw::c_char;
+        use winit::platform::macos::WindowExtMacOS;
+
+        let view = window.ns_view() as *mut Object;
+        // in points, so the image's pixels are the screen's
+        let scale = window.scale_factor();
+        unsafe {
+            let color_space: *mut Object = msg_send![
+                class!(NSString),
+                stringWithUTF8String: b"NSDeviceRGBColorSpace\0".as_ptr() as *const c_char
+            ];
+            let rep: *mut Object = msg_send![class!(NSBitmapImageRep), alloc];
+            // with no planes given, it allocates its own
+            let rep: *mut Object = msg_send![
+                rep,
+                initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>()
+                pixelsWide: width as isize
+                pixelsHigh: height as isize
+                bitsPerSample: 8isize
+                samplesPerPixel: 4isize
+                hasAlpha: YES
+                isPlanar: NO
+                colorSpaceName: color_space
+                bytesPerRow: (width * 4) as isize
+                bitsPerPixel: 32isize
+            ];
+            if rep.is_null() {
+                return Err(GameError::WindowError(String::from(
+                    "failed to create the cursor image",
+                )));
+            }
+            let data: *mut u8 = msg_send![rep, bitmapData];
+            let data = std::slice::from_raw_parts_mut(data, rgba.len());
+            // AppKit takes premultiplied RGBA
+            for (pixel, rgba) in data.chunks_exact_mut(4).zip(rgba.chunks_exact(4)) {
+                let a = u32::from(rgba[3]);
+                let premultiply = |c: u8| (u32::from(c) * a / 255) as u8;
+                pixel.copy_from_slice(&[
+                    premultiply(rgba[0]),
+                    premultiply(rgba[1]),
+                    premultiply(rgba[2]),
+                    rgba[3],
+                ]);
+            }
+            let size = NSSize {
+                width: f64::from(width) / scale,
+                height: f64::from(height) / scale,
+            };
+            let image: *mut Object = msg_send![class!(NSImage), alloc];
+            let image: *mut Object = msg_send![image, initWithSize: size];
+            let () = msg_send![image, addRepresentation: rep];
+            let hotspot = NSPoint {
+                x: f64::from(hotspot.0) / scale,
+                y: f64::from(hotspot.1) / scale,
+            };
+            let cursor: *mut Object = msg_send![class!(NSCursor), alloc];
+            let cursor: *mut Object = msg_send![cursor, initWithImage: image hotSpot: hotspot];
+            // over winit's cursor rect, until it's reset, see `MouseContext::restore_cursor_image`
+            let bounds: NSRect = msg_send![view, bounds];
+            let () = msg_send![view, discardCursorRects];
+            let () = msg_send![view, addCursorRect: bounds cursor: cursor];
+            // rather than on the next move
+            let () = msg_send![cursor, set];
+            // the view keeps the cursor, which keeps the image
+            let () = msg_send![cursor, release];
+            let () = msg_send![image, release];
+            let () = msg_send![rep, release];
+        }
+        Ok(())
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use winit::platform::unix::WindowExtUnix;
+        use x11_dl::{xcursor, xlib};
+
+        let (display, xwindow) = match (window.xlib_display(), window.xlib_window()) {
+            (Some(display), Some(xwindow)) => (display as *mut xlib::Display, xwindow),
+            _ => {
+                return Err(GameError::WindowError(String::from(
+                    "custom cursor images are only supported on X11, not on Wayland",
+                )))
+            }
+        };
+        let xlib = xlib::Xlib::open().map_err(|e| GameError::WindowError(e.to_string()))?;
+        let xcursor =
+            xcursor::Xcursor::open().map_err(|e| GameError::WindowError(e.to_string()))?;
+        unsafe {
+            let image = (xcursor.XcursorImageCreate)(width as i32, height as i32);
+            if image.is_null() {
+                return Err(GameError::WindowError(String::from(
+                    "failed to create the cursor image",
+                )));
+            }
+            (*image).xhot = hotspot.0;
+            (*image).yhot = hotspot.1;
+            let pixels = std::slice::from_raw_parts_mut((*image).pixels, (width * height) as usize);
+            // Xcursor takes premultiplied ARGB
+            for (pixel, rgba) in pixels.iter_mut().zip(rgba.chunks_exact(4)) {
+                let a = u32::from(rgba[3]);
+                let premultiply = |c: u8| u32::from(c) * a / 255;
+                *pixel = a << 24
+                    | premultiply(rgba[0]) << 16
+                    | premultiply(rgba[1]) << 8
+                    | premultiply(rgba[2]);
+            }
+            let cursor = (xcursor.XcursorImageLoadCursor)(display, image);
+            (xcursor.XcursorImageDestroy)(image);
+            let _ = (xlib.XDefineCursor)(display, xwindow, cursor);
+            // the window keeps the cursor alive
+            let _ = (xlib.XFreeCursor)(display, cursor);
+            let _ = (xlib.XFlush)(display);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let _ = (window, rgba, width, height, hotspot);
+        Err(GameError::WindowError(String::from(
+            "custom cursor images are not supported on this platform",
+        )))
+    }
+}
+
+// Goes back from a cursor image to winit's cursor icon, where setting the icon doesn't already.
+#[allow(unsafe_code)]
+pub(crate) fn clear_custom_cursor(window: &winit::window::Window) {
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::WindowExtWindows;
+
+        unsafe { remove_cursor_subclass(window.hwnd()) };
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = window;
+    }
+}
+
+// The taskbar entry, or dock icon, of the app, which winit doesn't expose, showing a progress bar.
+#[derive(Default)]
+pub(crate) struct Taskbar {
+    // connected to on the first progress, the desktop shell listening on it
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    session_bus: Option<zbus::blocking::Connection>,
+}
+
+impl Taskbar {
+    // Shows `progress`, in `0.0..=1.0`, on the taskbar entry of `window`, or removes it with `None`.
+    #[allow(unsafe_code)]
+    pub(crate) fn set_progress(
+        &mut self,
+        window: &winit::window::Window,
+        progress: Option<f32>,
+    ) -> GameResult {
+        #[cfg(target_os = "windows")]
+        {
+            use std::ffi::c_void;
+            use windows_sys::core::{GUID, HRESULT};
+            use windows_sys::Win32::System::Com::{
+                CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+            };
+            use winit::platform::windows::WindowExtWindows;
+
+            // windows-sys has no COM interfaces, so ITaskbarList3's vtable is spelled out, up to
+            // the methods used
+            #[repr(C)]
+            struct TaskbarList3Vtbl {
+                query_interface: usize,
+                add_ref: usize,
+                release: unsafe extern "system" fn(*mut c_void) -> u32,
+                hr_init: unsafe extern "system" fn(*mut c_void) -> HRESULT,
+                add_tab: usize,
+                delete_tab: usize,
+                activate_tab: usize,
+                set_active_alt: usize,
+                mark_fullscreen_window: usize,
+                set_progress_value:
+                    unsafe extern "system" fn(*mut c_void, isize, u64, u64) -> HRESULT,
+                set_progress_state: unsafe extern "system" fn(*mut c_void, isize, i32) -> HRESULT,
+            }
+            const CLSID_TASKBAR_LIST: GUID = GUID {
+                data1: 0x56fd_f344,
+                data2: 0xfd6d,
+                data3: 0x11d0,
+                data4: [0x95, 0x8a, 0x00, 0x60, 0x97, 0xc9, 0xa0, 0x90],
+            };
+            const IID_TASKBAR_LIST3: GUID = GUID {
+                data1: 0xea1a_fb91,
+                data2: 0x9e28,
+                data3: 0x4b86,
+                data4: [0x90, 0xe9, 0x9e, 0x9f, 0x8a, 0x5e, 0xef, 0xaf],
+            };
+            const TBPF_NOPROGRESS: i32 = 0;
+            const TBPF_NORMAL: i32 = 2;
+
+            let hwnd = window.hwnd();
+            let hr = unsafe {
+                // winit's thread has COM initialized already, in which case this does nothing
+                let _ = CoInitializeEx(std::ptr::null(), COINIT_APARTMENTTHREADED);
+                let mut taskbar: *mut c_void = std::ptr::null_mut();
+                let hr = CoCreateInstance(
+                    &CLSID_TASKBAR_LIST,
+                    std::ptr::null_mut(),
+                    CLSCTX_INPROC_SERVER,
+                    &IID_TASKBAR_LIST3,
+                    &mut taskbar,
+                );
+                if hr < 0 {
+                    hr
+                } else {
+                    let vtbl = &**(taskbar as *const *const TaskbarList3Vtbl);
+                    let mut hr = (vtbl.hr_init)(taskbar);
+                    if hr >= 0 {
+                        hr = match progress {
+                            Some(progress) => {
+                                match (vtbl.set_progress_state)(taskbar, hwnd, TBPF_NORMAL) {
+                                    hr if hr < 0 => hr,
+                                    _ => (vtbl.set_progress_value)(
+                                        taskbar,
+                                        hwnd,
+                                        (f64::from(progress) * 1000.0).round() as u64,
+                                        1000,
+                                    ),
+                                }
+                            }
+                            None => (vtbl.set_progress_state)(taskbar, hwnd, TBPF_NOPROGRESS),
+                        };
+                    }
+                    let _ = (vtbl.release)(taskbar);
+                    hr
+                }
+            };
+            if hr < 0 {
+                Err(GameError::WindowError(format!(
+                    "failed to set the taskbar progress (HRESULT {:#010x})",
+                    hr
+                )))
+            } else {
+                Ok(())
+            }
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            use objc::{class, msg_send, runtime::Object, runtime::NO, sel, sel_impl};
+
+            const NS_PROGRESS_INDICATOR_STYLE_BAR: usize = 0;
+
+            // the dock icon is the app's, not the window's
+            let _ = window;
+            unsafe {
+                let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+                let dock_tile: *mut Object = msg_send![app, dockTile];
+                match progress {
+                    Some(progress) => {
+                        // the app's icon, with a bar over its bottom, as NSDockTile has none of
+                        // its own
+                        let size: NSSize = msg_send![dock_tile, size];
+                        let origin = NSPoint { x: 0.0, y: 0.0 };
+                        let view: *mut Object = msg_send![class!(NSImageView), alloc];
+                        let view: *mut Object =
+                            msg_send![view, initWithFrame: NSRect { origin, size }];
+                        let icon: *mut Object = msg_send![app, applicationIconImage];
+                        let () = msg_send![view, setImage: icon];
+                        let bar_frame = NSRect {
+                            origin,
+                            size: NSSize {
+                                width: size.width,
+                                height: size.height / 8.0,
+                            },
+                        };
+                        let bar: *mut Object = msg_send![class!(NSProgressIndicator), alloc];
+                        let bar: *mut Object = msg_send![bar, initWithFrame: bar_frame];
+                        let () = msg_send![bar, setStyle: NS_PROGRESS_INDICATOR_STYLE_BAR];
+                        let () = msg_send![bar, setIndeterminate: NO];
+                        let () = msg_send![bar, setMinValue: 0.0f64];
+                        let () = msg_send![bar, setMaxValue: 1.0f64];
+                        let () = msg_send![bar, setDoubleValue: f64::from(progress)];
+                        let () = msg_send![view, addSubview: bar];
+                        let () = msg_send![dock_tile, setContentView: view];
+                        // the dock tile keeps the view, which keeps the bar
+                        let () = msg_send![bar, release];
+                        let () = msg_send![view, release];
+                    }
+                    None => {
+                        let () =
+                            msg_send![dock_tile, setContentView: std::ptr::null_mut::<Object>()];
+                    }
+                }
+                let () = msg_send![dock_tile, display];
+            }
+            Ok(())
+        }
+
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        {
+            use std::collections::HashMap;
+            use zbus::zvariant::Value;
+
+            // the launcher entry is the app's, on X11 and Wayland alike
+            let _ = window;
+            if self.session_bus.is_none() {
+                self.session_bus = Some(zbus::blocking::Connection::session().map_err(|e| {
+                    GameError::WindowError(format!("failed to connect to the session bus: {}", e))
+                })?);
+            }
+            let bus = self.session_bus.as_ref().unwrap();
+            // Unity's LauncherEntry API, which KDE Plasma and GNOME's Dash to Dock also follow,
+            // knows apps by their desktop file, which is usually named after the executable
+            let exe = std::env::current_exe()?;
+            let app_uri = format!(
+                "application://{}.desktop",
+                exe.file_stem().unwrap_or_default().to_string_lossy()
+            );
+            let mut properties = HashMap::new();
+            let _ = properties.insert("progress", Value::from(f64::from(progress.unwrap_or(0.0))));
+            let _ = properties.insert("progress-visible", Value::from(progress.is_some()));
+            bus.emit_signal(
+                None::<&str>,
+                format!("/com/canonical/unity/launcherentry/{}", std::process::id()),
+                "com.canonical.Unity.LauncherEntry",
+                "Update",
+                &(app_uri, properties),
+            )
+            .map_err(|e| {
+                GameError::WindowError(format!("failed to set the taskbar progress: {}", e))
+            })
+        }
+
+        #[cfg(not(any(
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )))]
+        {
+            let _ = (window, progress);
+            Err(GameError::WindowError(String::from(
+                "taskbar progress is not supported on this platform",
+            )))
+        }
+    }
+}
+
+// Hands resizing the window over to the window manager, like grabbing the window's border would.
+#[allow(unsafe_code)]
+pub(crate) fn drag_resize_window(
+    window: &winit::window::Window,
+    direction: ResizeDirection,
+) -> GameResult {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::ReleaseCapture;
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            GetCursorPos, PostMessageW, HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTLEFT, HTRIGHT,
+            HTTOP, HTTOPLEFT, HTTOPRIGHT, WM_NCLBUTTONDOWN,
+        };
+        use winit::platform::windows::WindowExtWindows;
+
+        let hit_test = match direction {
+            ResizeDirection::North => HTTOP,
+            ResizeDirection::NorthEast => HTTOPRIGHT,
+            ResizeDirection::East => HTRIGHT,
+            ResizeDirection::SouthEast => HTBOTTOMRIGHT,
+            ResizeDirection::South => HTBOTTOM,
+            ResizeDirection::SouthWest => HTBOTTOMLEFT,
+            ResizeDirection::West => HTLEFT,
+            ResizeDirection::NorthWest => HTTOPLEFT,
+        };
+        // pretend the border was clicked
+        let ok = unsafe {
+            let mut cursor = std::mem::zeroed();
+            let _ = GetCursorPos(&mut cursor);
+            let position = (cursor.x as u16 as u32 | (cursor.y as u16 as u32) << 16) as isize;
+            let _ = ReleaseCapture();
+            PostMessageW(window.hwnd(), WM_NCLBUTTONDOWN, hit_test as usize, position)
+        };
+        if ok == 0 {
+            Err(GameError::WindowError(String::from(
+                "failed to start resizing the window",
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use std::{
+            ffi::CString,
+            os::raw::{c_int, c_long, c_uint, c_ulong},
+        };
+        use winit::platform::unix::WindowExtUnix;
+        use x11_dl::xlib;
+
+        let (display, xwindow) = match (window.xlib_display(), window.xlib_window()) {
+            (Some(display), Some(xwindow)) => (display as *mut xlib::Display, xwindow),
+            _ => {
+                return Err(GameError::WindowError(String::from(
+                    "interactive window resizing is only supported on X11, not on Wayland",
+                )))
+            }
+        };
+        // see the _NET_WM_MOVERESIZE section of the EWMH spec
+        let direction: c_long = match direction {
+            ResizeDirection::NorthWest => 0,
+            ResizeDirection::North => 1,
+            ResizeDirection::NorthEast => 2,
+            ResizeDirection::East => 3,
+            ResizeDirection::SouthEast => 4,
+            ResizeDirection::South => 5,
+            ResizeDirection::SouthWest => 6,
+            ResizeDirection::West => 7,
+        };
+        let xlib = xlib::Xlib::open().map_err(|e| GameError::WindowError(e.to_string()))?;
+        let name = CString::new("_NET_WM_MOVERESIZE").unwrap();
+        unsafe {
+            let root = (xlib.XDefaultRootWindow)(display);
+            let (mut root_return, mut child_return): (c_ulong, c_ulong) = (0, 0);
+            let (mut root_x, mut root_y, mut win_x, mut win_y): (c_int, c_int, c_int, c_int) =
+                (0, 0, 0, 0);
+            let mut mask: c_uint = 0;
+            let _ = (xlib.XQueryPointer)(
+                display,
+                xwindow,
+                &mut root_return,
+                &mut child_return,
+                &mut root_x,
+                &mut root_y,
+                &mut win_x,
+                &mut win_y,
+                &mut mask,
+            );
+            // the window manager can't take over while we hold the pointer
+            let _ = (xlib.XUngrabPointer)(display, xlib::CurrentTime);
+
+            let mut message: xlib::XClientMessageEvent = std::mem::zeroed();
+            message.type_ = xlib::ClientMessage;
+            message.window = xwindow;
+            message.message_type = (xlib.XInternAtom)(display, name.as_ptr(), xlib::False);
+            message.format = 32;
+            message.data.set_long(0, c_long::from(root_x));
+            message.data.set_long(1, c_long::from(root_y));
+            message.data.set_long(2, direction);
+            message.data.set_long(3, xlib::Button1 as c_long);
+            message.data.set_long(4, 1);
+            let mut event = xlib::XEvent {
+                client_message: message,
+            };
+            let _ = (xlib.XSendEvent)(
+                display,
+                root,
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+            let _ = (xlib.XFlush)(display);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let _ = (window, direction);
+        Err(GameError::WindowError(String::from(
+            "interactive window resizing is not supported on this platform",
+        )))
+    }
+}
@@ -0,0 +1,427 @@
+//! Virtual axes, combining keys, buttons and gamepad axes into one value for movement.
+//!
+//! ```rust,no_run
+//! # use ggez::Context;
+//! use ggez::input::Axis2D;
+//! # #[cfg(feature = "gamepad")]
+//! use ggez::input::gamepad::gilrs::Axis;
+//!
+//! # fn f(ctx: &Context) {
+//! let mut movement = Axis2D::wasd().or_arrows();
+//! # #[cfg(feature = "gamepad")]
+//! let mut movement = movement.or_gamepad_stick(Axis::LeftStickX, Axis::LeftStickY);
+//!
+//! // in `update`
+//! let direction = movement.value(ctx);
+//! # }
+//! ```
+//!
+//! The bindings are [`AxisBinding`]s like those of an [`InputMap`](super::InputMap),
+//! and the axes can be (de)serialized along with it to keep them rebindable.
+
+use crate::context::Context;
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{gilrs::Axis, PersistentGamepadId};
+use crate::input::input_map::{AxisBinding, Binding, GamepadFilter};
+use crate::input::keyboard::{self, KeyCode};
+use std::time::Duration;
+
+/// The deadzone of gamepad axes and sticks bound without giving one.
+pub const DEFAULT_DEADZONE: f32 = 0.15;
+
+/// How long digital inputs take to move an axis all the way, see [`Axis1D::with_ramp`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ramp {
+    /// The time to move from zero to fully pressed.
+    pub attack: Duration,
+    /// The time to move back from fully pressed to zero.
+    pub release: Duration,
+}
+
+// The value of the digital inputs after ramping, and the frame it's from.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+struct RampState {
+    value: f32,
+    tick: Option<usize>,
+}
+
+impl RampState {
+    // Moves the value towards the one of the pressed inputs, once per frame.
+    fn update(&mut self, target: f32, ramp: Option<Ramp>, ticks: usize, delta: Duration) -> f32 {
+        if self.tick == Some(ticks) {
+            return self.value;
+        }
+        self.tick = Some(ticks);
+        let ramp = match ramp {
+            Some(ramp) => ramp,
+            None => {
+                self.value = target;
+                return target;
+            }
+        };
+        // reversing direction first releases back to zero
+        let (goal, time) = if target * self.value < 0. {
+            (0., ramp.release)
+        } else if target.abs() < self.value.abs() {
+            (target, ramp.release)
+        } else {
+            (target, ramp.attack)
+        };
+        self.value = if time.is_zero() {
+            goal
+        } else {
+            let step = delta.as_secs_f32() / time.as_secs_f32();
+            self.value + (goal - self.value).clamp(-step, step)
+        };
+        self.value
+    }
+}
+
+/// A virtual axis from -1 to 1, like steering or the throttle.
+///
+/// Opposing inputs held at the same time cancel out, and so do different bindings,
+/// e.g. Left and D held at the same time.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Axis1D {
+    bindings: Vec<AxisBinding>,
+    ramp: Option<Ramp>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    state: RampState,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    filter: GamepadFilter,
+}
+
+impl Axis1D {
+    /// Creates an axis without bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an axis moved by two keys.
+    pub fn keys(negative: KeyCode, positive: KeyCode) -> Self {
+        Self::new().or_keys(negative, positive)
+    }
+
+    /// Adds a binding.
+    pub fn or(mut self, binding: AxisBinding) -> Self {
+        if !self.bindings.contains(&binding) {
+            self.bindings.push(binding);
+        }
+        self
+    }
+
+    /// Adds two keys moving the axis.
+    pub fn or_keys(self, negative: KeyCode, positive: KeyCode) -> Self {
+        self.or(AxisBinding::Digital {
+            negative: Binding::Key(negative),
+            positive: Binding::Key(positive),
+        })
+    }
+
+    /// Adds a gamepad axis, with the [`DEFAULT_DEADZONE`].
+    #[cfg(feature = "gamepad")]
+    pub fn or_gamepad_axis(self, axis: Axis) -> Self {
+        self.or(AxisBinding::Gamepad {
+            axis,
+            deadzone: DEFAULT_DEADZONE,
+        })
+    }
+
+    /// Makes keys and buttons move the axis gradually, over the `attack` time when pressed
+    /// and over the `release` time when released. By default they move it at once.
+    /// Gamepad axes aren't affected.
+    pub fn with_ramp(mut self, attack: Duration, release: Duration) -> Self {
+        self.ramp = Some(Ramp { attack, release });
+        self
+    }
+
+    /// Returns the bindings.
+    pub fn bindings(&self) -> &[AxisBinding] {
+        &self.bindings
+    }
+
+    /// Returns the bindings for changing them, e.g. from a rebinding screen.
+    pub fn bindings_mut(&mut self) -> &mut Vec<AxisBinding> {
+        &mut self.bindings
+    }
+
+    /// Returns how gradually keys and buttons move the axis, see [`with_ramp`](Self::with_ramp).
+    pub fn ramp(&self) -> Option<Ramp> {
+        self.ramp
+    }
+
+    /// Sets how gradually keys and buttons move the axis, see [`with_ramp`](Self::with_ramp).
+    pub fn set_ramp(&mut self, ramp: Option<Ramp>) {
+        self.ramp = ramp;
+    }
+
+    /// Sets the gamepad the gamepad bindings apply to; `None` means all connected gamepads,
+    /// like [`InputMap::set_gamepad`](super::InputMap::set_gamepad).
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad(&mut self, gamepad: Option<PersistentGamepadId>) {
+        self.filter.only = gamepad;
+    }
+
+    /// Returns the value of the axis from -1 to 1.
+    ///
+    /// This takes `&mut self` to advance the ramp, which happens once per frame
+    /// no matter how often it's called.
+    pub fn value(&mut self, ctx: &Context) -> f32 {
+        let (digital, analog) = self.inputs(ctx);
+        let digital = self.state.update(
+            digital,
+            self.ramp,
+            ctx.time.ticks(),
+            ctx.time.unscaled_delta(),
+        );
+        (digital + analog).clamp(-1., 1.)
+    }
+
+    // The sums of the digital and the analog bindings.
+    fn inputs(&self, ctx: &Context) -> (f32, f32) {
+        let mut sums = (0., 0.);
+        for binding in &self.bindings {
+            let value = self.filter.axis_value(ctx, *binding);
+            match binding {
+                AxisBinding::Digital { .. } => sums.0 += value,
+                #[cfg(feature = "gamepad")]
+                AxisBinding::Gamepad { .. } => sums.1 += value,
+            }
+        }
+        (sums.0.clamp(-1., 1.), sums.1)
+    }
+}
+
+/// A gamepad stick bound to an [`Axis2D`].
+#[cfg(feature = "gamepad")]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stick {
+    /// The horizontal axis.
+    pub x: Axis,
+    /// The vertical axis, which is negated since gamepads report up as positive.
+    pub y: Axis,
+    /// How far the stick has to be pushed in any direction to count.
+    pub deadzone: f32,
+}
+
+/// A virtual 2D axis for movement, whose value is a direction with a length of up to 1,
+/// with negative y pointing up like screen coordinates.
+///
+/// Diagonals of digital inputs are normalized, so moving diagonally isn't faster.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Axis2D {
+    x: Axis1D,
+    y: Axis1D,
+    #[cfg(feature = "gamepad")]
+    sticks: Vec<Stick>,
+}
+
+impl Axis2D {
+    /// Creates an axis without bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an axis moved by W, A, S and D, by their position on the keyboard
+    /// so they work on any layout.
+    pub fn wasd() -> Self {
+        Self::new().or_scancodes(
+            keyboard::SCANCODE_W,
+            keyboard::SCANCODE_A,
+            keyboard::SCANCODE_S,
+            keyboard::SCANCODE_D,
+        )
+    }
+
+    /// Creates an axis moved by the arrow keys.
+    pub fn arrows() -> Self {
+        Self::new().or_arrows()
+    }
+
+    /// Adds the arrow keys.
+    pub fn or_arrows(self) -> Self {
+        self.or_keys(KeyCode::Up, KeyCode::Left, KeyCode::Down, KeyCode::Right)
+    }
+
+    /// Adds four keys.
+    pub fn or_keys(self, up: KeyCode, left: KeyCode, down: KeyCode, right: KeyCode) -> Self {
+        self.or_bindings(
+            Binding::Key(up),
+            Binding::Key(left),
+            Binding::Key(down),
+            Binding::Key(right),
+        )
+    }
+
+    /// Adds four keys by their position on the keyboard, see [`Binding::ScanCode`].
+    pub fn or_scancodes(
+        self,
+        up: keyboard::ScanCode,
+        left: keyboard::ScanCode,
+        down: keyboard::ScanCode,
+        right: keyboard::ScanCode,
+    ) -> Self {
+        self.or_bindings(
+            Binding::ScanCode(up),
+            Binding::ScanCode(left),
+            Binding::ScanCode(down),
+            Binding::ScanCode(right),
+        )
+    }
+
+    /// Adds four digital inputs, like keys or gamepad buttons.
+    pub fn or_bindings(
+        mut self,
+        up: Binding,
+        left: Binding,
+        down: Binding,
+        right: Binding,
+    ) -> Self {
+        self.x = self.x.or(AxisBinding::Digital {
+            negative: left,
+            positive: right,
+        });
+        self.y = self.y.or(AxisBinding::Digital {
+            negative: up,
+            positive: down,
+        });
+        self
+    }
+
+    /// Adds a gamepad stick, with the [`DEFAULT_DEADZONE`].
+    #[cfg(feature = "gamepad")]
+    pub fn or_gamepad_stick(self, x: Axis, y: Axis) -> Self {
+        self.or_stick(Stick {
+            x,
+            y,
+            deadzone: DEFAULT_DEADZONE,
+        })
+    }
+
+    /// Adds a gamepad stick.
+    #[cfg(feature = "gamepad")]
+    pub fn or_stick(mut self, stick: Stick) -> Self {
+        if !self.sticks.contains(&stick) {
+            self.sticks.push(stick);
+        }
+        self
+    }
+
+    /// Makes keys and buttons move the axis gradually, see [`Axis1D::with_ramp`].
+    pub fn with_ramp(mut self, attack: Duration, release: Duration) -> Self {
+        self.x = self.x.with_ramp(attack, release);
+        self.y = self.y.with_ramp(attack, release);
+        self
+    }
+
+    /// Returns the horizontal axis, for changing its bindings.
+    pub fn x_mut(&mut self) -> &mut Axis1D {
+        &mut self.x
+    }
+
+    /// Returns the vertical axis, for changing its bindings.
+    pub fn y_mut(&mut self) -> &mut Axis1D {
+        &mut self.y
+    }
+
+    /// Returns the gamepad sticks, for changing them.
+    #[cfg(feature = "gamepad")]
+    pub fn sticks_mut(&mut self) -> &mut Vec<Stick> {
+        &mut self.sticks
+    }
+
+    /// Sets the gamepad the gamepad bindings apply to; `None` means all connected gamepads,
+    /// like [`InputMap::set_gamepad`](super::InputMap::set_gamepad).
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad(&mut self, gamepad: Option<PersistentGamepadId>) {
+        self.x.set_gamepad(gamepad);
+        self.y.set_gamepad(gamepad);
+    }
+
+    /// Returns the direction, with a length of up to 1.
+    ///
+    /// This takes `&mut self` to advance the ramp, see [`Axis1D::value`].
+    pub fn value(&mut self, ctx: &Context) -> mint::Vector2<f32> {
+        #[allow(unused_mut)]
+        let mut value = glam::vec2(self.x.value(ctx), self.y.value(ctx));
+        #[cfg(feature = "gamepad")]
+        for stick in &self.sticks {
+            value += self.stick_value(ctx, *stick);
+        }
+        value.clamp_length_max(1.).into()
+    }
+
+    // The position of the stick furthest from the center among the gamepads.
+    #[cfg(feature = "gamepad")]
+    fn stick_value(&self, ctx: &Context, stick: Stick) -> glam::Vec2 {
+        self.x
+            .filter
+            .gamepads(ctx)
+            .map(|id| {
+                glam::vec2(
+                    ctx.gamepad.axis_value(id, stick.x),
+                    -ctx.gamepad.axis_value(id, stick.y),
+                )
+            })
+            .map(|value| radial_deadzone(value, stick.deadzone))
+            .fold(glam::Vec2::ZERO, |a, b| {
+                if b.length_squared() > a.length_squared() {
+                    b
+                } else {
+                    a
+                }
+            })
+    }
+}
+
+// Rescales the stick position so values just outside of the deadzone start from zero.
+#[cfg(feature = "gamepad")]
+fn radial_deadzone(value: glam::Vec2, deadzone: f32) -> glam::Vec2 {
+    let length = value.length();
+    if length <= deadzone || deadzone >= 1. {
+        return glam::Vec2::ZERO;
+    }
+    let scaled = ((length - deadzone) / (1. - deadzone)).min(1.);
+    value * (scaled / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramping() {
+        let ramp = Some(Ramp {
+            attack: Duration::from_millis(100),
+            release: Duration::from_millis(50),
+        });
+        let frame = Duration::from_millis(25);
+        let mut state = RampState::default();
+        assert_eq!(state.update(1., ramp, 1, frame), 0.25);
+        // only once per frame
+        assert_eq!(state.update(1., ramp, 1, frame), 0.25);
+        assert_eq!(state.update(1., ramp, 2, frame), 0.5);
+        // reversing releases to zero first
+        assert_eq!(state.update(-1., ramp, 3, frame), 0.);
+        assert_eq!(state.update(-1., ramp, 4, frame), -0.25);
+        assert_eq!(state.update(0., ramp, 5, frame), 0.);
+
+        assert_eq!(state.update(-1., None, 6, frame), -1.);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn deadzone() {
+        assert_eq!(radial_deadzone(glam::vec2(0.1, 0.1), 0.2), glam::Vec2::ZERO);
+        let value = radial_deadzone(glam::vec2(0.6, 0.), 0.2);
+        assert!((value.x - 0.5).abs() < 1e-6);
+        assert_eq!(
+            radial_deadzone(glam::vec2(0., -1.), 0.2),
+            glam::vec2(0., -1.)
+        );
+    }
+}
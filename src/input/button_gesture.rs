@@ -0,0 +1,249 @@
+//! Telling taps, double taps and holds of a button apart, like "tap to dodge, hold to sprint".
+//!
+//! A [`ButtonGesture`] is fed whether the button is pressed every frame, so it works the same
+//! for keys, mouse buttons, gamepad buttons or anything else which is either on or off:
+//!
+//! ```rust,no_run
+//! # use ggez::Context;
+//! use ggez::input::{keyboard::KeyCode, ButtonGesture};
+//! use std::time::Duration;
+//!
+//! # fn f(ctx: &Context) {
+//! let mut dodge = ButtonGesture::new(Duration::from_millis(250), Duration::from_millis(300));
+//!
+//! // in `update`
+//! dodge.update(ctx.time.delta(), ctx.keyboard.is_key_pressed(KeyCode::Space));
+//! if dodge.tapped() {
+//!     // dodge
+//! } else if dodge.double_tapped() {
+//!     // roll
+//! } else if dodge.held_for() > Duration::from_millis(250) {
+//!     // sprint
+//! }
+//! # }
+//! ```
+
+use std::time::Duration;
+
+/// Classifies the presses of a button into taps, double taps and holds, see the [module docs](self).
+#[derive(Copy, Clone, Debug)]
+pub struct ButtonGesture {
+    hold_threshold: Duration,
+    double_tap_window: Duration,
+    pressed: bool,
+    held: Duration,
+    hold_reported: bool,
+    // whether the current press is the second one of a double tap
+    second_press: bool,
+    // the time since the last tap, while a second one would still make it a double tap
+    since_tap: Option<Duration>,
+    // what happened with the latest update
+    tapped: bool,
+    tapped_eager: bool,
+    double_tapped: bool,
+    hold_started: bool,
+    released_after: Option<Duration>,
+}
+
+impl ButtonGesture {
+    /// Creates a classifier for which pressing the button for at least `hold_threshold` is a hold,
+    /// and shorter presses are taps. A tap following another within `double_tap_window`
+    /// after it was released makes a double tap.
+    pub fn new(hold_threshold: Duration, double_tap_window: Duration) -> Self {
+        ButtonGesture {
+            hold_threshold,
+            double_tap_window,
+            pressed: false,
+            held: Duration::ZERO,
+            hold_reported: false,
+            second_press: false,
+            since_tap: None,
+            tapped: false,
+            tapped_eager: false,
+            double_tapped: false,
+            hold_started: false,
+            released_after: None,
+        }
+    }
+
+    /// Returns how long the button has to be held to count as a hold.
+    pub fn hold_threshold(&self) -> Duration {
+        self.hold_threshold
+    }
+
+    /// Returns the time after a tap in which another tap makes a double tap.
+    pub fn double_tap_window(&self) -> Duration {
+        self.double_tap_window
+    }
+
+    /// Updates the state with the time since the last update and whether the button is pressed.
+    /// Call this once every frame.
+    pub fn update(&mut self, delta: Duration, pressed: bool) {
+        self.tapped = false;
+        self.tapped_eager = false;
+        self.double_tapped = false;
+        self.hold_started = false;
+        self.released_after = None;
+
+        if let Some(since) = &mut self.since_tap {
+            *since += delta;
+        }
+        match (self.pressed, pressed) {
+            (false, true) => {
+                self.held = Duration::ZERO;
+                self.second_press = false;
+                match self.since_tap.take() {
+                    Some(since) if since <= self.double_tap_window => {
+                        self.double_tapped = true;
+                        self.second_press = true;
+                    }
+                    Some(_) => self.tapped = true,
+                    None => {}
+                }
+            }
+            (true, true) | (true, false) => self.held += delta,
+            (false, false) => {
+                if matches!(self.since_tap, Some(since) if since > self.double_tap_window) {
+                    self.since_tap = None;
+                    self.tapped = true;
+                }
+            }
+        }
+
+        if pressed && !self.hold_reported && self.held >= self.hold_threshold {
+            self.hold_reported = true;
+            self.hold_started = true;
+        }
+        if self.pressed && !pressed {
+            self.released_after = Some(self.held);
+            if !self.hold_reported && !self.second_press {
+                self.tapped_eager = true;
+                if self.double_tap_window.is_zero() {
+                    self.tapped = true;
+                } else {
+                    self.since_tap = Some(Duration::ZERO);
+                }
+            }
+            self.held = Duration::ZERO;
+            self.hold_reported = false;
+        }
+        self.pressed = pressed;
+    }
+
+    /// Returns whether the button has been tapped, i.e. pressed shorter than a hold without
+    /// being tapped again within the double tap window.
+    ///
+    /// This is only known once the double tap window has passed, so it's reported that much later.
+    /// To react right away to the first tap of a double tap as well, use [`tapped_eager`](Self::tapped_eager).
+    pub fn tapped(&self) -> bool {
+        self.tapped
+    }
+
+    /// Returns whether the button has been released after being pressed shorter than a hold,
+    /// without waiting for the double tap window. The second tap of a double tap doesn't count.
+    pub fn tapped_eager(&self) -> bool {
+        self.tapped_eager
+    }
+
+    /// Returns whether the button has been pressed again within the double tap window after a tap.
+    /// This is reported with the second press.
+    pub fn double_tapped(&self) -> bool {
+        self.double_tapped
+    }
+
+    /// Returns whether the button has just been held long enough to count as a hold.
+    pub fn hold_started(&self) -> bool {
+        self.hold_started
+    }
+
+    /// Returns how long the button has been pressed, or zero if it isn't.
+    pub fn held_for(&self) -> Duration {
+        if self.pressed {
+            self.held
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Returns how long the button was pressed, if it has just been released.
+    pub fn released_after(&self) -> Option<Duration> {
+        self.released_after
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    // Feeds the button states and returns the frames in which `happened`.
+    fn frames(
+        gesture: &mut ButtonGesture,
+        states: &[bool],
+        happened: impl Fn(&ButtonGesture) -> bool,
+    ) -> Vec<usize> {
+        states
+            .iter()
+            .enumerate()
+            .filter_map(|(frame, pressed)| {
+                gesture.update(FRAME, *pressed);
+                happened(gesture).then(|| frame)
+            })
+            .collect()
+    }
+
+    fn gesture() -> ButtonGesture {
+        ButtonGesture::new(Duration::from_millis(300), Duration::from_millis(200))
+    }
+
+    #[test]
+    fn taps() {
+        let states = [true, false, false, false, false];
+        assert_eq!(
+            frames(&mut gesture(), &states, ButtonGesture::tapped_eager),
+            [1]
+        );
+        // once the window closed
+        assert_eq!(frames(&mut gesture(), &states, ButtonGesture::tapped), [4]);
+        assert_eq!(
+            frames(&mut gesture(), &states, |g| g.released_after().is_some()),
+            [1]
+        );
+
+        let double = [true, false, false, true, false, false, false, false];
+        assert_eq!(
+            frames(&mut gesture(), &double, ButtonGesture::double_tapped),
+            [3]
+        );
+        assert!(frames(&mut gesture(), &double, ButtonGesture::tapped).is_empty());
+        assert_eq!(
+            frames(&mut gesture(), &double, ButtonGesture::tapped_eager),
+            [1]
+        );
+
+        // too late for a double tap
+        let two = [
+            true, false, false, false, false, true, false, false, false, false,
+        ];
+        assert_eq!(frames(&mut gesture(), &two, ButtonGesture::tapped), [4, 9]);
+        assert!(frames(&mut gesture(), &two, ButtonGesture::double_tapped).is_empty());
+    }
+
+    #[test]
+    fn holds() {
+        let states = [true, true, true, true, true, false, false, false, false];
+        let mut hold = gesture();
+        assert_eq!(
+            frames(&mut hold, &states[..4], ButtonGesture::hold_started),
+            [3]
+        );
+        assert_eq!(hold.held_for(), Duration::from_millis(300));
+        hold.update(FRAME, false);
+        assert_eq!(hold.released_after(), Some(Duration::from_millis(400)));
+        assert_eq!(hold.held_for(), Duration::ZERO);
+
+        assert!(frames(&mut gesture(), &states, ButtonGesture::tapped).is_empty());
+        assert!(frames(&mut gesture(), &states, ButtonGesture::tapped_eager).is_empty());
+    }
+}
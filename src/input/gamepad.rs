@@ -5,21 +5,354 @@
 //! cross-platform support.  Why not give it a hand?
 #![cfg(feature = "gamepad")]
 
-use gilrs::ConnectedGamepadsIterator;
+use crate::input::gamepad_backend::{
+    GamepadBackend, GamepadDescription, GamepadEvent, GamepadEventKind, GilrsBackend,
+};
+use instant::Instant;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
+use std::time::{Duration, SystemTime};
 
 pub use gilrs::{self, Event, Gamepad, Gilrs};
 
 /// A unique identifier for a particular GamePad
+///
+/// A gamepad can get another id when it's connected again, see [`PersistentGamepadId`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub struct GamepadId(pub(crate) gilrs::GamepadId);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GamepadId(pub(crate) usize);
 
-use crate::context::Context;
-use crate::error::GameResult;
+impl GamepadId {
+    /// Creates an id, for the gamepads of a [`GamepadBackend`]. Gilrs numbers its gamepads from 0.
+    pub fn new(index: usize) -> Self {
+        GamepadId(index)
+    }
+
+    /// Returns the number of the id.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+use crate::context::{Context, Has};
+use crate::error::{GameError, GameResult};
+use crate::filesystem::Filesystem;
+use crate::input::keyboard::repeat_count;
+use std::io::Read;
+use std::path::Path;
+
+/// An identity of a gamepad which stays the same when it's disconnected and connected again,
+/// and between runs of the game, unlike [`GamepadId`]. With the `serde` feature it can be
+/// serialized, e.g. to remember which gamepad a player used.
+///
+/// It's made of the [`uuid`](GamepadInfo::uuid) and the name the OS reports, which identify
+/// the model of the gamepad, so identical gamepads are told apart by an [`index`](Self::index)
+/// instead: a gamepad connecting gets the lowest index which none of the identical gamepads
+/// connected has. The first of two identical gamepads connected is 0, the second 1, and a gamepad
+/// connected again while the other one stays connected gets its index back. If both are
+/// disconnected, the one connected first again is 0, as gilrs doesn't tell which one it is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistentGamepadId {
+    uuid: [u8; 16],
+    // a hash of the OS name, which is stable between runs unlike the one of std
+    name_hash: u64,
+    index: u32,
+}
+
+impl PersistentGamepadId {
+    pub(crate) fn new(uuid: [u8; 16], os_name: &str, index: u32) -> Self {
+        // FNV-1a
+        let name_hash = os_name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        PersistentGamepadId {
+            uuid,
+            name_hash,
+            index,
+        }
+    }
+
+    /// Returns the UUID of the gamepad.
+    pub fn uuid(&self) -> [u8; 16] {
+        self.uuid
+    }
+
+    /// Returns which of several identical gamepads this is, starting at 0.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+// Returns the persistent id for a gamepad connecting, given the ones connected.
+fn next_persistent_id(
+    connected: &[(GamepadId, GamepadInfo)],
+    description: &GamepadDescription,
+) -> PersistentGamepadId {
+    let mut persistent_id = PersistentGamepadId::new(description.uuid, &description.os_name, 0);
+    while connected
+        .iter()
+        .any(|(_, info)| info.persistent_id == persistent_id)
+    {
+        persistent_id.index += 1;
+    }
+    persistent_id
+}
+
+/// Whether a gamepad runs on a battery and is charging.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChargeState {
+    /// The gamepad is connected by cable and has no battery.
+    Wired,
+    /// The gamepad runs on its battery.
+    Discharging,
+    /// The battery is charging.
+    Charging,
+    /// The battery is fully charged.
+    Charged,
+}
+
+/// Information about a gamepad, e.g. to show "DualSense (wireless, 60%)" on an options screen.
+///
+/// Gamepads, especially wireless ones, don't report everything on every platform,
+/// so some of it is optional.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GamepadInfo {
+    /// The name of the gamepad, from its mapping if there is one or as reported by the OS.
+    pub name: String,
+    /// The name of the gamepad as reported by the OS.
+    pub os_name: String,
+    /// The SDL compatible UUID of the gamepad, identifying its model.
+    pub uuid: [u8; 16],
+    /// The USB vendor id, where the UUID contains it.
+    pub vendor_id: Option<u16>,
+    /// The USB product id, where the UUID contains it.
+    pub product_id: Option<u16>,
+    /// Whether the gamepad runs on a battery and is charging.
+    pub charge_state: Option<ChargeState>,
+    /// How full the battery is in percent.
+    pub battery_level: Option<u8>,
+    /// Whether the buttons and axes are mapped by the SDL gamepad database,
+    /// the driver provides a standard layout, or they are unmapped.
+    pub mapping_source: gilrs::MappingSource,
+    /// The identity of the gamepad which stays the same when it's connected again.
+    pub persistent_id: PersistentGamepadId,
+}
+
+impl GamepadInfo {
+    fn new(
+        description: GamepadDescription,
+        power_info: gilrs::PowerInfo,
+        persistent_id: PersistentGamepadId,
+    ) -> Self {
+        let uuid = description.uuid;
+        let (vendor_id, product_id) = match usb_ids(uuid) {
+            Some((vendor, product)) => (Some(vendor), Some(product)),
+            None => (None, None),
+        };
+        let (charge_state, battery_level) = match power_info {
+            gilrs::PowerInfo::Unknown => (None, None),
+            gilrs::PowerInfo::Wired => (Some(ChargeState::Wired), None),
+            gilrs::PowerInfo::Discharging(level) => (Some(ChargeState::Discharging), Some(level)),
+            gilrs::PowerInfo::Charging(level) => (Some(ChargeState::Charging), Some(level)),
+            gilrs::PowerInfo::Charged => (Some(ChargeState::Charged), Some(100)),
+        };
+        GamepadInfo {
+            name: description.name,
+            os_name: description.os_name,
+            uuid,
+            vendor_id,
+            product_id,
+            charge_state,
+            battery_level,
+            mapping_source: description.mapping_source,
+            persistent_id,
+        }
+    }
+}
+
+// SDL UUIDs are the bus type, a CRC of the name, the vendor id, zero, the product id,
+// zero and the version, in little endian. Others don't have the zeros.
+fn usb_ids(uuid: [u8; 16]) -> Option<(u16, u16)> {
+    if uuid[6..8] != [0, 0] || uuid[10..12] != [0, 0] {
+        return None;
+    }
+    let vendor = u16::from_le_bytes([uuid[4], uuid[5]]);
+    let product = u16::from_le_bytes([uuid[8], uuid[9]]);
+    if vendor == 0 && product == 0 {
+        return None;
+    }
+    Some((vendor, product))
+}
+
+// How long the power info of a gamepad is kept, as querying it can be slow.
+const POWER_INFO_AGE: Duration = Duration::from_secs(5);
+
+// How far an axis has to be pushed to make its gamepad the active one.
+const ACTIVE_AXIS_THRESHOLD: f32 = 0.5;
+
+/// The axes of a gamepad which share [`AxisSettings`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AxisGroup {
+    /// The left stick, `LeftStickX` and `LeftStickY`.
+    LeftStick,
+    /// The right stick, `RightStickX` and `RightStickY`.
+    RightStick,
+    /// The analog triggers, `LeftZ` and `RightZ`, and with them the values of
+    /// `LeftTrigger2` and `RightTrigger2`, see [`GamepadContext::button_value`].
+    ///
+    /// Their settings are independent of the sticks, so triggers can have a deadzone
+    /// of their own. It doesn't change when gilrs reports them as pressed, which is
+    /// at 0.75 and released again at 0.65 by default, see [`gilrs::GilrsBuilder::set_axis_to_btn`].
+    Triggers,
+}
+
+impl AxisGroup {
+    /// Returns the group of an axis, if it has one.
+    pub fn of(axis: gilrs::Axis) -> Option<Self> {
+        use gilrs::Axis::*;
+        match axis {
+            LeftStickX | LeftStickY => Some(AxisGroup::LeftStick),
+            RightStickX | RightStickY => Some(AxisGroup::RightStick),
+            LeftZ | RightZ => Some(AxisGroup::Triggers),
+            _ => None,
+        }
+    }
+}
+
+// The axis a trigger button is reported as too.
+fn trigger_axis(button: gilrs::Button) -> Option<gilrs::Axis> {
+    match button {
+        gilrs::Button::LeftTrigger2 => Some(gilrs::Axis::LeftZ),
+        gilrs::Button::RightTrigger2 => Some(gilrs::Axis::RightZ),
+        _ => None,
+    }
+}
+
+// The trigger button an axis is reported as too.
+fn trigger_button(axis: gilrs::Axis) -> Option<gilrs::Button> {
+    match axis {
+        gilrs::Axis::LeftZ => Some(gilrs::Button::LeftTrigger2),
+        gilrs::Axis::RightZ => Some(gilrs::Button::RightTrigger2),
+        _ => None,
+    }
+}
+
+/// How values near the center of an axis are cut off, to hide the drift of worn sticks.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DeadzoneMode {
+    /// Values are zero while the stick is within this distance of the center, in any direction.
+    /// This keeps diagonal movement smooth, and is the same as `Axial` for triggers.
+    Radial(f32),
+    /// Each axis is zero while it's within this distance of the center on its own,
+    /// which makes it easier to move along only one of them.
+    Axial(f32),
+}
+
+/// How the distance of an axis from its center, after the deadzone, maps to its value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResponseCurve {
+    /// The value grows as the stick is pushed.
+    Linear,
+    /// The value grows slowly at first, for finer control of small movements.
+    Squared,
+    /// The distance raised to this power; above 1 works like `Squared`, below 1 the other way around.
+    Exponent(f32),
+}
+
+/// How the values of an [`AxisGroup`] are processed, see [`GamepadContext::axis_value`].
+///
+/// The default changes nothing: no deadzone, a saturation of 1 and a linear curve.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AxisSettings {
+    /// The deadzone around the center.
+    pub deadzone: DeadzoneMode,
+    /// The distance from the center beyond which an axis counts as fully pushed,
+    /// for sticks which don't quite reach their edges.
+    pub saturation: f32,
+    /// The response curve applied to the distance between the deadzone and the saturation.
+    pub curve: ResponseCurve,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        AxisSettings {
+            deadzone: DeadzoneMode::Radial(0.0),
+            saturation: 1.0,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl AxisSettings {
+    // Maps the distance from the center to the processed distance, from 0 to 1.
+    fn apply(&self, distance: f32, deadzone: f32) -> f32 {
+        if distance <= deadzone {
+            return 0.0;
+        }
+        let scaled = if self.saturation > deadzone {
+            ((distance - deadzone) / (self.saturation - deadzone)).min(1.0)
+        } else {
+            1.0
+        };
+        match self.curve {
+            ResponseCurve::Linear => scaled,
+            ResponseCurve::Squared => scaled * scaled,
+            ResponseCurve::Exponent(exponent) => scaled.powf(exponent),
+        }
+    }
+
+    // Processes the value of an axis, given the value of the other axis of its stick.
+    fn process(&self, value: f32, other: f32) -> f32 {
+        match self.deadzone {
+            DeadzoneMode::Radial(deadzone) => {
+                let distance = value.hypot(other);
+                if distance == 0.0 {
+                    0.0
+                } else {
+                    value / distance * self.apply(distance, deadzone)
+                }
+            }
+            DeadzoneMode::Axial(deadzone) => value.signum() * self.apply(value.abs(), deadzone),
+        }
+    }
+}
 
-/// A structure that contains gamepad state using `gilrs`.
+/// A structure that contains gamepad state, by default using `gilrs`,
+/// see [`gamepad_backend`](crate::input::gamepad_backend).
 pub struct GamepadContext {
-    pub(crate) gilrs: Gilrs,
+    backend: Box<dyn GamepadBackend>,
+    event_time: Option<Instant>,
+    // in the order they were connected
+    connected: Vec<(GamepadId, GamepadInfo)>,
+    active: Option<GamepadId>,
+    // connections and disconnections to pass to the event handler, with whether it's a connection
+    connection_events: VecDeque<(GamepadId, bool)>,
+    // of every gamepad connected so far, also after it's disconnected
+    persistent_ids: HashMap<GamepadId, PersistentGamepadId>,
+    // kept from the events passed to the event handler, so it follows played back recordings
+    states: HashMap<GamepadId, GamepadState>,
+    axis_settings: HashMap<AxisGroup, AxisSettings>,
+    power_infos: RefCell<HashMap<GamepadId, (Instant, gilrs::PowerInfo)>>,
+    gamepad_axis_settings: HashMap<(GamepadId, AxisGroup), AxisSettings>,
+    process_axis_events: bool,
+    // the initial delay and interval of synthesized button repeats
+    repeat: Option<(Duration, Duration)>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct GamepadState {
+    pressed: HashSet<gilrs::Button>,
+    just_pressed: HashSet<gilrs::Button>,
+    just_released: HashSet<gilrs::Button>,
+    pressed_at: HashMap<gilrs::Button, Instant>,
+    // how long each pressed button has been held, in frame time, and which of them repeat this frame
+    held: HashMap<gilrs::Button, Duration>,
+    repeating: HashSet<gilrs::Button>,
+    // the analog values of the buttons which reported one
+    button_values: HashMap<gilrs::Button, f32>,
+    axes: HashMap<gilrs::Axis, f32>,
 }
 
 impl fmt::Debug for GamepadContext {
@@ -30,40 +363,691 @@ impl fmt::Debug for GamepadContext {
 
 impl GamepadContext {
     pub(crate) fn new() -> GameResult<Self> {
-        let gilrs = Gilrs::new()?;
-        Ok(GamepadContext { gilrs })
+        Ok(GamepadContext::with_backend(Box::new(GilrsBackend::new()?)))
+    }
+
+    /// Creates a context which gets its gamepads from a backend, e.g. a
+    /// [`MockBackend`](crate::input::gamepad_backend::MockBackend) in tests.
+    pub fn with_backend(backend: Box<dyn GamepadBackend>) -> Self {
+        // gamepads connected already are reported as being connected with the first events
+        let connected = connected_gamepads(&*backend);
+        let persistent_ids = connected
+            .iter()
+            .map(|(id, info)| (*id, info.persistent_id))
+            .collect();
+        let connection_events = connected.iter().map(|(id, _)| (*id, true)).collect();
+        Self {
+            active: connected.first().map(|(id, _)| *id),
+            backend,
+            event_time: None,
+            connected,
+            connection_events,
+            persistent_ids,
+            states: HashMap::new(),
+            axis_settings: HashMap::new(),
+            power_infos: RefCell::new(HashMap::new()),
+            gamepad_axis_settings: HashMap::new(),
+            process_axis_events: false,
+            repeat: Some((Duration::from_millis(500), Duration::from_millis(50))),
+        }
     }
 }
 
 impl From<Gilrs> for GamepadContext {
     /// Converts from a `Gilrs` custom instance to a `GilrsGamepadContext`
     fn from(gilrs: Gilrs) -> Self {
-        Self { gilrs }
+        GamepadContext::with_backend(Box::new(GilrsBackend::from(gilrs)))
     }
 }
 
+// Returns the gamepads connected to the backend, numbering identical ones in order.
+fn connected_gamepads(backend: &dyn GamepadBackend) -> Vec<(GamepadId, GamepadInfo)> {
+    let mut connected = Vec::new();
+    for id in backend.gamepads() {
+        let description = backend.description(id);
+        let persistent_id = next_persistent_id(&connected, &description);
+        let info = GamepadInfo::new(description, backend.power_info(id), persistent_id);
+        connected.push((id, info));
+    }
+    connected
+}
+
 impl GamepadContext {
-    /// Returns a gamepad event.
-    pub fn next_event(&mut self) -> Option<Event> {
-        self.gilrs.next_event()
+    /// Returns a gamepad event, updating the state of the gamepads returned by
+    /// [`is_button_pressed`](Self::is_button_pressed) and the like.
+    pub fn next_event(&mut self) -> Option<GamepadEvent> {
+        let event = self.next_unapplied_event()?;
+        let id = event.id;
+        match event.kind {
+            GamepadEventKind::ButtonPressed(button) => self.set_button(id, button, true),
+            GamepadEventKind::ButtonReleased(button) => self.set_button(id, button, false),
+            GamepadEventKind::ButtonChanged(button, value) => {
+                let _ = self.set_button_value(id, button, value);
+            }
+            GamepadEventKind::AxisChanged(axis, value) => self.set_axis(id, axis, value),
+            GamepadEventKind::Connected | GamepadEventKind::Disconnected => {}
+        }
+        Some(event)
+    }
+
+    // Returns a gamepad event, leaving the buttons and axes to the caller.
+    pub(crate) fn next_unapplied_event(&mut self) -> Option<GamepadEvent> {
+        let event = self.backend.next_event()?;
+        self.event_time = Some(to_instant(event.time));
+        let id = event.id;
+        let index = self
+            .connected
+            .iter()
+            .position(|(connected, _)| *connected == id);
+        match (event.kind, index) {
+            (GamepadEventKind::Connected, None) => {
+                let description = self.backend.description(id);
+                let persistent_id = next_persistent_id(&self.connected, &description);
+                let info =
+                    GamepadInfo::new(description, self.backend.power_info(id), persistent_id);
+                let _ = self.persistent_ids.insert(id, persistent_id);
+                self.connected.push((id, info));
+                self.connection_events.push_back((id, true));
+                self.active = self.active.or(Some(id));
+            }
+            (GamepadEventKind::Disconnected, Some(index)) => {
+                let _ = self.connected.remove(index);
+                self.connection_events.push_back((id, false));
+                let _ = self.states.remove(&id);
+                let _ = self.power_infos.get_mut().remove(&id);
+                if self.active == Some(id) {
+                    self.active = self.connected.first().map(|(id, _)| *id);
+                }
+            }
+            _ => {}
+        }
+        Some(event)
+    }
+
+    pub(crate) fn set_button(&mut self, id: GamepadId, button: gilrs::Button, pressed: bool) {
+        let time = self.event_time.unwrap_or_else(Instant::now);
+        let state = self.states.entry(id).or_default();
+        if pressed {
+            self.active = Some(id);
+            if state.pressed.insert(button) {
+                let _ = state.just_pressed.insert(button);
+                let _ = state.pressed_at.insert(button, time);
+                let _ = state.held.insert(button, Duration::ZERO);
+            }
+        } else if state.pressed.remove(&button) {
+            let _ = state.just_released.insert(button);
+            let _ = state.pressed_at.remove(&button);
+            let _ = state.held.remove(&button);
+            let _ = state.repeating.remove(&button);
+        }
+    }
+
+    // Sets the analog value of a button, returning the axis it's reported as too for triggers.
+    pub(crate) fn set_button_value(
+        &mut self,
+        id: GamepadId,
+        button: gilrs::Button,
+        value: f32,
+    ) -> Option<gilrs::Axis> {
+        let axis = trigger_axis(button);
+        if let Some(axis) = axis {
+            self.store_axis(id, axis, value);
+        }
+        let state = self.states.entry(id).or_default();
+        let _ = state.button_values.insert(button, value);
+        axis
+    }
+
+    // Triggers reported as axes range from -1 to 1, which is scaled to the 0 to 1 of trigger buttons.
+    pub(crate) fn set_axis(&mut self, id: GamepadId, axis: gilrs::Axis, value: f32) {
+        match trigger_button(axis) {
+            Some(button) => {
+                let value = ((value + 1.0) / 2.0).clamp(0.0, 1.0);
+                self.store_axis(id, axis, value);
+                let state = self.states.entry(id).or_default();
+                let _ = state.button_values.insert(button, value);
+            }
+            None => self.store_axis(id, axis, value),
+        }
+    }
+
+    fn store_axis(&mut self, id: GamepadId, axis: gilrs::Axis, value: f32) {
+        if value.abs() >= ACTIVE_AXIS_THRESHOLD {
+            self.active = Some(id);
+        }
+        let _ = self.states.entry(id).or_default().axes.insert(axis, value);
+    }
+
+    // Returns the connections and disconnections since the last call, with whether it's a connection.
+    pub(crate) fn take_connection_events(&mut self) -> Vec<(GamepadId, bool)> {
+        self.connection_events.drain(..).collect()
+    }
+
+    /// Returns when the latest gamepad event happened, on the same clock as
+    /// [`TimeContext`](crate::timer::TimeContext). In the gamepad events of
+    /// [`EventHandler`](crate::event::EventHandler) that's the time of the event handled.
+    ///
+    /// The time comes from the driver where it's reported, like on Linux,
+    /// otherwise it's when the event was received.
+    pub fn event_time(&self) -> Option<Instant> {
+        self.event_time
+    }
+
+    pub(crate) fn set_event_time(&mut self, time: Instant) {
+        self.event_time = Some(time);
+    }
+
+    /// Returns how long a button of a gamepad has been held down, or `None` if it isn't pressed.
+    pub fn press_time(&self, id: GamepadId, button: gilrs::Button) -> Option<Duration> {
+        self.states
+            .get(&id)
+            .and_then(|state| state.pressed_at.get(&button))
+            .map(Instant::elapsed)
     }
 
     /// Returns the `Gamepad` associated with an `id`.
-    pub fn gamepad(&self, id: GamepadId) -> Gamepad {
-        self.gilrs.gamepad(id.0)
+    ///
+    /// Ids stay valid after the gamepad is disconnected, with [`Gamepad::is_connected`] returning false.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the gamepads don't come from gilrs, see [`gamepad_backend`](crate::input::gamepad_backend).
+    /// [`try_gamepad`](Self::try_gamepad) doesn't, and [`gamepad_info`](Self::gamepad_info)
+    /// works with every backend.
+    pub fn gamepad(&self, id: GamepadId) -> Gamepad<'_> {
+        self.try_gamepad(id)
+            .expect("the gamepad backend doesn't use gilrs")
+    }
+
+    /// Returns the `Gamepad` associated with an `id`, or `None` if the gamepads don't come
+    /// from gilrs, see [`gamepad`](Self::gamepad).
+    pub fn try_gamepad(&self, id: GamepadId) -> Option<Gamepad<'_>> {
+        self.backend.gilrs_gamepad(id)
+    }
+
+    /// Returns whether the buttons and axes of a gamepad are mapped by the SDL gamepad database,
+    /// the driver provides a standard layout, or they are unmapped, e.g. to offer remapping them.
+    pub fn mapping_source(&self, id: GamepadId) -> gilrs::MappingSource {
+        self.backend.description(id).mapping_source
+    }
+
+    /// Adds a gamepad mapping in the format of the [SDL_GameControllerDB](https://github.com/gabomdq/SDL_GameControllerDB),
+    /// for gamepads which aren't in the database built in.
+    ///
+    /// The mapping applies to the gamepads connected already too. For that, they're opened again,
+    /// which stops their rumble and releases their buttons. Where they get other ids then,
+    /// they're reported as disconnected and connected again, and the ids from before are no longer valid.
+    /// This uses the default settings of gilrs, also for contexts converted from a custom [`Gilrs`].
+    /// Other [backends](crate::input::gamepad_backend) may not support mappings and return an error.
+    pub fn add_mapping(&mut self, mapping: &str) -> GameResult {
+        let _ = self.add_mappings(mapping)?;
+        Ok(())
+    }
+
+    /// Adds the gamepad mappings of a file like `gamecontrollerdb.txt` from the
+    /// [SDL_GameControllerDB](https://github.com/gabomdq/SDL_GameControllerDB),
+    /// see [`add_mapping`](Self::add_mapping). Returns how many mappings there were,
+    /// including those for other platforms.
+    ///
+    /// If there's an invalid mapping, none of them are added and the error tells its line.
+    pub fn add_mappings_from_path(
+        &mut self,
+        fs: &impl Has<Filesystem>,
+        path: impl AsRef<Path>,
+    ) -> GameResult<usize> {
+        let mut mappings = String::new();
+        let _ = fs.retrieve().open(path)?.read_to_string(&mut mappings)?;
+        self.add_mappings(&mappings)
+    }
+
+    fn add_mappings(&mut self, mappings: &str) -> GameResult<usize> {
+        let mut count = 0;
+        for (i, line) in mappings.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            validate_mapping(line).map_err(|e| {
+                GameError::GamepadError(format!("Invalid gamepad mapping on line {}: {}", i + 1, e))
+            })?;
+            count += 1;
+        }
+
+        self.backend.add_mappings(mappings)?;
+        self.reopen();
+        Ok(count)
+    }
+
+    // Looks up the gamepads again after the backend opened them again.
+    fn reopen(&mut self) {
+        self.power_infos.get_mut().clear();
+        let reopened = connected_gamepads(&*self.backend);
+        self.persistent_ids
+            .extend(reopened.iter().map(|(id, info)| (*id, info.persistent_id)));
+        let same = |a: &GamepadInfo, b: &GamepadInfo| a.uuid == b.uuid && a.os_name == b.os_name;
+        for (id, info) in &self.connected {
+            if !reopened
+                .iter()
+                .any(|(new_id, new_info)| new_id == id && same(info, new_info))
+            {
+                self.connection_events.push_back((*id, false));
+                if self.active == Some(*id) {
+                    self.active = None;
+                }
+            }
+        }
+        for (id, info) in &reopened {
+            if !self
+                .connected
+                .iter()
+                .any(|(old_id, old_info)| old_id == id && same(info, old_info))
+            {
+                self.connection_events.push_back((*id, true));
+            }
+        }
+        self.states.clear();
+        self.connected = reopened;
+        if self.active.is_none() {
+            self.active = self.connected.first().map(|(id, _)| *id);
+        }
+    }
+
+    /// Returns information about a gamepad, see [`gamepad`](Self::gamepad).
+    ///
+    /// The charge of the battery is queried again after a few seconds at the earliest,
+    /// so this can be called every frame.
+    pub fn gamepad_info(&self, id: GamepadId) -> GamepadInfo {
+        let mut power_infos = self.power_infos.borrow_mut();
+        let power_info = match power_infos.get(&id) {
+            Some((queried, power_info)) if queried.elapsed() < POWER_INFO_AGE => *power_info,
+            _ => {
+                let power_info = self.backend.power_info(id);
+                let _ = power_infos.insert(id, (Instant::now(), power_info));
+                power_info
+            }
+        };
+        GamepadInfo::new(
+            self.backend.description(id),
+            power_info,
+            self.persistent_id(id),
+        )
+    }
+
+    /// Returns the identity of a gamepad which stays the same when it's connected again,
+    /// see [`PersistentGamepadId`].
+    pub fn persistent_id(&self, id: GamepadId) -> PersistentGamepadId {
+        match self.persistent_ids.get(&id) {
+            Some(persistent_id) => *persistent_id,
+            None => {
+                let description = self.backend.description(id);
+                PersistentGamepadId::new(description.uuid, &description.os_name, 0)
+            }
+        }
+    }
+
+    /// Returns the connected gamepad with a persistent id, e.g. to find the gamepad
+    /// a player used the last time the game ran.
+    pub fn find_gamepad(&self, persistent_id: PersistentGamepadId) -> Option<GamepadId> {
+        self.connected
+            .iter()
+            .find(|(_, info)| info.persistent_id == persistent_id)
+            .map(|(id, _)| *id)
+    }
+
+    /// Returns the gamepads that are connected, in the order they were connected.
+    ///
+    /// The information is from when they were connected; for the current charge of
+    /// their batteries, use [`gamepad_info`](Self::gamepad_info).
+    pub fn gamepads(&self) -> impl Iterator<Item = (GamepadId, &GamepadInfo)> + '_ {
+        self.connected.iter().map(|(id, info)| (*id, info))
+    }
+
+    /// Returns the gamepad the player used last, for games which don't care which gamepad is used.
+    ///
+    /// Pressing a button or pushing an axis halfway makes a gamepad the active one.
+    /// Until then, it's the first gamepad connected. When the active gamepad is disconnected,
+    /// the one connected the earliest of the others becomes active, or none if there is none left.
+    pub fn active_gamepad(&self) -> Option<GamepadId> {
+        self.active
+    }
+
+    /// Returns whether a button of the [active gamepad](Self::active_gamepad) is held down.
+    pub fn active_button_pressed(&self, button: gilrs::Button) -> bool {
+        matches!(self.active, Some(id) if self.is_button_pressed(id, button))
+    }
+
+    /// Returns whether a button of the [active gamepad](Self::active_gamepad) has been pressed down this frame.
+    pub fn active_button_just_pressed(&self, button: gilrs::Button) -> bool {
+        matches!(self.active, Some(id) if self.button_just_pressed(id, button))
+    }
+
+    /// Returns whether a button of the [active gamepad](Self::active_gamepad) has been released this frame.
+    pub fn active_button_just_released(&self, button: gilrs::Button) -> bool {
+        matches!(self.active, Some(id) if self.button_just_released(id, button))
+    }
+
+    /// Returns whether a held button of the [active gamepad](Self::active_gamepad) repeats this frame,
+    /// see [`button_repeated`](Self::button_repeated).
+    pub fn active_button_repeated(&self, button: gilrs::Button) -> bool {
+        matches!(self.active, Some(id) if self.button_repeated(id, button))
+    }
+
+    /// Returns the value of an axis of the [active gamepad](Self::active_gamepad),
+    /// see [`axis_value`](Self::axis_value), or 0 without one.
+    pub fn active_axis_value(&self, axis: gilrs::Axis) -> f32 {
+        self.active.map_or(0.0, |id| self.axis_value(id, axis))
+    }
+
+    /// Returns whether the given button of a gamepad is held down.
+    pub fn is_button_pressed(&self, id: GamepadId, button: gilrs::Button) -> bool {
+        matches!(self.states.get(&id), Some(state) if state.pressed.contains(&button))
+    }
+
+    /// Returns whether the given button of a gamepad has been pressed down this frame.
+    ///
+    /// Like [`KeyboardContext::is_key_just_pressed`](crate::input::keyboard::KeyboardContext::is_key_just_pressed)
+    /// this stays true for all `update` calls of a frame, and a button which was pressed and released
+    /// again within a single frame is reported as both just pressed and just released.
+    pub fn button_just_pressed(&self, id: GamepadId, button: gilrs::Button) -> bool {
+        matches!(self.states.get(&id), Some(state) if state.just_pressed.contains(&button))
+    }
+
+    /// Returns whether the given button of a gamepad has been released this frame,
+    /// see [`button_just_pressed`](Self::button_just_pressed).
+    pub fn button_just_released(&self, id: GamepadId, button: gilrs::Button) -> bool {
+        matches!(self.states.get(&id), Some(state) if state.just_released.contains(&button))
+    }
+
+    /// Returns whether a held button of a gamepad repeats this frame, for holding the d-pad
+    /// to scroll through a menu and the like.
+    ///
+    /// The repeats are synthesized from the frame time as set up with [`set_repeat`](Self::set_repeat),
+    /// like [`KeyboardContext::is_key_repeating`](crate::input::keyboard::KeyboardContext::is_key_repeating).
+    /// The initial press isn't a repeat, so check for `button_just_pressed(id, button) || button_repeated(id, button)`
+    /// to act on both.
+    pub fn button_repeated(&self, id: GamepadId, button: gilrs::Button) -> bool {
+        matches!(self.states.get(&id), Some(state) if state.repeating.contains(&button))
+    }
+
+    /// Sets the initial delay and the interval of the repeats reported by
+    /// [`button_repeated`](Self::button_repeated), or disables them with `None`.
+    ///
+    /// Defaults to repeating every 50ms after a delay of 500ms, like the keyboard.
+    pub fn set_repeat(&mut self, repeat: Option<(Duration, Duration)>) {
+        self.repeat = repeat;
+        if repeat.is_none() {
+            for state in self.states.values_mut() {
+                state.repeating.clear();
+            }
+        }
+    }
+
+    /// Returns the initial delay and interval of synthesized button repeats, see [`set_repeat`](Self::set_repeat).
+    pub fn repeat(&self) -> Option<(Duration, Duration)> {
+        self.repeat
+    }
+
+    /// Advances the synthesized button repeats by the time since the last frame. If you are writing
+    /// your own event loop you need to call this before every update in order to use
+    /// `button_repeated`, like `KeyboardContext::update_repeats`. Otherwise this is handled for you.
+    pub fn update_repeats(&mut self, delta: Duration) {
+        let repeat = self.repeat;
+        for state in self.states.values_mut() {
+            state.repeating.clear();
+            let (delay, interval) = match repeat {
+                Some(repeat) => repeat,
+                None => continue,
+            };
+            for (button, held) in &mut state.held {
+                let previous = repeat_count(*held, delay, interval);
+                *held += delta;
+                if repeat_count(*held, delay, interval) > previous {
+                    let _ = state.repeating.insert(*button);
+                }
+            }
+        }
+    }
+
+    /// Returns the buttons of a gamepad which are held down.
+    pub fn buttons_pressed(&self, id: GamepadId) -> impl Iterator<Item = gilrs::Button> + '_ {
+        self.states
+            .get(&id)
+            .into_iter()
+            .flat_map(|state| state.pressed.iter().copied())
+    }
+
+    /// Returns how far a button of a gamepad is pressed, from 0 to 1, for analog triggers
+    /// and the buttons of gamepads reporting their pressure.
+    ///
+    /// Buttons without an analog value are 1 while they're pressed. The values of
+    /// `LeftTrigger2` and `RightTrigger2` are processed with the [`AxisSettings`] of [`AxisGroup::Triggers`].
+    ///
+    /// Depending on the gamepad and its mapping, triggers report either a button value or
+    /// the axes `LeftZ` and `RightZ`. Each is passed on as the other too, so both work with every gamepad:
+    /// `LeftTrigger2` is reported to [`gamepad_axis_event`](crate::event::EventHandler::gamepad_axis_event)
+    /// as `LeftZ` from 0 to 1, and `LeftZ` sets the value of `LeftTrigger2`, scaled from -1 to 1 to 0 to 1.
+    /// Only the button is reported as pressed and released, when the trigger is pulled far enough.
+    pub fn button_value(&self, id: GamepadId, button: gilrs::Button) -> f32 {
+        let value = self.raw_button_value(id, button);
+        match trigger_axis(button) {
+            Some(_) => self
+                .axis_settings(id, AxisGroup::Triggers)
+                .process(value, 0.0),
+            None => value,
+        }
+    }
+
+    /// Returns how far a button of a gamepad is pressed as reported, see [`button_value`](Self::button_value).
+    pub fn raw_button_value(&self, id: GamepadId, button: gilrs::Button) -> f32 {
+        let state = match self.states.get(&id) {
+            Some(state) => state,
+            None => return 0.0,
+        };
+        match state.button_values.get(&button) {
+            Some(value) => *value,
+            None if state.pressed.contains(&button) => 1.0,
+            None => 0.0,
+        }
+    }
+
+    /// Returns the value of an axis of a gamepad, from -1 to 1 and 0 while it hasn't moved yet,
+    /// processed with the [`AxisSettings`] of its group, see [`set_axis_settings`](Self::set_axis_settings).
+    /// The triggers `LeftZ` and `RightZ` range from 0 to 1, see [`button_value`](Self::button_value).
+    pub fn axis_value(&self, id: GamepadId, axis: gilrs::Axis) -> f32 {
+        let value = self.raw_axis_value(id, axis);
+        match AxisGroup::of(axis) {
+            Some(group) => {
+                let other = axis
+                    .second_axis()
+                    .map_or(0.0, |other| self.raw_axis_value(id, other));
+                self.axis_settings(id, group).process(value, other)
+            }
+            None => value,
+        }
+    }
+
+    /// Returns the value of an axis of a gamepad as reported, e.g. for a calibration screen.
+    ///
+    /// That's after the small deadzone of the device, which gilrs applies by default,
+    /// see [`Gamepad::deadzone`]. Contexts converted from a [`Gilrs`] built without
+    /// the default filters report the values of the device.
+    pub fn raw_axis_value(&self, id: GamepadId, axis: gilrs::Axis) -> f32 {
+        self.states
+            .get(&id)
+            .and_then(|state| state.axes.get(&axis))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Returns the settings the axes of a group of a gamepad are processed with.
+    pub fn axis_settings(&self, id: GamepadId, group: AxisGroup) -> AxisSettings {
+        self.gamepad_axis_settings
+            .get(&(id, group))
+            .or_else(|| self.axis_settings.get(&group))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the settings the axes of a group are processed with, on all gamepads
+    /// without settings of their own.
+    pub fn set_axis_settings(&mut self, group: AxisGroup, settings: AxisSettings) {
+        let _ = self.axis_settings.insert(group, settings);
+    }
+
+    /// Sets the deadzone of a group of axes, keeping their other settings.
+    pub fn set_deadzone(&mut self, group: AxisGroup, deadzone: DeadzoneMode) {
+        let settings = self.axis_settings.entry(group).or_default();
+        settings.deadzone = deadzone;
+    }
+
+    /// Sets the settings the axes of a group of one gamepad are processed with,
+    /// or with `None` makes them follow [`set_axis_settings`](Self::set_axis_settings) again.
+    pub fn set_gamepad_axis_settings(
+        &mut self,
+        id: GamepadId,
+        group: AxisGroup,
+        settings: Option<AxisSettings>,
+    ) {
+        match settings {
+            Some(settings) => {
+                let _ = self.gamepad_axis_settings.insert((id, group), settings);
+            }
+            None => {
+                let _ = self.gamepad_axis_settings.remove(&(id, group));
+            }
+        }
+    }
+
+    /// Sets whether the values passed to
+    /// [`EventHandler::gamepad_axis_event`](crate::event::EventHandler::gamepad_axis_event)
+    /// are processed like [`axis_value`](Self::axis_value), rather than raw. They're raw by default.
+    pub fn set_process_axis_events(&mut self, process: bool) {
+        self.process_axis_events = process;
+    }
+
+    /// Returns whether axis events are processed, see [`set_process_axis_events`](Self::set_process_axis_events).
+    pub fn process_axis_events(&self) -> bool {
+        self.process_axis_events
+    }
+
+    /// Returns whether a gamepad can rumble, see [`rumble`](Self::rumble).
+    pub fn supports_rumble(&self, id: GamepadId) -> bool {
+        self.backend.supports_rumble(id)
+    }
+
+    /// Rumbles a gamepad for `duration`, with the strong (low frequency) and the weak
+    /// (high frequency) motor at the given strengths from 0 to 1.
+    ///
+    /// Rumbling again before that replaces the previous rumble, rather than adding to it.
+    /// Gamepads which can't rumble, see [`supports_rumble`](Self::supports_rumble),
+    /// return a [`GamepadError`](crate::GameError::GamepadError).
+    pub fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> GameResult {
+        self.backend.rumble(id, strong, weak, duration)
+    }
+
+    /// Stops the rumble of a gamepad, see [`rumble`](Self::rumble).
+    pub fn stop_rumble(&mut self, id: GamepadId) {
+        self.backend.stop_rumble(id);
     }
 
-    /// Return an iterator of all the `Gamepads` that are connected.
-    pub fn gamepads(&self) -> GamepadsIterator {
-        GamepadsIterator {
-            wrapped: self.gilrs.gamepads(),
+    /// Starts a new frame for the purposes of [`button_just_pressed`](Self::button_just_pressed)
+    /// and [`button_just_released`](Self::button_just_released). If you are writing your own event loop
+    /// you need to call this at the end of every update, like `save_keyboard_state`. Otherwise this is handled for you.
+    pub fn save_gamepad_state(&mut self) {
+        self.backend.new_frame();
+        for state in self.states.values_mut() {
+            state.just_pressed.clear();
+            state.just_released.clear();
         }
     }
 }
 
+// Checks the format of a line of the SDL_GameControllerDB, returning what's wrong.
+fn validate_mapping(line: &str) -> Result<(), String> {
+    let mut fields = line.split(',');
+    let uuid = fields.next().unwrap_or_default();
+    // SDL maps all XInput gamepads with the `xinput` UUID
+    let is_uuid = uuid.len() == 32 && uuid.chars().all(|c| c.is_ascii_hexdigit());
+    if !is_uuid && uuid != "xinput" {
+        return Err(format!("`{}` isn't a gamepad UUID", uuid));
+    }
+    if fields.next().is_none() {
+        return Err(String::from("the name is missing"));
+    }
+    for field in fields.filter(|field| !field.is_empty()) {
+        let (element, input) = field
+            .split_once(':')
+            .ok_or_else(|| format!("`{}` isn't an input mapping", field))?;
+        let element = element.trim_start_matches(['+', '-']);
+        // other keys, like the platform, take any value
+        if SDL_ELEMENTS.contains(&element) && !is_sdl_input(input) {
+            return Err(format!("`{}` isn't a button, axis or hat", input));
+        }
+    }
+    Ok(())
+}
+
+const SDL_ELEMENTS: &[&str] = &[
+    "a",
+    "b",
+    "x",
+    "y",
+    "back",
+    "guide",
+    "start",
+    "leftstick",
+    "rightstick",
+    "leftshoulder",
+    "rightshoulder",
+    "dpup",
+    "dpdown",
+    "dpleft",
+    "dpright",
+    "leftx",
+    "lefty",
+    "rightx",
+    "righty",
+    "lefttrigger",
+    "righttrigger",
+    "misc1",
+    "paddle1",
+    "paddle2",
+    "paddle3",
+    "paddle4",
+    "touchpad",
+];
+
+// Inputs look like `b3`, `a2`, `+a1`, `a0~` or `h0.4`.
+fn is_sdl_input(input: &str) -> bool {
+    let input = input.trim_start_matches(['+', '-']);
+    let input = input.strip_suffix('~').unwrap_or(input);
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    match input.split_at(input.len().min(1)) {
+        ("b", index) | ("a", index) => is_number(index),
+        ("h", hat) => {
+            matches!(hat.split_once('.'), Some((hat, mask)) if is_number(hat) && is_number(mask))
+        }
+        _ => false,
+    }
+}
+
+// gilrs stamps events with the system clock, which unlike `Instant` may jump.
+fn to_instant(time: SystemTime) -> Instant {
+    let age = crate::timer::system_time_now()
+        .duration_since(time)
+        .unwrap_or_default();
+    Instant::now().checked_sub(age).unwrap_or_else(Instant::now)
+}
+
 /// An iterator of the connected gamepads
 pub struct GamepadsIterator<'a> {
-    wrapped: ConnectedGamepadsIterator<'a>,
+    wrapped: std::slice::Iter<'a, (GamepadId, GamepadInfo)>,
 }
 
 impl<'a> fmt::Debug for GamepadsIterator<'a> {
@@ -73,23 +1057,25 @@ impl<'a> fmt::Debug for GamepadsIterator<'a> {
 }
 
 impl<'a> Iterator for GamepadsIterator<'a> {
-    type Item = (GamepadId, Gamepad<'a>);
+    type Item = (GamepadId, &'a GamepadInfo);
 
-    fn next(&mut self) -> Option<(GamepadId, Gamepad<'a>)> {
-        self.wrapped.next().map(|(id, gp)| (GamepadId(id), gp))
+    fn next(&mut self) -> Option<(GamepadId, &'a GamepadInfo)> {
+        self.wrapped.next().map(|(id, info)| (*id, info))
     }
 }
 
-/// Returns the `Gamepad` associated with an `id`.
-#[deprecated(since = "0.8.0", note = "Use `ctx.gamepad.gamepad` instead")]
-pub fn gamepad(ctx: &Context, id: GamepadId) -> Gamepad {
-    ctx.gamepad.gamepad(id)
+/// Returns information about a gamepad, with any [`GamepadBackend`].
+#[deprecated(since = "0.8.0", note = "Use `ctx.gamepad.gamepad_info` instead")]
+pub fn gamepad(ctx: &Context, id: GamepadId) -> GamepadInfo {
+    ctx.gamepad.gamepad_info(id)
 }
 
-/// Return an iterator of all the `Gamepads` that are connected.
+/// Return an iterator of all the gamepads that are connected, with any [`GamepadBackend`].
 #[deprecated(since = "0.8.0", note = "Use `ctx.gamepad.gamepads` instead")]
-pub fn gamepads(ctx: &Context) -> GamepadsIterator {
-    ctx.gamepad.gamepads()
+pub fn gamepads(ctx: &Context) -> GamepadsIterator<'_> {
+    GamepadsIterator {
+        wrapped: ctx.gamepad.connected.iter(),
+    }
 }
 
 // Properties gamepads might want:
@@ -124,4 +1110,253 @@ mod tests {
     fn gilrs_init() {
         assert!(GamepadContext::new().is_ok());
     }
+
+    #[test]
+    fn polled_state() {
+        let mut gamepad = GamepadContext::new().unwrap();
+        let id: GamepadId = GamepadId(0);
+        gamepad.set_button(id, gilrs::Button::South, true);
+        gamepad.set_axis(id, gilrs::Axis::LeftStickX, 0.5);
+        assert!(gamepad.is_button_pressed(id, gilrs::Button::South));
+        assert!(gamepad.button_just_pressed(id, gilrs::Button::South));
+        assert_eq!(
+            gamepad.buttons_pressed(id).collect::<Vec<_>>(),
+            [gilrs::Button::South]
+        );
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::LeftStickX), 0.5);
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::LeftStickY), 0.0);
+
+        gamepad.save_gamepad_state();
+        assert!(!gamepad.button_just_pressed(id, gilrs::Button::South));
+        gamepad.set_button(id, gilrs::Button::South, false);
+        assert!(!gamepad.is_button_pressed(id, gilrs::Button::South));
+        assert!(gamepad.button_just_released(id, gilrs::Button::South));
+    }
+
+    #[test]
+    fn button_repeats() {
+        let mut gamepad = GamepadContext::new().unwrap();
+        let id: GamepadId = GamepadId(0);
+        gamepad.set_repeat(Some((
+            Duration::from_millis(300),
+            Duration::from_millis(100),
+        )));
+        gamepad.set_button(id, gilrs::Button::DPadDown, true);
+        let frame = Duration::from_millis(50);
+        let repeated: Vec<_> = (1..=10)
+            .filter(|_| {
+                gamepad.update_repeats(frame);
+                gamepad.button_repeated(id, gilrs::Button::DPadDown)
+            })
+            .collect();
+        // at 300, 400 and 500ms
+        assert_eq!(repeated, [6, 8, 10]);
+        assert!(!gamepad.button_repeated(id, gilrs::Button::South));
+
+        gamepad.set_button(id, gilrs::Button::DPadDown, false);
+        assert!(!gamepad.button_repeated(id, gilrs::Button::DPadDown));
+        gamepad.update_repeats(Duration::from_secs(1));
+        assert!(!gamepad.button_repeated(id, gilrs::Button::DPadDown));
+    }
+
+    #[test]
+    fn active_gamepad() {
+        let mut gamepad = GamepadContext::new().unwrap();
+        let first: GamepadId = GamepadId(0);
+        let second: GamepadId = GamepadId(1);
+        gamepad.set_button(first, gilrs::Button::South, true);
+        assert_eq!(gamepad.active_gamepad(), Some(first));
+        assert!(gamepad.active_button_pressed(gilrs::Button::South));
+
+        // slight movement doesn't count
+        gamepad.set_axis(second, gilrs::Axis::LeftStickX, 0.2);
+        assert_eq!(gamepad.active_gamepad(), Some(first));
+        gamepad.set_axis(second, gilrs::Axis::LeftStickX, -0.8);
+        assert_eq!(gamepad.active_gamepad(), Some(second));
+        assert!(!gamepad.active_button_pressed(gilrs::Button::South));
+        assert_eq!(gamepad.active_axis_value(gilrs::Axis::LeftStickX), -0.8);
+    }
+
+    #[test]
+    fn trigger_values() {
+        let mut gamepad = GamepadContext::new().unwrap();
+        let id: GamepadId = GamepadId(0);
+        // triggers reported as buttons are passed on as axes
+        assert_eq!(
+            gamepad.set_button_value(id, gilrs::Button::LeftTrigger2, 0.4),
+            Some(gilrs::Axis::LeftZ)
+        );
+        assert_eq!(gamepad.button_value(id, gilrs::Button::LeftTrigger2), 0.4);
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::LeftZ), 0.4);
+        // and the other way around, from -1 to 1
+        gamepad.set_axis(id, gilrs::Axis::RightZ, 0.0);
+        assert_eq!(gamepad.button_value(id, gilrs::Button::RightTrigger2), 0.5);
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::RightZ), 0.5);
+
+        assert_eq!(
+            gamepad.set_button_value(id, gilrs::Button::South, 0.3),
+            None
+        );
+        assert_eq!(gamepad.button_value(id, gilrs::Button::South), 0.3);
+        gamepad.set_button(id, gilrs::Button::East, true);
+        assert_eq!(gamepad.button_value(id, gilrs::Button::East), 1.0);
+
+        // triggers have a deadzone of their own
+        gamepad.set_deadzone(AxisGroup::Triggers, DeadzoneMode::Axial(0.5));
+        assert_eq!(gamepad.button_value(id, gilrs::Button::LeftTrigger2), 0.0);
+        assert_eq!(
+            gamepad.raw_button_value(id, gilrs::Button::LeftTrigger2),
+            0.4
+        );
+        assert_eq!(gamepad.button_value(id, gilrs::Button::South), 0.3);
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::LeftStickX), 0.0);
+    }
+
+    #[test]
+    fn mapping_validation() {
+        let valid = "030000005e0400008e02000014010000,Xbox 360 Controller,a:b0,b:b1,\
+            dpup:h0.1,lefttrigger:a2,+leftx:a0,righty:a4~,platform:Linux,";
+        assert_eq!(validate_mapping(valid), Ok(()));
+        assert!(validate_mapping("030000005e04,Xbox 360 Controller,a:b0").is_err());
+        assert!(validate_mapping("030000005e0400008e02000014010000").is_err());
+        assert!(validate_mapping("030000005e0400008e02000014010000,Pad,a:c0").is_err());
+        assert!(validate_mapping("030000005e0400008e02000014010000,Pad,a").is_err());
+
+        let mut gamepad = GamepadContext::new().unwrap();
+        assert_eq!(
+            gamepad
+                .add_mappings(&format!("# comment\n{}\n\n", valid))
+                .unwrap(),
+            1
+        );
+        match gamepad.add_mappings(&format!("{}\nnonsense", valid)) {
+            Err(GameError::GamepadError(e)) => assert!(e.contains("line 2"), "{}", e),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn mock_backend() {
+        use crate::input::gamepad_backend::MockBackend;
+
+        let mock = MockBackend::new();
+        let mut gamepad = GamepadContext::with_backend(Box::new(mock.clone()));
+        let description = GamepadDescription {
+            name: "Mock pad".to_owned(),
+            ..GamepadDescription::default()
+        };
+        let id = mock.connect(description);
+        mock.press(id, gilrs::Button::South);
+        mock.move_axis(id, gilrs::Axis::LeftStickX, 0.5);
+        while gamepad.next_event().is_some() {}
+        assert_eq!(gamepad.take_connection_events(), [(id, true)]);
+        assert_eq!(gamepad.gamepad_info(id).name, "Mock pad");
+        assert!(gamepad.try_gamepad(id).is_none());
+        assert_eq!(
+            gamepad.gamepads().map(|(id, _)| id).collect::<Vec<_>>(),
+            [id]
+        );
+        assert!(gamepad.is_button_pressed(id, gilrs::Button::South));
+        assert!(gamepad.press_time(id, gilrs::Button::South).is_some());
+        assert_eq!(gamepad.raw_axis_value(id, gilrs::Axis::LeftStickX), 0.5);
+
+        assert!(gamepad.supports_rumble(id));
+        gamepad
+            .rumble(id, 1.0, 0.5, Duration::from_millis(200))
+            .unwrap();
+        assert_eq!(
+            mock.rumble_of(id),
+            Some((1.0, 0.5, Duration::from_millis(200)))
+        );
+        gamepad.stop_rumble(id);
+        assert_eq!(mock.rumble_of(id), None);
+
+        mock.disconnect(id);
+        while gamepad.next_event().is_some() {}
+        assert_eq!(gamepad.take_connection_events(), [(id, false)]);
+        assert!(!gamepad.is_button_pressed(id, gilrs::Button::South));
+        assert_eq!(gamepad.gamepads().count(), 0);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_functions_with_mock_backend() {
+        use crate::input::gamepad_backend::MockBackend;
+        use crate::test::headless_context;
+
+        let mock = MockBackend::new();
+        let mut ctx = match headless_context(
+            crate::ContextBuilder::new("test", "ggez")
+                .with_conf_file(false)
+                .gamepad_backend(Box::new(mock.clone())),
+        ) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let id = mock.connect(GamepadDescription {
+            name: "Mock pad".to_owned(),
+            ..GamepadDescription::default()
+        });
+        while ctx.gamepad.next_event().is_some() {}
+        assert_eq!(gamepad(&ctx, id).name, "Mock pad");
+        let connected: Vec<_> = gamepads(&ctx)
+            .map(|(id, info)| (id, info.name.clone()))
+            .collect();
+        assert_eq!(connected, [(id, "Mock pad".to_owned())]);
+        assert!(ctx.gamepad.try_gamepad(id).is_none());
+    }
+
+    #[test]
+    fn persistent_ids() {
+        let a = PersistentGamepadId::new([1; 16], "Pad", 0);
+        assert_eq!(a, PersistentGamepadId::new([1; 16], "Pad", 0));
+        assert_ne!(a, PersistentGamepadId::new([1; 16], "Other pad", 0));
+        assert_ne!(a, PersistentGamepadId::new([1; 16], "Pad", 1));
+        // the same between runs and platforms
+        assert_eq!(a.name_hash, 0x8dd3_8019_f42f_ba0a);
+    }
+
+    #[test]
+    fn usb_ids_from_uuid() {
+        // an Xbox 360 controller on Linux
+        let uuid = [
+            0x03, 0x00, 0x00, 0x00, 0x5e, 0x04, 0x00, 0x00, 0x8e, 0x02, 0x00, 0x00, 0x14, 0x01,
+            0x00, 0x00,
+        ];
+        assert_eq!(usb_ids(uuid), Some((0x045e, 0x028e)));
+        assert_eq!(usb_ids([0; 16]), None);
+        assert_eq!(usb_ids([1; 16]), None);
+    }
+
+    #[test]
+    fn axis_settings() {
+        let settings = AxisSettings {
+            deadzone: DeadzoneMode::Radial(0.2),
+            saturation: 0.9,
+            curve: ResponseCurve::Linear,
+        };
+        assert_eq!(settings.process(0.1, 0.1), 0.0);
+        assert!((settings.process(0.55, 0.0) - 0.5).abs() < 1e-6);
+        assert_eq!(settings.process(-0.95, 0.0), -1.0);
+        // radial deadzones keep diagonals
+        assert!(settings.process(0.15, 0.15) > 0.0);
+
+        let axial = AxisSettings {
+            deadzone: DeadzoneMode::Axial(0.2),
+            curve: ResponseCurve::Squared,
+            ..settings
+        };
+        assert_eq!(axial.process(0.15, 0.15), 0.0);
+        assert!((axial.process(-0.55, 0.0) + 0.25).abs() < 1e-6);
+        assert_eq!(AxisSettings::default().process(0.3, 0.4), 0.3);
+
+        let mut gamepad = GamepadContext::new().unwrap();
+        let id: GamepadId = GamepadId(0);
+        gamepad.set_axis(id, gilrs::Axis::LeftStickX, 0.1);
+        gamepad.set_deadzone(AxisGroup::LeftStick, DeadzoneMode::Radial(0.2));
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::LeftStickX), 0.0);
+        assert_eq!(gamepad.raw_axis_value(id, gilrs::Axis::LeftStickX), 0.1);
+        gamepad.set_gamepad_axis_settings(id, AxisGroup::LeftStick, Some(AxisSettings::default()));
+        assert_eq!(gamepad.axis_value(id, gilrs::Axis::LeftStickX), 0.1);
+    }
 }
@@ -0,0 +1,475 @@
+//! Where the gamepads of a [`GamepadContext`](crate::input::gamepad::GamepadContext) come from.
+//!
+//! By default that's [`gilrs`], through the [`GilrsBackend`]. Another [`GamepadBackend`] can be
+//! set with [`ContextBuilder::gamepad_backend`](crate::ContextBuilder::gamepad_backend), e.g. for
+//! a device gilrs doesn't support. The events and the polled state of the context work the same
+//! with every backend.
+//!
+//! The [`MockBackend`] feeds made up input, which makes gamepad code testable without any gamepads:
+//!
+//! ```rust
+//! use ggez::input::gamepad::{gilrs::Button, GamepadContext};
+//! use ggez::input::gamepad_backend::{GamepadDescription, MockBackend};
+//!
+//! let mock = MockBackend::new();
+//! let mut gamepad = GamepadContext::with_backend(Box::new(mock.clone()));
+//! let id = mock.connect(GamepadDescription::default());
+//! mock.press(id, Button::South);
+//! while gamepad.next_event().is_some() {}
+//! assert!(gamepad.is_button_pressed(id, Button::South));
+//! ```
+#![cfg(feature = "gamepad")]
+
+use crate::error::{GameError, GameResult};
+use crate::input::gamepad::GamepadId;
+use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Repeat, Replay, Ticks};
+use gilrs::{Axis, Button, Gamepad, Gilrs};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+/// Something which happened with a gamepad, as reported by a [`GamepadBackend`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GamepadEvent {
+    /// The gamepad.
+    pub id: GamepadId,
+    /// What happened.
+    pub kind: GamepadEventKind,
+    /// When it happened.
+    pub time: SystemTime,
+}
+
+/// What happened with a gamepad, see [`GamepadEvent`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GamepadEventKind {
+    /// The gamepad was connected.
+    Connected,
+    /// The gamepad was disconnected.
+    Disconnected,
+    /// A button was pressed.
+    ButtonPressed(Button),
+    /// A button was released.
+    ButtonReleased(Button),
+    /// How far a button is pressed changed, from 0 to 1.
+    ButtonChanged(Button, f32),
+    /// An axis moved, from -1 to 1.
+    AxisChanged(Axis, f32),
+}
+
+/// What a [`GamepadBackend`] tells about a gamepad, which makes its
+/// [`GamepadInfo`](crate::input::gamepad::GamepadInfo).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GamepadDescription {
+    /// The name of the gamepad, from its mapping if there is one or as reported by the OS.
+    pub name: String,
+    /// The name of the gamepad as reported by the OS.
+    pub os_name: String,
+    /// The SDL compatible UUID of the gamepad, identifying its model.
+    pub uuid: [u8; 16],
+    /// Whether the buttons and axes are mapped.
+    pub mapping_source: gilrs::MappingSource,
+}
+
+impl Default for GamepadDescription {
+    fn default() -> Self {
+        GamepadDescription {
+            name: String::from("Gamepad"),
+            os_name: String::from("Gamepad"),
+            uuid: [0; 16],
+            mapping_source: gilrs::MappingSource::None,
+        }
+    }
+}
+
+/// A source of gamepads and their input for a [`GamepadContext`](crate::input::gamepad::GamepadContext).
+///
+/// Backends report what happens as [`GamepadEvent`]s; the context keeps the state of the gamepads
+/// from them. Only the events and the gamepads connected are required, the rest has defaults for
+/// backends which can't do it.
+pub trait GamepadBackend: fmt::Debug {
+    /// Returns the next event, or `None` if there are none for now.
+    fn next_event(&mut self) -> Option<GamepadEvent>;
+
+    /// Returns the gamepads which are connected, in the order they were connected.
+    fn gamepads(&self) -> Vec<GamepadId>;
+
+    /// Describes a gamepad the backend reported, also after it's disconnected.
+    fn description(&self, id: GamepadId) -> GamepadDescription;
+
+    /// Returns whether a gamepad runs on a battery and how full it is. This is called
+    /// every few seconds at most, so it may be slow.
+    fn power_info(&self, _id: GamepadId) -> gilrs::PowerInfo {
+        gilrs::PowerInfo::Unknown
+    }
+
+    /// Returns whether a gamepad can rumble.
+    fn supports_rumble(&self, _id: GamepadId) -> bool {
+        false
+    }
+
+    /// Rumbles a gamepad, see [`GamepadContext::rumble`](crate::input::gamepad::GamepadContext::rumble).
+    fn rumble(
+        &mut self,
+        _id: GamepadId,
+        _strong: f32,
+        _weak: f32,
+        _duration: Duration,
+    ) -> GameResult {
+        Err(GameError::GamepadError(String::from(
+            "The gamepad backend doesn't support rumble",
+        )))
+    }
+
+    /// Stops the rumble of a gamepad.
+    fn stop_rumble(&mut self, _id: GamepadId) {}
+
+    /// Adds SDL gamepad mappings, which have been checked already,
+    /// see [`GamepadContext::add_mapping`](crate::input::gamepad::GamepadContext::add_mapping).
+    /// The gamepads connected are looked up again afterwards.
+    fn add_mappings(&mut self, _mappings: &str) -> GameResult {
+        Err(GameError::GamepadError(String::from(
+            "The gamepad backend doesn't support mappings",
+        )))
+    }
+
+    /// Called at the end of every frame.
+    fn new_frame(&mut self) {}
+
+    /// Returns the gilrs instance, for backends using gilrs.
+    fn gilrs(&self) -> Option<&Gilrs> {
+        None
+    }
+
+    /// Returns the gilrs gamepad of an id, for backends using gilrs.
+    fn gilrs_gamepad(&self, _id: GamepadId) -> Option<Gamepad<'_>> {
+        None
+    }
+}
+
+/// The default backend, which uses [`gilrs`].
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    // the rumble playing on each gamepad, which stops when dropped
+    rumbles: HashMap<GamepadId, gilrs::ff::Effect>,
+    // gilrs ids can't be created from their index, so the ones seen are kept
+    ids: HashMap<GamepadId, gilrs::GamepadId>,
+    // the SDL mappings added, to create gilrs with
+    mappings: String,
+}
+
+impl fmt::Debug for GilrsBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<GilrsBackend: {:p}>", self)
+    }
+}
+
+impl GilrsBackend {
+    /// Creates the backend with the default settings of gilrs.
+    pub fn new() -> GameResult<Self> {
+        Ok(GilrsBackend::from(Gilrs::new()?))
+    }
+
+    /// Returns the gilrs instance.
+    pub fn gilrs_mut(&mut self) -> &mut Gilrs {
+        &mut self.gilrs
+    }
+
+    fn gamepad(&self, id: GamepadId) -> Gamepad<'_> {
+        self.gilrs.gamepad(self.gilrs_id(id))
+    }
+
+    fn gilrs_id(&self, id: GamepadId) -> gilrs::GamepadId {
+        *self
+            .ids
+            .get(&id)
+            .expect("the gamepad wasn't reported by gilrs")
+    }
+
+    fn set_gilrs(&mut self, gilrs: Gilrs) {
+        self.ids = connected_ids(&gilrs);
+        self.gilrs = gilrs;
+    }
+}
+
+fn connected_ids(gilrs: &Gilrs) -> HashMap<GamepadId, gilrs::GamepadId> {
+    gilrs
+        .gamepads()
+        .map(|(id, _)| (GamepadId::from(id), id))
+        .collect()
+}
+
+impl From<Gilrs> for GilrsBackend {
+    fn from(gilrs: Gilrs) -> Self {
+        GilrsBackend {
+            ids: connected_ids(&gilrs),
+            gilrs,
+            rumbles: HashMap::new(),
+            mappings: String::new(),
+        }
+    }
+}
+
+impl From<gilrs::GamepadId> for GamepadId {
+    fn from(id: gilrs::GamepadId) -> Self {
+        GamepadId(usize::from(id))
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        loop {
+            let event = self.gilrs.next_event()?;
+            let _ = self.ids.insert(GamepadId::from(event.id), event.id);
+            let kind = match event.event {
+                gilrs::EventType::Connected => GamepadEventKind::Connected,
+                gilrs::EventType::Disconnected => {
+                    let _ = self.rumbles.remove(&GamepadId::from(event.id));
+                    GamepadEventKind::Disconnected
+                }
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    GamepadEventKind::ButtonPressed(button)
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    GamepadEventKind::ButtonReleased(button)
+                }
+                gilrs::EventType::ButtonChanged(button, value, _) => {
+                    GamepadEventKind::ButtonChanged(button, value)
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    GamepadEventKind::AxisChanged(axis, value)
+                }
+                // repeats are synthesized from the frame time instead
+                _ => continue,
+            };
+            return Some(GamepadEvent {
+                id: GamepadId::from(event.id),
+                kind,
+                time: event.time,
+            });
+        }
+    }
+
+    fn gamepads(&self) -> Vec<GamepadId> {
+        self.gilrs
+            .gamepads()
+            .map(|(id, _)| GamepadId::from(id))
+            .collect()
+    }
+
+    fn description(&self, id: GamepadId) -> GamepadDescription {
+        let gamepad = self.gamepad(id);
+        GamepadDescription {
+            name: gamepad.name().to_owned(),
+            os_name: gamepad.os_name().to_owned(),
+            uuid: gamepad.uuid(),
+            mapping_source: gamepad.mapping_source(),
+        }
+    }
+
+    fn power_info(&self, id: GamepadId) -> gilrs::PowerInfo {
+        self.gamepad(id).power_info()
+    }
+
+    fn supports_rumble(&self, id: GamepadId) -> bool {
+        let gamepad = self
+            .ids
+            .get(&id)
+            .and_then(|&id| self.gilrs.connected_gamepad(id));
+        matches!(gamepad, Some(gamepad) if gamepad.is_ff_supported())
+    }
+
+    fn rumble(&mut self, id: GamepadId, strong: f32, weak: f32, duration: Duration) -> GameResult {
+        let play_for = Ticks::from_ms(duration.as_millis().try_into().unwrap_or(u32::MAX));
+        let scheduling = Replay {
+            play_for,
+            ..Replay::default()
+        };
+        let magnitude = |strength: f32| (strength.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(strong),
+                },
+                scheduling,
+                ..BaseEffect::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(weak),
+                },
+                scheduling,
+                ..BaseEffect::default()
+            })
+            .gamepads(&[self.gilrs_id(id)])
+            .repeat(Repeat::For(play_for))
+            .finish(&mut self.gilrs)?;
+        effect.play()?;
+        // replacing the previous rumble drops and with that stops it
+        let _ = self.rumbles.insert(id, effect);
+        Ok(())
+    }
+
+    fn stop_rumble(&mut self, id: GamepadId) {
+        let _ = self.rumbles.remove(&id);
+    }
+
+    // gilrs can't change the mappings of the gamepads connected, so it's created again with them
+    fn add_mappings(&mut self, mappings: &str) -> GameResult {
+        let mut all = self.mappings.clone();
+        all.push_str(mappings);
+        all.push('\n');
+        self.set_gilrs(gilrs::GilrsBuilder::new().add_mappings(&all).build()?);
+        self.mappings = all;
+        self.rumbles.clear();
+        Ok(())
+    }
+
+    fn new_frame(&mut self) {
+        self.gilrs.inc();
+    }
+
+    fn gilrs(&self) -> Option<&Gilrs> {
+        Some(&self.gilrs)
+    }
+
+    fn gilrs_gamepad(&self, id: GamepadId) -> Option<Gamepad<'_>> {
+        Some(self.gamepad(id))
+    }
+}
+
+/// A backend without any gamepads, e.g. for platforms gilrs doesn't support.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NullBackend;
+
+impl GamepadBackend for NullBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        None
+    }
+
+    fn gamepads(&self) -> Vec<GamepadId> {
+        Vec::new()
+    }
+
+    fn description(&self, _id: GamepadId) -> GamepadDescription {
+        GamepadDescription::default()
+    }
+}
+
+/// A backend with made up gamepads, for tests, see the [module docs](self).
+///
+/// Clones share the gamepads, so one can be given to the context and another one kept to drive it.
+/// Every change is reported as an event right away, with the current time.
+#[derive(Clone, Debug, Default)]
+pub struct MockBackend {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    events: VecDeque<GamepadEvent>,
+    // every gamepad connected so far, with whether it's still connected
+    gamepads: Vec<(GamepadDescription, bool)>,
+    rumbles: HashMap<GamepadId, (f32, f32, Duration)>,
+}
+
+impl MockBackend {
+    /// Creates a backend without any gamepads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // the state stays consistent when a test panics
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Connects a gamepad, returning its id.
+    pub fn connect(&self, description: GamepadDescription) -> GamepadId {
+        let mut state = self.state();
+        let id = GamepadId(state.gamepads.len());
+        state.gamepads.push((description, true));
+        drop(state);
+        self.push_event(id, GamepadEventKind::Connected);
+        id
+    }
+
+    /// Disconnects a gamepad. Connecting it again gives it another id, as gilrs may.
+    pub fn disconnect(&self, id: GamepadId) {
+        if let Some((_, connected)) = self.state().gamepads.get_mut(id.0) {
+            *connected = false;
+        }
+        self.push_event(id, GamepadEventKind::Disconnected);
+    }
+
+    /// Presses a button all the way.
+    pub fn press(&self, id: GamepadId, button: Button) {
+        self.push_event(id, GamepadEventKind::ButtonPressed(button));
+        self.push_event(id, GamepadEventKind::ButtonChanged(button, 1.0));
+    }
+
+    /// Releases a button.
+    pub fn release(&self, id: GamepadId, button: Button) {
+        self.push_event(id, GamepadEventKind::ButtonReleased(button));
+        self.push_event(id, GamepadEventKind::ButtonChanged(button, 0.0));
+    }
+
+    /// Moves an axis.
+    pub fn move_axis(&self, id: GamepadId, axis: Axis, value: f32) {
+        self.push_event(id, GamepadEventKind::AxisChanged(axis, value));
+    }
+
+    /// Reports any event.
+    pub fn push_event(&self, id: GamepadId, kind: GamepadEventKind) {
+        self.state().events.push_back(GamepadEvent {
+            id,
+            kind,
+            time: crate::timer::system_time_now(),
+        });
+    }
+
+    /// Returns the strengths and the duration of the latest rumble of a gamepad,
+    /// unless it was stopped.
+    pub fn rumble_of(&self, id: GamepadId) -> Option<(f32, f32, Duration)> {
+        self.state().rumbles.get(&id).copied()
+    }
+}
+
+impl GamepadBackend for MockBackend {
+    fn next_event(&mut self) -> Option<GamepadEvent> {
+        self.state().events.pop_front()
+    }
+
+    fn gamepads(&self) -> Vec<GamepadId> {
+        self.state()
+            .gamepads
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, connected))| *connected)
+            .map(|(index, _)| GamepadId(index))
+            .collect()
+    }
+
+    fn description(&self, id: GamepadId) -> GamepadDescription {
+        self.state()
+            .gamepads
+            .get(id.0)
+            .map(|(description, _)| description.clone())
+            .unwrap_or_default()
+    }
+
+    fn supports_rumble(&self, _id: GamepadId) -> bool {
+        true
+    }
+
+    fn rumble(&mut self, id: GamepadId, strong: f32, weak: f32, duration: Duration) -> GameResult {
+        let _ = self.state().rumbles.insert(id, (strong, weak, duration));
+        Ok(())
+    }
+
+    fn stop_rumble(&mut self, id: GamepadId) {
+        let _ = self.state().rumbles.remove(&id);
+    }
+}
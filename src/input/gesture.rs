@@ -0,0 +1,279 @@
+//! Recognition of two finger gestures, pinching, rotating and panning, from touches.
+//!
+//! The gestures of each frame are passed to [`EventHandler::pinch_event`](crate::event::EventHandler::pinch_event),
+//! [`rotate_event`](crate::event::EventHandler::rotate_event) and [`pan_event`](crate::event::EventHandler::pan_event),
+//! and are available from [`TouchContext::gestures`](crate::input::touch::TouchContext::gestures).
+//! They are recognized in addition to the touch events, which are still delivered.
+//!
+//! Touchpad gestures, such as macOS' magnify and rotate, aren't reported by the window library yet.
+
+use super::touch::{Touch, TouchPhase};
+use std::f32::consts::PI;
+
+/// A gesture made with two fingers, in physical pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gesture {
+    /// The fingers moved apart (`scale` above 1) or together (`scale` below 1).
+    Pinch {
+        /// How much the distance between the fingers changed, as a factor.
+        scale: f32,
+        /// The point between the fingers.
+        center: mint::Point2<f32>,
+    },
+    /// The fingers rotated around each other.
+    Rotate {
+        /// The change of angle in radians, clockwise on the screen.
+        angle: f32,
+        /// The point between the fingers.
+        center: mint::Point2<f32>,
+    },
+    /// The fingers moved in the same direction.
+    Pan {
+        /// How far the point between the fingers moved.
+        delta: mint::Vector2<f32>,
+    },
+}
+
+// The state of two fingers touching the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Fingers {
+    ids: (u64, u64),
+    distance: f32,
+    angle: f32,
+    center: glam::Vec2,
+}
+
+impl Fingers {
+    fn new(a: &Touch, b: &Touch) -> Self {
+        let (a_pos, b_pos) = (glam::Vec2::from(a.position), glam::Vec2::from(b.position));
+        let offset = b_pos - a_pos;
+        Fingers {
+            ids: (a.id, b.id),
+            distance: offset.length(),
+            angle: offset.y.atan2(offset.x),
+            center: (a_pos + b_pos) / 2.,
+        }
+    }
+}
+
+// A gesture is reported once it passes its threshold, and from then on with every change.
+#[derive(Copy, Clone, Debug, Default)]
+struct Tracked {
+    reference: Option<Fingers>,
+    active: bool,
+}
+
+/// Recognizes gestures from two fingers touching the screen.
+///
+/// To keep a sloppy two finger tap from registering as a gesture, each one needs
+/// to pass a threshold first, see [`set_thresholds`](Self::set_thresholds).
+#[derive(Copy, Clone, Debug)]
+pub struct GestureRecognizer {
+    pinch: Tracked,
+    rotate: Tracked,
+    pan: Tracked,
+    pinch_threshold: f32,
+    rotate_threshold: f32,
+    pan_threshold: f32,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        GestureRecognizer {
+            pinch: Tracked::default(),
+            rotate: Tracked::default(),
+            pan: Tracked::default(),
+            pinch_threshold: 0.1,
+            rotate_threshold: 0.15,
+            pan_threshold: 20.,
+        }
+    }
+}
+
+impl GestureRecognizer {
+    /// Creates a recognizer with the default thresholds:
+    /// a 10% change of distance, 0.15 radians and 20 pixels.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how much the distance between the fingers has to change as a factor for pinching,
+    /// how far they have to rotate in radians and to move in physical pixels for panning,
+    /// until each of these gestures is recognized.
+    pub fn set_thresholds(&mut self, pinch: f32, rotate: f32, pan: f32) {
+        self.pinch_threshold = pinch;
+        self.rotate_threshold = rotate;
+        self.pan_threshold = pan;
+    }
+
+    /// Recognizes the gestures since the last update from the current touches.
+    /// Gestures are only recognized while exactly two fingers touch the screen.
+    pub fn update(&mut self, touches: &[Touch]) -> Vec<Gesture> {
+        let mut active = touches
+            .iter()
+            .filter(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled));
+        let fingers = match (active.next(), active.next(), active.next()) {
+            (Some(a), Some(b), None) => Fingers::new(a, b),
+            _ => {
+                self.reset();
+                return Vec::new();
+            }
+        };
+        // other fingers than before start new gestures
+        if !matches!(self.pan.reference, Some(reference) if reference.ids == fingers.ids) {
+            self.reset();
+        }
+
+        let mut gestures = Vec::new();
+        let pinch_threshold = self.pinch_threshold;
+        if let Some(reference) = track(&mut self.pinch, fingers, |reference| {
+            reference.distance > 0.
+                && (fingers.distance / reference.distance - 1.).abs() > pinch_threshold
+        }) {
+            if reference.distance > 0. && fingers.distance != reference.distance {
+                gestures.push(Gesture::Pinch {
+                    scale: fingers.distance / reference.distance,
+                    center: fingers.center.into(),
+                });
+            }
+        }
+        let rotate_threshold = self.rotate_threshold;
+        if let Some(reference) = track(&mut self.rotate, fingers, |reference| {
+            angle_between(reference.angle, fingers.angle).abs() > rotate_threshold
+        }) {
+            let angle = angle_between(reference.angle, fingers.angle);
+            if angle != 0. {
+                gestures.push(Gesture::Rotate {
+                    angle,
+                    center: fingers.center.into(),
+                });
+            }
+        }
+        let pan_threshold = self.pan_threshold;
+        if let Some(reference) = track(&mut self.pan, fingers, |reference| {
+            reference.center.distance(fingers.center) > pan_threshold
+        }) {
+            let delta = fingers.center - reference.center;
+            if delta != glam::Vec2::ZERO {
+                gestures.push(Gesture::Pan {
+                    delta: delta.into(),
+                });
+            }
+        }
+        gestures
+    }
+
+    fn reset(&mut self) {
+        self.pinch = Tracked::default();
+        self.rotate = Tracked::default();
+        self.pan = Tracked::default();
+    }
+}
+
+// Returns what the gesture changed from, if it's recognized.
+fn track(
+    tracked: &mut Tracked,
+    fingers: Fingers,
+    passes_threshold: impl Fn(&Fingers) -> bool,
+) -> Option<Fingers> {
+    let reference = match tracked.reference {
+        Some(reference) => reference,
+        None => {
+            tracked.reference = Some(fingers);
+            return None;
+        }
+    };
+    // until recognized, the change is measured from where the fingers started
+    if !tracked.active && !passes_threshold(&reference) {
+        return None;
+    }
+    tracked.active = true;
+    tracked.reference = Some(fingers);
+    Some(reference)
+}
+
+// The shortest signed rotation from one angle to another.
+fn angle_between(from: f32, to: f32) -> f32 {
+    let angle = (to - from) % (2. * PI);
+    if angle > PI {
+        angle - 2. * PI
+    } else if angle < -PI {
+        angle + 2. * PI
+    } else {
+        angle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, x: f32, y: f32) -> Touch {
+        let position = mint::Point2 { x, y };
+        Touch {
+            id,
+            phase: TouchPhase::Moved,
+            position,
+            previous_position: position,
+            force: None,
+        }
+    }
+
+    #[test]
+    fn pinch_with_threshold() {
+        let mut gestures = GestureRecognizer::new();
+        assert!(gestures
+            .update(&[touch(0, 0., 0.), touch(1, 100., 0.)])
+            .is_empty());
+        // too small a change
+        assert!(gestures
+            .update(&[touch(0, 0., 0.), touch(1, 105., 0.)])
+            .is_empty());
+        let recognized = gestures.update(&[touch(0, 0., 0.), touch(1, 120., 0.)]);
+        assert_eq!(
+            recognized,
+            [Gesture::Pinch {
+                scale: 1.2,
+                center: mint::Point2 { x: 60., y: 0. }
+            }]
+        );
+        // from now on every change is reported
+        let recognized = gestures.update(&[touch(0, 0., 0.), touch(1, 126., 0.)]);
+        assert_eq!(
+            recognized,
+            [Gesture::Pinch {
+                scale: 1.05,
+                center: mint::Point2 { x: 63., y: 0. }
+            }]
+        );
+
+        // lifting a finger ends the gesture
+        assert!(gestures.update(&[touch(0, 0., 0.)]).is_empty());
+        assert!(gestures
+            .update(&[touch(0, 0., 0.), touch(2, 105., 0.)])
+            .is_empty());
+    }
+
+    #[test]
+    fn rotate_and_pan() {
+        let mut gestures = GestureRecognizer::new();
+        let _ = gestures.update(&[touch(0, 0., 0.), touch(1, 100., 0.)]);
+        let recognized = gestures.update(&[touch(0, 0., 50.), touch(1, 100., 50.)]);
+        assert_eq!(
+            recognized,
+            [Gesture::Pan {
+                delta: mint::Vector2 { x: 0., y: 50. }
+            }]
+        );
+
+        let recognized = gestures.update(&[touch(0, 50., 0.), touch(1, 50., 100.)]);
+        match recognized[..] {
+            [Gesture::Rotate { angle, center }] => {
+                assert!((angle - PI / 2.).abs() < 1e-5);
+                assert_eq!(center, mint::Point2 { x: 50., y: 50. });
+            }
+            _ => panic!("unexpected gestures {:?}", recognized),
+        }
+        assert!((angle_between(3., -3.) - (2. * PI - 6.)).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,928 @@
+//! Mapping of physical inputs to game actions, for rebindable controls.
+//!
+//! Instead of checking for `KeyCode::Space` all over the game, bind the inputs to actions once
+//! and query the actions:
+//!
+//! ```rust,no_run
+//! # use ggez::Context;
+//! use ggez::input::{input_map::Binding, keyboard::KeyCode, InputMap};
+//!
+//! #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+//! enum Action {
+//!     Jump,
+//! }
+//!
+//! # fn f(ctx: &Context) {
+//! let mut map = InputMap::new();
+//! map.bind(Action::Jump, Binding::Key(KeyCode::Space));
+//! map.bind(Action::Jump, Binding::Key(KeyCode::W));
+//!
+//! if map.just_pressed(ctx, Action::Jump) {
+//!     // ...
+//! }
+//! # }
+//! ```
+//!
+//! With the `serde` feature, maps can be (de)serialized with serde, e.g. to store the player's
+//! bindings from an options screen.
+//!
+//! # Saving bindings
+//!
+//! With the `serde` feature, [`InputMap::save`] and [`InputMap::load`] store the bindings
+//! in a versioned TOML file in the user config directory, which stays readable as the game changes:
+//!
+//! * Actions in the file which the game doesn't know (anymore) are kept and saved again,
+//!   so going back to another version of the game doesn't lose them.
+//! * Actions missing from the file get their default bindings.
+//! * Keys are stored by the names of their [`KeyCode`], e.g. `"Space"` or `"LShift"`.
+//! * Scancodes are stored as PC scancodes (set 1, as reported on Windows and Linux),
+//!   so e.g. a [`SCANCODE_W`](crate::input::keyboard::SCANCODE_W) binding moves between platforms.
+//!   On macOS the keys of the `SCANCODE_*` constants are translated; the other macOS keys
+//!   are stored as their macOS scancode plus `0x10000`, and only match on macOS.
+//!
+//! ```toml
+//! version = 1
+//!
+//! [[buttons]]
+//! action = "Jump"
+//!
+//! [[buttons.bindings]]
+//! type = "Key"
+//! input = "Space"
+//! ```
+
+use crate::context::Context;
+#[cfg(feature = "serde")]
+use crate::context::Has;
+#[cfg(feature = "serde")]
+use crate::filesystem::Filesystem;
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{
+    gilrs::{Axis, Button},
+    GamepadId, PersistentGamepadId,
+};
+use crate::input::keyboard::{KeyCode, ScanCode};
+use crate::input::mouse::MouseButton;
+#[cfg(feature = "serde")]
+use crate::{GameError, GameResult};
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::hash::Hash;
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+// The version of the files written by `InputMap::save`.
+#[cfg(feature = "serde")]
+const FORMAT_VERSION: u32 = 1;
+
+/// A physical input which can be bound to an action of an [`InputMap`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "input"))]
+pub enum Binding {
+    /// A keyboard key, by its meaning in the user's keyboard layout.
+    Key(KeyCode),
+    /// A keyboard key, by its physical position (see the `SCANCODE_*` constants in
+    /// [`keyboard`](crate::input::keyboard)), e.g. for WASD movement on any layout.
+    ScanCode(#[cfg_attr(feature = "serde", serde(with = "portable_scancode"))] ScanCode),
+    /// A mouse button.
+    MouseButton(MouseButton),
+    /// A gamepad button.
+    #[cfg(feature = "gamepad")]
+    GamepadButton(Button),
+    /// A gamepad axis, counting as pressed while it is pushed beyond `threshold`
+    /// (towards negative values for a negative threshold).
+    ///
+    /// Axes don't report being just pressed or released.
+    #[cfg(feature = "gamepad")]
+    GamepadAxis {
+        /// The axis.
+        axis: Axis,
+        /// The value beyond which the axis counts as pressed.
+        threshold: f32,
+    },
+}
+
+/// An input which can be bound to an analog action of an [`InputMap`], see [`InputMap::axis`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "input"))]
+pub enum AxisBinding {
+    /// Two digital inputs, moving the axis to -1 while `negative` and to 1 while `positive` is pressed.
+    Digital {
+        /// The input for negative values, e.g. the left arrow key.
+        negative: Binding,
+        /// The input for positive values, e.g. the right arrow key.
+        positive: Binding,
+    },
+    /// A gamepad axis, with values within `deadzone` of zero reported as zero.
+    #[cfg(feature = "gamepad")]
+    Gamepad {
+        /// The axis.
+        axis: Axis,
+        /// The deadzone around the center.
+        deadzone: f32,
+    },
+}
+
+/// Maps physical inputs to game actions, with any number of bindings per action.
+///
+/// `A` is usually an enum of all the actions in the game. Bindings are resolved against the live
+/// keyboard, mouse and gamepad state of the [`Context`] when actions are queried.
+///
+/// For local multiplayer, give each player their own map bound to one gamepad with
+/// [`InputMap::for_gamepad`]; keyboard and mouse bindings apply to every map.
+/// Maps are bound to the [`PersistentGamepadId`] of a gamepad, so they follow it when it's
+/// connected again, also in the next session.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "A: serde::Serialize + Eq + Hash",
+        deserialize = "A: serde::Deserialize<'de> + Eq + Hash"
+    ))
+)]
+pub struct InputMap<A> {
+    // stored as lists, as formats like toml only allow strings as map keys
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "serialize_entries",
+            deserialize_with = "deserialize_entries"
+        )
+    )]
+    buttons: HashMap<A, Vec<Binding>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "serialize_entries",
+            deserialize_with = "deserialize_entries"
+        )
+    )]
+    axes: HashMap<A, Vec<AxisBinding>>,
+    #[cfg(feature = "gamepad")]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    gamepad: Option<PersistentGamepadId>,
+    // the entries of a loaded file which couldn't be read, to save them again
+    #[cfg(feature = "serde")]
+    #[serde(skip)]
+    unknown_buttons: Vec<toml::Value>,
+    #[cfg(feature = "serde")]
+    #[serde(skip)]
+    unknown_axes: Vec<toml::Value>,
+}
+
+/// What [`InputMap::load`] made of a file, e.g. to point out problems on an options screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadReport<A> {
+    /// The actions missing from the file, which got their default bindings.
+    pub defaulted: Vec<A>,
+    /// How many entries of actions the game doesn't know were kept.
+    pub unknown_actions: usize,
+    /// How many bindings couldn't be read and were kept, e.g. gamepad bindings
+    /// when the `gamepad` feature is disabled.
+    pub unknown_bindings: usize,
+    /// The bindings bound to more than one action, see [`InputMap::conflicts`].
+    pub conflicts: Vec<(Binding, Vec<A>)>,
+}
+
+impl<A: Eq + Hash> Default for InputMap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Eq + Hash> InputMap<A> {
+    /// Creates an empty map, whose gamepad bindings apply to all connected gamepads.
+    pub fn new() -> Self {
+        InputMap {
+            buttons: HashMap::new(),
+            axes: HashMap::new(),
+            #[cfg(feature = "gamepad")]
+            gamepad: None,
+            #[cfg(feature = "serde")]
+            unknown_buttons: Vec::new(),
+            #[cfg(feature = "serde")]
+            unknown_axes: Vec::new(),
+        }
+    }
+
+    /// Creates an empty map, whose gamepad bindings only apply to the given gamepad,
+    /// see [`GamepadContext::persistent_id`](crate::input::gamepad::GamepadContext::persistent_id).
+    #[cfg(feature = "gamepad")]
+    pub fn for_gamepad(gamepad: PersistentGamepadId) -> Self {
+        InputMap {
+            gamepad: Some(gamepad),
+            ..Self::new()
+        }
+    }
+
+    /// Sets the gamepad the gamepad bindings apply to; `None` means all connected gamepads.
+    ///
+    /// This is serialized with serde, but not saved by [`save`](Self::save), which only stores the bindings.
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad(&mut self, gamepad: Option<PersistentGamepadId>) {
+        self.gamepad = gamepad;
+    }
+
+    /// Returns the gamepad the gamepad bindings apply to; `None` means all connected gamepads.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad(&self) -> Option<PersistentGamepadId> {
+        self.gamepad
+    }
+
+    /// Adds a binding to an action, unless it's bound to it already.
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        let bindings = self.buttons.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Adds an axis binding to an action, see [`InputMap::axis`].
+    pub fn bind_axis(&mut self, action: A, binding: AxisBinding) {
+        let bindings = self.axes.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes a binding from an action.
+    pub fn unbind(&mut self, action: &A, binding: Binding) {
+        if let Some(bindings) = self.buttons.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Removes all bindings, including axis bindings, from an action.
+    pub fn clear(&mut self, action: &A) {
+        let _ = self.buttons.remove(action);
+        let _ = self.axes.remove(action);
+    }
+
+    /// Returns the bindings of an action.
+    pub fn bindings(&self, action: &A) -> &[Binding] {
+        self.buttons.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the axis bindings of an action.
+    pub fn axis_bindings(&self, action: &A) -> &[AxisBinding] {
+        self.axes.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns whether any of the action's bindings is pressed.
+    pub fn pressed(&self, ctx: &Context, action: A) -> bool {
+        self.bindings(&action)
+            .iter()
+            .any(|binding| self.filter().pressed(ctx, *binding))
+    }
+
+    /// Returns whether any of the action's bindings has been pressed this frame.
+    pub fn just_pressed(&self, ctx: &Context, action: A) -> bool {
+        self.bindings(&action)
+            .iter()
+            .any(|binding| self.filter().just_pressed(ctx, *binding))
+    }
+
+    /// Returns whether any of the action's bindings has been released this frame.
+    pub fn just_released(&self, ctx: &Context, action: A) -> bool {
+        self.bindings(&action)
+            .iter()
+            .any(|binding| self.filter().just_released(ctx, *binding))
+    }
+
+    /// Returns the value of an analog action in `-1.0..=1.0`, summing up all of its axis bindings.
+    pub fn axis(&self, ctx: &Context, action: A) -> f32 {
+        let filter = self.filter();
+        let value: f32 = self
+            .axis_bindings(&action)
+            .iter()
+            .map(|binding| filter.axis_value(ctx, *binding))
+            .sum();
+        value.clamp(-1.0, 1.0)
+    }
+
+    /// Returns the bindings which are bound to more than one action, along with those actions,
+    /// e.g. to warn about conflicts on a rebinding screen. Digital axis bindings are included.
+    pub fn conflicts(&self) -> Vec<(Binding, Vec<&A>)> {
+        let all = self
+            .buttons
+            .iter()
+            .flat_map(|(action, bindings)| bindings.iter().map(move |b| (*b, action)))
+            .chain(self.axes.iter().flat_map(|(action, bindings)| {
+                bindings.iter().flat_map(move |b| match *b {
+                    AxisBinding::Digital { negative, positive } => {
+                        vec![(negative, action), (positive, action)]
+                    }
+                    #[cfg(feature = "gamepad")]
+                    AxisBinding::Gamepad { .. } => Vec::new(),
+                })
+            }));
+
+        let mut grouped: Vec<(Binding, Vec<&A>)> = Vec::new();
+        for (binding, action) in all {
+            match grouped.iter_mut().find(|(b, _)| *b == binding) {
+                Some((_, actions)) => {
+                    if !actions.contains(&action) {
+                        actions.push(action);
+                    }
+                }
+                None => grouped.push((binding, vec![action])),
+            }
+        }
+        grouped.retain(|(_, actions)| actions.len() > 1);
+        grouped
+    }
+
+    /// Writes the bindings as TOML, in the format described in the [module docs](self).
+    #[cfg(feature = "serde")]
+    pub fn write_to(&self, mut writer: impl io::Write) -> GameResult
+    where
+        A: serde::Serialize,
+    {
+        // as a table, which writes the empty sections before the others as TOML requires
+        let mut file = toml::value::Table::new();
+        let _ = file.insert(String::from("version"), FORMAT_VERSION.into());
+        let _ = file.insert(
+            String::from("buttons"),
+            toml::Value::Array(write_section(&self.buttons, &self.unknown_buttons)?),
+        );
+        let _ = file.insert(
+            String::from("axes"),
+            toml::Value::Array(write_section(&self.axes, &self.unknown_axes)?),
+        );
+        writer.write_all(&toml::to_vec(&toml::Value::Table(file))?)?;
+        Ok(())
+    }
+
+    /// Saves the bindings to a file in the user config directory,
+    /// see [`Filesystem::create`](crate::filesystem::Filesystem::create).
+    #[cfg(feature = "serde")]
+    pub fn save(&self, fs: &impl Has<Filesystem>, path: impl AsRef<Path>) -> GameResult
+    where
+        A: serde::Serialize,
+    {
+        let file = fs.retrieve().create(path)?;
+        self.write_to(io::BufWriter::new(file))
+    }
+
+    /// Reads bindings written by [`write_to`](Self::write_to), taking the bindings of the actions
+    /// missing from them, and the gamepad, from `defaults`.
+    #[cfg(feature = "serde")]
+    pub fn read_from(
+        mut reader: impl io::Read,
+        defaults: &Self,
+    ) -> GameResult<(Self, LoadReport<A>)>
+    where
+        A: DeserializeOwned + Clone,
+    {
+        let mut s = String::new();
+        let _ = reader.read_to_string(&mut s)?;
+        let file: toml::value::Table = toml::from_str(&s)?;
+        match file.get("version").and_then(toml::Value::as_integer) {
+            Some(version) if version >= 1 && version <= FORMAT_VERSION.into() => {}
+            Some(version) => {
+                return Err(GameError::ResourceLoadError(format!(
+                    "Input bindings have format version {}, expected at most {}",
+                    version, FORMAT_VERSION
+                )))
+            }
+            None => {
+                return Err(GameError::ResourceLoadError(String::from(
+                    "Input bindings have no format version",
+                )))
+            }
+        }
+        Ok(Self::from_file(&file, defaults))
+    }
+
+    /// Loads bindings saved by [`save`](Self::save), taking the bindings of the actions
+    /// missing from the file, and the gamepad, from `defaults`.
+    /// Without a file, e.g. on the first start of the game, the defaults are returned.
+    ///
+    /// The report tells which actions got their defaults and which bindings conflict,
+    /// among others.
+    #[cfg(feature = "serde")]
+    pub fn load(
+        fs: &impl Has<Filesystem>,
+        path: impl AsRef<Path>,
+        defaults: &Self,
+    ) -> GameResult<(Self, LoadReport<A>)>
+    where
+        A: DeserializeOwned + Clone,
+    {
+        let fs = fs.retrieve();
+        if !fs.exists(&path) {
+            return Ok(Self::from_file(&toml::value::Table::new(), defaults));
+        }
+        let file = fs.open(path)?;
+        Self::read_from(io::BufReader::new(file), defaults)
+    }
+
+    #[cfg(feature = "serde")]
+    fn from_file(file: &toml::value::Table, defaults: &Self) -> (Self, LoadReport<A>)
+    where
+        A: DeserializeOwned + Clone,
+    {
+        let mut map = Self::new();
+        #[cfg(feature = "gamepad")]
+        map.set_gamepad(defaults.gamepad);
+        let (mut unknown_actions, mut unknown_bindings) = read_section(
+            file.get("buttons"),
+            &mut map.buttons,
+            &mut map.unknown_buttons,
+        );
+        let (actions, bindings) =
+            read_section(file.get("axes"), &mut map.axes, &mut map.unknown_axes);
+        unknown_actions += actions;
+        unknown_bindings += bindings;
+
+        let mut defaulted = Vec::new();
+        for (action, bindings) in &defaults.buttons {
+            if !map.buttons.contains_key(action) {
+                let _ = map.buttons.insert(action.clone(), bindings.clone());
+                defaulted.push(action.clone());
+            }
+        }
+        for (action, bindings) in &defaults.axes {
+            if !map.axes.contains_key(action) {
+                let _ = map.axes.insert(action.clone(), bindings.clone());
+                if !defaulted.contains(action) {
+                    defaulted.push(action.clone());
+                }
+            }
+        }
+
+        let conflicts = map
+            .conflicts()
+            .into_iter()
+            .map(|(binding, actions)| (binding, actions.into_iter().cloned().collect()))
+            .collect();
+        let report = LoadReport {
+            defaulted,
+            unknown_actions,
+            unknown_bindings,
+            conflicts,
+        };
+        (map, report)
+    }
+
+    fn filter(&self) -> GamepadFilter {
+        GamepadFilter {
+            #[cfg(feature = "gamepad")]
+            only: self.gamepad,
+        }
+    }
+}
+
+// Which gamepads bindings are resolved against, shared with the virtual axes.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub(crate) struct GamepadFilter {
+    #[cfg(feature = "gamepad")]
+    pub(crate) only: Option<PersistentGamepadId>,
+}
+
+impl GamepadFilter {
+    pub(crate) fn pressed(self, ctx: &Context, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => ctx.keyboard.is_key_pressed(key),
+            Binding::ScanCode(code) => ctx.keyboard.is_scancode_pressed(code),
+            Binding::MouseButton(button) => ctx.mouse.button_pressed(button),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadButton(button) => self
+                .gamepads(ctx)
+                .any(|id| ctx.gamepad.is_button_pressed(id, button)),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadAxis { axis, threshold } => {
+                self.gamepad_axis_values(ctx, axis).any(|value| {
+                    if threshold < 0.0 {
+                        value <= threshold
+                    } else {
+                        value >= threshold
+                    }
+                })
+            }
+        }
+    }
+
+    fn just_pressed(self, ctx: &Context, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => ctx.keyboard.is_key_just_pressed(key),
+            Binding::ScanCode(code) => ctx.keyboard.is_scancode_just_pressed(code),
+            Binding::MouseButton(button) => ctx.mouse.button_just_pressed(button),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadButton(button) => self
+                .gamepads(ctx)
+                .any(|id| ctx.gamepad.button_just_pressed(id, button)),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadAxis { .. } => false,
+        }
+    }
+
+    fn just_released(self, ctx: &Context, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => ctx.keyboard.is_key_just_released(key),
+            Binding::ScanCode(code) => ctx.keyboard.is_scancode_just_released(code),
+            Binding::MouseButton(button) => ctx.mouse.button_just_released(button),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadButton(button) => self
+                .gamepads(ctx)
+                .any(|id| ctx.gamepad.button_just_released(id, button)),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadAxis { .. } => false,
+        }
+    }
+
+    // The value of a single axis binding, not clamped.
+    pub(crate) fn axis_value(self, ctx: &Context, binding: AxisBinding) -> f32 {
+        match binding {
+            AxisBinding::Digital { negative, positive } => {
+                let negative = if self.pressed(ctx, negative) {
+                    1.0
+                } else {
+                    0.0
+                };
+                let positive = if self.pressed(ctx, positive) {
+                    1.0
+                } else {
+                    0.0
+                };
+                positive - negative
+            }
+            #[cfg(feature = "gamepad")]
+            AxisBinding::Gamepad { axis, deadzone } => {
+                // the value furthest from the center among the gamepads
+                self.gamepad_axis_values(ctx, axis)
+                    .filter(|value| value.abs() > deadzone)
+                    .fold(0.0, |a: f32, b: f32| if b.abs() > a.abs() { b } else { a })
+            }
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn gamepads<'a>(self, ctx: &'a Context) -> impl Iterator<Item = GamepadId> + 'a {
+        let only = self.only;
+        ctx.gamepad
+            .gamepads()
+            .filter(move |(_, info)| only.is_none() || only == Some(info.persistent_id))
+            .map(|(id, _)| id)
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn gamepad_axis_values<'a>(
+        self,
+        ctx: &'a Context,
+        axis: Axis,
+    ) -> impl Iterator<Item = f32> + 'a {
+        self.gamepads(ctx)
+            .map(move |id| ctx.gamepad.axis_value(id, axis))
+    }
+}
+
+// Scancodes in files are PC scancodes, see the module docs.
+#[cfg(feature = "serde")]
+mod portable_scancode {
+    use crate::input::keyboard::ScanCode;
+    #[cfg(target_os = "macos")]
+    use crate::input::keyboard::PC_AND_MAC_SCANCODES;
+
+    // marks macOS scancodes without a PC equivalent, and the other way around
+    #[cfg(target_os = "macos")]
+    const MAC_ONLY: u32 = 0x1_0000;
+    #[cfg(target_os = "macos")]
+    const PC_ONLY: u32 = 0x2_0000;
+
+    #[cfg(target_os = "macos")]
+    pub(super) fn to_portable(code: ScanCode) -> u32 {
+        if code >= PC_ONLY {
+            return code - PC_ONLY;
+        }
+        PC_AND_MAC_SCANCODES
+            .iter()
+            .find(|(_, mac)| *mac == code)
+            .map_or(code + MAC_ONLY, |(pc, _)| *pc)
+    }
+
+    #[cfg(target_os = "macos")]
+    pub(super) fn from_portable(code: u32) -> ScanCode {
+        if (MAC_ONLY..PC_ONLY).contains(&code) {
+            return code - MAC_ONLY;
+        }
+        PC_AND_MAC_SCANCODES
+            .iter()
+            .find(|(pc, _)| *pc == code)
+            .map_or(code + PC_ONLY, |(_, mac)| *mac)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub(super) fn to_portable(code: ScanCode) -> u32 {
+        code
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub(super) fn from_portable(code: u32) -> ScanCode {
+        code
+    }
+
+    pub(super) fn serialize<S: serde::Serializer>(
+        code: &ScanCode,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(to_portable(*code))
+    }
+
+    pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ScanCode, D::Error> {
+        let code: u32 = serde::Deserialize::deserialize(deserializer)?;
+        Ok(from_portable(code))
+    }
+}
+
+// Reads the entries of a section of a file, keeping those which can't be read.
+// Returns how many unknown actions and bindings there were.
+#[cfg(feature = "serde")]
+fn read_section<A, T>(
+    section: Option<&toml::Value>,
+    entries: &mut HashMap<A, Vec<T>>,
+    unknown: &mut Vec<toml::Value>,
+) -> (usize, usize)
+where
+    A: DeserializeOwned + Eq + Hash,
+    T: DeserializeOwned + PartialEq,
+{
+    let (mut unknown_actions, mut unknown_bindings) = (0, 0);
+    for raw in section
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let parsed = raw
+            .get("action")
+            .map(|raw_action| (raw_action, raw_action.clone().try_into::<A>()));
+        let (raw_action, action) = match parsed {
+            Some((raw_action, Ok(action))) => (raw_action, action),
+            _ => {
+                unknown_actions += 1;
+                unknown.push(raw.clone());
+                continue;
+            }
+        };
+        let bindings = entries.entry(action).or_default();
+        let mut unknown_entry_bindings = Vec::new();
+        let raw_bindings = raw.get("bindings").and_then(toml::Value::as_array);
+        for raw_binding in raw_bindings.into_iter().flatten() {
+            match raw_binding.clone().try_into::<T>() {
+                Ok(binding) => {
+                    if !bindings.contains(&binding) {
+                        bindings.push(binding);
+                    }
+                }
+                Err(_) => unknown_entry_bindings.push(raw_binding.clone()),
+            }
+        }
+        // kept as an entry of their own, which is merged with the others when loaded again
+        if !unknown_entry_bindings.is_empty() {
+            unknown_bindings += unknown_entry_bindings.len();
+            let mut entry = toml::value::Table::new();
+            let _ = entry.insert(String::from("action"), raw_action.clone());
+            let _ = entry.insert(
+                String::from("bindings"),
+                toml::Value::Array(unknown_entry_bindings),
+            );
+            unknown.push(toml::Value::Table(entry));
+        }
+    }
+    (unknown_actions, unknown_bindings)
+}
+
+#[cfg(feature = "serde")]
+fn write_section<A, T>(
+    entries: &HashMap<A, Vec<T>>,
+    unknown: &[toml::Value],
+) -> GameResult<Vec<toml::Value>>
+where
+    A: serde::Serialize,
+    T: serde::Serialize,
+{
+    let mut values = entries
+        .iter()
+        .map(|(action, bindings)| toml::Value::try_from(EntryRef { action, bindings }))
+        .collect::<Result<Vec<_>, _>>()?;
+    // sorted, so saving the same bindings again writes the same file
+    values.sort_by_cached_key(|value| value.get("action").map(ToString::to_string));
+    values.extend(unknown.iter().cloned());
+    Ok(values)
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct EntryRef<'a, A, T> {
+    action: &'a A,
+    bindings: &'a [T],
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct Entry<A, T> {
+    action: A,
+    bindings: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+fn serialize_entries<S, A, T>(map: &HashMap<A, Vec<T>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    A: serde::Serialize,
+    T: serde::Serialize,
+{
+    serializer.collect_seq(
+        map.iter()
+            .map(|(action, bindings)| EntryRef { action, bindings }),
+    )
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_entries<'de, D, A, T>(deserializer: D) -> Result<HashMap<A, Vec<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    A: serde::Deserialize<'de> + Eq + Hash,
+    T: serde::Deserialize<'de>,
+{
+    let entries: Vec<Entry<A, T>> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| (entry.action, entry.bindings))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    enum Action {
+        Jump,
+        Fire,
+        MoveX,
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut map = InputMap::new();
+        map.bind(Action::Jump, Binding::Key(KeyCode::Space));
+        map.bind(Action::Jump, Binding::Key(KeyCode::Space));
+        map.bind(Action::Fire, Binding::MouseButton(MouseButton::Left));
+        assert_eq!(map.bindings(&Action::Jump).len(), 1);
+        assert!(map.conflicts().is_empty());
+
+        map.bind(Action::Fire, Binding::Key(KeyCode::Space));
+        map.bind_axis(
+            Action::MoveX,
+            AxisBinding::Digital {
+                negative: Binding::Key(KeyCode::A),
+                positive: Binding::Key(KeyCode::Space),
+            },
+        );
+        let conflicts = map.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].0, Binding::Key(KeyCode::Space));
+        assert_eq!(conflicts[0].1.len(), 3);
+
+        map.unbind(&Action::Fire, Binding::Key(KeyCode::Space));
+        map.clear(&Action::MoveX);
+        assert!(map.conflicts().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialization() {
+        let mut map = InputMap::new();
+        map.bind(Action::Jump, Binding::Key(KeyCode::Space));
+        map.bind(Action::Fire, Binding::MouseButton(MouseButton::Left));
+        map.bind_axis(
+            Action::MoveX,
+            AxisBinding::Digital {
+                negative: Binding::Key(KeyCode::Left),
+                positive: Binding::Key(KeyCode::Right),
+            },
+        );
+        let serialized = toml::to_string(&map).unwrap();
+        let deserialized: InputMap<Action> = toml::from_str(&serialized).unwrap();
+        assert_eq!(
+            deserialized.bindings(&Action::Jump),
+            map.bindings(&Action::Jump)
+        );
+        assert_eq!(
+            deserialized.bindings(&Action::Fire),
+            map.bindings(&Action::Fire)
+        );
+        assert_eq!(
+            deserialized.axis_bindings(&Action::MoveX),
+            map.axis_bindings(&Action::MoveX)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bindings_by_name() {
+        let bindings = vec![
+            Binding::Key(KeyCode::LShift),
+            Binding::MouseButton(MouseButton::Right),
+            #[cfg(feature = "gamepad")]
+            Binding::GamepadButton(Button::South),
+        ];
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert!(json.contains(r#"{"type":"Key","input":"LShift"}"#));
+        assert!(json.contains(r#"{"type":"MouseButton","input":"Right"}"#));
+        #[cfg(feature = "gamepad")]
+        assert!(json.contains(r#"{"type":"GamepadButton","input":"South"}"#));
+        let from_json: Vec<Binding> = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json, bindings);
+
+        let ron = ron::to_string(&bindings).unwrap();
+        assert!(ron.contains("input:LShift"));
+        let from_ron: Vec<Binding> = ron::from_str(&ron).unwrap();
+        assert_eq!(from_ron, bindings);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_and_load() {
+        // a later version of the game
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+        enum NewAction {
+            Jump,
+            Fire,
+            Crouch,
+        }
+        let crouch = Binding::ScanCode(crate::input::keyboard::SCANCODE_LCONTROL);
+        let mut map = InputMap::new();
+        map.bind(NewAction::Jump, Binding::Key(KeyCode::Space));
+        map.bind(NewAction::Fire, Binding::Key(KeyCode::Space));
+        map.bind(NewAction::Crouch, crouch);
+        let mut file = Vec::new();
+        map.write_to(&mut file).unwrap();
+
+        let mut defaults = InputMap::new();
+        defaults.bind(Action::Jump, Binding::Key(KeyCode::W));
+        defaults.bind(Action::Fire, Binding::MouseButton(MouseButton::Left));
+        defaults.bind_axis(
+            Action::MoveX,
+            AxisBinding::Digital {
+                negative: Binding::Key(KeyCode::Left),
+                positive: Binding::Key(KeyCode::Right),
+            },
+        );
+        let (old, report) = InputMap::read_from(&file[..], &defaults).unwrap();
+        assert_eq!(old.bindings(&Action::Jump), [Binding::Key(KeyCode::Space)]);
+        assert_eq!(
+            old.axis_bindings(&Action::MoveX),
+            defaults.axis_bindings(&Action::MoveX)
+        );
+        assert_eq!(report.defaulted, [Action::MoveX]);
+        assert_eq!(report.unknown_actions, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].0, Binding::Key(KeyCode::Space));
+
+        // the action unknown to the old version survives being saved by it
+        let mut file = Vec::new();
+        old.write_to(&mut file).unwrap();
+        let (new, report) = InputMap::read_from(&file[..], &InputMap::new()).unwrap();
+        assert_eq!(new.bindings(&NewAction::Crouch), [crouch]);
+        assert_eq!(report.unknown_actions, 1);
+
+        assert!(InputMap::read_from(&b"version = 2"[..], &defaults).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn unknown_bindings() {
+        let file = r#"
+            version = 1
+
+            [[buttons]]
+            action = "Jump"
+            bindings = [
+                { type = "Key", input = "Space" },
+                { type = "Joystick", input = 3 },
+            ]
+        "#;
+        let (map, report) = InputMap::read_from(file.as_bytes(), &InputMap::new()).unwrap();
+        assert_eq!(map.bindings(&Action::Jump), [Binding::Key(KeyCode::Space)]);
+        assert_eq!(report.unknown_bindings, 1);
+
+        let mut saved = Vec::new();
+        map.write_to(&mut saved).unwrap();
+        let (map, report) = InputMap::read_from(&saved[..], &InputMap::new()).unwrap();
+        assert_eq!(map.bindings(&Action::Jump), [Binding::Key(KeyCode::Space)]);
+        assert_eq!(report.unknown_bindings, 1);
+    }
+}
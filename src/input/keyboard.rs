@@ -27,6 +27,10 @@
 //! specific key code. This will happen on non-English keyboards, for
 //! example.
 //!
+//! Since scancodes differ between platforms, the `SCANCODE_*` constants
+//! provide the scancodes of common keys by their position on a US QWERTY keyboard,
+//! e.g. [`SCANCODE_W`] is the key right above [`SCANCODE_S`] on any layout.
+//!
 //! -----
 //!
 //! Example:
@@ -108,17 +112,152 @@
 //! ```
 
 use crate::context::Context;
+use crate::graphics::Rect;
+use crate::{GameError, GameResult};
 
-use std::collections::HashSet;
+use instant::Instant;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use winit::event::ModifiersState;
 pub use winit::event::ScanCode;
 /// A key code.
+///
+/// It's serialized with serde by the name of its variant, e.g. `"LShift"`, so what's saved
+/// doesn't depend on the order of the variants.
 pub use winit::event::VirtualKeyCode as KeyCode;
 
+macro_rules! scancodes {
+    ($($name:ident = $pc:expr, $mac:expr, $key:literal;)*) => {
+        $(
+            #[doc = concat!("The scancode of the key in the position of ", $key, " on a US QWERTY keyboard.")]
+            #[cfg(not(target_os = "macos"))]
+            pub const $name: ScanCode = $pc;
+            #[doc = concat!("The scancode of the key in the position of ", $key, " on a US QWERTY keyboard.")]
+            #[cfg(target_os = "macos")]
+            pub const $name: ScanCode = $mac;
+        )*
+
+        // The PC and macOS scancodes of the keys above, to store scancodes the same on all platforms.
+        #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+        pub(crate) const PC_AND_MAC_SCANCODES: &[(ScanCode, ScanCode)] = &[$(($pc, $mac)),*];
+    };
+}
+
+// PC scancode set 1 (as reported on Windows and Linux) and macOS virtual key codes
+scancodes! {
+    SCANCODE_ESCAPE = 1, 53, "Escape";
+    SCANCODE_1 = 2, 18, "1";
+    SCANCODE_2 = 3, 19, "2";
+    SCANCODE_3 = 4, 20, "3";
+    SCANCODE_4 = 5, 21, "4";
+    SCANCODE_5 = 6, 23, "5";
+    SCANCODE_6 = 7, 22, "6";
+    SCANCODE_7 = 8, 26, "7";
+    SCANCODE_8 = 9, 28, "8";
+    SCANCODE_9 = 10, 25, "9";
+    SCANCODE_0 = 11, 29, "0";
+    SCANCODE_BACKSPACE = 14, 51, "Backspace";
+    SCANCODE_TAB = 15, 48, "Tab";
+    SCANCODE_Q = 16, 12, "Q";
+    SCANCODE_W = 17, 13, "W";
+    SCANCODE_E = 18, 14, "E";
+    SCANCODE_R = 19, 15, "R";
+    SCANCODE_T = 20, 17, "T";
+    SCANCODE_Y = 21, 16, "Y";
+    SCANCODE_U = 22, 32, "U";
+    SCANCODE_I = 23, 34, "I";
+    SCANCODE_O = 24, 31, "O";
+    SCANCODE_P = 25, 35, "P";
+    SCANCODE_ENTER = 28, 36, "Enter";
+    SCANCODE_LCONTROL = 29, 59, "left Control";
+    SCANCODE_A = 30, 0, "A";
+    SCANCODE_S = 31, 1, "S";
+    SCANCODE_D = 32, 2, "D";
+    SCANCODE_F = 33, 3, "F";
+    SCANCODE_G = 34, 5, "G";
+    SCANCODE_H = 35, 4, "H";
+    SCANCODE_J = 36, 38, "J";
+    SCANCODE_K = 37, 40, "K";
+    SCANCODE_L = 38, 37, "L";
+    SCANCODE_LSHIFT = 42, 56, "left Shift";
+    SCANCODE_Z = 44, 6, "Z";
+    SCANCODE_X = 45, 7, "X";
+    SCANCODE_C = 46, 8, "C";
+    SCANCODE_V = 47, 9, "V";
+    SCANCODE_B = 48, 11, "B";
+    SCANCODE_N = 49, 45, "N";
+    SCANCODE_M = 50, 46, "M";
+    SCANCODE_SPACE = 57, 49, "Space";
+}
+
+/// Returns a human-readable name of a key, e.g. for showing the current bindings on a rebinding screen.
+///
+/// The names are in English; for keys which print a character, prefer showing the character
+/// from the user's layout, see [`KeyboardContext::scancode_to_keycode`].
+pub fn keycode_name(key: KeyCode) -> String {
+    use KeyCode::*;
+    let name = match key {
+        Key1 | Numpad1 => "1",
+        Key2 | Numpad2 => "2",
+        Key3 | Numpad3 => "3",
+        Key4 | Numpad4 => "4",
+        Key5 | Numpad5 => "5",
+        Key6 | Numpad6 => "6",
+        Key7 | Numpad7 => "7",
+        Key8 | Numpad8 => "8",
+        Key9 | Numpad9 => "9",
+        Key0 | Numpad0 => "0",
+        Escape => "Esc",
+        Back => "Backspace",
+        Return | NumpadEnter => "Enter",
+        Space => "Space",
+        Left => "Left Arrow",
+        Right => "Right Arrow",
+        Up => "Up Arrow",
+        Down => "Down Arrow",
+        LShift => "Left Shift",
+        RShift => "Right Shift",
+        LControl => "Left Ctrl",
+        RControl => "Right Ctrl",
+        LAlt => "Left Alt",
+        RAlt => "Right Alt",
+        LWin => "Left Super",
+        RWin => "Right Super",
+        PageUp => "Page Up",
+        PageDown => "Page Down",
+        Snapshot => "Print Screen",
+        Scroll => "Scroll Lock",
+        Capital => "Caps Lock",
+        Numlock => "Num Lock",
+        Minus | NumpadSubtract => "-",
+        Equals | NumpadEquals => "=",
+        Plus | NumpadAdd => "+",
+        NumpadMultiply | Asterisk => "*",
+        Slash | NumpadDivide => "/",
+        Backslash => "\\",
+        Comma | NumpadComma => ",",
+        Period | NumpadDecimal => ".",
+        Semicolon => ";",
+        Apostrophe => "'",
+        Grave => "`",
+        LBracket => "[",
+        RBracket => "]",
+        _ => return format!("{:?}", key),
+    };
+    name.to_string()
+}
+
 bitflags::bitflags! {
     /// Bitflags describing the state of keyboard modifiers, such as `Control` or `Shift`.
+    ///
+    /// Besides the combined flags, like `SHIFT` for either Shift key, there are flags for the left
+    /// and right keys, like `LSHIFT` and `RSHIFT`. Since these are set along with the combined flags,
+    /// prefer checking for modifiers with `contains` over comparing with `==`.
     #[derive(Default)]
-    pub struct KeyMods: u8 {
+    pub struct KeyMods: u16 {
         /// No modifiers; equivalent to `KeyMods::default()` and
         /// [`KeyMods::empty()`](struct.KeyMods.html#method.empty).
         const NONE  = 0b0000_0000;
@@ -130,9 +269,33 @@ bitflags::bitflags! {
         const ALT   = 0b0000_0100;
         /// Left or right Win/Cmd/equivalent key.
         const LOGO  = 0b0000_1000;
+        /// Left Shift key.
+        const LSHIFT = 0b0001_0000;
+        /// Right Shift key.
+        const RSHIFT = 0b0010_0000;
+        /// Left Control key.
+        const LCTRL = 0b0100_0000;
+        /// Right Control key.
+        const RCTRL = 0b1000_0000;
+        /// Left Alt key.
+        const LALT = 0b0001_0000_0000;
+        /// Right Alt key, which is AltGr on many layouts.
+        const RALT = 0b0010_0000_0000;
+        /// Left Win/Cmd/equivalent key.
+        const LLOGO = 0b0100_0000_0000;
+        /// Right Win/Cmd/equivalent key.
+        const RLOGO = 0b1000_0000_0000;
     }
 }
 
+// The combined, left and right flags of each modifier.
+const MODIFIER_SIDES: [(KeyMods, KeyMods, KeyMods); 4] = [
+    (KeyMods::SHIFT, KeyMods::LSHIFT, KeyMods::RSHIFT),
+    (KeyMods::CTRL, KeyMods::LCTRL, KeyMods::RCTRL),
+    (KeyMods::ALT, KeyMods::LALT, KeyMods::RALT),
+    (KeyMods::LOGO, KeyMods::LLOGO, KeyMods::RLOGO),
+];
+
 impl From<ModifiersState> for KeyMods {
     fn from(state: ModifiersState) -> Self {
         let mut keymod = KeyMods::empty();
@@ -152,7 +315,7 @@ impl From<ModifiersState> for KeyMods {
     }
 }
 
-/// A simple wrapper bundling the four properties of a keyboard stroke.
+/// A simple wrapper bundling the properties of a keyboard stroke.
 #[derive(Copy, Clone, Debug)]
 pub struct KeyInput {
     /// The scancode. For more info on what they are and when to use them refer to the
@@ -162,6 +325,11 @@ pub struct KeyInput {
     pub keycode: Option<KeyCode>,
     /// The keyboard modifiers active at the moment of input.
     pub mods: KeyMods,
+    /// When the key was pressed or released, on the same clock as [`TimeContext`](crate::timer::TimeContext).
+    ///
+    /// The windowing backends don't provide hardware timestamps, so this is when ggez received the
+    /// event. Events arriving while a frame is drawn are received once it's done.
+    pub time: Instant,
 }
 
 /// Tracks held down keyboard keys, active keyboard modifiers,
@@ -179,9 +347,46 @@ pub struct KeyboardContext {
     last_pressed: Option<ScanCode>,
     current_pressed: Option<ScanCode>,
 
-    // Represents the state of pressed_keys_set last frame.
-    previously_pressed_keys_set: HashSet<KeyCode>,
+    // Keys which went down or up since the last `save_keyboard_state`. Tracked separately
+    // from the pressed keys, so that a press and release within one frame isn't lost.
+    just_pressed_keys_set: HashSet<KeyCode>,
+    just_released_keys_set: HashSet<KeyCode>,
+    // The keycodes last seen together with each scancode, i.e. the current layout as far as it's known.
+    scancode_keycodes: HashMap<ScanCode, KeyCode>,
+    // Represents the state of pressed_scancodes_set last frame.
     previously_pressed_scancodes_set: HashSet<ScanCode>,
+
+    // The initial delay and interval of synthesized key repeats, and how long each key has been held.
+    repeat: Option<(Duration, Duration)>,
+    held_durations: HashMap<KeyCode, Duration>,
+    repeating_keys_set: HashSet<KeyCode>,
+    // When each held key was pressed.
+    pressed_at: HashMap<KeyCode, Instant>,
+
+    // The text input received since the last `save_keyboard_state`.
+    text: String,
+    text_newlines: bool,
+
+    // The input method settings, applied to the window with the next event.
+    ime_allowed: bool,
+    ime_area: Option<Rect>,
+    ime_changed: bool,
+
+    // Opened with the first use, and kept since the copied text is lost
+    // with the last handle on some platforms.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    clipboard: Option<Clipboard>,
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+#[derive(Clone)]
+struct Clipboard(Arc<Mutex<arboard::Clipboard>>);
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+impl fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<Clipboard: {:p}>", self.0)
+    }
 }
 
 impl KeyboardContext {
@@ -193,8 +398,21 @@ impl KeyboardContext {
             pressed_scancodes_set: HashSet::with_capacity(256),
             last_pressed: None,
             current_pressed: None,
-            previously_pressed_keys_set: HashSet::with_capacity(256),
+            just_pressed_keys_set: HashSet::new(),
+            just_released_keys_set: HashSet::new(),
+            scancode_keycodes: HashMap::new(),
             previously_pressed_scancodes_set: HashSet::with_capacity(256),
+            repeat: Some((Duration::from_millis(500), Duration::from_millis(50))),
+            held_durations: HashMap::new(),
+            repeating_keys_set: HashSet::new(),
+            pressed_at: HashMap::new(),
+            text: String::new(),
+            text_newlines: true,
+            ime_allowed: false,
+            ime_area: None,
+            ime_changed: false,
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+            clipboard: None,
         }
     }
 
@@ -204,13 +422,30 @@ impl KeyboardContext {
     }
 
     /// Checks if a key has been pressed down this frame.
+    ///
+    /// "This frame" means since the events of the current pass through the event loop were processed,
+    /// so this stays true for all `update` calls of a frame, e.g. when running several fixed timesteps per frame.
+    /// Key repeats don't count as presses. A key which was pressed and released again within a single frame
+    /// is reported as both just pressed and just released, while [`is_key_pressed`](Self::is_key_pressed) is false.
     pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
-        self.pressed_keys_set.contains(&key) && !self.previously_pressed_keys_set.contains(&key)
+        self.just_pressed_keys_set.contains(&key)
     }
 
-    /// Checks if a key has been released this frame.
+    /// Checks if a key has been released this frame, see [`is_key_just_pressed`](Self::is_key_just_pressed).
     pub fn is_key_just_released(&self, key: KeyCode) -> bool {
-        !self.pressed_keys_set.contains(&key) && self.previously_pressed_keys_set.contains(&key)
+        self.just_released_keys_set.contains(&key)
+    }
+
+    /// Returns an iterator over the keys which have been pressed down this frame,
+    /// see [`is_key_just_pressed`](Self::is_key_just_pressed).
+    pub fn keys_just_pressed(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.just_pressed_keys_set.iter().copied()
+    }
+
+    /// Returns an iterator over the keys which have been released this frame,
+    /// see [`is_key_just_pressed`](Self::is_key_just_pressed).
+    pub fn keys_just_released(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.just_released_keys_set.iter().copied()
     }
 
     /// Checks if a key with the corresponding scan code is currently pressed down.
@@ -230,6 +465,14 @@ impl KeyboardContext {
             && self.previously_pressed_scancodes_set.contains(&code)
     }
 
+    /// Returns the keycode the key with the given scancode produces in the user's keyboard layout,
+    /// e.g. to label a [`SCANCODE_W`] binding as "Z" on AZERTY keyboards.
+    ///
+    /// The layout can't be queried directly, so this is only known for keys which have been pressed already.
+    pub fn scancode_to_keycode(&self, code: ScanCode) -> Option<KeyCode> {
+        self.scancode_keycodes.get(&code).copied()
+    }
+
     /// Checks if the last keystroke sent by the system is repeated,
     /// like when a key is held down for a period of time.
     pub fn is_key_repeated(&self) -> bool {
@@ -240,6 +483,169 @@ impl KeyboardContext {
         }
     }
 
+    /// Checks if a held key repeats this frame, for holding a key to scroll through a list and the like.
+    ///
+    /// Unlike [`is_key_repeated`](Self::is_key_repeated) these repeats don't come from the system,
+    /// but are synthesized from the frame time as set up with [`set_repeat`](Self::set_repeat),
+    /// so they don't depend on the OS settings. The initial press isn't a repeat, so check for
+    /// `is_key_just_pressed(key) || is_key_repeating(key)` to act on both.
+    pub fn is_key_repeating(&self, key: KeyCode) -> bool {
+        self.repeating_keys_set.contains(&key)
+    }
+
+    /// Returns how long a key has been held down, or `None` if it isn't pressed.
+    pub fn press_time(&self, key: KeyCode) -> Option<Duration> {
+        self.pressed_at.get(&key).map(Instant::elapsed)
+    }
+
+    /// Sets the initial delay and the interval of the repeats reported by
+    /// [`is_key_repeating`](Self::is_key_repeating), or disables them with `None`.
+    ///
+    /// Defaults to repeating every 50ms after a delay of 500ms.
+    pub fn set_repeat(&mut self, repeat: Option<(Duration, Duration)>) {
+        self.repeat = repeat;
+        if repeat.is_none() {
+            self.repeating_keys_set.clear();
+        }
+    }
+
+    /// Returns the initial delay and interval of synthesized key repeats, see [`set_repeat`](Self::set_repeat).
+    pub fn repeat(&self) -> Option<(Duration, Duration)> {
+        self.repeat
+    }
+
+    /// Advances the synthesized key repeats by the time since the last frame. If you are writing
+    /// your own event loop you need to call this before every update in order to use
+    /// `is_key_repeating`. Otherwise this is handled for you.
+    pub fn update_repeats(&mut self, delta: Duration) {
+        self.repeating_keys_set.clear();
+        let (delay, interval) = match self.repeat {
+            Some(repeat) => repeat,
+            None => return,
+        };
+        for (key, held) in &mut self.held_durations {
+            let previous = repeat_count(*held, delay, interval);
+            *held += delta;
+            if repeat_count(*held, delay, interval) > previous {
+                let _ = self.repeating_keys_set.insert(*key);
+            }
+        }
+    }
+
+    /// Returns all text input received this frame, in order, for polling text fields from `update`.
+    ///
+    /// Like [`text_input_event`](crate::event::EventHandler::text_input_event) this follows the
+    /// keyboard layout, and pasted text as well as text committed by input methods arrives here too. Control characters,
+    /// such as backspace or tab, are left out, check for their keys instead. Enter is kept
+    /// as `'\n'`, unless disabled with [`set_text_newlines`](Self::set_text_newlines).
+    pub fn text_entered(&self) -> &str {
+        &self.text
+    }
+
+    /// Sets whether Enter adds a `'\n'` to [`text_entered`](Self::text_entered), which single line
+    /// text fields may not want. Defaults to `true`.
+    pub fn set_text_newlines(&mut self, newlines: bool) {
+        self.text_newlines = newlines;
+    }
+
+    /// Adds a received character to [`text_entered`](Self::text_entered).
+    /// Really useful only if you are writing your own event loop
+    pub fn push_text(&mut self, ch: char) {
+        match ch {
+            // Enter sends '\r' on most platforms
+            '\r' | '\n' if self.text_newlines => self.text.push('\n'),
+            _ if ch.is_control() => (),
+            _ => self.text.push(ch),
+        }
+    }
+
+    /// Sets whether input methods may be used for text input, which are needed for typing Chinese,
+    /// Japanese or Korean, and for dead keys on some platforms. Defaults to `false`.
+    ///
+    /// Allow them while a text field is focused, since they capture keys as part of their
+    /// compositions. See [`ime_preedit_event`](crate::event::EventHandler::ime_preedit_event)
+    /// and [`ime_commit_event`](crate::event::EventHandler::ime_commit_event) for their input.
+    pub fn set_ime_allowed(&mut self, allowed: bool) {
+        self.ime_changed |= self.ime_allowed != allowed;
+        self.ime_allowed = allowed;
+    }
+
+    /// Returns whether input methods may be used, see [`set_ime_allowed`](Self::set_ime_allowed).
+    pub fn is_ime_allowed(&self) -> bool {
+        self.ime_allowed
+    }
+
+    /// Sets the area of the text field in window pixels, so that the input method's
+    /// candidate window can be shown right below it.
+    pub fn set_ime_cursor_area(&mut self, area: Rect) {
+        self.ime_changed |= self.ime_area != Some(area);
+        self.ime_area = Some(area);
+    }
+
+    pub(crate) fn apply_ime(&mut self, window: &winit::window::Window) {
+        if !self.ime_changed {
+            return;
+        }
+        self.ime_changed = false;
+        window.set_ime_allowed(self.ime_allowed);
+        if let Some(area) = self.ime_area {
+            // winit only takes the position where the candidate window is shown
+            window.set_ime_position(winit::dpi::PhysicalPosition::new(area.x, area.y + area.h));
+        }
+    }
+
+    /// Returns the text on the system clipboard, for pasting into a text field.
+    ///
+    /// Fails if the clipboard is empty or holds something else than text, like an image,
+    /// or if it can't be accessed at all. On Wayland this needs the compositor to support
+    /// the data control protocol, otherwise it falls back to X11 if available.
+    ///
+    /// On the web the browser only hands out the clipboard asynchronously, and on Android it's
+    /// only reachable through Java, so this always fails there.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    pub fn clipboard_text(&mut self) -> GameResult<String> {
+        let text = self.clipboard()?.get_text()?;
+        Ok(text)
+    }
+
+    /// Returns the text on the system clipboard, for pasting into a text field.
+    ///
+    /// On the web the browser only hands out the clipboard asynchronously, and on Android it's
+    /// only reachable through Java, so this always fails there.
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    pub fn clipboard_text(&mut self) -> GameResult<String> {
+        Err(GameError::ClipboardError(String::from(
+            "Clipboard error: the clipboard isn't available on this platform",
+        )))
+    }
+
+    /// Puts text onto the system clipboard, see [`clipboard_text`](Self::clipboard_text).
+    ///
+    /// On Linux the text stays available to other programs only while the game is running.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    pub fn set_clipboard_text(&mut self, text: &str) -> GameResult {
+        self.clipboard()?.set_text(text)?;
+        Ok(())
+    }
+
+    /// Puts text onto the system clipboard, see [`clipboard_text`](Self::clipboard_text).
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    pub fn set_clipboard_text(&mut self, _text: &str) -> GameResult {
+        Err(GameError::ClipboardError(String::from(
+            "Clipboard error: the clipboard isn't available on this platform",
+        )))
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    fn clipboard(&mut self) -> GameResult<std::sync::MutexGuard<'_, arboard::Clipboard>> {
+        if self.clipboard.is_none() {
+            let clipboard = arboard::Clipboard::new()?;
+            self.clipboard = Some(Clipboard(Arc::new(Mutex::new(clipboard))));
+        }
+        let clipboard = self.clipboard.as_ref().expect("clipboard was just opened");
+        clipboard.0.lock().map_err(|_| GameError::LockError)
+    }
+
     /// Returns a reference to the set of currently pressed keys.
     pub fn pressed_keys(&self) -> &HashSet<KeyCode> {
         &self.pressed_keys_set
@@ -261,23 +667,36 @@ impl KeyboardContext {
     }
 
     /// Copies the current state of the keyboard into the context. If you are writing your own event loop
-    /// you need to call this at the end of every update in order to use the functions `is_key_just_pressed`
-    /// and `is_key_just_released`. Otherwise this is handled for you.
+    /// you need to call this at the end of every update in order to use the functions `is_key_just_pressed`,
+    /// `is_key_just_released` and `text_entered`. Otherwise this is handled for you.
     pub fn save_keyboard_state(&mut self) {
-        self.previously_pressed_keys_set = self.pressed_keys_set.clone();
+        self.just_pressed_keys_set.clear();
+        self.just_released_keys_set.clear();
+        self.text.clear();
         self.previously_pressed_scancodes_set = self.pressed_scancodes_set.clone();
     }
 
     pub(crate) fn set_key(&mut self, key: KeyCode, pressed: bool) {
         if pressed {
-            let _ = self.pressed_keys_set.insert(key);
-        } else {
-            let _ = self.pressed_keys_set.remove(&key);
+            if self.pressed_keys_set.insert(key) {
+                let _ = self.just_pressed_keys_set.insert(key);
+                let _ = self.held_durations.insert(key, Duration::ZERO);
+                let _ = self.pressed_at.insert(key, Instant::now());
+            }
+        } else if self.pressed_keys_set.remove(&key) {
+            let _ = self.just_released_keys_set.insert(key);
+            let _ = self.held_durations.remove(&key);
+            let _ = self.pressed_at.remove(&key);
+            let _ = self.repeating_keys_set.remove(&key);
         }
 
         self.set_key_modifier(key, pressed);
     }
 
+    pub(crate) fn learn_scancode(&mut self, code: ScanCode, key: KeyCode) {
+        let _ = self.scancode_keycodes.insert(code, key);
+    }
+
     pub(crate) fn set_scancode(&mut self, code: ScanCode, pressed: bool) {
         if pressed {
             let _ = self.pressed_scancodes_set.insert(code);
@@ -291,8 +710,30 @@ impl KeyboardContext {
 
     /// Set the keyboard active modifiers
     /// Really useful only if you are writing your own event loop
+    ///
+    /// If `keymods` only contains combined flags, the known left and right flags of active modifiers are kept.
     pub fn set_modifiers(&mut self, keymods: KeyMods) {
-        self.active_modifiers = keymods;
+        // winit doesn't tell left and right apart, that's only known from the modifier keys themselves
+        let mut modifiers = keymods;
+        for (combined, left, right) in MODIFIER_SIDES {
+            if keymods.contains(combined) && !keymods.intersects(left | right) {
+                modifiers |= self.active_modifiers & (left | right);
+            }
+        }
+        self.active_modifiers = modifiers;
+    }
+
+    // Releases all keys and modifiers, as releases aren't reported while the window is unfocused.
+    pub(crate) fn release_all(&mut self) {
+        for key in self.pressed_keys_set.drain() {
+            let _ = self.just_released_keys_set.insert(key);
+        }
+        self.pressed_scancodes_set.clear();
+        self.current_pressed = None;
+        self.active_modifiers = KeyMods::empty();
+        self.held_durations.clear();
+        self.repeating_keys_set.clear();
+        self.pressed_at.clear();
     }
 
     /// Take a modifier key code and alter our state.
@@ -305,26 +746,40 @@ impl KeyboardContext {
     /// make winit do more of the work.
     /// But to quote Scott Pilgrim, "This is... this is... Booooooring."
     fn set_key_modifier(&mut self, key: KeyCode, pressed: bool) {
+        let side = match key {
+            KeyCode::LShift => KeyMods::LSHIFT,
+            KeyCode::RShift => KeyMods::RSHIFT,
+            KeyCode::LControl => KeyMods::LCTRL,
+            KeyCode::RControl => KeyMods::RCTRL,
+            KeyCode::LAlt => KeyMods::LALT,
+            KeyCode::RAlt => KeyMods::RALT,
+            KeyCode::LWin => KeyMods::LLOGO,
+            KeyCode::RWin => KeyMods::RLOGO,
+            _ => return,
+        };
         if pressed {
-            match key {
-                KeyCode::LShift | KeyCode::RShift => self.active_modifiers |= KeyMods::SHIFT,
-                KeyCode::LControl | KeyCode::RControl => self.active_modifiers |= KeyMods::CTRL,
-                KeyCode::LAlt | KeyCode::RAlt => self.active_modifiers |= KeyMods::ALT,
-                KeyCode::LWin | KeyCode::RWin => self.active_modifiers |= KeyMods::LOGO,
-                _ => (),
-            }
+            self.active_modifiers |= side;
         } else {
-            match key {
-                KeyCode::LShift | KeyCode::RShift => self.active_modifiers -= KeyMods::SHIFT,
-                KeyCode::LControl | KeyCode::RControl => self.active_modifiers -= KeyMods::CTRL,
-                KeyCode::LAlt | KeyCode::RAlt => self.active_modifiers -= KeyMods::ALT,
-                KeyCode::LWin | KeyCode::RWin => self.active_modifiers -= KeyMods::LOGO,
-                _ => (),
+            self.active_modifiers -= side;
+        }
+        // the combined flag stays active while the key on the other side is held
+        for (combined, left, right) in MODIFIER_SIDES {
+            if (left | right).contains(side) {
+                self.active_modifiers
+                    .set(combined, self.active_modifiers.intersects(left | right));
             }
         }
     }
 }
 
+// The number of synthesized repeats after holding a key or button for some time.
+pub(crate) fn repeat_count(held: Duration, delay: Duration, interval: Duration) -> u128 {
+    match held.checked_sub(delay) {
+        Some(past_delay) => past_delay.as_nanos() / interval.as_nanos().max(1) + 1,
+        None => 0,
+    }
+}
+
 impl Default for KeyboardContext {
     fn default() -> Self {
         Self::new()
@@ -457,6 +912,122 @@ mod tests {
         assert_eq!(keyboard.pressed_keys(), &[].iter().cloned().collect());
     }
 
+    #[test]
+    fn just_pressed_keys_tracking() {
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_key(KeyCode::A, true);
+        assert!(keyboard.is_key_just_pressed(KeyCode::A));
+        assert!(!keyboard.is_key_just_released(KeyCode::A));
+        // repeats aren't presses
+        keyboard.save_keyboard_state();
+        keyboard.set_key(KeyCode::A, true);
+        assert!(!keyboard.is_key_just_pressed(KeyCode::A));
+        keyboard.set_key(KeyCode::A, false);
+        assert!(keyboard.is_key_just_released(KeyCode::A));
+        assert_eq!(
+            keyboard.keys_just_released().collect::<Vec<_>>(),
+            [KeyCode::A]
+        );
+        keyboard.save_keyboard_state();
+        assert!(!keyboard.is_key_just_released(KeyCode::A));
+
+        // a tap within a single frame is reported both ways
+        keyboard.set_key(KeyCode::B, true);
+        keyboard.set_key(KeyCode::B, false);
+        assert!(!keyboard.is_key_pressed(KeyCode::B));
+        assert!(keyboard.is_key_just_pressed(KeyCode::B));
+        assert!(keyboard.is_key_just_released(KeyCode::B));
+        assert_eq!(
+            keyboard.keys_just_pressed().collect::<Vec<_>>(),
+            [KeyCode::B]
+        );
+    }
+
+    #[test]
+    fn synthesized_repeats() {
+        let frame = Duration::from_millis(100);
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_repeat(Some((
+            Duration::from_millis(250),
+            Duration::from_millis(100),
+        )));
+        keyboard.set_key(KeyCode::Down, true);
+        let repeats: Vec<bool> = (0..6)
+            .map(|_| {
+                keyboard.update_repeats(frame);
+                keyboard.is_key_repeating(KeyCode::Down)
+            })
+            .collect();
+        assert_eq!(repeats, [false, false, true, true, true, true]);
+
+        keyboard.set_key(KeyCode::Down, false);
+        assert!(!keyboard.is_key_repeating(KeyCode::Down));
+        keyboard.set_key(KeyCode::Down, true);
+        keyboard.update_repeats(frame);
+        assert!(!keyboard.is_key_repeating(KeyCode::Down));
+
+        assert!(keyboard.press_time(KeyCode::Down).is_some());
+        keyboard.release_all();
+        keyboard.update_repeats(Duration::from_secs(1));
+        assert!(!keyboard.is_key_repeating(KeyCode::Down));
+        assert!(keyboard.press_time(KeyCode::Down).is_none());
+
+        keyboard.set_repeat(None);
+        keyboard.set_key(KeyCode::Down, true);
+        keyboard.update_repeats(Duration::from_secs(1));
+        assert!(!keyboard.is_key_repeating(KeyCode::Down));
+    }
+
+    #[test]
+    fn text_buffer() {
+        let mut keyboard = KeyboardContext::new();
+        for ch in "ab\u{8}\tc\rd".chars() {
+            keyboard.push_text(ch);
+        }
+        assert_eq!(keyboard.text_entered(), "abc\nd");
+        keyboard.save_keyboard_state();
+        assert_eq!(keyboard.text_entered(), "");
+
+        keyboard.set_text_newlines(false);
+        "ä\r\n€".chars().for_each(|ch| keyboard.push_text(ch));
+        assert_eq!(keyboard.text_entered(), "ä€");
+    }
+
+    #[test]
+    fn modifier_sides() {
+        let mut keyboard = KeyboardContext::new();
+        keyboard.set_key(KeyCode::LShift, true);
+        keyboard.set_key(KeyCode::RShift, true);
+        assert!(keyboard.is_mod_active(KeyMods::SHIFT | KeyMods::LSHIFT | KeyMods::RSHIFT));
+        keyboard.set_key(KeyCode::LShift, false);
+        assert!(keyboard.is_mod_active(KeyMods::SHIFT | KeyMods::RSHIFT));
+        assert!(!keyboard.is_mod_active(KeyMods::LSHIFT));
+
+        // winit reports combined flags only
+        keyboard.set_modifiers(KeyMods::SHIFT);
+        assert!(keyboard.is_mod_active(KeyMods::RSHIFT));
+        keyboard.set_modifiers(KeyMods::empty());
+        assert_eq!(keyboard.active_mods(), KeyMods::empty());
+
+        keyboard.set_key(KeyCode::RAlt, true);
+        keyboard.release_all();
+        assert_eq!(keyboard.active_mods(), KeyMods::empty());
+        assert!(!keyboard.is_key_pressed(KeyCode::RAlt));
+        assert!(keyboard.is_key_just_released(KeyCode::RAlt));
+    }
+
+    #[test]
+    fn scancode_layout() {
+        let mut keyboard = KeyboardContext::new();
+        assert_eq!(keyboard.scancode_to_keycode(SCANCODE_W), None);
+        // an AZERTY keyboard
+        keyboard.learn_scancode(SCANCODE_W, KeyCode::Z);
+        assert_eq!(keyboard.scancode_to_keycode(SCANCODE_W), Some(KeyCode::Z));
+        assert_eq!(keycode_name(KeyCode::Z), "Z");
+        assert_eq!(keycode_name(KeyCode::Key1), "1");
+        assert_eq!(keycode_name(KeyCode::LShift), "Left Shift");
+    }
+
     #[test]
     fn pressed_scancodes_tracking() {
         let mut keyboard = KeyboardContext::new();
@@ -1,4 +1,19 @@
-//! Input handling modules for keyboard, mouse and gamepad.
+//! Input handling modules for keyboard, mouse, touch and gamepad.
+pub mod axis;
+pub mod button_gesture;
 pub mod gamepad;
+pub mod gamepad_backend;
+pub mod gesture;
+pub mod input_map;
 pub mod keyboard;
 pub mod mouse;
+pub mod pen;
+pub mod player_slots;
+pub mod recording;
+pub mod touch;
+
+pub use axis::{Axis1D, Axis2D};
+pub use button_gesture::ButtonGesture;
+pub use input_map::InputMap;
+#[cfg(feature = "gamepad")]
+pub use player_slots::PlayerSlots;
@@ -1,13 +1,72 @@
 //! Mouse utility functions.
 
-use crate::context::Context;
+use crate::context::{Context, Has};
 use crate::error::GameError;
 use crate::error::GameResult;
-use std::collections::HashSet;
+use crate::graphics::{
+    window::{clear_custom_cursor, set_custom_cursor},
+    GraphicsContext, Image, ImageFormat,
+};
+use instant::Instant;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
 use winit::dpi;
+use winit::error::ExternalError;
+/// A mouse button, serialized with serde by name, e.g. `"Left"`, or as `{ "Other": 4 }`.
 pub use winit::event::MouseButton;
-use winit::window::CursorGrabMode;
-pub use winit::window::CursorIcon;
+pub use winit::window::{CursorGrabMode, CursorIcon};
+
+/// A press of a mouse button, counting how many times it was clicked in a row.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClickInfo {
+    /// The button which was pressed.
+    pub button: MouseButton,
+    /// The position of the mouse at the press, in physical pixels.
+    pub position: mint::Point2<f32>,
+    /// The number of clicks in a row, 1 for a single click, 2 for a double click and so on.
+    pub count: u32,
+    /// When the button was pressed, see [`MouseContext::button_event_time`].
+    pub time: Instant,
+}
+
+/// The largest width and height of cursor images.
+pub const MAX_CURSOR_SIZE: u32 = 256;
+
+/// The distance of a scroll, as reported by the device.
+///
+/// Mouse wheels usually scroll by lines, while touchpads scroll by pixels, which should be applied
+/// 1:1 for smooth scrolling. `x` is positive to the right, `y` is positive away from the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScrollDelta {
+    /// The number of lines, or rows and columns, to scroll.
+    Lines(mint::Vector2<f32>),
+    /// The distance to scroll in physical pixels.
+    Pixels(mint::Vector2<f32>),
+}
+
+impl From<winit::event::MouseScrollDelta> for ScrollDelta {
+    fn from(delta: winit::event::MouseScrollDelta) -> Self {
+        match delta {
+            winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                ScrollDelta::Lines(mint::Vector2 { x, y })
+            }
+            winit::event::MouseScrollDelta::PixelDelta(pos) => ScrollDelta::Pixels(mint::Vector2 {
+                x: pos.x as f32,
+                y: pos.y as f32,
+            }),
+        }
+    }
+}
+
+// A custom cursor image as RGBA, kept for showing it again after hiding the cursor.
+#[derive(Clone, Debug)]
+struct CursorImage {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+    hotspot: (u32, u32),
+}
 
 /// Stores state information for the mouse input.
 // TODO: Add "differences with window cursor" notice
@@ -19,8 +78,26 @@ pub struct MouseContext {
     buttons_pressed: HashSet<MouseButton>,
     cursor_type: CursorIcon,
     cursor_grabbed: bool,
+    cursor_grab: CursorGrabMode,
     cursor_hidden: bool,
-    previous_buttons_pressed: HashSet<MouseButton>,
+    cursor_image: Option<CursorImage>,
+    wheel_delta: glam::Vec2,
+    scroll_lines: glam::Vec2,
+    scroll_pixels: glam::Vec2,
+    raw_input: bool,
+    raw_delta: glam::Vec2,
+    window_focused: bool,
+    cursor_in_window: bool,
+    // Buttons which went down or up since the last `save_mouse_state`.
+    just_pressed_buttons: HashSet<MouseButton>,
+    just_released_buttons: HashSet<MouseButton>,
+    last_click: Option<ClickInfo>,
+    // When each held button was pressed, and when the latest button was pressed or released.
+    pressed_at: HashMap<MouseButton, Instant>,
+    button_event_time: Option<Instant>,
+    double_click_interval: Duration,
+    double_click_distance: f32,
+    window: Option<Arc<winit::window::Window>>,
 }
 
 impl MouseContext {
@@ -32,11 +109,41 @@ impl MouseContext {
             cursor_type: CursorIcon::Default,
             buttons_pressed: HashSet::new(),
             cursor_grabbed: false,
+            cursor_grab: CursorGrabMode::None,
             cursor_hidden: false,
-            previous_buttons_pressed: HashSet::new(),
+            cursor_image: None,
+            wheel_delta: glam::Vec2::ZERO,
+            scroll_lines: glam::Vec2::ZERO,
+            scroll_pixels: glam::Vec2::ZERO,
+            raw_input: false,
+            raw_delta: glam::Vec2::ZERO,
+            // assumed until told otherwise, since not every platform reports the initial focus
+            window_focused: true,
+            cursor_in_window: false,
+            just_pressed_buttons: HashSet::new(),
+            just_released_buttons: HashSet::new(),
+            last_click: None,
+            pressed_at: HashMap::new(),
+            button_event_time: None,
+            double_click_interval: Duration::from_millis(500),
+            double_click_distance: 4.,
+            window: None,
         }
     }
 
+    pub(crate) fn with_window(window: Arc<winit::window::Window>) -> Self {
+        Self {
+            window: Some(window),
+            ..Self::new()
+        }
+    }
+
+    fn window(&self) -> GameResult<&winit::window::Window> {
+        self.window
+            .as_deref()
+            .ok_or_else(|| GameError::WindowError("The mouse context has no window".to_owned()))
+    }
+
     /// Returns the current mouse cursor type of the window.
     pub fn cursor_type(&self) -> CursorIcon {
         self.cursor_type
@@ -49,16 +156,233 @@ impl MouseContext {
 
     /// Get the current position of the mouse cursor, in pixels.
     /// Complement to [`set_position()`](fn.set_position.html).
-    /// Uses strictly window-only coordinates.
+    /// Uses strictly window-only coordinates, in physical pixels; divide by
+    /// [`GraphicsContext::scale_factor`](crate::graphics::GraphicsContext::scale_factor) for logical pixels.
     pub fn position(&self) -> mint::Point2<f32> {
         self.last_position.into()
     }
 
+    /// Get the current position of the mouse cursor in the screen coordinates of the last frame canvas,
+    /// as set by `Canvas::set_screen_coordinates` or a virtual resolution.
+    ///
+    /// This is a shorthand for [`GraphicsContext::window_to_screen_coords`] with [`position`](Self::position),
+    /// so it takes resizing, the scale factor and letterboxing into account.
+    pub fn game_position(&self, gfx: &impl Has<GraphicsContext>) -> mint::Point2<f32> {
+        gfx.retrieve().window_to_screen_coords(self.position())
+    }
+
+    /// Returns whether the mouse cursor is currently inside the window area.
+    /// This is independent of whether the window has focus.
+    pub fn is_cursor_in_window(&self) -> bool {
+        self.cursor_in_window
+    }
+
     /// Get the distance the cursor was moved during the current frame, in pixels.
     pub fn delta(&self) -> mint::Point2<f32> {
         self.delta.into()
     }
 
+    /// Get the amount scrolled during the current frame, the sum of what's passed to
+    /// [`mouse_wheel_event`](crate::event::EventHandler::mouse_wheel_event). Use
+    /// [`scroll_lines`](Self::scroll_lines) and [`scroll_pixels`](Self::scroll_pixels) to tell wheels and touchpads apart.
+    pub fn wheel_delta(&self) -> mint::Vector2<f32> {
+        self.wheel_delta.into()
+    }
+
+    /// Get the number of lines scrolled during the current frame, usually by mouse wheels.
+    /// See [`ScrollDelta`].
+    pub fn scroll_lines(&self) -> mint::Vector2<f32> {
+        self.scroll_lines.into()
+    }
+
+    /// Get the distance scrolled during the current frame in physical pixels, usually by touchpads.
+    /// See [`ScrollDelta`].
+    pub fn scroll_pixels(&self) -> mint::Vector2<f32> {
+        self.scroll_pixels.into()
+    }
+
+    /// Get the distance the mouse itself was moved during the current frame, if enabled with
+    /// [`set_raw_input`](Self::set_raw_input). See also [`raw_mouse_motion_event`](crate::event::EventHandler::raw_mouse_motion_event).
+    ///
+    /// Raw motion isn't accelerated and keeps changing while the cursor is locked with
+    /// [`set_cursor_grab`](Self::set_cursor_grab) or stopped at the edge of the screen, so use
+    /// it for controlling a camera. It has no defined unit across platforms and devices, and is
+    /// independent of the cursor position and the scale factor, so scale it with a sensitivity setting.
+    /// Movement while the window isn't focused is left out.
+    pub fn raw_delta(&self) -> mint::Point2<f32> {
+        self.raw_delta.into()
+    }
+
+    /// Sets whether raw mouse motion is reported, see [`raw_delta`](Self::raw_delta). Defaults to `false`.
+    pub fn set_raw_input(&mut self, raw_input: bool) {
+        self.raw_input = raw_input;
+        if !raw_input {
+            self.raw_delta = glam::Vec2::ZERO;
+        }
+    }
+
+    /// Returns whether raw mouse motion is reported, see [`set_raw_input`](Self::set_raw_input).
+    pub fn raw_input(&self) -> bool {
+        self.raw_input
+    }
+
+    /// Sets whether the cursor is hidden while it's over the window. Hiding overrides the cursor icon
+    /// or image, which is shown again when the cursor is shown again.
+    pub fn set_cursor_hidden(&mut self, hidden: bool) {
+        self.cursor_hidden = hidden;
+        if let Ok(window) = self.window() {
+            window.set_cursor_visible(!hidden);
+        }
+        if !hidden {
+            // showing the cursor brings back the icon
+            self.restore_cursor_image();
+        }
+    }
+
+    // Sets the cursor image again, where the platform went back to the cursor icon, like on
+    // macOS when the window is resized.
+    pub(crate) fn restore_cursor_image(&self) {
+        if self.cursor_hidden {
+            return;
+        }
+        if let (Ok(window), Some(image)) = (self.window(), &self.cursor_image) {
+            let res = set_custom_cursor(
+                window,
+                &image.rgba,
+                image.width,
+                image.height,
+                image.hotspot,
+            );
+            if let Err(e) = res {
+                warn!("Could not show the cursor image again: {}", e);
+            }
+        }
+    }
+
+    /// Sets the cursor to one of the system's cursor icons, replacing a cursor image.
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.cursor_type = icon;
+        if let Ok(window) = self.window() {
+            if self.cursor_image.is_some() {
+                clear_custom_cursor(window);
+            }
+            window.set_cursor_icon(icon);
+        }
+        self.cursor_image = None;
+    }
+
+    /// Sets the cursor to an image, with the `hotspot` being the pixel which is pointed at.
+    /// The image is read back from the GPU, so call this sparingly.
+    ///
+    /// See [`set_cursor_rgba`](Self::set_cursor_rgba) for the supported sizes and platforms.
+    pub fn set_cursor_image(
+        &mut self,
+        gfx: &impl Has<GraphicsContext>,
+        image: &Image,
+        hotspot: (u32, u32),
+    ) -> GameResult {
+        let mut pixels = image.to_pixels(gfx)?;
+        match image.format() {
+            ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => {}
+            ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2)),
+            format => {
+                return Err(GameError::WindowError(format!(
+                    "cursor images need an RGBA or BGRA image, not {:?}",
+                    format
+                )))
+            }
+        }
+        self.set_cursor_rgba(&pixels, image.width(), image.height(), hotspot)
+    }
+
+    /// Sets the cursor to an image given as RGBA pixels, row by row, see [`set_cursor_image`](Self::set_cursor_image).
+    ///
+    /// Cursor images can be at most [`MAX_CURSOR_SIZE`] pixels wide and high, and are shown at
+    /// their size in physical pixels. They're supported on Windows, macOS and X11, and an error is
+    /// returned on Wayland, the web and mobile platforms, where the cursor can be hidden and the
+    /// image drawn instead.
+    pub fn set_cursor_rgba(
+        &mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot: (u32, u32),
+    ) -> GameResult {
+        if width == 0 || height == 0 || width > MAX_CURSOR_SIZE || height > MAX_CURSOR_SIZE {
+            return Err(GameError::WindowError(format!(
+                "cursor images must be between 1x1 and {0}x{0} pixels, not {1}x{2}",
+                MAX_CURSOR_SIZE, width, height
+            )));
+        }
+        if rgba.len() != (width * height * 4) as usize {
+            return Err(GameError::WindowError(format!(
+                "expected {} bytes of RGBA pixels for a {}x{} cursor image, got {}",
+                width * height * 4,
+                width,
+                height,
+                rgba.len()
+            )));
+        }
+        if hotspot.0 >= width || hotspot.1 >= height {
+            return Err(GameError::WindowError(format!(
+                "the cursor hotspot {:?} is outside of the {}x{} image",
+                hotspot, width, height
+            )));
+        }
+        // applied when the cursor is shown again
+        if !self.cursor_hidden {
+            set_custom_cursor(self.window()?, rgba, width, height, hotspot)?;
+        }
+        self.cursor_image = Some(CursorImage {
+            rgba: rgba.to_vec(),
+            width,
+            height,
+            hotspot,
+        });
+        Ok(())
+    }
+
+    /// Returns how the cursor is grabbed, see [`set_cursor_grab`](Self::set_cursor_grab).
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.cursor_grab
+    }
+
+    /// Sets whether the cursor is confined to the window (`Confined`), locked in place (`Locked`)
+    /// or free (`None`), and returns the mode which was actually applied.
+    ///
+    /// Most platforms support only one of `Confined` and `Locked`, in which case the other
+    /// one is used instead. The grab is released while the window isn't focused and applied
+    /// again when it's focused, so that the cursor isn't stuck after switching to another window.
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) -> GameResult<CursorGrabMode> {
+        let fallback = match mode {
+            CursorGrabMode::None => CursorGrabMode::None,
+            CursorGrabMode::Confined => CursorGrabMode::Locked,
+            CursorGrabMode::Locked => CursorGrabMode::Confined,
+        };
+        let window = self.window()?;
+        let applied = match window.set_cursor_grab(mode) {
+            Ok(()) => mode,
+            Err(ExternalError::NotSupported(_)) => {
+                window
+                    .set_cursor_grab(fallback)
+                    .map_err(|e| GameError::WindowError(e.to_string()))?;
+                fallback
+            }
+            Err(e) => return Err(GameError::WindowError(e.to_string())),
+        };
+        self.cursor_grab = applied;
+        self.cursor_grabbed = applied != CursorGrabMode::None;
+        Ok(applied)
+    }
+
+    /// Returns a reference to the set of currently pressed buttons.
+    /// Extra buttons, like back and forward, are reported as `MouseButton::Other`.
+    pub fn buttons_pressed(&self) -> &HashSet<MouseButton> {
+        &self.buttons_pressed
+    }
+
     /// Returns whether or not the given mouse button is pressed.
 
     pub fn button_pressed(&self, button: MouseButton) -> bool {
@@ -66,13 +390,45 @@ impl MouseContext {
     }
 
     /// Returns whether or not the given mouse button has been pressed this frame.
+    /// A click within one frame counts as both just pressed and just released.
     pub fn button_just_pressed(&self, button: MouseButton) -> bool {
-        self.buttons_pressed.contains(&button) && !self.previous_buttons_pressed.contains(&button)
+        self.just_pressed_buttons.contains(&button)
     }
 
     /// Returns whether or not the given mouse button has been released this frame.
     pub fn button_just_released(&self, button: MouseButton) -> bool {
-        !self.buttons_pressed.contains(&button) && self.previous_buttons_pressed.contains(&button)
+        self.just_released_buttons.contains(&button)
+    }
+
+    /// Returns the latest press of a mouse button, with the number of times it was clicked in a row.
+    ///
+    /// A press counts as another click in a row if it's of the same button, within the double
+    /// click interval of the previous one, and the mouse didn't move further than the double click
+    /// distance in between, see [`set_double_click`](Self::set_double_click).
+    pub fn last_click(&self) -> Option<ClickInfo> {
+        self.last_click
+    }
+
+    /// Returns how long a mouse button has been held down, or `None` if it isn't pressed.
+    pub fn press_time(&self, button: MouseButton) -> Option<Duration> {
+        self.pressed_at.get(&button).map(Instant::elapsed)
+    }
+
+    /// Returns when a mouse button was last pressed or released, on the same clock as
+    /// [`TimeContext`](crate::timer::TimeContext). In [`mouse_button_down_event`](crate::event::EventHandler::mouse_button_down_event)
+    /// and [`mouse_button_up_event`](crate::event::EventHandler::mouse_button_up_event) that's the time of the event handled.
+    ///
+    /// The windowing backends don't provide hardware timestamps, so this is when ggez received the
+    /// event. Events arriving while a frame is drawn are received once it's done.
+    pub fn button_event_time(&self) -> Option<Instant> {
+        self.button_event_time
+    }
+
+    /// Sets the longest time between clicks and the furthest distance in physical pixels between them
+    /// for counting them as clicks in a row. Defaults to 500ms and 4 pixels.
+    pub fn set_double_click(&mut self, interval: Duration, distance: f32) {
+        self.double_click_interval = interval;
+        self.double_click_distance = distance;
     }
 
     /// Updates delta and position values.
@@ -102,18 +458,68 @@ impl MouseContext {
         self.set_last_position(glam::Vec2::new(new_x as f32, new_y as f32));
     }
 
-    /// Resets the value returned by [`mouse::delta`](fn.delta.html) to zero.
+    /// Resets the values returned by [`delta`](Self::delta), [`raw_delta`](Self::raw_delta) and the scroll functions to zero.
     /// You shouldn't need to call this, except when you're running your own event loop.
     /// In this case call it right at the end, after `draw` and `update` have finished.
     pub fn reset_delta(&mut self) {
         self.delta = glam::Vec2::ZERO;
+        self.raw_delta = glam::Vec2::ZERO;
+        self.wheel_delta = glam::Vec2::ZERO;
+        self.scroll_lines = glam::Vec2::ZERO;
+        self.scroll_pixels = glam::Vec2::ZERO;
+    }
+
+    /// Adds to the value returned by [`wheel_delta`](Self::wheel_delta).
+    /// Really useful only if you are writing your own event loop
+    pub fn handle_wheel(&mut self, x: f32, y: f32) {
+        self.wheel_delta += glam::Vec2::new(x, y);
+    }
+
+    /// Adds to the values returned by [`scroll_lines`](Self::scroll_lines) and [`scroll_pixels`](Self::scroll_pixels).
+    /// Really useful only if you are writing your own event loop
+    pub fn handle_scroll(&mut self, delta: ScrollDelta) {
+        match delta {
+            ScrollDelta::Lines(lines) => self.scroll_lines += glam::Vec2::from(lines),
+            ScrollDelta::Pixels(pixels) => self.scroll_pixels += glam::Vec2::from(pixels),
+        }
+    }
+
+    /// Adds to the value returned by [`raw_delta`](Self::raw_delta), if raw input is enabled and the window is focused.
+    /// Really useful only if you are writing your own event loop
+    pub fn handle_raw_motion(&mut self, dx: f64, dy: f64) {
+        if self.wants_raw_motion() {
+            self.raw_delta += glam::Vec2::new(dx as f32, dy as f32);
+        }
+    }
+
+    pub(crate) fn wants_raw_motion(&self) -> bool {
+        self.raw_input && self.window_focused
+    }
+
+    // Releases the cursor grab while the window isn't focused and restores it afterwards.
+    pub(crate) fn set_window_focused(&mut self, focused: bool) {
+        self.window_focused = focused;
+        if self.cursor_grab == CursorGrabMode::None {
+            return;
+        }
+        let mode = if focused {
+            self.cursor_grab
+        } else {
+            CursorGrabMode::None
+        };
+        if let Ok(window) = self.window() {
+            if let Err(e) = window.set_cursor_grab(mode) {
+                warn!("Could not change the cursor grab on a focus change: {}", e);
+            }
+        }
     }
 
     /// Copies the current state of the mouse buttons into the context. If you are writing your own event loop
-    /// you need to call this at the end of every update in order to use the functions `is_button_just_pressed`
-    /// and `is_button_just_released`. Otherwise this is handled for you.
+    /// you need to call this at the end of every update in order to use the functions `button_just_pressed`
+    /// and `button_just_released`. Otherwise this is handled for you.
     pub fn save_mouse_state(&mut self) {
-        self.previous_buttons_pressed = self.buttons_pressed.clone();
+        self.just_pressed_buttons.clear();
+        self.just_released_buttons.clear();
     }
 
     pub(crate) fn set_last_position(&mut self, p: glam::Vec2) {
@@ -128,14 +534,49 @@ impl MouseContext {
         self.delta = p;
     }
 
+    pub(crate) fn set_cursor_in_window(&mut self, in_window: bool) {
+        self.cursor_in_window = in_window;
+    }
+
     pub(crate) fn set_button(&mut self, button: MouseButton, pressed: bool) {
+        self.set_button_at(button, pressed, Instant::now());
+    }
+
+    fn set_button_at(&mut self, button: MouseButton, pressed: bool, time: Instant) {
+        self.button_event_time = Some(time);
         if pressed {
-            let _ = self.buttons_pressed.insert(button);
-        } else {
-            let _ = self.buttons_pressed.remove(&button);
+            if self.buttons_pressed.insert(button) {
+                let _ = self.just_pressed_buttons.insert(button);
+                let _ = self.pressed_at.insert(button, time);
+                self.click(button, time);
+            }
+        } else if self.buttons_pressed.remove(&button) {
+            let _ = self.just_released_buttons.insert(button);
+            let _ = self.pressed_at.remove(&button);
         }
     }
 
+    fn click(&mut self, button: MouseButton, time: Instant) {
+        let position = self.last_position;
+        let count = match self.last_click {
+            Some(last)
+                if last.button == button
+                    && time.duration_since(last.time) <= self.double_click_interval
+                    && position.distance(last.position.into()) <= self.double_click_distance =>
+            {
+                last.count + 1
+            }
+            _ => 1,
+        };
+        let click = ClickInfo {
+            button,
+            position: position.into(),
+            count,
+            time,
+        };
+        self.last_click = Some(click);
+    }
+
     /// Get the distance the cursor was moved between the latest two mouse_motion_events.
     /// Really useful only if you are writing your own event loop
     pub fn last_delta(&self) -> mint::Point2<f32> {
@@ -213,15 +654,13 @@ pub fn handle_move(ctx: &mut Context, new_x: f32, new_y: f32) {
 /// Set whether or not the mouse is hidden (invisible).
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_hidden(ctx: &mut Context, hidden: bool) {
-    ctx.mouse.cursor_hidden = hidden;
-    ctx.gfx.window.set_cursor_visible(!hidden)
+    ctx.mouse.set_cursor_hidden(hidden)
 }
 
 /// Modifies the mouse cursor type of the window.
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_type(ctx: &mut Context, cursor_type: CursorIcon) {
-    ctx.mouse.cursor_type = cursor_type;
-    ctx.gfx.window.set_cursor_icon(cursor_type);
+    ctx.mouse.set_cursor_icon(cursor_type)
 }
 
 /// Get whether or not the mouse is grabbed.
@@ -235,19 +674,13 @@ pub fn cursor_grabbed(ctx: &Context) -> bool {
 /// **Note**: macOS locks the cursor rather than confining it.
 // TODO: Move to graphics context (This isn't input)
 pub fn set_cursor_grabbed(ctx: &mut Context, grabbed: bool) -> GameResult {
-    ctx.mouse.cursor_grabbed = grabbed;
-    ctx.gfx
-        .window
-        .set_cursor_grab(if grabbed {
-            if cfg!(target_os = "macos") {
-                CursorGrabMode::Locked
-            } else {
-                CursorGrabMode::Confined
-            }
-        } else {
-            CursorGrabMode::None
-        })
-        .map_err(|e| GameError::WindowError(e.to_string()))
+    let mode = if grabbed {
+        CursorGrabMode::Confined
+    } else {
+        CursorGrabMode::None
+    };
+    let _ = ctx.mouse.set_cursor_grab(mode)?;
+    Ok(())
 }
 
 /// Set the current position of the mouse cursor, in pixels.
@@ -260,10 +693,97 @@ where
     let point = glam::Vec2::from(point.into());
     ctx.mouse.last_position = point;
     ctx.gfx
-        .window
+        .main_window()?
         .set_cursor_position(dpi::LogicalPosition {
             x: f64::from(point.x),
             y: f64::from(point.y),
         })
         .map_err(|_| GameError::WindowError("Couldn't set mouse cursor position!".to_owned()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn click_counting() {
+        let mut mouse = MouseContext::new();
+        let start = Instant::now();
+        let click = |mouse: &mut MouseContext, button, ms| {
+            let time = start + Duration::from_millis(ms);
+            mouse.set_button_at(button, true, time);
+            mouse.set_button_at(button, false, time);
+            mouse.last_click().unwrap().count
+        };
+        assert_eq!(click(&mut mouse, MouseButton::Left, 0), 1);
+        assert!(mouse.button_just_pressed(MouseButton::Left));
+        assert!(mouse.button_just_released(MouseButton::Left));
+        assert!(!mouse.button_pressed(MouseButton::Left));
+        assert_eq!(click(&mut mouse, MouseButton::Left, 200), 2);
+        assert_eq!(click(&mut mouse, MouseButton::Left, 400), 3);
+        // too late
+        assert_eq!(click(&mut mouse, MouseButton::Left, 1000), 1);
+        // other button
+        assert_eq!(click(&mut mouse, MouseButton::Right, 1100), 1);
+        // moved too far
+        mouse.handle_move(10., 0.);
+        assert_eq!(click(&mut mouse, MouseButton::Right, 1200), 1);
+        mouse.handle_move(12., 2.);
+        assert_eq!(click(&mut mouse, MouseButton::Right, 1300), 2);
+
+        let time = start + Duration::from_millis(1300);
+        assert_eq!(mouse.last_click().unwrap().time, time);
+        assert_eq!(mouse.button_event_time(), Some(time));
+
+        mouse.save_mouse_state();
+        assert!(!mouse.button_just_pressed(MouseButton::Right));
+    }
+
+    #[test]
+    fn buttons_pressed() {
+        let mut mouse = MouseContext::new();
+        mouse.set_button(MouseButton::Left, true);
+        mouse.set_button(MouseButton::Other(8), true);
+        assert_eq!(mouse.buttons_pressed().len(), 2);
+        assert!(mouse.buttons_pressed().contains(&MouseButton::Other(8)));
+        assert!(mouse.press_time(MouseButton::Left).is_some());
+        mouse.set_button(MouseButton::Left, false);
+        assert_eq!(mouse.buttons_pressed().len(), 1);
+        assert!(mouse.press_time(MouseButton::Left).is_none());
+        assert!(mouse.press_time(MouseButton::Other(8)).is_some());
+    }
+
+    #[test]
+    fn scroll_accumulation() {
+        let mut mouse = MouseContext::new();
+        mouse.handle_scroll(ScrollDelta::Lines(mint::Vector2 { x: 0., y: 1. }));
+        mouse.handle_scroll(ScrollDelta::Lines(mint::Vector2 { x: -1., y: 1. }));
+        mouse.handle_scroll(ScrollDelta::Pixels(mint::Vector2 { x: 3., y: -4. }));
+        assert_eq!(mouse.scroll_lines(), mint::Vector2 { x: -1., y: 2. });
+        assert_eq!(mouse.scroll_pixels(), mint::Vector2 { x: 3., y: -4. });
+        mouse.handle_wheel(0., 1.);
+        mouse.handle_wheel(2., 1.);
+        assert_eq!(mouse.wheel_delta(), mint::Vector2 { x: 2., y: 2. });
+        mouse.reset_delta();
+        assert_eq!(mouse.wheel_delta(), mint::Vector2 { x: 0., y: 0. });
+        assert_eq!(mouse.scroll_lines(), mint::Vector2 { x: 0., y: 0. });
+        assert_eq!(mouse.scroll_pixels(), mint::Vector2 { x: 0., y: 0. });
+    }
+
+    #[test]
+    fn cursor_image_validation() {
+        let mut mouse = MouseContext::new();
+        assert!(mouse.set_cursor_rgba(&[], 0, 0, (0, 0)).is_err());
+        assert!(mouse.set_cursor_rgba(&[0; 12], 2, 2, (0, 0)).is_err());
+        assert!(mouse.set_cursor_rgba(&[0; 16], 2, 2, (2, 0)).is_err());
+        let size = MAX_CURSOR_SIZE + 1;
+        let pixels = vec![0; (size * size * 4) as usize];
+        assert!(mouse.set_cursor_rgba(&pixels, size, size, (0, 0)).is_err());
+
+        // applied once the cursor is shown, which needs a window
+        mouse.set_cursor_hidden(true);
+        assert!(mouse.set_cursor_rgba(&[0; 16], 2, 2, (1, 1)).is_ok());
+        mouse.set_cursor_icon(CursorIcon::Hand);
+        assert!(mouse.cursor_image.is_none());
+    }
+}
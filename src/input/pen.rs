@@ -0,0 +1,172 @@
+//! Pen and stylus input, passed to [`EventHandler::pen_event`](crate::event::EventHandler::pen_event).
+//!
+//! Pen events are also synthesized from the mouse, so a drawing program written against them
+//! works with a mouse too: the left button is the pen touching the surface with a pressure of 1,
+//! moving the mouse without it is hovering, and the right and middle buttons are the barrel buttons.
+//! Tablet drivers commonly map their pens and barrel buttons to these, which gives you the position
+//! but not the pressure of such pens.
+//!
+//! What the backends provide:
+//!
+//! * iOS reports the Apple Pencil with its real pressure. Fingers aren't reported as pens.
+//! * Windows reports pens like fingers, as touches, with a pressure in [`Touch::force`](super::touch::Touch::force)
+//!   and [`touch_event`](crate::event::EventHandler::touch_event). Pen events come from the mouse.
+//! * Everywhere else pen events come from the mouse.
+//!
+//! No backend provides the tilt of the pen or whether its eraser is used yet,
+//! so `tilt` is always zero and `eraser` is always false.
+
+use crate::input::mouse::MouseContext;
+use std::collections::HashSet;
+use winit::event::{Force, MouseButton, TouchPhase};
+
+bitflags::bitflags! {
+    /// The buttons on the barrel of a pen which are held down.
+    #[derive(Default)]
+    pub struct PenButtons: u8 {
+        /// The button closest to the tip, the right mouse button when synthesized from the mouse.
+        const PRIMARY = 0b01;
+        /// The second button, the middle mouse button when synthesized from the mouse.
+        const SECONDARY = 0b10;
+    }
+}
+
+/// Whether the pen is touching the surface.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PenPhase {
+    /// The pen moved above the surface, close enough to be noticed but without touching it.
+    Hover,
+    /// The pen started touching the surface.
+    Down,
+    /// The pen moved while touching the surface.
+    Move,
+    /// The pen stopped touching the surface.
+    Up,
+}
+
+/// The state of a pen when it moved or touched the surface.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PenEvent {
+    /// Where the tip of the pen is, in physical pixels like mouse positions.
+    pub position: mint::Point2<f32>,
+    /// How hard the pen is pressed, from 0 to 1. It's 0 while hovering and
+    /// 1 while touching the surface on backends which don't report pressure.
+    pub pressure: f32,
+    /// How far the pen is tilted from pointing straight into the screen,
+    /// towards the right and the bottom in radians. See the [module docs](self) for its support.
+    pub tilt: mint::Vector2<f32>,
+    /// Whether the pen is touching the surface.
+    pub phase: PenPhase,
+    /// The barrel buttons held down.
+    pub barrel_buttons: PenButtons,
+    /// Whether the pen is used the other way around to erase. See the [module docs](self) for its support.
+    pub eraser: bool,
+}
+
+impl PenEvent {
+    /// Synthesizes the pen event for the current state of the mouse.
+    pub(crate) fn from_mouse(mouse: &MouseContext, phase: PenPhase) -> Self {
+        from_buttons(mouse.position(), phase, mouse.buttons_pressed())
+    }
+
+    /// Returns the pen event for a touch made with a pen, on platforms which tell them apart from fingers.
+    pub(crate) fn from_touch(touch: &winit::event::Touch) -> Option<Self> {
+        // only iOS reports the altitude, and only for the Apple Pencil
+        let force = match touch.force {
+            Some(
+                force @ Force::Calibrated {
+                    altitude_angle: Some(_),
+                    ..
+                },
+            ) => force,
+            _ => return None,
+        };
+        let phase = match touch.phase {
+            TouchPhase::Started => PenPhase::Down,
+            TouchPhase::Moved => PenPhase::Move,
+            TouchPhase::Ended | TouchPhase::Cancelled => PenPhase::Up,
+        };
+        Some(PenEvent {
+            position: mint::Point2 {
+                x: touch.location.x as f32,
+                y: touch.location.y as f32,
+            },
+            pressure: if phase == PenPhase::Up {
+                0.
+            } else {
+                pressure(force)
+            },
+            tilt: mint::Vector2 { x: 0., y: 0. },
+            phase,
+            barrel_buttons: PenButtons::empty(),
+            eraser: false,
+        })
+    }
+}
+
+/// Returns the phase of the pen synthesized when the mouse moved.
+pub(crate) fn mouse_motion_phase(mouse: &MouseContext) -> PenPhase {
+    if mouse.button_pressed(MouseButton::Left) {
+        PenPhase::Move
+    } else {
+        PenPhase::Hover
+    }
+}
+
+fn from_buttons(
+    position: mint::Point2<f32>,
+    phase: PenPhase,
+    buttons: &HashSet<MouseButton>,
+) -> PenEvent {
+    let mut barrel_buttons = PenButtons::empty();
+    barrel_buttons.set(PenButtons::PRIMARY, buttons.contains(&MouseButton::Right));
+    barrel_buttons.set(
+        PenButtons::SECONDARY,
+        buttons.contains(&MouseButton::Middle),
+    );
+    PenEvent {
+        position,
+        pressure: match phase {
+            PenPhase::Down | PenPhase::Move => 1.,
+            PenPhase::Hover | PenPhase::Up => 0.,
+        },
+        tilt: mint::Vector2 { x: 0., y: 0. },
+        phase,
+        barrel_buttons,
+        eraser: false,
+    }
+}
+
+// Calibrated forces can exceed the force of a normal touch.
+fn pressure(force: Force) -> f32 {
+    (force.normalized() as f32).clamp(0., 1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthesized_from_mouse() {
+        let position = mint::Point2 { x: 5., y: 7. };
+        let mut buttons = HashSet::new();
+        let event = from_buttons(position, PenPhase::Hover, &buttons);
+        assert_eq!(event.pressure, 0.);
+        assert!(event.barrel_buttons.is_empty());
+
+        let _ = buttons.insert(MouseButton::Left);
+        let _ = buttons.insert(MouseButton::Right);
+        let event = from_buttons(position, PenPhase::Move, &buttons);
+        assert_eq!(event.pressure, 1.);
+        assert_eq!(event.barrel_buttons, PenButtons::PRIMARY);
+        assert_eq!(event.position, position);
+
+        assert_eq!(pressure(Force::Normalized(0.25)), 0.25);
+        let hard = Force::Calibrated {
+            force: 6.,
+            max_possible_force: 4.,
+            altitude_angle: Some(std::f64::consts::FRAC_PI_2),
+        };
+        assert_eq!(pressure(hard), 1.);
+    }
+}
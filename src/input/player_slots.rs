@@ -0,0 +1,416 @@
+//! Assigning gamepads and the keyboard to the players of a local multiplayer game.
+//!
+//! [`PlayerSlots`] keeps track of which device controls which player, with a "press A to join" flow:
+//!
+//! ```rust,no_run
+//! # use ggez::Context;
+//! use ggez::input::{gamepad::gilrs::Button, PlayerSlots};
+//!
+//! # fn f(ctx: &Context) {
+//! let mut slots = PlayerSlots::new();
+//! slots.begin_join(4);
+//!
+//! // in `update`
+//! slots.update(ctx);
+//! for player in slots.just_joined() {
+//!     println!("Player {} joined", player + 1);
+//! }
+//! if let Some(id) = slots.gamepad_for(0) {
+//!     if ctx.gamepad.button_just_pressed(id, Button::East) {
+//!         // player 1 wants to leave
+//!         slots.release(0);
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! Players are numbered from 0. When a gamepad is disconnected, its player's slot stays reserved
+//! for it, and the gamepad reclaims it when it's connected again, even with another [`GamepadId`],
+//! so a wireless gamepad falling asleep doesn't shuffle the players around. Gamepads are recognized
+//! by their [`PersistentGamepadId`], which tells identical gamepads apart by the order they
+//! were connected in. Slots are only freed by [`release`](PlayerSlots::release).
+//!
+//! The slots can be serialized to remember the players for the next session. Their gamepads
+//! are reserved until they're connected.
+#![cfg(feature = "gamepad")]
+
+use crate::context::Context;
+use crate::input::gamepad::{gilrs::Button, GamepadId, PersistentGamepadId};
+use crate::input::keyboard::KeyCode;
+
+/// A device controlling a player.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayerDevice {
+    /// The keyboard, and the mouse with it.
+    Keyboard,
+    /// A gamepad.
+    Gamepad(GamepadId),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Slot {
+    Vacant,
+    Keyboard,
+    // the persistent id is known once the gamepad has been seen connected
+    Gamepad {
+        id: GamepadId,
+        persistent_id: Option<PersistentGamepadId>,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        connected: bool,
+    },
+}
+
+/// Assigns gamepads and the keyboard to players, see the [module docs](self).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerSlots {
+    slots: Vec<Slot>,
+    joining: bool,
+    join_button: Button,
+    join_key: Option<KeyCode>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    just_joined: Vec<usize>,
+}
+
+impl Default for PlayerSlots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlayerSlots {
+    /// Creates slots without any players, in which gamepads join by pressing `South`
+    /// (A on Xbox controllers) once [`begin_join`](Self::begin_join) was called.
+    pub fn new() -> Self {
+        PlayerSlots {
+            slots: Vec::new(),
+            joining: false,
+            join_button: Button::South,
+            join_key: None,
+            just_joined: Vec::new(),
+        }
+    }
+
+    /// Lets devices join by pressing the join button, up to `max_players` players.
+    ///
+    /// Players already assigned keep their slots. If there were more slots before,
+    /// the players beyond `max_players` are released.
+    pub fn begin_join(&mut self, max_players: usize) {
+        self.slots.resize(max_players, Slot::Vacant);
+        self.joining = true;
+    }
+
+    /// Stops devices from joining, e.g. once the game started. Reserved slots can still be reclaimed.
+    pub fn end_join(&mut self) {
+        self.joining = false;
+    }
+
+    /// Returns whether devices can join, see [`begin_join`](Self::begin_join).
+    pub fn is_joining(&self) -> bool {
+        self.joining
+    }
+
+    /// Sets the gamepad button which joins the game.
+    pub fn set_join_button(&mut self, button: Button) {
+        self.join_button = button;
+    }
+
+    /// Returns the gamepad button which joins the game.
+    pub fn join_button(&self) -> Button {
+        self.join_button
+    }
+
+    /// Sets the key with which the keyboard joins as a player, or with `None`,
+    /// the default, keeps it from joining on its own. It can still be [assigned](Self::assign).
+    pub fn set_join_key(&mut self, key: Option<KeyCode>) {
+        self.join_key = key;
+    }
+
+    /// Returns the key with which the keyboard joins, see [`set_join_key`](Self::set_join_key).
+    pub fn join_key(&self) -> Option<KeyCode> {
+        self.join_key
+    }
+
+    /// Assigns joining and reconnected devices to their slots. Call this once every frame.
+    pub fn update(&mut self, ctx: &Context) {
+        let connected: Vec<_> = ctx
+            .gamepad
+            .gamepads()
+            .map(|(id, info)| (id, info.persistent_id))
+            .collect();
+        let mut join_presses: Vec<_> = connected
+            .iter()
+            .filter(|(id, _)| ctx.gamepad.button_just_pressed(*id, self.join_button))
+            .map(|(id, _)| PlayerDevice::Gamepad(*id))
+            .collect();
+        if matches!(self.join_key, Some(key) if ctx.keyboard.is_key_just_pressed(key)) {
+            join_presses.push(PlayerDevice::Keyboard);
+        }
+        self.update_from(&connected, &join_presses);
+    }
+
+    // Updates the slots from the gamepads connected, in the order they were connected,
+    // and the devices which pressed their join button.
+    fn update_from(
+        &mut self,
+        connected: &[(GamepadId, PersistentGamepadId)],
+        join_presses: &[PlayerDevice],
+    ) {
+        self.just_joined.clear();
+
+        for slot in &mut self.slots {
+            if let Slot::Gamepad {
+                id,
+                persistent_id,
+                connected: is_connected,
+            } = slot
+            {
+                // gamepads can get another id when they're connected again
+                let found = match persistent_id {
+                    Some(persistent_id) => connected
+                        .iter()
+                        .find(|(_, connected_id)| connected_id == persistent_id),
+                    None => connected
+                        .iter()
+                        .find(|(connected_id, _)| connected_id == id),
+                };
+                match found {
+                    Some((connected_id, connected_persistent_id)) => {
+                        *id = *connected_id;
+                        *persistent_id = Some(*connected_persistent_id);
+                        *is_connected = true;
+                    }
+                    None => *is_connected = false,
+                }
+            }
+        }
+
+        if !self.joining {
+            return;
+        }
+        for device in join_presses {
+            if self.player_for_device(*device).is_some() {
+                continue;
+            }
+            let player = match self.slots.iter().position(|slot| *slot == Slot::Vacant) {
+                Some(player) => player,
+                None => break,
+            };
+            self.slots[player] = match *device {
+                PlayerDevice::Keyboard => Slot::Keyboard,
+                PlayerDevice::Gamepad(id) => Slot::Gamepad {
+                    id,
+                    persistent_id: connected
+                        .iter()
+                        .find(|(connected_id, _)| *connected_id == id)
+                        .map(|(_, persistent_id)| *persistent_id),
+                    connected: true,
+                },
+            };
+            self.just_joined.push(player);
+        }
+    }
+
+    /// Returns the players who joined with the latest [`update`](Self::update).
+    /// Players who reclaimed their slot by connecting their gamepad again aren't included.
+    pub fn just_joined(&self) -> &[usize] {
+        &self.just_joined
+    }
+
+    /// Assigns a device to a player, taking it from the player it was assigned to before.
+    /// There are more slots afterwards if the player is beyond them.
+    ///
+    /// Gamepads should be connected, as they're recognized by their persistent id
+    /// from the next [`update`](Self::update) on.
+    pub fn assign(&mut self, player: usize, device: PlayerDevice) {
+        if let Some(previous) = self.player_for_device(device) {
+            self.slots[previous] = Slot::Vacant;
+        }
+        if player >= self.slots.len() {
+            self.slots.resize(player + 1, Slot::Vacant);
+        }
+        self.slots[player] = match device {
+            PlayerDevice::Keyboard => Slot::Keyboard,
+            PlayerDevice::Gamepad(id) => Slot::Gamepad {
+                id,
+                persistent_id: None,
+                connected: true,
+            },
+        };
+    }
+
+    /// Frees the slot of a player, so another device can join in it.
+    pub fn release(&mut self, player: usize) {
+        if let Some(slot) = self.slots.get_mut(player) {
+            *slot = Slot::Vacant;
+        }
+    }
+
+    /// Returns the number of slots, see [`begin_join`](Self::begin_join).
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns whether there are no slots.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the device controlling a player, if there is one.
+    /// That's `None` as well while the slot is reserved for a disconnected gamepad.
+    pub fn device_for(&self, player: usize) -> Option<PlayerDevice> {
+        match self.slots.get(player)? {
+            Slot::Vacant
+            | Slot::Gamepad {
+                connected: false, ..
+            } => None,
+            Slot::Keyboard => Some(PlayerDevice::Keyboard),
+            Slot::Gamepad { id, .. } => Some(PlayerDevice::Gamepad(*id)),
+        }
+    }
+
+    /// Returns the gamepad controlling a player, if it's controlled by a connected gamepad.
+    pub fn gamepad_for(&self, player: usize) -> Option<GamepadId> {
+        match self.device_for(player)? {
+            PlayerDevice::Gamepad(id) => Some(id),
+            PlayerDevice::Keyboard => None,
+        }
+    }
+
+    /// Returns the player a connected gamepad controls.
+    pub fn player_for(&self, id: GamepadId) -> Option<usize> {
+        self.player_for_device(PlayerDevice::Gamepad(id))
+    }
+
+    /// Returns the player the keyboard controls.
+    pub fn keyboard_player(&self) -> Option<usize> {
+        self.player_for_device(PlayerDevice::Keyboard)
+    }
+
+    /// Returns the player a device controls, if the device is connected.
+    pub fn player_for_device(&self, device: PlayerDevice) -> Option<usize> {
+        self.slots.iter().position(|slot| match (slot, device) {
+            (Slot::Keyboard, PlayerDevice::Keyboard) => true,
+            (
+                Slot::Gamepad {
+                    id,
+                    connected: true,
+                    ..
+                },
+                PlayerDevice::Gamepad(device),
+            ) => *id == device,
+            _ => false,
+        })
+    }
+
+    /// Returns the player a gamepad controls, also while their slot is reserved for it.
+    pub fn player_for_persistent(&self, persistent_id: PersistentGamepadId) -> Option<usize> {
+        self.slots.iter().position(|slot| {
+            matches!(slot, Slot::Gamepad { persistent_id: Some(id), .. } if *id == persistent_id)
+        })
+    }
+
+    /// Returns whether a player's slot is reserved for a disconnected gamepad.
+    pub fn is_reserved(&self, player: usize) -> bool {
+        matches!(
+            self.slots.get(player),
+            Some(Slot::Gamepad {
+                connected: false,
+                ..
+            })
+        )
+    }
+
+    /// Returns the players and the devices controlling them, leaving out vacant and reserved slots.
+    pub fn players(&self) -> impl Iterator<Item = (usize, PlayerDevice)> + '_ {
+        (0..self.slots.len()).filter_map(|player| Some((player, self.device_for(player)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: usize) -> GamepadId {
+        GamepadId::new(n)
+    }
+
+    fn pad(index: u32) -> PersistentGamepadId {
+        PersistentGamepadId::new([1; 16], "Pad", index)
+    }
+
+    #[test]
+    fn joining() {
+        let (first, second) = (id(0), id(1));
+        let connected = [(first, pad(0)), (second, pad(1))];
+        let mut slots = PlayerSlots::new();
+        slots.set_join_key(Some(KeyCode::Return));
+
+        // nobody joins before joining begins
+        slots.update_from(&connected, &[PlayerDevice::Gamepad(first)]);
+        assert_eq!(slots.player_for(first), None);
+
+        slots.begin_join(2);
+        slots.update_from(&connected, &[PlayerDevice::Gamepad(second)]);
+        assert_eq!(slots.just_joined(), [0]);
+        slots.update_from(
+            &connected,
+            &[PlayerDevice::Keyboard, PlayerDevice::Gamepad(second)],
+        );
+        assert_eq!(slots.just_joined(), [1]);
+        assert_eq!(slots.gamepad_for(0), Some(second));
+        assert_eq!(slots.keyboard_player(), Some(1));
+        // all slots are taken
+        slots.update_from(&connected, &[PlayerDevice::Gamepad(first)]);
+        assert!(slots.just_joined().is_empty());
+        assert_eq!(slots.player_for(first), None);
+
+        slots.release(1);
+        slots.assign(1, PlayerDevice::Gamepad(first));
+        assert_eq!(
+            slots.players().collect::<Vec<_>>(),
+            [
+                (0, PlayerDevice::Gamepad(second)),
+                (1, PlayerDevice::Gamepad(first))
+            ]
+        );
+        slots.assign(0, PlayerDevice::Gamepad(first));
+        assert_eq!(slots.player_for(first), Some(0));
+        assert_eq!(slots.device_for(1), None);
+    }
+
+    #[test]
+    fn reconnecting() {
+        let mut slots = PlayerSlots::new();
+        slots.begin_join(2);
+        slots.update_from(&[(id(0), pad(0))], &[PlayerDevice::Gamepad(id(0))]);
+        slots.end_join();
+
+        // disconnected, the slot stays reserved
+        slots.update_from(&[], &[]);
+        assert!(slots.is_reserved(0));
+        assert_eq!(slots.gamepad_for(0), None);
+        assert_eq!(slots.player_for(id(0)), None);
+        assert_eq!(slots.player_for_persistent(pad(0)), Some(0));
+
+        // another gamepad doesn't take it, also with the same id
+        slots.update_from(&[(id(0), pad(1))], &[]);
+        assert!(slots.is_reserved(0));
+        // the same one does, even with another id
+        slots.update_from(&[(id(0), pad(1)), (id(2), pad(0))], &[]);
+        assert_eq!(slots.gamepad_for(0), Some(id(2)));
+        assert!(slots.just_joined().is_empty());
+
+        // remembered for the next session
+        #[cfg(feature = "serde")]
+        {
+            let saved = bincode::serialize(&slots).unwrap();
+            let mut slots: PlayerSlots = bincode::deserialize(&saved).unwrap();
+            assert!(slots.is_reserved(0));
+            slots.update_from(&[(id(5), pad(0))], &[]);
+            assert_eq!(slots.gamepad_for(0), Some(id(5)));
+        }
+    }
+}
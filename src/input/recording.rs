@@ -0,0 +1,747 @@
+//! Recording input and playing it back, for replays and automated tests.
+//!
+//! A [`Recorder`] collects the keyboard, mouse, gamepad, resize and focus events of the main window
+//! along with the update they happened before, counted with [`TimeContext::ticks`]. A [`Player`]
+//! feeds them back into the game at the same updates, while live input is ignored:
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! # use ggez::input::recording::Player;
+//! # fn f(ctx: &mut Context) -> GameResult {
+//! ctx.recording.start_recording(&ctx.time);
+//! // ...play for a while, then
+//! if let Some(recorder) = ctx.recording.stop_recording() {
+//!     let player = Player::new(recorder.events().to_vec());
+//!     ctx.recording.play(player, &ctx.time);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `serde` feature, recordings can be saved to a file with [`Recorder::save`]
+//! and loaded again with [`Player::load`].
+//!
+//! [`event::run`](crate::event::run) records and plays the events for you,
+//! see [`RecordingContext`] if you are writing your own event loop.
+//!
+//! Playing back input only reproduces the game if it reacts the same way to the same input.
+//! Game logic depending on how much time passed, like moving things by
+//! [`TimeContext::delta`] or updating with [`TimeContext::check_update_time`],
+//! will diverge, as will unseeded randomness. Advance the game by a fixed step every update instead.
+//!
+//! Some input can't be played back exactly:
+//!
+//! * Resizes are played back by resizing the window, and their `resize_event` arrives
+//!   when the window actually changed size, which may be an update later.
+//! * The state of gamepads polled through [`Gamepad`](crate::input::gamepad::Gamepad) is the real one,
+//!   only the gamepad events and [`button_just_pressed`](crate::input::gamepad::GamepadContext::button_just_pressed)
+//!   are played back.
+//! * Touches and the input of secondary windows aren't recorded, but ignored while playing, like all live input.
+
+#[cfg(feature = "serde")]
+use crate::context::Has;
+#[cfg(feature = "serde")]
+use crate::error::{GameError, GameResult};
+#[cfg(feature = "serde")]
+use crate::filesystem::Filesystem;
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad::{GamepadContext, GamepadId};
+#[cfg(feature = "gamepad")]
+use crate::input::gamepad_backend::{GamepadEvent, GamepadEventKind};
+use crate::timer::TimeContext;
+#[cfg(feature = "gamepad")]
+use gilrs::{Axis, Button};
+#[cfg(feature = "gamepad")]
+use instant::Instant;
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use std::path::Path;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{
+    DeviceEvent, DeviceId, ElementState, Event, Ime, KeyboardInput, ModifiersState, MouseButton,
+    MouseScrollDelta, TouchPhase, WindowEvent,
+};
+use winit::window::WindowId;
+
+// Bumped whenever the serialized events change, so old recordings are refused instead of misread.
+#[cfg(feature = "serde")]
+const FORMAT_VERSION: u32 = 1;
+
+/// An input event as it's recorded.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecordedEvent {
+    /// A key was pressed or released.
+    Key(KeyboardInput),
+    /// The modifier keys held down changed.
+    Modifiers(ModifiersState),
+    /// A character was typed.
+    Text(char),
+    /// The input method composed text.
+    Ime(Ime),
+    /// The mouse moved to a position in physical pixels.
+    MouseMotion {
+        /// The horizontal position.
+        x: f64,
+        /// The vertical position.
+        y: f64,
+    },
+    /// The mouse moved by an amount without defined units, see
+    /// [`EventHandler::raw_mouse_motion_event`](crate::event::EventHandler::raw_mouse_motion_event).
+    RawMouseMotion {
+        /// The horizontal motion.
+        dx: f64,
+        /// The vertical motion.
+        dy: f64,
+    },
+    /// A mouse button was pressed or released.
+    MouseButton {
+        /// The button.
+        button: MouseButton,
+        /// Whether it was pressed or released.
+        state: ElementState,
+    },
+    /// The mouse wheel or touchpad scrolled.
+    MouseWheel {
+        /// How far it scrolled.
+        delta: MouseScrollDelta,
+        /// Whether the scrolling started or ended.
+        phase: TouchPhase,
+    },
+    /// The mouse entered (`true`) or left (`false`) the window.
+    MouseEnterOrLeave(bool),
+    /// The window was resized to a size in physical pixels.
+    Resized {
+        /// The new width.
+        width: u32,
+        /// The new height.
+        height: u32,
+    },
+    /// The window gained (`true`) or lost (`false`) focus.
+    Focused(bool),
+    /// A gamepad button was pressed or released.
+    #[cfg(feature = "gamepad")]
+    GamepadButton {
+        /// The gamepad.
+        id: GamepadId,
+        /// The button.
+        button: Button,
+        /// Whether it was pressed or released.
+        pressed: bool,
+    },
+    /// A gamepad axis changed.
+    #[cfg(feature = "gamepad")]
+    GamepadAxis {
+        /// The gamepad.
+        id: GamepadId,
+        /// The axis.
+        axis: Axis,
+        /// Its new value.
+        value: f32,
+    },
+    /// How far a gamepad button is pressed changed, for analog triggers and pressure sensitive buttons.
+    #[cfg(feature = "gamepad")]
+    GamepadButtonValue {
+        /// The gamepad.
+        id: GamepadId,
+        /// The button.
+        button: Button,
+        /// Its new value, from 0 to 1.
+        value: f32,
+    },
+}
+
+impl RecordedEvent {
+    // The recorded form of a window or device event, if it's input which is recorded.
+    fn from_event(event: &Event<()>, window_id: WindowId) -> Option<Self> {
+        let event = match event {
+            Event::WindowEvent {
+                event,
+                window_id: id,
+            } if *id == window_id => event,
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                ..
+            } => return Some(RecordedEvent::RawMouseMotion { dx: *dx, dy: *dy }),
+            _ => return None,
+        };
+        Some(match event {
+            WindowEvent::KeyboardInput { input, .. } => RecordedEvent::Key(*input),
+            WindowEvent::ModifiersChanged(mods) => RecordedEvent::Modifiers(*mods),
+            WindowEvent::ReceivedCharacter(ch) => RecordedEvent::Text(*ch),
+            WindowEvent::Ime(ime) => RecordedEvent::Ime(ime.clone()),
+            WindowEvent::CursorMoved { position, .. } => RecordedEvent::MouseMotion {
+                x: position.x,
+                y: position.y,
+            },
+            WindowEvent::MouseInput { state, button, .. } => RecordedEvent::MouseButton {
+                button: *button,
+                state: *state,
+            },
+            WindowEvent::MouseWheel { delta, phase, .. } => RecordedEvent::MouseWheel {
+                delta: *delta,
+                phase: *phase,
+            },
+            WindowEvent::CursorEntered { .. } => RecordedEvent::MouseEnterOrLeave(true),
+            WindowEvent::CursorLeft { .. } => RecordedEvent::MouseEnterOrLeave(false),
+            WindowEvent::Resized(size) => RecordedEvent::Resized {
+                width: size.width,
+                height: size.height,
+            },
+            WindowEvent::Focused(gained) => RecordedEvent::Focused(*gained),
+            _ => return None,
+        })
+    }
+
+    // The event to play back, or `None` for gamepad events.
+    #[allow(deprecated)]
+    fn to_event(&self, window_id: WindowId) -> Option<Event<'static, ()>> {
+        // Safety: the id is only compared by the user, ggez never passes it to winit.
+        #[allow(unsafe_code)]
+        let device_id = unsafe { DeviceId::dummy() };
+        let modifiers = ModifiersState::empty();
+        let event = match self {
+            RecordedEvent::Key(input) => WindowEvent::KeyboardInput {
+                device_id,
+                input: *input,
+                is_synthetic: false,
+            },
+            RecordedEvent::Modifiers(mods) => WindowEvent::ModifiersChanged(*mods),
+            RecordedEvent::Text(ch) => WindowEvent::ReceivedCharacter(*ch),
+            RecordedEvent::Ime(ime) => WindowEvent::Ime(ime.clone()),
+            RecordedEvent::MouseMotion { x, y } => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(*x, *y),
+                modifiers,
+            },
+            RecordedEvent::RawMouseMotion { dx, dy } => {
+                return Some(Event::DeviceEvent {
+                    device_id,
+                    event: DeviceEvent::MouseMotion { delta: (*dx, *dy) },
+                })
+            }
+            RecordedEvent::MouseButton { button, state } => WindowEvent::MouseInput {
+                device_id,
+                state: *state,
+                button: *button,
+                modifiers,
+            },
+            RecordedEvent::MouseWheel { delta, phase } => WindowEvent::MouseWheel {
+                device_id,
+                delta: *delta,
+                phase: *phase,
+                modifiers,
+            },
+            RecordedEvent::MouseEnterOrLeave(true) => WindowEvent::CursorEntered { device_id },
+            RecordedEvent::MouseEnterOrLeave(false) => WindowEvent::CursorLeft { device_id },
+            RecordedEvent::Resized { width, height } => {
+                WindowEvent::Resized(PhysicalSize::new(*width, *height))
+            }
+            RecordedEvent::Focused(gained) => WindowEvent::Focused(*gained),
+            #[cfg(feature = "gamepad")]
+            RecordedEvent::GamepadButton { .. }
+            | RecordedEvent::GamepadAxis { .. }
+            | RecordedEvent::GamepadButtonValue { .. } => return None,
+        };
+        Some(Event::WindowEvent { window_id, event })
+    }
+
+    fn is_gamepad_event(&self) -> bool {
+        #[cfg(feature = "gamepad")]
+        if let RecordedEvent::GamepadButton { .. }
+        | RecordedEvent::GamepadAxis { .. }
+        | RecordedEvent::GamepadButtonValue { .. } = self
+        {
+            return true;
+        }
+        false
+    }
+}
+
+/// A recorded event and the update it happened before.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedEvent {
+    /// The number of updates from the start of the recording until the one this event happened before.
+    pub tick: usize,
+    /// The event.
+    pub event: RecordedEvent,
+}
+
+/// Records input events, see the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    start: usize,
+    events: Vec<TimedEvent>,
+}
+
+impl Recorder {
+    fn new(time: &TimeContext) -> Self {
+        Recorder {
+            start: time.ticks(),
+            events: Vec::new(),
+        }
+    }
+
+    // `update` is the value of `ticks` during the update seeing the event.
+    fn record(&mut self, update: usize, event: RecordedEvent) {
+        self.events.push(TimedEvent {
+            tick: update.saturating_sub(self.start),
+            event,
+        });
+    }
+
+    /// Returns the events recorded so far.
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// Writes the recorded events in a compact binary format.
+    #[cfg(feature = "serde")]
+    pub fn write_to(&self, writer: impl io::Write) -> GameResult {
+        bincode::serialize_into(writer, &(FORMAT_VERSION, &self.events))?;
+        Ok(())
+    }
+
+    /// Saves the recorded events to a file in the user directory,
+    /// see [`Filesystem::create`](crate::filesystem::Filesystem::create).
+    #[cfg(feature = "serde")]
+    pub fn save(&self, fs: &impl Has<Filesystem>, path: impl AsRef<Path>) -> GameResult {
+        let file = fs.retrieve().create(path)?;
+        self.write_to(io::BufWriter::new(file))
+    }
+}
+
+/// Plays back recorded input events, see the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Player {
+    start: usize,
+    events: Vec<TimedEvent>,
+    // gamepad events are played back later during an update than the others
+    next_event: usize,
+    next_gamepad_event: usize,
+    played_tick: Option<usize>,
+}
+
+impl Player {
+    /// Creates a player for the given events, sorted by their `tick`.
+    pub fn new(events: Vec<TimedEvent>) -> Self {
+        Player {
+            start: 0,
+            events,
+            next_event: 0,
+            next_gamepad_event: 0,
+            played_tick: None,
+        }
+    }
+
+    /// Reads events written by [`Recorder::write_to`].
+    #[cfg(feature = "serde")]
+    pub fn read_from(reader: impl io::Read) -> GameResult<Self> {
+        let (version, events): (u32, Vec<TimedEvent>) = bincode::deserialize_from(reader)?;
+        if version != FORMAT_VERSION {
+            return Err(GameError::ResourceLoadError(format!(
+                "Input recording has format version {}, expected {}",
+                version, FORMAT_VERSION
+            )));
+        }
+        Ok(Player::new(events))
+    }
+
+    /// Loads events saved by [`Recorder::save`],
+    /// see [`Filesystem::open`](crate::filesystem::Filesystem::open).
+    #[cfg(feature = "serde")]
+    pub fn load(fs: &impl Has<Filesystem>, path: impl AsRef<Path>) -> GameResult<Self> {
+        let file = fs.retrieve().open(path)?;
+        Player::read_from(io::BufReader::new(file))
+    }
+
+    /// Returns the events played back.
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// Returns whether all events have been played back.
+    pub fn is_finished(&self) -> bool {
+        match (self.events.last(), self.played_tick) {
+            (Some(last), Some(played)) => played >= last.tick,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    fn take_due(&mut self, update: usize, gamepad_events: bool) -> Vec<RecordedEvent> {
+        let tick = update.saturating_sub(self.start);
+        let next = if gamepad_events {
+            &mut self.next_gamepad_event
+        } else {
+            &mut self.next_event
+        };
+        let mut due = Vec::new();
+        while let Some(timed) = self.events.get(*next).filter(|timed| timed.tick <= tick) {
+            if timed.event.is_gamepad_event() == gamepad_events {
+                due.push(timed.event.clone());
+            }
+            *next += 1;
+        }
+        self.played_tick = Some(tick);
+        due
+    }
+}
+
+impl From<Recorder> for Player {
+    fn from(recorder: Recorder) -> Self {
+        Player::new(recorder.events)
+    }
+}
+
+/// Records and plays back input, see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct RecordingContext {
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+}
+
+impl RecordingContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts recording input, dropping what was recorded before.
+    pub fn start_recording(&mut self, time: &TimeContext) {
+        self.recorder = Some(Recorder::new(time));
+    }
+
+    /// Returns whether input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Returns what has been recorded so far.
+    pub fn recorder(&self) -> Option<&Recorder> {
+        self.recorder.as_ref()
+    }
+
+    /// Stops recording input and returns what was recorded.
+    pub fn stop_recording(&mut self) -> Option<Recorder> {
+        self.recorder.take()
+    }
+
+    /// Starts playing back input from the next update on. Live input is ignored until it finished.
+    pub fn play(&mut self, mut player: Player, time: &TimeContext) {
+        player.start = time.ticks();
+        self.player = Some(player);
+    }
+
+    /// Returns whether input is being played back.
+    pub fn is_playing(&self) -> bool {
+        matches!(&self.player, Some(player) if !player.is_finished())
+    }
+
+    /// Stops playing back input, which lets live input through again.
+    pub fn stop_playing(&mut self) -> Option<Player> {
+        self.player.take()
+    }
+
+    /// Returns whether a live event should be ignored, since input is being played back.
+    /// If you are writing your own event loop, skip these events instead of processing them.
+    pub fn suppresses(&self, event: &Event<()>) -> bool {
+        if !self.is_playing() {
+            return false;
+        }
+        match event {
+            Event::WindowEvent { event, .. } => matches!(
+                event,
+                WindowEvent::KeyboardInput { .. }
+                    | WindowEvent::ModifiersChanged(_)
+                    | WindowEvent::ReceivedCharacter(_)
+                    | WindowEvent::Ime(_)
+                    | WindowEvent::CursorMoved { .. }
+                    | WindowEvent::MouseInput { .. }
+                    | WindowEvent::MouseWheel { .. }
+                    | WindowEvent::CursorEntered { .. }
+                    | WindowEvent::CursorLeft { .. }
+                    | WindowEvent::Touch(_)
+                    | WindowEvent::Focused(_)
+            ),
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { .. },
+                ..
+            } => true,
+            _ => false,
+        }
+    }
+
+    /// Records a live event of the window with the given id, if input is being recorded.
+    /// If you are writing your own event loop, you need to call this on the events you receive,
+    /// unless they are suppressed. Otherwise this is handled for you.
+    pub fn record_event(&mut self, event: &Event<()>, time: &TimeContext, window_id: WindowId) {
+        if let Some(recorder) = &mut self.recorder {
+            if let Some(recorded) = RecordedEvent::from_event(event, window_id) {
+                // the event is seen by the next update
+                recorder.record(time.ticks() + 1, recorded);
+            }
+        }
+    }
+
+    /// Returns the events played back for the next update, as events of the window with the given id.
+    /// Resizes are returned as `Resized` events, apply them by resizing the window instead of processing them.
+    ///
+    /// If you are writing your own event loop, you need to call this before every update and
+    /// process the events returned like live ones, before calling `TimeContext::tick`.
+    /// Otherwise this is handled for you.
+    pub fn playback_events(
+        &mut self,
+        time: &TimeContext,
+        window_id: WindowId,
+    ) -> Vec<Event<'static, ()>> {
+        match &mut self.player {
+            Some(player) => player
+                .take_due(time.ticks() + 1, false)
+                .iter()
+                .filter_map(|event| event.to_event(window_id))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the gamepad events for this update and when they happened, which are either the live ones,
+    /// recorded if input is being recorded, or the ones played back.
+    ///
+    /// If you are writing your own event loop, you need to call this instead of
+    /// [`GamepadContext::next_event`] every update, after calling `TimeContext::tick`.
+    /// Otherwise this is handled for you.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_events(
+        &mut self,
+        time: &TimeContext,
+        gamepad: &mut GamepadContext,
+    ) -> Vec<(RecordedEvent, Instant)> {
+        let playing = self.is_playing();
+        let mut events = Vec::new();
+        while let Some(GamepadEvent { id, kind, .. }) = gamepad.next_unapplied_event() {
+            let event = match kind {
+                GamepadEventKind::ButtonPressed(button) => RecordedEvent::GamepadButton {
+                    id,
+                    button,
+                    pressed: true,
+                },
+                GamepadEventKind::ButtonReleased(button) => RecordedEvent::GamepadButton {
+                    id,
+                    button,
+                    pressed: false,
+                },
+                GamepadEventKind::ButtonChanged(button, value) => {
+                    RecordedEvent::GamepadButtonValue { id, button, value }
+                }
+                GamepadEventKind::AxisChanged(axis, value) => {
+                    RecordedEvent::GamepadAxis { id, axis, value }
+                }
+                GamepadEventKind::Connected | GamepadEventKind::Disconnected => continue,
+            };
+            if playing {
+                continue;
+            }
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(time.ticks(), event.clone());
+            }
+            if let Some(time) = gamepad.event_time() {
+                events.push((event, time));
+            }
+        }
+        if let Some(player) = &mut self.player {
+            let now = Instant::now();
+            events.extend(
+                player
+                    .take_due(time.ticks(), true)
+                    .into_iter()
+                    .map(|event| (event, now)),
+            );
+        }
+        events
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<bincode::Error> for GameError {
+    fn from(e: bincode::Error) -> GameError {
+        let errstr = format!("Input recording error: {}", e);
+        GameError::ResourceLoadError(errstr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::keyboard::{KeyCode, KeyboardContext};
+    use crate::input::mouse::MouseContext;
+
+    #[allow(unsafe_code)]
+    fn window_id() -> WindowId {
+        unsafe { WindowId::dummy() }
+    }
+
+    #[allow(deprecated)]
+    fn key(keycode: KeyCode, state: ElementState) -> Event<'static, ()> {
+        let input = KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(keycode),
+            modifiers: ModifiersState::empty(),
+        };
+        RecordedEvent::Key(input).to_event(window_id()).unwrap()
+    }
+
+    fn mouse_motion(x: f64, y: f64) -> Event<'static, ()> {
+        RecordedEvent::MouseMotion { x, y }
+            .to_event(window_id())
+            .unwrap()
+    }
+
+    // A small game moving a point with the arrow keys, whose other point follows the mouse.
+    #[derive(Debug, Default, PartialEq)]
+    struct Game {
+        position: (i32, i32),
+        mouse: glam::Vec2,
+    }
+
+    struct Input {
+        keyboard: KeyboardContext,
+        mouse: MouseContext,
+    }
+
+    impl Input {
+        fn new() -> Self {
+            Input {
+                keyboard: KeyboardContext::new(),
+                mouse: MouseContext::new(),
+            }
+        }
+
+        fn process(&mut self, event: &Event<()>) {
+            match event {
+                Event::WindowEvent {
+                    event: WindowEvent::KeyboardInput { input, .. },
+                    ..
+                } => self.keyboard.set_key(
+                    input.virtual_keycode.unwrap(),
+                    input.state == ElementState::Pressed,
+                ),
+                Event::WindowEvent {
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } => self.mouse.handle_move(position.x as f32, position.y as f32),
+                _ => {}
+            }
+        }
+
+        fn update(&mut self, game: &mut Game) {
+            if self.keyboard.is_key_pressed(KeyCode::Right) {
+                game.position.0 += 1;
+            }
+            if self.keyboard.is_key_pressed(KeyCode::Down) {
+                game.position.1 += 1;
+            }
+            game.mouse = self.mouse.position().into();
+            self.keyboard.save_keyboard_state();
+        }
+    }
+
+    #[test]
+    fn record_and_play_back() {
+        // the live input arriving before each update
+        let live = vec![
+            vec![
+                mouse_motion(10., 20.),
+                key(KeyCode::Right, ElementState::Pressed),
+            ],
+            vec![],
+            vec![key(KeyCode::Down, ElementState::Pressed)],
+            vec![
+                mouse_motion(30., 40.),
+                key(KeyCode::Right, ElementState::Released),
+            ],
+            vec![],
+        ];
+
+        let mut time = TimeContext::new();
+        let mut recording = RecordingContext::new();
+        let mut input = Input::new();
+        let mut recorded_game = Game::default();
+        recording.start_recording(&time);
+        for events in &live {
+            for event in events {
+                recording.record_event(event, &time, window_id());
+                input.process(event);
+            }
+            time.tick();
+            input.update(&mut recorded_game);
+        }
+        assert_eq!(recorded_game.position, (3, 3));
+        assert_eq!(recorded_game.mouse, glam::vec2(30., 40.));
+
+        let recorder = recording.stop_recording().unwrap();
+        #[cfg(feature = "serde")]
+        let player = {
+            let mut bytes = Vec::new();
+            recorder.write_to(&mut bytes).unwrap();
+            Player::read_from(bytes.as_slice()).unwrap()
+        };
+        #[cfg(not(feature = "serde"))]
+        let player = Player::new(recorder.events().to_vec());
+
+        // play back later in another game, ignoring the live input
+        let mut time = TimeContext::new();
+        for _ in 0..3 {
+            time.tick();
+        }
+        let mut input = Input::new();
+        let mut played_game = Game::default();
+        recording.play(player, &time);
+        for _ in &live {
+            let stray = key(KeyCode::Down, ElementState::Released);
+            assert_eq!(recording.suppresses(&stray), recording.is_playing());
+            for event in recording.playback_events(&time, window_id()) {
+                input.process(&event);
+            }
+            time.tick();
+            input.update(&mut played_game);
+        }
+        assert_eq!(played_game, recorded_game);
+        assert!(!recording.is_playing());
+    }
+    // The events driving `EventHandler::gamepad_button_down_event`, without any gamepads.
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn mock_gamepad_events() {
+        use crate::input::gamepad_backend::{GamepadDescription, MockBackend};
+
+        let mock = MockBackend::new();
+        let mut gamepad = GamepadContext::with_backend(Box::new(mock.clone()));
+        let id = mock.connect(GamepadDescription::default());
+        mock.press(id, Button::South);
+        let time = TimeContext::new();
+        let mut recording = RecordingContext::new();
+        let events: Vec<_> = recording
+            .gamepad_events(&time, &mut gamepad)
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect();
+        assert_eq!(
+            events,
+            [
+                RecordedEvent::GamepadButton {
+                    id,
+                    button: Button::South,
+                    pressed: true,
+                },
+                RecordedEvent::GamepadButtonValue {
+                    id,
+                    button: Button::South,
+                    value: 1.0,
+                },
+            ]
+        );
+    }
+}
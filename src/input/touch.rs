@@ -0,0 +1,197 @@
+//! Touch utility functions.
+//!
+//! Touch positions are physical window pixels, like mouse positions, see
+//! [`Touch::game_position`] for screen coordinates.
+
+use super::gesture::{Gesture, GestureRecognizer};
+use crate::context::Has;
+use crate::graphics::GraphicsContext;
+pub use winit::event::TouchPhase;
+
+/// A finger touching the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Touch {
+    /// Identifies the finger while it touches the screen, ids may be reused afterwards.
+    pub id: u64,
+    /// Whether the touch started or ended this frame.
+    /// A touch which ended stays in [`TouchContext::touches`] until the end of the frame.
+    pub phase: TouchPhase,
+    /// The current position, in physical pixels.
+    pub position: mint::Point2<f32>,
+    /// The position at the end of the last frame, or where the touch started.
+    pub previous_position: mint::Point2<f32>,
+    /// How hard the screen is pressed, from 0 to 1, on platforms which report it.
+    pub force: Option<f32>,
+}
+
+impl Touch {
+    /// Returns the current position in the screen coordinates of the last frame canvas,
+    /// like [`MouseContext::game_position`](crate::input::mouse::MouseContext::game_position).
+    pub fn game_position(&self, gfx: &impl Has<GraphicsContext>) -> mint::Point2<f32> {
+        gfx.retrieve().window_to_screen_coords(self.position)
+    }
+
+    /// Returns the distance moved since the last frame, in physical pixels.
+    pub fn delta(&self) -> mint::Vector2<f32> {
+        mint::Vector2 {
+            x: self.position.x - self.previous_position.x,
+            y: self.position.y - self.previous_position.y,
+        }
+    }
+}
+
+/// Stores state information for touch input.
+#[derive(Clone, Debug, Default)]
+pub struct TouchContext {
+    touches: Vec<Touch>,
+    // The touch which started while no other one was active.
+    primary: Option<u64>,
+    recognizer: GestureRecognizer,
+    gestures: Vec<Gesture>,
+}
+
+impl TouchContext {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the fingers currently touching the screen, in the order they started,
+    /// and those which stopped this frame.
+    pub fn touches(&self) -> &[Touch] {
+        &self.touches
+    }
+
+    /// Returns the touch with the given id, see [`touches`](Self::touches).
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches.iter().find(|touch| touch.id == id)
+    }
+
+    /// Returns whether the touch started while no other finger was touching the screen,
+    /// which makes it the one emulating the mouse by default.
+    pub fn is_primary(&self, id: u64) -> bool {
+        self.primary == Some(id)
+    }
+
+    /// Returns the gestures recognized this frame, see [`gesture`](crate::input::gesture).
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    /// Returns the recognizer of gestures, for changing its thresholds.
+    pub fn gesture_recognizer_mut(&mut self) -> &mut GestureRecognizer {
+        &mut self.recognizer
+    }
+
+    /// Recognizes the gestures of this frame. If you are writing your own event loop
+    /// you need to call this before every update in order to use `gestures`.
+    /// Otherwise this is handled for you.
+    pub fn recognize_gestures(&mut self) -> &[Gesture] {
+        self.gestures = self.recognizer.update(&self.touches);
+        &self.gestures
+    }
+
+    /// Updates the touch with the given id.
+    /// Really useful only if you are writing your own event loop
+    pub fn handle_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: mint::Point2<f32>,
+        force: Option<f32>,
+    ) {
+        match self.touches.iter_mut().find(|touch| touch.id == id) {
+            Some(touch) => {
+                // keep that the touch started this frame
+                if touch.phase != TouchPhase::Started || phase != TouchPhase::Moved {
+                    touch.phase = phase;
+                }
+                touch.position = position;
+                touch.force = force;
+            }
+            None => {
+                if !self.touches.iter().any(|touch| !has_ended(touch)) {
+                    self.primary = Some(id);
+                }
+                self.touches.push(Touch {
+                    id,
+                    phase,
+                    position,
+                    previous_position: position,
+                    force,
+                })
+            }
+        }
+    }
+
+    /// Removes the touches which ended and remembers the positions of the others for
+    /// [`Touch::previous_position`]. If you are writing your own event loop you need
+    /// to call this at the end of every update. Otherwise this is handled for you.
+    pub fn save_touch_state(&mut self) {
+        if let Some(primary) = self.primary {
+            if !self
+                .touches
+                .iter()
+                .any(|touch| touch.id == primary && !has_ended(touch))
+            {
+                self.primary = None;
+            }
+        }
+        self.touches.retain(|touch| !has_ended(touch));
+        for touch in &mut self.touches {
+            touch.phase = TouchPhase::Moved;
+            touch.previous_position = touch.position;
+        }
+    }
+
+    // Touches don't end while the window is unfocused, so they'd get stuck.
+    pub(crate) fn cancel_all(&mut self) {
+        for touch in &mut self.touches {
+            touch.phase = TouchPhase::Cancelled;
+        }
+    }
+}
+
+fn has_ended(touch: &Touch) -> bool {
+    matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> mint::Point2<f32> {
+        mint::Point2 { x, y }
+    }
+
+    #[test]
+    fn touch_tracking() {
+        let mut touch = TouchContext::new();
+        touch.handle_touch(3, TouchPhase::Started, point(10., 10.), None);
+        touch.handle_touch(3, TouchPhase::Moved, point(12., 10.), None);
+        touch.handle_touch(5, TouchPhase::Started, point(50., 50.), Some(0.5));
+        assert_eq!(touch.touches().len(), 2);
+        assert_eq!(touch.touch(3).unwrap().phase, TouchPhase::Started);
+        assert_eq!(
+            touch.touch(3).unwrap().delta(),
+            mint::Vector2 { x: 2., y: 0. }
+        );
+        assert!(touch.is_primary(3));
+
+        touch.save_touch_state();
+        touch.handle_touch(3, TouchPhase::Ended, point(14., 10.), None);
+        assert_eq!(
+            touch.touch(3).unwrap().delta(),
+            mint::Vector2 { x: 2., y: 0. }
+        );
+        // still there for the event of it ending
+        assert!(touch.is_primary(3));
+        touch.save_touch_state();
+        assert!(touch.touch(3).is_none());
+        assert!(!touch.is_primary(5));
+        assert_eq!(touch.touch(5).unwrap().force, Some(0.5));
+
+        touch.cancel_all();
+        touch.save_touch_state();
+        assert!(touch.touches().is_empty());
+    }
+}
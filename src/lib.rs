@@ -193,19 +193,37 @@
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "egui")]
+pub extern crate egui;
 pub extern crate glam;
+/// The `image` crate, of the version ggez decodes images with, e.g. for
+/// [`Image::from_dynamic_image`](crate::graphics::Image::from_dynamic_image).
+pub extern crate image as image_crate;
+#[cfg(feature = "embed")]
+pub extern crate include_dir;
 pub extern crate mint;
+/// The glue between winit and Android's native activity, of the version winit uses, for
+/// starting the game with `#[ggez::ndk_glue::main(ndk_glue = "ggez::ndk_glue")]`.
+#[cfg(target_os = "android")]
+pub extern crate ndk_glue;
+pub extern crate raw_window_handle;
 
 pub mod audio;
 pub mod conf;
 pub mod context;
+pub mod debug;
+mod dialog;
 pub mod error;
 pub mod event;
 pub mod filesystem;
 pub mod graphics;
 pub mod input;
+pub mod loading;
+pub mod scene;
+pub mod test;
 pub mod timer;
 mod vfs;
 
 pub use crate::context::{winit, Context, ContextBuilder};
+pub use crate::dialog::show_error_dialog;
 pub use crate::error::*;
@@ -0,0 +1,432 @@
+//! Loading assets on other threads, e.g. while a loading screen animates.
+//!
+//! An [`AssetLoader`] can be sent to any thread, and does the part of loading which doesn't
+//! need the `Context`: reading files, and decoding images and sounds. What it prepares is made
+//! into assets on the main thread, which for images is only uploading them to the GPU with
+//! [`Image::from_prepared`]. A [`LoadingBatch`] does all that on threads of its own, with the
+//! progress to show on a loading screen:
+//!
+//! ```rust,no_run
+//! # use ggez::{Context, GameResult};
+//! # use ggez::loading::{AssetLoader, LoadingBatch};
+//! # fn t(ctx: &mut Context) -> GameResult {
+//! let mut batch = LoadingBatch::new(&AssetLoader::new(ctx));
+//! batch.image("/player.png").sound("/music.ogg").bytes("/level1.txt");
+//! // and every frame, until it's done:
+//! if let Some(assets) = batch.take(ctx) {
+//!     let player = assets?.image("/player.png");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Errors name the path of the asset which failed to load.
+//!
+//! Files which aren't assets can be read on the same threads with
+//! [`Filesystem::read_async`](crate::filesystem::Filesystem::read_async).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{mpsc, OnceLock};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+#[cfg(feature = "audio")]
+use crate::audio::SoundData;
+use crate::context::Has;
+use crate::error::{GameError, GameResult};
+use crate::filesystem::{Filesystem, InternalClone};
+use crate::graphics::{GraphicsContext, Image, PreparedImage};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs `job` on one of the threads loading assets, which are started the first time.
+///
+/// Browsers don't give wasm threads, so there the job is done right away instead.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn run(job: Job) {
+    job()
+}
+
+/// Runs `job` on one of the threads loading assets, which are started the first time.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run(job: Job) {
+    static JOBS: OnceLock<Mutex<mpsc::Sender<Job>>> = OnceLock::new();
+    let jobs = JOBS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let threads = thread::available_parallelism().map_or(2, |n| n.get().min(4));
+        for i in 0..threads {
+            let receiver = receiver.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("ggez loading {}", i))
+                .spawn(move || loop {
+                    let job = receiver
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => return,
+                    }
+                });
+            if let Err(e) = spawned {
+                warn!("Could not start a thread to load assets on: {}", e);
+            }
+        }
+        Mutex::new(sender)
+    });
+    // the threads only stop when the sender is dropped, which it never is
+    let _ = jobs
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .send(job);
+}
+
+// Adds the path to errors which don't name it already.
+fn at_path(path: &Path, e: GameError) -> GameError {
+    match e {
+        GameError::ResourceNotFound(..) => e,
+        e => e.context(format!("loading {:?}", path)),
+    }
+}
+
+/// Loads what doesn't need the `Context` of assets, on any thread, made with
+/// [`AssetLoader::new`].
+pub struct AssetLoader {
+    fs: Filesystem,
+}
+
+impl AssetLoader {
+    /// Makes a loader reading from the filesystem of the context.
+    pub fn new(fs: &impl Has<Filesystem>) -> Self {
+        AssetLoader {
+            fs: InternalClone::clone(fs.retrieve()),
+        }
+    }
+
+    /// Reads the whole file at `path`.
+    pub fn bytes(&self, path: impl AsRef<Path>) -> GameResult<Vec<u8>> {
+        let path = path.as_ref();
+        self.fs.read_file(path).map_err(|e| at_path(path, e))
+    }
+
+    /// Reads and decodes the image at `path`, which only needs uploading with
+    /// [`Image::from_prepared`].
+    pub fn image(&self, path: impl AsRef<Path>) -> GameResult<PreparedImage> {
+        let path = path.as_ref();
+        PreparedImage::from_bytes(&self.bytes(path)?).map_err(|e| at_path(path, e))
+    }
+
+    /// Reads the sound at `path`, checking that it decodes.
+    #[cfg(feature = "audio")]
+    pub fn sound(&self, path: impl AsRef<Path>) -> GameResult<SoundData> {
+        let path = path.as_ref();
+        let data = SoundData::from(self.bytes(path)?);
+        data.check(&format!("the audio file {:?}", path))?;
+        Ok(data)
+    }
+}
+
+impl Clone for AssetLoader {
+    fn clone(&self) -> Self {
+        AssetLoader {
+            fs: InternalClone::clone(&self.fs),
+        }
+    }
+}
+
+impl fmt::Debug for AssetLoader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AssetLoader").finish()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Kind {
+    Image,
+    #[cfg(feature = "audio")]
+    Sound,
+    Bytes,
+}
+
+enum Prepared {
+    Image(PreparedImage),
+    #[cfg(feature = "audio")]
+    Sound(SoundData),
+    Bytes(Vec<u8>),
+}
+
+struct Item {
+    path: PathBuf,
+    kind: Kind,
+    result: Mutex<Option<GameResult<Prepared>>>,
+    started: AtomicBool,
+    done: AtomicBool,
+}
+
+#[derive(Default)]
+struct Progress {
+    done: AtomicUsize,
+    // locked to wait on `loaded`
+    lock: Mutex<()>,
+    loaded: Condvar,
+    // set when the batch is dropped, to skip what didn't start loading
+    cancelled: AtomicBool,
+}
+
+/// Assets loading on other threads, with the progress of them, e.g. for a loading screen,
+/// which stop loading when dropped.
+///
+/// Assets are added with [`image()`](#method.image), [`sound()`](#method.sound) and
+/// [`bytes()`](#method.bytes), and start loading right away. Once all of them are done,
+/// [`take()`](#method.take) makes them into [`Assets`].
+pub struct LoadingBatch {
+    loader: AssetLoader,
+    items: Vec<Arc<Item>>,
+    progress: Arc<Progress>,
+}
+
+impl LoadingBatch {
+    /// Makes a batch loading with `loader`.
+    pub fn new(loader: &AssetLoader) -> Self {
+        LoadingBatch {
+            loader: loader.clone(),
+            items: Vec::new(),
+            progress: Arc::default(),
+        }
+    }
+
+    /// Starts loading the image at `path`.
+    pub fn image(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.start(path.as_ref(), Kind::Image)
+    }
+
+    /// Starts loading the sound at `path`.
+    #[cfg(feature = "audio")]
+    pub fn sound(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.start(path.as_ref(), Kind::Sound)
+    }
+
+    /// Starts reading the file at `path`, e.g. a level to parse.
+    pub fn bytes(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        self.start(path.as_ref(), Kind::Bytes)
+    }
+
+    fn start(&mut self, path: &Path, kind: Kind) -> &mut Self {
+        let item = Arc::new(Item {
+            path: path.to_owned(),
+            kind,
+            result: Mutex::new(None),
+            started: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+        });
+        let loader = self.loader.clone();
+        let progress = self.progress.clone();
+        let loading = item.clone();
+        run(Box::new(move || {
+            if progress.cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            loading.started.store(true, Ordering::Relaxed);
+            let path = &loading.path;
+            let result = match loading.kind {
+                Kind::Image => loader.image(path).map(Prepared::Image),
+                #[cfg(feature = "audio")]
+                Kind::Sound => loader.sound(path).map(Prepared::Sound),
+                Kind::Bytes => loader.bytes(path).map(Prepared::Bytes),
+            };
+            *loading
+                .result
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(result);
+            loading.done.store(true, Ordering::Release);
+            // counted while locked, so a wait can't miss it
+            let lock = progress.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            let _ = progress.done.fetch_add(1, Ordering::AcqRel);
+            drop(lock);
+            progress.loaded.notify_all();
+        }));
+        self.items.push(item);
+        self
+    }
+
+    /// Returns how many assets there are to load.
+    pub fn total(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns how many of the assets finished loading, or failed to.
+    pub fn loaded(&self) -> usize {
+        self.progress.done.load(Ordering::Acquire)
+    }
+
+    /// Returns how much of the assets finished loading, from 0 to 1, which is 1 if there are
+    /// none.
+    pub fn progress(&self) -> f32 {
+        if self.items.is_empty() {
+            1.0
+        } else {
+            self.loaded() as f32 / self.items.len() as f32
+        }
+    }
+
+    /// Returns whether all the assets finished loading, or failed to.
+    pub fn is_done(&self) -> bool {
+        self.loaded() == self.items.len()
+    }
+
+    /// Returns the path of an asset loading now, e.g. to show under a loading bar, or `None`
+    /// if none is.
+    pub fn current(&self) -> Option<&Path> {
+        self.items
+            .iter()
+            .find(|item| item.started.load(Ordering::Relaxed) && !item.done.load(Ordering::Acquire))
+            .map(|item| item.path.as_path())
+    }
+
+    /// Returns the assets once all of them are done, uploading the images, or the error of the
+    /// first of them which failed, after which the batch is empty. Returns `None` while any is
+    /// loading.
+    pub fn take(&mut self, gfx: &impl Has<GraphicsContext>) -> Option<GameResult<Assets>> {
+        if !self.is_done() {
+            return None;
+        }
+        let gfx = gfx.retrieve();
+        let mut assets = Assets::default();
+        let mut error = None;
+        for item in std::mem::take(&mut self.items) {
+            let result = item
+                .result
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            match result {
+                Some(Ok(Prepared::Image(prepared))) => {
+                    let _ = assets
+                        .images
+                        .insert(item.path.clone(), Image::from_prepared(gfx, &prepared));
+                }
+                #[cfg(feature = "audio")]
+                Some(Ok(Prepared::Sound(data))) => {
+                    let _ = assets.sounds.insert(item.path.clone(), data);
+                }
+                Some(Ok(Prepared::Bytes(bytes))) => {
+                    let _ = assets.bytes.insert(item.path.clone(), bytes);
+                }
+                Some(Err(e)) => {
+                    let _ = error.get_or_insert(e);
+                }
+                None => (),
+            }
+        }
+        self.progress = Arc::default();
+        Some(match error {
+            Some(e) => Err(e),
+            None => Ok(assets),
+        })
+    }
+
+    /// Waits for all the assets to load, returning them as [`take()`](#method.take) does.
+    pub fn wait(mut self, gfx: &impl Has<GraphicsContext>) -> GameResult<Assets> {
+        let mut lock = self
+            .progress
+            .lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while !self.is_done() {
+            lock = self
+                .progress
+                .loaded
+                .wait(lock)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        drop(lock);
+        self.take(gfx).unwrap(/* done */)
+    }
+}
+
+impl Drop for LoadingBatch {
+    fn drop(&mut self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for LoadingBatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoadingBatch")
+            .field("total", &self.total())
+            .field("loaded", &self.loaded())
+            .finish()
+    }
+}
+
+/// The assets loaded by a [`LoadingBatch`], by the paths they were loaded from.
+#[derive(Debug, Default)]
+pub struct Assets {
+    images: HashMap<PathBuf, Image>,
+    #[cfg(feature = "audio")]
+    sounds: HashMap<PathBuf, SoundData>,
+    bytes: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl Assets {
+    /// Returns the image loaded from `path`.
+    pub fn image(&self, path: impl AsRef<Path>) -> Option<&Image> {
+        self.images.get(path.as_ref())
+    }
+
+    /// Returns the sound loaded from `path`.
+    #[cfg(feature = "audio")]
+    pub fn sound(&self, path: impl AsRef<Path>) -> Option<&SoundData> {
+        self.sounds.get(path.as_ref())
+    }
+
+    /// Returns the contents of the file read from `path`.
+    pub fn bytes(&self, path: impl AsRef<Path>) -> Option<&[u8]> {
+        self.bytes.get(path.as_ref()).map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::headless_context;
+    use crate::ContextBuilder;
+
+    #[test]
+    fn loading() {
+        let ctx = match headless_context(
+            ContextBuilder::new("test", "ggez")
+                .with_conf_file(false)
+                .add_resource_path(concat!(env!("CARGO_MANIFEST_DIR"), "/resources")),
+        ) {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let loader = AssetLoader::new(&ctx);
+        // it can be sent to the threads loading
+        let sent = loader.clone();
+        let prepared = thread::spawn(move || sent.image("/tile.png"))
+            .join()
+            .unwrap();
+        assert!(prepared.unwrap().width() > 0);
+
+        let mut batch = LoadingBatch::new(&loader);
+        let _ = batch.image("/tile.png").bytes("/cube.wgsl");
+        assert_eq!(batch.total(), 2);
+        let assets = batch.wait(&ctx).unwrap();
+        assert!(assets.image("/tile.png").is_some());
+        assert!(assets.bytes("/cube.wgsl").is_some());
+
+        // errors name the asset
+        let mut batch = LoadingBatch::new(&loader);
+        let _ = batch.image("/tile.png").image("/cube.wgsl");
+        let e = batch.wait(&ctx).unwrap_err();
+        assert!(e.to_string().contains("/cube.wgsl"), "{}", e);
+    }
+}
@@ -0,0 +1,473 @@
+//! A stack of scenes, such as a title screen, the game and a pause menu on top of it, which
+//! takes care of going from one to the next.
+//!
+//! Each scene implements [`Scene`], which is much like
+//! [`EventHandler`](crate::event::EventHandler), with the data the whole game shares as `W`, the
+//! world type. A [`SceneStack`] owns the scenes and is the event handler given to
+//! [`event::run()`](crate::event::run), passing events to the scene on top, which goes to
+//! another one by returning a [`SceneSwitch`] from [`Scene::update`].
+//!
+//! ```rust,no_run
+//! use ggez::graphics::Canvas;
+//! use ggez::scene::{Scene, SceneStack, SceneSwitch};
+//! use ggez::{event, Context, GameResult};
+//!
+//! struct World {
+//!     score: u32,
+//! }
+//!
+//! struct Title;
+//!
+//! impl Scene<World> for Title {
+//!     fn update(&mut self, ctx: &mut Context, world: &mut World) -> GameResult<SceneSwitch<World>> {
+//!         Ok(SceneSwitch::None)
+//!     }
+//!
+//!     fn draw(&mut self, ctx: &mut Context, world: &mut World, canvas: &mut Canvas) -> GameResult {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! fn main() -> GameResult {
+//!     let (mut ctx, event_loop) = ggez::ContextBuilder::new("scenes", "ggez").build()?;
+//!     let stack = SceneStack::new(&mut ctx, World { score: 0 }, Title)?;
+//!     event::run(ctx, event_loop, stack)
+//! }
+//! ```
+
+use std::fmt;
+
+#[cfg(feature = "gamepad")]
+use crate::event::{Axis, Button, GamepadId};
+use crate::event::{EventHandler, MouseButton};
+use crate::graphics::{Canvas, Color};
+use crate::input::keyboard::KeyInput;
+use crate::{Context, GameResult};
+
+/// Where to go from a scene, returned from [`Scene::update`].
+#[derive(Default)]
+pub enum SceneSwitch<W> {
+    /// Stays in the scene.
+    #[default]
+    None,
+    /// Puts a scene on top of this one, which goes on once that one is popped.
+    Push(Box<dyn Scene<W>>),
+    /// Removes this scene, going back to the one under it, or quitting if there is none.
+    Pop,
+    /// Removes this scene and puts another one in its place.
+    Replace(Box<dyn Scene<W>>),
+}
+
+impl<W> SceneSwitch<W> {
+    /// Makes a [`SceneSwitch::Push`] of `scene`.
+    pub fn push(scene: impl Scene<W> + 'static) -> Self {
+        SceneSwitch::Push(Box::new(scene))
+    }
+
+    /// Makes a [`SceneSwitch::Replace`] with `scene`.
+    pub fn replace(scene: impl Scene<W> + 'static) -> Self {
+        SceneSwitch::Replace(Box::new(scene))
+    }
+}
+
+impl<W> fmt::Debug for SceneSwitch<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneSwitch::None => write!(f, "None"),
+            SceneSwitch::Push(scene) => write!(f, "Push({:?})", scene.name()),
+            SceneSwitch::Pop => write!(f, "Pop"),
+            SceneSwitch::Replace(scene) => write!(f, "Replace({:?})", scene.name()),
+        }
+    }
+}
+
+/// A scene of the game, in a [`SceneStack`], with the data the whole game shares as `W`.
+///
+/// Only the scene on top gets events, so the ones under it are left as they were until it's
+/// popped. Unlike [`EventHandler`], nothing quits on escape by default.
+pub trait Scene<W> {
+    /// Called every frame while this scene is on top, returning where to go from it.
+    fn update(&mut self, ctx: &mut Context, world: &mut W) -> GameResult<SceneSwitch<W>>;
+
+    /// Called for fixed updates while this scene is on top, see
+    /// [`EventHandler::fixed_update`].
+    fn fixed_update(&mut self, _ctx: &mut Context, _world: &mut W) -> GameResult {
+        Ok(())
+    }
+
+    /// Draws the scene onto `canvas`, which goes to the frame once all the scenes drawn are.
+    fn draw(&mut self, ctx: &mut Context, world: &mut W, canvas: &mut Canvas) -> GameResult;
+
+    /// Returns whether the scenes under this one are drawn too, first, e.g. under a translucent
+    /// pause menu. Only the scene on top is drawn by default.
+    fn draw_previous(&self) -> bool {
+        false
+    }
+
+    /// Called when the scene is put on the stack.
+    fn on_enter(&mut self, _ctx: &mut Context, _world: &mut W) -> GameResult {
+        Ok(())
+    }
+
+    /// Called when the scene is taken off the stack, by a [`SceneSwitch::Pop`] or
+    /// [`SceneSwitch::Replace`].
+    fn on_exit(&mut self, _ctx: &mut Context, _world: &mut W) -> GameResult {
+        Ok(())
+    }
+
+    /// Returns the name of the scene, to tell it apart when debugging.
+    fn name(&self) -> &str {
+        "scene"
+    }
+
+    /// A keyboard button was pressed, see [`EventHandler::key_down_event`].
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// A keyboard button was released, see [`EventHandler::key_up_event`].
+    fn key_up_event(&mut self, _ctx: &mut Context, _world: &mut W, _input: KeyInput) -> GameResult {
+        Ok(())
+    }
+
+    /// A character was typed, see [`EventHandler::text_input_event`].
+    fn text_input_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _character: char,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// A mouse button was pressed, see [`EventHandler::mouse_button_down_event`].
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// A mouse button was released, see [`EventHandler::mouse_button_up_event`].
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// The mouse was moved, see [`EventHandler::mouse_motion_event`].
+    fn mouse_motion_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _x: f32,
+        _y: f32,
+        _dx: f32,
+        _dy: f32,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// The mousewheel was scrolled, see [`EventHandler::mouse_wheel_event`].
+    fn mouse_wheel_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// A gamepad button was pressed, see [`EventHandler::gamepad_button_down_event`].
+    #[cfg(feature = "gamepad")]
+    fn gamepad_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _btn: Button,
+        _id: GamepadId,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// A gamepad button was released, see [`EventHandler::gamepad_button_up_event`].
+    #[cfg(feature = "gamepad")]
+    fn gamepad_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _btn: Button,
+        _id: GamepadId,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// A gamepad axis moved, see [`EventHandler::gamepad_axis_event`].
+    #[cfg(feature = "gamepad")]
+    fn gamepad_axis_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _axis: Axis,
+        _value: f32,
+        _id: GamepadId,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    /// The window gained or lost focus, see [`EventHandler::focus_event`].
+    fn focus_event(&mut self, _ctx: &mut Context, _world: &mut W, _gained: bool) -> GameResult {
+        Ok(())
+    }
+
+    /// The window was resized, see [`EventHandler::resize_event`].
+    fn resize_event(
+        &mut self,
+        _ctx: &mut Context,
+        _world: &mut W,
+        _width: f32,
+        _height: f32,
+    ) -> GameResult {
+        Ok(())
+    }
+}
+
+/// The scenes of the game, with the one on top getting the events, and the world they share.
+///
+/// It's the [`EventHandler`] given to [`event::run()`](crate::event::run), and quits once the
+/// last scene is popped.
+pub struct SceneStack<W> {
+    world: W,
+    scenes: Vec<Box<dyn Scene<W>>>,
+    clear_color: Color,
+}
+
+impl<W> SceneStack<W> {
+    /// Creates a stack with the world and the first scene, which enters right away.
+    pub fn new(ctx: &mut Context, world: W, first: impl Scene<W> + 'static) -> GameResult<Self> {
+        let mut stack = SceneStack {
+            world,
+            scenes: Vec::new(),
+            clear_color: Color::BLACK,
+        };
+        stack.push(ctx, Box::new(first))?;
+        Ok(stack)
+    }
+
+    /// Returns the data the scenes share.
+    pub fn world(&self) -> &W {
+        &self.world
+    }
+
+    /// Returns the data the scenes share, to change.
+    pub fn world_mut(&mut self) -> &mut W {
+        &mut self.world
+    }
+
+    /// Sets the color the frame is cleared to before the scenes are drawn, black by default.
+    pub fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    /// Returns how many scenes there are.
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Returns whether there are no scenes left, after which the game quits.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Returns the name of the scene on top, if there is one.
+    pub fn current(&self) -> Option<&str> {
+        self.scenes.last().map(|scene| scene.name())
+    }
+
+    /// Puts `scene` on top, calling its [`on_enter`](Scene::on_enter).
+    pub fn push(&mut self, ctx: &mut Context, mut scene: Box<dyn Scene<W>>) -> GameResult {
+        scene.on_enter(ctx, &mut self.world)?;
+        self.scenes.push(scene);
+        Ok(())
+    }
+
+    /// Takes the scene on top off, calling its [`on_exit`](Scene::on_exit), and requests
+    /// quitting if it was the last one.
+    pub fn pop(&mut self, ctx: &mut Context) -> GameResult<Option<Box<dyn Scene<W>>>> {
+        let mut scene = match self.scenes.pop() {
+            Some(scene) => scene,
+            None => return Ok(None),
+        };
+        if self.scenes.is_empty() {
+            ctx.request_quit();
+        }
+        scene.on_exit(ctx, &mut self.world)?;
+        Ok(Some(scene))
+    }
+
+    /// Goes where `switch` says, as if the scene on top returned it from
+    /// [`update`](Scene::update).
+    pub fn switch(&mut self, ctx: &mut Context, switch: SceneSwitch<W>) -> GameResult {
+        match switch {
+            SceneSwitch::None => Ok(()),
+            SceneSwitch::Push(scene) => self.push(ctx, scene),
+            SceneSwitch::Pop => self.pop(ctx).map(|_| ()),
+            SceneSwitch::Replace(scene) => {
+                if let Some(mut old) = self.scenes.pop() {
+                    old.on_exit(ctx, &mut self.world)?;
+                }
+                self.push(ctx, scene)
+            }
+        }
+    }
+
+    /// Calls `f` with the scene on top and the world, if there is a scene.
+    fn with_current(
+        &mut self,
+        f: impl FnOnce(&mut dyn Scene<W>, &mut W) -> GameResult,
+    ) -> GameResult {
+        match self.scenes.last_mut() {
+            Some(scene) => f(scene.as_mut(), &mut self.world),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W> fmt::Debug for SceneStack<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = self.scenes.iter().map(|scene| scene.name()).collect();
+        f.debug_struct("SceneStack")
+            .field("scenes", &names)
+            .field("clear_color", &self.clear_color)
+            .finish()
+    }
+}
+
+impl<W> EventHandler for SceneStack<W> {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let switch = match self.scenes.last_mut() {
+            Some(scene) => scene.update(ctx, &mut self.world)?,
+            None => SceneSwitch::None,
+        };
+        self.switch(ctx, switch)
+    }
+
+    fn fixed_update(&mut self, ctx: &mut Context) -> GameResult {
+        self.with_current(|scene, world| scene.fixed_update(ctx, world))
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = Canvas::from_frame(ctx, self.clear_color);
+        // from the lowest scene seen, through the ones drawing those under them
+        let mut first = self.scenes.len().saturating_sub(1);
+        while first > 0 && self.scenes[first].draw_previous() {
+            first -= 1;
+        }
+        for scene in self.scenes.iter_mut().skip(first) {
+            scene.draw(ctx, &mut self.world, &mut canvas)?;
+        }
+        canvas.finish(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> GameResult {
+        self.with_current(|scene, world| scene.key_down_event(ctx, world, input, repeated))
+    }
+
+    fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> GameResult {
+        self.with_current(|scene, world| scene.key_up_event(ctx, world, input))
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) -> GameResult {
+        self.with_current(|scene, world| scene.text_input_event(ctx, world, character))
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.with_current(|scene, world| scene.mouse_button_down_event(ctx, world, button, x, y))
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.with_current(|scene, world| scene.mouse_button_up_event(ctx, world, button, x, y))
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult {
+        self.with_current(|scene, world| scene.mouse_motion_event(ctx, world, x, y, dx, dy))
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
+        self.with_current(|scene, world| scene.mouse_wheel_event(ctx, world, x, y))
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.with_current(|scene, world| scene.gamepad_button_down_event(ctx, world, btn, id))
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.with_current(|scene, world| scene.gamepad_button_up_event(ctx, world, btn, id))
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn gamepad_axis_event(
+        &mut self,
+        ctx: &mut Context,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) -> GameResult {
+        self.with_current(|scene, world| scene.gamepad_axis_event(ctx, world, axis, value, id))
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) -> GameResult {
+        self.with_current(|scene, world| scene.focus_event(ctx, world, gained))
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.with_current(|scene, world| scene.resize_event(ctx, world, width, height))
+    }
+}
@@ -0,0 +1,325 @@
+//! Golden-image tests of drawing code, asserting that it still draws the pixels it drew when
+//! its golden image was blessed, in a context made by
+//! [`ContextBuilder::headless`](crate::ContextBuilder::headless):
+//!
+//! ```rust,no_run
+//! use ggez::graphics::{Color, DrawMode, Mesh};
+//! use ggez::test::{assert_image_matches, headless_context, render_to_image, Tolerance};
+//!
+//! #[test]
+//! fn circle() {
+//!     let mut ctx = match headless_context(ggez::ContextBuilder::new("test", "me")) {
+//!         Some(ctx) => ctx,
+//!         None => return,
+//!     };
+//!     let circle =
+//!         Mesh::new_circle(&ctx, DrawMode::fill(), [32.0, 32.0], 20.0, 0.1, Color::WHITE).unwrap();
+//!     let image = render_to_image(&mut ctx, (64, 64), |canvas| canvas.draw(&circle, [0.0, 0.0]))
+//!         .unwrap();
+//!     assert_image_matches(
+//!         &ctx,
+//!         &image,
+//!         "tests/golden/circle.png",
+//!         Tolerance {
+//!             max_diff_per_channel: 2,
+//!             // where the polygon of the circle is off its edge
+//!             max_differing_pixels: 64,
+//!         },
+//!     );
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Golden images are made, or remade after the drawing changed on purpose, by running the tests
+//! with the `GGEZ_BLESS` environment variable set, e.g. `GGEZ_BLESS=1 cargo test`, which writes
+//! what's drawn as the golden images instead of comparing it, to be committed. When an image
+//! doesn't match, what was drawn and a diff of the two are written next to the golden image for
+//! a look, as `circle.actual.png` and `circle.diff.png`.
+//!
+//! # Choosing a tolerance
+//!
+//! GPUs and their drivers don't rasterize alike, so an image blessed on one adapter seldom
+//! matches what another one draws to the bit, which is what [`Tolerance`] is for:
+//!
+//! * Colors, and blending, are computed in floating point and rounded to 8 bits, in sRGB for
+//!   the sRGB images [`render_to_image`] draws to, which adapters round differently, so a
+//!   channel may be off by one or two. A `max_diff_per_channel` of 2 covers that.
+//! * Which pixels are covered along the edges of shapes depends on the rasterizer's precision
+//!   and its rules for the pixels exactly on an edge, and curves are drawn as polygons. The
+//!   pixels along edges which aren't on the pixel grid may differ entirely, so
+//!   `max_differing_pixels` is to be about the number of pixels on those edges, like the
+//!   circumference of a circle, and 0 for shapes on the grid.
+//! * Anti-aliasing with MSAA is up to the adapter's sample pattern, and textures sampled
+//!   linearly between texels, like text drawn off the pixel grid or scaled images, are filtered
+//!   at whatever precision the adapter has. Both are better kept out of golden images, by
+//!   drawing at whole pixels, or allowed for with a larger `max_diff_per_channel`.
+//!
+//! Rather than loosening the tolerance until any adapter passes, CI is better off rendering on
+//! the same adapter every time, e.g. a software one like llvmpipe, and blessing the images
+//! there.
+//!
+//! # Without an adapter
+//!
+//! Where there is no adapter at all, not even a software one, [`headless_context`] fails the
+//! test rather than let it pass without having drawn anything. Setting the [`ALLOW_NO_GPU_VAR`]
+//! environment variable, e.g. `GGEZ_ALLOW_NO_GPU=1 cargo test`, skips those tests instead,
+//! printing that they were skipped.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::context::{Has, HasMut};
+use crate::error::{GameError, GameResult};
+use crate::graphics::{Canvas, Color, GraphicsContext, Image, ImageFormat};
+use crate::{Context, ContextBuilder};
+
+/// The environment variable which, when set, makes [`assert_image_matches`] write the golden
+/// images rather than compare with them.
+pub const BLESS_VAR: &str = "GGEZ_BLESS";
+
+/// The environment variable which, when set, makes [`headless_context`] skip tests where there
+/// is no adapter to draw with, rather than fail them.
+pub const ALLOW_NO_GPU_VAR: &str = "GGEZ_ALLOW_NO_GPU";
+
+/// How different an image may be from its golden image and still match, see the
+/// [module docs](self) for how to choose it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Tolerance {
+    /// How much a channel of a pixel, from 0 to 255, may be off before the pixel differs.
+    pub max_diff_per_channel: u8,
+    /// How many pixels may differ before the image doesn't match.
+    pub max_differing_pixels: usize,
+}
+
+impl Tolerance {
+    /// Every pixel is to be exactly the same.
+    pub const EXACT: Tolerance = Tolerance {
+        max_diff_per_channel: 0,
+        max_differing_pixels: 0,
+    };
+}
+
+impl Default for Tolerance {
+    /// Channels may be off by 2, for the rounding of adapters, but no pixel may differ more.
+    fn default() -> Self {
+        Tolerance {
+            max_diff_per_channel: 2,
+            max_differing_pixels: 0,
+        }
+    }
+}
+
+/// Builds a [headless](ContextBuilder::headless) context with `builder`, for a test to draw in.
+///
+/// Returns `None` if there is no adapter, not even a software one, and the [`ALLOW_NO_GPU_VAR`]
+/// environment variable is set, printing that the test is skipped, for it to return early.
+///
+/// # Panics
+///
+/// If the context can't be built, which without an adapter is only allowed with
+/// [`ALLOW_NO_GPU_VAR`] set, so that tests don't pass without having run.
+pub fn headless_context(builder: ContextBuilder) -> Option<Context> {
+    match builder.headless() {
+        Ok(ctx) => Some(ctx),
+        Err(GameError::GraphicsInitializationError) if env::var_os(ALLOW_NO_GPU_VAR).is_some() => {
+            let thread = std::thread::current();
+            eprintln!(
+                "skipping {}: there is no graphics adapter, and {} is set",
+                thread.name().unwrap_or("the test"),
+                ALLOW_NO_GPU_VAR
+            );
+            None
+        }
+        Err(GameError::GraphicsInitializationError) => panic!(
+            "there is no graphics adapter to test with, not even a software one; \
+             set {}=1 to skip the tests which need one",
+            ALLOW_NO_GPU_VAR
+        ),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Draws with `draw` on a canvas of an sRGB image of `size`, cleared to transparent black, and
+/// returns the image once it's drawn, ending the frame in progress and beginning the next, as
+/// [`Context::advance_frame`](crate::Context::advance_frame) does for the graphics.
+///
+/// It's for a headless context, where the frame isn't shown. What's drawn in it has its own
+/// screen coordinates, from 0 to the size of the image.
+pub fn render_to_image(
+    gfx: &mut impl HasMut<GraphicsContext>,
+    (width, height): (u32, u32),
+    draw: impl FnOnce(&mut Canvas),
+) -> GameResult<Image> {
+    let gfx = gfx.retrieve_mut();
+    let image = Image::new_canvas_image(&*gfx, ImageFormat::Rgba8UnormSrgb, width, height, 1);
+    let mut canvas = Canvas::from_image(&*gfx, image.clone(), Color::new(0.0, 0.0, 0.0, 0.0));
+    draw(&mut canvas);
+    canvas.finish(gfx)?;
+    // what's drawn is done at the end of the frame
+    gfx.end_frame()?;
+    gfx.begin_frame()?;
+    Ok(image)
+}
+
+/// Asserts that `image` matches the golden image at `golden`, a PNG file, relative to the
+/// working directory, which is the crate's own when run by `cargo test`.
+///
+/// # Panics
+///
+/// If the image differs by more than `tolerance`, writing what was drawn and a diff next to the
+/// golden image, where differing pixels are red, those different within the tolerance yellow,
+/// and the same ones dimmed. Also if there is no golden image, or it's a different size, or the
+/// image isn't in an 8-bit RGBA format.
+///
+/// With the [`BLESS_VAR`] environment variable set, `image` is written as the golden image
+/// instead.
+pub fn assert_image_matches(
+    gfx: &impl Has<GraphicsContext>,
+    image: &Image,
+    golden: impl AsRef<Path>,
+    tolerance: Tolerance,
+) {
+    if let Err(e) = image_matches(gfx.retrieve(), image, golden.as_ref(), tolerance) {
+        panic!("{}", e);
+    }
+}
+
+fn image_matches(
+    gfx: &GraphicsContext,
+    image: &Image,
+    golden: &Path,
+    tolerance: Tolerance,
+) -> GameResult {
+    if !matches!(
+        image.format(),
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb
+    ) {
+        return Err(GameError::RenderError(format!(
+            "cannot compare {:?} images with golden images, only 8-bit RGBA ones",
+            image.format()
+        )));
+    }
+    let (width, height) = (image.width(), image.height());
+    let pixels = image.to_pixels(gfx)?;
+    let actual_path = sibling(golden, "actual");
+    let diff_path = sibling(golden, "diff");
+
+    if env::var_os(BLESS_VAR).is_some() {
+        if let Some(dir) = golden.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        save(golden, &pixels, width, height)?;
+        // what was left of a failure before
+        let _ = fs::remove_file(&actual_path);
+        let _ = fs::remove_file(&diff_path);
+        return Ok(());
+    }
+
+    let expected = ::image::open(golden)
+        .map_err(|e| {
+            GameError::RenderError(format!(
+                "cannot read the golden image {:?} ({}); run with {}=1 to make it",
+                golden, e, BLESS_VAR
+            ))
+        })?
+        .into_rgba8();
+    if expected.dimensions() != (width, height) {
+        return Err(GameError::RenderError(format!(
+            "the image is {}x{}, but the golden image {:?} is {}x{}",
+            width,
+            height,
+            golden,
+            expected.width(),
+            expected.height()
+        )));
+    }
+
+    let comparison = compare(expected.as_raw(), &pixels, tolerance);
+    if comparison.differing <= tolerance.max_differing_pixels {
+        return Ok(());
+    }
+    save(&actual_path, &pixels, width, height)?;
+    save(&diff_path, &comparison.diff, width, height)?;
+    Err(GameError::RenderError(format!(
+        "the image doesn't match the golden image {:?}: {} pixels differ by more than {}, \
+         of at most {}, by up to {}; see {:?} and {:?}",
+        golden,
+        comparison.differing,
+        tolerance.max_diff_per_channel,
+        tolerance.max_differing_pixels,
+        comparison.max_diff,
+        actual_path,
+        diff_path
+    )))
+}
+
+// `circle.png` to `circle.{suffix}.png`
+fn sibling(golden: &Path, suffix: &str) -> PathBuf {
+    let stem = golden.file_stem().unwrap_or_default().to_string_lossy();
+    golden.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+fn save(path: &Path, pixels: &[u8], width: u32, height: u32) -> GameResult {
+    ::image::save_buffer(path, pixels, width, height, ::image::ColorType::Rgba8)
+        .map_err(|e| GameError::RenderError(format!("cannot write {:?}: {}", path, e)))
+}
+
+struct Comparison {
+    // the pixels off by more than the tolerance
+    differing: usize,
+    max_diff: u8,
+    diff: Vec<u8>,
+}
+
+fn compare(expected: &[u8], actual: &[u8], tolerance: Tolerance) -> Comparison {
+    let mut comparison = Comparison {
+        differing: 0,
+        max_diff: 0,
+        diff: Vec::with_capacity(expected.len()),
+    };
+    for (expected, actual) in expected.chunks_exact(4).zip(actual.chunks_exact(4)) {
+        let diff = expected
+            .iter()
+            .zip(actual)
+            .map(|(e, a)| e.abs_diff(*a))
+            .max()
+            .unwrap_or(0);
+        comparison.max_diff = comparison.max_diff.max(diff);
+        let pixel = if diff > tolerance.max_diff_per_channel {
+            comparison.differing += 1;
+            [255, 0, 0, 255]
+        } else if diff > 0 {
+            [255, 255, 0, 255]
+        } else {
+            let gray = ((expected[0] as u32 + expected[1] as u32 + expected[2] as u32) / 12) as u8;
+            [gray, gray, gray, 255]
+        };
+        comparison.diff.extend_from_slice(&pixel);
+    }
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tolerance() {
+        let expected = [10, 20, 30, 255, 100, 100, 100, 255, 0, 0, 0, 255];
+        let actual = [10, 20, 30, 255, 102, 99, 100, 255, 0, 40, 0, 255];
+        let exact = compare(&expected, &actual, Tolerance::EXACT);
+        assert_eq!((exact.differing, exact.max_diff), (2, 40));
+        let loose = compare(&expected, &actual, Tolerance::default());
+        assert_eq!(loose.differing, 1);
+        assert_eq!(loose.diff, [5, 5, 5, 255, 255, 255, 0, 255, 255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn siblings() {
+        assert_eq!(
+            sibling(Path::new("tests/golden/circle.png"), "diff"),
+            Path::new("tests/golden/circle.diff.png")
+        );
+    }
+}
@@ -12,9 +12,12 @@
 //! way to cap your displayed framerate.
 //!
 //! For a more detailed tutorial in how to handle frame timings in games,
-//! see <http://gafferongames.com/game-physics/fix-your-timestep/>
+//! see <http://gafferongames.com/game-physics/fix-your-timestep/>, which
+//! [`TimeContext::set_fixed_update_rate`] does for you. To cap the frame rate
+//! whether or not vsync is on, see [`TimeContext::set_target_fps`].
 
-use std::{cmp, convert::TryFrom, f64, thread, time};
+use instant::Instant;
+use std::{cmp, convert::TryFrom, f64, fmt, thread, time};
 
 use crate::Context;
 
@@ -23,113 +26,263 @@ use crate::Context;
 /// N items that have been inserted into it,
 /// overwriting old ones in a round-robin fashion.
 ///
-/// It's not quite a ring buffer 'cause you can't
-/// remove items from it, it just holds the last N
-/// things.
+/// Every item is stored twice, N apart, so that the last ones
+/// are always next to each other, in order, without copying them.
 #[derive(Debug, Clone)]
 struct LogBuffer<T>
 where
     T: Clone,
 {
-    head: usize,
-    size: usize,
-    /// The number of actual samples inserted, used for
-    /// smarter averaging.
-    samples: usize,
+    capacity: usize,
+    // where the next item goes, and how many of the last ones there are
+    next: usize,
+    len: usize,
     contents: Vec<T>,
+    // what the latest item is before there are any
+    init_val: T,
 }
 
 impl<T> LogBuffer<T>
 where
     T: Clone + Copy,
 {
-    fn new(size: usize, init_val: T) -> LogBuffer<T> {
+    fn new(capacity: usize, init_val: T) -> LogBuffer<T> {
+        let capacity = capacity.max(1);
         LogBuffer {
-            head: 0,
-            size,
-            contents: vec![init_val; size],
-            // Never divide by 0
-            samples: 1,
+            capacity,
+            next: 0,
+            len: 0,
+            contents: vec![init_val; 2 * capacity],
+            init_val,
         }
     }
 
     /// Pushes a new item into the `LogBuffer`, overwriting
     /// the oldest item in it.
     fn push(&mut self, item: T) {
-        self.head = (self.head + 1) % self.contents.len();
-        self.contents[self.head] = item;
-        self.size = cmp::min(self.size + 1, self.contents.len());
-        self.samples += 1;
+        self.contents[self.next] = item;
+        self.contents[self.next + self.capacity] = item;
+        self.next = (self.next + 1) % self.capacity;
+        self.len = cmp::min(self.len + 1, self.capacity);
     }
 
-    /// Returns a slice pointing at the contents of the buffer.
-    /// They are in *no particular order*, and if not all the
-    /// slots are filled, the empty slots will be present but
-    /// contain the initial value given to [`new()`](#method.new).
-    ///
-    /// We're only using this to log FPS for a short time,
-    /// so we don't care for the second or so when it's inaccurate.
+    /// Returns the items in the buffer, from the oldest to the latest.
     fn contents(&self) -> &[T] {
-        if self.samples > self.size {
-            &self.contents
-        } else {
-            &self.contents[..self.samples]
-        }
+        let end = self.next + self.capacity;
+        &self.contents[end - self.len..end]
     }
 
     /// Returns the most recent value in the buffer.
     fn latest(&self) -> T {
-        self.contents[self.head]
+        self.contents().last().copied().unwrap_or(self.init_val)
     }
 }
 
+/// The percentiles and the longest of the frame times, see
+/// [`TimeContext::frame_time_stats`]. They're zero before the first frame.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FrameTimeStats {
+    /// The median frame time, which half of the frames are no longer than.
+    pub p50: time::Duration,
+    /// The frame time which 95% of the frames are no longer than.
+    pub p95: time::Duration,
+    /// The frame time which 99% of the frames are no longer than.
+    pub p99: time::Duration,
+    /// The longest frame time.
+    pub max: time::Duration,
+}
+
 /// A structure that contains our time-tracking state.
 #[derive(Debug)]
 pub struct TimeContext {
-    init_instant: time::Instant,
-    last_instant: time::Instant,
+    init_instant: Instant,
+    last_instant: Instant,
     frame_durations: LogBuffer<time::Duration>,
+    // the longest a frame counts as, if they're clamped, and the last one's length as clamped
+    max_frame_dt: Option<time::Duration>,
+    unscaled_delta: time::Duration,
+    // how fast the game's time goes, the length of the last frame in it, and all of it so far
+    time_scale: f32,
+    scaled_delta: time::Duration,
+    scaled_time: time::Duration,
     residual_update_dt: time::Duration,
     frame_count: usize,
+    // the time between fixed updates, if they're on, and the time owed to them
+    fixed_dt: Option<time::Duration>,
+    residual_fixed_dt: time::Duration,
+    max_fixed_updates: u32,
+    catch_up: CatchUpPolicy,
+    catch_up_report: CatchUpReport,
+    // what the scopes timed between the last two ticks
+    profile_report: Vec<ProfileEntry>,
+    // the time between frames to wait for, if capped, and when the last frame ended
+    target_dt: Option<time::Duration>,
+    unfocused_target_dt: Option<time::Duration>,
+    focused: bool,
+    last_frame_end: Instant,
 }
 
-/// How many frames we log update times for.
-const TIME_LOG_FRAMES: usize = 200;
+/// How many frames we log update times for, by default.
+const TIME_LOG_FRAMES: usize = 240;
+
+/// How long before the end of a frame to stop sleeping and spin instead, since sleeping usually
+/// overshoots by about this much.
+const SPIN_TIME: time::Duration = time::Duration::from_millis(2);
 
 impl TimeContext {
     /// Creates a new `TimeContext` and initializes the start to this instant.
     pub fn new() -> TimeContext {
         let initial_dt = time::Duration::from_millis(16);
         TimeContext {
-            init_instant: time::Instant::now(),
-            last_instant: time::Instant::now(),
+            init_instant: Instant::now(),
+            last_instant: Instant::now(),
             frame_durations: LogBuffer::new(TIME_LOG_FRAMES, initial_dt),
+            max_frame_dt: None,
+            unscaled_delta: initial_dt,
+            time_scale: 1.0,
+            scaled_delta: initial_dt,
+            scaled_time: time::Duration::ZERO,
             residual_update_dt: time::Duration::from_secs(0),
             frame_count: 0,
+            fixed_dt: None,
+            residual_fixed_dt: time::Duration::from_secs(0),
+            max_fixed_updates: 8,
+            catch_up: CatchUpPolicy::CapTicks,
+            catch_up_report: CatchUpReport::default(),
+            profile_report: Vec::new(),
+            target_dt: None,
+            unfocused_target_dt: None,
+            focused: true,
+            last_frame_end: Instant::now(),
         }
     }
 
     /// Get the time between the start of the last frame and the current one;
     /// in other words, the length of the last frame.
+    ///
+    /// This is in the game's time, scaled by [`set_time_scale()`](#method.set_time_scale), see
+    /// [`unscaled_delta()`](#method.unscaled_delta) for the actual time.
     pub fn delta(&self) -> time::Duration {
-        self.frame_durations.latest()
+        self.scaled_delta
+    }
+
+    /// Returns the actual length of the last frame, however fast the game's time goes, e.g.
+    /// for menus which should animate at the usual speed during slow motion.
+    ///
+    /// This is clamped to [`max_frame_time()`](#method.max_frame_time), like
+    /// [`delta()`](#method.delta), unlike the [`frame_times()`](#method.frame_times).
+    pub fn unscaled_delta(&self) -> time::Duration {
+        self.unscaled_delta
+    }
+
+    /// Makes frames longer than `max` count as only `max` long, from the next frame on, or
+    /// lifts the limit with `None`, which is how it starts.
+    ///
+    /// After a long hitch, e.g. while a shader compiles or the window is dragged around, the
+    /// game then carries on from about where it was, rather than jumping ahead, or running
+    /// many updates to catch up on the time, which makes the next frame longer still.
+    /// This clamps [`delta()`](#method.delta), and the time owed to
+    /// [`check_update_time()`](#method.check_update_time) and fixed updates. The
+    /// [`frame_times()`](#method.frame_times) are the lengths measured.
+    pub fn set_max_frame_time(&mut self, max: Option<time::Duration>) {
+        self.max_frame_dt = max;
+    }
+
+    /// Returns the longest frames count as, if they're clamped.
+    pub fn max_frame_time(&self) -> Option<time::Duration> {
+        self.max_frame_dt
+    }
+
+    /// Sets how fast the game's time goes, e.g. 0.5 for slow motion, or 0 to freeze it, 1 by
+    /// default. Negative scales are taken as 0.
+    ///
+    /// This scales [`delta()`](#method.delta) and the time owed to
+    /// [`check_update_time()`](#method.check_update_time) and fixed updates, from the next
+    /// frame on, so everything made to run at the frame rate slows down together. The frame
+    /// rate itself, the input and [`time_since_start()`](#method.time_since_start) aren't
+    /// scaled.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        // `max` takes NaN as 0 too
+        self.time_scale = scale.max(0.0);
+    }
+
+    /// Returns how fast the game's time goes, see [`set_time_scale()`](#method.set_time_scale).
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Returns how much of the game's time went by up to the current frame, the sum of
+    /// every [`delta()`](#method.delta) so far.
+    pub fn scaled_time_since_start(&self) -> time::Duration {
+        self.scaled_time
     }
 
-    /// Gets the average time of a frame, averaged
-    /// over the last 200 frames.
+    /// Gets the average time of a frame, averaged over the
+    /// [`frame_times()`](#method.frame_times), the last 240 frames by default.
     pub fn average_delta(&self) -> time::Duration {
-        let sum: time::Duration = self.frame_durations.contents().iter().sum();
-        // If our buffer is actually full, divide by its size.
-        // Otherwise divide by the number of samples we've added
-        if self.frame_durations.samples > self.frame_durations.size {
-            sum / u32::try_from(self.frame_durations.size).unwrap()
-        } else {
-            sum / u32::try_from(self.frame_durations.samples).unwrap()
+        let frame_times = self.frame_durations.contents();
+        if frame_times.is_empty() {
+            return self.frame_durations.latest();
+        }
+        let sum: time::Duration = frame_times.iter().sum();
+        sum / u32::try_from(frame_times.len()).unwrap_or(u32::MAX)
+    }
+
+    /// Returns the actual lengths of the last frames, from the oldest to the latest, e.g. to
+    /// graph them, as many as [`set_frame_time_history()`](#method.set_frame_time_history)
+    /// says, 240 by default, fewer at the start.
+    pub fn frame_times(&self) -> &[time::Duration] {
+        self.frame_durations.contents()
+    }
+
+    /// Sets how many frames [`frame_times()`](#method.frame_times) keeps the lengths of, and
+    /// [`average_delta()`](#method.average_delta) and [`fps()`](#method.fps) are averaged over,
+    /// forgetting the ones so far.
+    pub fn set_frame_time_history(&mut self, frames: usize) {
+        self.frame_durations = LogBuffer::new(frames, self.frame_durations.latest());
+    }
+
+    /// Returns how many frames [`frame_times()`](#method.frame_times) keeps the lengths of.
+    pub fn frame_time_history(&self) -> usize {
+        self.frame_durations.capacity
+    }
+
+    /// Returns the percentiles and the longest of the [`frame_times()`](#method.frame_times),
+    /// which show the occasional long frame an average hides, e.g. to log how the game runs.
+    ///
+    /// This sorts a copy of the frame times, so it's meant to be called every now and then
+    /// rather than every frame.
+    pub fn frame_time_stats(&self) -> FrameTimeStats {
+        let mut sorted = self.frame_durations.contents().to_vec();
+        sorted.sort_unstable();
+        // the nearest rank, the shortest frame time at least `percent` of them are no longer than
+        let percentile = |percent: usize| {
+            let rank = (sorted.len() * percent).div_ceil(100);
+            sorted
+                .get(rank.saturating_sub(1))
+                .copied()
+                .unwrap_or_default()
+        };
+        FrameTimeStats {
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: sorted.last().copied().unwrap_or_default(),
         }
     }
 
-    /// Gets the FPS of the game, averaged over the last
-    /// 200 frames.
+    /// Returns how many of the [`frame_times()`](#method.frame_times) are longer than
+    /// `threshold`, e.g. 50 ms to count the hitches players notice.
+    pub fn spike_count(&self, threshold: time::Duration) -> usize {
+        self.frame_durations
+            .contents()
+            .iter()
+            .filter(|&&dt| dt > threshold)
+            .count()
+    }
+
+    /// Gets the FPS of the game, averaged over the
+    /// [`frame_times()`](#method.frame_times).
     pub fn fps(&self) -> f64 {
         let duration_per_frame = self.average_delta();
         let seconds_per_frame = duration_per_frame.as_secs_f64();
@@ -147,7 +300,7 @@ impl TimeContext {
     /// Returns the time since the game was initialized,
     /// as reported by the system clock.
     pub fn time_since_start(&self) -> time::Duration {
-        time::Instant::now() - self.init_instant
+        Instant::now() - self.init_instant
     }
 
     /// Check whether or not the desired amount of time has elapsed
@@ -176,6 +329,35 @@ impl TimeContext {
         }
     }
 
+    /// Like [`check_update_time()`](#method.check_update_time) called until it returns
+    /// `false`, returning how many updates at `target_fps` fit in the time since the last
+    /// frame, and what's left over from before, although no more than `max_ticks`.
+    ///
+    /// The whole updates beyond those are dropped, so the game slows down rather than falling
+    /// further and further behind when the updates take longer than the time they simulate, and
+    /// the time dropped is returned too, e.g. to log it. What's left over of an update goes on
+    /// to the next frame as usual.
+    ///
+    /// ```rust,no_run
+    /// # fn update(ctx: &mut ggez::Context) {
+    /// let ticks = ctx.time.ticks_this_frame(60, 5);
+    /// for _ in 0..ticks.ticks {
+    ///     // advance the simulation by 1/60 of a second
+    /// }
+    /// if !ticks.dropped.is_zero() {
+    ///     println!("Fell behind by {:?}", ticks.dropped);
+    /// }
+    /// # }
+    /// ```
+    pub fn ticks_this_frame(&mut self, target_fps: u32, max_ticks: u32) -> UpdateTicks {
+        let dt = fps_as_duration(target_fps.max(1));
+        let (ticks, dropped, left_over) = owed_updates(self.residual_update_dt, dt, max_ticks);
+        self.residual_update_dt = left_over;
+        self.catch_up_report.ticks += ticks;
+        self.catch_up_report.dropped += dropped;
+        UpdateTicks { ticks, dropped }
+    }
+
     /// Returns the fractional amount of a frame not consumed
     /// by  [`check_update_time()`](fn.check_update_time.html).
     /// For example, if the desired
@@ -193,6 +375,186 @@ impl TimeContext {
         self.residual_update_dt
     }
 
+    /// Makes [`event::run()`](crate::event::run) call
+    /// [`EventHandler::fixed_update`](crate::event::EventHandler::fixed_update)
+    /// `ticks_per_second` times a second, however fast the game draws, or stops with `None` or 0.
+    ///
+    /// Every frame it's called as many times as fit in the time since the last frame, and what's
+    /// left over goes on to the next frame, see [`blend_factor()`](#method.blend_factor) to
+    /// draw between the last two updates with. [`update()`](crate::event::EventHandler::update)
+    /// is still called once every frame, after the fixed updates. The events of the frame come
+    /// before either, so the input state is the same for all the fixed updates of a frame, and a
+    /// key pressed in a frame without any is just pressed for none of them.
+    pub fn set_fixed_update_rate(&mut self, ticks_per_second: Option<u32>) {
+        self.fixed_dt = ticks_per_second
+            .filter(|&ticks| ticks > 0)
+            .map(fps_as_duration);
+        self.residual_fixed_dt = time::Duration::ZERO;
+    }
+
+    /// Returns how many times a second
+    /// [`EventHandler::fixed_update`](crate::event::EventHandler::fixed_update) is called,
+    /// if it is.
+    pub fn fixed_update_rate(&self) -> Option<u32> {
+        self.fixed_dt
+            .map(|dt| (1.0 / dt.as_secs_f64()).round() as u32)
+    }
+
+    /// Returns the time between fixed updates, to advance the simulation by in every one of
+    /// them, if they're on.
+    pub fn fixed_delta(&self) -> Option<time::Duration> {
+        self.fixed_dt
+    }
+
+    /// Sets how many fixed updates there are in a frame at most, 8 by default, so that when they
+    /// take longer than the time they simulate, the game slows down rather than falling further
+    /// and further behind. The time owed to more is dropped, or stretched with
+    /// [`CatchUpPolicy::SlowDown`].
+    pub fn set_max_fixed_updates(&mut self, max: u32) {
+        self.max_fixed_updates = max.max(1);
+    }
+
+    /// Returns how many fixed updates there are in a frame at most, unless the policy is
+    /// [`CatchUpPolicy::RunAll`].
+    pub fn max_fixed_updates(&self) -> u32 {
+        self.max_fixed_updates
+    }
+
+    /// Sets what happens when more fixed updates are owed in a frame than
+    /// [`max_fixed_updates()`](#method.max_fixed_updates), as the game can't keep up, which is
+    /// [`CatchUpPolicy::CapTicks`] by default.
+    pub fn set_catch_up_policy(&mut self, policy: CatchUpPolicy) {
+        self.catch_up = policy;
+    }
+
+    /// Returns what happens when the game can't keep up with the fixed updates.
+    pub fn catch_up_policy(&self) -> CatchUpPolicy {
+        self.catch_up
+    }
+
+    /// Returns how the updates caught up with the time in this frame, e.g. to show that the game
+    /// is running slow: the fixed updates run, and the time dropped or stretched at the start of
+    /// the frame since there would have been too many. Calls to
+    /// [`ticks_this_frame()`](#method.ticks_this_frame) in the frame add to it too.
+    pub fn catch_up_report(&self) -> CatchUpReport {
+        self.catch_up_report
+    }
+
+    /// Returns the time owed to fixed updates which was dropped at the start of this frame as
+    /// there would have been more than [`max_fixed_updates()`](#method.max_fixed_updates),
+    /// or zero if none was, e.g. to log it.
+    pub fn dropped_fixed_time(&self) -> time::Duration {
+        self.catch_up_report.dropped
+    }
+
+    /// Returns how far the time is between the last fixed update and the next one, from 0 to 1,
+    /// e.g. to draw objects at `previous + (current - previous) * blend_factor` so they move
+    /// smoothly whatever the frame rate, or 0 if there are no fixed updates.
+    pub fn blend_factor(&self) -> f32 {
+        match self.fixed_dt {
+            Some(dt) => (self.residual_fixed_dt.as_secs_f64() / dt.as_secs_f64()).min(1.0) as f32,
+            None => 0.0,
+        }
+    }
+
+    /// Returns whether the next fixed update is due, counting it as done if it is.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](../event/fn.run.html) calls it until it returns `false` every frame.
+    pub fn take_fixed_update(&mut self) -> bool {
+        match self.fixed_dt {
+            Some(dt) if self.residual_fixed_dt >= dt => {
+                self.residual_fixed_dt -= dt;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Caps the frame rate of [`event::run()`](crate::event::run) at `fps`, or lifts the cap
+    /// with `None` or 0, which is how it starts.
+    ///
+    /// After each frame is drawn, it waits until it's time for the next one, sleeping most of
+    /// the way and spinning the last couple of milliseconds to get the timing right. With vsync
+    /// on too, whichever is slower wins. It can be changed at any time, e.g. from an options
+    /// menu, and [`fps()`](#method.fps) shows the capped rate.
+    pub fn set_target_fps(&mut self, fps: Option<u32>) {
+        self.target_dt = fps.filter(|&fps| fps > 0).map(fps_as_duration);
+    }
+
+    /// Returns the frame rate [`event::run()`](crate::event::run) is capped at, if it is.
+    pub fn target_fps(&self) -> Option<u32> {
+        self.target_dt
+            .map(|dt| (1.0 / dt.as_secs_f64()).round() as u32)
+    }
+
+    /// Caps the frame rate at `fps` instead while the window isn't focused, e.g. 10 to not use
+    /// much of the computer in the background, or uses the same cap as when focused with `None`
+    /// or 0, which is how it starts.
+    pub fn set_unfocused_target_fps(&mut self, fps: Option<u32>) {
+        self.unfocused_target_dt = fps.filter(|&fps| fps > 0).map(fps_as_duration);
+    }
+
+    /// Returns the frame rate the game is capped at while the window isn't focused, if it's
+    /// different from [`target_fps()`](#method.target_fps).
+    pub fn unfocused_target_fps(&self) -> Option<u32> {
+        self.unfocused_target_dt
+            .map(|dt| (1.0 / dt.as_secs_f64()).round() as u32)
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub(crate) fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    // Drops the time owed to updates while the game was paused, whichever of them it was.
+    pub(crate) fn skip_paused_time(&mut self) {
+        self.last_instant = Instant::now();
+        self.residual_update_dt = time::Duration::ZERO;
+        self.residual_fixed_dt = time::Duration::ZERO;
+    }
+
+    /// Waits until it's time for the next frame, as capped by
+    /// [`set_target_fps()`](#method.set_target_fps), or returns right away if it isn't.
+    ///
+    /// It's usually not necessary to call this function yourself,
+    /// [`event::run()`](../event/fn.run.html) calls it after every frame is drawn.
+    /// You only need to call this function if you're writing your
+    /// own custom event loop.
+    pub fn wait_for_next_frame(&mut self) {
+        let dt = match self.frame_cap() {
+            Some(dt) => dt,
+            None => {
+                self.last_frame_end = Instant::now();
+                return;
+            }
+        };
+        let deadline = self.last_frame_end + dt;
+        // the browser's animation frames can't be waited in, they pace the frames themselves
+        if cfg!(target_arch = "wasm32") {
+            self.last_frame_end = Instant::now();
+            return;
+        }
+        if let Some(sleep) = sleep_time(Instant::now(), deadline) {
+            thread::sleep(sleep);
+        }
+        while Instant::now() < deadline {
+            thread::yield_now();
+        }
+        self.last_frame_end = frame_end(deadline, Instant::now(), dt);
+    }
+
+    // The shortest time between frames, from the cap which applies with the current focus.
+    fn frame_cap(&self) -> Option<time::Duration> {
+        match self.unfocused_target_dt {
+            Some(dt) if !self.focused => Some(dt),
+            _ => self.target_dt,
+        }
+    }
+
     /// Update the state of the `TimeContext` to record that
     /// another frame has taken place.  Necessary for the FPS
     /// tracking and [`check_update_time()`](fn.check_update_time.html)
@@ -203,16 +565,155 @@ impl TimeContext {
     /// You only need to call this function if you're writing your
     /// own custom event loop.
     pub fn tick(&mut self) {
-        let now = time::Instant::now();
+        #[cfg(feature = "profiling")]
+        take_profile(&mut self.profile_report);
+
+        let now = Instant::now();
         let time_since_last = now - self.last_instant;
         self.frame_durations.push(time_since_last);
         self.last_instant = now;
         self.frame_count += 1;
+        self.advance(time_since_last);
+    }
+
+    // Moves the game's time on by the time since the last frame.
+    fn advance(&mut self, time_since_last: time::Duration) {
+        self.unscaled_delta = match self.max_frame_dt {
+            Some(max) => time_since_last.min(max),
+            None => time_since_last,
+        };
+        self.catch_up_report = CatchUpReport::default();
+        let mut dt = scale(self.unscaled_delta, self.time_scale);
+        if let (Some(fixed_dt), CatchUpPolicy::SlowDown) = (self.fixed_dt, self.catch_up) {
+            // the time beyond the most updates doesn't pass, for the whole game
+            let (_, beyond, _) = owed_updates(
+                self.residual_fixed_dt + dt,
+                fixed_dt,
+                self.max_fixed_updates,
+            );
+            dt -= beyond;
+            self.catch_up_report.stretched = beyond;
+        }
+        self.scaled_delta = dt;
+        self.scaled_time += self.scaled_delta;
+        self.residual_update_dt += self.scaled_delta;
+        self.owe_fixed_updates(self.scaled_delta);
+    }
+
+    /// Returns how long each [`scope()`] took in total, and how many times it was entered, from
+    /// the start of the last frame to the start of this one, in the order they were first
+    /// entered. This includes the engine's own phases, `"events"`, `"fixed_update"`,
+    /// `"update"`, `"draw"`, `"canvas finish"` and `"present"`.
+    ///
+    /// There are none without the `profiling` feature.
+    pub fn profile_report(&self) -> &[ProfileEntry] {
+        &self.profile_report
+    }
+
+    fn owe_fixed_updates(&mut self, time_since_last: time::Duration) {
+        if let Some(dt) = self.fixed_dt {
+            let max = match self.catch_up {
+                CatchUpPolicy::RunAll => u32::MAX,
+                _ => self.max_fixed_updates,
+            };
+            let (ticks, dropped, left_over) =
+                owed_updates(self.residual_fixed_dt + time_since_last, dt, max);
+            // taken one at a time by `take_fixed_update`
+            self.residual_fixed_dt = dt * ticks + left_over;
+            self.catch_up_report.ticks = ticks;
+            self.catch_up_report.dropped = dropped;
+        }
+    }
+}
+
+/// What happens when more fixed updates are owed in a frame than
+/// [`TimeContext::max_fixed_updates`], as the game can't keep up with them, e.g. on slow
+/// hardware, see [`TimeContext::set_catch_up_policy`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CatchUpPolicy {
+    /// All of them run, however many there are, which may make the frames longer and longer.
+    RunAll,
+    /// No more than [`TimeContext::max_fixed_updates`] run, and the time owed to more is
+    /// dropped, so the fixed updates slow down but the rest of the game's time, like
+    /// [`TimeContext::delta`], doesn't.
+    CapTicks,
+    /// No more than [`TimeContext::max_fixed_updates`] run, and the time owed to more doesn't
+    /// pass for the whole game, stretching it: [`TimeContext::delta`] and
+    /// [`TimeContext::scaled_time_since_start`] leave it out too, so everything slows down
+    /// together, as in a strategy game falling behind.
+    SlowDown,
+}
+
+/// How the updates caught up with the time in a frame, see [`TimeContext::catch_up_report`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CatchUpReport {
+    /// How many updates were run.
+    pub ticks: u32,
+    /// The time owed to more updates than there may be, which was dropped.
+    pub dropped: time::Duration,
+    /// The time owed to more updates than there may be which didn't pass for the game, with
+    /// [`CatchUpPolicy::SlowDown`].
+    pub stretched: time::Duration,
+}
+
+impl CatchUpReport {
+    /// Returns whether the updates fell behind the time in the frame, dropping or stretching
+    /// some of it, e.g. to show that the game is running slow.
+    pub fn fell_behind(&self) -> bool {
+        !self.dropped.is_zero() || !self.stretched.is_zero()
+    }
+}
+
+/// How many updates to run this frame, see [`TimeContext::ticks_this_frame`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct UpdateTicks {
+    /// How many updates to run.
+    pub ticks: u32,
+    /// The time owed to the updates beyond the most there may be, which was dropped.
+    pub dropped: time::Duration,
+}
+
+// Splits the time owed to updates `dt` apart into how many of them to run, at most `max`, the
+// time of the whole updates beyond those, which is dropped, and what's left over of an update.
+fn owed_updates(
+    owed: time::Duration,
+    dt: time::Duration,
+    max: u32,
+) -> (u32, time::Duration, time::Duration) {
+    let dt_nanos = dt.as_nanos().max(1);
+    let whole = owed.as_nanos() / dt_nanos;
+    let ticks = whole.min(u128::from(max)) as u32;
+    let dropped = time::Duration::from_nanos(((whole - u128::from(ticks)) * dt_nanos) as u64);
+    let left_over = time::Duration::from_nanos((owed.as_nanos() % dt_nanos) as u64);
+    (ticks, dropped, left_over)
+}
 
-        self.residual_update_dt += time_since_last;
+// How long to sleep at `now` before the frame due at `deadline`, leaving `SPIN_TIME` to yield in.
+fn sleep_time(now: Instant, deadline: Instant) -> Option<time::Duration> {
+    if now + SPIN_TIME < deadline {
+        Some(deadline - now - SPIN_TIME)
+    } else {
+        None
+    }
+}
+
+// When the frame due at `deadline`, which ended at `now`, counts as ended for the next deadline.
+// Frames are kept to the deadlines, unless one ran over by a whole frame `dt`, after which the
+// next ones would all come at once to catch up.
+fn frame_end(deadline: Instant, now: Instant, dt: time::Duration) -> Instant {
+    if now - deadline < dt {
+        deadline
+    } else {
+        now
     }
 }
 
+// `dt` scaled by `scale`, which is at least 0, saturating rather than overflowing.
+fn scale(dt: time::Duration, scale: f32) -> time::Duration {
+    time::Duration::try_from_secs_f64(dt.as_secs_f64() * f64::from(scale))
+        .unwrap_or(time::Duration::MAX)
+}
+
 impl Default for TimeContext {
     fn default() -> Self {
         Self::new()
@@ -227,7 +728,7 @@ pub fn delta(ctx: &Context) -> time::Duration {
 }
 
 /// Gets the average time of a frame, averaged
-/// over the last 200 frames.
+/// over the last 240 frames.
 #[deprecated(note = "Use `ctx.time.average_delta` instead")]
 pub fn average_delta(ctx: &Context) -> time::Duration {
     ctx.time.average_delta()
@@ -243,7 +744,7 @@ fn fps_as_duration(fps: u32) -> time::Duration {
 }
 
 /// Gets the FPS of the game, averaged over the last
-/// 200 frames.
+/// 240 frames.
 #[deprecated(note = "Use `ctx.time.fps` instead")]
 pub fn fps(ctx: &Context) -> f64 {
     ctx.time.fps()
@@ -254,7 +755,7 @@ pub fn fps(ctx: &Context) -> f64 {
 #[deprecated(note = "Use `ctx.time.time_since_start` instead")]
 pub fn time_since_start(ctx: &Context) -> time::Duration {
     let tc = &ctx.time;
-    time::Instant::now() - tc.init_instant
+    Instant::now() - tc.init_instant
 }
 
 /// Check whether or not the desired amount of time has elapsed
@@ -319,6 +820,18 @@ pub fn yield_now() {
     thread::yield_now();
 }
 
+// The wall clock time, which `SystemTime::now` panics getting in browsers.
+pub(crate) fn system_time_now() -> time::SystemTime {
+    #[cfg(target_arch = "wasm32")]
+    {
+        time::UNIX_EPOCH + time::Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        time::SystemTime::now()
+    }
+}
+
 /// Gets the number of times the game has gone through its event loop.
 ///
 /// Specifically, the number of times that [`TimeContext::tick()`](struct.TimeContext.html#method.tick)
@@ -327,3 +840,325 @@ pub fn yield_now() {
 pub fn ticks(ctx: &Context) -> usize {
     ctx.time.frame_count
 }
+
+/// How long a [`scope()`] took in a frame, see [`TimeContext::profile_report`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+    /// The name the scope was given.
+    pub name: &'static str,
+    /// How long it took, every time it was entered put together.
+    pub total: time::Duration,
+    /// How many times it was entered.
+    pub count: u32,
+}
+
+/// Times the code until it's dropped, returned by [`scope()`].
+#[must_use = "the scope ends as soon as this is dropped"]
+// it's empty without the feature, but it's still a guard
+#[cfg_attr(not(feature = "profiling"), allow(missing_copy_implementations))]
+pub struct ProfileScope {
+    #[cfg(feature = "profiling")]
+    name: &'static str,
+    #[cfg(feature = "profiling")]
+    start: Instant,
+    #[cfg(feature = "profiling")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Times the code from here to the end of the block, which shows in
+/// [`TimeContext::profile_report`] under `name`, with the `profiling` feature.
+///
+/// The scopes are `tracing` spans too, named `"scope"` with the name in a `name` field, so a
+/// subscriber such as `tracing-tracy` shows them in a profiler with the engine's own. Without
+/// the feature this does nothing, and costs nothing.
+///
+/// ```rust
+/// # fn think() {}
+/// fn update_ai() {
+///     let _scope = ggez::timer::scope("ai");
+///     think();
+/// }
+/// ```
+#[inline]
+pub fn scope(name: &'static str) -> ProfileScope {
+    #[cfg(feature = "profiling")]
+    {
+        ProfileScope {
+            name,
+            start: Instant::now(),
+            _span: tracing::info_span!("scope", name).entered(),
+        }
+    }
+    #[cfg(not(feature = "profiling"))]
+    {
+        let _ = name;
+        ProfileScope {}
+    }
+}
+
+// What the scopes timed since the last tick, from every thread.
+#[cfg(feature = "profiling")]
+static PROFILE: std::sync::Mutex<Vec<ProfileEntry>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(feature = "profiling")]
+fn take_profile(report: &mut Vec<ProfileEntry>) {
+    let mut profile = PROFILE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    report.clear();
+    report.append(&mut profile);
+}
+
+#[cfg(feature = "profiling")]
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let mut profile = PROFILE
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match profile.iter_mut().find(|entry| entry.name == self.name) {
+            Some(entry) => {
+                entry.total += elapsed;
+                entry.count += 1;
+            }
+            None => profile.push(ProfileEntry {
+                name: self.name,
+                total: elapsed,
+                count: 1,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for ProfileScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProfileScope").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_updates() {
+        let ms = time::Duration::from_millis;
+        let mut time = TimeContext::new();
+        time.set_fixed_update_rate(Some(100));
+        assert_eq!(time.fixed_delta(), Some(ms(10)));
+        let updates = |time: &mut TimeContext, elapsed| {
+            time.owe_fixed_updates(elapsed);
+            std::iter::from_fn(|| time.take_fixed_update().then_some(())).count()
+        };
+        assert_eq!(updates(&mut time, ms(25)), 2);
+        assert!((time.blend_factor() - 0.5).abs() < 1e-6);
+        assert_eq!(updates(&mut time, ms(5)), 1);
+        assert_eq!(time.blend_factor(), 0.0);
+        // no more than the most in a frame, keeping what's left over
+        time.set_max_fixed_updates(3);
+        assert_eq!(updates(&mut time, ms(1004)), 3);
+        assert!((time.blend_factor() - 0.4).abs() < 1e-6);
+        assert_eq!(time.dropped_fixed_time(), ms(970));
+        assert_eq!(updates(&mut time, ms(1)), 0);
+        assert_eq!(time.dropped_fixed_time(), time::Duration::ZERO);
+        time.set_fixed_update_rate(None);
+        assert_eq!(updates(&mut time, ms(100)), 0);
+    }
+
+    #[test]
+    fn catch_up_policies() {
+        let ms = time::Duration::from_millis;
+        let mut time = TimeContext::new();
+        time.set_fixed_update_rate(Some(100));
+        assert_eq!(time.catch_up_policy(), CatchUpPolicy::CapTicks);
+        assert_eq!(time.max_fixed_updates(), 8);
+        time.set_max_fixed_updates(3);
+
+        time.advance(ms(105));
+        assert_eq!(time.delta(), ms(105));
+        assert_eq!(
+            time.catch_up_report(),
+            CatchUpReport {
+                ticks: 3,
+                dropped: ms(70),
+                stretched: time::Duration::ZERO
+            }
+        );
+        assert!(time.catch_up_report().fell_behind());
+        while time.take_fixed_update() {}
+
+        time.set_catch_up_policy(CatchUpPolicy::RunAll);
+        time.advance(ms(100));
+        assert_eq!(time.catch_up_report().ticks, 10);
+        assert!(!time.catch_up_report().fell_behind());
+        while time.take_fixed_update() {}
+
+        // the 5 ms left over and 30 ms of the frame make the three updates and what's left over
+        // again, the rest is stretched
+        time.set_catch_up_policy(CatchUpPolicy::SlowDown);
+        let before = time.scaled_time_since_start();
+        time.advance(ms(100));
+        assert_eq!(time.delta(), ms(30));
+        assert_eq!(time.scaled_time_since_start() - before, ms(30));
+        assert_eq!(
+            time.catch_up_report(),
+            CatchUpReport {
+                ticks: 3,
+                dropped: time::Duration::ZERO,
+                stretched: ms(70)
+            }
+        );
+        while time.take_fixed_update() {}
+        assert!((time.blend_factor() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn catching_up() {
+        let ms = time::Duration::from_millis;
+        let mut time = TimeContext::new();
+        time.residual_update_dt = ms(1055);
+        // 105 updates are owed, the last 5 ms are left over for the next frame
+        let ticks = time.ticks_this_frame(100, 3);
+        assert_eq!(ticks.ticks, 3);
+        assert_eq!(ticks.dropped, ms(1020));
+        assert_eq!(time.remaining_update_time(), ms(5));
+        time.residual_update_dt += ms(25);
+        assert_eq!(
+            time.ticks_this_frame(100, 3),
+            UpdateTicks {
+                ticks: 3,
+                dropped: time::Duration::ZERO
+            }
+        );
+
+        // a long frame only counts as the most it may be
+        time.set_max_frame_time(Some(ms(1)));
+        thread::sleep(ms(5));
+        time.tick();
+        assert_eq!(time.unscaled_delta(), ms(1));
+        assert_eq!(time.delta(), ms(1));
+        assert!(time.frame_times()[0] >= ms(5));
+    }
+
+    #[test]
+    fn frame_times() {
+        let ms = time::Duration::from_millis;
+        let mut buffer = LogBuffer::new(3, ms(16));
+        assert_eq!((buffer.contents(), buffer.latest()), (&[][..], ms(16)));
+        for i in 1..=5 {
+            buffer.push(ms(i));
+        }
+        // the last ones, in order, after wrapping around
+        assert_eq!(buffer.contents(), &[ms(3), ms(4), ms(5)]);
+        assert_eq!(buffer.latest(), ms(5));
+
+        let mut time = TimeContext::new();
+        assert_eq!(time.frame_time_stats(), FrameTimeStats::default());
+        assert_eq!(time.average_delta(), ms(16));
+        time.set_frame_time_history(100);
+        for i in 1..=100 {
+            time.frame_durations.push(ms(i));
+        }
+        let stats = time.frame_time_stats();
+        assert_eq!(
+            (stats.p50, stats.p95, stats.p99, stats.max),
+            (ms(50), ms(95), ms(99), ms(100))
+        );
+        assert_eq!(time.spike_count(ms(90)), 10);
+        assert_eq!(time.frame_times().len(), 100);
+        assert_eq!(time.frame_time_history(), 100);
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn profile_report() {
+        let mut time = TimeContext::new();
+        for _ in 0..3 {
+            let _scope = scope("test scope");
+            thread::sleep(time::Duration::from_millis(1));
+        }
+        time.tick();
+        let entry = time
+            .profile_report()
+            .iter()
+            .find(|entry| entry.name == "test scope")
+            .unwrap();
+        assert_eq!(entry.count, 3);
+        assert!(entry.total >= time::Duration::from_millis(3));
+        // only the last frame's
+        time.tick();
+        assert!(time
+            .profile_report()
+            .iter()
+            .all(|entry| entry.name != "test scope"));
+    }
+
+    #[test]
+    fn time_scale() {
+        let ms = time::Duration::from_millis;
+        assert_eq!(scale(ms(20), 0.5), ms(10));
+        assert_eq!(scale(ms(20), 0.0), time::Duration::ZERO);
+        assert_eq!(scale(ms(20), f32::INFINITY), time::Duration::MAX);
+
+        let mut time = TimeContext::new();
+        time.set_time_scale(-1.0);
+        assert_eq!(time.time_scale(), 0.0);
+        time.set_fixed_update_rate(Some(1000));
+        thread::sleep(ms(5));
+        time.tick();
+        // frozen: no game time goes by, while the actual time does
+        assert_eq!(time.delta(), time::Duration::ZERO);
+        assert!(time.unscaled_delta() >= ms(5));
+        assert_eq!(time.scaled_time_since_start(), time::Duration::ZERO);
+        assert!(!time.take_fixed_update());
+        assert_eq!(time.blend_factor(), 0.0);
+
+        time.set_time_scale(0.5);
+        thread::sleep(ms(10));
+        time.tick();
+        assert!(time.delta() >= ms(5) && time.delta() < time.unscaled_delta());
+        assert_eq!(time.scaled_time_since_start(), time.delta());
+        assert!(time.take_fixed_update());
+    }
+
+    #[test]
+    fn target_fps() {
+        let ms = time::Duration::from_millis;
+        let mut time = TimeContext::new();
+        time.set_target_fps(Some(100));
+        assert_eq!(time.target_fps(), Some(100));
+        assert_eq!(time.frame_cap(), Some(ms(10)));
+        time.wait_for_next_frame();
+        let start = Instant::now();
+        time.wait_for_next_frame();
+        time.wait_for_next_frame();
+        assert!(start.elapsed() >= ms(15));
+        // the cap while unfocused replaces the other one
+        time.set_unfocused_target_fps(Some(1000));
+        assert_eq!(time.frame_cap(), Some(ms(10)));
+        time.set_focused(false);
+        assert_eq!(time.frame_cap(), Some(ms(1)));
+        time.set_target_fps(Some(0));
+        assert_eq!(time.target_fps(), None);
+        time.set_unfocused_target_fps(None);
+        assert_eq!(time.frame_cap(), None);
+    }
+
+    #[test]
+    fn frame_pacing() {
+        let ms = time::Duration::from_millis;
+        let start = Instant::now();
+        let deadline = start + ms(10);
+        assert_eq!(sleep_time(start, deadline), Some(ms(10) - SPIN_TIME));
+        // close to the deadline, it's only yielded
+        assert_eq!(sleep_time(deadline - SPIN_TIME, deadline), None);
+        assert_eq!(sleep_time(deadline + ms(1), deadline), None);
+        // late frames keep to the deadlines, unless a whole frame late
+        assert_eq!(frame_end(deadline, deadline, ms(10)), deadline);
+        assert_eq!(frame_end(deadline, deadline + ms(9), ms(10)), deadline);
+        assert_eq!(
+            frame_end(deadline, deadline + ms(12), ms(10)),
+            deadline + ms(12)
+        );
+    }
+}
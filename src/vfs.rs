@@ -9,12 +9,16 @@
 //! as a trait object, and its path abstraction is not the most
 //! convenient.
 
-use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{self, Debug};
 use std::fs;
 use std::io::{self, Read, Seek, Write};
 use std::path::{self, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use flate2::read::DeflateDecoder;
 
 use crate::error::{GameError, GameResult};
 
@@ -25,9 +29,10 @@ fn convenient_path_to_str(path: &path::Path) -> GameResult<&str> {
     })
 }
 
-pub trait VFile: Read + Write + Seek + Debug {}
+// Send, so that sounds can be streamed from files on the audio thread
+pub trait VFile: Read + Write + Seek + Debug + Send {}
 
-impl<T> VFile for T where T: Read + Write + Seek + Debug {}
+impl<T> VFile for T where T: Read + Write + Seek + Debug + Send {}
 
 /// Options for opening files
 ///
@@ -40,6 +45,7 @@ pub struct OpenOptions {
     read: bool,
     write: bool,
     create: bool,
+    create_new: bool,
     append: bool,
     truncate: bool,
 }
@@ -68,6 +74,12 @@ impl OpenOptions {
         self
     }
 
+    /// Create the file, failing if it exists already
+    pub fn create_new(mut self, create_new: bool) -> OpenOptions {
+        self.create_new = create_new;
+        self
+    }
+
     /// Append at the end of the file
     pub fn append(mut self, append: bool) -> OpenOptions {
         self.append = append;
@@ -80,6 +92,16 @@ impl OpenOptions {
         self
     }
 
+    // Whether the file is created if it doesn't exist.
+    pub(crate) fn creates(&self) -> bool {
+        self.create || self.create_new
+    }
+
+    // Whether the file may be changed, so it can't be opened in a read-only mount.
+    fn alters(&self) -> bool {
+        self.write || self.creates() || self.append || self.truncate
+    }
+
     fn to_fs_openoptions(self) -> fs::OpenOptions {
         let mut opt = fs::OpenOptions::new();
         let _ = opt
@@ -88,13 +110,14 @@ impl OpenOptions {
             .create(self.create)
             .append(self.append)
             .truncate(self.truncate)
-            .create(self.create);
+            .create(self.create)
+            .create_new(self.create_new);
         opt
     }
 }
 
 #[allow(clippy::upper_case_acronyms)]
-pub trait VFS: Debug {
+pub trait VFS: Debug + Send {
     /// Open the file at this path with the given options
     fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>>;
     /// Open the file at this path for reading
@@ -121,6 +144,14 @@ pub trait VFS: Debug {
     /// Remove a file or an empty directory.
     fn rm(&self, path: &Path) -> GameResult;
 
+    /// Write the whole file at the given path so it's left with either its
+    /// old contents or the new ones if writing it fails partway
+    fn write_atomic(&self, path: &Path, bytes: &[u8]) -> GameResult {
+        let mut file = self.create(path)?;
+        file.write_all(bytes)?;
+        Ok(file.flush()?)
+    }
+
     /// Remove a file or directory and all its contents
     fn rmrf(&self, path: &Path) -> GameResult;
 
@@ -133,10 +164,41 @@ pub trait VFS: Debug {
     /// Retrieve all file and directory entries in the given directory.
     fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>>;
 
+    /// Retrieve all file and directory entries in the given directory,
+    /// with their metadata.
+    fn read_dir_with_metadata(&self, path: &Path) -> GameResult<MetadataEntries> {
+        let itr = self
+            .read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                let metadata = self.metadata(&entry)?;
+                Ok((entry, metadata))
+            })
+            .collect::<Vec<_>>();
+        Ok(Box::new(itr.into_iter()))
+    }
+
     /// Retrieve the actual location of the VFS root, if available.
     fn to_path_buf(&self) -> Option<PathBuf>;
+
+    /// Whether nothing can be written to this VFS
+    fn is_readonly(&self) -> bool;
+
+    /// Whether this VFS is backed by an archive rather than a directory
+    fn is_archive(&self) -> bool {
+        false
+    }
+
+    /// This VFS, if it's kept in memory
+    fn as_memory(&self) -> Option<&MemoryFS> {
+        None
+    }
 }
 
+/// The entries of a directory with their metadata, as returned by
+/// `VFS::read_dir_with_metadata`.
+pub type MetadataEntries = Box<dyn Iterator<Item = GameResult<(PathBuf, Box<dyn VMetadata>)>>>;
+
 pub trait VMetadata {
     /// Returns whether or not it is a directory.
     /// Note that zip files don't actually have directories, awkwardly,
@@ -147,6 +209,14 @@ pub trait VMetadata {
     /// Returns the length of the thing.  If it is a directory,
     /// the result of this is undefined/platform dependent.
     fn len(&self) -> u64;
+    /// Returns when it was last modified, if that's known.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
+    /// Returns when it was created, if that's known.
+    fn created(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 /// A VFS that points to a directory and uses it as the root of its
@@ -174,43 +244,75 @@ impl VMetadata for PhysicalMetadata {
     fn len(&self) -> u64 {
         self.0.len()
     }
+    fn modified(&self) -> Option<SystemTime> {
+        self.0.modified().ok()
+    }
+    fn created(&self) -> Option<SystemTime> {
+        self.0.created().ok()
+    }
 }
 
-/// This takes an absolute path and returns either a sanitized relative
-/// version of it, or None if there's something bad in it.
+/// Splits an absolute path into the names in it, the same way for every
+/// VFS, or returns None if there's something bad in it.
 ///
-/// What we want is an absolute path with no `..`'s in it, so, something
-/// like "/foo" or "/foo/bar.txt".  This means a path with components
-/// starting with a `RootDir`, and zero or more `Normal` components.
+/// Both `/` and `\` separate names, so paths written on Windows work
+/// everywhere else too, and `.` and empty names are skipped, so
+/// `\sprites\.\hero.png` is `/sprites/hero.png`. Paths which don't
+/// start with one of them, or have a `..` in them, are rejected rather than
+/// resolved, so nothing outside of a VFS's root can be reached.
+pub(crate) fn path_names(path: &path::Path) -> Option<Vec<&str>> {
+    let rest = path.to_str()?.strip_prefix(['/', '\\'])?;
+    let mut names = Vec::new();
+    for name in rest.split(['/', '\\']) {
+        match name {
+            "" | "." => (),
+            ".." => return None,
+            name => names.push(name),
+        }
+    }
+    Some(names)
+}
+
+/// This takes an absolute path and returns either a sanitized relative
+/// version of it, or None if there's something bad in it, following the
+/// rules of `path_names`.
 ///
 /// We gotta return a new path because there's apparently no real good way
 /// to turn an absolute path into a relative path with the same
 /// components (other than the first), and pushing an absolute `Path`
 /// onto a `PathBuf` just completely nukes its existing contents.
-fn sanitize_path(path: &path::Path) -> Option<PathBuf> {
-    let mut c = path.components();
-    match c.next() {
-        Some(path::Component::RootDir) => (),
-        _ => return None,
-    }
+pub(crate) fn sanitize_path(path: &path::Path) -> Option<PathBuf> {
+    path_names(path).map(|names| names.into_iter().collect())
+}
 
-    fn is_normal_component(comp: path::Component) -> Option<&str> {
-        match comp {
-            path::Component::Normal(s) => s.to_str(),
-            _ => None,
-        }
+/// Writes `bytes` to the file at `path` on disk so that it has either its
+/// old contents or the new ones even if the game is closed or the power
+/// goes out partway: they're written to another file in the same
+/// directory, which is flushed to the disk and then renamed over it.
+/// Renaming replaces the file on Windows as well, as `fs::rename` does.
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    let written = fs::File::create(&temporary)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temporary, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temporary);
     }
-
-    // This could be done more cleverly but meh
-    let mut accm = PathBuf::new();
-    for component in c {
-        if let Some(s) = is_normal_component(component) {
-            accm.push(s)
-        } else {
-            return None;
-        }
+    written?;
+    // and the rename, which is kept in the directory
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
     }
-    Some(accm)
+    Ok(())
 }
 
 /// We need to return a string.
@@ -218,31 +320,7 @@ fn sanitize_path(path: &path::Path) -> Option<PathBuf> {
 /// delimiter in rust is environment dependent.
 /// For example, on Windows, `PathBuf` to str in rust make "foo\\bar.txt".
 fn sanitize_path_for_zip(path: &path::Path) -> Option<String> {
-    let mut c = path.components();
-    match c.next() {
-        Some(path::Component::RootDir) => (),
-        _ => return None,
-    }
-
-    fn is_normal_component(comp: path::Component) -> Option<&str> {
-        match comp {
-            path::Component::Normal(s) => s.to_str(),
-            _ => None,
-        }
-    }
-
-    // This could be done more cleverly but meh
-    let mut accm = String::new();
-    for component in c {
-        if let Some(s) = is_normal_component(component) {
-            accm.push_str(s);
-            accm.push('/');
-        } else {
-            return None;
-        }
-    }
-    let accm = accm.trim_end_matches('/').to_string();
-    Some(accm)
+    path_names(path).map(|names| names.join("/"))
 }
 
 impl PhysicalFS {
@@ -295,20 +373,15 @@ impl Debug for PhysicalFS {
 impl VFS for PhysicalFS {
     /// Open the file at this path with the given options
     fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
-        if self.readonly
-            && (open_options.write
-                || open_options.create
-                || open_options.append
-                || open_options.truncate)
-        {
+        if self.readonly && open_options.alters() {
             let msg = format!(
                 "Cannot alter file {:?} in root {:?}, filesystem read-only",
                 path, self
             );
-            return Err(GameError::FilesystemError(msg));
+            return Err(GameError::ReadOnlyError(msg));
         }
 
-        if open_options.create {
+        if open_options.creates() {
             self.create_root()?;
         }
 
@@ -320,14 +393,25 @@ impl VFS for PhysicalFS {
             .map_err(GameError::from)
     }
 
+    /// Write the whole file at this path, creating the directories it's in
+    fn write_atomic(&self, path: &Path, bytes: &[u8]) -> GameResult {
+        if self.readonly {
+            return Err(GameError::ReadOnlyError(format!(
+                "Cannot write file {:?} in root {:?}, filesystem read-only",
+                path, self
+            )));
+        }
+        let p = self.to_absolute(path)?;
+        Ok(write_atomically(&p, bytes)?)
+    }
+
     /// Create a directory at the location by this path
     fn mkdir(&self, path: &Path) -> GameResult {
         if self.readonly {
-            return Err(GameError::FilesystemError(
-                "Tried to make directory {} but FS is \
-                 read-only"
-                    .to_string(),
-            ));
+            return Err(GameError::ReadOnlyError(format!(
+                "Tried to make directory {:?} but FS is read-only",
+                path
+            )));
         }
 
         self.create_root()?;
@@ -343,9 +427,10 @@ impl VFS for PhysicalFS {
     /// Remove a file
     fn rm(&self, path: &Path) -> GameResult {
         if self.readonly {
-            return Err(GameError::FilesystemError(
-                "Tried to remove file {} but FS is read-only".to_string(),
-            ));
+            return Err(GameError::ReadOnlyError(format!(
+                "Tried to remove file {:?} but FS is read-only",
+                path
+            )));
         }
 
         let p = self.to_absolute(path)?;
@@ -359,11 +444,10 @@ impl VFS for PhysicalFS {
     /// Remove a file or directory and all its contents
     fn rmrf(&self, path: &Path) -> GameResult {
         if self.readonly {
-            return Err(GameError::FilesystemError(
-                "Tried to remove file/dir {} but FS is \
-                 read-only"
-                    .to_string(),
-            ));
+            return Err(GameError::ReadOnlyError(format!(
+                "Tried to remove file/dir {:?} but FS is read-only",
+                path
+            )));
         }
 
         let p = self.to_absolute(path)?;
@@ -416,41 +500,260 @@ impl VFS for PhysicalFS {
         Ok(Box::new(itr))
     }
 
+    /// Retrieve the path entries in this path, with their metadata, which
+    /// comes with them on some platforms rather than taking a call for each.
+    fn read_dir_with_metadata(&self, path: &Path) -> GameResult<MetadataEntries> {
+        let p = self.to_absolute(path)?;
+        let itr = fs::read_dir(p)?
+            .map(|entry| -> GameResult<(PathBuf, Box<dyn VMetadata>)> {
+                let entry = entry?;
+                let mut metadata = entry.metadata()?;
+                // which is of the link itself, but they're followed everywhere else
+                if metadata.file_type().is_symlink() {
+                    metadata = fs::metadata(entry.path())?;
+                }
+                let fname = entry
+                    .file_name()
+                    .into_string()
+                    .expect("Non-unicode char in file path?  Should never happen, I hope!");
+                Ok((path.join(fname), Box::new(PhysicalMetadata(metadata))))
+            })
+            .collect::<Vec<_>>();
+        Ok(Box::new(itr.into_iter()))
+    }
+
     /// Retrieve the actual location of the VFS root, if available.
     fn to_path_buf(&self) -> Option<PathBuf> {
         Some(self.root.clone())
     }
+
+    fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+}
+
+#[derive(Debug)]
+struct Root {
+    priority: i32,
+    fs: Box<dyn VFS>,
+}
+
+// The names in a directory, to find them in another case.
+#[derive(Debug)]
+struct DirNames {
+    exact: HashSet<String>,
+    // by their names in lowercase
+    folded: HashMap<String, String>,
+}
+
+impl DirNames {
+    fn read(fs: &dyn VFS, dir: &Path) -> Self {
+        let mut names = DirNames {
+            exact: HashSet::new(),
+            folded: HashMap::new(),
+        };
+        if let Ok(entries) = fs.read_dir(dir) {
+            for entry in entries.filter_map(Result::ok) {
+                if let Some(name) = entry.file_name().and_then(|name| name.to_str()) {
+                    let _ = names.exact.insert(name.to_owned());
+                    let _ = names
+                        .folded
+                        .entry(name.to_lowercase())
+                        .or_insert_with(|| name.to_owned());
+                }
+            }
+        }
+        names
+    }
+
+    fn find<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if self.exact.contains(name) {
+            return Some(name);
+        }
+        self.folded.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+// What's been found looking up paths without regard to case.
+#[derive(Debug, Default)]
+struct CaseFolding {
+    // the names in each directory of each VFS, by its index, read as they're looked in
+    dirs: HashMap<(usize, PathBuf), DirNames>,
+    // the paths which were warned about being asked for in another case
+    warned: HashSet<PathBuf>,
 }
 
 /// A structure that joins several VFS's together in order.
+///
+/// They are searched from the highest priority to the lowest, and in the
+/// order they were added for the same priority.
 #[derive(Debug)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct OverlayFS {
-    roots: VecDeque<Box<dyn VFS>>,
+    roots: Vec<Root>,
+    // Some if paths are looked up without regard to case
+    case_folding: Option<Mutex<CaseFolding>>,
 }
 
 impl OverlayFS {
     pub fn new() -> Self {
         Self {
-            roots: VecDeque::new(),
+            roots: Vec::new(),
+            case_folding: None,
         }
     }
 
-    /// Adds a new VFS to the front of the list.
-    /// Currently unused, I suppose, but good to
-    /// have at least for tests.
-    #[allow(dead_code)]
+    /// Adds a new VFS to the front of the list of those with the default
+    /// priority, 0.
     pub fn push_front(&mut self, fs: Box<dyn VFS>) {
-        self.roots.push_front(fs);
+        let index = self.roots.partition_point(|root| root.priority > 0);
+        self.roots.insert(index, Root { priority: 0, fs });
+        self.forget_dirs();
     }
 
-    /// Adds a new VFS to the end of the list.
+    /// Adds a new VFS to the end of the list of those with the default
+    /// priority, 0.
     pub fn push_back(&mut self, fs: Box<dyn VFS>) {
-        self.roots.push_back(fs);
+        self.insert(fs, 0);
     }
 
-    pub fn roots(&self) -> &VecDeque<Box<dyn VFS>> {
-        &self.roots
+    /// Adds a new VFS after all of those with the same or a higher priority.
+    pub fn insert(&mut self, fs: Box<dyn VFS>, priority: i32) {
+        let index = self.roots.partition_point(|root| root.priority >= priority);
+        self.roots.insert(index, Root { priority, fs });
+        self.forget_dirs();
+    }
+
+    /// Removes the VFS's rooted at `path`, returning whether there were any.
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let count = self.roots.len();
+        self.roots
+            .retain(|root| root.fs.to_path_buf().as_deref() != Some(path));
+        self.forget_dirs();
+        self.roots.len() != count
+    }
+
+    /// Sets whether paths which aren't in a VFS as they're given are looked
+    /// for in it in another case, reading the directories on the way to
+    /// them once and keeping what's in them until the VFS's or what's in
+    /// them is changed through this.
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_folding = case_insensitive.then(Mutex::default);
+    }
+
+    /// The VFS's in the order they are searched in.
+    pub fn roots(&self) -> impl Iterator<Item = &dyn VFS> + '_ {
+        self.roots.iter().map(|root| &*root.fs)
+    }
+
+    /// The VFS's in the order they are searched in, with their priorities.
+    pub fn mounts(&self) -> impl Iterator<Item = (i32, &dyn VFS)> + '_ {
+        self.roots.iter().map(|root| (root.priority, &*root.fs))
+    }
+
+    /// The first VFS, with its priority, that has something at `path`.
+    pub fn resolve(&self, path: &Path) -> Option<(i32, &dyn VFS)> {
+        self.roots
+            .iter()
+            .enumerate()
+            .find(|(index, root)| root.fs.exists(&self.cased(*index, &*root.fs, path)))
+            .map(|(_, root)| (root.priority, &*root.fs))
+    }
+
+    // The VFS's in the order they are searched in, with the path to look
+    // for in each, which is `path` as it's cased in it if it's looked up
+    // without regard to case.
+    fn rooted<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = (&'a dyn VFS, Cow<'a, Path>)> {
+        self.roots.iter().enumerate().map(move |(index, root)| {
+            let fs = &*root.fs;
+            (fs, self.cased(index, fs, path))
+        })
+    }
+
+    fn cased<'a>(&self, index: usize, fs: &dyn VFS, path: &'a Path) -> Cow<'a, Path> {
+        let case_folding = match &self.case_folding {
+            Some(case_folding) if !fs.exists(path) => case_folding,
+            _ => return Cow::Borrowed(path),
+        };
+        let names = match path_names(path) {
+            Some(names) => names,
+            None => return Cow::Borrowed(path),
+        };
+        let mut case_folding = case_folding.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut cased = PathBuf::from("/");
+        let mut names = names.into_iter();
+        // as far as it's there in another case, the rest as it is
+        for name in names.by_ref() {
+            let dir = case_folding
+                .dirs
+                .entry((index, cased.clone()))
+                .or_insert_with(|| DirNames::read(fs, &cased));
+            match dir.find(name) {
+                Some(found) => cased.push(found),
+                None => {
+                    cased.push(name);
+                    break;
+                }
+            }
+        }
+        cased.extend(names);
+        if sanitize_path(&cased) != sanitize_path(path)
+            && fs.exists(&cased)
+            && case_folding.warned.insert(cased.clone())
+        {
+            warn!(
+                "{:?} was asked for as {:?}, which only works where case doesn't matter",
+                cased, path
+            );
+        }
+        Cow::Owned(cased)
+    }
+
+    // Does `op` in each of the mounts in turn until it works in one. If it
+    // doesn't, returns the first error which isn't because the mount is
+    // read-only, or a `ReadOnlyError` if they all were.
+    fn first_writeable(
+        &self,
+        path: &Path,
+        what: &str,
+        mut op: impl FnMut(&dyn VFS, &Path) -> GameResult,
+    ) -> GameResult {
+        let mut failed = None;
+        let mut done = false;
+        for (vfs, path) in self.rooted(path) {
+            match op(vfs, &path) {
+                Ok(()) => {
+                    done = true;
+                    break;
+                }
+                Err(e) => {
+                    if failed.is_none() && !matches!(e.root(), GameError::ReadOnlyError(_)) {
+                        failed = Some(e);
+                    }
+                }
+            }
+        }
+        self.forget_dirs();
+        if done {
+            return Ok(());
+        }
+        Err(failed.unwrap_or_else(|| {
+            GameError::ReadOnlyError(format!(
+                "Could not find anywhere writeable to {} {:?}",
+                what, path
+            ))
+        }))
+    }
+
+    // Forgets what's in the directories, when it may have changed.
+    fn forget_dirs(&self) {
+        if let Some(case_folding) = &self.case_folding {
+            case_folding
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .dirs
+                .clear();
+        }
     }
 }
 
@@ -459,8 +762,8 @@ impl VFS for OverlayFS {
     fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
         let mut tried: Vec<(PathBuf, GameError)> = vec![];
 
-        for vfs in &self.roots {
-            match vfs.open_options(path, open_options) {
+        for (vfs, path) in self.rooted(path) {
+            match vfs.open_options(&path, open_options) {
                 Err(e) => {
                     if let Some(vfs_path) = vfs.to_path_buf() {
                         tried.push((vfs_path, e));
@@ -468,59 +771,55 @@ impl VFS for OverlayFS {
                         tried.push((PathBuf::from("<invalid path>"), e));
                     }
                 }
-                f => return f,
+                f => {
+                    if open_options.creates() {
+                        self.forget_dirs();
+                    }
+                    return f;
+                }
             }
         }
+        // so it can be told whether there was nowhere to write it or writing it failed
+        if open_options.alters() && !tried.is_empty() {
+            let read_only = |e: &GameError| matches!(e.root(), GameError::ReadOnlyError(_));
+            return match tried.into_iter().map(|(_, e)| e).find(|e| !read_only(e)) {
+                Some(e) => Err(e),
+                None => Err(GameError::ReadOnlyError(format!(
+                    "Could not find anywhere writeable to open {:?}",
+                    path
+                ))),
+            };
+        }
         let errmessage = String::from(convenient_path_to_str(path)?);
         Err(GameError::ResourceNotFound(errmessage, tried))
     }
 
     /// Create a directory at the location by this path
     fn mkdir(&self, path: &Path) -> GameResult {
-        for vfs in &self.roots {
-            match vfs.mkdir(path) {
-                Err(_) => (),
-                f => return f,
-            }
-        }
-        Err(GameError::FilesystemError(format!(
-            "Could not find anywhere writeable to make dir {:?}",
-            path
-        )))
+        self.first_writeable(path, "make dir", |vfs, path| vfs.mkdir(path))
+    }
+
+    /// Write a file to the first VFS that can be written to
+    fn write_atomic(&self, path: &Path, bytes: &[u8]) -> GameResult {
+        self.first_writeable(path, "write file", |vfs, path| {
+            vfs.write_atomic(path, bytes)
+        })
     }
 
     /// Remove a file
     fn rm(&self, path: &Path) -> GameResult {
-        for vfs in &self.roots {
-            match vfs.rm(path) {
-                Err(_) => (),
-                f => return f,
-            }
-        }
-        Err(GameError::FilesystemError(format!(
-            "Could not remove file {:?}",
-            path
-        )))
+        self.first_writeable(path, "remove file", |vfs, path| vfs.rm(path))
     }
 
     /// Remove a file or directory and all its contents
     fn rmrf(&self, path: &Path) -> GameResult {
-        for vfs in &self.roots {
-            match vfs.rmrf(path) {
-                Err(_) => (),
-                f => return f,
-            }
-        }
-        Err(GameError::FilesystemError(format!(
-            "Could not remove file/dir {:?}",
-            path
-        )))
+        self.first_writeable(path, "remove file/dir", |vfs, path| vfs.rmrf(path))
     }
 
     /// Check if the file exists
     fn exists(&self, path: &Path) -> bool {
-        for vfs in &self.roots {
-            if vfs.exists(path) {
+        for (vfs, path) in self.rooted(path) {
+            if vfs.exists(&path) {
                 return true;
             }
         }
@@ -530,8 +829,8 @@ impl VFS for OverlayFS {
 
     /// Get the file's metadata
     fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
-        for vfs in &self.roots {
-            match vfs.metadata(path) {
+        for (vfs, path) in self.rooted(path) {
+            match vfs.metadata(&path) {
                 Err(_) => (),
                 f => return f,
             }
@@ -545,11 +844,32 @@ impl VFS for OverlayFS {
     /// Retrieve the path entries in this path
     fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
         // This is tricky 'cause we have to actually merge iterators together...
-        // Doing it the simple and stupid way works though.
+        // Doing it the simple and stupid way works though, with each path
+        // listed once however many VFS's have something there.
+        let mut v = Vec::new();
+        let mut seen = HashSet::new();
+        for (fs, path) in self.rooted(path) {
+            if let Ok(rddir) = fs.read_dir(&path) {
+                v.extend(rddir.filter(|entry| match entry {
+                    Ok(path) => seen.insert(path.clone()),
+                    Err(_) => true,
+                }))
+            }
+        }
+        Ok(Box::new(v.into_iter()))
+    }
+
+    /// Retrieve the path entries in this path with their metadata, from
+    /// the first VFS listing each as `metadata()` would get it from.
+    fn read_dir_with_metadata(&self, path: &Path) -> GameResult<MetadataEntries> {
         let mut v = Vec::new();
-        for fs in &self.roots {
-            if let Ok(rddir) = fs.read_dir(path) {
-                v.extend(rddir)
+        let mut seen = HashSet::new();
+        for (fs, path) in self.rooted(path) {
+            if let Ok(rddir) = fs.read_dir_with_metadata(&path) {
+                v.extend(rddir.filter(|entry| match entry {
+                    Ok((path, _)) => seen.insert(path.clone()),
+                    Err(_) => true,
+                }))
             }
         }
         Ok(Box::new(v.into_iter()))
@@ -559,15 +879,19 @@ impl VFS for OverlayFS {
     fn to_path_buf(&self) -> Option<PathBuf> {
         None
     }
+
+    fn is_readonly(&self) -> bool {
+        self.roots().all(|fs| fs.is_readonly())
+    }
 }
 
-trait ZipArchiveAccess {
+trait ZipArchiveAccess: Send {
     fn by_name(&mut self, name: &str) -> zip::result::ZipResult<zip::read::ZipFile<'_>>;
     fn by_index(&mut self, file_number: usize) -> zip::result::ZipResult<zip::read::ZipFile<'_>>;
     fn len(&self) -> usize;
 }
 
-impl<T: Read + Seek> ZipArchiveAccess for zip::ZipArchive<T> {
+impl<T: Read + Seek + Send> ZipArchiveAccess for zip::ZipArchive<T> {
     fn by_name(&mut self, name: &str) -> zip::result::ZipResult<zip::read::ZipFile> {
         let filename =
             sanitize_path_for_zip(Path::new(name)).ok_or(zip::result::ZipError::FileNotFound)?;
@@ -603,32 +927,50 @@ pub struct ZipFS {
     // ALSO THE SEMANTICS OF ZIPARCHIVE AND HAVING ZIPFILES BORROW IT IS
     // HORRIFICALLY BROKEN BY DESIGN SO WE'RE JUST GONNA REFCELL IT AND COPY
     // ALL CONTENTS OUT OF IT AAAAA.
+    // (it's a mutex now, so the filesystem can be read from other threads)
     source: Option<PathBuf>,
-    archive: RefCell<Box<dyn ZipArchiveAccess>>,
-    // We keep an index of what files are in the zip file
-    // because trying to read it lazily is a pain in the butt.
-    index: Vec<String>,
+    // unless it was made from some other reader, to read the files stored
+    // or deflated in it straight from it
+    bytes: Option<ZipBytes>,
+    archive: Mutex<Box<dyn ZipArchiveAccess>>,
+    // We keep an index of the directories in the zip file, with the names of
+    // what's in each, because trying to read it lazily is a pain in the butt.
+    // Archives don't have to have entries for directories, so they're found
+    // from the files' paths; the root is `""`.
+    dirs: HashMap<String, BTreeSet<String>>,
 }
 
 impl ZipFS {
     pub fn new(filename: &Path) -> GameResult<Self> {
         let f = fs::File::open(filename)?;
         let archive = Box::new(zip::ZipArchive::new(f)?);
-        Ok(ZipFS::from_boxed_archive(archive, Some(filename.into())))
+        let mut zipfs = ZipFS::from_boxed_archive(archive, Some(filename.into()));
+        zipfs.bytes = Some(ZipBytes::File(filename.into()));
+        Ok(zipfs)
+    }
+
+    /// Creates a `ZipFS` from the bytes of an archive, whose files are read
+    /// straight from them rather than copied out.
+    pub fn from_bytes(bytes: Cow<'static, [u8]>) -> GameResult<Self> {
+        let bytes = SharedBytes(Arc::new(bytes));
+        let archive = Box::new(zip::ZipArchive::new(io::Cursor::new(bytes.clone()))?);
+        let mut zipfs = ZipFS::from_boxed_archive(archive, None);
+        zipfs.bytes = Some(ZipBytes::Memory(bytes));
+        Ok(zipfs)
     }
 
     /// Creates a `ZipFS` from any `Read+Seek` object, most useful with an
     /// in-memory `std::io::Cursor`.
     pub fn from_read<R>(reader: R) -> GameResult<Self>
     where
-        R: Read + Seek + 'static,
+        R: Read + Seek + Send + 'static,
     {
         let archive = Box::new(zip::ZipArchive::new(reader)?);
         Ok(ZipFS::from_boxed_archive(archive, None))
     }
 
     fn from_boxed_archive(mut archive: Box<dyn ZipArchiveAccess>, source: Option<PathBuf>) -> Self {
-        let idx = (0..archive.len())
+        let idx: Vec<String> = (0..archive.len())
             .map(|i| {
                 archive
                     .by_index(i)
@@ -637,10 +979,247 @@ impl ZipFS {
                     .to_string()
             })
             .collect();
+        let mut dirs: HashMap<String, BTreeSet<String>> = HashMap::new();
+        let _ = dirs.insert(String::new(), BTreeSet::new());
+        for name in &idx {
+            // with entries of their own for directories ending in `/`
+            let is_dir = name.ends_with('/');
+            let name = name.trim_end_matches('/');
+            let mut parent = 0;
+            for (i, _) in name.match_indices('/').chain(Some((name.len(), ""))) {
+                let dir = if parent == 0 { "" } else { &name[..parent - 1] };
+                let child = &name[parent..i];
+                if !child.is_empty() {
+                    let _ = dirs
+                        .entry(dir.to_string())
+                        .or_default()
+                        .insert(child.to_string());
+                }
+                if i < name.len() || is_dir {
+                    let _ = dirs.entry(name[..i].to_string()).or_default();
+                }
+                parent = i + 1;
+            }
+        }
         Self {
             source,
-            archive: RefCell::new(archive),
-            index: idx,
+            bytes: None,
+            archive: Mutex::new(archive),
+            dirs,
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        match sanitize_path_for_zip(path) {
+            Some(path) => self.dirs.contains_key(&path),
+            None => false,
+        }
+    }
+}
+
+/// The bytes of a zip archive, shared by the files read from it.
+#[derive(Clone)]
+struct SharedBytes(Arc<Cow<'static, [u8]>>);
+
+impl Debug for SharedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        // rather than what are likely to be megabytes of them
+        write!(f, "<{} bytes>", self.0.len())
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Where a zip archive can be read from again, for each file opened in it
+/// to have its own reader.
+#[derive(Clone, Debug)]
+enum ZipBytes {
+    File(PathBuf),
+    Memory(SharedBytes),
+}
+
+impl ZipBytes {
+    fn open(&self) -> io::Result<RawZipReader> {
+        Ok(match self {
+            ZipBytes::File(path) => RawZipReader::File(fs::File::open(path)?),
+            ZipBytes::Memory(bytes) => RawZipReader::Memory(io::Cursor::new(bytes.clone())),
+        })
+    }
+}
+
+enum RawZipReader {
+    File(fs::File),
+    Memory(io::Cursor<SharedBytes>),
+}
+
+impl io::Read for RawZipReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            RawZipReader::File(file) => file.read(buf),
+            RawZipReader::Memory(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl io::Seek for RawZipReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match self {
+            RawZipReader::File(file) => file.seek(pos),
+            RawZipReader::Memory(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+/// The `len` bytes from `start` on of something which can be read and
+/// seeked in, read and seeked in as if they were all there was.
+pub(crate) struct Region<R> {
+    inner: R,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl<R: Seek> Region<R> {
+    pub fn new(mut inner: R, start: u64, len: u64) -> io::Result<Self> {
+        let _ = inner.seek(io::SeekFrom::Start(start))?;
+        Ok(Region {
+            inner,
+            start,
+            len,
+            pos: 0,
+        })
+    }
+}
+
+impl<R: Read> io::Read for Region<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.len.saturating_sub(self.pos);
+        let max = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..max])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> io::Seek for Region<R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let pos = seek_target(pos, self.pos, self.len)?;
+        let _ = self.inner.seek(io::SeekFrom::Start(self.start + pos))?;
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
+impl<R> Debug for Region<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<Region {}..{}>", self.start, self.start + self.len)
+    }
+}
+
+// Where seeking to `pos` in something `len` long goes from `current`.
+fn seek_target(pos: io::SeekFrom, current: u64, len: u64) -> io::Result<u64> {
+    let target = match pos {
+        io::SeekFrom::Start(target) => Some(target),
+        io::SeekFrom::Current(offset) => current.checked_add_signed(offset),
+        io::SeekFrom::End(offset) => len.checked_add_signed(offset),
+    };
+    target.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Tried to seek before the start of the file",
+        )
+    })
+}
+
+/// A file in a zip archive, read straight from the archive rather than
+/// copied out of it.
+enum ZipEntryFile {
+    // which can be seeked in right away
+    Stored(Region<RawZipReader>),
+    // decompressed as it's read, so seeking forward decompresses what's
+    // skipped, and seeking back starts over from the start of it
+    Deflated {
+        bytes: ZipBytes,
+        start: u64,
+        compressed_len: u64,
+        decoder: DeflateDecoder<Region<RawZipReader>>,
+        pos: u64,
+        len: u64,
+    },
+}
+
+impl io::Read for ZipEntryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ZipEntryFile::Stored(region) => region.read(buf),
+            ZipEntryFile::Deflated {
+                decoder, pos, len, ..
+            } => {
+                if *pos >= *len {
+                    return Ok(0);
+                }
+                let read = decoder.read(buf)?;
+                *pos += read as u64;
+                Ok(read)
+            }
+        }
+    }
+}
+
+impl io::Seek for ZipEntryFile {
+    fn seek(&mut self, seek: io::SeekFrom) -> io::Result<u64> {
+        let (bytes, start, compressed_len, decoder, pos, len) = match self {
+            ZipEntryFile::Stored(region) => return region.seek(seek),
+            ZipEntryFile::Deflated {
+                bytes,
+                start,
+                compressed_len,
+                decoder,
+                pos,
+                len,
+            } => (bytes, *start, *compressed_len, decoder, pos, *len),
+        };
+        let target = seek_target(seek, *pos, len)?;
+        if target < *pos {
+            *decoder = DeflateDecoder::new(Region::new(bytes.open()?, start, compressed_len)?);
+            *pos = 0;
+        }
+        let mut skipped = [0; 8192];
+        while *pos < target.min(len) {
+            let max = skipped.len().min((target.min(len) - *pos) as usize);
+            match decoder.read(&mut skipped[..max])? {
+                0 => break,
+                read => *pos += read as u64,
+            }
+        }
+        // past the end, where there's nothing to read
+        *pos = target;
+        Ok(target)
+    }
+}
+
+impl io::Write for ZipEntryFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "zip archives are a read-only mount",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Debug for ZipEntryFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ZipEntryFile::Stored(_) => write!(f, "<Zipfile, stored>"),
+            ZipEntryFile::Deflated { .. } => write!(f, "<Zipfile, deflated>"),
         }
     }
 }
@@ -648,7 +1227,9 @@ impl ZipFS {
 /// A wrapper to contain a zipfile so we can implement
 /// (janky) Seek on it and such.
 ///
-/// We're going to do it the *really* janky way and just read
+/// For archives made from readers other than a file or bytes, which can't
+/// be read from again, and files compressed other ways than deflate,
+/// we're going to do it the *really* janky way and just read
 /// the whole `ZipFile` into a buffer, which is kind of awful but means
 /// we don't have to deal with lifetimes, self-borrowing structs,
 /// rental, re-implementing Seek on compressed data, making multiple zip
@@ -702,6 +1283,7 @@ struct ZipMetadata {
     len: u64,
     is_dir: bool,
     is_file: bool,
+    modified: Option<SystemTime>,
 }
 
 impl ZipMetadata {
@@ -720,6 +1302,7 @@ impl ZipMetadata {
                     len,
                     is_file: true,
                     is_dir: false, // mu
+                    modified: dos_time_to_system_time(zipfile.last_modified()),
                 })
             }
         }
@@ -736,34 +1319,76 @@ impl VMetadata for ZipMetadata {
     fn len(&self) -> u64 {
         self.len
     }
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+/// Zip archives store when their files were modified as the date and time
+/// of MS-DOS, with no time zone, which is taken to be UTC here. Archives
+/// written without one have the earliest there is, 1980-01-01 00:00, which
+/// is taken to mean there isn't one, as are invalid dates.
+fn dos_time_to_system_time(time: zip::DateTime) -> Option<SystemTime> {
+    let date = (time.year(), time.month(), time.day());
+    if date == (1980, 1, 1) && (time.hour(), time.minute(), time.second()) == (0, 0, 0)
+        || !(1..=12).contains(&date.1)
+        || date.2 == 0
+    {
+        return None;
+    }
+    // the days since 1970-01-01 of the date, counting years from March so
+    // the leap day comes last
+    let (month, day) = (u64::from(time.month()), u64::from(time.day()));
+    let year = u64::from(time.year()) - u64::from(month <= 2);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year - 719_468;
+    let seconds = days * 86_400
+        + u64::from(time.hour()) * 3_600
+        + u64::from(time.minute()) * 60
+        + u64::from(time.second());
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
 }
 
 impl VFS for ZipFS {
     fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
         // Zip is readonly
         let path = convenient_path_to_str(path)?;
-        if open_options.write || open_options.create || open_options.append || open_options.truncate
-        {
+        if open_options.alters() {
             let msg = format!(
                 "Cannot alter file {:?} in zipfile {:?}, filesystem read-only",
                 path, self
             );
-            return Err(GameError::FilesystemError(msg));
+            return Err(GameError::ReadOnlyError(msg));
         }
-        let mut stupid_archive_borrow = self.archive
-            .try_borrow_mut()
-            .expect("Couldn't borrow ZipArchive in ZipFS::open_options(); should never happen! Report a bug at https://github.com/ggez/ggez/");
+        let mut stupid_archive_borrow = self.archive.lock().unwrap_or_else(PoisonError::into_inner);
         let mut f = stupid_archive_borrow.by_name(path)?;
-        let zipfile = ZipFileWrapper::new(&mut f)?;
-        Ok(Box::new(zipfile) as Box<dyn VFile>)
-    }
+        let (start, compressed_len, len) = (f.data_start(), f.compressed_size(), f.size());
+        let file: Box<dyn VFile> = match (&self.bytes, f.compression()) {
+            (Some(bytes), zip::CompressionMethod::Stored) => Box::new(ZipEntryFile::Stored(
+                Region::new(bytes.open()?, start, len)?,
+            )),
+            (Some(bytes), zip::CompressionMethod::Deflated) => {
+                let raw = Region::new(bytes.open()?, start, compressed_len)?;
+                Box::new(ZipEntryFile::Deflated {
+                    bytes: bytes.clone(),
+                    start,
+                    compressed_len,
+                    decoder: DeflateDecoder::new(raw),
+                    pos: 0,
+                    len,
+                })
+            }
+            _ => Box::new(ZipFileWrapper::new(&mut f)?),
+        };
+        Ok(file)
+    }
 
     fn mkdir(&self, path: &Path) -> GameResult {
         let msg = format!(
             "Cannot mkdir {:?} in zipfile {:?}, filesystem read-only",
             path, self
         );
-        Err(GameError::FilesystemError(msg))
+        Err(GameError::ReadOnlyError(msg))
     }
 
     fn rm(&self, path: &Path) -> GameResult {
@@ -771,7 +1396,7 @@ impl VFS for ZipFS {
             "Cannot rm {:?} in zipfile {:?}, filesystem read-only",
             path, self
         );
-        Err(GameError::FilesystemError(msg))
+        Err(GameError::ReadOnlyError(msg))
     }
 
     fn rmrf(&self, path: &Path) -> GameResult {
@@ -779,13 +1404,14 @@ impl VFS for ZipFS {
             "Cannot rmrf {:?} in zipfile {:?}, filesystem read-only",
             path, self
         );
-        Err(GameError::FilesystemError(msg))
+        Err(GameError::ReadOnlyError(msg))
     }
 
     fn exists(&self, path: &Path) -> bool {
-        let mut stupid_archive_borrow = self.archive
-            .try_borrow_mut()
-            .expect("Couldn't borrow ZipArchive in ZipFS::exists(); should never happen!  Report a bug at https://github.com/ggez/ggez/");
+        if self.is_dir(path) {
+            return true;
+        }
+        let mut stupid_archive_borrow = self.archive.lock().unwrap_or_else(PoisonError::into_inner);
         if let Ok(path) = convenient_path_to_str(path) {
             stupid_archive_borrow.by_name(path).is_ok()
         } else {
@@ -794,10 +1420,16 @@ impl VFS for ZipFS {
     }
 
     fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+        if self.is_dir(path) {
+            return Ok(Box::new(ZipMetadata {
+                len: 0,
+                is_file: false,
+                is_dir: true,
+                modified: None,
+            }));
+        }
         let path = convenient_path_to_str(path)?;
-        let mut stupid_archive_borrow = self.archive
-            .try_borrow_mut()
-            .expect("Couldn't borrow ZipArchive in ZipFS::metadata(); should never happen! Report a bug at https://github.com/ggez/ggez/");
+        let mut stupid_archive_borrow = self.archive.lock().unwrap_or_else(PoisonError::into_inner);
         match ZipMetadata::new(path, &mut **stupid_archive_borrow) {
             None => Err(GameError::FilesystemError(format!(
                 "Metadata not found in zip file for {}",
@@ -807,19 +1439,20 @@ impl VFS for ZipFS {
         }
     }
 
-    #[allow(clippy::needless_collect)]
-    /// Zip files don't have real directories, so we (incorrectly) hack it by
-    /// just looking for a path prefix for now.
+    /// Zip files don't have real directories, so this lists the files and
+    /// directories whose paths are directly under the given one.
     fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
-        let path = sanitize_path_for_zip(path).ok_or_else(|| {
+        let dir = sanitize_path_for_zip(path).ok_or_else(|| {
             let errmessage = format!("Invalid path format for resource: {:?}", path);
             GameError::FilesystemError(errmessage)
-        })? + "/";
-        let itr = self
-            .index
+        })?;
+        let children = self.dirs.get(&dir).ok_or_else(|| {
+            GameError::FilesystemError(format!("Directory {:?} not found in zip file", path))
+        })?;
+        let dir = PathBuf::from("/").join(dir);
+        let itr = children
             .iter()
-            .filter(|&s| s.starts_with(&path) && s != &path)
-            .map(|s| Ok(PathBuf::from("/").join(s)))
+            .map(|child| Ok(dir.join(child)))
             .collect::<Vec<_>>();
         Ok(Box::new(itr.into_iter()))
     }
@@ -827,6 +1460,823 @@ impl VFS for ZipFS {
     fn to_path_buf(&self) -> Option<PathBuf> {
         self.source.clone()
     }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_archive(&self) -> bool {
+        true
+    }
+}
+
+/// A read-only filesystem of files compiled into the executable with
+/// `include_dir`.
+#[cfg(feature = "embed")]
+#[allow(clippy::upper_case_acronyms)]
+pub struct EmbeddedFS {
+    root: &'static include_dir::Dir<'static>,
+}
+
+#[cfg(feature = "embed")]
+enum EmbeddedEntry {
+    Dir(&'static include_dir::Dir<'static>),
+    File(&'static include_dir::File<'static>),
+}
+
+#[cfg(feature = "embed")]
+impl EmbeddedFS {
+    pub fn new(root: &'static include_dir::Dir<'static>) -> Self {
+        EmbeddedFS { root }
+    }
+
+    fn entry(&self, path: &Path) -> Option<EmbeddedEntry> {
+        let relative = sanitize_path(path)?;
+        if relative.as_os_str().is_empty() {
+            return Some(EmbeddedEntry::Dir(self.root));
+        }
+        match self.root.get_entry(&relative)? {
+            include_dir::DirEntry::Dir(dir) => Some(EmbeddedEntry::Dir(dir)),
+            include_dir::DirEntry::File(file) => Some(EmbeddedEntry::File(file)),
+        }
+    }
+
+    fn read_only(&self, action: &str, path: &Path) -> GameError {
+        GameError::ReadOnlyError(format!(
+            "Cannot {} {:?}, embedded files are a read-only mount",
+            action, path
+        ))
+    }
+}
+
+#[cfg(feature = "embed")]
+impl Debug for EmbeddedFS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<EmbeddedFS>")
+    }
+}
+
+/// A file compiled into the executable, read straight from where it is in
+/// memory.
+#[cfg(feature = "embed")]
+struct EmbeddedFile(io::Cursor<&'static [u8]>);
+
+#[cfg(feature = "embed")]
+impl io::Read for EmbeddedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(feature = "embed")]
+impl io::Write for EmbeddedFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "embedded files are a read-only mount",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embed")]
+impl io::Seek for EmbeddedFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+#[cfg(feature = "embed")]
+impl Debug for EmbeddedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<EmbeddedFile>")
+    }
+}
+
+#[cfg(feature = "embed")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct EmbeddedMetadata {
+    len: u64,
+    is_dir: bool,
+}
+
+#[cfg(feature = "embed")]
+impl VMetadata for EmbeddedMetadata {
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+    fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+#[cfg(feature = "embed")]
+impl VFS for EmbeddedFS {
+    fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
+        if open_options.alters() {
+            return Err(self.read_only("alter the file", path));
+        }
+        match self.entry(path) {
+            Some(EmbeddedEntry::File(file)) => {
+                Ok(Box::new(EmbeddedFile(io::Cursor::new(file.contents()))))
+            }
+            _ => Err(GameError::FilesystemError(format!(
+                "File {:?} not found in the embedded files",
+                path
+            ))),
+        }
+    }
+
+    fn mkdir(&self, path: &Path) -> GameResult {
+        Err(self.read_only("make the directory", path))
+    }
+
+    fn rm(&self, path: &Path) -> GameResult {
+        Err(self.read_only("remove", path))
+    }
+
+    fn rmrf(&self, path: &Path) -> GameResult {
+        Err(self.read_only("remove", path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.entry(path).is_some()
+    }
+
+    fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+        let metadata = match self.entry(path) {
+            Some(EmbeddedEntry::Dir(_)) => EmbeddedMetadata {
+                len: 0,
+                is_dir: true,
+            },
+            Some(EmbeddedEntry::File(file)) => EmbeddedMetadata {
+                len: file.contents().len() as u64,
+                is_dir: false,
+            },
+            None => {
+                return Err(GameError::FilesystemError(format!(
+                    "Metadata not found in the embedded files for {:?}",
+                    path
+                )))
+            }
+        };
+        Ok(Box::new(metadata))
+    }
+
+    fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
+        match self.entry(path) {
+            Some(EmbeddedEntry::Dir(dir)) => {
+                let itr = dir
+                    .entries()
+                    .iter()
+                    .map(|entry| Ok(PathBuf::from("/").join(entry.path())))
+                    .collect::<Vec<_>>();
+                Ok(Box::new(itr.into_iter()))
+            }
+            _ => Err(GameError::FilesystemError(format!(
+                "Directory {:?} not found in the embedded files",
+                path
+            ))),
+        }
+    }
+
+    fn to_path_buf(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+}
+
+/// A read-only filesystem of the assets packed in the APK of an Android app,
+/// read with the `AAssetManager` of its activity.
+///
+/// The NDK only lists the files in directories, not the directories in them,
+/// so directories which only have other directories in them aren't found.
+#[cfg(target_os = "android")]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AssetFS {
+    manager: ndk::asset::AssetManager,
+}
+
+// The asset manager may be used from any thread, and lives as long as the
+// activity, which is as long as the app.
+#[cfg(target_os = "android")]
+#[allow(unsafe_code)]
+unsafe impl Send for AssetFS {}
+
+#[cfg(target_os = "android")]
+impl AssetFS {
+    pub fn new(manager: ndk::asset::AssetManager) -> Self {
+        AssetFS { manager }
+    }
+
+    // The path of the asset, which is relative to the assets directory.
+    fn asset_path(path: &Path) -> Option<std::ffi::CString> {
+        let relative = sanitize_path(path)?;
+        std::ffi::CString::new(relative.to_str()?).ok()
+    }
+
+    fn open_asset(&self, path: &Path) -> Option<ndk::asset::Asset> {
+        let asset_path = Self::asset_path(path)?;
+        // the root is the directory of the assets
+        if asset_path.as_bytes().is_empty() {
+            return None;
+        }
+        self.manager.open(&asset_path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        match Self::asset_path(path) {
+            Some(asset_path) if asset_path.as_bytes().is_empty() => true,
+            // opening a directory never fails, even if there's no such directory
+            Some(asset_path) => self
+                .manager
+                .open_dir(&asset_path)
+                .map_or(false, |mut dir| dir.next().is_some()),
+            None => false,
+        }
+    }
+
+    fn read_only(&self, action: &str, path: &Path) -> GameError {
+        GameError::ReadOnlyError(format!(
+            "Cannot {} {:?}, the APK's assets are a read-only mount",
+            action, path
+        ))
+    }
+}
+
+#[cfg(target_os = "android")]
+impl Debug for AssetFS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<AssetFS>")
+    }
+}
+
+/// An asset of the APK, read from it as it's needed, so sounds can be
+/// streamed from it.
+#[cfg(target_os = "android")]
+struct AssetFile(ndk::asset::Asset);
+
+// An asset is only read from one thread at a time, which it may be moved to.
+#[cfg(target_os = "android")]
+#[allow(unsafe_code)]
+unsafe impl Send for AssetFile {}
+
+#[cfg(target_os = "android")]
+impl io::Read for AssetFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(target_os = "android")]
+impl io::Write for AssetFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the APK's assets are a read-only mount",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "android")]
+impl io::Seek for AssetFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+#[cfg(target_os = "android")]
+impl Debug for AssetFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<AssetFile>")
+    }
+}
+
+#[cfg(target_os = "android")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct AssetMetadata {
+    len: u64,
+    is_dir: bool,
+}
+
+#[cfg(target_os = "android")]
+impl VMetadata for AssetMetadata {
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+    fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+#[cfg(target_os = "android")]
+impl VFS for AssetFS {
+    fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
+        if open_options.alters() {
+            return Err(self.read_only("alter the file", path));
+        }
+        match self.open_asset(path) {
+            Some(asset) => Ok(Box::new(AssetFile(asset))),
+            None => Err(GameError::FilesystemError(format!(
+                "File {:?} not found in the APK's assets",
+                path
+            ))),
+        }
+    }
+
+    fn mkdir(&self, path: &Path) -> GameResult {
+        Err(self.read_only("make the directory", path))
+    }
+
+    fn rm(&self, path: &Path) -> GameResult {
+        Err(self.read_only("remove", path))
+    }
+
+    fn rmrf(&self, path: &Path) -> GameResult {
+        Err(self.read_only("remove", path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.open_asset(path).is_some() || self.is_dir(path)
+    }
+
+    fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+        let metadata = match self.open_asset(path) {
+            Some(asset) => AssetMetadata {
+                len: asset.get_length() as u64,
+                is_dir: false,
+            },
+            None if self.is_dir(path) => AssetMetadata {
+                len: 0,
+                is_dir: true,
+            },
+            None => {
+                return Err(GameError::FilesystemError(format!(
+                    "Metadata not found in the APK's assets for {:?}",
+                    path
+                )))
+            }
+        };
+        Ok(Box::new(metadata))
+    }
+
+    fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
+        let not_found = || {
+            GameError::FilesystemError(format!(
+                "Directory {:?} not found in the APK's assets",
+                path
+            ))
+        };
+        let relative = sanitize_path(path).ok_or_else(not_found)?;
+        if !self.is_dir(path) {
+            return Err(not_found());
+        }
+        let dir = Self::asset_path(path)
+            .and_then(|asset_path| self.manager.open_dir(&asset_path))
+            .ok_or_else(not_found)?;
+        let itr = dir
+            .filter_map(|name| name.into_string().ok())
+            .map(|name| Ok(PathBuf::from("/").join(&relative).join(name)))
+            .collect::<Vec<_>>();
+        Ok(Box::new(itr.into_iter()))
+    }
+
+    fn to_path_buf(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn is_readonly(&self) -> bool {
+        true
+    }
+
+    fn is_archive(&self) -> bool {
+        true
+    }
+}
+
+/// A writeable filesystem kept entirely in memory, with the files and
+/// directories under `prefix`, so nothing written to it touches the disk.
+///
+/// Its clones share the same files.
+#[derive(Clone)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct MemoryFS {
+    // relative, as `sanitize_path` makes it
+    prefix: PathBuf,
+    entries: Arc<Mutex<MemoryEntries>>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryEntries {
+    files: BTreeMap<PathBuf, MemoryFileEntry>,
+    // as well as the prefix, the directories of the files in it and those made with mkdir
+    dirs: BTreeSet<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct MemoryFileEntry {
+    contents: Vec<u8>,
+    created: SystemTime,
+    modified: SystemTime,
+}
+
+impl MemoryFileEntry {
+    fn new() -> Self {
+        let now = crate::timer::system_time_now();
+        MemoryFileEntry {
+            contents: Vec::new(),
+            created: now,
+            modified: now,
+        }
+    }
+}
+
+impl MemoryFS {
+    /// Makes an empty one holding the paths under the absolute `prefix`,
+    /// or `None` if it isn't a valid one.
+    pub fn new(prefix: &Path) -> Option<Self> {
+        let prefix = sanitize_path(prefix)?;
+        Some(MemoryFS {
+            prefix,
+            entries: Arc::default(),
+        })
+    }
+
+    /// The absolute path its files are under.
+    pub fn prefix(&self) -> PathBuf {
+        Path::new("/").join(&self.prefix)
+    }
+
+    /// The contents of all of its files, by their absolute paths.
+    pub fn snapshot(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.entries()
+            .files
+            .iter()
+            .map(|(path, file)| (Path::new("/").join(path), file.contents.clone()))
+            .collect()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, MemoryEntries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The relative path of something that can be written to, under the prefix.
+    fn writeable(&self, path: &Path) -> GameResult<PathBuf> {
+        match sanitize_path(path) {
+            Some(relative) if relative.starts_with(&self.prefix) && relative != self.prefix => {
+                Ok(relative)
+            }
+            _ => Err(GameError::ReadOnlyError(format!(
+                "Cannot alter {:?}, which isn't under {:?} in memory",
+                path,
+                self.prefix()
+            ))),
+        }
+    }
+
+    // Whether the relative path is a directory, the prefix and those it's in counting as ones.
+    fn is_dir(&self, entries: &MemoryEntries, relative: &Path) -> bool {
+        self.prefix.starts_with(relative) || entries.dirs.contains(relative)
+    }
+
+    fn add_dirs(entries: &mut MemoryEntries, prefix: &Path, relative: &Path) -> GameResult {
+        for dir in relative.ancestors().skip(1) {
+            if !dir.starts_with(prefix) || dir == prefix {
+                break;
+            }
+            if entries.files.contains_key(dir) {
+                return Err(GameError::FilesystemError(format!(
+                    "Cannot make {:?}, {:?} is a file",
+                    relative, dir
+                )));
+            }
+            let _ = entries.dirs.insert(dir.to_owned());
+        }
+        Ok(())
+    }
+}
+
+impl Debug for MemoryFS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<MemoryFS {:?}>", self.prefix())
+    }
+}
+
+/// A file in a `MemoryFS`, whose contents are written back to it when
+/// it's flushed or dropped, or right away when it's appended to, so files
+/// appended to by several at once get all of what each of them wrote.
+struct MemoryFile {
+    entries: Arc<Mutex<MemoryEntries>>,
+    path: PathBuf,
+    contents: io::Cursor<Vec<u8>>,
+    write: bool,
+    append: bool,
+    changed: bool,
+    // whether it was appended to since its contents were last read from
+    // the `MemoryFS`, which they're read from again before they're used
+    stale: bool,
+}
+
+impl MemoryFile {
+    fn refresh(&mut self) {
+        if self.stale {
+            let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(file) = entries.files.get(&self.path) {
+                let contents = file.contents.clone();
+                let end = contents.len() as u64;
+                self.contents = io::Cursor::new(contents);
+                // at the end, as it is after appending to it
+                self.contents.set_position(end);
+            }
+            self.stale = false;
+        }
+    }
+}
+
+impl io::Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.refresh();
+        self.contents.read(buf)
+    }
+}
+
+impl io::Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.write {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file not opened for writing",
+            ));
+        }
+        if self.append {
+            let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            let file = entries
+                .files
+                .entry(self.path.clone())
+                .or_insert_with(MemoryFileEntry::new);
+            file.contents.extend_from_slice(buf);
+            file.modified = crate::timer::system_time_now();
+            self.stale = true;
+            return Ok(buf.len());
+        }
+        self.changed = true;
+        self.contents.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.changed {
+            let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            let file = entries
+                .files
+                .entry(self.path.clone())
+                .or_insert_with(MemoryFileEntry::new);
+            file.contents = self.contents.get_ref().clone();
+            file.modified = crate::timer::system_time_now();
+            self.changed = false;
+        }
+        Ok(())
+    }
+}
+
+impl io::Seek for MemoryFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.refresh();
+        self.contents.seek(pos)
+    }
+}
+
+impl Drop for MemoryFile {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl Debug for MemoryFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<MemoryFile {:?}>", self.path)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct MemoryMetadata {
+    len: u64,
+    is_dir: bool,
+    created: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+impl VMetadata for MemoryMetadata {
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+    fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+    fn len(&self) -> u64 {
+        self.len
+    }
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+    fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+}
+
+impl VFS for MemoryFS {
+    fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
+        let relative = if open_options.alters() {
+            self.writeable(path)?
+        } else {
+            sanitize_path(path).ok_or_else(|| {
+                GameError::FilesystemError(format!("Invalid path {:?} in memory", path))
+            })?
+        };
+        let mut entries = self.entries();
+        if self.is_dir(&entries, &relative) {
+            return Err(GameError::FilesystemError(format!(
+                "Cannot open {:?} in memory, which is a directory",
+                path
+            )));
+        }
+        let contents = match entries.files.get_mut(&relative) {
+            Some(_) if open_options.create_new => {
+                return Err(GameError::from(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("File {:?} already exists in memory", path),
+                )))
+            }
+            Some(file) if open_options.truncate => {
+                file.contents.clear();
+                file.modified = crate::timer::system_time_now();
+                Vec::new()
+            }
+            Some(file) => file.contents.clone(),
+            None if open_options.creates() => {
+                Self::add_dirs(&mut entries, &self.prefix, &relative)?;
+                let _ = entries
+                    .files
+                    .insert(relative.clone(), MemoryFileEntry::new());
+                Vec::new()
+            }
+            None => {
+                return Err(GameError::FilesystemError(format!(
+                    "File {:?} not found in memory",
+                    path
+                )))
+            }
+        };
+        let mut contents = io::Cursor::new(contents);
+        if open_options.append {
+            let _ = contents.seek(io::SeekFrom::End(0))?;
+        }
+        Ok(Box::new(MemoryFile {
+            entries: self.entries.clone(),
+            path: relative,
+            contents,
+            write: open_options.write || open_options.append,
+            append: open_options.append,
+            changed: false,
+            stale: false,
+        }))
+    }
+
+    fn mkdir(&self, path: &Path) -> GameResult {
+        let relative = self.writeable(path)?;
+        let mut entries = self.entries();
+        if entries.files.contains_key(&relative) {
+            return Err(GameError::FilesystemError(format!(
+                "Cannot make the directory {:?} in memory, which is a file",
+                path
+            )));
+        }
+        Self::add_dirs(&mut entries, &self.prefix, &relative)?;
+        let _ = entries.dirs.insert(relative);
+        Ok(())
+    }
+
+    fn rm(&self, path: &Path) -> GameResult {
+        let relative = self.writeable(path)?;
+        let mut entries = self.entries();
+        if entries.files.remove(&relative).is_some() {
+            return Ok(());
+        }
+        let empty = !entries
+            .files
+            .keys()
+            .chain(&entries.dirs)
+            .any(|p| p.parent() == Some(&relative));
+        if empty && entries.dirs.remove(&relative) {
+            Ok(())
+        } else {
+            Err(GameError::FilesystemError(format!(
+                "Cannot remove {:?} in memory, which is missing or a directory with \
+                 something in it",
+                path
+            )))
+        }
+    }
+
+    fn rmrf(&self, path: &Path) -> GameResult {
+        let relative = self.writeable(path)?;
+        let mut entries = self.entries();
+        let (files, dirs) = (entries.files.len(), entries.dirs.len());
+        entries.files.retain(|p, _| !p.starts_with(&relative));
+        entries.dirs.retain(|p| !p.starts_with(&relative));
+        if entries.files.len() == files && entries.dirs.len() == dirs {
+            return Err(GameError::FilesystemError(format!(
+                "Cannot remove {:?} in memory, which doesn't exist",
+                path
+            )));
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        match sanitize_path(path) {
+            Some(relative) => {
+                let entries = self.entries();
+                entries.files.contains_key(&relative) || self.is_dir(&entries, &relative)
+            }
+            None => false,
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+        if let Some(relative) = sanitize_path(path) {
+            let entries = self.entries();
+            if let Some(file) = entries.files.get(&relative) {
+                return Ok(Box::new(MemoryMetadata {
+                    len: file.contents.len() as u64,
+                    is_dir: false,
+                    created: Some(file.created),
+                    modified: Some(file.modified),
+                }));
+            }
+            if self.is_dir(&entries, &relative) {
+                return Ok(Box::new(MemoryMetadata {
+                    len: 0,
+                    is_dir: true,
+                    created: None,
+                    modified: None,
+                }));
+            }
+        }
+        Err(GameError::FilesystemError(format!(
+            "Metadata not found in memory for {:?}",
+            path
+        )))
+    }
+
+    fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
+        let entries = self.entries();
+        let relative = match sanitize_path(path) {
+            Some(relative) if self.is_dir(&entries, &relative) => relative,
+            _ => {
+                return Err(GameError::FilesystemError(format!(
+                    "Directory {:?} not found in memory",
+                    path
+                )))
+            }
+        };
+        let children: BTreeSet<PathBuf> = self
+            .prefix
+            .ancestors()
+            .chain(entries.files.keys().map(PathBuf::as_path))
+            .chain(entries.dirs.iter().map(PathBuf::as_path))
+            .filter(|p| p.parent() == Some(&relative))
+            .map(|p| Path::new("/").join(p))
+            .collect();
+        Ok(Box::new(children.into_iter().map(Ok)))
+    }
+
+    fn to_path_buf(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn is_readonly(&self) -> bool {
+        false
+    }
+
+    fn as_memory(&self) -> Option<&MemoryFS> {
+        Some(self)
+    }
 }
 
 #[cfg(test)]
@@ -867,6 +2317,22 @@ mod tests {
 
         let p = path::Path::new("");
         assert!(sanitize_path(p).is_none());
+
+        // Normalized the same everywhere
+        let p = path::Path::new("\\sprites\\.\\hero.png");
+        assert_eq!(
+            sanitize_path(p),
+            Some(PathBuf::from("sprites").join("hero.png"))
+        );
+        let p = path::Path::new("/sprites//./hero.png");
+        assert_eq!(
+            sanitize_path_for_zip(p).as_deref(),
+            Some("sprites/hero.png")
+        );
+        let p = path::Path::new("/sprites\\..\\..\\secret.txt");
+        assert!(sanitize_path(p).is_none());
+        let p = path::Path::new("/");
+        assert_eq!(sanitize_path_for_zip(p).as_deref(), Some(""));
     }
 
     #[test]
@@ -899,6 +2365,78 @@ mod tests {
         assert!(!ofs.exists(Path::new("/foobaz.rs")));
     }
 
+    #[test]
+    fn headless_test_memory_all() {
+        let fs = MemoryFS::new(Path::new("/saves")).unwrap();
+        let testdir = Path::new("/saves/testdir");
+        let f1 = Path::new("/saves/testdir/file1.txt");
+
+        assert!(fs.exists(Path::new("/")));
+        assert!(fs.exists(Path::new("/saves")));
+        assert!(!fs.exists(testdir));
+        assert!(fs.mkdir(Path::new("/elsewhere")).is_err());
+        assert!(fs.create(Path::new("/file.txt")).is_err());
+
+        fs.mkdir(testdir).unwrap();
+        assert!(fs.exists(testdir));
+        fs.rm(testdir).unwrap();
+        assert!(!fs.exists(testdir));
+
+        {
+            let mut f = fs.append(f1).unwrap();
+            let _ = f.write(b"Foo").unwrap();
+        }
+        {
+            let mut f = fs.append(f1).unwrap();
+            let _ = f.write(b"!").unwrap();
+        }
+        let mut buf = Vec::new();
+        let _ = fs.open(f1).unwrap().read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf[..], b"Foo!");
+        assert!(fs.open(f1).unwrap().write(b"?").is_err());
+        assert!(fs.rm(testdir).is_err());
+
+        let m = fs.metadata(f1).unwrap();
+        assert!(m.is_file());
+        assert_eq!(m.len(), 4);
+        assert!(fs.metadata(testdir).unwrap().is_dir());
+
+        let r: Vec<_> = fs.read_dir(Path::new("/")).unwrap().collect();
+        assert_eq!(r.len(), 1);
+        let r: Vec<_> = fs
+            .read_dir(testdir)
+            .unwrap()
+            .collect::<GameResult<_>>()
+            .unwrap();
+        assert_eq!(r, vec![f1.to_path_buf()]);
+
+        {
+            let _ = fs.create(f1).unwrap();
+        }
+        assert_eq!(fs.snapshot()[f1], b"");
+
+        fs.rmrf(testdir).unwrap();
+        assert!(!fs.exists(f1));
+        assert!(fs.snapshot().is_empty());
+    }
+
+    #[test]
+    fn headless_test_write_atomic() {
+        let dir = std::env::temp_dir().join("ggez_test_write_atomic");
+        let _ = fs::remove_dir_all(&dir);
+        let physical = PhysicalFS::new(&dir, false);
+        let file = Path::new("/saves/game.sav");
+        physical.write_atomic(file, b"first").unwrap();
+        physical.write_atomic(file, b"second").unwrap();
+        assert_eq!(fs::read(dir.join("saves/game.sav")).unwrap(), b"second");
+        // with nothing left over
+        assert_eq!(fs::read_dir(dir.join("saves")).unwrap().count(), 1);
+        assert!(PhysicalFS::new(&dir, true)
+            .write_atomic(file, b"third")
+            .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn headless_test_physical_all() {
         let cargo_path = Path::new(env!("CARGO_MANIFEST_DIR"));
@@ -998,5 +2536,125 @@ mod tests {
         assert_eq!(contents, "Zip contents!");
     }
 
+    #[test]
+    fn headless_test_zip_directories() {
+        let mut finished_zip_bytes: io::Cursor<_> = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            let stored = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            let deflated = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+
+            zip_archive.start_file("top.txt", stored).unwrap();
+            let _bytes = zip_archive.write(b"stored").unwrap();
+            zip_archive.add_directory("empty/", stored).unwrap();
+            zip_archive
+                .start_file("sprites/player/idle.png", deflated)
+                .unwrap();
+            let _bytes = zip_archive.write(&[7; 1000]).unwrap();
+            zip_archive
+                .start_file("sprites/ñandú.txt", deflated)
+                .unwrap();
+            let _bytes = zip_archive.write("ñandú".as_bytes()).unwrap();
+            zip_archive.finish().unwrap()
+        };
+
+        let _bytes = finished_zip_bytes.seek(io::SeekFrom::Start(0)).unwrap();
+        let zfs = ZipFS::from_read(finished_zip_bytes).unwrap();
+
+        let list = |path: &str| -> Vec<PathBuf> {
+            zfs.read_dir(Path::new(path))
+                .unwrap()
+                .map(Result::unwrap)
+                .collect()
+        };
+        assert_eq!(
+            list("/"),
+            vec![
+                PathBuf::from("/empty"),
+                PathBuf::from("/sprites"),
+                PathBuf::from("/top.txt")
+            ]
+        );
+        assert_eq!(
+            list("/sprites"),
+            vec![
+                PathBuf::from("/sprites/player"),
+                PathBuf::from("/sprites/ñandú.txt")
+            ]
+        );
+        assert!(list("/empty").is_empty());
+        assert!(zfs.read_dir(Path::new("/missing")).is_err());
+
+        assert!(zfs.exists(Path::new("/sprites/player")));
+        assert!(zfs.metadata(Path::new("/sprites/player")).unwrap().is_dir());
+        assert!(zfs.metadata(Path::new("/top.txt")).unwrap().is_file());
+        assert!(zfs.open(Path::new("/sprites")).is_err());
+
+        let mut contents = String::new();
+        let _bytes = zfs
+            .open(Path::new("/sprites/ñandú.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "ñandú");
+
+        // seeking around the deflated file
+        let mut file = zfs.open(Path::new("/sprites/player/idle.png")).unwrap();
+        assert_eq!(file.seek(io::SeekFrom::End(-10)).unwrap(), 990);
+        let mut tail = vec![];
+        let _bytes = file.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, [7; 10]);
+    }
+
+    #[test]
+    fn headless_test_zip_streaming() {
+        let data: Vec<u8> = (0..300_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let bytes = {
+            let mut zip_archive = zip::ZipWriter::new(io::Cursor::new(vec![]));
+            for (name, method) in [
+                ("stored.ogg", zip::CompressionMethod::Stored),
+                ("deflated.ogg", zip::CompressionMethod::Deflated),
+            ] {
+                let options = zip::write::FileOptions::default().compression_method(method);
+                zip_archive.start_file(name, options).unwrap();
+                zip_archive.write_all(&data).unwrap();
+            }
+            zip_archive.finish().unwrap().into_inner()
+        };
+        let zip_path = std::env::temp_dir().join("ggez_test_streaming.zip");
+        std::fs::write(&zip_path, &bytes).unwrap();
+
+        let from_bytes = ZipFS::from_bytes(Cow::Owned(bytes)).unwrap();
+        let from_file = ZipFS::new(&zip_path).unwrap();
+        for zfs in [&from_bytes, &from_file] {
+            for name in ["/stored.ogg", "/deflated.ogg"] {
+                let mut file = zfs.open(Path::new(name)).unwrap();
+                let mut read_at = |pos: io::SeekFrom, expected: usize| {
+                    assert_eq!(file.seek(pos).unwrap(), expected as u64);
+                    let mut buf = [0; 100];
+                    file.read_exact(&mut buf).unwrap();
+                    assert_eq!(buf[..], data[expected..expected + 100], "{}", name);
+                };
+                read_at(io::SeekFrom::Start(250_000), 250_000);
+                read_at(io::SeekFrom::Current(-1000), 249_100);
+                read_at(io::SeekFrom::Start(10), 10);
+                read_at(io::SeekFrom::Current(0), 110);
+                read_at(io::SeekFrom::End(-100), 299_900);
+
+                assert_eq!(file.seek(io::SeekFrom::End(10)).unwrap(), 300_010);
+                assert_eq!(file.read(&mut [0; 10]).unwrap(), 0);
+                assert!(file.seek(io::SeekFrom::Current(-300_011)).is_err());
+                assert!(file.write(b"nope").is_err());
+
+                let _bytes = file.seek(io::SeekFrom::Start(0)).unwrap();
+                let mut contents = vec![];
+                let _bytes = file.read_to_end(&mut contents).unwrap();
+                assert!(contents == data, "{}", name);
+            }
+        }
+        let _ = std::fs::remove_file(&zip_path);
+    }
+
     // BUGGO: TODO: Make sure all functions are tested for OverlayFS and ZipFS!!
 }
@@ -0,0 +1,109 @@
+//! What's drawn, compared with the golden images in `tests/golden`, remade with
+//! `GGEZ_BLESS=1 cargo test --test golden` when the drawing changed on purpose.
+
+use ggez::graphics::{self, BlendMode, Color, DrawMode, DrawParam, Mesh, Rect, Text};
+use ggez::test::{assert_image_matches, headless_context, render_to_image, Tolerance};
+use ggez::{Context, ContextBuilder};
+
+fn context() -> Option<Context> {
+    headless_context(ContextBuilder::new("golden_test", "ggez"))
+}
+
+#[test]
+fn mesh() {
+    let mut ctx = match context() {
+        Some(ctx) => ctx,
+        None => return,
+    };
+    let fill = Mesh::new_rectangle(
+        &ctx,
+        DrawMode::fill(),
+        Rect::new(8.0, 8.0, 20.0, 12.0),
+        Color::RED,
+    )
+    .unwrap();
+    let stroke = Mesh::new_rectangle(
+        &ctx,
+        DrawMode::stroke(2.0),
+        Rect::new(4.0, 36.0, 24.0, 20.0),
+        Color::GREEN,
+    )
+    .unwrap();
+    let circle =
+        Mesh::new_circle(&ctx, DrawMode::fill(), [44.0, 40.0], 14.0, 0.1, Color::BLUE).unwrap();
+    let image = render_to_image(&mut ctx, (64, 64), |canvas| {
+        canvas.draw(&fill, DrawParam::new());
+        canvas.draw(&stroke, DrawParam::new());
+        canvas.draw(&circle, DrawParam::new());
+    })
+    .unwrap();
+    assert_image_matches(
+        &ctx,
+        &image,
+        "tests/golden/mesh.png",
+        Tolerance {
+            max_diff_per_channel: 2,
+            // the pixels whose centers are within a quarter pixel of the circle's edge
+            max_differing_pixels: 32,
+        },
+    );
+}
+
+#[test]
+fn blend() {
+    let mut ctx = match context() {
+        Some(ctx) => ctx,
+        None => return,
+    };
+    let modes = [
+        BlendMode::ALPHA,
+        BlendMode::ADD,
+        BlendMode::MULTIPLY,
+        BlendMode::REPLACE,
+    ];
+    let image = render_to_image(&mut ctx, (64, 16), |canvas| {
+        canvas.draw(
+            &graphics::Quad,
+            DrawParam::new()
+                .dest_rect(Rect::new(0.0, 0.0, 64.0, 16.0))
+                .color(Color::new(0.2, 0.4, 0.6, 1.0)),
+        );
+        for (i, mode) in modes.into_iter().enumerate() {
+            canvas.set_blend_mode(mode);
+            canvas.draw(
+                &graphics::Quad,
+                DrawParam::new()
+                    .dest_rect(Rect::new(16.0 * i as f32 + 2.0, 2.0, 12.0, 12.0))
+                    .color(Color::new(0.8, 0.2, 0.4, 0.6)),
+            );
+        }
+    })
+    .unwrap();
+    // every pixel is on the grid, so only the rounding differs
+    assert_image_matches(&ctx, &image, "tests/golden/blend.png", Tolerance::default());
+}
+
+#[test]
+fn text() {
+    let mut ctx = match context() {
+        Some(ctx) => ctx,
+        None => return,
+    };
+    let mut text = Text::new("ggez");
+    let _ = text.set_scale(16.0);
+    let image = render_to_image(&mut ctx, (48, 24), |canvas| {
+        // at whole pixels, so the glyphs aren't filtered between texels
+        canvas.draw(&text, DrawParam::new().dest([4.0, 4.0]).color(Color::WHITE));
+    })
+    .unwrap();
+    assert_image_matches(
+        &ctx,
+        &image,
+        "tests/golden/text.png",
+        Tolerance {
+            max_diff_per_channel: 8,
+            // the antialiased edges of the glyphs
+            max_differing_pixels: 16,
+        },
+    );
+}